@@ -3,7 +3,7 @@ extern crate altius_session;
 
 use std::collections::HashMap;
 
-use altius_core::optimize;
+use altius_core::optimize::pass_manager::OptimizationLevel;
 use altius_core::tensor::{TensorElemType, TensorElemTypeExt};
 use altius_core::value::ValueId;
 use altius_core::{model::Model, tensor::Tensor};
@@ -41,7 +41,7 @@ fn load(path: String) -> PyResult<PyModel> {
 }
 
 #[pyfunction(
-    text_signature = "(model, enable_profiling=False, intra_op_num_threads=1, backend=\"interpreter\")"
+    text_signature = "(model, enable_profiling=False, intra_op_num_threads=1, backend=\"interpreter\", optimization_level=2)"
 )]
 fn session(
     py: Python,
@@ -49,16 +49,20 @@ fn session(
     enable_profiling: bool,
     intra_op_num_threads: usize,
     backend: String,
+    optimization_level: usize,
 ) -> PyResult<Py<PyAny>> {
-    let mut model = model.0;
-    optimize::layer_norm_fusion::fuse_layer_norm(&mut model);
-    optimize::gelu_fusion::fuse_gelu(&mut model);
-    optimize::fast_gelu_fusion::fuse_fast_gelu(&mut model);
-    if backend == "cpu" {
-        optimize::elemwise_fusion::fuse_elemwise_ops(&mut model).map_err(|e| {
-            PyRuntimeError::new_err(format!("Failed to fuse element-wise operations: {e:?}"))
-        })?
-    }
+    let model = model.0;
+    let optimization_level = match optimization_level {
+        0 => OptimizationLevel::O0,
+        1 => OptimizationLevel::O1,
+        2 => OptimizationLevel::O2,
+        3 => OptimizationLevel::O3,
+        _ => {
+            return Err(PyRuntimeError::new_err(format!(
+                "Unknown optimization level: {optimization_level}"
+            )))
+        }
+    };
 
     match backend.as_str() {
         "interpreter" => Ok(PyInterpreterSession(
@@ -66,6 +70,7 @@ fn session(
                 InterpreterSessionBuilder::new(model)
                     .with_profiling_enabled(enable_profiling)
                     .with_intra_op_num_threads(intra_op_num_threads)
+                    .with_optimization_level(optimization_level)
                     .build()
             })
             .map_err(|e| PyRuntimeError::new_err(e.to_string()))?,
@@ -76,6 +81,7 @@ fn session(
                 CPUSessionBuilder::new(model)
                     .with_profiling_enabled(enable_profiling)
                     .with_intra_op_num_threads(intra_op_num_threads)
+                    .with_optimization_level(optimization_level)
                     .build()
             })
             .map_err(|e| PyRuntimeError::new_err(e.to_string()))?,
@@ -100,15 +106,22 @@ trait Session {
             let val_id = model
                 .lookup_named_value(&name)
                 .ok_or_else(|| PyRuntimeError::new_err(format!("Input '{name}' not found")))?;
-            Ok((
-                val_id,
-                Tensor::new(
-                    val.shape().to_vec().into(),
-                    val.as_slice()
-                        .map_err(|_| PyRuntimeError::new_err("Array not contiguous"))?
-                        .to_vec(),
-                ),
-            ))
+            let tensor = Tensor::new(
+                val.shape().to_vec().into(),
+                val.as_slice()
+                    .map_err(|_| PyRuntimeError::new_err("Array not contiguous"))?
+                    .to_vec(),
+            );
+            // Half-precision inputs are given as float32 arrays.
+            let tensor = match &model.graph.values[val_id].shape {
+                Some(shape) if shape.elem_ty.is_half() => {
+                    tensor.cast_float(shape.elem_ty).ok_or_else(|| {
+                        PyRuntimeError::new_err(format!("Input '{name}' must be float32"))
+                    })?
+                }
+                _ => tensor,
+            };
+            Ok((val_id, tensor))
         }
 
         let mut new_inputs = HashMap::<ValueId, Tensor>::default();
@@ -131,6 +144,18 @@ trait Session {
                 continue;
             }
 
+            if let Ok((name, val)) = item.extract::<(String, PyReadonlyArrayDyn<i8>)>() {
+                let (key, val) = create_input(self.model(), name, val)?;
+                new_inputs.insert(key, val);
+                continue;
+            }
+
+            if let Ok((name, val)) = item.extract::<(String, PyReadonlyArrayDyn<u8>)>() {
+                let (key, val) = create_input(self.model(), name, val)?;
+                new_inputs.insert(key, val);
+                continue;
+            }
+
             if let Ok((name, val)) = item.extract::<(String, PyReadonlyArrayDyn<bool>)>() {
                 let (key, val) = create_input(self.model(), name, val)?;
                 new_inputs.insert(key, val);
@@ -154,6 +179,12 @@ trait Session {
             ._run(new_inputs)
             .map_err(|e| PyRuntimeError::new_err(format!("Inference failed: {e}")))?
         {
+            // Half-precision outputs are returned as float32 arrays.
+            let out = if out.elem_ty().is_half() {
+                out.cast_float(TensorElemType::F32).unwrap()
+            } else {
+                out
+            };
             macro_rules! arr {
                 ($t:tt) => {
                     ArrayD::from_shape_vec(
@@ -171,7 +202,10 @@ trait Session {
                 TensorElemType::F32 => arr!(f32),
                 TensorElemType::I32 => arr!(i32),
                 TensorElemType::I64 => arr!(i64),
+                TensorElemType::I8 => arr!(i8),
+                TensorElemType::U8 => arr!(u8),
                 TensorElemType::Bool => arr!(bool),
+                TensorElemType::F16 | TensorElemType::BF16 => unreachable!(),
             };
             outputs.push(arr);
         }
@@ -0,0 +1,185 @@
+import altius_py
+import onnxruntime as ort
+import onnx
+import tempfile
+import pytest
+import os
+import numpy as np
+from onnx import helper, TensorProto, numpy_helper
+
+
+@pytest.mark.parametrize("axis", [0, 1, -1])
+def test_gather_general(axis):
+    with tempfile.TemporaryDirectory() as tmpdir:
+        op_indexing(
+            os.path.join(tmpdir, "model.onnx"),
+            "Gather",
+            [3, 4, 5],
+            {"indices": np.array([[2, 0], [-1, 1]], dtype=np.int64)},
+            axis=axis,
+        )
+
+
+@pytest.mark.parametrize("axis", [0, 1])
+def test_gather_elements(axis):
+    with tempfile.TemporaryDirectory() as tmpdir:
+        op_indexing(
+            os.path.join(tmpdir, "model.onnx"),
+            "GatherElements",
+            [3, 4],
+            {"indices": np.array([[0, 2, 1, 0], [2, -1, 0, 1]], dtype=np.int64)},
+            axis=axis,
+        )
+
+
+@pytest.mark.parametrize("batch_dims", [0, 1])
+def test_gather_nd(batch_dims):
+    with tempfile.TemporaryDirectory() as tmpdir:
+        op_indexing(
+            os.path.join(tmpdir, "model.onnx"),
+            "GatherND",
+            [2, 3, 4],
+            {"indices": np.array([[[1, 2]], [[0, 3]]], dtype=np.int64)},
+            batch_dims=batch_dims,
+        )
+
+
+@pytest.mark.parametrize("reduction", ["none", "add", "mul", "max", "min"])
+def test_scatter_elements(reduction):
+    with tempfile.TemporaryDirectory() as tmpdir:
+        op_indexing(
+            os.path.join(tmpdir, "model.onnx"),
+            "ScatterElements",
+            [3, 4],
+            {
+                "indices": np.array([[1, 0, 2, 0], [2, 2, 0, 1]], dtype=np.int64),
+                "updates": np.random.random_sample([2, 4]).astype(np.float32),
+            },
+            opset=18,
+            axis=0,
+            reduction=reduction,
+        )
+
+
+@pytest.mark.parametrize("reduction", ["none", "add", "mul", "max", "min"])
+def test_scatter_nd(reduction):
+    indices = [[1], [3]] if reduction == "none" else [[1], [3], [1]]
+    with tempfile.TemporaryDirectory() as tmpdir:
+        op_indexing(
+            os.path.join(tmpdir, "model.onnx"),
+            "ScatterND",
+            [4, 2, 3],
+            {
+                "indices": np.array(indices, dtype=np.int64),
+                "updates": np.random.random_sample([len(indices), 2, 3]).astype(
+                    np.float32
+                ),
+            },
+            opset=18,
+            reduction=reduction,
+        )
+
+
+@pytest.mark.parametrize("axis", [0, -1])
+@pytest.mark.parametrize("largest", [0, 1])
+def test_top_k(axis, largest):
+    with tempfile.TemporaryDirectory() as tmpdir:
+        op_indexing(
+            os.path.join(tmpdir, "model.onnx"),
+            "TopK",
+            [5, 6],
+            {"k": np.array([3], dtype=np.int64)},
+            num_outputs=2,
+            axis=axis,
+            largest=largest,
+        )
+
+
+@pytest.mark.parametrize("axis", [0, -1])
+def test_one_hot(axis):
+    with tempfile.TemporaryDirectory() as tmpdir:
+        op_indexing(
+            os.path.join(tmpdir, "model.onnx"),
+            "OneHot",
+            None,
+            {
+                "indices": np.array([[0, 3], [-1, 7]], dtype=np.int64),
+                "depth": np.array(5, dtype=np.int64),
+                "values": np.array([-1, 1], dtype=np.float32),
+            },
+            axis=axis,
+        )
+
+
+def test_non_zero():
+    with tempfile.TemporaryDirectory() as tmpdir:
+        op_indexing(
+            os.path.join(tmpdir, "model.onnx"),
+            "NonZero",
+            [4, 5],
+            {},
+            transform=lambda x: np.where(x > 0.5, x, 0),
+        )
+
+
+@pytest.mark.parametrize("exclusive", [0, 1])
+@pytest.mark.parametrize("reverse", [0, 1])
+def test_cum_sum(exclusive, reverse):
+    with tempfile.TemporaryDirectory() as tmpdir:
+        op_indexing(
+            os.path.join(tmpdir, "model.onnx"),
+            "CumSum",
+            [3, 4],
+            {"axis": np.array(1, dtype=np.int64)},
+            exclusive=exclusive,
+            reverse=reverse,
+        )
+
+
+def op_indexing(
+    filepath,
+    op_type,
+    shape_x,
+    consts,
+    num_outputs=1,
+    opset=13,
+    transform=lambda x: x,
+    **kwargs,
+):
+    """Compares `op_type` of a random input `x` of `shape_x`, if any, followed by
+    `consts` given as initializers."""
+    inputs = []
+    initializer = [numpy_helper.from_array(v, name=k) for k, v in consts.items()]
+    if shape_x is not None:
+        inputs.append(helper.make_tensor_value_info("x", TensorProto.FLOAT, shape_x))
+    # Output shapes are left to shape inference.
+    outputs = [
+        helper.make_tensor_value_info(f"y{i}", TensorProto.UNDEFINED, None)
+        for i in range(num_outputs)
+    ]
+    nodes = [
+        helper.make_node(
+            op_type,
+            [i.name for i in inputs] + list(consts),
+            [o.name for o in outputs],
+            **kwargs,
+        )
+    ]
+    graph = helper.make_graph(nodes, "graph", inputs, outputs, initializer=initializer)
+    model = helper.make_model(graph, opset_imports=[helper.make_opsetid("", opset)])
+
+    onnx.checker.check_model(model)
+    onnx.save(model, filepath)
+    ort_sess = ort.InferenceSession(filepath, providers=["CPUExecutionProvider"])
+    altius_sess = altius_py.InferenceSession(filepath, backend="interpreter")
+
+    inputs = {}
+    if shape_x is not None:
+        x = np.random.random_sample(shape_x).astype(np.float32)
+        inputs["x"] = transform(x).astype(np.float32)
+    expected = ort_sess.run(None, inputs)
+    actual = altius_sess.run(None, inputs)
+
+    for expected, actual in zip(expected, actual):
+        assert expected.shape == actual.shape
+        assert np.allclose(expected, actual)
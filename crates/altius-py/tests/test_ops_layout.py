@@ -0,0 +1,101 @@
+import altius_py
+import onnxruntime as ort
+import onnx
+import tempfile
+import pytest
+import os
+import numpy as np
+from onnx import helper, TensorProto, numpy_helper
+
+
+@pytest.mark.parametrize("mode", ["constant", "reflect", "edge", "wrap"])
+def test_pad_1(mode):
+    # Wrap is supported from opset version 19.
+    with tempfile.TemporaryDirectory() as tmpdir:
+        op_layout(
+            os.path.join(tmpdir, "model.onnx"),
+            "Pad",
+            [1, 3, 4, 5],
+            {"pads": np.array([0, 0, 1, 2, 0, 0, 2, -1], dtype=np.int64)},
+            opset=19,
+            mode=mode,
+        )
+
+
+def test_pad_constant_value_and_axes():
+    with tempfile.TemporaryDirectory() as tmpdir:
+        op_layout(
+            os.path.join(tmpdir, "model.onnx"),
+            "Pad",
+            [2, 3, 4],
+            {
+                "pads": np.array([1, 3, -1, 0], dtype=np.int64),
+                "constant_value": np.array(1.5, dtype=np.float32),
+                "axes": np.array([-1, 0], dtype=np.int64),
+            },
+            opset=18,
+        )
+
+
+def test_pad_attribute():
+    with tempfile.TemporaryDirectory() as tmpdir:
+        op_layout(
+            os.path.join(tmpdir, "model.onnx"),
+            "Pad",
+            [2, 3],
+            {},
+            opset=10,
+            pads=[1, 0, 0, 2],
+            value=-2.0,
+        )
+
+
+@pytest.mark.parametrize("mode", ["DCR", "CRD"])
+def test_depth_to_space_1(mode):
+    with tempfile.TemporaryDirectory() as tmpdir:
+        op_layout(
+            os.path.join(tmpdir, "model.onnx"),
+            "DepthToSpace",
+            [2, 12, 3, 4],
+            {},
+            blocksize=2,
+            mode=mode,
+        )
+
+
+def test_space_to_depth_1():
+    with tempfile.TemporaryDirectory() as tmpdir:
+        op_layout(
+            os.path.join(tmpdir, "model.onnx"),
+            "SpaceToDepth",
+            [2, 3, 6, 9],
+            {},
+            blocksize=3,
+        )
+
+
+def op_layout(filepath, op_type, shape_x, consts, opset=13, **kwargs):
+    """Compares `op_type` of a random input `x` of `shape_x` followed by `consts` given as
+    initializers."""
+    inputs = [helper.make_tensor_value_info("x", TensorProto.FLOAT, shape_x)]
+    initializer = [numpy_helper.from_array(v, name=k) for k, v in consts.items()]
+    # Output shapes are left to shape inference.
+    outputs = [helper.make_tensor_value_info("y", TensorProto.UNDEFINED, None)]
+    nodes = [helper.make_node(op_type, ["x"] + list(consts), ["y"], **kwargs)]
+    graph = helper.make_graph(nodes, "graph", inputs, outputs, initializer=initializer)
+    model = helper.make_model(graph, opset_imports=[helper.make_opsetid("", opset)])
+
+    onnx.checker.check_model(model)
+    onnx.save(model, filepath)
+    ort_sess = ort.InferenceSession(filepath, providers=["CPUExecutionProvider"])
+
+    for backend in ["interpreter", "cpu"]:
+        altius_sess = altius_py.InferenceSession(filepath, backend=backend)
+
+        inputs = {"x": np.random.random_sample(shape_x).astype(np.float32)}
+        expected = ort_sess.run(None, inputs)
+        actual = altius_sess.run(None, inputs)
+
+        for expected, actual in zip(expected, actual):
+            assert expected.shape == actual.shape
+            assert np.allclose(expected, actual)
@@ -18,6 +18,30 @@ def test_layer_norm_1():
         op_layer_norm(os.path.join(tmpdir, "model.onnx"), [1, 20, 10])
 
 
+def test_instance_norm_1():
+    with tempfile.TemporaryDirectory() as tmpdir:
+        op_group_norm(
+            os.path.join(tmpdir, "model.onnx"),
+            "InstanceNormalization",
+            [2, 8, 5, 5],
+            8,
+        )
+
+
+@pytest.mark.parametrize("num_groups,opset", [(4, 18), (2, 21), (1, 21)])
+def test_group_norm_1(num_groups, opset):
+    # Scale and bias are given per group until opset version 21.
+    with tempfile.TemporaryDirectory() as tmpdir:
+        op_group_norm(
+            os.path.join(tmpdir, "model.onnx"),
+            "GroupNormalization",
+            [2, 8, 5, 5],
+            num_groups if opset < 21 else 8,
+            opset=opset,
+            num_groups=num_groups,
+        )
+
+
 def op_batch_norm(filepath, shape, **kwargs):
     assert len(shape) == 4
     inputs = [
@@ -84,3 +108,31 @@ def op_layer_norm(filepath, shape, **kwargs):
 
         for expected, actual in zip(expected, actual):
             assert np.allclose(expected, actual, rtol=1e-4, atol=1e-5)
+
+
+def op_group_norm(filepath, op_type, shape, param_len, opset=18, **kwargs):
+    inputs = [
+        helper.make_tensor_value_info("x", TensorProto.FLOAT, shape),
+        helper.make_tensor_value_info("scale", TensorProto.FLOAT, [param_len]),
+        helper.make_tensor_value_info("bias", TensorProto.FLOAT, [param_len]),
+    ]
+    outputs = [helper.make_tensor_value_info("z", TensorProto.FLOAT, shape)]
+    nodes = [helper.make_node(op_type, ["x", "scale", "bias"], ["z"], **kwargs)]
+    graph = helper.make_graph(nodes, "graph", inputs, outputs)
+    model = helper.make_model(graph, opset_imports=[helper.make_opsetid("", opset)])
+
+    onnx.save(model, filepath)
+    ort_sess = ort.InferenceSession(filepath, providers=["CPUExecutionProvider"])
+
+    for backend in ["interpreter", "cpu"]:
+        altius_sess = altius_py.InferenceSession(filepath, backend=backend)
+
+        x = np.random.random_sample(shape).astype(np.float32)
+        scale = np.random.random_sample(param_len).astype(np.float32)
+        bias = np.random.random_sample(param_len).astype(np.float32)
+        inputs = {"x": x, "scale": scale, "bias": bias}
+        expected = ort_sess.run(None, inputs)
+        actual = altius_sess.run(None, inputs)
+
+        for expected, actual in zip(expected, actual):
+            assert np.allclose(expected, actual, rtol=1e-4, atol=1e-5)
@@ -2,9 +2,10 @@ import altius_py
 import onnxruntime as ort
 import onnx
 import tempfile
+import pytest
 import os
 import numpy as np
-from onnx import helper, TensorProto
+from onnx import helper, TensorProto, numpy_helper
 
 
 def test_reduce_mean_1():
@@ -41,14 +42,87 @@ def test_reduce_max_1():
         )
 
 
+@pytest.mark.parametrize(
+    "op_type",
+    [
+        "ReduceMin",
+        "ReduceMax",
+        "ReduceMean",
+        "ReduceSum",
+        "ReduceProd",
+        "ReduceL1",
+        "ReduceL2",
+        "ReduceLogSum",
+        "ReduceLogSumExp",
+        "ReduceSumSquare",
+    ],
+)
+@pytest.mark.parametrize("keepdims", [0, 1])
+def test_reduce_axes_input(op_type, keepdims):
+    with tempfile.TemporaryDirectory() as tmpdir:
+        op_reduce(
+            os.path.join(tmpdir, "model.onnx"),
+            op_type,
+            [2, 3, 4, 5],
+            [2, 1, 4, 1] if keepdims else [2, 4],
+            axes_input=[-1, 1],
+            opset=18,
+            keepdims=keepdims,
+        )
+
+
+def test_reduce_noop_with_empty_axes():
+    with tempfile.TemporaryDirectory() as tmpdir:
+        op_reduce(
+            os.path.join(tmpdir, "model.onnx"),
+            "ReduceSum",
+            [2, 3, 4],
+            [2, 3, 4],
+            axes_input=[],
+            opset=18,
+            noop_with_empty_axes=1,
+        )
+
+
+@pytest.mark.parametrize("op_type", ["ArgMax", "ArgMin"])
+@pytest.mark.parametrize("select_last_index", [0, 1])
+def test_arg_reduce(op_type, select_last_index):
+    with tempfile.TemporaryDirectory() as tmpdir:
+        op_reduce(
+            os.path.join(tmpdir, "model.onnx"),
+            op_type,
+            [3, 4, 5],
+            [3, 5],
+            y_type=TensorProto.INT64,
+            axis=1,
+            keepdims=0,
+            select_last_index=select_last_index,
+        )
+
+
 def op_reduce(
-    filepath, op_type, shape_x, shape_y, backends=["interpreter", "cpu"], **kwargs
+    filepath,
+    op_type,
+    shape_x,
+    shape_y,
+    backends=["interpreter", "cpu"],
+    axes_input=None,
+    opset=13,
+    y_type=TensorProto.FLOAT,
+    **kwargs,
 ):
     inputs = [helper.make_tensor_value_info("x", TensorProto.FLOAT, shape_x)]
-    outputs = [helper.make_tensor_value_info("y", TensorProto.FLOAT, shape_y)]
-    nodes = [helper.make_node(op_type, ["x"], ["y"], **kwargs)]
-    graph = helper.make_graph(nodes, "graph", inputs, outputs)
-    model = helper.make_model(graph, opset_imports=[helper.make_opsetid("", 13)])
+    outputs = [helper.make_tensor_value_info("y", y_type, shape_y)]
+    initializer = []
+    if axes_input is None:
+        nodes = [helper.make_node(op_type, ["x"], ["y"], **kwargs)]
+    else:
+        # From opset version 18, axes are given as an input.
+        nodes = [helper.make_node(op_type, ["x", "axes"], ["y"], **kwargs)]
+        axes = np.array(axes_input, dtype=np.int64)
+        initializer.append(numpy_helper.from_array(axes, name="axes"))
+    graph = helper.make_graph(nodes, "graph", inputs, outputs, initializer=initializer)
+    model = helper.make_model(graph, opset_imports=[helper.make_opsetid("", opset)])
 
     onnx.checker.check_model(model)
     onnx.save(model, filepath)
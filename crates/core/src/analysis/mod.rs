@@ -1 +1,3 @@
 pub mod shape;
+pub mod symbolic_shape;
+pub mod verify;
@@ -4,10 +4,11 @@ use rustc_hash::FxHashMap;
 use thiserror::Error;
 
 use crate::{
-    fixed_dim::FixedDimensions,
+    fixed_dim::{self, FixedDimensions},
+    graph::Graph,
     model::Model,
     node::NodeId,
-    op::Op,
+    op::{Conv2d, ConvTranspose, DepthToSpace, Op, Pool, SpaceToDepth},
     tensor::{Tensor, TensorElemType, TypedFixedShape},
     value::ValueId,
 };
@@ -29,118 +30,68 @@ impl Model {
     ) -> Result<Vec<TypedFixedShape>, ShapeError> {
         let mut shapes = vec![];
         let opset_version = self.opset_version;
+        let name = op.name();
 
         match op {
             Op::Conv2d(conv) => {
-                let auto_pad = &conv.auto_pad;
-                let kernel = &conv.kernel_shape;
-                let stride = &conv.strides;
-                let padding = &conv.padding;
-                let dilations = &conv.dilations;
-                let input = inputs[Op::CONV2D_IN].dims();
-                let weight = inputs[Op::CONV2D_WEIGHT].dims();
-
-                assert_eq!(dilations.len(), 2);
-
-                let pad_h;
-                let pad_w;
-                if !auto_pad.is_empty() && auto_pad != "NOTSET" {
-                    let out0 = (input[2] as f32 / stride[0] as f32).ceil() as usize;
-                    let out1 = (input[3] as f32 / stride[1] as f32).ceil() as usize;
-                    let pad0 =
-                        ((out0 - 1) * stride[0] + ((kernel[0] - 1) + 1)).saturating_sub(input[2]);
-                    let pad1 =
-                        ((out1 - 1) * stride[1] + ((kernel[1] - 1) + 1)).saturating_sub(input[3]);
-                    assert!(auto_pad == "SAME_UPPER");
-                    let new_padding = vec![pad0 / 2, pad1 / 2, pad0 - pad0 / 2, pad1 - pad1 / 2];
-                    conv.padding = new_padding.into();
-                    pad_h = pad0;
-                    pad_w = pad1;
-                } else if padding.len() == 2 {
-                    pad_h = padding[0] * 2;
-                    pad_w = padding[1] * 2;
-                    conv.padding = vec![padding[0], padding[1], padding[0], padding[1]].into();
-                } else if padding.len() == 4 {
-                    pad_h = padding[0] + padding[2];
-                    pad_w = padding[1] + padding[3];
-                } else {
-                    return Err(ShapeError::Message(
-                        format!("Conv2d: Unknown padding pattern: {padding:?}").into(),
-                    ));
-                }
-
-                let h_in = input[2];
-                let w_in = input[3];
-
-                let output_shape = vec![
-                    input[0],
-                    weight[0],
-                    (h_in + pad_h - dilations[0] * (kernel[0] - 1) - 1) / stride[0] + 1,
-                    (w_in + pad_w - dilations[1] * (kernel[1] - 1) - 1) / stride[1] + 1,
-                ];
+                let dims = conv2d_output_dims(
+                    conv,
+                    inputs[Op::CONV2D_IN].dims(),
+                    inputs[Op::CONV2D_WEIGHT].dims(),
+                )?;
+                shapes.push(TypedFixedShape::new(dims, inputs[Op::CONV2D_IN].elem_ty()));
+            }
+            Op::ConvTranspose(conv) => {
+                let dims = conv_transpose_output_dims(
+                    conv,
+                    inputs[Op::CONVTRANSPOSE_IN].dims(),
+                    inputs[Op::CONVTRANSPOSE_WEIGHT].dims(),
+                )?;
                 shapes.push(TypedFixedShape::new(
-                    output_shape.into(),
-                    inputs[Op::CONV2D_IN].elem_ty(),
+                    dims,
+                    inputs[Op::CONVTRANSPOSE_IN].elem_ty(),
                 ));
             }
             Op::Add | Op::Sub | Op::Mul | Op::Div | Op::Pow => {
-                let x = inputs[0].dims();
-                let y = inputs[1].dims();
-                let shape = x.broadcast(y).unwrap();
+                let shape = broadcast_inputs(op, inputs)?;
                 shapes.push(TypedFixedShape::new(shape, inputs[0].elem_ty()));
             }
-            Op::Greater => {
-                let x = inputs[0].dims();
-                let y = inputs[1].dims();
-                let shape = x.broadcast(y).unwrap();
+            Op::Equal
+            | Op::Less
+            | Op::LessOrEqual
+            | Op::Greater
+            | Op::GreaterOrEqual
+            | Op::And
+            | Op::Or
+            | Op::Xor => {
+                let shape = broadcast_inputs(op, inputs)?;
                 shapes.push(TypedFixedShape::new(shape, TensorElemType::Bool));
             }
             Op::Where => {
-                let x = inputs[1].dims();
-                let y = inputs[2].dims();
-                let shape = x.broadcast(y).unwrap();
+                let shape = broadcast_inputs(op, inputs)?;
                 shapes.push(TypedFixedShape::new(shape, inputs[1].elem_ty()));
             }
-            Op::MaxPool(maxpool) => {
-                let auto_pad = &maxpool.auto_pad;
-                let kernel = &maxpool.kernel_shape;
-                let stride = &maxpool.strides;
-                let input = &inputs[Op::MAXPOOL_IN].dims();
-                let mut padding = &maxpool.padding;
-
-                if !auto_pad.is_empty() && auto_pad != "NOTSET" {
-                    let out0 = (input[2] as f32 / stride[0] as f32).ceil() as usize;
-                    let out1 = (input[3] as f32 / stride[1] as f32).ceil() as usize;
-                    let pad0 =
-                        ((out0 - 1) * stride[0] + ((kernel[0] - 1) + 1)).saturating_sub(input[2]);
-                    let pad1 =
-                        ((out1 - 1) * stride[1] + ((kernel[1] - 1) + 1)).saturating_sub(input[3]);
-                    assert!(auto_pad == "SAME_UPPER");
-                    let new_padding = vec![pad0 / 2, pad1 / 2, pad0 - pad0 / 2, pad1 - pad1 / 2];
-                    maxpool.padding = new_padding.into();
-                    padding = &maxpool.padding;
+            Op::MaxPool(pool) | Op::AveragePool(pool) | Op::LpPool(pool) => {
+                let input = inputs[0];
+                let dims = pool_output_dims(pool, input.dims())?;
+                if num_outputs > 1 {
+                    // `Indices` of `MaxPool`.
+                    shapes.push(TypedFixedShape::new(dims.clone(), input.elem_ty()));
+                    shapes.push(TypedFixedShape::new(dims, TensorElemType::I64));
+                } else {
+                    shapes.push(TypedFixedShape::new(dims, input.elem_ty()));
                 }
-
-                let h_in = input[2];
-                let w_in = input[3];
-                let output_shape = vec![
-                    input[0],
-                    input[1],
-                    (h_in + (padding[0] + padding[2]) - (kernel[0] - 1) - 1) / stride[0] + 1,
-                    (w_in + (padding[1] + padding[3]) - (kernel[1] - 1) - 1) / stride[1] + 1,
-                ];
-                shapes.push(TypedFixedShape::new(
-                    output_shape.into(),
-                    inputs[Op::MAXPOOL_IN].elem_ty(),
-                ));
             }
-            Op::GlobalAveragePool => {
-                let input = &inputs[Op::GLOBALAVERAGEPOOL_IN].dims();
-                assert!(input.len() == 4);
-                shapes.push(TypedFixedShape::new(
-                    vec![input[0], input[1], 1, 1].into(),
-                    inputs[Op::GLOBALAVERAGEPOOL_IN].elem_ty(),
-                ));
+            Op::GlobalAveragePool | Op::GlobalMaxPool => {
+                let input = inputs[0].dims();
+                if input.len() < 3 {
+                    return Err(ShapeError::Message(
+                        format!("{}: Input must be at least 3D: {input:?}", op.name()).into(),
+                    ));
+                }
+                let mut dims = vec![1; input.len()];
+                dims[..2].copy_from_slice(&input[..2]);
+                shapes.push(TypedFixedShape::new(dims.into(), inputs[0].elem_ty()));
             }
             Op::Expand => {
                 let input = inputs[0];
@@ -156,20 +107,20 @@ impl Model {
                 ));
             }
             Op::Range => {
-                if !inputs[0].elem_ty().is_i64() {
+                let elem_ty = inputs[0].elem_ty();
+                let [start, limit, delta] = [0, 1, 2].map(|i| match elem_ty {
+                    TensorElemType::F32 => Some(inputs[i].data::<f32>()[0] as f64),
+                    TensorElemType::I32 => Some(inputs[i].data::<i32>()[0] as f64),
+                    TensorElemType::I64 => Some(inputs[i].data::<i64>()[0] as f64),
+                    _ => None,
+                });
+                let (Some(start), Some(limit), Some(delta)) = (start, limit, delta) else {
                     return Err(ShapeError::Message(
-                        "Range only supports i64 for now".into(),
+                        format!("Range: Unsupported type {elem_ty:?}").into(),
                     ));
-                }
-
-                let start = inputs[0].data::<i64>()[0];
-                let limit = inputs[1].data::<i64>()[0];
-                let delta = inputs[2].data::<i64>()[0];
-                let num_elems = (((limit - start) as f32 / delta as f32).ceil() as i64).max(0);
-                shapes.push(TypedFixedShape::new(
-                    vec![num_elems as usize].into(),
-                    inputs[0].elem_ty(),
-                ));
+                };
+                let num_elems = range_len(start, limit, delta)?;
+                shapes.push(TypedFixedShape::new(vec![num_elems].into(), elem_ty));
             }
             Op::Reshape => {
                 let shape = inputs[Op::RESHAPE_SHAPE]
@@ -203,50 +154,118 @@ impl Model {
                     inputs[Op::FLATTEN_IN].elem_ty(),
                 ));
             }
-            Op::Resize(resize) => {
-                if inputs.len() == 4 {
-                    let sizes = &inputs[Op::RESIZE_IN_SIZES];
-                    assert!(sizes.dims().len() == 1 && sizes.dims()[0] == 4);
-                    shapes.push(TypedFixedShape::new(
-                        FixedDimensions::from_i64(sizes.data::<i64>()),
-                        inputs[Op::RESIZE_IN_X].elem_ty(),
-                    ))
-                } else if inputs.len() == 3 {
-                    // TODO: Support other cases.
-                    assert!(resize.coordinate_transformation_mode == "asymmetric");
-                    assert!(resize.mode == "nearest");
-                    assert!(resize.nearest_mode == "floor");
-                    assert!(inputs.len() == 3);
-                    let x = &inputs[Op::RESIZE_IN_X];
-                    assert!(x.dims().len() == 4);
-                    let _roi = &inputs[Op::RESIZE_IN_ROI].data::<f32>(); // TODO: According to https://github.com/onnx/onnx/blob/main/docs/Operators.md#Resize,
-                                                                         // it only takes effect when coordinate_transformation_mode is "tf_crop_and_resize".
-                                                                         // Since we assume coordinate_transformation_mode is "asymmetric" for now, just ignore roi.
-                    let scales = &inputs[Op::RESIZE_IN_SCALES].data::<f32>();
-                    shapes.push(TypedFixedShape::new(
-                        vec![
-                            (x.dims()[0] as f32 * scales[0]).floor() as usize,
-                            (x.dims()[1] as f32 * scales[1]).floor() as usize,
-                            (x.dims()[2] as f32 * scales[2]).floor() as usize,
-                            (x.dims()[3] as f32 * scales[3]).floor() as usize,
-                        ]
-                        .into(),
-                        inputs[Op::RESIZE_IN_X].elem_ty(),
-                    ))
-                    // NOTE: Use the following code when roi takes effect ... right?
-                    // shapes.push(TypedFixedShape::new(
-                    //     vec![
-                    //         (x.dims()[0] as f32 * (roi[4] - roi[0]) * scales[0]).floor() as usize,
-                    //         (x.dims()[1] as f32 * (roi[5] - roi[1]) * scales[1]).floor() as usize,
-                    //         (x.dims()[2] as f32 * (roi[6] - roi[2]) * scales[2]).floor() as usize,
-                    //         (x.dims()[3] as f32 * (roi[7] - roi[3]) * scales[3]).floor() as usize,
-                    //     ]
-                    //     .into(),
-                    //     inputs[Op::RESIZE_IN_X].elem_ty(),
-                    // ))
+            Op::Resize(_) => {
+                let x = inputs[Op::RESIZE_IN_X];
+                // Omitted optional inputs are given as empty tensors.
+                let given = |i: usize| {
+                    inputs
+                        .get(i)
+                        .copied()
+                        .filter(|t| t.dims().total_elems() > 0)
+                };
+                let dims = if let Some(sizes) = given(Op::RESIZE_IN_SIZES) {
+                    sizes
+                        .data::<i64>()
+                        .iter()
+                        .map(|&s| s as usize)
+                        .collect::<Vec<_>>()
+                } else if let Some(scales) = given(Op::RESIZE_IN_SCALES) {
+                    x.dims()
+                        .iter()
+                        .zip(scales.data::<f32>())
+                        .map(|(&d, &s)| (d as f64 * s as f64).floor() as usize)
+                        .collect()
                 } else {
-                    return Err(ShapeError::Message("Resize: Unsupported pattern".into()));
+                    return Err(ShapeError::Message(
+                        "Resize: Either scales or sizes must be given".into(),
+                    ));
+                };
+                if x.dims().is_empty() || dims.len() != x.dims().len() {
+                    return Err(ShapeError::Message(
+                        format!("Resize: Cannot resize {:?} into {dims:?}", x.dims()).into(),
+                    ));
                 }
+                shapes.push(TypedFixedShape::new(dims.into(), x.elem_ty()))
+            }
+            Op::Pad(pad) => {
+                let data = inputs[Op::PAD_IN_DATA];
+                let rank = data.dims().len();
+                let given = |i: usize| {
+                    inputs
+                        .get(i)
+                        .copied()
+                        .filter(|t| t.dims().total_elems() > 0)
+                };
+                // From opset version 11, pads are given as an input.
+                if let Some(pads) = given(Op::PAD_IN_PADS) {
+                    pad.pads = pads.data::<i64>().to_vec();
+                }
+                // From opset version 18, pads may be given only for `axes`.
+                if let Some(axes) = given(Op::PAD_IN_AXES) {
+                    let axes = match axes.elem_ty() {
+                        TensorElemType::I32 => {
+                            axes.data::<i32>().iter().map(|&a| a as i64).collect()
+                        }
+                        _ => axes.data::<i64>().to_vec(),
+                    };
+                    if pad.pads.len() != axes.len() * 2 {
+                        return Err(ShapeError::Message(
+                            format!("{name}: Pads {:?} don't match axes {axes:?}", pad.pads).into(),
+                        ));
+                    }
+                    let mut pads = vec![0; rank * 2];
+                    for (i, mut axis) in axes.iter().copied().enumerate() {
+                        normalize_axis(name, &mut axis, rank)?;
+                        pads[axis as usize] = pad.pads[i];
+                        pads[rank + axis as usize] = pad.pads[axes.len() + i];
+                    }
+                    pad.pads = pads;
+                }
+                if pad.pads.len() != rank * 2 {
+                    return Err(ShapeError::Message(
+                        format!("{name}: Invalid pads {:?} for rank {rank}", pad.pads).into(),
+                    ));
+                }
+                let mut dims = Vec::with_capacity(rank);
+                for (i, &d) in data.dims().iter().enumerate() {
+                    let (begin, end) = (pad.pads[i], pad.pads[rank + i]);
+                    // Reflection doesn't repeat the edge, so it needs more elements than pads.
+                    let too_large = match pad.mode.as_str() {
+                        "reflect" => begin.max(end) >= d as i64 && d > 0,
+                        "edge" | "wrap" => begin.max(end) > 0 && d == 0,
+                        _ => false,
+                    };
+                    let len = d as i64 + begin + end;
+                    if too_large || len < 0 {
+                        return Err(ShapeError::Message(
+                            format!("{name}: Cannot pad {:?} by {:?}", data.dims(), pad.pads)
+                                .into(),
+                        ));
+                    }
+                    dims.push(len as usize);
+                }
+                shapes.push(TypedFixedShape::new(dims.into(), data.elem_ty()))
+            }
+            Op::DepthToSpace(DepthToSpace { blocksize, .. })
+            | Op::SpaceToDepth(SpaceToDepth { blocksize }) => {
+                let input = inputs[0];
+                let b = *blocksize as usize;
+                let &[n, c, h, w] = input.dims().as_slice() else {
+                    return Err(ShapeError::Message(
+                        format!("{name}: Input {:?} must be 4-D", input.dims()).into(),
+                    ));
+                };
+                let dims = match op {
+                    Op::DepthToSpace(_) if c % (b * b) == 0 => [n, c / (b * b), h * b, w * b],
+                    Op::SpaceToDepth(_) if h % b == 0 && w % b == 0 => [n, c * b * b, h / b, w / b],
+                    _ => {
+                        return Err(ShapeError::Message(
+                            format!("{name}: Input {:?} doesn't fit blocksize {b}", input.dims())
+                                .into(),
+                        ))
+                    }
+                };
+                shapes.push(TypedFixedShape::new(dims.to_vec().into(), input.elem_ty()))
             }
             Op::Concat(concat) => {
                 let mut dims = inputs[Op::CONCAT_IN].dims().clone();
@@ -322,99 +341,108 @@ impl Model {
                     ))
                 }
             }
-            Op::ReduceMin(rmin) => {
-                let in_dims = inputs[0].dims();
-                let keepdims = if rmin.keep_dims { Some(1) } else { None };
-                let axes = rmin
-                    .axes
-                    .iter()
-                    .map(|&axis| {
-                        if axis < 0 {
-                            (in_dims.len() as i64 + axis) as usize
-                        } else {
-                            axis as usize
-                        }
-                    })
-                    .collect::<Vec<_>>();
-                let mut dims = in_dims
-                    .as_slice()
-                    .iter()
-                    .enumerate()
-                    .filter_map(|(i, &d)| if axes.contains(&i) { keepdims } else { Some(d) })
-                    .collect::<Vec<_>>();
-                if dims.is_empty() {
-                    dims.push(1);
+            Op::ReduceMin(r)
+            | Op::ReduceMax(r)
+            | Op::ReduceMean(r)
+            | Op::ReduceSum(r)
+            | Op::ReduceProd(r)
+            | Op::ReduceL1(r)
+            | Op::ReduceL2(r)
+            | Op::ReduceLogSum(r)
+            | Op::ReduceLogSumExp(r)
+            | Op::ReduceSumSquare(r) => {
+                let in_dims = inputs[Op::REDUCE_IN].dims();
+                let rank = in_dims.len() as i64;
+                // From opset version 18 (13 for ReduceSum), axes are given as an input.
+                if let Some(axes) = inputs
+                    .get(Op::REDUCE_IN_AXES)
+                    .filter(|a| a.dims().total_elems() > 0)
+                {
+                    r.axes = axes.data::<i64>().to_vec();
                 }
-                shapes.push(TypedFixedShape::new(dims.into(), inputs[0].elem_ty()))
-            }
-            Op::ReduceMax(rmax) => {
-                assert!(opset_version <= 13);
-                let in_dims = inputs[0].dims();
-                let keepdims = if rmax.keep_dims { Some(1) } else { None };
-                let axes = if rmax.axes.is_empty() {
-                    in_dims
-                        .iter()
-                        .enumerate()
-                        .map(|(i, _)| i)
-                        .collect::<Vec<_>>()
-                } else {
-                    rmax.axes
-                        .iter()
-                        .map(|&axis| {
-                            if axis < 0 {
-                                (in_dims.len() as i64 + axis) as usize
-                            } else {
-                                axis as usize
-                            }
-                        })
-                        .collect::<Vec<_>>()
-                };
-                let mut dims = in_dims
+                // Axes are normalized so that kernels can use them as they are.
+                // Empty axes with `noop_with_empty_axes` stay empty, reducing no axis.
+                if r.axes.is_empty() && !r.noop_with_empty_axes {
+                    r.axes = (0..rank).collect();
+                }
+                for axis in r.axes.iter_mut() {
+                    if !(-rank..rank).contains(axis) {
+                        return Err(ShapeError::Message(
+                            format!("{name}: Axis {axis} is out of range").into(),
+                        ));
+                    }
+                    if *axis < 0 {
+                        *axis += rank;
+                    }
+                }
+                r.axes.sort_unstable();
+                r.axes.dedup();
+                let dims = in_dims
                     .as_slice()
                     .iter()
                     .enumerate()
-                    .filter_map(|(i, &d)| if axes.contains(&i) { keepdims } else { Some(d) })
+                    .filter_map(|(i, &d)| match r.axes.binary_search(&(i as i64)) {
+                        Ok(_) if r.keep_dims => Some(1),
+                        Ok(_) => None,
+                        Err(_) => Some(d),
+                    })
                     .collect::<Vec<_>>();
-                if dims.is_empty() {
-                    dims.push(1);
+                shapes.push(TypedFixedShape::new(
+                    dims.into(),
+                    inputs[Op::REDUCE_IN].elem_ty(),
+                ))
+            }
+            Op::ArgMax(a) | Op::ArgMin(a) => {
+                let in_dims = inputs[Op::ARGREDUCE_IN].dims();
+                normalize_axis(name, &mut a.axis, in_dims.len())?;
+                let mut dims = in_dims.as_slice().to_vec();
+                if a.keep_dims {
+                    dims[a.axis as usize] = 1;
+                } else {
+                    dims.remove(a.axis as usize);
                 }
-                shapes.push(TypedFixedShape::new(dims.into(), inputs[0].elem_ty()))
+                shapes.push(TypedFixedShape::new(dims.into(), TensorElemType::I64))
             }
-            Op::ReduceMean(rmean) => {
-                let in_dims = inputs[0].dims();
-                let keepdims = if rmean.keep_dims { Some(1) } else { None };
-                let axes = rmean
-                    .axes
-                    .iter()
-                    .map(|&axis| {
-                        if axis < 0 {
-                            (in_dims.len() as i64 + axis) as usize
-                        } else {
-                            axis as usize
-                        }
-                    })
-                    .collect::<Vec<_>>();
-                let mut dims = in_dims
-                    .as_slice()
+            Op::Loop(ref l) => {
+                // Scan outputs depend on the trip count, so only loops without them are
+                // inferred here, and only if the body keeps the shapes of loop-carried values.
+                let num_carried = l.body.inputs.len().saturating_sub(2);
+                if l.body.outputs.len() != num_carried + 1 {
+                    return Err(ShapeError::Message(
+                        "Loop: Shapes of scan outputs are unknown until run".into(),
+                    ));
+                }
+                let v_initial = &inputs[Op::LOOP_IN_V_INITIAL..][..num_carried];
+                let iter_num = Tensor::new(vec![].into(), vec![0i64]);
+                let cond = Tensor::new(vec![].into(), vec![true]);
+                let mut body_inputs = vec![&iter_num, &cond];
+                body_inputs.extend(&inputs[Op::LOOP_IN_V_INITIAL..]);
+                let carried = v_initial
                     .iter()
-                    .enumerate()
-                    .filter_map(|(i, &d)| if axes.contains(&i) { keepdims } else { Some(d) })
+                    .map(|v| TypedFixedShape::new(v.dims().clone(), v.elem_ty()))
                     .collect::<Vec<_>>();
-                if dims.is_empty() {
-                    dims.push(1);
+                match self.infer_subgraph_output_shapes(&l.body, &body_inputs)? {
+                    Some(body_shapes) if body_shapes[1..] == carried[..] => shapes = carried,
+                    _ => {
+                        return Err(ShapeError::Message(
+                            "Loop: Shapes of loop-carried values may change".into(),
+                        ))
+                    }
+                }
+            }
+            Op::If(ref i) => {
+                let num_then = i.then_branch.outer_inputs.len();
+                let (then_inputs, else_inputs) = inputs[Op::IF_IN_COND + 1..].split_at(num_then);
+                let then_shapes = self.infer_subgraph_output_shapes(&i.then_branch, then_inputs)?;
+                let else_shapes = self.infer_subgraph_output_shapes(&i.else_branch, else_inputs)?;
+                match (then_shapes, else_shapes) {
+                    (Some(t), Some(e)) if t == e => shapes = t,
+                    _ => {
+                        return Err(ShapeError::Message(
+                            "If: Output shapes depend on the condition".into(),
+                        ))
+                    }
                 }
-                shapes.push(TypedFixedShape::new(dims.into(), inputs[0].elem_ty()))
-            }
-            Op::Loop => {
-                assert!(inputs.len() == 3);
-                let _m = inputs[0].data::<i64>();
-                let cond = inputs[1].data::<u8>();
-                assert!(cond[0] == 1);
-                let v_initial = inputs[2].data::<i32>();
-                assert!(v_initial[0] == 0);
-                return Err(ShapeError::Message("Loop: Unsupported op".into()));
-                // shapes.push(vec![1].into());
-                // shapes.push(vec![m[0] as usize].into());
             }
             Op::Tile => {
                 let in_dims = inputs[Op::TILE_IN].dims();
@@ -491,23 +519,109 @@ impl Model {
                 ))
             }
             Op::Gather(gather) => {
-                let mut data = inputs[0].dims().0.to_owned();
-                let indices = inputs[1].dims();
-                assert!(gather.axis >= 0);
-                assert!(
-                    indices.is_scalar() || (indices.len() == 2 && indices[0] == 1),
-                    "Unsupported indices shape: {indices:?}"
-                );
-                if indices.is_scalar() {
-                    data.remove(gather.axis as usize);
-                    shapes.push(TypedFixedShape::new(data.into(), inputs[0].elem_ty()))
-                } else {
-                    assert_eq!(gather.axis, 0);
-                    data.remove(gather.axis as usize);
-                    data.insert(0, 1);
-                    data.insert(1, indices[1]);
-                    shapes.push(TypedFixedShape::new(data.into(), inputs[0].elem_ty()))
+                let data = inputs[Op::GATHER_IN_DATA].dims();
+                normalize_axis(name, &mut gather.axis, data.len())?;
+                let axis = gather.axis as usize;
+                let mut dims = data[..axis].to_vec();
+                dims.extend(inputs[Op::GATHER_IN_INDICES].dims().iter());
+                dims.extend(data[axis + 1..].iter());
+                shapes.push(TypedFixedShape::new(
+                    dims.into(),
+                    inputs[Op::GATHER_IN_DATA].elem_ty(),
+                ))
+            }
+            Op::GatherElements(gather) => {
+                let data = inputs[Op::GATHERELEMENTS_IN_DATA];
+                let indices = inputs[Op::GATHERELEMENTS_IN_INDICES].dims();
+                normalize_axis(name, &mut gather.axis, data.dims().len())?;
+                if indices.len() != data.dims().len() {
+                    return Err(ShapeError::Message(
+                        format!("{name}: Ranks of data and indices differ").into(),
+                    ));
+                }
+                shapes.push(TypedFixedShape::new(indices.clone(), data.elem_ty()))
+            }
+            Op::GatherND(gather) => {
+                let data = inputs[Op::GATHERND_IN_DATA];
+                let indices = inputs[Op::GATHERND_IN_INDICES].dims();
+                let batch_dims = gather.batch_dims as usize;
+                let Some((&index_len, outer)) = indices.split_last() else {
+                    return Err(ShapeError::Message(
+                        format!("{name}: Indices must not be a scalar").into(),
+                    ));
+                };
+                if gather.batch_dims < 0
+                    || batch_dims >= indices.len().min(data.dims().len())
+                    || batch_dims + index_len > data.dims().len()
+                {
+                    return Err(ShapeError::Message(
+                        format!("{name}: Invalid shapes of data and indices").into(),
+                    ));
                 }
+                let mut dims = outer.to_vec();
+                dims.extend(data.dims()[batch_dims + index_len..].iter());
+                shapes.push(TypedFixedShape::new(dims.into(), data.elem_ty()))
+            }
+            Op::ScatterElements(scatter) => {
+                let data = inputs[Op::SCATTER_IN_DATA];
+                normalize_axis(name, &mut scatter.axis, data.dims().len())?;
+                shapes.push(TypedFixedShape::new(data.dims().clone(), data.elem_ty()))
+            }
+            Op::ScatterND(_) => {
+                let data = inputs[Op::SCATTER_IN_DATA];
+                shapes.push(TypedFixedShape::new(data.dims().clone(), data.elem_ty()))
+            }
+            Op::TopK(topk) => {
+                let x = inputs[Op::TOPK_IN_X];
+                normalize_axis(name, &mut topk.axis, x.dims().len())?;
+                let k = inputs[Op::TOPK_IN_K].data::<i64>()[0];
+                let mut dims = x.dims().clone();
+                let axis = topk.axis as usize;
+                if !(0..=dims[axis] as i64).contains(&k) {
+                    return Err(ShapeError::Message(
+                        format!("{name}: K {k} is out of range").into(),
+                    ));
+                }
+                dims[axis] = k as usize;
+                shapes.push(TypedFixedShape::new(dims.clone(), x.elem_ty()));
+                shapes.push(TypedFixedShape::new(dims, TensorElemType::I64))
+            }
+            Op::OneHot(onehot) => {
+                let indices = inputs[Op::ONEHOT_IN_INDICES].dims();
+                let depth = inputs[Op::ONEHOT_IN_DEPTH];
+                let depth = match depth.elem_ty() {
+                    TensorElemType::I64 => depth.data::<i64>()[0],
+                    TensorElemType::I32 => depth.data::<i32>()[0] as i64,
+                    TensorElemType::F32 => depth.data::<f32>()[0] as i64,
+                    ty => {
+                        return Err(ShapeError::Message(
+                            format!("{name}: Unsupported depth type {ty:?}").into(),
+                        ))
+                    }
+                };
+                if depth < 0 {
+                    return Err(ShapeError::Message(
+                        format!("{name}: Negative depth {depth}").into(),
+                    ));
+                }
+                // The new axis is counted in the output rank.
+                normalize_axis(name, &mut onehot.axis, indices.len() + 1)?;
+                let mut dims = indices.as_slice().to_vec();
+                dims.insert(onehot.axis as usize, depth as usize);
+                shapes.push(TypedFixedShape::new(
+                    dims.into(),
+                    inputs[Op::ONEHOT_IN_VALUES].elem_ty(),
+                ))
+            }
+            Op::NonZero => {
+                // The number of nonzero elements depends on the values of the input.
+                return Err(ShapeError::Message(
+                    "NonZero: Output shapes are known only after running".into(),
+                ));
+            }
+            Op::CumSum(_) => {
+                let x = inputs[Op::CUMSUM_IN_X];
+                shapes.push(TypedFixedShape::new(x.dims().clone(), x.elem_ty()))
             }
             Op::Shape(shape) => {
                 assert!(shape.end.is_none());
@@ -518,10 +632,11 @@ impl Model {
                     TensorElemType::I64,
                 ));
             }
-            Op::NonMaxSuppression => {
+            Op::NonMaxSuppression(_) => {
+                // The number of selected boxes depends on the values of boxes and scores.
                 return Err(ShapeError::Message(
-                    "NonMaxSuppression: Unsupported op".into(),
-                ))
+                    "NonMaxSuppression: Output shapes are known only after running".into(),
+                ));
             }
             Op::MatMul => {
                 let in_a = &inputs[Op::MATMUL_IN_A].dims();
@@ -581,15 +696,156 @@ impl Model {
                     inputs[Op::GEMM_IN_A].elem_ty(),
                 ));
             }
-            Op::Constant(_) => return Err(ShapeError::Message("Constant: Unsupported op".into())),
+            Op::LSTM(_) | Op::GRU(_) | Op::RNN(_) => {
+                let (direction, layout) = match op {
+                    Op::LSTM(lstm) => (lstm.direction, lstm.layout),
+                    Op::GRU(gru) => (gru.direction, gru.layout),
+                    Op::RNN(rnn) => (rnn.direction, rnn.layout),
+                    _ => unreachable!(),
+                };
+                let x = inputs[Op::RNN_IN_X];
+                let [a, b, _] = x.fixed_dims::<3>();
+                let (seq_len, batch) = if layout == 0 { (a, b) } else { (b, a) };
+                let num_directions = direction.num_directions();
+                // R is of shape [num_directions, num_gates * hidden_size, hidden_size].
+                let hidden_size = inputs[Op::RNN_IN_R].dims()[2];
+                let y = if layout == 0 {
+                    vec![seq_len, num_directions, batch, hidden_size]
+                } else {
+                    vec![batch, seq_len, num_directions, hidden_size]
+                };
+                let state = if layout == 0 {
+                    vec![num_directions, batch, hidden_size]
+                } else {
+                    vec![batch, num_directions, hidden_size]
+                };
+                shapes.push(TypedFixedShape::new(y.into(), x.elem_ty()));
+                // Y_h, and Y_c of LSTM
+                for _ in 1..num_outputs {
+                    shapes.push(TypedFixedShape::new(state.clone().into(), x.elem_ty()));
+                }
+            }
+            Op::Constant(constant) => shapes.push(TypedFixedShape::new(
+                constant.value.dims().clone(),
+                constant.value.elem_ty(),
+            )),
+            Op::QuantizeLinear(_) => {
+                // The output type follows the zero point, which defaults to uint8.
+                let elem_ty = inputs
+                    .get(Op::QUANTIZELINEAR_IN_Y_ZERO_POINT)
+                    .filter(|zp| zp.dims().total_elems() > 0)
+                    .map_or(TensorElemType::U8, |zp| zp.elem_ty());
+                shapes.push(TypedFixedShape::new(
+                    inputs[Op::QUANTIZELINEAR_IN_X].dims().clone(),
+                    elem_ty,
+                ));
+            }
+            Op::DequantizeLinear(_) => {
+                shapes.push(TypedFixedShape::new(
+                    inputs[Op::DEQUANTIZELINEAR_IN_X].dims().clone(),
+                    inputs[Op::DEQUANTIZELINEAR_IN_X_SCALE].elem_ty(),
+                ));
+            }
+            Op::QLinearConv(conv) => {
+                let dims = conv2d_output_dims(
+                    conv,
+                    inputs[Op::QLINEARCONV_IN_X].dims(),
+                    inputs[Op::QLINEARCONV_IN_W].dims(),
+                )?;
+                shapes.push(TypedFixedShape::new(
+                    dims,
+                    inputs[Op::QLINEARCONV_IN_Y_ZERO_POINT].elem_ty(),
+                ));
+            }
+            Op::ConvInteger(conv) => {
+                let dims = conv2d_output_dims(
+                    conv,
+                    inputs[Op::CONVINTEGER_IN_X].dims(),
+                    inputs[Op::CONVINTEGER_IN_W].dims(),
+                )?;
+                shapes.push(TypedFixedShape::new(dims, TensorElemType::I32));
+            }
+            Op::QLinearMatMul => {
+                let dims = matmul_output_dims(
+                    inputs[Op::QLINEARMATMUL_IN_A].dims(),
+                    inputs[Op::QLINEARMATMUL_IN_B].dims(),
+                )?;
+                shapes.push(TypedFixedShape::new(
+                    dims,
+                    inputs[Op::QLINEARMATMUL_IN_Y_ZERO_POINT].elem_ty(),
+                ));
+            }
+            Op::MatMulInteger => {
+                let dims = matmul_output_dims(
+                    inputs[Op::MATMULINTEGER_IN_A].dims(),
+                    inputs[Op::MATMULINTEGER_IN_B].dims(),
+                )?;
+                shapes.push(TypedFixedShape::new(dims, TensorElemType::I32));
+            }
+            Op::FusedMatMul(matmul) => {
+                let transpose = |dims: &FixedDimensions, trans: bool| {
+                    let mut dims = dims.clone();
+                    let rank = dims.len();
+                    if trans && rank >= 2 {
+                        dims.0.swap(rank - 2, rank - 1);
+                    }
+                    dims
+                };
+                let dims = matmul_output_dims(
+                    &transpose(inputs[Op::FUSEDMATMUL_IN_A].dims(), matmul.trans_a),
+                    &transpose(inputs[Op::FUSEDMATMUL_IN_B].dims(), matmul.trans_b),
+                )?;
+                shapes.push(TypedFixedShape::new(
+                    dims,
+                    inputs[Op::FUSEDMATMUL_IN_A].elem_ty(),
+                ));
+            }
+            Op::Attention(attention) => {
+                let input = inputs[Op::ATTENTION_IN_INPUT];
+                let weights = inputs[Op::ATTENTION_IN_WEIGHTS].dims();
+                let &[batch, seq_len, _] = input.dims().as_slice() else {
+                    return Err(ShapeError::Message(
+                        format!("Attention: Input must be 3-D: {:?}", input.dims()).into(),
+                    ));
+                };
+                let v_hidden_size = match attention.qkv_hidden_sizes.as_slice() {
+                    [] if weights.len() == 2 => weights[1] / 3,
+                    &[_, _, v] => v,
+                    _ => {
+                        return Err(ShapeError::Message(
+                            "Attention: Invalid qkv_hidden_sizes or weights".into(),
+                        ))
+                    }
+                };
+                shapes.push(TypedFixedShape::new(
+                    vec![batch, seq_len, v_hidden_size].into(),
+                    input.elem_ty(),
+                ));
+            }
             // Element-wise operations.
             Op::Sqrt
+            | Op::Not
             | Op::ReLU
             | Op::LeakyReLU(_)
             | Op::Gelu
             | Op::Sigmoid
             | Op::Erf
             | Op::Tanh
+            | Op::Abs
+            | Op::Neg
+            | Op::Log
+            | Op::Reciprocal
+            | Op::Floor
+            | Op::Ceil
+            | Op::Sin
+            | Op::Cos
+            | Op::Softplus
+            | Op::Softsign
+            | Op::Elu(_)
+            | Op::Selu(_)
+            | Op::Mish
+            | Op::HardSwish
+            | Op::Sign
             | Op::Clip
             | Op::HardSigmoid(_)
             | Op::Round
@@ -599,16 +855,72 @@ impl Model {
                 let input = inputs[0];
                 shapes.push(TypedFixedShape::new(input.dims().clone(), input.elem_ty()));
             }
+            Op::PRelu => {
+                // The slope is broadcast to the input, and not vice versa.
+                let (input, slope) = (inputs[0], inputs[1]);
+                if slope
+                    .dims()
+                    .strides_for_broadcasting_to(input.dims())
+                    .is_none()
+                {
+                    return Err(ShapeError::Message(
+                        format!(
+                            "PRelu: Cannot broadcast slope {:?} to {:?}",
+                            slope.dims(),
+                            input.dims()
+                        )
+                        .into(),
+                    ));
+                }
+                shapes.push(TypedFixedShape::new(input.dims().clone(), input.elem_ty()));
+            }
             Op::LayerNormalization(ln) => {
                 assert!(num_outputs == 1);
                 let input = inputs[0];
                 assert!(ln.stash_type == 1);
                 shapes.push(TypedFixedShape::new(input.dims().clone(), input.elem_ty()));
             }
+            Op::InstanceNormalization(_) | Op::GroupNormalization(_) => {
+                let input = inputs[Op::NORM_IN_X];
+                let channels = input.dims().get(1).copied().unwrap_or(0);
+                // Scale and bias of GroupNormalization are given per group until opset version 21.
+                let groups = match op {
+                    Op::GroupNormalization(g) => g.num_groups as usize,
+                    _ => channels,
+                };
+                let params_ok = [Op::NORM_IN_SCALE, Op::NORM_IN_B].iter().all(|&i| {
+                    let len = inputs[i].dims().total_elems();
+                    len == channels || len == groups
+                });
+                if input.dims().len() < 2 || groups == 0 || channels % groups != 0 || !params_ok {
+                    return Err(ShapeError::Message(
+                        format!(
+                            "{name}: Invalid input {:?}, scale {:?} or bias {:?}",
+                            input.dims(),
+                            inputs[Op::NORM_IN_SCALE].dims(),
+                            inputs[Op::NORM_IN_B].dims()
+                        )
+                        .into(),
+                    ));
+                }
+                shapes.push(TypedFixedShape::new(input.dims().clone(), input.elem_ty()));
+            }
             Op::Cast(cast) => {
                 let input = inputs[0];
                 shapes.push(TypedFixedShape::new(input.dims().clone(), cast.to));
             }
+            Op::Custom(ref custom) => {
+                let Some(custom_op) = self.custom_ops.get(custom) else {
+                    return Err(ShapeError::Message(
+                        format!(
+                            "Custom: No op is registered for {}.{}",
+                            custom.domain, custom.op_type
+                        )
+                        .into(),
+                    ));
+                };
+                shapes = custom_op.compute_output_shapes(custom, inputs, num_outputs)?;
+            }
             Op::FusedElemwise(ref mut f) => {
                 let mut map = FxHashMap::default();
                 for (i, val_id) in f.input_map.iter().enumerate() {
@@ -650,6 +962,374 @@ impl Model {
     }
 }
 
+/// Computes the output dimensions of an N-D convolution.
+/// `conv.padding` is normalized to `[x1_begin, x2_begin, ..., x1_end, x2_end, ...]`.
+fn conv2d_output_dims(
+    conv: &mut Conv2d,
+    input: &FixedDimensions,
+    weight: &FixedDimensions,
+) -> Result<FixedDimensions, ShapeError> {
+    let rank = input.len().saturating_sub(2);
+    if rank == 0 || weight.len() != input.len() {
+        return Err(ShapeError::Message(
+            format!("Conv: Input {input:?} does not match weight {weight:?}").into(),
+        ));
+    }
+    if conv.kernel_shape.is_empty() {
+        conv.kernel_shape = weight[2..].to_vec().into();
+    }
+    if conv.strides.is_empty() {
+        conv.strides = vec![1; rank].into();
+    }
+    if conv.dilations.is_empty() {
+        conv.dilations = vec![1; rank].into();
+    }
+    if conv.padding.is_empty() {
+        conv.padding = vec![0; rank * 2].into();
+    } else if conv.padding.len() == rank {
+        // Pads of only one side (as in `[h, w]`) apply to both sides.
+        conv.padding = [&conv.padding[..], &conv.padding[..]].concat().into();
+    }
+    if conv.kernel_shape.len() != rank
+        || conv.strides.len() != rank
+        || conv.dilations.len() != rank
+        || conv.padding.len() != rank * 2
+    {
+        return Err(ShapeError::Message(
+            format!(
+                "Conv: Invalid kernel {:?}, strides {:?}, dilations {:?} or pads {:?}",
+                conv.kernel_shape, conv.strides, conv.dilations, conv.padding
+            )
+            .into(),
+        ));
+    }
+
+    let spatial = &input[2..];
+    let window = |i: usize| (conv.kernel_shape[i] - 1) * conv.dilations[i] + 1;
+
+    match conv.auto_pad.as_str() {
+        "" | "NOTSET" => {}
+        "VALID" => conv.padding = vec![0; rank * 2].into(),
+        auto_pad @ ("SAME_UPPER" | "SAME_LOWER") => {
+            for (i, &len) in spatial.iter().enumerate() {
+                let out = len.div_ceil(conv.strides[i]);
+                let total = ((out - 1) * conv.strides[i] + window(i)).saturating_sub(len);
+                (conv.padding[i], conv.padding[rank + i]) = split_same_padding(total, auto_pad);
+            }
+        }
+        auto_pad => {
+            return Err(ShapeError::Message(
+                format!("Conv: Unknown auto_pad: {auto_pad}").into(),
+            ))
+        }
+    }
+
+    let mut dims = vec![input[0], weight[0]];
+    for (i, &len) in spatial.iter().enumerate() {
+        let padded = len + conv.padding[i] + conv.padding[rank + i];
+        if padded < window(i) {
+            return Err(ShapeError::Message(
+                format!(
+                    "Conv: Kernel {:?} is larger than input {input:?}",
+                    conv.kernel_shape
+                )
+                .into(),
+            ));
+        }
+        dims.push((padded - window(i)) / conv.strides[i] + 1);
+    }
+    Ok(dims.into())
+}
+
+/// Computes the output dimensions of an N-D transposed convolution.
+/// `conv.padding` is computed from `conv.output_shape` or `conv.auto_pad` if either is given.
+fn conv_transpose_output_dims(
+    conv: &mut ConvTranspose,
+    input: &FixedDimensions,
+    weight: &FixedDimensions,
+) -> Result<FixedDimensions, ShapeError> {
+    let rank = input.len().saturating_sub(2);
+    if rank == 0 || weight.len() != input.len() {
+        return Err(ShapeError::Message(
+            format!("ConvTranspose: Input {input:?} does not match weight {weight:?}").into(),
+        ));
+    }
+    if conv.kernel_shape.is_empty() {
+        conv.kernel_shape = weight[2..].to_vec().into();
+    }
+    if conv.strides.is_empty() {
+        conv.strides = vec![1; rank].into();
+    }
+    if conv.dilations.is_empty() {
+        conv.dilations = vec![1; rank].into();
+    }
+    if conv.output_padding.is_empty() {
+        conv.output_padding = vec![0; rank].into();
+    }
+    if conv.padding.is_empty() {
+        conv.padding = vec![0; rank * 2].into();
+    }
+    if conv.kernel_shape.len() != rank
+        || conv.strides.len() != rank
+        || conv.dilations.len() != rank
+        || conv.output_padding.len() != rank
+        || conv.padding.len() != rank * 2
+    {
+        return Err(ShapeError::Message(
+            format!("ConvTranspose: Invalid attributes: {conv:?}").into(),
+        ));
+    }
+
+    let spatial = &input[2..];
+    // The output length without pads.
+    let full = |conv: &ConvTranspose, i: usize| {
+        conv.strides[i] * (spatial[i].max(1) - 1)
+            + conv.output_padding[i]
+            + (conv.kernel_shape[i] - 1) * conv.dilations[i]
+            + 1
+    };
+
+    let output_shape = match conv.auto_pad.as_str() {
+        _ if !conv.output_shape.is_empty() => {
+            // `output_shape` may include the batch and channel axes.
+            Some(conv.output_shape[conv.output_shape.len().saturating_sub(rank)..].to_vec())
+        }
+        "" | "NOTSET" => None,
+        "VALID" => {
+            conv.padding = vec![0; rank * 2].into();
+            None
+        }
+        "SAME_UPPER" | "SAME_LOWER" => Some(
+            spatial
+                .iter()
+                .zip(conv.strides.iter())
+                .map(|(&len, &stride)| len * stride)
+                .collect(),
+        ),
+        auto_pad => {
+            return Err(ShapeError::Message(
+                format!("ConvTranspose: Unknown auto_pad: {auto_pad}").into(),
+            ))
+        }
+    };
+    if let Some(output_shape) = output_shape {
+        if output_shape.len() != rank {
+            return Err(ShapeError::Message(
+                format!("ConvTranspose: Invalid output_shape: {output_shape:?}").into(),
+            ));
+        }
+        for (i, &len) in output_shape.iter().enumerate() {
+            let full = full(conv, i);
+            if len > full {
+                // The output goes past the input, like with `output_padding`.
+                conv.output_padding[i] += len - full;
+                (conv.padding[i], conv.padding[rank + i]) = (0, 0);
+            } else {
+                (conv.padding[i], conv.padding[rank + i]) =
+                    split_same_padding(full - len, &conv.auto_pad);
+            }
+        }
+    }
+
+    let mut dims = vec![input[0], weight[1] * conv.group as usize];
+    for i in 0..rank {
+        let pads = conv.padding[i] + conv.padding[rank + i];
+        dims.push(full(conv, i).checked_sub(pads).ok_or_else(|| {
+            ShapeError::Message(format!("ConvTranspose: Pads are too large: {conv:?}").into())
+        })?);
+    }
+    Ok(dims.into())
+}
+
+/// Makes `axis` of a tensor of `rank` non-negative, so that kernels can use it as it is.
+fn normalize_axis(name: &str, axis: &mut i64, rank: usize) -> Result<(), ShapeError> {
+    let rank = rank as i64;
+    if !(-rank..rank).contains(axis) {
+        return Err(ShapeError::Message(
+            format!("{name}: Axis {axis} is out of range").into(),
+        ));
+    }
+    if *axis < 0 {
+        *axis += rank;
+    }
+    Ok(())
+}
+
+/// Splits the `total` padding of an axis into `(begin, end)`.
+/// The odd one goes to the end for `SAME_UPPER`, and to the beginning otherwise.
+fn split_same_padding(total: usize, auto_pad: &str) -> (usize, usize) {
+    if auto_pad == "SAME_UPPER" {
+        (total / 2, total - total / 2)
+    } else {
+        (total - total / 2, total / 2)
+    }
+}
+
+/// Computes the output dimensions of `MaxPool`, `AveragePool` and `LpPool`.
+/// `pool.strides`, `pool.dilations` and `pool.padding` (`[x1_begin, x2_begin, ..., x1_end, x2_end, ...]`)
+/// are normalized so that every spatial axis has its own values.
+fn pool_output_dims(
+    pool: &mut Pool,
+    input: &FixedDimensions,
+) -> Result<FixedDimensions, ShapeError> {
+    let rank = pool.kernel_shape.len();
+    if input.len() != rank + 2 {
+        return Err(ShapeError::Message(
+            format!(
+                "Pool: Input {input:?} does not match kernel {:?}",
+                pool.kernel_shape
+            )
+            .into(),
+        ));
+    }
+    if pool.strides.is_empty() {
+        pool.strides = vec![1; rank].into();
+    }
+    if pool.dilations.is_empty() {
+        pool.dilations = vec![1; rank].into();
+    }
+    if pool.padding.is_empty() {
+        pool.padding = vec![0; rank * 2].into();
+    }
+    if pool.strides.len() != rank || pool.dilations.len() != rank || pool.padding.len() != rank * 2
+    {
+        return Err(ShapeError::Message(
+            format!(
+                "Pool: Invalid strides {:?}, dilations {:?} or pads {:?}",
+                pool.strides, pool.dilations, pool.padding
+            )
+            .into(),
+        ));
+    }
+
+    let spatial = &input[2..];
+    let window = |i: usize| (pool.kernel_shape[i] - 1) * pool.dilations[i] + 1;
+
+    match pool.auto_pad.as_str() {
+        "" | "NOTSET" => {}
+        "VALID" => pool.padding = vec![0; rank * 2].into(),
+        auto_pad @ ("SAME_UPPER" | "SAME_LOWER") => {
+            for (i, &len) in spatial.iter().enumerate() {
+                let out = len.div_ceil(pool.strides[i]);
+                let total = ((out - 1) * pool.strides[i] + window(i)).saturating_sub(len);
+                (pool.padding[i], pool.padding[rank + i]) = split_same_padding(total, auto_pad);
+            }
+        }
+        auto_pad => {
+            return Err(ShapeError::Message(
+                format!("Pool: Unknown auto_pad: {auto_pad}").into(),
+            ))
+        }
+    }
+
+    let mut dims = input[..2].to_vec();
+    for (i, &len) in spatial.iter().enumerate() {
+        let (stride, pad_begin) = (pool.strides[i], pool.padding[i]);
+        let padded = len + pad_begin + pool.padding[rank + i];
+        if padded < window(i) {
+            return Err(ShapeError::Message(
+                format!(
+                    "Pool: Kernel {:?} is larger than input {input:?}",
+                    pool.kernel_shape
+                )
+                .into(),
+            ));
+        }
+        let mut out = if pool.ceil_mode {
+            (padded - window(i)).div_ceil(stride) + 1
+        } else {
+            (padded - window(i)) / stride + 1
+        };
+        // With `ceil_mode`, the last window must start within the input or the begin padding.
+        if pool.ceil_mode && (out - 1) * stride >= len + pad_begin {
+            out -= 1;
+        }
+        dims.push(out);
+    }
+    Ok(dims.into())
+}
+
+/// Computes the output dimensions of elementwise ops by broadcasting all the inputs.
+fn broadcast_inputs(op: &Op, inputs: &[&Tensor]) -> Result<FixedDimensions, ShapeError> {
+    let dims = inputs.iter().map(|input| input.dims()).collect::<Vec<_>>();
+    fixed_dim::broadcast(&dims).ok_or_else(|| {
+        ShapeError::Message(format!("{}: Cannot broadcast {dims:?}", op.name()).into())
+    })
+}
+
+/// Computes the output dimensions of `numpy.matmul`.
+/// Both inputs must be at least two-dimensional.
+fn matmul_output_dims(
+    a: &FixedDimensions,
+    b: &FixedDimensions,
+) -> Result<FixedDimensions, ShapeError> {
+    if a.len() < 2 || b.len() < 2 {
+        return Err(ShapeError::Message(
+            format!("MatMul: Unsupported shapes: {a:?}, {b:?}").into(),
+        ));
+    }
+    let (a_batch, a_mat) = a.as_slice().split_at(a.len() - 2);
+    let (b_batch, b_mat) = b.as_slice().split_at(b.len() - 2);
+    if a_mat[1] != b_mat[0] {
+        return Err(ShapeError::Message(
+            format!("MatMul: Shape mismatch: {a:?}, {b:?}").into(),
+        ));
+    }
+    let batch = FixedDimensions::from(a_batch.to_vec())
+        .broadcast(FixedDimensions::from(b_batch.to_vec()))
+        .ok_or_else(|| {
+            ShapeError::Message(format!("MatMul: Cannot broadcast {a:?} and {b:?}").into())
+        })?;
+    let mut dims = batch.0;
+    dims.extend([a_mat[0], b_mat[1]]);
+    Ok(dims.into())
+}
+
+/// Computes the number of elements `Range` produces.
+pub(crate) fn range_len(start: f64, limit: f64, delta: f64) -> Result<usize, ShapeError> {
+    let len = ((limit - start) / delta).ceil();
+    if delta == 0. || !len.is_finite() {
+        return Err(ShapeError::Message(
+            format!("Range: Invalid delta {delta} for [{start}, {limit})").into(),
+        ));
+    }
+    Ok(len.max(0.) as usize)
+}
+
+impl Model {
+    /// Infers the output shapes of `graph`, a subgraph of this model, given `inputs` for its
+    /// inputs followed by its outer-scope values. Returns `None` if any of them is unknown.
+    fn infer_subgraph_output_shapes(
+        &self,
+        graph: &Graph,
+        inputs: &[&Tensor],
+    ) -> Result<Option<Vec<TypedFixedShape>>, ShapeError> {
+        let model = self.subgraph_model(graph);
+        let mut values = model.graph.inits.clone();
+        for (&id, &input) in graph
+            .inputs
+            .iter()
+            .chain(graph.outer_inputs.iter())
+            .zip(inputs)
+        {
+            values.insert(id, input.clone());
+        }
+        let mut shapes = FxHashMap::default();
+        for node in model.topo_sort_nodes() {
+            infer_shape(&model, &mut values, &mut shapes, node)?;
+        }
+        Ok(graph
+            .outputs
+            .iter()
+            .map(|id| {
+                values
+                    .get(id)
+                    .map(|t| TypedFixedShape::new(t.dims().clone(), t.elem_ty()))
+            })
+            .collect())
+    }
+}
+
 /// Infer `TypedFixedShape`s of output tensors for each node.
 /// It skips to infer on nodes without information for inference.
 pub fn infer_shapes(
@@ -657,22 +1337,48 @@ pub fn infer_shapes(
     shapes: &mut FxHashMap<NodeId, (Op, Vec<TypedFixedShape>)>,
     value_shapes: &mut FxHashMap<ValueId, TypedFixedShape>,
 ) -> Result<(), ShapeError> {
-    let sorted_nodes = model.topo_sort_nodes();
-    let mut values = model.graph.inits.clone();
+    let mut input_shapes = FxHashMap::default();
 
     for &val_id in &model.graph.inputs {
         let shape = &model.graph.values.inner()[val_id].shape;
         let Some(shape) = shape else { continue };
-        let tensor = Tensor::zeros_of_type(
-            shape.elem_ty,
-            shape
-                .dims
-                .as_fixed_dims()
-                .ok_or_else(|| ShapeError::Message("Must be fixed dimension".into()))?,
-        );
+        let dims = shape
+            .dims
+            .as_fixed_dims()
+            .ok_or_else(|| ShapeError::Message("Must be fixed dimension".into()))?;
+        input_shapes.insert(val_id, TypedFixedShape::new(dims, shape.elem_ty));
+    }
+
+    infer_shapes_from_inputs(model, &input_shapes, shapes, value_shapes)
+}
+
+/// Same as `infer_shapes`, but uses `input_shapes` as the shapes of graph inputs
+/// instead of the ones recorded in the model.
+/// This is useful to resolve dynamic dimensions once their actual sizes are known.
+pub fn infer_shapes_from_inputs(
+    model: &Model,
+    input_shapes: &FxHashMap<ValueId, TypedFixedShape>,
+    shapes: &mut FxHashMap<NodeId, (Op, Vec<TypedFixedShape>)>,
+    value_shapes: &mut FxHashMap<ValueId, TypedFixedShape>,
+) -> Result<(), ShapeError> {
+    let sorted_nodes = model.topo_sort_nodes();
+    let mut values = model.graph.inits.clone();
+
+    for &val_id in &model.graph.inputs {
+        let Some(shape) = input_shapes.get(&val_id) else {
+            continue;
+        };
+        let tensor = Tensor::zeros_of_type(shape.elem_ty, shape.dims.clone());
         values.insert(val_id, tensor);
     }
 
+    // Omitted optional inputs are named "" and given as empty tensors.
+    for (val_id, val) in model.graph.values.inner().iter() {
+        if val.name.as_deref() == Some("") && !values.contains_key(&val_id) {
+            values.insert(val_id, Tensor::zeros::<f32>(vec![0].into()));
+        }
+    }
+
     for node in sorted_nodes {
         infer_shape(model, &mut values, shapes, node)?
     }
@@ -702,7 +1408,12 @@ fn infer_shape(
         };
         inputs.push(input);
     }
-    let output_shapes = model.compute_output_shapes(&mut op, &inputs, node.outputs.len())?;
+    let output_shapes = match model.compute_output_shapes(&mut op, &inputs, node.outputs.len()) {
+        Ok(shapes) => shapes,
+        // Output shapes of some ops may be known only after running them.
+        Err(_) if op.has_dynamic_output_shapes() => return Ok(()),
+        Err(e) => return Err(e),
+    };
     let mut outputs = vec![];
     for shape in &output_shapes {
         outputs.push(Tensor::empty_of_type(shape.elem_ty, shape.dims.clone()));
@@ -713,3 +1424,20 @@ fn infer_shape(
     shapes.insert(node_id, (op, output_shapes));
     Ok(())
 }
+
+#[test]
+fn range_with_zero_delta() {
+    let model = Model::default();
+    let range =
+        |inputs: [Tensor; 3]| model.compute_output_shapes(&mut Op::Range, &inputs.each_ref(), 1);
+    let scalar = |x: f32| Tensor::new(vec![].into(), vec![x]);
+    assert!(range([scalar(0.), scalar(1.), scalar(0.)]).is_err());
+    assert!(range([scalar(0.), scalar(1.), scalar(f32::NAN)]).is_err());
+    let shapes = range([scalar(0.), scalar(1.), scalar(0.3)]).unwrap();
+    assert_eq!(shapes[0].dims.as_slice(), &[4]);
+
+    let scalar = |x: i64| Tensor::new(vec![].into(), vec![x]);
+    assert!(range([scalar(0), scalar(1), scalar(0)]).is_err());
+    let shapes = range([scalar(10), scalar(4), scalar(-3)]).unwrap();
+    assert_eq!(shapes[0].dims.as_slice(), &[2]);
+}
@@ -0,0 +1,1342 @@
+use std::{borrow::Cow, collections::BTreeMap, fmt};
+
+use rustc_hash::FxHashMap;
+
+use crate::{
+    dim::{Dimension, Dimensions},
+    model::Model,
+    node::{Node, NodeId},
+    op::{DepthToSpace, Op, SpaceToDepth},
+    tensor::{Tensor, TensorElemType, TypedShape},
+    value::ValueId,
+};
+
+use super::shape::ShapeError;
+
+/// A polynomial over dimension symbols with integer coefficients (e.g. `batch*seq`, `seq*2+1`).
+/// Each key is a sorted list of symbols (a monomial), and zero coefficients are never stored.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct SymExpr(BTreeMap<Vec<String>, i64>);
+
+#[derive(Debug, Clone)]
+struct SymShape {
+    dims: Vec<SymExpr>,
+    elem_ty: TensorElemType,
+}
+
+/// Values of small integer tensors (typically the outputs of `Shape` and what is computed from them).
+type SymValues = FxHashMap<ValueId, Vec<SymExpr>>;
+
+/// The maximum number of elements of an integer tensor whose values are tracked.
+const MAX_TRACKED_ELEMS: usize = 64;
+
+/// Infer shapes of all values, allowing dynamic dimensions.
+/// Dimensions derived from symbols (e.g. `seq*2` as a result of concatenating two tensors
+/// along the `seq` axis) are expressed as `Dimension::Dynamic`.
+/// Inferred shapes are written back into `Value::shape`.
+pub fn infer_symbolic_shapes(model: &mut Model) -> Result<(), ShapeError> {
+    let mut shapes: FxHashMap<ValueId, SymShape> = FxHashMap::default();
+    let mut values: SymValues = FxHashMap::default();
+
+    for (id, value) in model.graph.values.inner().iter() {
+        let Some(shape) = &value.shape else { continue };
+        shapes.insert(
+            id,
+            SymShape {
+                dims: shape.dims.iter().map(SymExpr::from_dim).collect(),
+                elem_ty: shape.elem_ty,
+            },
+        );
+    }
+
+    for (&id, tensor) in &model.graph.inits {
+        shapes.insert(id, SymShape::from_tensor(tensor));
+        if let Some(vals) = tensor_values(tensor) {
+            values.insert(id, vals);
+        }
+    }
+
+    let mut inferred = vec![];
+    for node_id in model.topo_sort_nodes() {
+        let node = &model.graph.nodes[node_id];
+        let Some(outputs) = infer_node(model, node_id, &shapes, &values)? else {
+            log::debug!("Could not infer shapes of {}", node.op.name());
+            continue;
+        };
+        if let Some(vals) = propagate_values(node, &shapes, &values) {
+            values.insert(node.outputs[0], vals);
+        }
+        for (&id, shape) in node.outputs.iter().zip(outputs) {
+            shapes.insert(id, shape.clone());
+            inferred.push((id, shape));
+        }
+    }
+
+    for (id, shape) in inferred {
+        let Some(dims) = shape
+            .dims
+            .iter()
+            .map(SymExpr::to_dim)
+            .collect::<Option<Vec<_>>>()
+        else {
+            continue;
+        };
+        model.graph.values[id].shape = Some(TypedShape::new(Dimensions(dims), shape.elem_ty));
+    }
+
+    Ok(())
+}
+
+fn infer_node(
+    model: &Model,
+    node_id: NodeId,
+    shapes: &FxHashMap<ValueId, SymShape>,
+    values: &SymValues,
+) -> Result<Option<Vec<SymShape>>, ShapeError> {
+    let node = &model.graph.nodes[node_id];
+    let Some(inputs) = node
+        .inputs
+        .iter()
+        .map(|id| shapes.get(id))
+        .collect::<Option<Vec<_>>>()
+    else {
+        return Ok(None);
+    };
+    let const_input = |i: usize| -> Option<Vec<i64>> {
+        node.inputs
+            .get(i)
+            .and_then(|id| values.get(id))
+            .and_then(|v| v.iter().map(SymExpr::as_const).collect())
+    };
+    let same_as_input = |i: usize| -> Result<Option<Vec<SymShape>>, ShapeError> {
+        Ok(Some(vec![inputs[i].clone()]))
+    };
+
+    match &node.op {
+        Op::Add | Op::Sub | Op::Mul | Op::Div | Op::Pow => {
+            let dims = broadcast(&inputs[0].dims, &inputs[1].dims)?;
+            Ok(dims.map(|dims| vec![SymShape::new(dims, inputs[0].elem_ty)]))
+        }
+        Op::Equal
+        | Op::Less
+        | Op::LessOrEqual
+        | Op::Greater
+        | Op::GreaterOrEqual
+        | Op::And
+        | Op::Or
+        | Op::Xor => {
+            let dims = broadcast(&inputs[0].dims, &inputs[1].dims)?;
+            Ok(dims.map(|dims| vec![SymShape::new(dims, TensorElemType::Bool)]))
+        }
+        Op::Where => {
+            let Some(dims) = broadcast(&inputs[0].dims, &inputs[1].dims)? else {
+                return Ok(None);
+            };
+            let dims = broadcast(&dims, &inputs[2].dims)?;
+            Ok(dims.map(|dims| vec![SymShape::new(dims, inputs[1].elem_ty)]))
+        }
+        Op::Sqrt
+        | Op::Not
+        | Op::ReLU
+        | Op::Gelu
+        | Op::LeakyReLU(_)
+        | Op::Sigmoid
+        | Op::Erf
+        | Op::Tanh
+        | Op::Abs
+        | Op::Neg
+        | Op::Log
+        | Op::Reciprocal
+        | Op::Floor
+        | Op::Ceil
+        | Op::Sin
+        | Op::Cos
+        | Op::Softplus
+        | Op::Softsign
+        | Op::Elu(_)
+        | Op::Selu(_)
+        | Op::Mish
+        | Op::HardSwish
+        | Op::Sign
+        | Op::PRelu
+        | Op::Clip
+        | Op::Softmax(_)
+        | Op::Round
+        | Op::Exp
+        | Op::HardSigmoid(_)
+            if node.outputs.len() == 1 =>
+        {
+            same_as_input(0)
+        }
+        Op::BatchNormalization(_) | Op::LayerNormalization(_) if node.outputs.len() == 1 => {
+            same_as_input(0)
+        }
+        Op::InstanceNormalization(_) | Op::GroupNormalization(_) => same_as_input(Op::NORM_IN_X),
+        Op::QuantizeLinear(_) => {
+            let elem_ty = inputs
+                .get(Op::QUANTIZELINEAR_IN_Y_ZERO_POINT)
+                .map_or(TensorElemType::U8, |zp| zp.elem_ty);
+            Ok(Some(vec![SymShape::new(
+                inputs[Op::QUANTIZELINEAR_IN_X].dims.clone(),
+                elem_ty,
+            )]))
+        }
+        Op::DequantizeLinear(_) => Ok(Some(vec![SymShape::new(
+            inputs[Op::DEQUANTIZELINEAR_IN_X].dims.clone(),
+            inputs[Op::DEQUANTIZELINEAR_IN_X_SCALE].elem_ty,
+        )])),
+        Op::Cast(cast) => Ok(Some(vec![SymShape::new(
+            inputs[Op::CAST_IN].dims.clone(),
+            cast.to,
+        )])),
+        Op::Shape(shape) => {
+            let rank = inputs[0].dims.len() as i64;
+            let (start, end) = shape_range(shape.start, shape.end, rank);
+            Ok(Some(vec![SymShape::new(
+                vec![SymExpr::constant(end as i64 - start as i64)],
+                TensorElemType::I64,
+            )]))
+        }
+        Op::Expand => {
+            let Some(target) = node.inputs.get(1).and_then(|id| values.get(id)) else {
+                return fallback(model, node_id, &inputs, values);
+            };
+            let dims = broadcast(&inputs[0].dims, target)?;
+            Ok(dims.map(|dims| vec![SymShape::new(dims, inputs[0].elem_ty)]))
+        }
+        Op::MatMul | Op::MatMulInteger | Op::QLinearMatMul | Op::FusedMatMul(_) => {
+            let transpose = |dims: &Vec<SymExpr>, trans: bool| {
+                let mut dims = dims.clone();
+                let rank = dims.len();
+                if trans && rank >= 2 {
+                    dims.swap(rank - 2, rank - 1);
+                }
+                dims
+            };
+            let (a, b, elem_ty) = match &node.op {
+                Op::MatMul => (
+                    inputs[Op::MATMUL_IN_A].dims.clone(),
+                    inputs[Op::MATMUL_IN_B].dims.clone(),
+                    inputs[Op::MATMUL_IN_A].elem_ty,
+                ),
+                Op::MatMulInteger => (
+                    inputs[Op::MATMULINTEGER_IN_A].dims.clone(),
+                    inputs[Op::MATMULINTEGER_IN_B].dims.clone(),
+                    TensorElemType::I32,
+                ),
+                Op::FusedMatMul(matmul) => (
+                    transpose(&inputs[Op::FUSEDMATMUL_IN_A].dims, matmul.trans_a),
+                    transpose(&inputs[Op::FUSEDMATMUL_IN_B].dims, matmul.trans_b),
+                    inputs[Op::FUSEDMATMUL_IN_A].elem_ty,
+                ),
+                _ => (
+                    inputs[Op::QLINEARMATMUL_IN_A].dims.clone(),
+                    inputs[Op::QLINEARMATMUL_IN_B].dims.clone(),
+                    inputs[Op::QLINEARMATMUL_IN_Y_ZERO_POINT].elem_ty,
+                ),
+            };
+            if a.is_empty() || b.is_empty() {
+                return Err(ShapeError::Message("MatMul: Scalar input".into()));
+            }
+            let a_mat = if a.len() == 1 {
+                vec![SymExpr::constant(1), a[0].clone()]
+            } else {
+                a[a.len() - 2..].to_vec()
+            };
+            let b_mat = if b.len() == 1 {
+                vec![b[0].clone(), SymExpr::constant(1)]
+            } else {
+                b[b.len() - 2..].to_vec()
+            };
+            check_same(&a_mat[1], &b_mat[0], "MatMul")?;
+            let a_batch = &a[..a.len().saturating_sub(2)];
+            let b_batch = &b[..b.len().saturating_sub(2)];
+            let Some(mut dims) = broadcast(a_batch, b_batch)? else {
+                return Ok(None);
+            };
+            if a.len() > 1 {
+                dims.push(a_mat[0].clone());
+            }
+            if b.len() > 1 {
+                dims.push(b_mat[1].clone());
+            }
+            Ok(Some(vec![SymShape::new(dims, elem_ty)]))
+        }
+        Op::Gemm(gemm) => {
+            let a = &inputs[Op::GEMM_IN_A].dims;
+            let b = &inputs[Op::GEMM_IN_B].dims;
+            if a.len() != 2 || b.len() != 2 {
+                return Err(ShapeError::Message("Gemm: Inputs must be matrices".into()));
+            }
+            let (m, k0) = if gemm.trans_a {
+                (&a[1], &a[0])
+            } else {
+                (&a[0], &a[1])
+            };
+            let (k1, n) = if gemm.trans_b {
+                (&b[1], &b[0])
+            } else {
+                (&b[0], &b[1])
+            };
+            check_same(k0, k1, "Gemm")?;
+            Ok(Some(vec![SymShape::new(
+                vec![m.clone(), n.clone()],
+                inputs[Op::GEMM_IN_A].elem_ty,
+            )]))
+        }
+        Op::Concat(concat) => {
+            let rank = inputs[0].dims.len();
+            let axis = normalize_axis("Concat", concat.axis, rank)?;
+            let mut dims = inputs[0].dims.clone();
+            let mut sum = SymExpr::constant(0);
+            for input in &inputs {
+                if input.dims.len() != rank {
+                    return Err(ShapeError::Message("Concat: Rank mismatch".into()));
+                }
+                sum = sum.add(&input.dims[axis]);
+                for (i, d) in input.dims.iter().enumerate() {
+                    // Prefer a fixed dimension if any.
+                    if i != axis && d.as_const().is_some() {
+                        dims[i] = d.clone();
+                    }
+                }
+            }
+            dims[axis] = sum;
+            Ok(Some(vec![SymShape::new(dims, inputs[0].elem_ty)]))
+        }
+        Op::Gather(gather) => {
+            let data = &inputs[0].dims;
+            let axis = normalize_axis("Gather", gather.axis, data.len())?;
+            let mut dims = data[..axis].to_vec();
+            dims.extend(inputs[1].dims.iter().cloned());
+            dims.extend(data[axis + 1..].iter().cloned());
+            Ok(Some(vec![SymShape::new(dims, inputs[0].elem_ty)]))
+        }
+        Op::GatherElements(_) => Ok(Some(vec![SymShape::new(
+            inputs[Op::GATHERELEMENTS_IN_INDICES].dims.clone(),
+            inputs[Op::GATHERELEMENTS_IN_DATA].elem_ty,
+        )])),
+        Op::GatherND(gather) => {
+            let data = &inputs[Op::GATHERND_IN_DATA].dims;
+            let indices = &inputs[Op::GATHERND_IN_INDICES].dims;
+            let Some((index_len, outer)) = indices.split_last() else {
+                return Err(ShapeError::Message(
+                    "GatherND: Indices must not be a scalar".into(),
+                ));
+            };
+            let Some(index_len) = index_len.as_const() else {
+                return Ok(None);
+            };
+            let mut dims = outer.to_vec();
+            dims.extend(
+                data.iter()
+                    .skip(gather.batch_dims as usize + index_len as usize)
+                    .cloned(),
+            );
+            Ok(Some(vec![SymShape::new(
+                dims,
+                inputs[Op::GATHERND_IN_DATA].elem_ty,
+            )]))
+        }
+        Op::ScatterElements(_) | Op::ScatterND(_) => same_as_input(Op::SCATTER_IN_DATA),
+        Op::CumSum(_) => same_as_input(Op::CUMSUM_IN_X),
+        Op::Slice => {
+            let data = &inputs[Op::SLICE_IN_DATA].dims;
+            let get = |i: usize| node.inputs.get(i).and_then(|id| values.get(id));
+            let (Some(starts), Some(ends)) = (get(Op::SLICE_IN_STARTS), get(Op::SLICE_IN_ENDS))
+            else {
+                return Ok(None);
+            };
+            let axes = match node.inputs.get(Op::SLICE_IN_AXES) {
+                Some(_) => {
+                    let Some(axes) = const_input(Op::SLICE_IN_AXES) else {
+                        return Ok(None);
+                    };
+                    axes
+                }
+                None => (0..starts.len() as i64).collect(),
+            };
+            let steps = match node.inputs.get(Op::SLICE_IN_STEPS) {
+                Some(_) => {
+                    let Some(steps) = const_input(Op::SLICE_IN_STEPS) else {
+                        return Ok(None);
+                    };
+                    steps
+                }
+                None => vec![1; axes.len()],
+            };
+            let mut dims = data.clone();
+            for (((start, end), &axis), &step) in starts.iter().zip(ends).zip(&axes).zip(&steps) {
+                let axis = normalize_axis("Slice", axis, data.len())?;
+                let Some(dim) = slice_dim(&data[axis], start, end, step) else {
+                    return Ok(None);
+                };
+                dims[axis] = dim;
+            }
+            Ok(Some(vec![SymShape::new(
+                dims,
+                inputs[Op::SLICE_IN_DATA].elem_ty,
+            )]))
+        }
+        Op::Reshape => {
+            let Some(target) = node
+                .inputs
+                .get(Op::RESHAPE_SHAPE)
+                .and_then(|id| values.get(id))
+            else {
+                return Ok(None);
+            };
+            let input = &inputs[Op::RESHAPE_IN].dims;
+            let mut dims = vec![];
+            let mut infer_at = None;
+            for (i, d) in target.iter().enumerate() {
+                match d.as_const() {
+                    Some(0) => match input.get(i) {
+                        Some(d) => dims.push(d.clone()),
+                        None => return Err(ShapeError::Message("Reshape: Invalid shape".into())),
+                    },
+                    Some(-1) => {
+                        infer_at = Some(i);
+                        dims.push(SymExpr::constant(1));
+                    }
+                    _ => dims.push(d.clone()),
+                }
+            }
+            if let Some(i) = infer_at {
+                let Some(d) = product(input).div(&product(&dims)) else {
+                    return Ok(None);
+                };
+                dims[i] = d;
+            }
+            Ok(Some(vec![SymShape::new(
+                dims,
+                inputs[Op::RESHAPE_IN].elem_ty,
+            )]))
+        }
+        Op::Flatten(flatten) => {
+            let input = &inputs[Op::FLATTEN_IN].dims;
+            let axis = normalize_axis("Flatten", flatten.axis, input.len() + 1)?;
+            Ok(Some(vec![SymShape::new(
+                vec![product(&input[..axis]), product(&input[axis..])],
+                inputs[Op::FLATTEN_IN].elem_ty,
+            )]))
+        }
+        Op::Transpose(trans) => {
+            let input = &inputs[Op::TRANSPOSE_IN].dims;
+            let dims = if trans.perm.is_empty() {
+                input.iter().rev().cloned().collect()
+            } else {
+                trans
+                    .perm
+                    .iter()
+                    .map(|&p| Ok(input[normalize_axis("Transpose", p, input.len())?].clone()))
+                    .collect::<Result<_, ShapeError>>()?
+            };
+            Ok(Some(vec![SymShape::new(
+                dims,
+                inputs[Op::TRANSPOSE_IN].elem_ty,
+            )]))
+        }
+        Op::Squeeze(squeeze) => {
+            let input = &inputs[Op::SQUEEZE_IN].dims;
+            let axes = if node.inputs.len() > 1 {
+                let Some(axes) = const_input(1) else {
+                    return Ok(None);
+                };
+                axes
+            } else {
+                squeeze.axes.clone()
+            };
+            let dims = if axes.is_empty() {
+                if input.iter().any(|d| d.as_const().is_none()) {
+                    return Ok(None);
+                }
+                input
+                    .iter()
+                    .filter(|d| d.as_const() != Some(1))
+                    .cloned()
+                    .collect()
+            } else {
+                let axes = axes
+                    .iter()
+                    .map(|&a| normalize_axis("Squeeze", a, input.len()))
+                    .collect::<Result<Vec<_>, _>>()?;
+                input
+                    .iter()
+                    .enumerate()
+                    .filter(|(i, _)| !axes.contains(i))
+                    .map(|(_, d)| d.clone())
+                    .collect()
+            };
+            Ok(Some(vec![SymShape::new(
+                dims,
+                inputs[Op::SQUEEZE_IN].elem_ty,
+            )]))
+        }
+        Op::Unsqueeze(unsqueeze) => {
+            let input = &inputs[Op::UNSQUEEZE_IN].dims;
+            let axes = if node.inputs.len() > 1 {
+                let Some(axes) = const_input(1) else {
+                    return Ok(None);
+                };
+                axes
+            } else {
+                unsqueeze.axes.clone()
+            };
+            let rank = input.len() + axes.len();
+            let mut axes = axes
+                .iter()
+                .map(|&a| normalize_axis("Unsqueeze", a, rank))
+                .collect::<Result<Vec<_>, _>>()?;
+            axes.sort_unstable();
+            let mut dims = input.clone();
+            for axis in axes {
+                dims.insert(axis, SymExpr::constant(1));
+            }
+            Ok(Some(vec![SymShape::new(
+                dims,
+                inputs[Op::UNSQUEEZE_IN].elem_ty,
+            )]))
+        }
+        Op::ReduceMin(r)
+        | Op::ReduceMax(r)
+        | Op::ReduceMean(r)
+        | Op::ReduceSum(r)
+        | Op::ReduceProd(r)
+        | Op::ReduceL1(r)
+        | Op::ReduceL2(r)
+        | Op::ReduceLogSum(r)
+        | Op::ReduceLogSumExp(r)
+        | Op::ReduceSumSquare(r) => {
+            let input = &inputs[Op::REDUCE_IN].dims;
+            let axes = if node.inputs.len() > Op::REDUCE_IN_AXES {
+                let Some(axes) = const_input(Op::REDUCE_IN_AXES) else {
+                    return Ok(None);
+                };
+                axes
+            } else {
+                r.axes.clone()
+            };
+            let axes = if axes.is_empty() && !r.noop_with_empty_axes {
+                (0..input.len()).collect::<Vec<_>>()
+            } else {
+                axes.iter()
+                    .map(|&a| normalize_axis(node.op.name(), a, input.len()))
+                    .collect::<Result<_, _>>()?
+            };
+            let dims = input
+                .iter()
+                .enumerate()
+                .filter_map(|(i, d)| match axes.contains(&i) {
+                    true if r.keep_dims => Some(SymExpr::constant(1)),
+                    true => None,
+                    false => Some(d.clone()),
+                })
+                .collect();
+            Ok(Some(vec![SymShape::new(
+                dims,
+                inputs[Op::REDUCE_IN].elem_ty,
+            )]))
+        }
+        Op::Pad(pad) => {
+            let data = &inputs[Op::PAD_IN_DATA].dims;
+            let rank = data.len();
+            // From opset version 11, pads are given as an input.
+            let mut pads = if node.inputs.len() > Op::PAD_IN_PADS {
+                let Some(pads) = const_input(Op::PAD_IN_PADS) else {
+                    return Ok(None);
+                };
+                pads
+            } else {
+                pad.pads.clone()
+            };
+            if node.inputs.len() > Op::PAD_IN_AXES {
+                let Some(axes) = const_input(Op::PAD_IN_AXES) else {
+                    return Ok(None);
+                };
+                if pads.len() != axes.len() * 2 {
+                    return Err(ShapeError::Message("Pad: Pads don't match axes".into()));
+                }
+                let mut full = vec![0; rank * 2];
+                for (i, &axis) in axes.iter().enumerate() {
+                    let axis = normalize_axis("Pad", axis, rank)?;
+                    full[axis] = pads[i];
+                    full[rank + axis] = pads[axes.len() + i];
+                }
+                pads = full;
+            }
+            if pads.len() != rank * 2 {
+                return Err(ShapeError::Message("Pad: Invalid pads".into()));
+            }
+            let dims = data
+                .iter()
+                .enumerate()
+                .map(|(i, d)| d.add(&SymExpr::constant(pads[i] + pads[rank + i])))
+                .collect();
+            Ok(Some(vec![SymShape::new(
+                dims,
+                inputs[Op::PAD_IN_DATA].elem_ty,
+            )]))
+        }
+        Op::DepthToSpace(DepthToSpace { blocksize, .. })
+        | Op::SpaceToDepth(SpaceToDepth { blocksize }) => {
+            let input = &inputs[0];
+            let [n, c, h, w] = input.dims.as_slice() else {
+                return Err(ShapeError::Message(
+                    format!("{}: Input must be 4-D", node.op.name()).into(),
+                ));
+            };
+            let b = SymExpr::constant(*blocksize);
+            let bb = SymExpr::constant(blocksize * blocksize);
+            let dims = if matches!(node.op, Op::DepthToSpace(_)) {
+                let Some(c) = c.div(&bb) else { return Ok(None) };
+                vec![n.clone(), c, h.mul(&b), w.mul(&b)]
+            } else {
+                let (Some(h), Some(w)) = (h.div(&b), w.div(&b)) else {
+                    return Ok(None);
+                };
+                vec![n.clone(), c.mul(&bb), h, w]
+            };
+            Ok(Some(vec![SymShape::new(dims, input.elem_ty)]))
+        }
+        Op::ArgMax(a) | Op::ArgMin(a) => {
+            let input = &inputs[Op::ARGREDUCE_IN].dims;
+            let axis = normalize_axis(node.op.name(), a.axis, input.len())?;
+            let mut dims = input.clone();
+            if a.keep_dims {
+                dims[axis] = SymExpr::constant(1);
+            } else {
+                dims.remove(axis);
+            }
+            Ok(Some(vec![SymShape::new(dims, TensorElemType::I64)]))
+        }
+        Op::Constant(constant) => Ok(Some(vec![SymShape::from_tensor(&constant.value)])),
+        Op::Attention(attention) => {
+            let input = &inputs[Op::ATTENTION_IN_INPUT];
+            let weights = &inputs[Op::ATTENTION_IN_WEIGHTS].dims;
+            if input.dims.len() != 3 {
+                return Err(ShapeError::Message("Attention: Input must be 3-D".into()));
+            }
+            let v_hidden_size = match attention.qkv_hidden_sizes.as_slice() {
+                [] => match weights.get(1).and_then(SymExpr::as_const) {
+                    Some(hidden_size) => hidden_size / 3,
+                    None => return Ok(None),
+                },
+                &[_, _, v] => v as i64,
+                _ => {
+                    return Err(ShapeError::Message(
+                        "Attention: Invalid qkv_hidden_sizes".into(),
+                    ))
+                }
+            };
+            Ok(Some(vec![SymShape::new(
+                vec![
+                    input.dims[0].clone(),
+                    input.dims[1].clone(),
+                    SymExpr::constant(v_hidden_size),
+                ],
+                input.elem_ty,
+            )]))
+        }
+        _ => fallback(model, node_id, &inputs, values),
+    }
+}
+
+/// Falls back on `Model::compute_output_shapes` when all the input shapes are fixed.
+fn fallback(
+    model: &Model,
+    node_id: NodeId,
+    inputs: &[&SymShape],
+    values: &SymValues,
+) -> Result<Option<Vec<SymShape>>, ShapeError> {
+    let node = &model.graph.nodes[node_id];
+    let mut tensors = vec![];
+    for (i, (&id, input)) in node.inputs.iter().zip(inputs).enumerate() {
+        let Some(dims) = input
+            .dims
+            .iter()
+            .map(SymExpr::as_const)
+            .collect::<Option<Vec<_>>>()
+        else {
+            return Ok(None);
+        };
+        let dims = dims.into_iter().map(|d| d as usize).collect::<Vec<_>>();
+        let known = values
+            .get(&id)
+            .and_then(|v| v.iter().map(SymExpr::as_const).collect::<Option<Vec<_>>>());
+        let tensor = if let Some(init) = model.graph.inits.get(&id) {
+            Cow::Borrowed(init)
+        } else if let (Some(known), TensorElemType::I64) = (&known, input.elem_ty) {
+            Cow::Owned(Tensor::new(dims.into(), known.clone()))
+        } else if let (Some(known), TensorElemType::I32) = (&known, input.elem_ty) {
+            Cow::Owned(Tensor::new(
+                dims.into(),
+                known.iter().map(|&x| x as i32).collect(),
+            ))
+        } else if determines_output_shapes(&node.op, i) {
+            // Output shapes depend on values we don't know.
+            return Ok(None);
+        } else {
+            // Only the shape of this input matters.
+            Cow::Owned(Tensor::empty_of_type(input.elem_ty, dims.into()))
+        };
+        tensors.push(tensor);
+    }
+
+    let mut op = node.op.clone();
+    let shapes = match model.compute_output_shapes(
+        &mut op,
+        &tensors.iter().map(|t| t.as_ref()).collect::<Vec<_>>(),
+        node.outputs.len(),
+    ) {
+        Ok(shapes) => shapes,
+        // Output shapes of some ops may be known only after running them.
+        Err(_) if op.has_dynamic_output_shapes() => return Ok(None),
+        Err(e) => return Err(e),
+    };
+    Ok(Some(
+        shapes
+            .into_iter()
+            .map(|s| SymShape {
+                dims: s
+                    .dims
+                    .iter()
+                    .map(|&d| SymExpr::constant(d as i64))
+                    .collect(),
+                elem_ty: s.elem_ty,
+            })
+            .collect(),
+    ))
+}
+
+/// Returns true if the values (not only the shape) of the `i`-th input determine the output
+/// shapes.
+fn determines_output_shapes(op: &Op, i: usize) -> bool {
+    match op {
+        // Even the first inputs are values: `start` of Range, the trip count of Loop and the
+        // condition of If. Custom ops may read any of their inputs.
+        Op::Range | Op::Loop(_) | Op::If(_) | Op::Custom(_) => true,
+        _ => i > 0 && has_shape_determining_inputs(op),
+    }
+}
+
+/// Returns true if the values (not only the shapes) of the inputs except the first one
+/// determine the output shapes.
+fn has_shape_determining_inputs(op: &Op) -> bool {
+    matches!(
+        op,
+        Op::Reshape
+            | Op::Resize(_)
+            | Op::Pad(_)
+            | Op::Squeeze(_)
+            | Op::Unsqueeze(_)
+            | Op::ReduceMin(_)
+            | Op::ReduceMax(_)
+            | Op::ReduceMean(_)
+            | Op::ReduceSum(_)
+            | Op::ReduceProd(_)
+            | Op::ReduceL1(_)
+            | Op::ReduceL2(_)
+            | Op::ReduceLogSum(_)
+            | Op::ReduceLogSumExp(_)
+            | Op::ReduceSumSquare(_)
+            | Op::Expand
+            | Op::Tile
+            | Op::Split(_)
+            | Op::Slice
+            | Op::TopK(_)
+            | Op::OneHot(_)
+            | Op::NonMaxSuppression(_)
+    )
+}
+
+/// Computes the values of the output of `node` if it is a small integer tensor.
+fn propagate_values(
+    node: &Node,
+    shapes: &FxHashMap<ValueId, SymShape>,
+    values: &SymValues,
+) -> Option<Vec<SymExpr>> {
+    let input = |i: usize| node.inputs.get(i).and_then(|id| values.get(id));
+    let const_input = |i: usize| -> Option<Vec<i64>> {
+        input(i).and_then(|v| v.iter().map(SymExpr::as_const).collect())
+    };
+
+    match &node.op {
+        Op::Shape(shape) => {
+            let dims = &shapes.get(&node.inputs[0])?.dims;
+            let (start, end) = shape_range(shape.start, shape.end, dims.len() as i64);
+            Some(dims[start..end].to_vec())
+        }
+        Op::Cast(cast) if matches!(cast.to, TensorElemType::I64 | TensorElemType::I32) => {
+            input(0).cloned()
+        }
+        Op::Reshape | Op::Squeeze(_) | Op::Unsqueeze(_) => input(0).cloned(),
+        Op::Concat(_) => {
+            let mut vals = vec![];
+            for i in 0..node.inputs.len() {
+                vals.extend(input(i)?.iter().cloned());
+            }
+            Some(vals)
+        }
+        Op::Gather(gather) => {
+            let data = input(0)?;
+            if gather.axis != 0 && gather.axis != -1 {
+                return None;
+            }
+            const_input(1)?
+                .iter()
+                .map(|&i| {
+                    let i = if i < 0 { data.len() as i64 + i } else { i };
+                    data.get(i as usize).cloned()
+                })
+                .collect()
+        }
+        Op::Slice => {
+            let data = input(Op::SLICE_IN_DATA)?;
+            let start = const_input(Op::SLICE_IN_STARTS)?;
+            let end = const_input(Op::SLICE_IN_ENDS)?;
+            let step = match node.inputs.get(Op::SLICE_IN_STEPS) {
+                Some(_) => const_input(Op::SLICE_IN_STEPS)?,
+                None => vec![1],
+            };
+            if start.len() != 1 || step != [1] {
+                return None;
+            }
+            let len = data.len() as i64;
+            let clamp = |x: i64| (if x < 0 { x + len } else { x }).clamp(0, len) as usize;
+            let (start, end) = (clamp(start[0]), clamp(end[0]));
+            Some(data.get(start..end.max(start))?.to_vec())
+        }
+        Op::Add | Op::Sub | Op::Mul | Op::Div => {
+            let (a, b) = (input(0)?, input(1)?);
+            let len = a.len().max(b.len());
+            if (a.len() != len && a.len() != 1) || (b.len() != len && b.len() != 1) {
+                return None;
+            }
+            (0..len)
+                .map(|i| {
+                    let x = &a[i.min(a.len() - 1)];
+                    let y = &b[i.min(b.len() - 1)];
+                    match node.op {
+                        Op::Add => Some(x.add(y)),
+                        Op::Sub => Some(x.sub(y)),
+                        Op::Mul => Some(x.mul(y)),
+                        Op::Div => match (x.as_const(), y.as_const()) {
+                            (Some(x), Some(y)) if y != 0 => Some(SymExpr::constant(x / y)),
+                            _ => x.div(y),
+                        },
+                        _ => unreachable!(),
+                    }
+                })
+                .collect()
+        }
+        Op::Constant(constant) => tensor_values(&constant.value),
+        _ => None,
+    }
+}
+
+fn broadcast(a: &[SymExpr], b: &[SymExpr]) -> Result<Option<Vec<SymExpr>>, ShapeError> {
+    let rank = a.len().max(b.len());
+    let one = SymExpr::constant(1);
+    let mut dims = vec![];
+    for i in 0..rank {
+        let x = (i + a.len()).checked_sub(rank).map_or(&one, |i| &a[i]);
+        let y = (i + b.len()).checked_sub(rank).map_or(&one, |i| &b[i]);
+        let dim = match (x.as_const(), y.as_const()) {
+            _ if x == y => x.clone(),
+            (Some(1), _) => y.clone(),
+            (_, Some(1)) => x.clone(),
+            (Some(_), Some(_)) => {
+                return Err(ShapeError::Message(
+                    format!("Cannot broadcast {x} and {y}").into(),
+                ))
+            }
+            // Suppose that the symbol is bound to the fixed dimension.
+            (Some(_), None) => x.clone(),
+            (None, Some(_)) => y.clone(),
+            (None, None) => return Ok(None),
+        };
+        dims.push(dim);
+    }
+    Ok(Some(dims))
+}
+
+fn check_same(x: &SymExpr, y: &SymExpr, op: &str) -> Result<(), ShapeError> {
+    match (x.as_const(), y.as_const()) {
+        (Some(a), Some(b)) if a != b => Err(ShapeError::Message(
+            format!("{op}: Dimension mismatch ({a} vs {b})").into(),
+        )),
+        _ => Ok(()),
+    }
+}
+
+fn slice_dim(dim: &SymExpr, start: &SymExpr, end: &SymExpr, step: i64) -> Option<SymExpr> {
+    // Any end larger than this is regarded as the end of the dimension.
+    const INT_MAX: i64 = i32::MAX as i64;
+
+    if let (Some(dim), Some(start), Some(end)) = (dim.as_const(), start.as_const(), end.as_const())
+    {
+        if step == 0 {
+            return None;
+        }
+        let adjust = |x: i64| if x < 0 { x + dim } else { x };
+        let (start, end) = if step > 0 {
+            (adjust(start).clamp(0, dim), adjust(end).clamp(0, dim))
+        } else {
+            (
+                adjust(start).clamp(-1, dim - 1),
+                adjust(end).clamp(-1, dim - 1),
+            )
+        };
+        let len = ((end - start + step - step.signum()) / step).max(0);
+        return Some(SymExpr::constant(len));
+    }
+
+    if step != 1 {
+        return None;
+    }
+    let start = match start.as_const() {
+        Some(s) if s < 0 => dim.add(&SymExpr::constant(s)),
+        _ => start.clone(),
+    };
+    let end = match end.as_const() {
+        Some(e) if e >= INT_MAX => dim.clone(),
+        Some(e) if e < 0 => dim.add(&SymExpr::constant(e)),
+        _ => end.clone(),
+    };
+    let len = end.sub(&start);
+    match len.as_const() {
+        Some(l) if l < 0 => None,
+        _ => Some(len),
+    }
+}
+
+fn product(dims: &[SymExpr]) -> SymExpr {
+    dims.iter().fold(SymExpr::constant(1), |acc, d| acc.mul(d))
+}
+
+fn normalize_axis(name: &str, axis: i64, rank: usize) -> Result<usize, ShapeError> {
+    let rank = rank as i64;
+    if !(-rank..rank).contains(&axis) {
+        return Err(ShapeError::Message(
+            format!("{name}: Axis {axis} is out of range").into(),
+        ));
+    }
+    Ok(if axis < 0 { axis + rank } else { axis } as usize)
+}
+
+fn shape_range(start: i64, end: Option<i64>, rank: i64) -> (usize, usize) {
+    let clamp = |x: i64| (if x < 0 { x + rank } else { x }).clamp(0, rank) as usize;
+    let start = clamp(start);
+    let end = end.map_or(rank as usize, clamp);
+    (start, end.max(start))
+}
+
+fn tensor_values(tensor: &Tensor) -> Option<Vec<SymExpr>> {
+    if tensor.dims().len() > 1 || tensor.dims().total_elems() > MAX_TRACKED_ELEMS {
+        return None;
+    }
+    match tensor.elem_ty() {
+        TensorElemType::I64 => Some(
+            tensor
+                .data::<i64>()
+                .iter()
+                .map(|&x| SymExpr::constant(x))
+                .collect(),
+        ),
+        TensorElemType::I32 => Some(
+            tensor
+                .data::<i32>()
+                .iter()
+                .map(|&x| SymExpr::constant(x as i64))
+                .collect(),
+        ),
+        _ => None,
+    }
+}
+
+impl SymShape {
+    fn new(dims: Vec<SymExpr>, elem_ty: TensorElemType) -> Self {
+        Self { dims, elem_ty }
+    }
+
+    fn from_tensor(tensor: &Tensor) -> Self {
+        Self {
+            dims: tensor
+                .dims()
+                .iter()
+                .map(|&d| SymExpr::constant(d as i64))
+                .collect(),
+            elem_ty: tensor.elem_ty(),
+        }
+    }
+}
+
+impl SymExpr {
+    fn constant(c: i64) -> Self {
+        let mut terms = BTreeMap::new();
+        if c != 0 {
+            terms.insert(vec![], c);
+        }
+        Self(terms)
+    }
+
+    fn symbol(sym: &str) -> Self {
+        Self(BTreeMap::from([(vec![sym.to_string()], 1)]))
+    }
+
+    fn from_dim(dim: &Dimension) -> Self {
+        match dim {
+            Dimension::Fixed(d) => Self::constant(*d as i64),
+            Dimension::Dynamic(s) => match Self::parse(s) {
+                // Only accept expressions in the canonical form. Otherwise, `s` is just a name.
+                Some(e) if e.to_string() == *s => e,
+                _ => Self::symbol(s),
+            },
+        }
+    }
+
+    fn to_dim(&self) -> Option<Dimension> {
+        match self.as_const() {
+            Some(c) if c >= 0 => Some(Dimension::Fixed(c as usize)),
+            Some(_) => None,
+            None => Some(Dimension::Dynamic(self.to_string())),
+        }
+    }
+
+    fn as_const(&self) -> Option<i64> {
+        match self.0.len() {
+            0 => Some(0),
+            1 => self.0.get(&vec![]).copied(),
+            _ => None,
+        }
+    }
+
+    fn add(&self, rhs: &Self) -> Self {
+        let mut terms = self.0.clone();
+        for (mono, &coef) in &rhs.0 {
+            let c = terms.entry(mono.clone()).or_insert(0);
+            *c += coef;
+            if *c == 0 {
+                terms.remove(mono);
+            }
+        }
+        Self(terms)
+    }
+
+    fn sub(&self, rhs: &Self) -> Self {
+        self.add(&rhs.mul(&Self::constant(-1)))
+    }
+
+    fn mul(&self, rhs: &Self) -> Self {
+        let mut result = Self::constant(0);
+        for (m0, &c0) in &self.0 {
+            for (m1, &c1) in &rhs.0 {
+                let mut mono = m0.iter().chain(m1).cloned().collect::<Vec<_>>();
+                mono.sort();
+                result = result.add(&Self(BTreeMap::from([(mono, c0 * c1)])));
+            }
+        }
+        result
+    }
+
+    /// Exact division. Returns `None` if `self` is not divisible by `rhs`.
+    fn div(&self, rhs: &Self) -> Option<Self> {
+        if self == rhs {
+            return Some(Self::constant(1));
+        }
+        if rhs.0.len() != 1 {
+            return None;
+        }
+        let (div_mono, &div_coef) = rhs.0.iter().next().unwrap();
+        let mut terms = BTreeMap::new();
+        for (mono, &coef) in &self.0 {
+            if coef % div_coef != 0 {
+                return None;
+            }
+            let mut mono = mono.clone();
+            for sym in div_mono {
+                let pos = mono.iter().position(|s| s == sym)?;
+                mono.remove(pos);
+            }
+            terms.insert(mono, coef / div_coef);
+        }
+        Some(Self(terms))
+    }
+
+    fn parse(s: &str) -> Option<Self> {
+        let is_ident = |t: &str| {
+            t.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+                && t.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        };
+        let mut expr = Self::constant(0);
+        let mut rest = s;
+        let mut sign = 1;
+        if let Some(r) = rest.strip_prefix('-') {
+            sign = -1;
+            rest = r;
+        }
+        loop {
+            let end = rest.find(['+', '-']).unwrap_or(rest.len());
+            let mut term = Self::constant(sign);
+            for factor in rest[..end].split('*') {
+                if is_ident(factor) {
+                    term = term.mul(&Self::symbol(factor));
+                } else if !factor.is_empty() && factor.chars().all(|c| c.is_ascii_digit()) {
+                    term = term.mul(&Self::constant(factor.parse().ok()?));
+                } else {
+                    return None;
+                }
+            }
+            expr = expr.add(&term);
+            if end == rest.len() {
+                return Some(expr);
+            }
+            sign = if rest[end..].starts_with('-') { -1 } else { 1 };
+            rest = &rest[end + 1..];
+        }
+    }
+}
+
+impl fmt::Display for SymExpr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.0.is_empty() {
+            return write!(f, "0");
+        }
+        // Print the constant term last.
+        let terms = self
+            .0
+            .iter()
+            .filter(|(mono, _)| !mono.is_empty())
+            .chain(self.0.iter().filter(|(mono, _)| mono.is_empty()));
+        for (i, (mono, &coef)) in terms.enumerate() {
+            if coef < 0 {
+                write!(f, "-")?;
+            } else if i > 0 {
+                write!(f, "+")?;
+            }
+            if mono.is_empty() {
+                write!(f, "{}", coef.abs())?;
+            } else {
+                write!(f, "{}", mono.join("*"))?;
+                if coef.abs() != 1 {
+                    write!(f, "*{}", coef.abs())?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+fn dyn_shape(dims: &[&str]) -> TypedShape {
+    TypedShape::new(
+        Dimensions(
+            dims.iter()
+                .map(|d| match d.parse() {
+                    Ok(d) => Dimension::Fixed(d),
+                    Err(_) => Dimension::Dynamic(d.to_string()),
+                })
+                .collect(),
+        ),
+        TensorElemType::F32,
+    )
+}
+
+#[test]
+fn sym_expr_roundtrip() {
+    for s in [
+        "seq",
+        "seq*2",
+        "batch*seq",
+        "seq-1",
+        "-seq+3",
+        "batch*seq*4+seq*2",
+    ] {
+        let e = SymExpr::parse(s).unwrap();
+        assert_eq!(e.to_string(), s);
+    }
+    let seq2 = SymExpr::from_dim(&Dimension::Dynamic("seq*2".into()));
+    assert_eq!(seq2.div(&SymExpr::constant(2)).unwrap().to_string(), "seq");
+    assert_eq!(
+        SymExpr::from_dim(&Dimension::Dynamic("batch size".into())).to_string(),
+        "batch size"
+    );
+}
+
+#[test]
+fn infer_concat_matmul_reshape() {
+    let mut model = Model::default();
+    let x = model
+        .graph
+        .values
+        .new_val_named_and_shaped("x", dyn_shape(&["batch", "seq", "8"]));
+    let w = model.graph.values.new_val_named("w");
+    let shape = model.graph.values.new_val_named("shape");
+    let concat = model.graph.values.new_val_named("concat");
+    let matmul = model.graph.values.new_val_named("matmul");
+    let reshape = model.graph.values.new_val_named("reshape");
+    model
+        .graph
+        .inits
+        .insert(w, Tensor::zeros::<f32>(vec![8, 4].into()));
+    model
+        .graph
+        .inits
+        .insert(shape, Tensor::new(vec![2].into(), vec![0i64, -1]));
+    model.graph.add_node(
+        Node::new(Op::Concat(crate::op::Concat { axis: 1 }))
+            .with_ins(vec![x, x])
+            .with_out(concat),
+    );
+    model.graph.add_node(
+        Node::new(Op::MatMul)
+            .with_ins(vec![concat, w])
+            .with_out(matmul),
+    );
+    model.graph.add_node(
+        Node::new(Op::Reshape)
+            .with_ins(vec![matmul, shape])
+            .with_out(reshape),
+    );
+    model.graph.inputs.push(x);
+    model.graph.outputs.push(reshape);
+
+    infer_symbolic_shapes(&mut model).unwrap();
+
+    let values = &model.graph.values;
+    assert_eq!(
+        values[concat].shape,
+        Some(dyn_shape(&["batch", "seq*2", "8"]))
+    );
+    assert_eq!(
+        values[matmul].shape,
+        Some(dyn_shape(&["batch", "seq*2", "4"]))
+    );
+    assert_eq!(values[reshape].shape, Some(dyn_shape(&["batch", "seq*8"])));
+}
+
+#[test]
+fn infer_shape_gather_slice_broadcast() {
+    let mut model = Model::default();
+    let x = model
+        .graph
+        .values
+        .new_val_named_and_shaped("x", dyn_shape(&["batch", "seq", "8"]));
+    let y = model
+        .graph
+        .values
+        .new_val_named_and_shaped("y", dyn_shape(&["seq", "1"]));
+    let starts = model.graph.values.new_val_named("starts");
+    let ends = model.graph.values.new_val_named("ends");
+    let axes = model.graph.values.new_val_named("axes");
+    let idx = model.graph.values.new_val_named("idx");
+    let x_shape = model.graph.values.new_val_named("x_shape");
+    let seq = model.graph.values.new_val_named("seq");
+    let sliced = model.graph.values.new_val_named("sliced");
+    let added = model.graph.values.new_val_named("added");
+    for (id, val) in [(starts, 1), (ends, i64::MAX), (axes, 1)] {
+        model
+            .graph
+            .inits
+            .insert(id, Tensor::new(vec![1].into(), vec![val]));
+    }
+    model
+        .graph
+        .inits
+        .insert(idx, Tensor::new(vec![].into(), vec![1i64]));
+    model.graph.add_node(
+        Node::new(Op::Shape(crate::op::Shape {
+            start: 0,
+            end: None,
+        }))
+        .with_in(x)
+        .with_out(x_shape),
+    );
+    model.graph.add_node(
+        Node::new(Op::Gather(crate::op::Gather { axis: 0 }))
+            .with_ins(vec![x_shape, idx])
+            .with_out(seq),
+    );
+    model.graph.add_node(
+        Node::new(Op::Slice)
+            .with_ins(vec![x, starts, ends, axes])
+            .with_out(sliced),
+    );
+    model
+        .graph
+        .add_node(Node::new(Op::Add).with_ins(vec![x, y]).with_out(added));
+    model.graph.inputs.extend([x, y]);
+    model.graph.outputs.extend([seq, sliced, added]);
+
+    infer_symbolic_shapes(&mut model).unwrap();
+
+    let values = &model.graph.values;
+    assert_eq!(
+        values[x_shape].shape.as_ref().unwrap().dims,
+        dyn_shape(&["3"]).dims
+    );
+    assert_eq!(
+        values[seq].shape.as_ref().unwrap().dims,
+        dyn_shape(&[]).dims
+    );
+    assert_eq!(
+        values[sliced].shape,
+        Some(dyn_shape(&["batch", "seq-1", "8"]))
+    );
+    assert_eq!(values[added].shape, Some(dyn_shape(&["batch", "seq", "8"])));
+}
+
+#[test]
+fn out_of_range_axis() {
+    let mut model = Model::default();
+    let x = model
+        .graph
+        .values
+        .new_val_named_and_shaped("x", dyn_shape(&["batch", "8"]));
+    let idx = model.graph.values.new_val_named("idx");
+    let y = model.graph.values.new_val_named("y");
+    model
+        .graph
+        .inits
+        .insert(idx, Tensor::new(vec![].into(), vec![0i64]));
+    model.graph.add_node(
+        Node::new(Op::Gather(crate::op::Gather { axis: 2 }))
+            .with_ins(vec![x, idx])
+            .with_out(y),
+    );
+    model.graph.inputs.push(x);
+    model.graph.outputs.push(y);
+
+    assert!(infer_symbolic_shapes(&mut model).is_err());
+}
+
+#[test]
+fn range_with_dynamic_start() {
+    let mut model = Model::default();
+    let start = model.graph.values.new_val_named_and_shaped(
+        "start",
+        TypedShape::new(Dimensions(vec![]), TensorElemType::I64),
+    );
+    let [limit, delta] = ["limit", "delta"].map(|name| model.graph.values.new_val_named(name));
+    let y = model.graph.values.new_val_named("y");
+    model
+        .graph
+        .inits
+        .insert(limit, Tensor::new(vec![].into(), vec![10i64]));
+    model
+        .graph
+        .inits
+        .insert(delta, Tensor::new(vec![].into(), vec![2i64]));
+    model.graph.add_node(
+        Node::new(Op::Range)
+            .with_ins(vec![start, limit, delta])
+            .with_out(y),
+    );
+    model.graph.inputs.push(start);
+    model.graph.outputs.push(y);
+
+    // The length depends on the value of `start`, which is known only when running.
+    infer_symbolic_shapes(&mut model).unwrap();
+    assert_eq!(model.graph.values[y].shape, None);
+}
@@ -0,0 +1,704 @@
+use std::{borrow::Cow, ops::RangeInclusive};
+
+use rustc_hash::{FxHashMap, FxHashSet};
+use thiserror::Error;
+
+use crate::{
+    model::Model,
+    node::{Node, NodeId},
+    op::Op,
+    value::ValueId,
+};
+
+/// A violation of the invariants a `Model` must satisfy.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum VerifyError {
+    #[error("Graph input {0:?} does not exist")]
+    UnknownGraphInput(ValueId),
+
+    #[error("Graph output {0:?} does not exist")]
+    UnknownGraphOutput(ValueId),
+
+    #[error("Graph output {0:?} is never produced")]
+    UndefinedGraphOutput(ValueId),
+
+    #[error("{op} ({node:?}) refers to a nonexistent value {value:?}")]
+    DanglingValue {
+        node: NodeId,
+        op: &'static str,
+        value: ValueId,
+    },
+
+    #[error("{op} ({node:?}) uses {value:?} which is never produced")]
+    UndefinedValue {
+        node: NodeId,
+        op: &'static str,
+        value: ValueId,
+    },
+
+    #[error("Value {value:?} has more than one producer: {producers:?}")]
+    MultipleProducers {
+        value: ValueId,
+        producers: Vec<NodeId>,
+    },
+
+    #[error("Value {value:?} is a graph input or an initializer but is produced by {node:?}")]
+    ProducedConstant { value: ValueId, node: NodeId },
+
+    #[error("Nodes {0:?} form a cycle")]
+    Cycle(Vec<NodeId>),
+
+    #[error("{op} ({node:?}) takes {expected:?} inputs but got {actual}")]
+    InputArity {
+        node: NodeId,
+        op: &'static str,
+        expected: RangeInclusive<usize>,
+        actual: usize,
+    },
+
+    #[error("{op} ({node:?}) produces {expected:?} outputs but got {actual}")]
+    OutputArity {
+        node: NodeId,
+        op: &'static str,
+        expected: RangeInclusive<usize>,
+        actual: usize,
+    },
+
+    #[error("{op} ({node:?}) has an invalid attribute: {message}")]
+    Attribute {
+        node: NodeId,
+        op: &'static str,
+        message: Cow<'static, str>,
+    },
+
+    #[error("{op} ({node:?}) has an invalid subgraph: {errors:?}")]
+    Subgraph {
+        node: NodeId,
+        op: &'static str,
+        errors: Vec<VerifyError>,
+    },
+}
+
+/// Checks `model` and returns all the violations found.
+/// Deleted nodes are ignored.
+pub fn verify(model: &Model) -> Result<(), Vec<VerifyError>> {
+    let mut errors = vec![];
+    let graph = &model.graph;
+    let exists = |id: ValueId| graph.values.inner().get(id).is_some();
+    // Omitted optional inputs and outputs are represented by values named "".
+    let is_omitted = |id: ValueId| {
+        graph
+            .values
+            .inner()
+            .get(id)
+            .map_or(false, |v| v.name.as_deref() == Some(""))
+    };
+
+    for &id in &graph.inputs {
+        if !exists(id) {
+            errors.push(VerifyError::UnknownGraphInput(id));
+        }
+    }
+    for &id in &graph.outputs {
+        if !exists(id) {
+            errors.push(VerifyError::UnknownGraphOutput(id));
+        }
+    }
+
+    let consts = graph
+        .inputs
+        .iter()
+        .chain(graph.inits.keys())
+        .chain(graph.outer_inputs.iter())
+        .copied()
+        .collect::<FxHashSet<_>>();
+    let nodes = graph
+        .nodes
+        .iter()
+        .filter(|(_, node)| !node.deleted)
+        .collect::<Vec<_>>();
+
+    // Single producer for every value.
+    let mut producers: FxHashMap<ValueId, Vec<NodeId>> = FxHashMap::default();
+    for &(node_id, node) in &nodes {
+        for &value in node.inputs.iter().chain(node.outputs.iter()) {
+            if !exists(value) {
+                errors.push(VerifyError::DanglingValue {
+                    node: node_id,
+                    op: node.op.name(),
+                    value,
+                });
+            }
+        }
+        for &value in &node.outputs {
+            if !exists(value) || is_omitted(value) {
+                continue;
+            }
+            if consts.contains(&value) {
+                errors.push(VerifyError::ProducedConstant {
+                    value,
+                    node: node_id,
+                });
+            }
+            producers.entry(value).or_default().push(node_id);
+        }
+    }
+    let mut multiple_producers = producers
+        .iter()
+        .filter(|(_, p)| p.len() > 1)
+        .map(|(&value, p)| VerifyError::MultipleProducers {
+            value,
+            producers: p.clone(),
+        })
+        .collect::<Vec<_>>();
+    multiple_producers.sort_by_key(|e| match e {
+        VerifyError::MultipleProducers { value, .. } => value.index(),
+        _ => unreachable!(),
+    });
+    errors.extend(multiple_producers);
+
+    // Every used value must be defined.
+    let is_defined = |id: ValueId| consts.contains(&id) || producers.contains_key(&id);
+    for &(node_id, node) in &nodes {
+        for &value in &node.inputs {
+            if exists(value) && !is_omitted(value) && !is_defined(value) {
+                errors.push(VerifyError::UndefinedValue {
+                    node: node_id,
+                    op: node.op.name(),
+                    value,
+                });
+            }
+        }
+    }
+    for &id in &graph.outputs {
+        if exists(id) && !is_defined(id) {
+            errors.push(VerifyError::UndefinedGraphOutput(id));
+        }
+    }
+
+    // Acyclicity. Nodes that are never ready in Kahn's algorithm are on (or after) a cycle.
+    let mut num_pending = FxHashMap::default();
+    let mut successors: FxHashMap<NodeId, Vec<NodeId>> = FxHashMap::default();
+    let mut que = vec![];
+    for &(node_id, node) in &nodes {
+        let preds = node
+            .inputs
+            .iter()
+            .filter(|i| !consts.contains(i))
+            .filter_map(|i| producers.get(i))
+            .flatten()
+            .copied()
+            .collect::<FxHashSet<_>>();
+        for &pred in &preds {
+            successors.entry(pred).or_default().push(node_id);
+        }
+        if preds.is_empty() {
+            que.push(node_id);
+        }
+        num_pending.insert(node_id, preds.len());
+    }
+    while let Some(node_id) = que.pop() {
+        for &succ in successors.get(&node_id).into_iter().flatten() {
+            let n = num_pending.get_mut(&succ).unwrap();
+            *n -= 1;
+            if *n == 0 {
+                que.push(succ);
+            }
+        }
+    }
+    let mut cyclic = num_pending
+        .into_iter()
+        .filter_map(|(id, n)| (n > 0).then_some(id))
+        .collect::<Vec<_>>();
+    if !cyclic.is_empty() {
+        cyclic.sort_by_key(|id| id.index());
+        errors.push(VerifyError::Cycle(cyclic));
+    }
+
+    for &(node_id, node) in &nodes {
+        verify_node(node_id, node, &mut errors);
+        for subgraph in node.op.subgraphs() {
+            if let Err(sub_errors) = verify(&model.subgraph_model(subgraph)) {
+                errors.push(VerifyError::Subgraph {
+                    node: node_id,
+                    op: node.op.name(),
+                    errors: sub_errors,
+                });
+            }
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+fn verify_node(node_id: NodeId, node: &Node, errors: &mut Vec<VerifyError>) {
+    let op = node.op.name();
+    let (inputs, outputs) = arity(&node.op);
+    if !inputs.contains(&node.inputs.len()) {
+        errors.push(VerifyError::InputArity {
+            node: node_id,
+            op,
+            expected: inputs,
+            actual: node.inputs.len(),
+        });
+    }
+    if !outputs.contains(&node.outputs.len()) {
+        errors.push(VerifyError::OutputArity {
+            node: node_id,
+            op,
+            expected: outputs,
+            actual: node.outputs.len(),
+        });
+    }
+
+    let mut invalid = |message: String| {
+        errors.push(VerifyError::Attribute {
+            node: node_id,
+            op,
+            message: message.into(),
+        })
+    };
+    match &node.op {
+        Op::Conv2d(c) | Op::QLinearConv(c) | Op::ConvInteger(c) => {
+            if c.group < 1 {
+                invalid(format!("group must be positive but got {}", c.group));
+            }
+            if c.strides.iter().chain(c.dilations.iter()).any(|&x| x == 0) {
+                invalid("strides and dilations must be positive".into());
+            }
+            if !c.kernel_shape.is_empty()
+                && !c.padding.is_empty()
+                && c.padding.len() != c.kernel_shape.len() * 2
+            {
+                invalid(format!(
+                    "padding has {} elements for a {}-D kernel",
+                    c.padding.len(),
+                    c.kernel_shape.len()
+                ));
+            }
+            if !matches!(
+                c.auto_pad.as_str(),
+                "" | "NOTSET" | "SAME_UPPER" | "SAME_LOWER" | "VALID"
+            ) {
+                invalid(format!("unknown auto_pad {:?}", c.auto_pad));
+            }
+        }
+        Op::ConvTranspose(c) => {
+            if c.group < 1 {
+                invalid(format!("group must be positive but got {}", c.group));
+            }
+            if c.strides.iter().chain(c.dilations.iter()).any(|&x| x == 0) {
+                invalid("strides and dilations must be positive".into());
+            }
+            if !matches!(
+                c.auto_pad.as_str(),
+                "" | "NOTSET" | "SAME_UPPER" | "SAME_LOWER" | "VALID"
+            ) {
+                invalid(format!("unknown auto_pad {:?}", c.auto_pad));
+            }
+        }
+        Op::MaxPool(p) | Op::AveragePool(p) | Op::LpPool(p) => {
+            if p.kernel_shape.is_empty() {
+                invalid("kernel_shape must not be empty".into());
+            }
+            if p.strides.iter().any(|&x| x == 0) {
+                invalid("strides must be positive".into());
+            }
+            if p.dilations.iter().any(|&x| x == 0) {
+                invalid("dilations must be positive".into());
+            }
+            if !matches!(
+                p.auto_pad.as_str(),
+                "" | "NOTSET" | "SAME_UPPER" | "SAME_LOWER" | "VALID"
+            ) {
+                invalid(format!("unknown auto_pad {:?}", p.auto_pad));
+            }
+        }
+        Op::Resize(r) => {
+            if !matches!(r.mode.as_str(), "nearest" | "linear" | "cubic") {
+                invalid(format!("unknown mode {:?}", r.mode));
+            }
+            if !matches!(
+                r.coordinate_transformation_mode.as_str(),
+                "half_pixel"
+                    | "half_pixel_symmetric"
+                    | "pytorch_half_pixel"
+                    | "align_corners"
+                    | "asymmetric"
+                    | "tf_crop_and_resize"
+            ) {
+                invalid(format!(
+                    "unknown coordinate_transformation_mode {:?}",
+                    r.coordinate_transformation_mode
+                ));
+            }
+            if !matches!(
+                r.nearest_mode.as_str(),
+                "round_prefer_floor" | "round_prefer_ceil" | "floor" | "ceil"
+            ) {
+                invalid(format!("unknown nearest_mode {:?}", r.nearest_mode));
+            }
+        }
+        Op::Pad(p) if !matches!(p.mode.as_str(), "constant" | "reflect" | "edge" | "wrap") => {
+            invalid(format!("unknown mode {:?}", p.mode));
+        }
+        Op::DepthToSpace(d) => {
+            if d.blocksize <= 0 {
+                invalid(format!(
+                    "blocksize must be positive but got {}",
+                    d.blocksize
+                ));
+            }
+            if !matches!(d.mode.as_str(), "DCR" | "CRD") {
+                invalid(format!("unknown mode {:?}", d.mode));
+            }
+        }
+        Op::SpaceToDepth(s) if s.blocksize <= 0 => {
+            invalid(format!(
+                "blocksize must be positive but got {}",
+                s.blocksize
+            ));
+        }
+        Op::Transpose(t) if !t.perm.is_empty() => {
+            let mut perm = t.perm.clone();
+            perm.sort_unstable();
+            if perm.into_iter().ne(0..t.perm.len() as i64) {
+                invalid(format!("perm {:?} is not a permutation", t.perm));
+            }
+        }
+        Op::Squeeze(s) if has_duplicates(&s.axes) => {
+            invalid(format!("axes {:?} have duplicates", s.axes));
+        }
+        Op::Unsqueeze(u) if has_duplicates(&u.axes) => {
+            invalid(format!("axes {:?} have duplicates", u.axes));
+        }
+        Op::Split(s) if s.split.iter().any(|&x| x < 0) => {
+            invalid(format!("split {:?} has negative sizes", s.split));
+        }
+        Op::Split(s) if !s.split.is_empty() && s.split.len() != node.outputs.len() => {
+            invalid(format!(
+                "split has {} sizes for {} outputs",
+                s.split.len(),
+                node.outputs.len()
+            ));
+        }
+        Op::BatchNormalization(bn) if bn.epsilon < 0.0 || bn.epsilon.is_nan() => {
+            invalid(format!(
+                "epsilon must be non-negative but got {}",
+                bn.epsilon
+            ));
+        }
+        Op::LayerNormalization(ln) if ln.epsilon < 0.0 || ln.epsilon.is_nan() => {
+            invalid(format!(
+                "epsilon must be non-negative but got {}",
+                ln.epsilon
+            ));
+        }
+        Op::InstanceNormalization(n) if n.epsilon < 0.0 || n.epsilon.is_nan() => {
+            invalid(format!(
+                "epsilon must be non-negative but got {}",
+                n.epsilon
+            ));
+        }
+        Op::GroupNormalization(n) => {
+            if n.epsilon < 0.0 || n.epsilon.is_nan() {
+                invalid(format!(
+                    "epsilon must be non-negative but got {}",
+                    n.epsilon
+                ));
+            }
+            if n.num_groups <= 0 {
+                invalid(format!(
+                    "num_groups must be positive but got {}",
+                    n.num_groups
+                ));
+            }
+        }
+        Op::Attention(a) => {
+            if a.num_heads == 0 {
+                invalid("num_heads must be positive".into());
+            }
+            if !matches!(a.qkv_hidden_sizes.len(), 0 | 3)
+                || a.qkv_hidden_sizes
+                    .iter()
+                    .any(|&x| x % a.num_heads.max(1) != 0)
+            {
+                invalid(format!(
+                    "qkv_hidden_sizes {:?} must be 3 multiples of num_heads",
+                    a.qkv_hidden_sizes
+                ));
+            }
+        }
+        Op::Loop(l) => {
+            let num_carried = l.body.inputs.len().saturating_sub(2);
+            if l.body.inputs.len() < 2 || l.body.outputs.len() < num_carried + 1 {
+                invalid(format!(
+                    "body must take at least 2 inputs and produce the condition and {num_carried} loop-carried values",
+                ));
+            } else if node.inputs.len() != num_carried + 2 + l.body.outer_inputs.len()
+                || node.outputs.len() != l.body.outputs.len() - 1
+            {
+                invalid("inputs and outputs must match those of body".into());
+            }
+        }
+        Op::If(i) => {
+            if i.then_branch.outputs.len() != node.outputs.len()
+                || i.else_branch.outputs.len() != node.outputs.len()
+            {
+                invalid("then_branch and else_branch must produce as many outputs as If".into());
+            }
+            if node.inputs.len()
+                != 1 + i.then_branch.outer_inputs.len() + i.else_branch.outer_inputs.len()
+            {
+                invalid("inputs must be the condition and outer-scope values of branches".into());
+            }
+        }
+        Op::Custom(c) => {
+            if c.op_type.is_empty() {
+                invalid("op_type must not be empty".into());
+            }
+        }
+        Op::FusedElemwise(f) => {
+            if f.chain.is_empty() {
+                invalid("chain must not be empty".into());
+            }
+            if let Some((op, _, _)) = f.chain.iter().find(|(op, _, _)| !op.is_elemwise()) {
+                invalid(format!("{} is not element-wise", op.name()));
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Returns the allowed numbers of inputs and outputs of `op`.
+fn arity(op: &Op) -> (RangeInclusive<usize>, RangeInclusive<usize>) {
+    const ANY: usize = usize::MAX;
+    let (ins, outs) = match op {
+        Op::Conv2d(_) => (Op::CONV2D_WEIGHT + 1..=Op::CONV2D_BIAS + 1, 1..=1),
+        Op::ConvTranspose(_) => (
+            Op::CONVTRANSPOSE_WEIGHT + 1..=Op::CONVTRANSPOSE_BIAS + 1,
+            1..=1,
+        ),
+        Op::Add
+        | Op::Sub
+        | Op::Mul
+        | Op::Div
+        | Op::Pow
+        | Op::Equal
+        | Op::Less
+        | Op::LessOrEqual
+        | Op::Greater
+        | Op::GreaterOrEqual
+        | Op::And
+        | Op::Or
+        | Op::Xor
+        | Op::PRelu
+        | Op::MatMul
+        | Op::Expand => (2..=2, 1..=1),
+        Op::Sqrt
+        | Op::Not
+        | Op::ReLU
+        | Op::Gelu
+        | Op::LeakyReLU(_)
+        | Op::Sigmoid
+        | Op::Erf
+        | Op::Tanh
+        | Op::Abs
+        | Op::Neg
+        | Op::Log
+        | Op::Reciprocal
+        | Op::Floor
+        | Op::Ceil
+        | Op::Sin
+        | Op::Cos
+        | Op::Softplus
+        | Op::Softsign
+        | Op::Elu(_)
+        | Op::Selu(_)
+        | Op::Mish
+        | Op::HardSwish
+        | Op::Sign
+        | Op::Softmax(_)
+        | Op::Cast(_)
+        | Op::GlobalAveragePool
+        | Op::GlobalMaxPool
+        | Op::AveragePool(_)
+        | Op::LpPool(_)
+        | Op::Flatten(_)
+        | Op::Transpose(_)
+        | Op::Round
+        | Op::Exp
+        | Op::Shape(_)
+        | Op::HardSigmoid(_) => (1..=1, 1..=1),
+        Op::Clip => (1..=3, 1..=1),
+        Op::Where => (3..=3, 1..=1),
+        Op::MaxPool(_) => (1..=1, 1..=2),
+        Op::Reshape => (Op::RESHAPE_SHAPE + 1..=Op::RESHAPE_SHAPE + 1, 1..=1),
+        Op::Resize(_) => (1..=Op::RESIZE_IN_SIZES + 1, 1..=1),
+        Op::Pad(_) => (1..=Op::PAD_IN_AXES + 1, 1..=1),
+        Op::DepthToSpace(_) | Op::SpaceToDepth(_) => (1..=1, 1..=1),
+        Op::Concat(_) => (1..=ANY, 1..=1),
+        Op::Squeeze(_) | Op::Unsqueeze(_) => (1..=2, 1..=1),
+        Op::ReduceMin(_)
+        | Op::ReduceMax(_)
+        | Op::ReduceMean(_)
+        | Op::ReduceSum(_)
+        | Op::ReduceProd(_)
+        | Op::ReduceL1(_)
+        | Op::ReduceL2(_)
+        | Op::ReduceLogSum(_)
+        | Op::ReduceLogSumExp(_)
+        | Op::ReduceSumSquare(_) => (1..=Op::REDUCE_IN_AXES + 1, 1..=1),
+        Op::ArgMax(_) | Op::ArgMin(_) => (1..=1, 1..=1),
+        Op::Range => (3..=3, 1..=1),
+        Op::Loop(_) => (2..=ANY, 1..=ANY),
+        Op::If(_) => (1..=ANY, 1..=ANY),
+        Op::Tile => (Op::TILE_REPEATS + 1..=Op::TILE_REPEATS + 1, 1..=1),
+        Op::Split(_) => (1..=2, 1..=ANY),
+        Op::Slice => (Op::SLICE_IN_ENDS + 1..=Op::SLICE_IN_STEPS + 1, 1..=1),
+        Op::Gather(_) | Op::GatherElements(_) | Op::GatherND(_) => (2..=2, 1..=1),
+        Op::ScatterElements(_) | Op::ScatterND(_) => (3..=3, 1..=1),
+        Op::TopK(_) => (2..=2, 2..=2),
+        Op::OneHot(_) => (3..=3, 1..=1),
+        Op::NonZero => (1..=1, 1..=1),
+        Op::CumSum(_) => (2..=2, 1..=1),
+        Op::NonMaxSuppression(_) => (
+            Op::NMS_IN_SCORES + 1..=Op::NMS_IN_NMS_SCORE_THRESHOLD + 1,
+            1..=1,
+        ),
+        Op::Gemm(_) => (Op::GEMM_IN_B + 1..=Op::GEMM_IN_C + 1, 1..=1),
+        Op::BatchNormalization(_) => (
+            Op::BATCHNORM_IN_INPUT_VAR + 1..=Op::BATCHNORM_IN_INPUT_VAR + 1,
+            1..=3,
+        ),
+        Op::LayerNormalization(_) => (2..=3, 1..=3),
+        Op::InstanceNormalization(_) | Op::GroupNormalization(_) => {
+            (Op::NORM_IN_B + 1..=Op::NORM_IN_B + 1, 1..=1)
+        }
+        Op::LSTM(_) => (Op::RNN_IN_R + 1..=Op::LSTM_IN_P + 1, 1..=3),
+        Op::GRU(_) | Op::RNN(_) => (Op::RNN_IN_R + 1..=Op::RNN_IN_INITIAL_H + 1, 1..=2),
+        Op::Constant(_) => (0..=0, 1..=1),
+        Op::QuantizeLinear(_) => (
+            Op::QUANTIZELINEAR_IN_Y_SCALE + 1..=Op::QUANTIZELINEAR_IN_Y_ZERO_POINT + 1,
+            1..=1,
+        ),
+        Op::DequantizeLinear(_) => (
+            Op::DEQUANTIZELINEAR_IN_X_SCALE + 1..=Op::DEQUANTIZELINEAR_IN_X_ZERO_POINT + 1,
+            1..=1,
+        ),
+        Op::QLinearConv(_) => (
+            Op::QLINEARCONV_IN_Y_ZERO_POINT + 1..=Op::QLINEARCONV_IN_B + 1,
+            1..=1,
+        ),
+        Op::QLinearMatMul => (
+            Op::QLINEARMATMUL_IN_Y_ZERO_POINT + 1..=Op::QLINEARMATMUL_IN_Y_ZERO_POINT + 1,
+            1..=1,
+        ),
+        Op::MatMulInteger => (
+            Op::MATMULINTEGER_IN_B + 1..=Op::MATMULINTEGER_IN_B_ZERO_POINT + 1,
+            1..=1,
+        ),
+        Op::ConvInteger(_) => (
+            Op::CONVINTEGER_IN_W + 1..=Op::CONVINTEGER_IN_W_ZERO_POINT + 1,
+            1..=1,
+        ),
+        Op::FusedMatMul(_) => (2..=2, 1..=1),
+        Op::Attention(_) => (
+            Op::ATTENTION_IN_BIAS + 1..=Op::ATTENTION_IN_MASK_INDEX + 1,
+            1..=1,
+        ),
+        Op::Custom(_) => (0..=ANY, 1..=ANY),
+        Op::FusedElemwise(_) => (1..=ANY, 1..=ANY),
+    };
+    (ins, outs)
+}
+
+fn has_duplicates(axes: &[i64]) -> bool {
+    let mut seen = FxHashSet::default();
+    axes.iter().any(|a| !seen.insert(a))
+}
+
+#[test]
+fn verify_valid_model() {
+    use crate::tensor::Tensor;
+
+    let mut model = Model::default();
+    let x = model.graph.values.new_val_named("x");
+    let w = model.graph.values.new_val_named("w");
+    let y = model.graph.values.new_val_named("y");
+    let z = model.graph.values.new_val_named("z");
+    model
+        .graph
+        .inits
+        .insert(w, Tensor::new(vec![1].into(), vec![1.0f32]));
+    model
+        .graph
+        .add_node(Node::new(Op::Add).with_ins(vec![x, w]).with_out(y));
+    model
+        .graph
+        .add_node(Node::new(Op::ReLU).with_in(y).with_out(z));
+    model.graph.inputs.push(x);
+    model.graph.outputs.push(z);
+
+    assert_eq!(verify(&model), Ok(()));
+}
+
+#[test]
+fn verify_broken_model() {
+    use crate::op::Transpose;
+
+    let mut model = Model::default();
+    let x = model.graph.values.new_val_named("x");
+    let undefined = model.graph.values.new_val_named("undefined");
+    let a = model.graph.values.new_val_named("a");
+    let b = model.graph.values.new_val_named("b");
+    let c = model.graph.values.new_val_named("c");
+    let n0 = model
+        .graph
+        .add_node(Node::new(Op::Add).with_ins(vec![x, b]).with_out(a));
+    let n1 = model
+        .graph
+        .add_node(Node::new(Op::ReLU).with_in(a).with_out(b));
+    let n2 = model
+        .graph
+        .add_node(Node::new(Op::Sigmoid).with_in(x).with_out(b));
+    let n3 = model.graph.add_node(
+        Node::new(Op::Transpose(Transpose { perm: vec![0, 0] }))
+            .with_ins(vec![undefined, x])
+            .with_out(c),
+    );
+    model.graph.inputs.push(x);
+    model.graph.outputs.push(c);
+
+    let errors = verify(&model).unwrap_err();
+    assert_eq!(
+        errors,
+        vec![
+            VerifyError::MultipleProducers {
+                value: b,
+                producers: vec![n1, n2]
+            },
+            VerifyError::UndefinedValue {
+                node: n3,
+                op: "Transpose",
+                value: undefined
+            },
+            VerifyError::Cycle(vec![n0, n1]),
+            VerifyError::InputArity {
+                node: n3,
+                op: "Transpose",
+                expected: 1..=1,
+                actual: 2
+            },
+            VerifyError::Attribute {
+                node: n3,
+                op: "Transpose",
+                message: "perm [0, 0] is not a permutation".into()
+            },
+        ]
+    );
+}
@@ -0,0 +1,63 @@
+use std::{borrow::Cow, fmt, sync::Arc};
+
+use rustc_hash::FxHashMap;
+use thiserror::Error;
+
+use crate::{
+    analysis::shape::ShapeError,
+    op::Custom,
+    tensor::{Tensor, TypedFixedShape},
+};
+
+#[derive(Debug, Clone, Error)]
+pub enum CustomOpError {
+    #[error("Something went wrong: {0}")]
+    Message(Cow<'static, str>),
+}
+
+/// A user-defined op that `Op::Custom` nodes are computed with.
+pub trait CustomOp: Send + Sync {
+    /// Computes the output shapes from `inputs`, as `Model::compute_output_shapes` does.
+    /// Only the shapes of `inputs` are meaningful unless they are initializers.
+    fn compute_output_shapes(
+        &self,
+        op: &Custom,
+        inputs: &[&Tensor],
+        num_outputs: usize,
+    ) -> Result<Vec<TypedFixedShape>, ShapeError>;
+
+    /// Computes `outputs`, which are allocated with the shapes `compute_output_shapes` returned.
+    fn compute(
+        &self,
+        op: &Custom,
+        inputs: &[&Tensor],
+        outputs: &mut [Tensor],
+    ) -> Result<(), CustomOpError>;
+}
+
+/// `CustomOp`s keyed by their domain and op type.
+#[derive(Default, Clone)]
+pub struct CustomOpRegistry(FxHashMap<(String, String), Arc<dyn CustomOp>>);
+
+impl CustomOpRegistry {
+    pub fn register(
+        &mut self,
+        domain: impl Into<String>,
+        op_type: impl Into<String>,
+        op: Arc<dyn CustomOp>,
+    ) {
+        self.0.insert((domain.into(), op_type.into()), op);
+    }
+
+    pub fn get(&self, op: &Custom) -> Option<&dyn CustomOp> {
+        self.0
+            .get(&(op.domain.clone(), op.op_type.clone()))
+            .map(|op| op.as_ref())
+    }
+}
+
+impl fmt::Debug for CustomOpRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.0.keys()).finish()
+    }
+}
@@ -4,6 +4,8 @@ use std::{
     slice::SliceIndex,
 };
 
+use rustc_hash::FxHashMap;
+
 use crate::fixed_dim::{FixedDimension, FixedDimensions};
 
 #[derive(Clone, PartialEq, Eq, Hash)]
@@ -43,6 +45,26 @@ impl Dimensions {
                 .collect(),
         ))
     }
+
+    /// Binds the dynamic dimensions to the corresponding ones in `dims`.
+    /// Returns false if `dims` doesn't match `self`, including the case where
+    /// an already bound symbol is given a different size.
+    pub fn bind(&self, dims: &[FixedDimension], bindings: &mut FxHashMap<String, usize>) -> bool {
+        if self.len() != dims.len() {
+            return false;
+        }
+
+        self.iter().zip(dims).all(|(d, &fixed)| match d {
+            Dimension::Fixed(d) => *d == fixed,
+            Dimension::Dynamic(sym) => match bindings.get(sym) {
+                Some(&bound) => bound == fixed,
+                None => {
+                    bindings.insert(sym.clone(), fixed);
+                    true
+                }
+            },
+        })
+    }
 }
 
 impl AsRef<Dimensions> for Dimensions {
@@ -106,3 +128,19 @@ fn use_symdims() {
         Dimension::Fixed(8),
     ]);
 }
+
+#[test]
+fn bind_symdims() {
+    let dims = Dimensions(vec![
+        Dimension::Dynamic("batch".into()),
+        Dimension::Fixed(8),
+        Dimension::Dynamic("batch".into()),
+    ]);
+    let mut bindings = FxHashMap::default();
+    assert!(dims.bind(&[2, 8, 2], &mut bindings));
+    assert_eq!(bindings["batch"], 2);
+    assert!(!dims.bind(&[3, 8, 3], &mut bindings));
+    assert!(!dims.bind(&[2, 4, 2], &mut FxHashMap::default()));
+    assert!(!dims.bind(&[2, 8, 3], &mut FxHashMap::default()));
+    assert!(!dims.bind(&[2, 8], &mut FxHashMap::default()));
+}
@@ -6,13 +6,16 @@ use crate::{
     value::{ValueArena, ValueId},
 };
 
-#[derive(Default, Clone)]
+#[derive(Debug, Default, Clone, PartialEq)]
 pub struct Graph {
     pub nodes: NodeArena,
     pub values: ValueArena,
     pub inits: HashMap<ValueId, Tensor>,
     pub inputs: Vec<ValueId>,
     pub outputs: Vec<ValueId>,
+    /// Values of the enclosing graph this subgraph refers to (e.g. the body of `Loop`).
+    /// They are bound to the trailing inputs of the node owning this subgraph.
+    pub outer_inputs: Vec<ValueId>,
 }
 
 impl Graph {
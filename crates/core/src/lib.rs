@@ -1,6 +1,7 @@
 #![allow(clippy::excessive_precision)]
 
 pub mod analysis;
+pub mod custom_op;
 pub mod dim;
 pub mod fixed_dim;
 pub mod graph;
@@ -9,12 +10,13 @@ pub mod node;
 pub mod onnx;
 pub mod op;
 pub mod optimize;
+pub mod quantize;
 pub mod tensor;
 pub mod value;
 
-#[cfg(not(target_arch = "wasm32"))]
+#[cfg(not(any(target_arch = "wasm32", feature = "system-allocator")))]
 use mimalloc::MiMalloc;
 
-#[cfg(not(target_arch = "wasm32"))]
+#[cfg(not(any(target_arch = "wasm32", feature = "system-allocator")))]
 #[global_allocator]
 static GLOBAL: MiMalloc = MiMalloc;
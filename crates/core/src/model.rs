@@ -1,6 +1,12 @@
 use std::mem;
 
-use crate::{graph::Graph, node::NodeId, value::ValueId};
+use crate::{
+    analysis::verify::{verify, VerifyError},
+    custom_op::CustomOpRegistry,
+    graph::Graph,
+    node::NodeId,
+    value::ValueId,
+};
 use id_arena::Arena;
 use rustc_hash::{FxHashMap, FxHashSet};
 
@@ -8,6 +14,10 @@ use rustc_hash::{FxHashMap, FxHashSet};
 pub struct Model {
     pub graph: Graph,
     pub opset_version: i64,
+    /// Opset versions of domains other than the default one (e.g. `com.microsoft`).
+    pub domain_opset_versions: FxHashMap<String, i64>,
+    /// Implementations of `Op::Custom` nodes.
+    pub custom_ops: CustomOpRegistry,
 }
 
 impl Model {
@@ -21,6 +31,16 @@ impl Model {
         })
     }
 
+    /// Returns a model of `graph`, a subgraph of this model, with the same opsets and custom ops.
+    pub fn subgraph_model(&self, graph: &Graph) -> Model {
+        Model {
+            graph: graph.clone(),
+            opset_version: self.opset_version,
+            domain_opset_versions: self.domain_opset_versions.clone(),
+            custom_ops: self.custom_ops.clone(),
+        }
+    }
+
     pub fn get_value_users(&self) -> FxHashMap<ValueId, FxHashSet<NodeId>> {
         let mut value_users: FxHashMap<ValueId, FxHashSet<NodeId>> = FxHashMap::default();
 
@@ -96,6 +116,13 @@ impl Model {
         nodes
     }
 
+    /// Checks that the graph is well-formed: every value has at most one producer, the graph is
+    /// acyclic, graph inputs and outputs exist, and every node has valid arity and attributes.
+    /// Returns all the violations found.
+    pub fn verify(&self) -> Result<(), Vec<VerifyError>> {
+        verify(self)
+    }
+
     /// Removes nodes labeled as deleted from the arena.
     pub fn remove_unnecessary_nodes(&mut self) {
         let order = self
@@ -121,7 +148,7 @@ impl Model {
 fn mnist_model() {
     use crate::{
         node::Node,
-        op::{Conv2d, MaxPool, Op},
+        op::{Conv2d, Op, Pool},
         tensor::Tensor,
     };
 
@@ -156,11 +183,12 @@ fn mnist_model() {
         .alloc(&mut m.graph.nodes);
 
     let maxpool0_out = m.graph.values.new_val();
-    let _maxpool0 = Node::new(Op::MaxPool(MaxPool {
+    let _maxpool0 = Node::new(Op::MaxPool(Pool {
         auto_pad: "NOTSET".into(),
         padding: vec![0, 0, 0, 0].into(),
         kernel_shape: vec![2, 2].into(),
         strides: vec![2, 2].into(),
+        ..Default::default()
     }))
     .with_in(relu0_out)
     .with_out(maxpool0_out)
@@ -194,11 +222,12 @@ fn mnist_model() {
         .alloc(&mut m.graph.nodes);
 
     let maxpool1_out = m.graph.values.new_val();
-    let _maxpool1 = Node::new(Op::MaxPool(MaxPool {
+    let _maxpool1 = Node::new(Op::MaxPool(Pool {
         auto_pad: "NOTSET".into(),
         padding: vec![0, 0, 0, 0].into(),
         kernel_shape: vec![3, 3].into(),
         strides: vec![3, 3].into(),
+        ..Default::default()
     }))
     .with_in(relu1_out)
     .with_out(maxpool1_out)
@@ -4,7 +4,7 @@ use id_arena::{Arena, Id};
 pub type NodeId = Id<Node>;
 pub type NodeArena = Arena<Node>;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Node {
     pub op: Op,
     pub name: Option<String>,
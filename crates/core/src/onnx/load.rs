@@ -1,19 +1,32 @@
+use half::{bf16, f16};
 use prost::{DecodeError, Message};
-use rustc_hash::FxHashMap;
-use std::{borrow::Cow, collections::hash_map::Entry, fs, io, path::Path};
+use rustc_hash::{FxHashMap, FxHashSet};
+use std::{
+    borrow::Cow,
+    collections::{hash_map::Entry, BTreeMap},
+    fs, io,
+    path::Path,
+};
 use thiserror::Error;
 
 use crate::{
+    analysis::symbolic_shape::infer_symbolic_shapes,
     dim::Dimension,
     fixed_dim::FixedDimensions,
+    graph::Graph,
     model::Model,
     node::Node,
     op::{
-        BatchNormalization, Cast, Concat, Constant, Conv2d, Flatten, Gather, Gemm, HardSigmoid,
-        LayerNormalization, LeakyReLU, MaxPool, Op, ReduceMax, ReduceMean, ReduceMin, Resize,
-        Shape, Softmax, Split, Squeeze, Transpose, Unsqueeze,
+        ArgReduce, Attention, BatchNormalization, Cast, Concat, Constant, Conv2d, ConvTranspose,
+        CumSum, Custom, CustomAttribute, DepthToSpace, DequantizeLinear, Elu, Flatten, FusedMatMul,
+        Gather, GatherElements, GatherND, Gemm, GroupNormalization, HardSigmoid, If,
+        InstanceNormalization, LayerNormalization, LeakyReLU, Loop, NonMaxSuppression, OneHot, Op,
+        Pad, Pool, QuantizeLinear, Reduce, Resize, RnnDirection, ScatterElements, ScatterND,
+        ScatterReduction, Selu, Shape, Softmax, SpaceToDepth, Split, Squeeze, TopK, Transpose,
+        Unsqueeze, GRU, LSTM, RNN,
     },
     tensor::{Tensor, TensorElemType, TypedShape},
+    value::ValueId,
 };
 
 use tensor_proto::DataType;
@@ -52,20 +65,67 @@ pub enum ModelLoadError {
     Todo(Cow<'static, str>),
 }
 
+/// Converts a node of a custom domain into an `Op`.
+/// It takes the node and the opset version of its domain.
+pub type DomainHandler = dyn Fn(&NodeProto, i64) -> Result<Op, ModelLoadError> + Send + Sync;
+
+/// Loads ONNX models with user-registered handlers for custom domains.
+/// Nodes of custom domains without handlers are loaded as `Op::Custom`.
+#[derive(Default)]
+pub struct OnnxLoader {
+    domain_handlers: FxHashMap<String, Box<DomainHandler>>,
+}
+
+impl OnnxLoader {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers `handler` for nodes of `domain`.
+    /// For `com.microsoft`, it's only called for ops altius doesn't support.
+    pub fn with_domain_handler(
+        mut self,
+        domain: impl Into<String>,
+        handler: impl Fn(&NodeProto, i64) -> Result<Op, ModelLoadError> + Send + Sync + 'static,
+    ) -> Self {
+        self.domain_handlers
+            .insert(domain.into(), Box::new(handler));
+        self
+    }
+
+    pub fn load(&self, path: impl AsRef<Path>) -> Result<Model, ModelLoadError> {
+        let model_proto = load_onnx_model_proto(path)?;
+        self.load_from_model_proto(model_proto)
+    }
+
+    pub fn load_from_buffer(&self, buf: &[u8]) -> Result<Model, ModelLoadError> {
+        let model = ModelProto::decode(buf).map_err(ModelLoadError::InvalidModel)?;
+        self.load_from_model_proto(model)
+    }
+
+    pub fn load_from_model_proto(&self, model_proto: ModelProto) -> Result<Model, ModelLoadError> {
+        load_model(model_proto, &self.domain_handlers)
+    }
+}
+
 pub fn load_onnx(path: impl AsRef<Path>) -> Result<Model, ModelLoadError> {
-    let model_proto = load_onnx_model_proto(path)?;
-    load_onnx_from_model_proto(model_proto)
+    OnnxLoader::new().load(path)
 }
 
 pub fn load_onnx_from_buffer(buf: &[u8]) -> Result<Model, ModelLoadError> {
-    let model = ModelProto::decode(buf).map_err(ModelLoadError::InvalidModel)?;
-    load_onnx_from_model_proto(model)
+    OnnxLoader::new().load_from_buffer(buf)
 }
 
 pub fn load_onnx_from_model_proto(model_proto: ModelProto) -> Result<Model, ModelLoadError> {
+    OnnxLoader::new().load_from_model_proto(model_proto)
+}
+
+fn load_model(
+    model_proto: ModelProto,
+    domain_handlers: &FxHashMap<String, Box<DomainHandler>>,
+) -> Result<Model, ModelLoadError> {
     let graph = model_proto.graph.ok_or(ModelLoadError::NoGraph)?;
     let mut model = Model::default();
-    let mut name_to_val = FxHashMap::default();
 
     let mut opset_version = None;
     for opset_import in &model_proto.opset_import {
@@ -75,23 +135,65 @@ pub fn load_onnx_from_model_proto(model_proto: ModelProto) -> Result<Model, Mode
             }
             "" | "ai.onnx" => return Err(ModelLoadError::DuplicateOpset),
             domain => {
-                return Err(ModelLoadError::Todo(
-                    format!("Custom domain ('{domain}') not supported yet").into(),
-                ))
+                if model
+                    .domain_opset_versions
+                    .insert(domain.to_string(), opset_import.version())
+                    .is_some()
+                {
+                    return Err(ModelLoadError::DuplicateOpset);
+                }
             }
         }
     }
     model.opset_version = opset_version.ok_or(ModelLoadError::DuplicateOpset)?;
 
+    load_graph(&mut model, &graph, domain_handlers, false)?;
+
+    // Make shapes of intermediate values, including symbolic ones, visible before any input arrives.
+    if let Err(e) = infer_symbolic_shapes(&mut model) {
+        log::warn!("Failed to infer symbolic shapes: {e}");
+    }
+
+    Ok(model)
+}
+
+/// Loads a subgraph given as the attribute `name` of `node`.
+fn load_subgraph(
+    model: &Model,
+    node: &NodeProto,
+    name: &'static str,
+    domain_handlers: &FxHashMap<String, Box<DomainHandler>>,
+) -> Result<Graph, ModelLoadError> {
+    let graph = get_attribute(&node.attribute, name)?
+        .g
+        .as_ref()
+        .ok_or(ModelLoadError::NoAttribute(name))?;
+    let mut subgraph = model.subgraph_model(&Graph::default());
+    load_graph(&mut subgraph, graph, domain_handlers, true)?;
+    Ok(subgraph.graph)
+}
+
+/// Loads `graph` into `model.graph`.
+/// In a subgraph, values defined nowhere in it refer to outer scopes and become `outer_inputs`.
+fn load_graph(
+    model: &mut Model,
+    graph: &GraphProto,
+    domain_handlers: &FxHashMap<String, Box<DomainHandler>>,
+    is_subgraph: bool,
+) -> Result<(), ModelLoadError> {
+    let mut name_to_val = FxHashMap::default();
+
     // Load initializers.
     for init in graph.initializer.iter() {
         let tensor = get_tensor(init)?;
-        let val = *name_to_val.entry(init.name()).or_insert_with(|| {
-            model.graph.values.new_val_named_and_shaped(
-                init.name(),
-                TypedShape::new(tensor.dims().clone().into(), tensor.elem_ty()),
-            )
-        });
+        let val = *name_to_val
+            .entry(init.name().to_string())
+            .or_insert_with(|| {
+                model.graph.values.new_val_named_and_shaped(
+                    init.name(),
+                    TypedShape::new(tensor.dims().clone().into(), tensor.elem_ty()),
+                )
+            });
         model.graph.inits.insert(val, tensor);
     }
 
@@ -101,40 +203,22 @@ pub fn load_onnx_from_model_proto(model_proto: ModelProto) -> Result<Model, Mode
         (&graph.output, &mut model.graph.outputs),
     ] {
         for x in vals {
-            let TensorType(tensor) = x
-                .r#type
-                .as_ref()
-                .ok_or_else(|| ModelLoadError::NoValueType)?
-                .value
-                .as_ref()
-                .ok_or_else(|| ModelLoadError::NoValueType)?
-            else {
-                return Err(ModelLoadError::Todo(
-                    "Graph input must be tensor type".into(),
-                ));
+            let shape = match load_value_shape(x) {
+                Ok(shape) => Some(shape),
+                // Inputs and outputs of subgraphs often come without types or shapes.
+                Err(ModelLoadError::NoValueType | ModelLoadError::NoValueShape) if is_subgraph => {
+                    None
+                }
+                Err(e) => return Err(e),
             };
 
-            let dims: Vec<Dimension> = tensor
-                .shape
-                .as_ref()
-                .ok_or_else(|| ModelLoadError::NoValueShape)?
-                .dim
-                .iter()
-                .map(|d| match d.value.as_ref().unwrap() {
-                    DimValue(i) => Dimension::Fixed(*i as usize),
-                    DimParam(s) => Dimension::Dynamic(s.clone()),
-                })
-                .collect();
-
-            let input = match name_to_val.entry(x.name()) {
+            let input = match name_to_val.entry(x.name().to_string()) {
                 Entry::Occupied(o) => *o.get(),
-                Entry::Vacant(v) => *v.insert(model.graph.values.new_val_named_and_shaped(
-                    x.name(),
-                    TypedShape::new(
-                        dims.into(),
-                        DataType::from_i32(tensor.elem_type()).unwrap().try_into()?,
-                    ),
-                )),
+                Entry::Vacant(v) => {
+                    let id = model.graph.values.new_val_named(x.name());
+                    model.graph.values[id].shape = shape;
+                    *v.insert(id)
+                }
             };
 
             vec.push(input);
@@ -149,37 +233,89 @@ pub fn load_onnx_from_model_proto(model_proto: ModelProto) -> Result<Model, Mode
 
     // Load nodes.
     for node in graph.node.iter() {
-        let inputs = node
+        let mut inputs: Vec<_> = node
             .input
             .iter()
-            .map(|input| {
-                *name_to_val
-                    .entry(input)
-                    .or_insert_with(|| model.graph.values.new_val_named(input))
-            })
+            .map(|input| lookup_value(model, &mut name_to_val, input, is_subgraph))
             .collect();
-        let outputs = node
+        let outputs: Vec<_> = node
             .output
             .iter()
-            .map(|input| {
-                *name_to_val
-                    .entry(input)
-                    .or_insert_with(|| model.graph.values.new_val_named(input))
+            .map(|output| match name_to_val.entry(output.clone()) {
+                // Omitted optional outputs must not be shared with omitted inputs.
+                _ if output.is_empty() => model.graph.values.new_val_named(output),
+                Entry::Occupied(o) => *o.get(),
+                Entry::Vacant(v) => *v.insert(model.graph.values.new_val_named(output)),
             })
             .collect();
 
+        let domain = node.domain();
+        if !matches!(domain, "" | "ai.onnx") {
+            if domain == "com.microsoft" && load_com_microsoft_node(model, node, &inputs, &outputs)?
+            {
+                continue;
+            }
+            // Nodes without handlers are left to `CustomOp`s registered on sessions.
+            let op = match domain_handlers.get(domain) {
+                Some(handler) => {
+                    let version = model.domain_opset_versions.get(domain).copied();
+                    handler(node, version.unwrap_or(1))?
+                }
+                None => Op::Custom(load_custom(node)?),
+            };
+            model.graph.add_node(
+                Node::new(op)
+                    .with_name(node.name.to_owned())
+                    .with_ins(inputs)
+                    .with_outs(outputs),
+            );
+            continue;
+        }
+
         let op = match node.op_type() {
             "Add" => Op::Add,
             "Sub" => Op::Sub,
             "Mul" => Op::Mul,
             "Div" => Op::Div,
+            "Equal" => Op::Equal,
+            "Less" => Op::Less,
+            "LessOrEqual" => Op::LessOrEqual,
             "Greater" => Op::Greater,
+            "GreaterOrEqual" => Op::GreaterOrEqual,
+            "And" => Op::And,
+            "Or" => Op::Or,
+            "Not" => Op::Not,
+            "Xor" => Op::Xor,
             "Pow" => Op::Pow,
             "Sqrt" => Op::Sqrt,
             "Relu" => Op::ReLU,
             "Sigmoid" => Op::Sigmoid,
             "Erf" => Op::Erf,
             "Tanh" => Op::Tanh,
+            "Abs" => Op::Abs,
+            "Neg" => Op::Neg,
+            "Log" => Op::Log,
+            "Reciprocal" => Op::Reciprocal,
+            "Floor" => Op::Floor,
+            "Ceil" => Op::Ceil,
+            "Sin" => Op::Sin,
+            "Cos" => Op::Cos,
+            "Softplus" => Op::Softplus,
+            "Softsign" => Op::Softsign,
+            "Elu" => Op::Elu(Elu {
+                alpha: get_attribute(&node.attribute, "alpha")
+                    .map_or(Elu::default().alpha, |a| a.f()),
+            }),
+            "Selu" => Op::Selu(Selu {
+                alpha: get_attribute(&node.attribute, "alpha")
+                    .map_or(Selu::default().alpha, |a| a.f()),
+                gamma: get_attribute(&node.attribute, "gamma")
+                    .map_or(Selu::default().gamma, |a| a.f()),
+            }),
+            "Mish" => Op::Mish,
+            "HardSwish" => Op::HardSwish,
+            "PRelu" => Op::PRelu,
+            "Sign" => Op::Sign,
             "Where" => Op::Where,
             "Softmax" => Op::Softmax(Softmax {
                 axis: get_attribute(&node.attribute, "axis").map_or(-1, |a| a.i()),
@@ -198,35 +334,53 @@ pub fn load_onnx_from_model_proto(model_proto: ModelProto) -> Result<Model, Mode
             "Gather" => Op::Gather(Gather {
                 axis: get_attribute(&node.attribute, "axis").map_or(0, |a| a.i()),
             }),
-            "NonMaxSuppression" => Op::NonMaxSuppression,
+            "GatherElements" => Op::GatherElements(GatherElements {
+                axis: get_attribute(&node.attribute, "axis").map_or(0, |a| a.i()),
+            }),
+            "GatherND" => Op::GatherND(GatherND {
+                batch_dims: get_attribute(&node.attribute, "batch_dims").map_or(0, |a| a.i()),
+            }),
+            "ScatterElements" => Op::ScatterElements(ScatterElements {
+                axis: get_attribute(&node.attribute, "axis").map_or(0, |a| a.i()),
+                reduction: get_scatter_reduction(&node.attribute)?,
+            }),
+            "ScatterND" => Op::ScatterND(ScatterND {
+                reduction: get_scatter_reduction(&node.attribute)?,
+            }),
+            "TopK" => Op::TopK(TopK {
+                axis: get_attribute(&node.attribute, "axis").map_or(-1, |a| a.i()),
+                largest: get_attribute(&node.attribute, "largest").map_or(true, |a| a.i() != 0),
+                sorted: get_attribute(&node.attribute, "sorted").map_or(true, |a| a.i() != 0),
+            }),
+            "OneHot" => Op::OneHot(OneHot {
+                axis: get_attribute(&node.attribute, "axis").map_or(-1, |a| a.i()),
+            }),
+            "NonZero" => Op::NonZero,
+            "CumSum" => Op::CumSum(CumSum {
+                exclusive: get_attribute(&node.attribute, "exclusive")
+                    .map_or(false, |a| a.i() != 0),
+                reverse: get_attribute(&node.attribute, "reverse").map_or(false, |a| a.i() != 0),
+            }),
+            "NonMaxSuppression" => Op::NonMaxSuppression(NonMaxSuppression {
+                center_point_box: get_attribute(&node.attribute, "center_point_box")
+                    .map_or(false, |a| a.i() == 1),
+            }),
             "Reshape" => Op::Reshape,
             "MatMul" => Op::MatMul,
             "GlobalAveragePool" => Op::GlobalAveragePool,
-            "Conv" => {
-                let auto_pad = get_attribute(&node.attribute, "auto_pad")
-                    .map_or("NOTSET".to_string(), |a| {
-                        unsafe { std::str::from_utf8_unchecked(a.s()) }.to_string()
-                    });
-                let kernel_shape = FixedDimensions::from_i64(
-                    &get_attribute(&node.attribute, "kernel_shape")?.ints,
-                );
-                let strides = get_attribute(&node.attribute, "strides")
-                    .map_or(vec![1, 1].into(), |a| FixedDimensions::from_i64(&a.ints));
-                let padding = get_attribute(&node.attribute, "pads")
-                    .map_or(vec![0, 0].into(), |a| FixedDimensions::from_i64(&a.ints));
-                let dilations = get_attribute(&node.attribute, "dilations")
-                    .map_or(vec![1, 1].into(), |a| FixedDimensions::from_i64(&a.ints));
-                let group = get_attribute(&node.attribute, "group").map_or(1, |a| a.i());
-                Op::Conv2d(Conv2d {
-                    auto_pad,
-                    dilations,
-                    kernel_shape,
-                    strides,
-                    group,
-                    padding,
-                    activation: None,
-                })
-            }
+            "GlobalMaxPool" => Op::GlobalMaxPool,
+            "Conv" => Op::Conv2d(get_conv2d(&node.attribute)?),
+            "ConvTranspose" => Op::ConvTranspose(get_conv_transpose(&node.attribute)?),
+            "QLinearConv" => Op::QLinearConv(get_conv2d(&node.attribute)?),
+            "ConvInteger" => Op::ConvInteger(get_conv2d(&node.attribute)?),
+            "QLinearMatMul" => Op::QLinearMatMul,
+            "MatMulInteger" => Op::MatMulInteger,
+            "QuantizeLinear" => Op::QuantizeLinear(QuantizeLinear {
+                axis: get_attribute(&node.attribute, "axis").map_or(1, |a| a.i()),
+            }),
+            "DequantizeLinear" => Op::DequantizeLinear(DequantizeLinear {
+                axis: get_attribute(&node.attribute, "axis").map_or(1, |a| a.i()),
+            }),
             "LeakyRelu" => Op::LeakyReLU(LeakyReLU {
                 alpha: get_attribute(&node.attribute, "alpha").map_or(0.01, |a| a.f()),
             }),
@@ -259,13 +413,30 @@ pub fn load_onnx_from_model_proto(model_proto: ModelProto) -> Result<Model, Mode
                     nearest_mode,
                 })
             }
+            "Pad" => Op::Pad(Pad {
+                mode: get_attribute(&node.attribute, "mode").map_or("constant".to_string(), |a| {
+                    unsafe { std::str::from_utf8_unchecked(a.s()) }.to_string()
+                }),
+                pads: get_attribute(&node.attribute, "pads")
+                    .map_or_else(|_| Vec::new(), |a| a.ints.clone()),
+                value: get_attribute(&node.attribute, "value").map_or(0.0, |a| a.f()),
+            }),
+            "DepthToSpace" => Op::DepthToSpace(DepthToSpace {
+                blocksize: get_attribute(&node.attribute, "blocksize")?.i(),
+                mode: get_attribute(&node.attribute, "mode").map_or("DCR".to_string(), |a| {
+                    unsafe { std::str::from_utf8_unchecked(a.s()) }.to_string()
+                }),
+            }),
+            "SpaceToDepth" => Op::SpaceToDepth(SpaceToDepth {
+                blocksize: get_attribute(&node.attribute, "blocksize")?.i(),
+            }),
             "Concat" => Op::Concat(Concat {
                 axis: get_attribute(&node.attribute, "axis")?.i(),
             }),
             "Transpose" => Op::Transpose(Transpose {
                 perm: get_attribute(&node.attribute, "perm")?.ints.clone(),
             }),
-            "Squeeze" if opset_version < Some(12) => Op::Squeeze(Squeeze {
+            "Squeeze" if model.opset_version < 12 => Op::Squeeze(Squeeze {
                 axes: get_attribute(&node.attribute, "axes")?.ints.clone(),
             }),
             "Squeeze" => Op::Squeeze(Squeeze { axes: vec![] }),
@@ -273,24 +444,25 @@ pub fn load_onnx_from_model_proto(model_proto: ModelProto) -> Result<Model, Mode
                 axes: get_attribute(&node.attribute, "axes")
                     .map_or_else(|_| Vec::new(), |a| a.ints.clone()),
             }),
-            "ReduceMin" => Op::ReduceMin(ReduceMin {
-                axes: get_attribute(&node.attribute, "axes")?.ints.clone(),
-                keep_dims: get_attribute(&node.attribute, "keepdims").map_or(true, |a| a.i() != 0),
-            }),
-            "ReduceMax" => Op::ReduceMax(ReduceMax {
-                axes: get_attribute(&node.attribute, "axes").map_or(vec![], |a| a.ints.clone()),
-                keep_dims: get_attribute(&node.attribute, "keepdims").map_or(true, |a| a.i() != 0),
+            "ReduceMin" => Op::ReduceMin(get_reduce(&node.attribute)),
+            "ReduceMax" => Op::ReduceMax(get_reduce(&node.attribute)),
+            "ReduceMean" => Op::ReduceMean(get_reduce(&node.attribute)),
+            "ReduceSum" => Op::ReduceSum(get_reduce(&node.attribute)),
+            "ReduceProd" => Op::ReduceProd(get_reduce(&node.attribute)),
+            "ReduceL1" => Op::ReduceL1(get_reduce(&node.attribute)),
+            "ReduceL2" => Op::ReduceL2(get_reduce(&node.attribute)),
+            "ReduceLogSum" => Op::ReduceLogSum(get_reduce(&node.attribute)),
+            "ReduceLogSumExp" => Op::ReduceLogSumExp(get_reduce(&node.attribute)),
+            "ReduceSumSquare" => Op::ReduceSumSquare(get_reduce(&node.attribute)),
+            "ArgMax" => Op::ArgMax(get_arg_reduce(&node.attribute)),
+            "ArgMin" => Op::ArgMin(get_arg_reduce(&node.attribute)),
+            "Loop" => Op::Loop(Loop {
+                body: load_subgraph(model, node, "body", domain_handlers)?,
             }),
-            "ReduceMean" => Op::ReduceMean(ReduceMean {
-                axes: get_attribute(&node.attribute, "axes")?.ints.clone(),
-                keep_dims: get_attribute(&node.attribute, "keepdims").map_or(true, |a| a.i() != 0),
+            "If" => Op::If(If {
+                then_branch: load_subgraph(model, node, "then_branch", domain_handlers)?,
+                else_branch: load_subgraph(model, node, "else_branch", domain_handlers)?,
             }),
-            "Loop" => {
-                // TODO
-                let _body = get_attribute(&node.attribute, "body")?;
-                log::warn!("Ignore loop body!");
-                Op::Loop
-            }
             "Cast" => {
                 let to = TensorElemType::try_from(
                     DataType::from_i32(get_attribute(&node.attribute, "to")?.i() as i32)
@@ -298,25 +470,9 @@ pub fn load_onnx_from_model_proto(model_proto: ModelProto) -> Result<Model, Mode
                 )?;
                 Op::Cast(Cast { to })
             }
-            "MaxPool" => {
-                let auto_pad = get_attribute(&node.attribute, "auto_pad")
-                    .map_or("NOTSET".to_string(), |a| {
-                        unsafe { std::str::from_utf8_unchecked(a.s()) }.to_string()
-                    });
-                let padding = get_attribute(&node.attribute, "pads")
-                    .map_or(vec![0, 0].into(), |a| FixedDimensions::from_i64(&a.ints));
-                let kernel = FixedDimensions::from_i64(
-                    &get_attribute(&node.attribute, "kernel_shape")?.ints,
-                );
-                let strides =
-                    FixedDimensions::from_i64(&get_attribute(&node.attribute, "strides")?.ints);
-                Op::MaxPool(MaxPool {
-                    auto_pad,
-                    padding,
-                    kernel_shape: kernel,
-                    strides,
-                })
-            }
+            "MaxPool" => Op::MaxPool(get_pool(&node.attribute)?),
+            "AveragePool" => Op::AveragePool(get_pool(&node.attribute)?),
+            "LpPool" => Op::LpPool(get_pool(&node.attribute)?),
             "HardSigmoid" => Op::HardSigmoid(HardSigmoid {
                 alpha: get_attribute(&node.attribute, "alpha").map_or(0.2, |a| a.f()),
                 beta: get_attribute(&node.attribute, "beta").map_or(0.5, |a| a.f()),
@@ -341,24 +497,75 @@ pub fn load_onnx_from_model_proto(model_proto: ModelProto) -> Result<Model, Mode
                 stash_type: get_attribute(&node.attribute, "stash_type").map_or(1, |a| a.i()),
                 epsilon: get_attribute(&node.attribute, "epsilon").map_or(1e-5, |a| a.f()),
             }),
-            "Clip" => Op::Clip,
+            "InstanceNormalization" => Op::InstanceNormalization(InstanceNormalization {
+                epsilon: get_attribute(&node.attribute, "epsilon").map_or(1e-5, |a| a.f()),
+            }),
+            "GroupNormalization" => Op::GroupNormalization(GroupNormalization {
+                epsilon: get_attribute(&node.attribute, "epsilon").map_or(1e-5, |a| a.f()),
+                num_groups: get_attribute(&node.attribute, "num_groups")?.i(),
+                stash_type: get_attribute(&node.attribute, "stash_type").map_or(1, |a| a.i()),
+            }),
+            "LSTM" => {
+                let rnn = get_rnn(&node.attribute, &["Sigmoid", "Tanh", "Tanh"])?;
+                Op::LSTM(LSTM {
+                    activation_alpha: rnn.activation_alpha,
+                    activation_beta: rnn.activation_beta,
+                    activations: rnn.activations,
+                    clip: rnn.clip,
+                    direction: rnn.direction,
+                    hidden_size: rnn.hidden_size,
+                    input_forget: get_attribute(&node.attribute, "input_forget")
+                        .map_or(false, |a| a.i() == 1),
+                    layout: rnn.layout,
+                })
+            }
+            "GRU" => {
+                let rnn = get_rnn(&node.attribute, &["Sigmoid", "Tanh"])?;
+                Op::GRU(GRU {
+                    activation_alpha: rnn.activation_alpha,
+                    activation_beta: rnn.activation_beta,
+                    activations: rnn.activations,
+                    clip: rnn.clip,
+                    direction: rnn.direction,
+                    hidden_size: rnn.hidden_size,
+                    layout: rnn.layout,
+                    linear_before_reset: get_attribute(&node.attribute, "linear_before_reset")
+                        .map_or(false, |a| a.i() == 1),
+                })
+            }
+            "RNN" => Op::RNN(get_rnn(&node.attribute, &["Tanh"])?),
+            "Clip" => {
+                // Before opset version 11, min and max are given as attributes.
+                if model.opset_version < 11 {
+                    let min = get_attribute(&node.attribute, "min").map_or(f32::MIN, |a| a.f());
+                    let max = get_attribute(&node.attribute, "max").map_or(f32::MAX, |a| a.f());
+                    for bound in [min, max] {
+                        let val = model.graph.values.new_val();
+                        model
+                            .graph
+                            .inits
+                            .insert(val, Tensor::new(vec![].into(), vec![bound]));
+                        inputs.push(val);
+                    }
+                }
+                Op::Clip
+            }
             "Shape" => Op::Shape(Shape {
                 end: get_attribute(&node.attribute, "end").map_or(None, |a| a.i),
                 start: get_attribute(&node.attribute, "start").map_or(0, |a| a.i()),
             }),
-            "Constant" => Op::Constant(Constant {
-                value: get_tensor(get_attribute(&node.attribute, "value").map_or_else(
-                    |_| {
-                        Err(ModelLoadError::Todo(
-                            "Constant.value must be specified for now".into(),
-                        ))
-                    },
-                    |a| Ok(a.t.as_ref().unwrap()),
-                )?)?,
-            }),
+            "Constant" => Op::Constant(load_constant(node)?),
             op => return Err(ModelLoadError::Todo(format!("Unsupported op: {op}").into())),
         };
 
+        // Outer-scope values of subgraphs follow the inputs.
+        for subgraph in op.subgraphs() {
+            for &id in &subgraph.outer_inputs {
+                let name = subgraph.values[id].name.as_deref().unwrap_or_default();
+                inputs.push(lookup_value(model, &mut name_to_val, name, is_subgraph));
+            }
+        }
+
         model.graph.add_node(
             Node::new(op)
                 .with_name(node.name.to_owned())
@@ -367,7 +574,421 @@ pub fn load_onnx_from_model_proto(model_proto: ModelProto) -> Result<Model, Mode
         );
     }
 
-    Ok(model)
+    // Subgraphs may output outer-scope values as they are.
+    if is_subgraph {
+        let graph = &mut model.graph;
+        let produced = graph
+            .nodes
+            .iter()
+            .flat_map(|(_, n)| n.outputs.iter().copied())
+            .chain(graph.inputs.iter().copied())
+            .chain(graph.inits.keys().copied())
+            .chain(graph.outer_inputs.iter().copied())
+            .collect::<FxHashSet<_>>();
+        for &id in &graph.outputs {
+            if !produced.contains(&id) && !graph.outer_inputs.contains(&id) {
+                graph.outer_inputs.push(id);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn load_value_shape(x: &ValueInfoProto) -> Result<TypedShape, ModelLoadError> {
+    let TensorType(tensor) = x
+        .r#type
+        .as_ref()
+        .ok_or_else(|| ModelLoadError::NoValueType)?
+        .value
+        .as_ref()
+        .ok_or_else(|| ModelLoadError::NoValueType)?
+    else {
+        return Err(ModelLoadError::Todo(
+            "Graph input must be tensor type".into(),
+        ));
+    };
+
+    let dims = tensor
+        .shape
+        .as_ref()
+        .ok_or_else(|| ModelLoadError::NoValueShape)?
+        .dim
+        .iter()
+        .map(|d| match d.value.as_ref() {
+            Some(DimValue(i)) => Ok(Dimension::Fixed(*i as usize)),
+            Some(DimParam(s)) => Ok(Dimension::Dynamic(s.clone())),
+            None => Err(ModelLoadError::NoValueShape),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(TypedShape::new(
+        dims.into(),
+        DataType::from_i32(tensor.elem_type()).unwrap().try_into()?,
+    ))
+}
+
+/// Returns the value named `name`, creating it if it's not found.
+/// In a subgraph, such a value refers to an outer scope.
+fn lookup_value(
+    model: &mut Model,
+    name_to_val: &mut FxHashMap<String, ValueId>,
+    name: &str,
+    is_subgraph: bool,
+) -> ValueId {
+    if let Some(&id) = name_to_val.get(name) {
+        return id;
+    }
+    let id = model.graph.values.new_val_named(name);
+    // Omitted optional values are named "".
+    if is_subgraph && !name.is_empty() {
+        model.graph.outer_inputs.push(id);
+    }
+    name_to_val.insert(name.to_string(), id);
+    id
+}
+
+/// Reads `reduction` of `ScatterElements` and `ScatterND`.
+fn get_scatter_reduction(attrs: &[AttributeProto]) -> Result<ScatterReduction, ModelLoadError> {
+    match get_attribute(attrs, "reduction").map_or(&b"none"[..], |a| a.s()) {
+        b"none" => Ok(ScatterReduction::None),
+        b"add" => Ok(ScatterReduction::Add),
+        b"mul" => Ok(ScatterReduction::Mul),
+        b"max" => Ok(ScatterReduction::Max),
+        b"min" => Ok(ScatterReduction::Min),
+        r => Err(ModelLoadError::Todo(
+            format!("Unknown reduction: {}", String::from_utf8_lossy(r)).into(),
+        )),
+    }
+}
+
+/// Reads the attributes shared by `RNN`, `GRU` and `LSTM`.
+/// `default_activations` are used for each direction unless `activations` is given.
+fn get_rnn(attrs: &[AttributeProto], default_activations: &[&str]) -> Result<RNN, ModelLoadError> {
+    let direction = match get_attribute(attrs, "direction").map_or(&b"forward"[..], |a| a.s()) {
+        b"forward" => RnnDirection::Forward,
+        b"reverse" => RnnDirection::Reverse,
+        b"bidirectional" => RnnDirection::Bidirectional,
+        d => {
+            return Err(ModelLoadError::Todo(
+                format!("Unknown direction: {}", String::from_utf8_lossy(d)).into(),
+            ))
+        }
+    };
+    let mut activations = get_attribute(attrs, "activations").map_or_else(
+        |_| Vec::new(),
+        |a| {
+            a.strings
+                .iter()
+                .map(|s| String::from_utf8_lossy(s).into_owned())
+                .collect()
+        },
+    );
+    if activations.is_empty() {
+        activations = default_activations.iter().map(|&a| a.to_string()).collect();
+    }
+    // Activations given only for one direction apply to both.
+    if activations.len() == default_activations.len() {
+        activations = vec![activations; direction.num_directions()].concat();
+    }
+    Ok(RNN {
+        activation_alpha: get_attribute(attrs, "activation_alpha")
+            .map_or_else(|_| Vec::new(), |a| a.floats.clone()),
+        activation_beta: get_attribute(attrs, "activation_beta")
+            .map_or_else(|_| Vec::new(), |a| a.floats.clone()),
+        activations,
+        clip: get_attribute(attrs, "clip").ok().map(|a| a.f()),
+        direction,
+        hidden_size: get_attribute(attrs, "hidden_size")
+            .ok()
+            .map(|a| a.i() as usize),
+        layout: get_attribute(attrs, "layout").map_or(0, |a| a.i()),
+    })
+}
+
+fn load_constant(node: &NodeProto) -> Result<Constant, ModelLoadError> {
+    let attr = node
+        .attribute
+        .first()
+        .ok_or(ModelLoadError::NoAttribute("value"))?;
+    let value = match attr.name() {
+        "value" => get_tensor(
+            attr.t
+                .as_ref()
+                .ok_or(ModelLoadError::NoAttribute("value"))?,
+        )?,
+        "value_float" => Tensor::new(vec![].into(), vec![attr.f()]),
+        "value_floats" => Tensor::new(vec![attr.floats.len()].into(), attr.floats.clone()),
+        "value_int" => Tensor::new(vec![].into(), vec![attr.i()]),
+        "value_ints" => Tensor::new(vec![attr.ints.len()].into(), attr.ints.clone()),
+        name => {
+            return Err(ModelLoadError::Todo(
+                format!("Constant.{name} is not supported for now").into(),
+            ))
+        }
+    };
+    Ok(Constant { value })
+}
+
+/// Loads a node of ONNX Runtime's contrib ops as altius nodes.
+/// Returns false if the op is not supported.
+/// <https://github.com/microsoft/onnxruntime/blob/main/docs/ContribOperators.md>
+fn load_com_microsoft_node(
+    model: &mut Model,
+    node: &NodeProto,
+    inputs: &[ValueId],
+    outputs: &[ValueId],
+) -> Result<bool, ModelLoadError> {
+    // Omitted optional inputs and outputs have empty names.
+    let has_input = |i: usize| node.input.get(i).map_or(false, |name| !name.is_empty());
+    let has_output = |i: usize| node.output.get(i).map_or(false, |name| !name.is_empty());
+    let graph = &mut model.graph;
+    let name = node.name.to_owned();
+
+    match node.op_type() {
+        "FusedMatMul" => {
+            if get_attribute(&node.attribute, "transBatchA").map_or(0, |a| a.i()) != 0
+                || get_attribute(&node.attribute, "transBatchB").map_or(0, |a| a.i()) != 0
+            {
+                return Err(ModelLoadError::Todo(
+                    "FusedMatMul: transBatchA and transBatchB are not supported".into(),
+                ));
+            }
+            graph.add_node(
+                Node::new(Op::FusedMatMul(FusedMatMul {
+                    alpha: get_attribute(&node.attribute, "alpha").map_or(1.0, |a| a.f()),
+                    trans_a: get_attribute(&node.attribute, "transA").map_or(false, |a| a.i() != 0),
+                    trans_b: get_attribute(&node.attribute, "transB").map_or(false, |a| a.i() != 0),
+                }))
+                .with_name(name)
+                .with_ins(inputs.to_vec())
+                .with_outs(outputs.to_vec()),
+            );
+        }
+        "Attention" => {
+            if (Op::ATTENTION_IN_MASK_INDEX + 1..inputs.len()).any(has_input)
+                || (1..outputs.len()).any(has_output)
+                || get_attribute(&node.attribute, "do_rotary").map_or(0, |a| a.i()) != 0
+            {
+                return Err(ModelLoadError::Todo(
+                    "Attention: Past states, attention biases and rotary embeddings are not supported"
+                        .into(),
+                ));
+            }
+            let mut inputs = inputs[..inputs.len().min(Op::ATTENTION_IN_MASK_INDEX + 1)].to_vec();
+            while inputs.len() > Op::ATTENTION_IN_BIAS + 1 && !has_input(inputs.len() - 1) {
+                inputs.pop();
+            }
+            graph.add_node(
+                Node::new(Op::Attention(Attention {
+                    num_heads: get_attribute(&node.attribute, "num_heads")?.i() as usize,
+                    qkv_hidden_sizes: get_attribute(&node.attribute, "qkv_hidden_sizes")
+                        .map_or(vec![], |a| a.ints.iter().map(|&x| x as usize).collect()),
+                    unidirectional: get_attribute(&node.attribute, "unidirectional")
+                        .map_or(false, |a| a.i() != 0),
+                    mask_filter_value: get_attribute(&node.attribute, "mask_filter_value")
+                        .map_or(-10000.0, |a| a.f()),
+                    scale: get_attribute(&node.attribute, "scale").ok().map(|a| a.f()),
+                }))
+                .with_name(name)
+                .with_ins(inputs)
+                .with_out(outputs[Op::ATTENTION_OUT]),
+            );
+        }
+        // output = LayerNormalization(input + skip + bias)
+        "SkipLayerNormalization" => {
+            if has_output(1) || has_output(2) {
+                return Err(ModelLoadError::Todo(
+                    "SkipLayerNormalization: mean and inv_std_var are not supported".into(),
+                ));
+            }
+            let sum = if has_output(3) {
+                outputs[3]
+            } else {
+                graph
+                    .values
+                    .new_val_named(format!("{}_skip_sum", node.output[0]))
+            };
+            if has_input(4) {
+                let skipped = graph
+                    .values
+                    .new_val_named(format!("{}_skipped", node.output[0]));
+                graph.add_node(
+                    Node::new(Op::Add)
+                        .with_ins(vec![inputs[0], inputs[1]])
+                        .with_out(skipped),
+                );
+                graph.add_node(
+                    Node::new(Op::Add)
+                        .with_ins(vec![skipped, inputs[4]])
+                        .with_out(sum),
+                );
+            } else {
+                graph.add_node(
+                    Node::new(Op::Add)
+                        .with_ins(vec![inputs[0], inputs[1]])
+                        .with_out(sum),
+                );
+            }
+            let mut ln_inputs = vec![sum, inputs[2]];
+            if has_input(3) {
+                ln_inputs.push(inputs[3]);
+            }
+            graph.add_node(
+                Node::new(Op::LayerNormalization(LayerNormalization {
+                    axis: -1,
+                    epsilon: get_attribute(&node.attribute, "epsilon").map_or(1e-12, |a| a.f()),
+                    stash_type: 1,
+                }))
+                .with_name(name)
+                .with_ins(ln_inputs)
+                .with_out(outputs[0]),
+            );
+        }
+        // Y = Gelu(A + B)
+        "BiasGelu" => {
+            let biased = graph
+                .values
+                .new_val_named(format!("{}_biased", node.output[0]));
+            graph.add_node(
+                Node::new(Op::Add)
+                    .with_ins(inputs.to_vec())
+                    .with_out(biased),
+            );
+            graph.add_node(
+                Node::new(Op::Gelu)
+                    .with_name(name)
+                    .with_in(biased)
+                    .with_out(outputs[0]),
+            );
+        }
+        "Gelu" => {
+            graph.add_node(
+                Node::new(Op::Gelu)
+                    .with_name(name)
+                    .with_ins(inputs.to_vec())
+                    .with_outs(outputs.to_vec()),
+            );
+        }
+        _ => return Ok(false),
+    }
+
+    Ok(true)
+}
+
+fn load_custom(node: &NodeProto) -> Result<Custom, ModelLoadError> {
+    use attribute_proto::AttributeType;
+
+    let string = |s: &[u8]| String::from_utf8_lossy(s).into_owned();
+    let mut attrs = BTreeMap::new();
+    for attr in &node.attribute {
+        let value = match attr.r#type() {
+            AttributeType::Float => CustomAttribute::Float(attr.f()),
+            AttributeType::Int => CustomAttribute::Int(attr.i()),
+            AttributeType::String => CustomAttribute::String(string(attr.s())),
+            AttributeType::Floats => CustomAttribute::Floats(attr.floats.clone()),
+            AttributeType::Ints => CustomAttribute::Ints(attr.ints.clone()),
+            AttributeType::Strings => {
+                CustomAttribute::Strings(attr.strings.iter().map(|s| string(s)).collect())
+            }
+            ty => {
+                return Err(ModelLoadError::Todo(
+                    format!(
+                        "{}.{}: Attribute '{}' of type {ty:?} is not supported",
+                        node.domain(),
+                        node.op_type(),
+                        attr.name()
+                    )
+                    .into(),
+                ))
+            }
+        };
+        attrs.insert(attr.name().to_string(), value);
+    }
+    Ok(Custom {
+        domain: node.domain().to_string(),
+        op_type: node.op_type().to_string(),
+        attrs,
+    })
+}
+
+/// Reads the attributes shared by `Conv`, `QLinearConv` and `ConvInteger`.
+fn get_conv2d(attrs: &[AttributeProto]) -> Result<Conv2d, ModelLoadError> {
+    let auto_pad = get_attribute(attrs, "auto_pad").map_or("NOTSET".to_string(), |a| {
+        unsafe { std::str::from_utf8_unchecked(a.s()) }.to_string()
+    });
+    let ints = |name| {
+        get_attribute(attrs, name).map_or(vec![].into(), |a| FixedDimensions::from_i64(&a.ints))
+    };
+    let group = get_attribute(attrs, "group").map_or(1, |a| a.i());
+    Ok(Conv2d {
+        auto_pad,
+        dilations: ints("dilations"),
+        kernel_shape: ints("kernel_shape"),
+        strides: ints("strides"),
+        group,
+        padding: ints("pads"),
+        activation: None,
+    })
+}
+
+fn get_conv_transpose(attrs: &[AttributeProto]) -> Result<ConvTranspose, ModelLoadError> {
+    let auto_pad = get_attribute(attrs, "auto_pad").map_or("NOTSET".to_string(), |a| {
+        unsafe { std::str::from_utf8_unchecked(a.s()) }.to_string()
+    });
+    let ints = |name| {
+        get_attribute(attrs, name).map_or(vec![].into(), |a| FixedDimensions::from_i64(&a.ints))
+    };
+    Ok(ConvTranspose {
+        auto_pad,
+        dilations: ints("dilations"),
+        group: get_attribute(attrs, "group").map_or(1, |a| a.i()),
+        kernel_shape: ints("kernel_shape"),
+        output_padding: ints("output_padding"),
+        output_shape: ints("output_shape"),
+        padding: ints("pads"),
+        strides: ints("strides"),
+    })
+}
+
+/// Reads the attributes shared by `MaxPool`, `AveragePool` and `LpPool`.
+fn get_pool(attrs: &[AttributeProto]) -> Result<Pool, ModelLoadError> {
+    let auto_pad = get_attribute(attrs, "auto_pad").map_or("NOTSET".to_string(), |a| {
+        unsafe { std::str::from_utf8_unchecked(a.s()) }.to_string()
+    });
+    let kernel_shape = FixedDimensions::from_i64(&get_attribute(attrs, "kernel_shape")?.ints);
+    let ints = |name| {
+        get_attribute(attrs, name).map_or(vec![].into(), |a| FixedDimensions::from_i64(&a.ints))
+    };
+    Ok(Pool {
+        auto_pad,
+        ceil_mode: get_attribute(attrs, "ceil_mode").map_or(false, |a| a.i() == 1),
+        count_include_pad: get_attribute(attrs, "count_include_pad").map_or(false, |a| a.i() == 1),
+        dilations: ints("dilations"),
+        kernel_shape,
+        p: get_attribute(attrs, "p").map_or(2, |a| a.i()),
+        padding: ints("pads"),
+        storage_order: get_attribute(attrs, "storage_order").map_or(0, |a| a.i()),
+        strides: ints("strides"),
+    })
+}
+
+/// Reads the attributes shared by the `Reduce*` ops. `axes` may instead be given as an input.
+fn get_reduce(attrs: &[AttributeProto]) -> Reduce {
+    Reduce {
+        axes: get_attribute(attrs, "axes").map_or(vec![], |a| a.ints.clone()),
+        keep_dims: get_attribute(attrs, "keepdims").map_or(true, |a| a.i() != 0),
+        noop_with_empty_axes: get_attribute(attrs, "noop_with_empty_axes")
+            .map_or(false, |a| a.i() != 0),
+    }
+}
+
+/// Reads the attributes of `ArgMax` and `ArgMin`.
+fn get_arg_reduce(attrs: &[AttributeProto]) -> ArgReduce {
+    ArgReduce {
+        axis: get_attribute(attrs, "axis").map_or(0, |a| a.i()),
+        keep_dims: get_attribute(attrs, "keepdims").map_or(true, |a| a.i() != 0),
+        select_last_index: get_attribute(attrs, "select_last_index").map_or(false, |a| a.i() != 0),
+    }
 }
 
 fn get_attribute<'a>(
@@ -414,6 +1035,52 @@ fn get_tensor(tensor: &TensorProto) -> Result<Tensor, ModelLoadError> {
             TensorElemType::Bool,
             tensor.raw_data().to_vec(),
         ),
+        // 8-bit integers are stored in `int32_data` unless `raw_data` is used.
+        DataType::Int8 if tensor.raw_data().is_empty() => Tensor::new(
+            FixedDimensions::from_i64(&tensor.dims),
+            tensor.int32_data.iter().map(|&x| x as i8).collect(),
+        ),
+        DataType::Int8 => Tensor::new_from_raw(
+            FixedDimensions::from_i64(&tensor.dims),
+            TensorElemType::I8,
+            tensor.raw_data().to_vec(),
+        ),
+        DataType::Uint8 if tensor.raw_data().is_empty() => Tensor::new(
+            FixedDimensions::from_i64(&tensor.dims),
+            tensor.int32_data.iter().map(|&x| x as u8).collect(),
+        ),
+        DataType::Uint8 => Tensor::new_from_raw(
+            FixedDimensions::from_i64(&tensor.dims),
+            TensorElemType::U8,
+            tensor.raw_data().to_vec(),
+        ),
+        // Half-precision values are stored as bit patterns in `int32_data` unless `raw_data` is used.
+        DataType::Float16 if tensor.raw_data().is_empty() => Tensor::new(
+            FixedDimensions::from_i64(&tensor.dims),
+            tensor
+                .int32_data
+                .iter()
+                .map(|&x| f16::from_bits(x as u16))
+                .collect(),
+        ),
+        DataType::Float16 => Tensor::new_from_raw(
+            FixedDimensions::from_i64(&tensor.dims),
+            TensorElemType::F16,
+            tensor.raw_data().to_vec(),
+        ),
+        DataType::Bfloat16 if tensor.raw_data().is_empty() => Tensor::new(
+            FixedDimensions::from_i64(&tensor.dims),
+            tensor
+                .int32_data
+                .iter()
+                .map(|&x| bf16::from_bits(x as u16))
+                .collect(),
+        ),
+        DataType::Bfloat16 => Tensor::new_from_raw(
+            FixedDimensions::from_i64(&tensor.dims),
+            TensorElemType::BF16,
+            tensor.raw_data().to_vec(),
+        ),
         t => {
             return Err(ModelLoadError::Todo(
                 format!("Unsupported data type for tensor: {t:?}").into(),
@@ -428,9 +1095,13 @@ impl TryFrom<DataType> for TensorElemType {
     fn try_from(ty: DataType) -> Result<Self, Self::Error> {
         match ty {
             DataType::Bool => Ok(TensorElemType::Bool),
+            DataType::Int8 => Ok(TensorElemType::I8),
+            DataType::Uint8 => Ok(TensorElemType::U8),
             DataType::Int32 => Ok(TensorElemType::I32),
             DataType::Int64 => Ok(TensorElemType::I64),
             DataType::Float => Ok(TensorElemType::F32),
+            DataType::Float16 => Ok(TensorElemType::F16),
+            DataType::Bfloat16 => Ok(TensorElemType::BF16),
             ty => Err(ModelLoadError::Todo(
                 format!("Unsupported tensor element type: {ty:?}").into(),
             )),
@@ -462,3 +1133,540 @@ fn load_mnist() {
     println!("{:#?}", model.graph.inputs);
     println!("{:#?}", model.graph.outputs);
 }
+
+#[test]
+fn load_quantized_ops() {
+    let value_info = |name: &str, elem_type: DataType, dims: &[i64]| ValueInfoProto {
+        name: Some(name.into()),
+        r#type: Some(TypeProto {
+            value: Some(TensorType(type_proto::Tensor {
+                elem_type: Some(elem_type as i32),
+                shape: Some(TensorShapeProto {
+                    dim: dims
+                        .iter()
+                        .map(|&d| tensor_shape_proto::Dimension {
+                            value: Some(DimValue(d)),
+                            ..Default::default()
+                        })
+                        .collect(),
+                }),
+            })),
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+    let node = |op_type: &str, inputs: &[&str], output: &str| NodeProto {
+        op_type: Some(op_type.into()),
+        input: inputs.iter().map(|&i| i.into()).collect(),
+        output: vec![output.into()],
+        ..Default::default()
+    };
+    let scalar = |name: &str, data_type: DataType, int32_data: Vec<i32>| TensorProto {
+        name: Some(name.into()),
+        data_type: Some(data_type as i32),
+        int32_data,
+        ..Default::default()
+    };
+
+    let mut dequantize = node("DequantizeLinear", &["w", "w_scale"], "w_f32");
+    dequantize.attribute.push(AttributeProto {
+        name: Some("axis".into()),
+        i: Some(0),
+        r#type: Some(attribute_proto::AttributeType::Int as i32),
+        ..Default::default()
+    });
+    let model_proto = ModelProto {
+        opset_import: vec![OperatorSetIdProto {
+            domain: Some("".into()),
+            version: Some(13),
+        }],
+        graph: Some(GraphProto {
+            node: vec![
+                node("QuantizeLinear", &["x", "x_scale", "x_zp"], "x_q"),
+                node("MatMulInteger", &["x_q", "w", "x_zp", "w_zp"], "y"),
+                dequantize,
+            ],
+            initializer: vec![
+                TensorProto {
+                    dims: vec![2, 2],
+                    ..scalar("w", DataType::Int8, vec![-128, 127, 0, 3])
+                },
+                TensorProto {
+                    float_data: vec![0.5, 0.25],
+                    dims: vec![2],
+                    ..scalar("w_scale", DataType::Float, vec![])
+                },
+                TensorProto {
+                    float_data: vec![0.1],
+                    ..scalar("x_scale", DataType::Float, vec![])
+                },
+                scalar("x_zp", DataType::Uint8, vec![128]),
+                scalar("w_zp", DataType::Int8, vec![0]),
+            ],
+            input: vec![value_info("x", DataType::Float, &[1, 2])],
+            output: vec![
+                value_info("y", DataType::Int32, &[1, 2]),
+                value_info("w_f32", DataType::Float, &[2, 2]),
+            ],
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+
+    let model = load_onnx_from_model_proto(model_proto).unwrap();
+    let ops = model
+        .graph
+        .nodes
+        .iter()
+        .map(|(_, n)| n.op.clone())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        ops,
+        vec![
+            Op::QuantizeLinear(QuantizeLinear { axis: 1 }),
+            Op::MatMulInteger,
+            Op::DequantizeLinear(DequantizeLinear { axis: 0 }),
+        ]
+    );
+    let w = model.lookup_named_value("w").unwrap();
+    assert_eq!(model.graph.inits[&w].data::<i8>(), &[-128, 127, 0, 3]);
+    let x_zp = model.lookup_named_value("x_zp").unwrap();
+    assert_eq!(model.graph.inits[&x_zp].data::<u8>(), &[128]);
+    assert!(model.verify().is_ok());
+}
+
+#[test]
+fn load_com_microsoft_ops() {
+    let value_info = |name: &str, dims: &[i64]| ValueInfoProto {
+        name: Some(name.into()),
+        r#type: Some(TypeProto {
+            value: Some(TensorType(type_proto::Tensor {
+                elem_type: Some(DataType::Float as i32),
+                shape: Some(TensorShapeProto {
+                    dim: dims
+                        .iter()
+                        .map(|&d| tensor_shape_proto::Dimension {
+                            value: Some(DimValue(d)),
+                            ..Default::default()
+                        })
+                        .collect(),
+                }),
+            })),
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+    let node = |domain: &str, op_type: &str, inputs: &[&str], output: &str| NodeProto {
+        domain: Some(domain.into()),
+        op_type: Some(op_type.into()),
+        input: inputs.iter().map(|&i| i.into()).collect(),
+        output: vec![output.into()],
+        ..Default::default()
+    };
+    let attr = |name: &str, i: i64| AttributeProto {
+        name: Some(name.into()),
+        i: Some(i),
+        r#type: Some(attribute_proto::AttributeType::Int as i32),
+        ..Default::default()
+    };
+    let tensor = |name: &str, dims: Vec<i64>| TensorProto {
+        name: Some(name.into()),
+        data_type: Some(DataType::Float as i32),
+        float_data: vec![0.5; dims.iter().product::<i64>() as usize],
+        dims,
+        ..Default::default()
+    };
+
+    let mut fused_mat_mul = node("com.microsoft", "FusedMatMul", &["x", "w"], "a");
+    fused_mat_mul.attribute.push(attr("transB", 1));
+    fused_mat_mul.attribute.push(AttributeProto {
+        name: Some("alpha".into()),
+        f: Some(0.5),
+        r#type: Some(attribute_proto::AttributeType::Float as i32),
+        ..Default::default()
+    });
+    let mut attention = node(
+        "com.microsoft",
+        "Attention",
+        &["c", "qkv_w", "qkv_b", ""],
+        "y",
+    );
+    attention.attribute.push(attr("num_heads", 2));
+    let model_proto = |custom: NodeProto| ModelProto {
+        opset_import: vec![
+            OperatorSetIdProto {
+                domain: Some("".into()),
+                version: Some(17),
+            },
+            OperatorSetIdProto {
+                domain: Some("com.microsoft".into()),
+                version: Some(1),
+            },
+            OperatorSetIdProto {
+                domain: Some("com.example".into()),
+                version: Some(3),
+            },
+        ],
+        graph: Some(GraphProto {
+            node: vec![
+                fused_mat_mul.clone(),
+                node(
+                    "com.microsoft",
+                    "SkipLayerNormalization",
+                    &["a", "x", "gamma", "beta"],
+                    "b",
+                ),
+                node("com.microsoft", "BiasGelu", &["b", "beta"], "c"),
+                attention.clone(),
+                custom,
+            ],
+            initializer: vec![
+                tensor("w", vec![4, 4]),
+                tensor("gamma", vec![4]),
+                tensor("beta", vec![4]),
+                tensor("qkv_w", vec![4, 12]),
+                tensor("qkv_b", vec![12]),
+            ],
+            input: vec![value_info("x", &[1, 3, 4])],
+            output: vec![value_info("z", &[1, 3, 4])],
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+    let custom = node("com.example", "MyRelu", &["y"], "z");
+
+    let model = load_onnx_from_model_proto(model_proto(custom.clone())).unwrap();
+    let (_, last) = model.graph.nodes.iter().last().unwrap();
+    assert_eq!(
+        last.op,
+        Op::Custom(Custom {
+            domain: "com.example".into(),
+            op_type: "MyRelu".into(),
+            attrs: BTreeMap::new(),
+        })
+    );
+
+    let model = OnnxLoader::new()
+        .with_domain_handler("com.example", |node, version| {
+            assert_eq!(version, 3);
+            match node.op_type() {
+                "MyRelu" => Ok(Op::ReLU),
+                op => Err(ModelLoadError::Todo(format!("Unknown op: {op}").into())),
+            }
+        })
+        .load_from_model_proto(model_proto(custom))
+        .unwrap();
+    assert_eq!(model.opset_version, 17);
+    assert_eq!(model.domain_opset_versions["com.microsoft"], 1);
+    assert_eq!(model.domain_opset_versions["com.example"], 3);
+
+    let ops = model
+        .graph
+        .nodes
+        .iter()
+        .map(|(_, n)| n.op.clone())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        ops,
+        vec![
+            Op::FusedMatMul(FusedMatMul {
+                alpha: 0.5,
+                trans_a: false,
+                trans_b: true,
+            }),
+            Op::Add,
+            Op::LayerNormalization(LayerNormalization {
+                axis: -1,
+                epsilon: 1e-12,
+                stash_type: 1,
+            }),
+            Op::Add,
+            Op::Gelu,
+            Op::Attention(Attention {
+                num_heads: 2,
+                qkv_hidden_sizes: vec![],
+                unidirectional: false,
+                mask_filter_value: -10000.0,
+                scale: None,
+            }),
+            Op::ReLU,
+        ]
+    );
+    // The omitted mask index is dropped.
+    let (_, attention) = model
+        .graph
+        .nodes
+        .iter()
+        .find(|(_, n)| matches!(n.op, Op::Attention(_)))
+        .unwrap();
+    assert_eq!(attention.inputs.len(), 3);
+    assert!(model.verify().is_ok());
+}
+
+#[test]
+fn load_nested_subgraphs() {
+    let value_info = |name: &str| ValueInfoProto {
+        name: Some(name.into()),
+        r#type: Some(TypeProto {
+            value: Some(TensorType(type_proto::Tensor {
+                elem_type: Some(DataType::Float as i32),
+                shape: Some(TensorShapeProto {
+                    dim: vec![tensor_shape_proto::Dimension {
+                        value: Some(DimValue(1)),
+                        ..Default::default()
+                    }],
+                }),
+            })),
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+    let node = |op_type: &str, inputs: &[&str], output: &str| NodeProto {
+        op_type: Some(op_type.into()),
+        input: inputs.iter().map(|&i| i.into()).collect(),
+        output: vec![output.into()],
+        ..Default::default()
+    };
+    let graph_attr = |name: &str, g: GraphProto| AttributeProto {
+        name: Some(name.into()),
+        g: Some(g),
+        r#type: Some(attribute_proto::AttributeType::Graph as i32),
+        ..Default::default()
+    };
+    let branch = |op_type: &str| GraphProto {
+        node: vec![node(op_type, &["v", "x"], "w")],
+        // Types of subgraph outputs may be omitted.
+        output: vec![ValueInfoProto {
+            name: Some("w".into()),
+            ..Default::default()
+        }],
+        ..Default::default()
+    };
+
+    // The branches refer to `v` of the loop body and `x` of the main graph.
+    let mut if_node = node("If", &["c"], "w");
+    if_node
+        .attribute
+        .push(graph_attr("then_branch", branch("Add")));
+    if_node
+        .attribute
+        .push(graph_attr("else_branch", branch("Sub")));
+    let body = GraphProto {
+        node: vec![if_node],
+        input: ["i", "c", "v"]
+            .map(|name| ValueInfoProto {
+                name: Some(name.into()),
+                ..Default::default()
+            })
+            .to_vec(),
+        output: ["c", "w"]
+            .map(|name| ValueInfoProto {
+                name: Some(name.into()),
+                ..Default::default()
+            })
+            .to_vec(),
+        ..Default::default()
+    };
+    let mut loop_node = node("Loop", &["", "", "x"], "y");
+    loop_node.attribute.push(graph_attr("body", body));
+    let model_proto = ModelProto {
+        opset_import: vec![OperatorSetIdProto {
+            domain: Some("".into()),
+            version: Some(16),
+        }],
+        graph: Some(GraphProto {
+            node: vec![loop_node],
+            input: vec![value_info("x")],
+            output: vec![value_info("y")],
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+
+    let model = load_onnx_from_model_proto(model_proto).unwrap();
+    assert!(model.verify().is_ok());
+    let x = model.lookup_named_value("x").unwrap();
+    let (_, loop_node) = model.graph.nodes.iter().next().unwrap();
+    let Op::Loop(Loop { body }) = &loop_node.op else {
+        panic!("Expected Loop, got {:?}", loop_node.op);
+    };
+    // M, cond, v_initial and the captured `x`.
+    assert_eq!(loop_node.inputs.len(), 4);
+    assert_eq!(loop_node.inputs[3], x);
+
+    let name = |g: &Graph, id: ValueId| g.values[id].name.clone().unwrap();
+    assert_eq!(body.outer_inputs.len(), 1);
+    assert_eq!(name(body, body.outer_inputs[0]), "x");
+    let (_, if_node) = body.nodes.iter().next().unwrap();
+    let Op::If(If {
+        then_branch,
+        else_branch,
+    }) = &if_node.op
+    else {
+        panic!("Expected If, got {:?}", if_node.op);
+    };
+    for branch in [then_branch, else_branch] {
+        let names = branch
+            .outer_inputs
+            .iter()
+            .map(|&id| name(branch, id))
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["v", "x"]);
+    }
+    // cond, then's `v` and `x`, and else's `v` and `x`.
+    let names = if_node
+        .inputs
+        .iter()
+        .map(|&id| name(body, id))
+        .collect::<Vec<_>>();
+    assert_eq!(names, ["c", "v", "x", "v", "x"]);
+}
+
+#[test]
+fn load_legacy_clip_and_constant_values() {
+    let float_attr = |name: &str, f: f32| AttributeProto {
+        name: Some(name.into()),
+        f: Some(f),
+        r#type: Some(attribute_proto::AttributeType::Float as i32),
+        ..Default::default()
+    };
+    let model_proto = ModelProto {
+        opset_import: vec![OperatorSetIdProto {
+            domain: Some("".into()),
+            version: Some(10),
+        }],
+        graph: Some(GraphProto {
+            node: vec![
+                NodeProto {
+                    op_type: Some("Clip".into()),
+                    input: vec!["x".into()],
+                    output: vec!["y".into()],
+                    attribute: vec![float_attr("min", -1.), float_attr("max", 1.)],
+                    ..Default::default()
+                },
+                NodeProto {
+                    op_type: Some("Constant".into()),
+                    output: vec!["c".into()],
+                    attribute: vec![AttributeProto {
+                        name: Some("value_ints".into()),
+                        ints: vec![1, 2, 3],
+                        r#type: Some(attribute_proto::AttributeType::Ints as i32),
+                        ..Default::default()
+                    }],
+                    ..Default::default()
+                },
+            ],
+            input: vec![ValueInfoProto {
+                name: Some("x".into()),
+                r#type: Some(TypeProto {
+                    value: Some(TensorType(type_proto::Tensor {
+                        elem_type: Some(DataType::Float as i32),
+                        shape: Some(TensorShapeProto { dim: vec![] }),
+                    })),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }],
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+
+    let model = load_onnx_from_model_proto(model_proto.clone()).unwrap();
+    let mut nodes = model.graph.nodes.iter().map(|(_, n)| n);
+    let clip = nodes.next().unwrap();
+    assert_eq!(clip.op, Op::Clip);
+    // The bounds are given as initializers following the input.
+    let bounds = clip.inputs[Op::CLIP_IN_MIN..]
+        .iter()
+        .map(|id| model.graph.inits[id].data::<f32>()[0])
+        .collect::<Vec<_>>();
+    assert_eq!(bounds, vec![-1., 1.]);
+
+    let Op::Constant(ref constant) = nodes.next().unwrap().op else {
+        panic!("Constant is expected");
+    };
+    assert_eq!(constant.value.dims().as_slice(), &[3]);
+    assert_eq!(constant.value.data::<i64>(), &[1, 2, 3]);
+
+    // `value` without a tensor is malformed.
+    let mut model_proto = model_proto;
+    model_proto.graph.as_mut().unwrap().node[1].attribute = vec![AttributeProto {
+        name: Some("value".into()),
+        r#type: Some(attribute_proto::AttributeType::Tensor as i32),
+        ..Default::default()
+    }];
+    assert!(matches!(
+        load_onnx_from_model_proto(model_proto),
+        Err(ModelLoadError::NoAttribute("value"))
+    ));
+}
+
+#[test]
+fn load_infers_symbolic_shapes() {
+    let dim = |d: &str| tensor_shape_proto::Dimension {
+        value: Some(match d.parse() {
+            Ok(d) => DimValue(d),
+            Err(_) => DimParam(d.into()),
+        }),
+        ..Default::default()
+    };
+    let value_info = |name: &str, dims: &[&str]| ValueInfoProto {
+        name: Some(name.into()),
+        r#type: Some(TypeProto {
+            value: Some(TensorType(type_proto::Tensor {
+                elem_type: Some(DataType::Float as i32),
+                shape: Some(TensorShapeProto {
+                    dim: dims.iter().map(|d| dim(d)).collect(),
+                }),
+            })),
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+    let model_proto = ModelProto {
+        opset_import: vec![OperatorSetIdProto {
+            domain: Some("".into()),
+            version: Some(13),
+        }],
+        graph: Some(GraphProto {
+            node: vec![
+                NodeProto {
+                    op_type: Some("Concat".into()),
+                    input: vec!["x".into(), "x".into()],
+                    output: vec!["c".into()],
+                    attribute: vec![AttributeProto {
+                        name: Some("axis".into()),
+                        i: Some(1),
+                        r#type: Some(attribute_proto::AttributeType::Int as i32),
+                        ..Default::default()
+                    }],
+                    ..Default::default()
+                },
+                NodeProto {
+                    op_type: Some("Relu".into()),
+                    input: vec!["c".into()],
+                    output: vec!["y".into()],
+                    ..Default::default()
+                },
+            ],
+            input: vec![value_info("x", &["batch", "seq", "8"])],
+            output: vec![value_info("y", &["batch", "seq*2", "8"])],
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+
+    let model = load_onnx_from_model_proto(model_proto).unwrap();
+    let expected = TypedShape::new(
+        vec![
+            Dimension::Dynamic("batch".into()),
+            Dimension::Dynamic("seq*2".into()),
+            Dimension::Fixed(8),
+        ]
+        .into(),
+        TensorElemType::F32,
+    );
+    let c = model.lookup_named_value("c").unwrap();
+    assert_eq!(model.graph.values[c].shape, Some(expected));
+}
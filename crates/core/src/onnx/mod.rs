@@ -1,3 +1,3 @@
 pub mod load;
 pub mod save;
-pub use load::{load_onnx, load_onnx_from_buffer};
+pub use load::{load_onnx, load_onnx_from_buffer, OnnxLoader};
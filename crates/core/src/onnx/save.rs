@@ -1,13 +1,19 @@
 use std::{fs, path::Path};
 
 use prost::Message;
+use rustc_hash::FxHashMap;
 use thiserror::Error;
 
 use crate::{
     dim::Dimension as Dim,
+    graph::Graph,
     model::Model,
-    op::Op,
+    op::{
+        CustomAttribute, DequantizeLinear, Elu, LeakyReLU, Op, QuantizeLinear, ScatterReduction,
+        Selu, RNN,
+    },
     tensor::{TensorElemType, TypedShape},
+    value::ValueId,
 };
 
 include!(concat!(env!("OUT_DIR"), "/onnx.rs"));
@@ -64,11 +70,23 @@ pub fn save_onnx(model: &Model, path: impl AsRef<Path>) -> Result<(), ModelSaveE
     let mut model_proto = ModelProto::default();
     let mut buf = Vec::new();
 
-    model_proto.graph = encode_graph(model)?.into();
+    model_proto.graph = encode_graph(model, "", &FxHashMap::default())?.into();
     model_proto.opset_import.push(OperatorSetIdProto {
         domain: Some("ai.onnx".to_string()),
         version: Some(model.opset_version),
     });
+    let mut domain_opset_versions = model.domain_opset_versions.clone();
+    for (_, node) in model.graph.nodes.iter().filter(|(_, n)| !n.deleted) {
+        if let Some(domain) = op_domain(&node.op) {
+            domain_opset_versions.entry(domain.to_string()).or_insert(1);
+        }
+    }
+    for (domain, version) in domain_opset_versions {
+        model_proto.opset_import.push(OperatorSetIdProto {
+            domain: Some(domain),
+            version: Some(version),
+        });
+    }
     model_proto.ir_version = Some(opset_to_ir_version(model.opset_version)?);
     model_proto.encode(&mut buf).unwrap();
 
@@ -77,8 +95,24 @@ pub fn save_onnx(model: &Model, path: impl AsRef<Path>) -> Result<(), ModelSaveE
     Ok(())
 }
 
-fn encode_graph(model: &Model) -> Result<GraphProto, ModelSaveError> {
+/// Encodes `model.graph`. Subgraphs are encoded with a non-empty `scope` prefixed to the names of
+/// unnamed values, and `outer_names` to refer to outer-scope values by their names in outer graphs.
+fn encode_graph(
+    model: &Model,
+    scope: &str,
+    outer_names: &FxHashMap<ValueId, String>,
+) -> Result<GraphProto, ModelSaveError> {
     let mut graph_proto = GraphProto::default();
+    let is_subgraph = !scope.is_empty();
+    let name_of = |id: ValueId| -> String {
+        if let Some(name) = outer_names.get(&id) {
+            return name.clone();
+        }
+        model.graph.values[id]
+            .name
+            .clone()
+            .unwrap_or_else(|| format!("{scope}value.{}", id.index()))
+    };
 
     // Encode graph initializers.
     for (&id, tensor) in &model.graph.inits {
@@ -90,8 +124,13 @@ fn encode_graph(model: &Model) -> Result<GraphProto, ModelSaveError> {
         let name = val
             .name
             .clone()
-            .unwrap_or_else(|| format!("initializer.{}", id.index()));
-        assert_eq!(elem_ty, DataType::Float, "Only float is supported for now");
+            .unwrap_or_else(|| format!("{scope}initializer.{}", id.index()));
+        let (float_data, raw_data) = if tensor.elem_ty().is_f32() {
+            (tensor.data::<f32>().to_vec(), None)
+        } else {
+            // Little-endian raw bytes keep half-precision weights in 2 bytes per element.
+            (vec![], Some(tensor.data_as_bytes().to_vec()))
+        };
 
         graph_proto.initializer.push(TensorProto {
             name: Some(name),
@@ -103,7 +142,8 @@ fn encode_graph(model: &Model) -> Result<GraphProto, ModelSaveError> {
                     Dim::Dynamic(_d) => todo!(),
                 })
                 .collect::<Vec<_>>(),
-            float_data: tensor.data::<f32>().iter().copied().collect::<Vec<_>>(),
+            float_data,
+            raw_data,
             ..Default::default()
         });
     }
@@ -116,6 +156,14 @@ fn encode_graph(model: &Model) -> Result<GraphProto, ModelSaveError> {
         for &id in vals {
             let val = &model.graph.values.inner()[id];
             let Some(TypedShape { dims, elem_ty }) = &val.shape else {
+                // Types of values in subgraphs may be unknown.
+                if is_subgraph {
+                    proto.push(ValueInfoProto {
+                        name: Some(name_of(id)),
+                        ..Default::default()
+                    });
+                    continue;
+                }
                 return Err(ModelSaveError::NoGraphInputShape);
             };
             let elem_ty: DataType = (*elem_ty).into();
@@ -140,40 +188,369 @@ fn encode_graph(model: &Model) -> Result<GraphProto, ModelSaveError> {
             };
 
             proto.push(ValueInfoProto {
-                name: val.name.clone(),
+                name: Some(name_of(id)),
                 r#type: ty.into(),
                 doc_string: "".to_string().into(),
             });
         }
     }
 
+    fn int(name: &str, i: i64) -> AttributeProto {
+        AttributeProto {
+            name: name.to_string().into(),
+            i: Some(i),
+            r#type: Some(AttributeType::Int as i32),
+            ..Default::default()
+        }
+    }
+
+    fn float(name: &str, f: f32) -> AttributeProto {
+        AttributeProto {
+            name: name.to_string().into(),
+            f: Some(f),
+            r#type: Some(AttributeType::Float as i32),
+            ..Default::default()
+        }
+    }
+
+    fn string(name: &str, s: &str) -> AttributeProto {
+        AttributeProto {
+            name: name.to_string().into(),
+            s: s.as_bytes().to_vec().into(),
+            r#type: Some(AttributeType::String as i32),
+            ..Default::default()
+        }
+    }
+
+    fn ints(name: &str, ints: &[usize]) -> AttributeProto {
+        AttributeProto {
+            name: name.to_string().into(),
+            ints: ints.iter().map(|x| *x as i64).collect::<Vec<_>>(),
+            r#type: Some(AttributeType::Ints as i32),
+            ..Default::default()
+        }
+    }
+
+    // Attributes shared by RNN, GRU and LSTM.
+    fn rnn_attrs(rnn: &RNN) -> Vec<AttributeProto> {
+        let mut attrs = vec![];
+        if !rnn.activation_alpha.is_empty() {
+            attrs.push(AttributeProto {
+                name: "activation_alpha".to_string().into(),
+                floats: rnn.activation_alpha.clone(),
+                r#type: Some(AttributeType::Floats as i32),
+                ..Default::default()
+            });
+        }
+        if !rnn.activation_beta.is_empty() {
+            attrs.push(AttributeProto {
+                name: "activation_beta".to_string().into(),
+                floats: rnn.activation_beta.clone(),
+                r#type: Some(AttributeType::Floats as i32),
+                ..Default::default()
+            });
+        }
+        attrs.push(AttributeProto {
+            name: "activations".to_string().into(),
+            strings: rnn
+                .activations
+                .iter()
+                .map(|a| a.clone().into_bytes())
+                .collect(),
+            r#type: Some(AttributeType::Strings as i32),
+            ..Default::default()
+        });
+        if let Some(clip) = rnn.clip {
+            attrs.push(float("clip", clip));
+        }
+        attrs.push(AttributeProto {
+            name: "direction".to_string().into(),
+            s: rnn.direction.as_str().to_string().into_bytes().into(),
+            r#type: Some(AttributeType::String as i32),
+            ..Default::default()
+        });
+        if let Some(hidden_size) = rnn.hidden_size {
+            attrs.push(int("hidden_size", hidden_size as i64));
+        }
+        attrs.push(int("layout", rnn.layout));
+        attrs
+    }
+
     // TODO: We need to cover all ops.
     fn attrs(op: &Op) -> Vec<AttributeProto> {
         let mut attrs = vec![];
         match op {
             Op::Conv2d(c) => {
-                attrs.push(AttributeProto {
-                    name: "auto_pad".to_string().into(),
-                    s: "SAME_UPPER".to_string().into_bytes().into(),
-                    r#type: Some(AttributeType::String as i32),
-                    ..Default::default()
-                });
-                attrs.push(AttributeProto {
-                    name: "kernel_shape".to_string().into(),
-                    ints: c.kernel_shape.iter().map(|x| *x as i64).collect::<Vec<_>>(),
-                    r#type: Some(AttributeType::Ints as i32),
-                    ..Default::default()
-                });
+                if !c.auto_pad.is_empty() && c.auto_pad != "NOTSET" {
+                    attrs.push(AttributeProto {
+                        name: "auto_pad".to_string().into(),
+                        s: c.auto_pad.clone().into_bytes().into(),
+                        r#type: Some(AttributeType::String as i32),
+                        ..Default::default()
+                    });
+                } else if !c.padding.is_empty() {
+                    // Pads of only one side (as in `[h, w]`) apply to both sides.
+                    let pads = if c.padding.len() == c.kernel_shape.len() {
+                        [&c.padding[..], &c.padding[..]].concat()
+                    } else {
+                        c.padding.to_vec()
+                    };
+                    attrs.push(ints("pads", &pads));
+                }
+                attrs.push(ints("kernel_shape", &c.kernel_shape));
+                if !c.strides.is_empty() {
+                    attrs.push(ints("strides", &c.strides));
+                }
+                if !c.dilations.is_empty() {
+                    attrs.push(ints("dilations", &c.dilations));
+                }
+                attrs.push(int("group", c.group));
+            }
+            Op::ConvTranspose(c) => {
+                if !c.auto_pad.is_empty() && c.auto_pad != "NOTSET" {
+                    attrs.push(AttributeProto {
+                        name: "auto_pad".to_string().into(),
+                        s: c.auto_pad.clone().into_bytes().into(),
+                        r#type: Some(AttributeType::String as i32),
+                        ..Default::default()
+                    });
+                } else if !c.padding.is_empty() {
+                    attrs.push(ints("pads", &c.padding));
+                }
+                for (name, value) in [
+                    ("kernel_shape", &c.kernel_shape),
+                    ("strides", &c.strides),
+                    ("dilations", &c.dilations),
+                    ("output_padding", &c.output_padding),
+                    ("output_shape", &c.output_shape),
+                ] {
+                    if !value.is_empty() {
+                        attrs.push(ints(name, value));
+                    }
+                }
+                attrs.push(int("group", c.group));
+            }
+            Op::MaxPool(pool) | Op::AveragePool(pool) | Op::LpPool(pool) => {
+                if !pool.auto_pad.is_empty() && pool.auto_pad != "NOTSET" {
+                    attrs.push(AttributeProto {
+                        name: "auto_pad".to_string().into(),
+                        s: pool.auto_pad.clone().into_bytes().into(),
+                        r#type: Some(AttributeType::String as i32),
+                        ..Default::default()
+                    });
+                } else if !pool.padding.is_empty() {
+                    attrs.push(ints("pads", &pool.padding));
+                }
+                attrs.push(ints("kernel_shape", &pool.kernel_shape));
+                if !pool.strides.is_empty() {
+                    attrs.push(ints("strides", &pool.strides));
+                }
+                if !pool.dilations.is_empty() {
+                    attrs.push(ints("dilations", &pool.dilations));
+                }
+                if pool.ceil_mode {
+                    attrs.push(int("ceil_mode", 1));
+                }
+                match op {
+                    Op::MaxPool(_) => attrs.push(int("storage_order", pool.storage_order)),
+                    Op::AveragePool(_) => {
+                        attrs.push(int("count_include_pad", pool.count_include_pad as i64))
+                    }
+                    _ => attrs.push(int("p", pool.p)),
+                }
+            }
+            Op::ReduceMin(r)
+            | Op::ReduceMax(r)
+            | Op::ReduceMean(r)
+            | Op::ReduceSum(r)
+            | Op::ReduceProd(r)
+            | Op::ReduceL1(r)
+            | Op::ReduceL2(r)
+            | Op::ReduceLogSum(r)
+            | Op::ReduceLogSumExp(r)
+            | Op::ReduceSumSquare(r) => {
+                if !r.axes.is_empty() {
+                    attrs.push(AttributeProto {
+                        name: "axes".to_string().into(),
+                        ints: r.axes.clone(),
+                        r#type: Some(AttributeType::Ints as i32),
+                        ..Default::default()
+                    });
+                }
+                attrs.push(int("keepdims", r.keep_dims as i64));
+                if r.noop_with_empty_axes {
+                    attrs.push(int("noop_with_empty_axes", 1));
+                }
+            }
+            Op::ArgMax(a) | Op::ArgMin(a) => {
+                attrs.push(int("axis", a.axis));
+                attrs.push(int("keepdims", a.keep_dims as i64));
+                attrs.push(int("select_last_index", a.select_last_index as i64));
+            }
+            Op::Gather(g) => attrs.push(int("axis", g.axis)),
+            Op::GatherElements(g) => attrs.push(int("axis", g.axis)),
+            Op::GatherND(g) => attrs.push(int("batch_dims", g.batch_dims)),
+            Op::ScatterElements(s) => {
+                attrs.push(int("axis", s.axis));
+                if s.reduction != ScatterReduction::None {
+                    attrs.push(string("reduction", s.reduction.as_str()));
+                }
+            }
+            Op::ScatterND(s) if s.reduction != ScatterReduction::None => {
+                attrs.push(string("reduction", s.reduction.as_str()))
+            }
+            Op::TopK(t) => {
+                attrs.push(int("axis", t.axis));
+                attrs.push(int("largest", t.largest as i64));
+                attrs.push(int("sorted", t.sorted as i64));
+            }
+            Op::OneHot(o) => attrs.push(int("axis", o.axis)),
+            Op::CumSum(c) => {
+                attrs.push(int("exclusive", c.exclusive as i64));
+                attrs.push(int("reverse", c.reverse as i64));
+            }
+            Op::Gemm(g) => {
+                attrs.push(float("alpha", g.alpha));
+                attrs.push(float("beta", g.beta));
+                attrs.push(int("transA", g.trans_a as i64));
+                attrs.push(int("transB", g.trans_b as i64));
+            }
+            Op::LeakyReLU(LeakyReLU { alpha }) => attrs.push(float("alpha", *alpha)),
+            Op::Elu(Elu { alpha }) => attrs.push(float("alpha", *alpha)),
+            Op::Selu(Selu { alpha, gamma }) => {
+                attrs.push(float("alpha", *alpha));
+                attrs.push(float("gamma", *gamma));
+            }
+            Op::Resize(r) => {
+                attrs.push(string(
+                    "coordinate_transformation_mode",
+                    &r.coordinate_transformation_mode,
+                ));
+                attrs.push(float("cubic_coeff_a", r.cubic_coeff_a));
+                attrs.push(int("exclude_outside", r.exclude_outside));
+                attrs.push(float("extrapolation_value", r.extrapolation_value));
+                attrs.push(string("mode", &r.mode));
+                attrs.push(string("nearest_mode", &r.nearest_mode));
+            }
+            Op::Pad(p) => {
+                attrs.push(string("mode", &p.mode));
+                // From opset version 11, pads and the constant value are given as inputs.
+                if !p.pads.is_empty() {
+                    attrs.push(AttributeProto {
+                        name: "pads".to_string().into(),
+                        ints: p.pads.clone(),
+                        r#type: Some(AttributeType::Ints as i32),
+                        ..Default::default()
+                    });
+                }
+                if p.value != 0.0 {
+                    attrs.push(float("value", p.value));
+                }
+            }
+            Op::DepthToSpace(d) => {
+                attrs.push(int("blocksize", d.blocksize));
+                attrs.push(string("mode", &d.mode));
+            }
+            Op::SpaceToDepth(s) => attrs.push(int("blocksize", s.blocksize)),
+            Op::InstanceNormalization(n) => attrs.push(float("epsilon", n.epsilon)),
+            Op::GroupNormalization(n) => {
+                attrs.push(float("epsilon", n.epsilon));
+                attrs.push(int("num_groups", n.num_groups));
+                // `stash_type` is new in opset version 21, so the default is left implicit.
+                if n.stash_type != 1 {
+                    attrs.push(int("stash_type", n.stash_type));
+                }
+            }
+            Op::LSTM(lstm) => {
+                attrs.extend(rnn_attrs(&RNN {
+                    activation_alpha: lstm.activation_alpha.clone(),
+                    activation_beta: lstm.activation_beta.clone(),
+                    activations: lstm.activations.clone(),
+                    clip: lstm.clip,
+                    direction: lstm.direction,
+                    hidden_size: lstm.hidden_size,
+                    layout: lstm.layout,
+                }));
+                attrs.push(int("input_forget", lstm.input_forget as i64));
+            }
+            Op::GRU(gru) => {
+                attrs.extend(rnn_attrs(&RNN {
+                    activation_alpha: gru.activation_alpha.clone(),
+                    activation_beta: gru.activation_beta.clone(),
+                    activations: gru.activations.clone(),
+                    clip: gru.clip,
+                    direction: gru.direction,
+                    hidden_size: gru.hidden_size,
+                    layout: gru.layout,
+                }));
+                attrs.push(int("linear_before_reset", gru.linear_before_reset as i64));
+            }
+            Op::RNN(rnn) => attrs.extend(rnn_attrs(rnn)),
+            Op::NonMaxSuppression(nms) => {
+                attrs.push(int("center_point_box", nms.center_point_box as i64))
+            }
+            Op::FusedMatMul(m) => {
+                attrs.push(float("alpha", m.alpha));
+                attrs.push(int("transA", m.trans_a as i64));
+                attrs.push(int("transB", m.trans_b as i64));
+            }
+            Op::Attention(a) => {
+                attrs.push(int("num_heads", a.num_heads as i64));
+                if !a.qkv_hidden_sizes.is_empty() {
+                    attrs.push(ints("qkv_hidden_sizes", &a.qkv_hidden_sizes));
+                }
+                attrs.push(int("unidirectional", a.unidirectional as i64));
+                attrs.push(float("mask_filter_value", a.mask_filter_value));
+                if let Some(scale) = a.scale {
+                    attrs.push(float("scale", scale));
+                }
+            }
+            Op::QuantizeLinear(QuantizeLinear { axis })
+            | Op::DequantizeLinear(DequantizeLinear { axis }) => attrs.push(int("axis", *axis)),
+            Op::Custom(c) => {
+                for (name, attr) in &c.attrs {
+                    attrs.push(match attr {
+                        CustomAttribute::Float(f) => float(name, *f),
+                        CustomAttribute::Int(i) => int(name, *i),
+                        CustomAttribute::String(s) => AttributeProto {
+                            name: name.clone().into(),
+                            s: s.clone().into_bytes().into(),
+                            r#type: Some(AttributeType::String as i32),
+                            ..Default::default()
+                        },
+                        CustomAttribute::Floats(fs) => AttributeProto {
+                            name: name.clone().into(),
+                            floats: fs.clone(),
+                            r#type: Some(AttributeType::Floats as i32),
+                            ..Default::default()
+                        },
+                        CustomAttribute::Ints(is) => AttributeProto {
+                            name: name.clone().into(),
+                            ints: is.clone(),
+                            r#type: Some(AttributeType::Ints as i32),
+                            ..Default::default()
+                        },
+                        CustomAttribute::Strings(ss) => AttributeProto {
+                            name: name.clone().into(),
+                            strings: ss.iter().map(|s| s.clone().into_bytes()).collect(),
+                            r#type: Some(AttributeType::Strings as i32),
+                            ..Default::default()
+                        },
+                    });
+                }
             }
             _ => {}
-        };
+        }
         attrs
     }
 
-    fn op_type(name: &'static str) -> &'static str {
-        match name {
-            "Conv2d" => "Conv",
-            name => name,
+    fn op_type(op: &Op) -> &str {
+        match op {
+            Op::Conv2d(_) => "Conv",
+            Op::ReLU => "Relu",
+            Op::LeakyReLU(_) => "LeakyRelu",
+            Op::Custom(c) => &c.op_type,
+            op => op.name(),
         }
     }
 
@@ -182,27 +559,48 @@ fn encode_graph(model: &Model) -> Result<GraphProto, ModelSaveError> {
         let node = &model.graph.nodes[node_id];
         let mut node_proto = NodeProto {
             name: node.name.clone(),
-            op_type: op_type(node.op.name()).to_string().into(),
+            op_type: op_type(&node.op).to_string().into(),
+            domain: op_domain(&node.op).map(Into::into),
             attribute: attrs(&node.op),
             ..Default::default()
         };
 
-        for &input_id in &node.inputs {
-            let input = &model.graph.values.inner()[input_id];
-            let name = input
-                .name
-                .clone()
-                .unwrap_or_else(|| format!("value.{}", input_id.index()));
-            node_proto.input.push(name);
+        // Outer-scope values of subgraphs are implicit inputs in ONNX.
+        let num_outer_inputs = node
+            .op
+            .subgraphs()
+            .iter()
+            .map(|g| g.outer_inputs.len())
+            .sum::<usize>();
+        let num_inputs = node.inputs.len() - num_outer_inputs;
+        let mut outer_offset = num_inputs;
+        for (name, subgraph) in subgraph_attrs(&node.op) {
+            let outer_names = subgraph
+                .outer_inputs
+                .iter()
+                .zip(&node.inputs[outer_offset..])
+                .map(|(&inner, &outer)| (inner, name_of(outer)))
+                .collect::<FxHashMap<_, _>>();
+            outer_offset += subgraph.outer_inputs.len();
+            let scope = format!("{scope}node{}.{name}.", node_id.index());
+            node_proto.attribute.push(AttributeProto {
+                name: Some(name.to_string()),
+                g: Some(encode_graph(
+                    &model.subgraph_model(subgraph),
+                    &scope,
+                    &outer_names,
+                )?),
+                r#type: Some(AttributeType::Graph as i32),
+                ..Default::default()
+            });
+        }
+
+        for &input_id in &node.inputs[..num_inputs] {
+            node_proto.input.push(name_of(input_id));
         }
 
         for &output_id in &node.outputs {
-            let output = &model.graph.values.inner()[output_id];
-            let name = output
-                .name
-                .clone()
-                .unwrap_or_else(|| format!("value.{}", output_id.index()));
-            node_proto.output.push(name);
+            node_proto.output.push(name_of(output_id));
         }
 
         graph_proto.node.push(node_proto);
@@ -211,13 +609,38 @@ fn encode_graph(model: &Model) -> Result<GraphProto, ModelSaveError> {
     Ok(graph_proto)
 }
 
+/// Returns the subgraphs of `op` with their attribute names.
+fn subgraph_attrs(op: &Op) -> Vec<(&'static str, &Graph)> {
+    match op {
+        Op::Loop(l) => vec![("body", &l.body)],
+        Op::If(i) => vec![
+            ("then_branch", &i.then_branch),
+            ("else_branch", &i.else_branch),
+        ],
+        _ => vec![],
+    }
+}
+
+/// Returns the domain of `op` if it's not the default one.
+fn op_domain(op: &Op) -> Option<&str> {
+    match op {
+        Op::FusedMatMul(_) | Op::Attention(_) => Some("com.microsoft"),
+        Op::Custom(c) if !matches!(c.domain.as_str(), "" | "ai.onnx") => Some(&c.domain),
+        _ => None,
+    }
+}
+
 impl From<TensorElemType> for DataType {
     fn from(ty: TensorElemType) -> Self {
         match ty {
+            TensorElemType::F16 => DataType::Float16,
+            TensorElemType::BF16 => DataType::Bfloat16,
             TensorElemType::F32 => DataType::Float,
             TensorElemType::I32 => DataType::Int32,
             TensorElemType::I64 => DataType::Int64,
             TensorElemType::Bool => DataType::Bool,
+            TensorElemType::I8 => DataType::Int8,
+            TensorElemType::U8 => DataType::Uint8,
         }
     }
 }
@@ -226,6 +649,54 @@ impl From<TensorElemType> for DataType {
 fn test_save_onnx() {
     use super::load::{load_onnx, load_onnx_model_proto};
     let model = load_onnx("../../models/mobilenetv3.onnx").unwrap();
-    let _ = save_onnx(&model, "/tmp/test.onnx").unwrap();
+    save_onnx(&model, "/tmp/test.onnx").unwrap();
     load_onnx_model_proto("/tmp/test.onnx").unwrap(); // TODO: Check the content.
 }
+
+#[test]
+fn save_and_load_half_precision() {
+    use super::load::load_onnx;
+    use crate::{fixed_dim::FixedDimensions, node::Node, tensor::Tensor};
+
+    for elem_ty in [TensorElemType::F16, TensorElemType::BF16] {
+        let shape = |dims: Vec<usize>| TypedShape::new(FixedDimensions::from(dims).into(), elem_ty);
+        let mut model = Model {
+            opset_version: 13,
+            ..Default::default()
+        };
+        let x = model
+            .graph
+            .values
+            .new_val_named_and_shaped("x", shape(vec![1, 2]));
+        let w = model
+            .graph
+            .values
+            .new_val_named_and_shaped("w", shape(vec![2, 2]));
+        let y = model
+            .graph
+            .values
+            .new_val_named_and_shaped("y", shape(vec![1, 2]));
+        let weight = Tensor::new(vec![2, 2].into(), vec![1.0f32, -0.5, 0.25, 3.0])
+            .cast_float(elem_ty)
+            .unwrap();
+        model.graph.inits.insert(w, weight.clone());
+        model
+            .graph
+            .add_node(Node::new(Op::MatMul).with_ins(vec![x, w]).with_out(y));
+        model.graph.inputs.push(x);
+        model.graph.outputs.push(y);
+
+        let path = std::env::temp_dir().join(format!("altius_half_{elem_ty:?}.onnx"));
+        save_onnx(&model, &path).unwrap();
+        let loaded = load_onnx(&path).unwrap();
+        let _ = fs::remove_file(&path);
+
+        let w = loaded.lookup_named_value("w").unwrap();
+        assert_eq!(loaded.graph.inits[&w], weight);
+        let x = loaded.graph.inputs[0];
+        assert_eq!(
+            loaded.graph.values[x].shape.as_ref().unwrap().elem_ty,
+            elem_ty
+        );
+    }
+}
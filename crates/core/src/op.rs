@@ -1,5 +1,8 @@
+use std::collections::BTreeMap;
+
 use crate::{
     fixed_dim::FixedDimensions,
+    graph::Graph,
     tensor::{Tensor, TensorElemType},
     value::ValueId,
 };
@@ -7,12 +10,21 @@ use crate::{
 #[derive(Debug, Clone, PartialEq)]
 pub enum Op {
     Conv2d(Conv2d),
+    ConvTranspose(ConvTranspose),
     Add,
     Sub,
     Mul,
     Div,
     Pow,
+    Equal,
+    Less,
+    LessOrEqual,
     Greater,
+    GreaterOrEqual,
+    And,
+    Or,
+    Not,
+    Xor,
     Sqrt,
     ReLU,
     Gelu,
@@ -20,42 +32,98 @@ pub enum Op {
     Sigmoid,
     Erf,
     Tanh,
+    Abs,
+    Neg,
+    Log,
+    Reciprocal,
+    Floor,
+    Ceil,
+    Sin,
+    Cos,
+    Softplus,
+    Softsign,
+    Elu(Elu),
+    Selu(Selu),
+    Mish,
+    HardSwish,
+    PRelu,
+    Sign,
     Clip,
     Where,
     Softmax(Softmax),
     Cast(Cast),
-    MaxPool(MaxPool),
+    MaxPool(Pool),
+    AveragePool(Pool),
+    LpPool(Pool),
     GlobalAveragePool,
+    GlobalMaxPool,
     Reshape,
     Flatten(Flatten),
     Resize(Resize),
+    Pad(Pad),
+    DepthToSpace(DepthToSpace),
+    SpaceToDepth(SpaceToDepth),
     Concat(Concat),
     Transpose(Transpose),
     Squeeze(Squeeze),
     Unsqueeze(Unsqueeze),
-    ReduceMin(ReduceMin),
-    ReduceMax(ReduceMax),
-    ReduceMean(ReduceMean),
+    ReduceMin(Reduce),
+    ReduceMax(Reduce),
+    ReduceMean(Reduce),
+    ReduceSum(Reduce),
+    ReduceProd(Reduce),
+    ReduceL1(Reduce),
+    ReduceL2(Reduce),
+    ReduceLogSum(Reduce),
+    ReduceLogSumExp(Reduce),
+    ReduceSumSquare(Reduce),
+    ArgMax(ArgReduce),
+    ArgMin(ArgReduce),
     Round,
     Exp,
     Expand,
     Range,
-    Loop,
+    Loop(Loop),
     Tile,
     Split(Split),
     Slice,
     Gather(Gather),
+    GatherElements(GatherElements),
+    GatherND(GatherND),
+    ScatterElements(ScatterElements),
+    ScatterND(ScatterND),
+    TopK(TopK),
+    OneHot(OneHot),
+    NonZero,
+    CumSum(CumSum),
     Shape(Shape),
-    NonMaxSuppression,
+    NonMaxSuppression(NonMaxSuppression),
+    If(If),
     MatMul,
     Gemm(Gemm),
     BatchNormalization(BatchNormalization),
     LayerNormalization(LayerNormalization),
+    InstanceNormalization(InstanceNormalization),
+    GroupNormalization(GroupNormalization),
+    LSTM(LSTM),
+    GRU(GRU),
+    RNN(RNN),
     HardSigmoid(HardSigmoid),
     Constant(Constant),
+    QuantizeLinear(QuantizeLinear),
+    DequantizeLinear(DequantizeLinear),
+    QLinearConv(Conv2d),
+    QLinearMatMul,
+    MatMulInteger,
+    ConvInteger(Conv2d),
+    FusedMatMul(FusedMatMul),     // com.microsoft
+    Attention(Attention),         // com.microsoft
+    Custom(Custom),               // Implemented by a user-registered `CustomOp`.
     FusedElemwise(FusedElemwise), // This is not part of the ONNX spec.
 }
 
+/// Attributes of `Conv`, which may have any number of spatial axes despite its name.
+/// Empty `kernel_shape`, `dilations`, `strides` and `padding` are filled in by shape inference.
 #[derive(Debug, Clone, PartialEq, Default)]
 pub struct Conv2d {
     pub auto_pad: String,
@@ -67,18 +135,39 @@ pub struct Conv2d {
     pub activation: Option<FusedActivation>, // This is not part of the ONNX spec.
 }
 
+/// Attributes of `ConvTranspose`. Shape inference fills in empty `kernel_shape`, `dilations`,
+/// `strides` and `output_padding`, and computes `padding` from `output_shape` if it is given.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ConvTranspose {
+    pub auto_pad: String,
+    pub dilations: FixedDimensions,
+    pub group: i64,
+    pub kernel_shape: FixedDimensions,
+    pub output_padding: FixedDimensions,
+    pub output_shape: FixedDimensions,
+    pub padding: FixedDimensions,
+    pub strides: FixedDimensions,
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum FusedActivation {
     Relu,
     HardSigmoid(HardSigmoid),
 }
 
+/// Attributes of `MaxPool`, `AveragePool` and `LpPool`.
+/// Empty `dilations`, `strides` and `padding` are filled in by shape inference.
 #[derive(Debug, Clone, PartialEq, Eq, Default)]
-pub struct MaxPool {
+pub struct Pool {
     pub auto_pad: String,
+    pub ceil_mode: bool,
+    pub count_include_pad: bool, // AveragePool only
+    pub dilations: FixedDimensions,
     pub kernel_shape: FixedDimensions,
-    pub strides: FixedDimensions,
+    pub p: i64, // LpPool only
     pub padding: FixedDimensions,
+    pub storage_order: i64, // MaxPool only
+    pub strides: FixedDimensions,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Default)]
@@ -92,6 +181,34 @@ pub struct LeakyReLU {
     pub alpha: f32,
 }
 
+/// <https://github.com/onnx/onnx/blob/main/docs/Operators.md#Elu>
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Elu {
+    pub alpha: f32,
+}
+
+/// <https://github.com/onnx/onnx/blob/main/docs/Operators.md#Selu>
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Selu {
+    pub alpha: f32,
+    pub gamma: f32,
+}
+
+impl Default for Elu {
+    fn default() -> Self {
+        Self { alpha: 1.0 }
+    }
+}
+
+impl Default for Selu {
+    fn default() -> Self {
+        Self {
+            alpha: 1.673_263_2,
+            gamma: 1.050_701,
+        }
+    }
+}
+
 /// <https://github.com/onnx/onnx/blob/main/docs/Operators.md#Softmax>
 #[derive(Debug, Clone, PartialEq, Default)]
 pub struct Softmax {
@@ -118,6 +235,29 @@ pub struct Resize {
     pub nearest_mode: String,
 }
 
+/// <https://github.com/onnx/onnx/blob/main/docs/Operators.md#Pad>
+/// Pads given as an input (from opset version 11) are moved here by shape inference, which
+/// also expands them to every axis as `[x1_begin, x2_begin, ..., x1_end, x2_end, ...]`.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Pad {
+    pub mode: String,
+    pub pads: Vec<i64>,
+    pub value: f32, // Until opset version 11, the constant value is given as this attribute.
+}
+
+/// <https://github.com/onnx/onnx/blob/main/docs/Operators.md#DepthToSpace>
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct DepthToSpace {
+    pub blocksize: i64,
+    pub mode: String,
+}
+
+/// <https://github.com/onnx/onnx/blob/main/docs/Operators.md#SpaceToDepth>
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct SpaceToDepth {
+    pub blocksize: i64,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Default)]
 pub struct Concat {
     pub axis: i64,
@@ -139,24 +279,22 @@ pub struct Unsqueeze {
     pub axes: Vec<i64>, // From opset version 13, this attribute is no longer used.
 }
 
+/// Attributes of the `Reduce*` ops.
+/// `axes` given as an input (from opset 18, or 13 for `ReduceSum`) are moved here, sorted
+/// and made non-negative by shape inference.
 #[derive(Debug, Clone, PartialEq, Eq, Default)]
-pub struct ReduceMin {
-    pub axes: Vec<i64>,
-    pub keep_dims: bool,
-}
-
-/// <https://github.com/onnx/onnx/blob/main/docs/Operators.md#ReduceMax>
-#[derive(Debug, Clone, PartialEq, Eq, Default)]
-pub struct ReduceMax {
+pub struct Reduce {
     pub axes: Vec<i64>,
     pub keep_dims: bool,
+    pub noop_with_empty_axes: bool,
 }
 
-/// <https://github.com/onnx/onnx/blob/main/docs/Operators.md#ReduceMean>
+/// Attributes of `ArgMax` and `ArgMin`.
 #[derive(Debug, Clone, PartialEq, Eq, Default)]
-pub struct ReduceMean {
-    pub axes: Vec<i64>,
+pub struct ArgReduce {
+    pub axis: i64,
     pub keep_dims: bool,
+    pub select_last_index: bool,
 }
 
 /// <https://github.com/onnx/onnx/blob/main/docs/Operators.md#Split>
@@ -172,6 +310,75 @@ pub struct Gather {
     pub axis: i64,
 }
 
+/// <https://github.com/onnx/onnx/blob/main/docs/Operators.md#GatherElements>
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct GatherElements {
+    pub axis: i64,
+}
+
+/// <https://github.com/onnx/onnx/blob/main/docs/Operators.md#GatherND>
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct GatherND {
+    pub batch_dims: i64,
+}
+
+/// <https://github.com/onnx/onnx/blob/main/docs/Operators.md#ScatterElements>
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ScatterElements {
+    pub axis: i64,
+    pub reduction: ScatterReduction,
+}
+
+/// <https://github.com/onnx/onnx/blob/main/docs/Operators.md#ScatterND>
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ScatterND {
+    pub reduction: ScatterReduction,
+}
+
+/// How `ScatterElements` and `ScatterND` combine updates with the existing elements.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ScatterReduction {
+    #[default]
+    None,
+    Add,
+    Mul,
+    Max,
+    Min,
+}
+
+impl ScatterReduction {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::None => "none",
+            Self::Add => "add",
+            Self::Mul => "mul",
+            Self::Max => "max",
+            Self::Min => "min",
+        }
+    }
+}
+
+/// <https://github.com/onnx/onnx/blob/main/docs/Operators.md#TopK>
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct TopK {
+    pub axis: i64,
+    pub largest: bool,
+    pub sorted: bool,
+}
+
+/// <https://github.com/onnx/onnx/blob/main/docs/Operators.md#OneHot>
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct OneHot {
+    pub axis: i64,
+}
+
+/// <https://github.com/onnx/onnx/blob/main/docs/Operators.md#CumSum>
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct CumSum {
+    pub exclusive: bool,
+    pub reverse: bool,
+}
+
 /// <https://github.com/onnx/onnx/blob/main/docs/Operators.md#Shape>
 #[derive(Debug, Clone, PartialEq, Eq, Default)]
 pub struct Shape {
@@ -179,6 +386,12 @@ pub struct Shape {
     pub start: i64,
 }
 
+/// <https://github.com/onnx/onnx/blob/main/docs/Operators.md#NonMaxSuppression>
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct NonMaxSuppression {
+    pub center_point_box: bool,
+}
+
 #[derive(Debug, Clone, PartialEq, Default)]
 pub struct Gemm {
     pub alpha: f32,
@@ -202,11 +415,151 @@ pub struct LayerNormalization {
     pub stash_type: i64,
 }
 
+/// <https://github.com/onnx/onnx/blob/main/docs/Operators.md#InstanceNormalization>
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct InstanceNormalization {
+    pub epsilon: f32,
+}
+
+/// <https://github.com/onnx/onnx/blob/main/docs/Operators.md#GroupNormalization>
+/// Scale and bias are given per group until opset version 21, and per channel after that.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct GroupNormalization {
+    pub epsilon: f32,
+    pub num_groups: i64,
+    pub stash_type: i64,
+}
+
+/// <https://github.com/onnx/onnx/blob/main/docs/Operators.md#LSTM>
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct LSTM {
+    pub activation_alpha: Vec<f32>,
+    pub activation_beta: Vec<f32>,
+    pub activations: Vec<String>, // Three for each direction.
+    pub clip: Option<f32>,
+    pub direction: RnnDirection,
+    pub hidden_size: Option<usize>,
+    pub input_forget: bool,
+    pub layout: i64,
+}
+
+/// <https://github.com/onnx/onnx/blob/main/docs/Operators.md#GRU>
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct GRU {
+    pub activation_alpha: Vec<f32>,
+    pub activation_beta: Vec<f32>,
+    pub activations: Vec<String>, // Two for each direction.
+    pub clip: Option<f32>,
+    pub direction: RnnDirection,
+    pub hidden_size: Option<usize>,
+    pub layout: i64,
+    pub linear_before_reset: bool,
+}
+
+/// <https://github.com/onnx/onnx/blob/main/docs/Operators.md#RNN>
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct RNN {
+    pub activation_alpha: Vec<f32>,
+    pub activation_beta: Vec<f32>,
+    pub activations: Vec<String>, // One for each direction.
+    pub clip: Option<f32>,
+    pub direction: RnnDirection,
+    pub hidden_size: Option<usize>,
+    pub layout: i64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RnnDirection {
+    #[default]
+    Forward,
+    Reverse,
+    Bidirectional,
+}
+
+impl RnnDirection {
+    pub fn num_directions(self) -> usize {
+        match self {
+            Self::Forward | Self::Reverse => 1,
+            Self::Bidirectional => 2,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Forward => "forward",
+            Self::Reverse => "reverse",
+            Self::Bidirectional => "bidirectional",
+        }
+    }
+}
+
 /// <https://github.com/onnx/onnx/blob/main/docs/Operators.md#Constant>
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Constant {
-    pub value: Tensor,
-    // TODO: Other attributes
+    pub value: Tensor, // `value_float(s)` and `value_int(s)` are also loaded into this.
+}
+
+/// <https://github.com/onnx/onnx/blob/main/docs/Operators.md#QuantizeLinear>
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuantizeLinear {
+    pub axis: i64, // Only used for per-axis quantization.
+}
+
+/// <https://github.com/onnx/onnx/blob/main/docs/Operators.md#DequantizeLinear>
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DequantizeLinear {
+    pub axis: i64, // Only used for per-axis dequantization.
+}
+
+/// <https://github.com/microsoft/onnxruntime/blob/main/docs/ContribOperators.md#com.microsoft.FusedMatMul>
+#[derive(Debug, Clone, PartialEq)]
+pub struct FusedMatMul {
+    pub alpha: f32,
+    pub trans_a: bool, // Transposes the last two dimensions.
+    pub trans_b: bool,
+}
+
+/// <https://github.com/microsoft/onnxruntime/blob/main/docs/ContribOperators.md#com.microsoft.Attention>
+#[derive(Debug, Clone, PartialEq)]
+pub struct Attention {
+    pub num_heads: usize,
+    pub qkv_hidden_sizes: Vec<usize>, // Empty if Q, K and V have the same hidden size.
+    pub unidirectional: bool,
+    pub mask_filter_value: f32,
+    pub scale: Option<f32>, // Defaults to 1/sqrt(head_size).
+}
+
+/// <https://github.com/onnx/onnx/blob/main/docs/Operators.md#Loop>
+#[derive(Debug, Clone, PartialEq)]
+pub struct Loop {
+    /// Takes the iteration number, the condition and the loop-carried values.
+    /// Produces the condition, the loop-carried values and the scan outputs.
+    pub body: Graph,
+}
+
+/// <https://github.com/onnx/onnx/blob/main/docs/Operators.md#If>
+#[derive(Debug, Clone, PartialEq)]
+pub struct If {
+    pub then_branch: Graph,
+    pub else_branch: Graph,
+}
+
+/// An op altius doesn't know, computed by the `CustomOp` registered for its domain and op type.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Custom {
+    pub domain: String,
+    pub op_type: String,
+    pub attrs: BTreeMap<String, CustomAttribute>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CustomAttribute {
+    Float(f32),
+    Int(i64),
+    String(String),
+    Floats(Vec<f32>),
+    Ints(Vec<i64>),
+    Strings(Vec<String>),
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -221,6 +574,11 @@ impl Op {
     pub const CONV2D_BIAS: usize = 2;
     pub const CONV2D_OUT: usize = 0;
 
+    pub const CONVTRANSPOSE_IN: usize = 0;
+    pub const CONVTRANSPOSE_WEIGHT: usize = 1;
+    pub const CONVTRANSPOSE_BIAS: usize = 2;
+    pub const CONVTRANSPOSE_OUT: usize = 0;
+
     pub const ADD_IN_A: usize = 0;
     pub const ADD_IN_B: usize = 1;
     pub const ADD_OUT: usize = 0;
@@ -247,6 +605,8 @@ impl Op {
     pub const SIGMOID_OUT: usize = 0;
 
     pub const CLIP_IN: usize = 0;
+    pub const CLIP_IN_MIN: usize = 1;
+    pub const CLIP_IN_MAX: usize = 2;
     pub const CLIP_OUT: usize = 0;
 
     pub const CAST_IN: usize = 0;
@@ -260,10 +620,20 @@ impl Op {
 
     pub const MAXPOOL_IN: usize = 0;
     pub const MAXPOOL_OUT: usize = 0;
+    pub const MAXPOOL_OUT_INDICES: usize = 1;
+
+    pub const AVERAGEPOOL_IN: usize = 0;
+    pub const AVERAGEPOOL_OUT: usize = 0;
+
+    pub const LPPOOL_IN: usize = 0;
+    pub const LPPOOL_OUT: usize = 0;
 
     pub const GLOBALAVERAGEPOOL_IN: usize = 0;
     pub const GLOBALAVERAGEPOOL_OUT: usize = 0;
 
+    pub const GLOBALMAXPOOL_IN: usize = 0;
+    pub const GLOBALMAXPOOL_OUT: usize = 0;
+
     pub const RESHAPE_IN: usize = 0;
     pub const RESHAPE_SHAPE: usize = 1;
     pub const RESHAPE_OUT: usize = 0;
@@ -277,6 +647,18 @@ impl Op {
     pub const RESIZE_IN_SIZES: usize = 3;
     pub const RESIZE_OUT: usize = 0;
 
+    pub const PAD_IN_DATA: usize = 0;
+    pub const PAD_IN_PADS: usize = 1;
+    pub const PAD_IN_CONSTANT_VALUE: usize = 2;
+    pub const PAD_IN_AXES: usize = 3;
+    pub const PAD_OUT: usize = 0;
+
+    pub const DEPTHTOSPACE_IN: usize = 0;
+    pub const DEPTHTOSPACE_OUT: usize = 0;
+
+    pub const SPACETODEPTH_IN: usize = 0;
+    pub const SPACETODEPTH_OUT: usize = 0;
+
     pub const CONCAT_IN: usize = 0; // variadic
     pub const CONCAT_OUT: usize = 0;
 
@@ -289,8 +671,12 @@ impl Op {
     pub const UNSQUEEZE_IN: usize = 0;
     pub const UNSQUEEZE_OUT: usize = 0;
 
-    pub const REDUCEMIN_IN: usize = 0;
-    pub const REDUCEMIN_OUT: usize = 0;
+    pub const REDUCE_IN: usize = 0;
+    pub const REDUCE_IN_AXES: usize = 1;
+    pub const REDUCE_OUT: usize = 0;
+
+    pub const ARGREDUCE_IN: usize = 0;
+    pub const ARGREDUCE_OUT: usize = 0;
 
     pub const TILE_IN: usize = 0;
     pub const TILE_REPEATS: usize = 1;
@@ -303,6 +689,40 @@ impl Op {
     pub const SLICE_IN_STEPS: usize = 4;
     pub const SLICE_OUT: usize = 0;
 
+    pub const GATHER_IN_DATA: usize = 0;
+    pub const GATHER_IN_INDICES: usize = 1;
+    pub const GATHER_OUT: usize = 0;
+
+    pub const GATHERELEMENTS_IN_DATA: usize = 0;
+    pub const GATHERELEMENTS_IN_INDICES: usize = 1;
+    pub const GATHERELEMENTS_OUT: usize = 0;
+
+    pub const GATHERND_IN_DATA: usize = 0;
+    pub const GATHERND_IN_INDICES: usize = 1;
+    pub const GATHERND_OUT: usize = 0;
+
+    pub const SCATTER_IN_DATA: usize = 0;
+    pub const SCATTER_IN_INDICES: usize = 1;
+    pub const SCATTER_IN_UPDATES: usize = 2;
+    pub const SCATTER_OUT: usize = 0;
+
+    pub const TOPK_IN_X: usize = 0;
+    pub const TOPK_IN_K: usize = 1;
+    pub const TOPK_OUT_VALUES: usize = 0;
+    pub const TOPK_OUT_INDICES: usize = 1;
+
+    pub const ONEHOT_IN_INDICES: usize = 0;
+    pub const ONEHOT_IN_DEPTH: usize = 1;
+    pub const ONEHOT_IN_VALUES: usize = 2;
+    pub const ONEHOT_OUT: usize = 0;
+
+    pub const NONZERO_IN: usize = 0;
+    pub const NONZERO_OUT: usize = 0;
+
+    pub const CUMSUM_IN_X: usize = 0;
+    pub const CUMSUM_IN_AXIS: usize = 1;
+    pub const CUMSUM_OUT: usize = 0;
+
     pub const NMS_IN_BOXES: usize = 0;
     pub const NMS_IN_SCORES: usize = 1;
     pub const NMS_IN_MAX_OUTPUT_BOXES_PER_CLASS: usize = 2;
@@ -310,6 +730,12 @@ impl Op {
     pub const NMS_IN_NMS_SCORE_THRESHOLD: usize = 4;
     pub const NMS_OUT: usize = 0;
 
+    pub const LOOP_IN_M: usize = 0;
+    pub const LOOP_IN_COND: usize = 1;
+    pub const LOOP_IN_V_INITIAL: usize = 2; // The first of the loop-carried values.
+
+    pub const IF_IN_COND: usize = 0;
+
     pub const MATMUL_IN_A: usize = 0;
     pub const MATMUL_IN_B: usize = 1;
     pub const MATMUL_OUT: usize = 0;
@@ -326,18 +752,100 @@ impl Op {
     pub const BATCHNORM_IN_INPUT_VAR: usize = 4;
     pub const BATCHNORM_OUT_Y: usize = 0;
 
+    // InstanceNormalization and GroupNormalization share these.
+    pub const NORM_IN_X: usize = 0;
+    pub const NORM_IN_SCALE: usize = 1;
+    pub const NORM_IN_B: usize = 2;
+    pub const NORM_OUT: usize = 0;
+
+    // LSTM and GRU share the inputs and outputs of RNN.
+    pub const RNN_IN_X: usize = 0;
+    pub const RNN_IN_W: usize = 1;
+    pub const RNN_IN_R: usize = 2;
+    pub const RNN_IN_B: usize = 3;
+    pub const RNN_IN_SEQUENCE_LENS: usize = 4;
+    pub const RNN_IN_INITIAL_H: usize = 5;
+    pub const RNN_OUT_Y: usize = 0;
+    pub const RNN_OUT_Y_H: usize = 1;
+
+    pub const LSTM_IN_INITIAL_C: usize = 6;
+    pub const LSTM_IN_P: usize = 7;
+    pub const LSTM_OUT_Y_C: usize = 2;
+
     pub const HARDSIGMOID_IN: usize = 0;
     pub const HARDSIGMOID_OUT: usize = 0;
 
+    pub const QUANTIZELINEAR_IN_X: usize = 0;
+    pub const QUANTIZELINEAR_IN_Y_SCALE: usize = 1;
+    pub const QUANTIZELINEAR_IN_Y_ZERO_POINT: usize = 2;
+    pub const QUANTIZELINEAR_OUT: usize = 0;
+
+    pub const DEQUANTIZELINEAR_IN_X: usize = 0;
+    pub const DEQUANTIZELINEAR_IN_X_SCALE: usize = 1;
+    pub const DEQUANTIZELINEAR_IN_X_ZERO_POINT: usize = 2;
+    pub const DEQUANTIZELINEAR_OUT: usize = 0;
+
+    pub const QLINEARCONV_IN_X: usize = 0;
+    pub const QLINEARCONV_IN_X_SCALE: usize = 1;
+    pub const QLINEARCONV_IN_X_ZERO_POINT: usize = 2;
+    pub const QLINEARCONV_IN_W: usize = 3;
+    pub const QLINEARCONV_IN_W_SCALE: usize = 4;
+    pub const QLINEARCONV_IN_W_ZERO_POINT: usize = 5;
+    pub const QLINEARCONV_IN_Y_SCALE: usize = 6;
+    pub const QLINEARCONV_IN_Y_ZERO_POINT: usize = 7;
+    pub const QLINEARCONV_IN_B: usize = 8;
+    pub const QLINEARCONV_OUT: usize = 0;
+
+    pub const QLINEARMATMUL_IN_A: usize = 0;
+    pub const QLINEARMATMUL_IN_A_SCALE: usize = 1;
+    pub const QLINEARMATMUL_IN_A_ZERO_POINT: usize = 2;
+    pub const QLINEARMATMUL_IN_B: usize = 3;
+    pub const QLINEARMATMUL_IN_B_SCALE: usize = 4;
+    pub const QLINEARMATMUL_IN_B_ZERO_POINT: usize = 5;
+    pub const QLINEARMATMUL_IN_Y_SCALE: usize = 6;
+    pub const QLINEARMATMUL_IN_Y_ZERO_POINT: usize = 7;
+    pub const QLINEARMATMUL_OUT: usize = 0;
+
+    pub const MATMULINTEGER_IN_A: usize = 0;
+    pub const MATMULINTEGER_IN_B: usize = 1;
+    pub const MATMULINTEGER_IN_A_ZERO_POINT: usize = 2;
+    pub const MATMULINTEGER_IN_B_ZERO_POINT: usize = 3;
+    pub const MATMULINTEGER_OUT: usize = 0;
+
+    pub const CONVINTEGER_IN_X: usize = 0;
+    pub const CONVINTEGER_IN_W: usize = 1;
+    pub const CONVINTEGER_IN_X_ZERO_POINT: usize = 2;
+    pub const CONVINTEGER_IN_W_ZERO_POINT: usize = 3;
+    pub const CONVINTEGER_OUT: usize = 0;
+
+    pub const FUSEDMATMUL_IN_A: usize = 0;
+    pub const FUSEDMATMUL_IN_B: usize = 1;
+    pub const FUSEDMATMUL_OUT: usize = 0;
+
+    pub const ATTENTION_IN_INPUT: usize = 0;
+    pub const ATTENTION_IN_WEIGHTS: usize = 1;
+    pub const ATTENTION_IN_BIAS: usize = 2;
+    pub const ATTENTION_IN_MASK_INDEX: usize = 3;
+    pub const ATTENTION_OUT: usize = 0;
+
     pub fn name(&self) -> &'static str {
         match self {
             Op::Conv2d(_) => "Conv2d",
+            Op::ConvTranspose(_) => "ConvTranspose",
             Op::Add => "Add",
             Op::Sub => "Sub",
             Op::Mul => "Mul",
             Op::Div => "Div",
             Op::Pow => "Pow",
+            Op::Equal => "Equal",
+            Op::Less => "Less",
+            Op::LessOrEqual => "LessOrEqual",
             Op::Greater => "Greater",
+            Op::GreaterOrEqual => "GreaterOrEqual",
+            Op::And => "And",
+            Op::Or => "Or",
+            Op::Not => "Not",
+            Op::Xor => "Xor",
             Op::Sqrt => "Sqrt",
             Op::ReLU => "ReLU",
             Op::LeakyReLU(_) => "LeakyReLU",
@@ -345,15 +853,37 @@ impl Op {
             Op::Sigmoid => "Sigmoid",
             Op::Erf => "Erf",
             Op::Tanh => "Tanh",
+            Op::Abs => "Abs",
+            Op::Neg => "Neg",
+            Op::Log => "Log",
+            Op::Reciprocal => "Reciprocal",
+            Op::Floor => "Floor",
+            Op::Ceil => "Ceil",
+            Op::Sin => "Sin",
+            Op::Cos => "Cos",
+            Op::Softplus => "Softplus",
+            Op::Softsign => "Softsign",
+            Op::Elu(_) => "Elu",
+            Op::Selu(_) => "Selu",
+            Op::Mish => "Mish",
+            Op::HardSwish => "HardSwish",
+            Op::PRelu => "PRelu",
+            Op::Sign => "Sign",
             Op::Clip => "Clip",
             Op::Where => "Where",
             Op::Softmax(_) => "Softmax",
             Op::Cast(_) => "Cast",
             Op::MaxPool(_) => "MaxPool",
+            Op::AveragePool(_) => "AveragePool",
+            Op::LpPool(_) => "LpPool",
             Op::GlobalAveragePool => "GlobalAveragePool",
+            Op::GlobalMaxPool => "GlobalMaxPool",
             Op::Reshape => "Reshape",
             Op::Flatten(_) => "Flatten",
             Op::Resize(_) => "Resize",
+            Op::Pad(_) => "Pad",
+            Op::DepthToSpace(_) => "DepthToSpace",
+            Op::SpaceToDepth(_) => "SpaceToDepth",
             Op::Concat(_) => "Concat",
             Op::Transpose(_) => "Transpose",
             Op::Squeeze(_) => "Squeeze",
@@ -361,27 +891,76 @@ impl Op {
             Op::ReduceMin(_) => "ReduceMin",
             Op::ReduceMax(_) => "ReduceMax",
             Op::ReduceMean(_) => "ReduceMean",
+            Op::ReduceSum(_) => "ReduceSum",
+            Op::ReduceProd(_) => "ReduceProd",
+            Op::ReduceL1(_) => "ReduceL1",
+            Op::ReduceL2(_) => "ReduceL2",
+            Op::ReduceLogSum(_) => "ReduceLogSum",
+            Op::ReduceLogSumExp(_) => "ReduceLogSumExp",
+            Op::ReduceSumSquare(_) => "ReduceSumSquare",
+            Op::ArgMax(_) => "ArgMax",
+            Op::ArgMin(_) => "ArgMin",
             Op::Round => "Round",
             Op::Exp => "Exp",
             Op::Expand => "Expand",
             Op::Range => "Range",
-            Op::Loop => "Loop",
+            Op::Loop(_) => "Loop",
             Op::Tile => "Tile",
             Op::Split(_) => "Split",
             Op::Slice => "Slice",
             Op::Gather(_) => "Gather",
+            Op::GatherElements(_) => "GatherElements",
+            Op::GatherND(_) => "GatherND",
+            Op::ScatterElements(_) => "ScatterElements",
+            Op::ScatterND(_) => "ScatterND",
+            Op::TopK(_) => "TopK",
+            Op::OneHot(_) => "OneHot",
+            Op::NonZero => "NonZero",
+            Op::CumSum(_) => "CumSum",
             Op::Shape(_) => "Shape",
-            Op::NonMaxSuppression => "NonMaxSuppression",
+            Op::NonMaxSuppression(_) => "NonMaxSuppression",
+            Op::If(_) => "If",
             Op::MatMul => "MatMul",
             Op::Gemm(_) => "Gemm",
             Op::BatchNormalization(_) => "BatchNormalization",
             Op::LayerNormalization(_) => "LayerNormalization",
+            Op::InstanceNormalization(_) => "InstanceNormalization",
+            Op::GroupNormalization(_) => "GroupNormalization",
+            Op::LSTM(_) => "LSTM",
+            Op::GRU(_) => "GRU",
+            Op::RNN(_) => "RNN",
             Op::HardSigmoid(_) => "HardSigmoid",
             Op::Constant(_) => "Constant",
+            Op::QuantizeLinear(_) => "QuantizeLinear",
+            Op::DequantizeLinear(_) => "DequantizeLinear",
+            Op::QLinearConv(_) => "QLinearConv",
+            Op::QLinearMatMul => "QLinearMatMul",
+            Op::MatMulInteger => "MatMulInteger",
+            Op::ConvInteger(_) => "ConvInteger",
+            Op::FusedMatMul(_) => "FusedMatMul",
+            Op::Attention(_) => "Attention",
+            Op::Custom(_) => "Custom",
             Op::FusedElemwise(_) => "FusedElemwise",
         }
     }
 
+    /// Returns the subgraphs of control-flow ops.
+    pub fn subgraphs(&self) -> Vec<&Graph> {
+        match self {
+            Op::Loop(l) => vec![&l.body],
+            Op::If(i) => vec![&i.then_branch, &i.else_branch],
+            _ => vec![],
+        }
+    }
+
+    /// Returns true if the output shapes may be known only after computing the op.
+    pub fn has_dynamic_output_shapes(&self) -> bool {
+        matches!(
+            self,
+            Op::Loop(_) | Op::If(_) | Op::NonMaxSuppression(_) | Op::NonZero
+        )
+    }
+
     pub fn is_elemwise(&self) -> bool {
         // TODO: Support in the future
         matches!(
@@ -395,6 +974,22 @@ impl Op {
                 | Self::Sqrt
                 | Self::ReLU
                 | Self::LeakyReLU(_)
+                | Self::Abs
+                | Self::Neg
+                | Self::Log
+                | Self::Reciprocal
+                | Self::Floor
+                | Self::Ceil
+                | Self::Sin
+                | Self::Cos
+                | Self::Softplus
+                | Self::Softsign
+                | Self::Elu(_)
+                | Self::Selu(_)
+                | Self::Mish
+                | Self::HardSwish
+                | Self::PRelu
+                | Self::Sign
                 // | Self::Gelu
                 // | Self::Sigmoid
                 // | Self::Erf
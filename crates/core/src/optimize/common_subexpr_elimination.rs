@@ -0,0 +1,115 @@
+use std::time::Instant;
+
+use rustc_hash::FxHashMap;
+
+use crate::{model::Model, node::NodeId, value::ValueId};
+
+/// Merges nodes that have the same op and the same inputs into one,
+/// and makes the users of the merged nodes refer to the remaining node.
+pub fn eliminate_common_subexprs(model: &mut Model) {
+    let start = Instant::now();
+    let nodes = model.topo_sort_nodes();
+    let value_users = model.get_value_users();
+
+    // (op name, inputs) -> nodes
+    let mut seen: FxHashMap<(&'static str, Vec<ValueId>), Vec<NodeId>> = FxHashMap::default();
+    let mut delete_list = vec![];
+
+    for node_id in nodes {
+        let node = &model.graph.nodes[node_id];
+        // Graph outputs must be produced by distinct nodes.
+        if node.outputs.iter().any(|o| model.graph.outputs.contains(o)) {
+            continue;
+        }
+
+        let key = (node.op.name(), node.inputs.clone());
+        let candidates = seen.entry(key).or_default();
+        let Some(&same_id) = candidates.iter().find(|&&id| {
+            let other = &model.graph.nodes[id];
+            other.op == node.op && other.outputs.len() == node.outputs.len()
+        }) else {
+            candidates.push(node_id);
+            continue;
+        };
+
+        // Common subexpression detected!
+
+        let replacements = node
+            .outputs
+            .iter()
+            .copied()
+            .zip(model.graph.nodes[same_id].outputs.iter().copied())
+            .collect::<Vec<_>>();
+        for (old, new) in replacements {
+            let Some(users) = value_users.get(&old) else {
+                continue;
+            };
+            for &user_id in users {
+                let user = &mut model.graph.nodes[user_id];
+                for input in user.inputs.iter_mut().filter(|i| **i == old) {
+                    *input = new;
+                }
+            }
+        }
+        delete_list.push(node_id);
+    }
+
+    let count = delete_list.len();
+
+    for node in delete_list {
+        model.graph.nodes[node].deleted = true
+    }
+
+    model.remove_unnecessary_nodes();
+
+    log::info!("eliminate_common_subexprs({count}): {:?}", start.elapsed());
+}
+
+#[test]
+fn merge_same_nodes() {
+    use crate::{node::Node, op::Op};
+
+    let mut model = Model::default();
+    let x = model.graph.values.new_val_named("x");
+    let y = model.graph.values.new_val_named("y");
+    let a0 = model.graph.values.new_val_named("a0");
+    let a1 = model.graph.values.new_val_named("a1");
+    let r0 = model.graph.values.new_val_named("r0");
+    let r1 = model.graph.values.new_val_named("r1");
+    let s = model.graph.values.new_val_named("s");
+    let z = model.graph.values.new_val_named("z");
+    model
+        .graph
+        .add_node(Node::new(Op::Add).with_ins(vec![x, y]).with_out(a0));
+    model
+        .graph
+        .add_node(Node::new(Op::Add).with_ins(vec![x, y]).with_out(a1));
+    model
+        .graph
+        .add_node(Node::new(Op::ReLU).with_in(a0).with_out(r0));
+    model
+        .graph
+        .add_node(Node::new(Op::ReLU).with_in(a1).with_out(r1));
+    // Both become `r0 - r0` but are kept since they are graph outputs.
+    model
+        .graph
+        .add_node(Node::new(Op::Sub).with_ins(vec![r0, r1]).with_out(s));
+    model
+        .graph
+        .add_node(Node::new(Op::Sub).with_ins(vec![r1, r0]).with_out(z));
+    model.graph.inputs.extend([x, y]);
+    model.graph.outputs.extend([s, z]);
+
+    eliminate_common_subexprs(&mut model);
+
+    assert_eq!(model.graph.nodes.len(), 4);
+    let subs = model
+        .graph
+        .nodes
+        .iter()
+        .filter(|(_, n)| matches!(n.op, Op::Sub))
+        .map(|(_, n)| n)
+        .collect::<Vec<_>>();
+    assert_eq!(subs.len(), 2);
+    assert!(subs.iter().all(|n| n.inputs[0] == n.inputs[1]));
+}
@@ -0,0 +1,580 @@
+use std::time::Instant;
+
+use rustc_hash::FxHashMap;
+
+use crate::{
+    analysis::shape::{infer_shapes, range_len},
+    fixed_dim::{broadcast, FixedDimensions},
+    model::Model,
+    op::Op,
+    tensor::{Tensor, TensorElemType, TensorElemTypeExt},
+    value::ValueId,
+};
+
+/// Evaluates nodes whose inputs are all constants (i.e. initializers) ahead of time,
+/// as well as `Shape`s of values whose shapes are statically known.
+/// The results are added to the initializers and the folded nodes are removed.
+pub fn fold_constants(model: &mut Model) {
+    let start = Instant::now();
+    let nodes = model.topo_sort_nodes();
+
+    let mut static_shapes = FxHashMap::default();
+    if let Err(e) = infer_shapes(model, &mut FxHashMap::default(), &mut static_shapes) {
+        log::debug!("fold_constants: Shape inference failed: {e}");
+    }
+    let mut static_dims = static_shapes
+        .into_iter()
+        .map(|(id, shape)| (id, shape.dims))
+        .collect::<FxHashMap<ValueId, FixedDimensions>>();
+    for (id, value) in model.graph.values.inner().iter() {
+        if let Some(dims) = value.shape.as_ref().and_then(|s| s.dims.as_fixed_dims()) {
+            static_dims.entry(id).or_insert(dims);
+        }
+    }
+
+    let mut delete_list = vec![];
+
+    for node_id in nodes {
+        let node = &model.graph.nodes[node_id];
+        // Graph outputs must be computed at run time.
+        if node.outputs.iter().any(|o| model.graph.outputs.contains(o)) {
+            continue;
+        }
+
+        let outputs = if let Op::Shape(shape) = &node.op {
+            let Some(dims) = static_dims.get(&node.inputs[0]) else {
+                continue;
+            };
+            let rank = dims.len() as i64;
+            let clamp = |x: i64| (if x < 0 { x + rank } else { x }).clamp(0, rank) as usize;
+            let (start, end) = (clamp(shape.start), shape.end.map_or(dims.len(), clamp));
+            let dims = dims.as_slice()[start..end.max(start)]
+                .iter()
+                .map(|&d| d as i64)
+                .collect::<Vec<_>>();
+            vec![Tensor::new(vec![dims.len()].into(), dims)]
+        } else {
+            let Some(inputs) = node
+                .inputs
+                .iter()
+                .map(|id| model.graph.inits.get(id))
+                .collect::<Option<Vec<_>>>()
+            else {
+                continue;
+            };
+            let Some(outputs) = evaluate(&node.op, &inputs) else {
+                log::debug!("fold_constants: Cannot evaluate {}", node.op.name());
+                continue;
+            };
+            outputs
+        };
+
+        for (&id, output) in node.outputs.iter().zip(outputs) {
+            static_dims.insert(id, output.dims().clone());
+            model.graph.inits.insert(id, output);
+        }
+        delete_list.push(node_id);
+    }
+
+    let count = delete_list.len();
+
+    for node in delete_list {
+        model.graph.nodes[node].deleted = true
+    }
+
+    model.remove_unnecessary_nodes();
+
+    log::info!("fold_constants({count}): {:?}", start.elapsed());
+}
+
+/// Computes the outputs of `op`. Returns `None` if `op` is not supported.
+fn evaluate(op: &Op, inputs: &[&Tensor]) -> Option<Vec<Tensor>> {
+    let output = match op {
+        Op::Constant(constant) => constant.value.clone(),
+        Op::Add
+        | Op::Sub
+        | Op::Mul
+        | Op::Div
+        | Op::Pow
+        | Op::Equal
+        | Op::Less
+        | Op::LessOrEqual
+        | Op::Greater
+        | Op::GreaterOrEqual
+        | Op::And
+        | Op::Or
+        | Op::Xor => binary(op, inputs[0], inputs[1])?,
+        Op::Not if inputs[0].elem_ty().is_bool() => Tensor::new(
+            inputs[0].dims().clone(),
+            inputs[0].data::<bool>().iter().map(|x| !x).collect(),
+        ),
+        Op::Sqrt if inputs[0].elem_ty().is_f32() => Tensor::new(
+            inputs[0].dims().clone(),
+            inputs[0].data::<f32>().iter().map(|x| x.sqrt()).collect(),
+        ),
+        Op::Cast(cast) => self::cast(inputs[0], cast.to)?,
+        Op::Reshape => {
+            let input = inputs[Op::RESHAPE_IN];
+            let shape = inputs[Op::RESHAPE_SHAPE];
+            if !shape.elem_ty().is_i64() {
+                return None;
+            }
+            let mut dims = shape.data::<i64>().to_vec();
+            for (i, d) in dims.iter_mut().enumerate() {
+                if *d == 0 {
+                    *d = *input.dims().get(i)? as i64;
+                }
+            }
+            let known = dims.iter().filter(|&&d| d != -1).product::<i64>();
+            for d in dims.iter_mut() {
+                if *d == -1 {
+                    *d = input.dims().total_elems() as i64 / known.max(1);
+                }
+            }
+            reshape(input, FixedDimensions::from_i64(&dims))?
+        }
+        Op::Flatten(flatten) => {
+            let input = inputs[Op::FLATTEN_IN];
+            let axis = normalize_axis(flatten.axis, input.dims().len() + 1);
+            let outer = input.dims()[..axis].iter().product::<usize>();
+            let inner = input.dims()[axis..].iter().product::<usize>();
+            reshape(input, vec![outer, inner].into())?
+        }
+        Op::Squeeze(squeeze) => {
+            let input = inputs[Op::SQUEEZE_IN];
+            let axes = if inputs.len() > 1 {
+                inputs[1].data::<i64>().to_vec()
+            } else {
+                squeeze.axes.clone()
+            };
+            let rank = input.dims().len();
+            let axes = axes
+                .iter()
+                .map(|&a| normalize_axis(a, rank))
+                .collect::<Vec<_>>();
+            let dims = input
+                .dims()
+                .iter()
+                .enumerate()
+                .filter(|&(i, &d)| !(d == 1 && (axes.is_empty() || axes.contains(&i))))
+                .map(|(_, &d)| d)
+                .collect::<Vec<_>>();
+            reshape(input, dims.into())?
+        }
+        Op::Unsqueeze(unsqueeze) => {
+            let input = inputs[Op::UNSQUEEZE_IN];
+            let axes = if inputs.len() > 1 {
+                inputs[1].data::<i64>().to_vec()
+            } else {
+                unsqueeze.axes.clone()
+            };
+            let rank = input.dims().len() + axes.len();
+            let mut axes = axes
+                .iter()
+                .map(|&a| normalize_axis(a, rank))
+                .collect::<Vec<_>>();
+            axes.sort_unstable();
+            let mut dims = input.dims().as_slice().to_vec();
+            for axis in axes {
+                dims.insert(axis, 1);
+            }
+            reshape(input, dims.into())?
+        }
+        Op::Concat(concat) => {
+            let rank = inputs[0].dims().len();
+            let axis = normalize_axis(concat.axis, rank);
+            let elem_ty = inputs[0].elem_ty();
+            if inputs
+                .iter()
+                .any(|i| i.elem_ty() != elem_ty || i.dims().len() != rank)
+            {
+                return None;
+            }
+            let outer = inputs[0].dims()[..axis].iter().product::<usize>();
+            let mut dims = inputs[0].dims().clone();
+            dims[axis] = inputs.iter().map(|i| i.dims()[axis]).sum();
+            let mut data = Vec::with_capacity(dims.total_elems() * elem_ty.size());
+            for o in 0..outer {
+                for input in inputs {
+                    let chunk = input.dims()[axis..].iter().product::<usize>() * elem_ty.size();
+                    data.extend_from_slice(&input.data_as_bytes()[o * chunk..(o + 1) * chunk]);
+                }
+            }
+            Tensor::new_from_raw(dims, elem_ty, data)
+        }
+        Op::Gather(gather) => {
+            let data = inputs[0];
+            let indices = to_i64_vec(inputs[1])?;
+            let axis = normalize_axis(gather.axis, data.dims().len());
+            let axis_len = data.dims()[axis] as i64;
+            let outer = data.dims()[..axis].iter().product::<usize>();
+            let inner = data.dims()[axis + 1..].iter().product::<usize>() * data.elem_ty().size();
+            let mut dims = data.dims()[..axis].to_vec();
+            dims.extend(inputs[1].dims().iter());
+            dims.extend(data.dims()[axis + 1..].iter());
+            let mut out = vec![];
+            for o in 0..outer {
+                for &i in &indices {
+                    let i = if i < 0 { i + axis_len } else { i };
+                    if !(0..axis_len).contains(&i) {
+                        return None;
+                    }
+                    let offset = (o * axis_len as usize + i as usize) * inner;
+                    out.extend_from_slice(&data.data_as_bytes()[offset..offset + inner]);
+                }
+            }
+            Tensor::new_from_raw(dims.into(), data.elem_ty(), out)
+        }
+        Op::Slice => {
+            let data = inputs[Op::SLICE_IN_DATA];
+            let rank = data.dims().len();
+            let starts = to_i64_vec(inputs[Op::SLICE_IN_STARTS])?;
+            let ends = to_i64_vec(inputs[Op::SLICE_IN_ENDS])?;
+            let axes = match inputs.get(Op::SLICE_IN_AXES) {
+                Some(axes) => to_i64_vec(axes)?,
+                None => (0..starts.len() as i64).collect(),
+            };
+            let steps = match inputs.get(Op::SLICE_IN_STEPS) {
+                Some(steps) => to_i64_vec(steps)?,
+                None => vec![1; starts.len()],
+            };
+            let mut ranges = data
+                .dims()
+                .iter()
+                .map(|&d| (0, d as i64, 1))
+                .collect::<Vec<_>>();
+            for (((&start, &end), &axis), &step) in starts.iter().zip(&ends).zip(&axes).zip(&steps)
+            {
+                let axis = normalize_axis(axis, rank);
+                let dim = data.dims()[axis] as i64;
+                let adjust = |x: i64| if x < 0 { x + dim } else { x };
+                ranges[axis] = match step {
+                    0 => return None,
+                    s if s > 0 => (adjust(start).clamp(0, dim), adjust(end).clamp(0, dim), s),
+                    s => (
+                        adjust(start).clamp(-1, dim - 1),
+                        adjust(end).clamp(-1, dim - 1),
+                        s,
+                    ),
+                };
+            }
+            let dims = ranges
+                .iter()
+                .map(|&(start, end, step)| {
+                    ((end - start + step - step.signum()) / step).max(0) as usize
+                })
+                .collect::<Vec<_>>();
+            let dims = FixedDimensions::from(dims);
+            gather_elements(data, &dims, |index| {
+                index
+                    .iter()
+                    .zip(&ranges)
+                    .zip(data.strides())
+                    .map(|((&i, &(start, _, step)), &stride)| {
+                        (start + i as i64 * step) as usize * stride
+                    })
+                    .sum()
+            })
+        }
+        Op::Transpose(trans) => {
+            let input = inputs[Op::TRANSPOSE_IN];
+            let rank = input.dims().len();
+            let perm = if trans.perm.is_empty() {
+                (0..rank).rev().collect::<Vec<_>>()
+            } else {
+                trans
+                    .perm
+                    .iter()
+                    .map(|&p| normalize_axis(p, rank))
+                    .collect()
+            };
+            let dims =
+                FixedDimensions::from(perm.iter().map(|&p| input.dims()[p]).collect::<Vec<_>>());
+            gather_elements(input, &dims, |index| {
+                index
+                    .iter()
+                    .zip(&perm)
+                    .map(|(&i, &p)| i * input.strides()[p])
+                    .sum()
+            })
+        }
+        Op::Range => match inputs[0].elem_ty() {
+            TensorElemType::I64 => {
+                let [start, limit, delta] = [0, 1, 2].map(|i| inputs[i].data::<i64>()[0]);
+                let len = range_len(start as f64, limit as f64, delta as f64).ok()?;
+                Tensor::new(
+                    vec![len].into(),
+                    (0..len as i64).map(|i| start + i * delta).collect(),
+                )
+            }
+            TensorElemType::F32 => {
+                let [start, limit, delta] = [0, 1, 2].map(|i| inputs[i].data::<f32>()[0]);
+                let len = range_len(start as f64, limit as f64, delta as f64).ok()?;
+                Tensor::new(
+                    vec![len].into(),
+                    (0..len).map(|i| start + i as f32 * delta).collect(),
+                )
+            }
+            _ => return None,
+        },
+        _ => return None,
+    };
+
+    Some(vec![output])
+}
+
+fn binary(op: &Op, a: &Tensor, b: &Tensor) -> Option<Tensor> {
+    if a.elem_ty() != b.elem_ty() {
+        return None;
+    }
+    let dims = broadcast(&[a.dims(), b.dims()])?;
+
+    fn map<T: TensorElemTypeExt, U: TensorElemTypeExt>(
+        a: &Tensor,
+        b: &Tensor,
+        dims: &FixedDimensions,
+        f: impl Fn(T, T) -> U,
+    ) -> Option<Tensor> {
+        let a_strides = a.strides_for_broadcasting(dims)?;
+        let b_strides = b.strides_for_broadcasting(dims)?;
+        let (a_data, b_data) = (a.data::<T>(), b.data::<T>());
+        let mut out = Vec::with_capacity(dims.total_elems());
+        for_each_index(dims, |index| {
+            let offset = |strides: &FixedDimensions| {
+                index
+                    .iter()
+                    .zip(strides.iter())
+                    .map(|(i, s)| i * s)
+                    .sum::<usize>()
+            };
+            out.push(f(a_data[offset(&a_strides)], b_data[offset(&b_strides)]));
+        });
+        Some(Tensor::new(dims.clone(), out))
+    }
+
+    fn compare<T: TensorElemTypeExt>(
+        op: &Op,
+        a: &Tensor,
+        b: &Tensor,
+        dims: &FixedDimensions,
+    ) -> Option<Tensor> {
+        match op {
+            Op::Equal => map(a, b, dims, |x: T, y| x == y),
+            Op::Less => map(a, b, dims, |x: T, y| x < y),
+            Op::LessOrEqual => map(a, b, dims, |x: T, y| x <= y),
+            Op::Greater => map(a, b, dims, |x: T, y| x > y),
+            Op::GreaterOrEqual => map(a, b, dims, |x: T, y| x >= y),
+            _ => None,
+        }
+    }
+
+    match (op, a.elem_ty()) {
+        (Op::Add, TensorElemType::F32) => map(a, b, &dims, |x: f32, y| x + y),
+        (Op::Sub, TensorElemType::F32) => map(a, b, &dims, |x: f32, y| x - y),
+        (Op::Mul, TensorElemType::F32) => map(a, b, &dims, |x: f32, y| x * y),
+        (Op::Div, TensorElemType::F32) => map(a, b, &dims, |x: f32, y| x / y),
+        (Op::Pow, TensorElemType::F32) => map(a, b, &dims, |x: f32, y| x.powf(y)),
+        (Op::Add, TensorElemType::I64) => map(a, b, &dims, |x: i64, y| x.wrapping_add(y)),
+        (Op::Sub, TensorElemType::I64) => map(a, b, &dims, |x: i64, y| x.wrapping_sub(y)),
+        (Op::Mul, TensorElemType::I64) => map(a, b, &dims, |x: i64, y| x.wrapping_mul(y)),
+        (Op::Div, TensorElemType::I64) if !b.data::<i64>().contains(&0) => {
+            map(a, b, &dims, |x: i64, y| x / y)
+        }
+        (Op::Add, TensorElemType::I32) => map(a, b, &dims, |x: i32, y| x.wrapping_add(y)),
+        (Op::Sub, TensorElemType::I32) => map(a, b, &dims, |x: i32, y| x.wrapping_sub(y)),
+        (Op::Mul, TensorElemType::I32) => map(a, b, &dims, |x: i32, y| x.wrapping_mul(y)),
+        (Op::Div, TensorElemType::I32) if !b.data::<i32>().contains(&0) => {
+            map(a, b, &dims, |x: i32, y| x / y)
+        }
+        (Op::And, TensorElemType::Bool) => map(a, b, &dims, |x: bool, y| x & y),
+        (Op::Or, TensorElemType::Bool) => map(a, b, &dims, |x: bool, y| x | y),
+        (Op::Xor, TensorElemType::Bool) => map(a, b, &dims, |x: bool, y| x ^ y),
+        (_, TensorElemType::F32) => compare::<f32>(op, a, b, &dims),
+        (_, TensorElemType::I64) => compare::<i64>(op, a, b, &dims),
+        (_, TensorElemType::I32) => compare::<i32>(op, a, b, &dims),
+        (_, TensorElemType::Bool) => compare::<bool>(op, a, b, &dims),
+        _ => None,
+    }
+}
+
+fn cast(input: &Tensor, to: TensorElemType) -> Option<Tensor> {
+    if let Some(output) = input.cast_float(to) {
+        return Some(output);
+    }
+    // Half-precision values go through f32.
+    if input.elem_ty().is_half() {
+        return cast(&input.cast_float(TensorElemType::F32)?, to);
+    }
+
+    let dims = input.dims().clone();
+    let output = match (input.elem_ty(), to) {
+        (from, to) if from == to => input.clone(),
+        (TensorElemType::F32, to) => {
+            let data = input.data::<f32>();
+            match to {
+                TensorElemType::I64 => Tensor::new(dims, data.iter().map(|&x| x as i64).collect()),
+                TensorElemType::I32 => Tensor::new(dims, data.iter().map(|&x| x as i32).collect()),
+                TensorElemType::I8 => Tensor::new(dims, data.iter().map(|&x| x as i8).collect()),
+                TensorElemType::U8 => Tensor::new(dims, data.iter().map(|&x| x as u8).collect()),
+                TensorElemType::Bool => Tensor::new(dims, data.iter().map(|&x| x != 0.).collect()),
+                _ => return None,
+            }
+        }
+        (_, to) => {
+            let data = to_i64_vec(input)?;
+            match to {
+                TensorElemType::F32 => Tensor::new(dims, data.iter().map(|&x| x as f32).collect()),
+                TensorElemType::I64 => Tensor::new(dims, data),
+                TensorElemType::I32 => Tensor::new(dims, data.iter().map(|&x| x as i32).collect()),
+                TensorElemType::I8 => Tensor::new(dims, data.iter().map(|&x| x as i8).collect()),
+                TensorElemType::U8 => Tensor::new(dims, data.iter().map(|&x| x as u8).collect()),
+                TensorElemType::Bool => Tensor::new(dims, data.iter().map(|&x| x != 0).collect()),
+                TensorElemType::F16 | TensorElemType::BF16 => {
+                    return Tensor::new(dims, data.iter().map(|&x| x as f32).collect())
+                        .cast_float(to)
+                }
+            }
+        }
+    };
+    Some(output)
+}
+
+fn reshape(input: &Tensor, dims: FixedDimensions) -> Option<Tensor> {
+    if dims.total_elems() != input.dims().total_elems() {
+        return None;
+    }
+    Some(Tensor::new_from_raw(
+        dims,
+        input.elem_ty(),
+        input.data_as_bytes().to_vec(),
+    ))
+}
+
+/// Creates a tensor of `dims` by copying the elements of `input` at the offsets
+/// (in elements) computed by `offset` from each output index.
+fn gather_elements(
+    input: &Tensor,
+    dims: &FixedDimensions,
+    offset: impl Fn(&[usize]) -> usize,
+) -> Tensor {
+    let size = input.elem_ty().size();
+    let bytes = input.data_as_bytes();
+    let mut data = Vec::with_capacity(dims.total_elems() * size);
+    for_each_index(dims, |index| {
+        let offset = offset(index) * size;
+        data.extend_from_slice(&bytes[offset..offset + size]);
+    });
+    Tensor::new_from_raw(dims.clone(), input.elem_ty(), data)
+}
+
+/// Calls `f` with every index of `dims` in row-major order.
+fn for_each_index(dims: &FixedDimensions, mut f: impl FnMut(&[usize])) {
+    if dims.total_elems() == 0 {
+        return;
+    }
+    let mut index = vec![0; dims.len()];
+    loop {
+        f(&index);
+        let mut axis = dims.len();
+        loop {
+            if axis == 0 {
+                return;
+            }
+            axis -= 1;
+            index[axis] += 1;
+            if index[axis] < dims[axis] {
+                break;
+            }
+            index[axis] = 0;
+        }
+    }
+}
+
+fn to_i64_vec(t: &Tensor) -> Option<Vec<i64>> {
+    match t.elem_ty() {
+        TensorElemType::I64 => Some(t.data::<i64>().to_vec()),
+        TensorElemType::I32 => Some(t.data::<i32>().iter().map(|&x| x as i64).collect()),
+        TensorElemType::I8 => Some(t.data::<i8>().iter().map(|&x| x as i64).collect()),
+        TensorElemType::U8 => Some(t.data::<u8>().iter().map(|&x| x as i64).collect()),
+        TensorElemType::Bool => Some(t.data::<bool>().iter().map(|&x| x as i64).collect()),
+        _ => None,
+    }
+}
+
+fn normalize_axis(axis: i64, rank: usize) -> usize {
+    if axis < 0 {
+        (rank as i64 + axis) as usize
+    } else {
+        axis as usize
+    }
+}
+
+#[test]
+fn fold_shape_and_arith() {
+    use crate::{dim::Dimensions, node::Node, op::Shape, tensor::TypedShape};
+
+    let mut model = Model::default();
+    let x = model.graph.values.new_val_named_and_shaped(
+        "x",
+        TypedShape::new(
+            Dimensions::from(FixedDimensions::from(vec![2, 3])),
+            TensorElemType::F32,
+        ),
+    );
+    // Reshaped to twice the dimensions of `x`.
+    let z = model.graph.values.new_val_named_and_shaped(
+        "z",
+        TypedShape::new(
+            Dimensions::from(FixedDimensions::from(vec![24])),
+            TensorElemType::F32,
+        ),
+    );
+    let two = model.graph.values.new_val_named("two");
+    let x_shape = model.graph.values.new_val_named("x_shape");
+    let doubled = model.graph.values.new_val_named("doubled");
+    let y = model.graph.values.new_val_named("y");
+    model
+        .graph
+        .inits
+        .insert(two, Tensor::new(vec![1].into(), vec![2i64]));
+    model.graph.add_node(
+        Node::new(Op::Shape(Shape {
+            start: 0,
+            end: None,
+        }))
+        .with_in(x)
+        .with_out(x_shape),
+    );
+    model.graph.add_node(
+        Node::new(Op::Mul)
+            .with_ins(vec![x_shape, two])
+            .with_out(doubled),
+    );
+    model.graph.add_node(
+        Node::new(Op::Reshape)
+            .with_ins(vec![z, doubled])
+            .with_out(y),
+    );
+    model.graph.inputs.extend([x, z]);
+    model.graph.outputs.push(y);
+
+    fold_constants(&mut model);
+
+    assert_eq!(model.graph.nodes.len(), 1);
+    assert!(model.graph.inits[&doubled].allclose(&[4i64, 6]));
+}
+
+#[test]
+fn skip_range_with_zero_delta() {
+    let scalar = |x: f32| Tensor::new(vec![].into(), vec![x]);
+    let range = |start, limit, delta| {
+        evaluate(
+            &Op::Range,
+            &[&scalar(start), &scalar(limit), &scalar(delta)],
+        )
+    };
+    assert!(range(0., 1., 0.).is_none());
+    assert!(range(0., 1., f32::NAN).is_none());
+    assert!(range(0., f32::INFINITY, 1.).is_none());
+    assert!(range(0., 1., 0.25).unwrap()[0].allclose(&[0f32, 0.25, 0.5, 0.75]));
+}
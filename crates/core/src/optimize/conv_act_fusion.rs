@@ -2,7 +2,7 @@ use std::time::Instant;
 
 use crate::{
     model::Model,
-    op::{FusedActivation, Op},
+    op::{Conv2d, FusedActivation, Op},
 };
 
 pub fn fuse_conv_act(model: &mut Model) {
@@ -16,10 +16,21 @@ pub fn fuse_conv_act(model: &mut Model) {
     for node_id in nodes {
         let conv_id = node_id;
         let conv = &model.graph.nodes[conv_id];
-        if !matches!(conv.op, Op::Conv2d(_)) {
+        // A conv that already has an activation can't take another one.
+        if !matches!(
+            conv.op,
+            Op::Conv2d(Conv2d {
+                activation: None,
+                ..
+            })
+        ) {
             continue;
         }
-        if value_users[&conv.outputs[0]].len() != 1 {
+        if model.graph.outputs.contains(&conv.outputs[0])
+            || value_users
+                .get(&conv.outputs[0])
+                .map_or(true, |users| users.len() != 1)
+        {
             continue;
         }
 
@@ -34,6 +45,12 @@ pub fn fuse_conv_act(model: &mut Model) {
             Op::HardSigmoid(ref h) => FusedActivation::HardSigmoid(*h),
             _ => continue,
         };
+        // Leave a dead activation alone.
+        if !value_users.contains_key(&act.outputs[0])
+            && !model.graph.outputs.contains(&act.outputs[0])
+        {
+            continue;
+        }
 
         // Conv+Activation Detected!
 
@@ -48,10 +65,16 @@ pub fn fuse_conv_act(model: &mut Model) {
             c.activation = Some(fused_act);
         }
 
-        for user_id in &value_users[&act] {
-            let user = &mut model.graph.nodes[*user_id];
-            let idx = user.inputs.iter().position(|&i| i == act).unwrap();
-            user.inputs[idx] = conv_out
+        for output in model.graph.outputs.iter_mut().filter(|o| **o == act) {
+            *output = conv_out;
+        }
+        if let Some(users) = value_users.get(&act) {
+            for user_id in users {
+                let user = &mut model.graph.nodes[*user_id];
+                for input in user.inputs.iter_mut().filter(|i| **i == act) {
+                    *input = conv_out;
+                }
+            }
         }
     }
 
@@ -63,3 +86,94 @@ pub fn fuse_conv_act(model: &mut Model) {
 
     log::info!("fuse_conv_act({count}): {:?}", start.elapsed());
 }
+
+#[test]
+fn fuse_act_used_and_output() {
+    use crate::{node::Node, tensor::Tensor};
+
+    let mut model = Model::default();
+    let x = model.graph.values.new_val_named("x");
+    let w = model.graph.values.new_val_named("w");
+    let conv_out = model.graph.values.new_val_named("conv_out");
+    let y = model.graph.values.new_val_named("y");
+    let z = model.graph.values.new_val_named("z");
+    model
+        .graph
+        .inits
+        .insert(w, Tensor::new(vec![1, 1, 1, 1].into(), vec![2.0f32]));
+    model.graph.add_node(
+        Node::new(Op::Conv2d(Conv2d {
+            kernel_shape: vec![1, 1].into(),
+            group: 1,
+            ..Default::default()
+        }))
+        .with_ins(vec![x, w])
+        .with_out(conv_out),
+    );
+    model
+        .graph
+        .add_node(Node::new(Op::ReLU).with_in(conv_out).with_out(y));
+    // `y` is both a graph output and used by another node.
+    model
+        .graph
+        .add_node(Node::new(Op::Neg).with_in(y).with_out(z));
+    model.graph.inputs.push(x);
+    model.graph.outputs.extend([y, z]);
+
+    fuse_conv_act(&mut model);
+
+    assert_eq!(model.graph.nodes.len(), 2);
+    assert_eq!(model.graph.outputs, vec![conv_out, z]);
+    for (_, node) in model.graph.nodes.iter() {
+        match node.op {
+            Op::Conv2d(ref c) => assert_eq!(c.activation, Some(FusedActivation::Relu)),
+            Op::Neg => assert_eq!(node.inputs, vec![conv_out]),
+            _ => unreachable!(),
+        }
+    }
+}
+
+#[test]
+fn skip_dead_act() {
+    use crate::{node::Node, tensor::Tensor};
+
+    let mut model = Model::default();
+    let x = model.graph.values.new_val_named("x");
+    let w = model.graph.values.new_val_named("w");
+    let conv_out = model.graph.values.new_val_named("conv_out");
+    let dead = model.graph.values.new_val_named("dead");
+    let y = model.graph.values.new_val_named("y");
+    model
+        .graph
+        .inits
+        .insert(w, Tensor::new(vec![1, 1, 1, 1].into(), vec![2.0f32]));
+    model.graph.add_node(
+        Node::new(Op::Conv2d(Conv2d {
+            kernel_shape: vec![1, 1].into(),
+            group: 1,
+            ..Default::default()
+        }))
+        .with_ins(vec![x, w])
+        .with_out(conv_out),
+    );
+    // The output of this ReLU is neither used nor a graph output.
+    model
+        .graph
+        .add_node(Node::new(Op::ReLU).with_in(conv_out).with_out(dead));
+    model
+        .graph
+        .add_node(Node::new(Op::Neg).with_in(x).with_out(y));
+    model.graph.inputs.push(x);
+    model.graph.outputs.push(y);
+
+    fuse_conv_act(&mut model);
+
+    assert_eq!(model.graph.outputs, vec![y]);
+    assert!(model.graph.nodes.iter().all(|(_, node)| !matches!(
+        node.op,
+        Op::Conv2d(Conv2d {
+            activation: Some(_),
+            ..
+        })
+    )));
+}
@@ -0,0 +1,271 @@
+use std::time::Instant;
+
+use crate::{model::Model, op::Op, tensor::Tensor, value::ValueId};
+
+/// Folds `BatchNormalization` into the weight and bias of the preceding `Conv2d`.
+/// This must run before `fuse_conv_act` so that `Conv2d`+`BatchNormalization`+`ReLU` ends up
+/// in a single `Conv2d` with a fused activation.
+pub fn fuse_conv_bn(model: &mut Model) {
+    let start = Instant::now();
+    let nodes = model.topo_sort_nodes();
+    let value_users = model.get_value_users();
+    let is_init = |model: &Model, id: &ValueId| {
+        model
+            .graph
+            .inits
+            .get(id)
+            .map_or(false, |t| t.elem_ty().is_f32())
+    };
+
+    let mut list = vec![];
+    let mut delete_list = vec![];
+
+    for node_id in nodes {
+        let conv_id = node_id;
+        let conv = &model.graph.nodes[conv_id];
+        let Op::Conv2d(ref c) = conv.op else {
+            continue;
+        };
+        // BatchNormalization after an activation can't be folded.
+        if c.activation.is_some() {
+            continue;
+        }
+        let Some(users) = value_users.get(&conv.outputs[Op::CONV2D_OUT]) else {
+            continue;
+        };
+        if model.graph.outputs.contains(&conv.outputs[Op::CONV2D_OUT]) || users.len() != 1 {
+            continue;
+        }
+        if !conv.inputs[Op::CONV2D_WEIGHT..]
+            .iter()
+            .all(|i| is_init(model, i))
+        {
+            continue;
+        }
+
+        let bn_id = users.iter().next().copied().unwrap();
+        let bn = &model.graph.nodes[bn_id];
+        let Op::BatchNormalization(ref batchnorm) = bn.op else {
+            continue;
+        };
+        if batchnorm.training_mode
+            || bn.outputs.len() != 1
+            || !bn.inputs[Op::BATCHNORM_IN_SCALE..]
+                .iter()
+                .all(|i| is_init(model, i))
+        {
+            continue;
+        }
+
+        // Conv+BatchNormalization Detected!
+
+        list.push((conv_id, bn_id));
+        delete_list.push(bn_id);
+    }
+
+    let count = list.len();
+    let mut replaced = vec![];
+
+    for (conv_id, bn_id) in list {
+        let conv = &model.graph.nodes[conv_id];
+        let bn = &model.graph.nodes[bn_id];
+        let Op::BatchNormalization(ref batchnorm) = bn.op else {
+            unreachable!()
+        };
+        let inits = &model.graph.inits;
+        let weight = &inits[&conv.inputs[Op::CONV2D_WEIGHT]];
+        let scale = inits[&bn.inputs[Op::BATCHNORM_IN_SCALE]].data::<f32>();
+        let b = inits[&bn.inputs[Op::BATCHNORM_IN_B]].data::<f32>();
+        let mean = inits[&bn.inputs[Op::BATCHNORM_IN_INPUT_MEAN]].data::<f32>();
+        let var = inits[&bn.inputs[Op::BATCHNORM_IN_INPUT_VAR]].data::<f32>();
+        let num_out_channels = weight.dims()[0];
+        let bias = conv
+            .inputs
+            .get(Op::CONV2D_BIAS)
+            .map_or(vec![0.; num_out_channels], |id| {
+                inits[id].data::<f32>().to_vec()
+            });
+
+        // y = (conv(x, w) + bias - mean) / sqrt(var + eps) * scale + b
+        //   = conv(x, w * k) + (bias - mean) * k + b  where k = scale / sqrt(var + eps)
+        let k = (0..num_out_channels)
+            .map(|c| scale[c] / (var[c] + batchnorm.epsilon).sqrt())
+            .collect::<Vec<_>>();
+        let weight_per_channel = weight.dims().total_elems() / num_out_channels;
+        let new_weight = weight
+            .data::<f32>()
+            .iter()
+            .enumerate()
+            .map(|(i, &w)| w * k[i / weight_per_channel])
+            .collect::<Vec<_>>();
+        let new_bias = (0..num_out_channels)
+            .map(|c| (bias[c] - mean[c]) * k[c] + b[c])
+            .collect::<Vec<_>>();
+        let new_weight = Tensor::new(weight.dims().clone(), new_weight);
+        let new_bias = Tensor::new(vec![num_out_channels].into(), new_bias);
+
+        replaced.extend(conv.inputs[Op::CONV2D_WEIGHT..].iter().copied());
+        replaced.extend(bn.inputs[Op::BATCHNORM_IN_SCALE..].iter().copied());
+        let conv_out = conv.outputs[Op::CONV2D_OUT];
+        let bn_out = bn.outputs[Op::BATCHNORM_OUT_Y];
+        let weight_id = model.graph.values.new_val();
+        let bias_id = model.graph.values.new_val();
+        model.graph.inits.insert(weight_id, new_weight);
+        model.graph.inits.insert(bias_id, new_bias);
+        let conv = &mut model.graph.nodes[conv_id];
+        conv.inputs[Op::CONV2D_WEIGHT] = weight_id;
+        conv.inputs.truncate(Op::CONV2D_BIAS);
+        conv.inputs.push(bias_id);
+
+        if let Some(users) = value_users.get(&bn_out) {
+            for user_id in users {
+                let user = &mut model.graph.nodes[*user_id];
+                for input in user.inputs.iter_mut().filter(|i| **i == bn_out) {
+                    *input = conv_out;
+                }
+            }
+        }
+        for output in model.graph.outputs.iter_mut().filter(|o| **o == bn_out) {
+            *output = conv_out;
+        }
+    }
+
+    for node in delete_list {
+        model.graph.nodes[node].deleted = true
+    }
+
+    model.remove_unnecessary_nodes();
+
+    // Remove the original weights and BatchNormalization parameters if no longer used.
+    let value_users = model.get_value_users();
+    for id in replaced {
+        if !value_users.contains_key(&id) && !model.graph.outputs.contains(&id) {
+            model.graph.inits.remove(&id);
+        }
+    }
+
+    log::info!("fuse_conv_bn({count}): {:?}", start.elapsed());
+}
+
+#[test]
+fn fold_bn_into_conv() {
+    use crate::{
+        node::Node,
+        op::{BatchNormalization, Conv2d},
+    };
+
+    let mut model = Model::default();
+    let x = model.graph.values.new_val_named("x");
+    let w = model.graph.values.new_val_named("w");
+    let conv_b = model.graph.values.new_val_named("conv_b");
+    let conv_out = model.graph.values.new_val_named("conv_out");
+    let params @ [scale, b, mean, var] =
+        ["scale", "b", "mean", "var"].map(|name| model.graph.values.new_val_named(name));
+    let y = model.graph.values.new_val_named("y");
+    let z = model.graph.values.new_val_named("z");
+    model.graph.inits.insert(
+        w,
+        Tensor::new(vec![2, 2, 1, 1].into(), vec![1.0f32, -2.0, 0.5, 3.0]),
+    );
+    model
+        .graph
+        .inits
+        .insert(conv_b, Tensor::new(vec![2].into(), vec![0.3f32, -0.4]));
+    for (id, data) in params.into_iter().zip([
+        vec![1.5f32, -2.0],
+        vec![0.1, 0.2],
+        vec![0.5, -1.0],
+        vec![0.25, 3.0],
+    ]) {
+        model
+            .graph
+            .inits
+            .insert(id, Tensor::new(vec![2].into(), data));
+    }
+    model.graph.add_node(
+        Node::new(Op::Conv2d(Conv2d {
+            kernel_shape: vec![1, 1].into(),
+            strides: vec![1, 1].into(),
+            padding: vec![0, 0, 0, 0].into(),
+            dilations: vec![1, 1].into(),
+            group: 1,
+            ..Default::default()
+        }))
+        .with_ins(vec![x, w, conv_b])
+        .with_out(conv_out),
+    );
+    model.graph.add_node(
+        Node::new(Op::BatchNormalization(BatchNormalization {
+            epsilon: 0.01,
+            momentum: 0.9,
+            training_mode: false,
+        }))
+        .with_ins(vec![conv_out, scale, b, mean, var])
+        .with_out(y),
+    );
+    model
+        .graph
+        .add_node(Node::new(Op::ReLU).with_in(y).with_out(z));
+    model.graph.inputs.push(x);
+    model.graph.outputs.push(z);
+
+    fuse_conv_bn(&mut model);
+    super::conv_act_fusion::fuse_conv_act(&mut model);
+
+    assert_eq!(model.graph.nodes.len(), 1);
+    let (_, conv) = model.graph.nodes.iter().next().unwrap();
+    assert!(matches!(
+        conv.op,
+        Op::Conv2d(Conv2d {
+            activation: Some(_),
+            ..
+        })
+    ));
+    assert_eq!(conv.outputs, model.graph.outputs);
+    // k = scale / sqrt(var + eps) = [1.5 / sqrt(0.26), -2 / sqrt(3.01)]
+    assert!(
+        model.graph.inits[&conv.inputs[Op::CONV2D_WEIGHT]].allclose(&[
+            2.941742f32,
+            -5.883484,
+            -0.576390,
+            -3.458343
+        ])
+    );
+    // (conv_b - mean) * k + b
+    assert!(model.graph.inits[&conv.inputs[Op::CONV2D_BIAS]].allclose(&[-0.488348f32, -0.491669]));
+    assert_eq!(model.graph.inits.len(), 2);
+}
+
+#[test]
+fn skip_conv_without_users() {
+    use crate::{node::Node, op::Conv2d};
+
+    let mut model = Model::default();
+    let x = model.graph.values.new_val_named("x");
+    let w = model.graph.values.new_val_named("w");
+    let dead = model.graph.values.new_val_named("dead");
+    let y = model.graph.values.new_val_named("y");
+    model
+        .graph
+        .inits
+        .insert(w, Tensor::new(vec![1, 1, 1, 1].into(), vec![2.0f32]));
+    // The output of this Conv2d is neither used nor a graph output.
+    model.graph.add_node(
+        Node::new(Op::Conv2d(Conv2d {
+            kernel_shape: vec![1, 1].into(),
+            group: 1,
+            ..Default::default()
+        }))
+        .with_ins(vec![x, w])
+        .with_out(dead),
+    );
+    model
+        .graph
+        .add_node(Node::new(Op::ReLU).with_in(x).with_out(y));
+    model.graph.inputs.push(x);
+    model.graph.outputs.push(y);
+
+    fuse_conv_bn(&mut model);
+
+    assert_eq!(model.graph.outputs, vec![y]);
+}
@@ -0,0 +1,165 @@
+use std::time::Instant;
+
+use rustc_hash::{FxHashMap, FxHashSet};
+
+use crate::{model::Model, op::Op, value::ValueId};
+
+/// How much `eliminate_dead_code` removed from the model.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DeadCodeStats {
+    pub nodes: usize,
+    pub values: usize,
+    pub inits: usize,
+}
+
+/// Removes nodes, values and initializers that don't contribute to the graph outputs.
+/// Graph inputs are always kept since they are part of the model interface.
+/// Note that `ValueId`s are reassigned, so ids obtained before calling this are invalidated.
+pub fn eliminate_dead_code(model: &mut Model) -> DeadCodeStats {
+    let start = Instant::now();
+    let value_parents = model.get_value_parents();
+
+    // Walk back from the graph outputs.
+    let mut live_nodes = FxHashSet::default();
+    let mut worklist = model.graph.outputs.clone();
+    while let Some(val) = worklist.pop() {
+        let Some(&node_id) = value_parents.get(&val) else {
+            continue;
+        };
+        if live_nodes.insert(node_id) {
+            worklist.extend(model.graph.nodes[node_id].inputs.iter().copied());
+        }
+    }
+
+    let mut stats = DeadCodeStats::default();
+
+    for (node_id, node) in model.graph.nodes.iter_mut() {
+        if !node.deleted && !live_nodes.contains(&node_id) {
+            node.deleted = true;
+            stats.nodes += 1;
+        }
+    }
+    model.remove_unnecessary_nodes();
+
+    let mut live_values = model
+        .graph
+        .inputs
+        .iter()
+        .chain(model.graph.outputs.iter())
+        .copied()
+        .collect::<FxHashSet<_>>();
+    for (_, node) in model.graph.nodes.iter() {
+        live_values.extend(node.inputs.iter().chain(node.outputs.iter()));
+        if let Op::FusedElemwise(f) = &node.op {
+            live_values.extend(f.input_map.iter());
+            for (_, ins, outs) in &f.chain {
+                live_values.extend(ins.iter().chain(outs.iter()));
+            }
+        }
+    }
+
+    let num_inits = model.graph.inits.len();
+    model.graph.inits.retain(|id, _| live_values.contains(id));
+    stats.inits = num_inits - model.graph.inits.len();
+
+    // Rebuild the value arena with live values only.
+    let old_values = std::mem::take(&mut model.graph.values);
+    let mut map = FxHashMap::default();
+    for (id, value) in old_values.inner().iter() {
+        if live_values.contains(&id) {
+            map.insert(id, model.graph.values.inner_mut().alloc(value.clone()));
+        } else {
+            stats.values += 1;
+        }
+    }
+    remap_values(model, &map);
+
+    log::info!(
+        "eliminate_dead_code(nodes: {}, values: {}, inits: {}): {:?}",
+        stats.nodes,
+        stats.values,
+        stats.inits,
+        start.elapsed()
+    );
+
+    stats
+}
+
+fn remap_values(model: &mut Model, map: &FxHashMap<ValueId, ValueId>) {
+    let remap = |ids: &mut Vec<ValueId>| ids.iter_mut().for_each(|id| *id = map[id]);
+
+    for (_, node) in model.graph.nodes.iter_mut() {
+        remap(&mut node.inputs);
+        remap(&mut node.outputs);
+        if let Op::FusedElemwise(f) = &mut node.op {
+            remap(&mut f.input_map);
+            for (_, ins, outs) in &mut f.chain {
+                remap(ins);
+                remap(outs);
+            }
+        }
+    }
+    remap(&mut model.graph.inputs);
+    remap(&mut model.graph.outputs);
+    model.graph.inits = std::mem::take(&mut model.graph.inits)
+        .into_iter()
+        .map(|(id, tensor)| (map[&id], tensor))
+        .collect();
+}
+
+#[test]
+fn eliminate_dead_branch() {
+    use crate::{node::Node, tensor::Tensor};
+
+    let mut model = Model::default();
+    let x = model.graph.values.new_val_named("x");
+    let w = model.graph.values.new_val_named("w");
+    let unused_w = model.graph.values.new_val_named("unused_w");
+    let y = model.graph.values.new_val_named("y");
+    let dead = model.graph.values.new_val_named("dead");
+    let dead2 = model.graph.values.new_val_named("dead2");
+    model
+        .graph
+        .inits
+        .insert(w, Tensor::new(vec![1].into(), vec![2.0f32]));
+    model
+        .graph
+        .inits
+        .insert(unused_w, Tensor::new(vec![1].into(), vec![3.0f32]));
+    model
+        .graph
+        .add_node(Node::new(Op::Mul).with_ins(vec![x, w]).with_out(y));
+    model.graph.add_node(
+        Node::new(Op::Add)
+            .with_ins(vec![x, unused_w])
+            .with_out(dead),
+    );
+    model
+        .graph
+        .add_node(Node::new(Op::ReLU).with_in(dead).with_out(dead2));
+    model.graph.inputs.push(x);
+    model.graph.outputs.push(y);
+
+    let stats = eliminate_dead_code(&mut model);
+
+    assert_eq!(
+        stats,
+        DeadCodeStats {
+            nodes: 2,
+            values: 3,
+            inits: 1
+        }
+    );
+    assert_eq!(model.graph.nodes.len(), 1);
+    assert_eq!(model.graph.values.inner().len(), 3);
+    let (_, mul) = model.graph.nodes.iter().next().unwrap();
+    assert_eq!(
+        mul.inputs,
+        vec![
+            model.graph.inputs[0],
+            model.lookup_named_value("w").unwrap()
+        ]
+    );
+    assert_eq!(mul.outputs, model.graph.outputs);
+    assert!(model.graph.inits[&mul.inputs[1]].allclose(&[2.0f32]));
+}
@@ -1,6 +1,12 @@
+pub mod common_subexpr_elimination;
+pub mod constant_folding;
 pub mod conv_act_fusion;
+pub mod conv_bn_fusion;
+pub mod dead_code_elimination;
 pub mod elemwise_fusion;
 pub mod fast_gelu_fusion;
 pub mod gelu_fusion;
 pub mod layer_norm_fusion;
+pub mod pad_conv_fusion;
+pub mod pass_manager;
 // pub mod transpose_fusion; // TODO: Implemented but I figured out it's not useful for now.
@@ -0,0 +1,274 @@
+use std::time::Instant;
+
+use crate::{
+    model::Model,
+    node::Node,
+    op::{Conv2d, Op},
+    tensor::Tensor,
+    value::ValueId,
+};
+
+/// Folds zero `Pad` of spatial axes into the `padding` of the following `Conv2d`.
+pub fn fuse_pad_conv(model: &mut Model) {
+    let start = Instant::now();
+    let nodes = model.topo_sort_nodes();
+    let value_users = model.get_value_users();
+
+    let mut list = vec![];
+    let mut delete_list = vec![];
+
+    for node_id in nodes {
+        let pad_id = node_id;
+        let pad = &model.graph.nodes[pad_id];
+        let Op::Pad(ref p) = pad.op else {
+            continue;
+        };
+        if p.mode != "constant" || p.value != 0.0 {
+            continue;
+        }
+        let pad_out = pad.outputs[Op::PAD_OUT];
+        if model.graph.outputs.contains(&pad_out)
+            || value_users.get(&pad_out).map_or(true, |u| u.len() != 1)
+        {
+            continue;
+        }
+
+        let conv_id = value_users[&pad_out].iter().next().copied().unwrap();
+        let conv = &model.graph.nodes[conv_id];
+        let Op::Conv2d(ref c) = conv.op else {
+            continue;
+        };
+        if !matches!(c.auto_pad.as_str(), "" | "NOTSET") || conv.inputs[Op::CONV2D_IN] != pad_out {
+            continue;
+        }
+        let rank = match model.graph.inits.get(&conv.inputs[Op::CONV2D_WEIGHT]) {
+            Some(weight) => weight.dims().len(),
+            None => p.pads.len() / 2,
+        };
+        let Some(pads) = pads_of(model, pad, rank) else {
+            continue;
+        };
+        // Only spatial axes can be padded by the conv.
+        let spatial = rank.saturating_sub(2);
+        if spatial == 0
+            || pads.len() != rank * 2
+            || pads.iter().any(|&p| p < 0)
+            || pads[0..2]
+                .iter()
+                .chain(&pads[rank..rank + 2])
+                .any(|&p| p != 0)
+        {
+            continue;
+        }
+        let conv_padding = match c.padding.len() {
+            0 => vec![0; spatial * 2],
+            // Pads of only one side (as in `[h, w]`) apply to both sides.
+            n if n == spatial => [&c.padding[..], &c.padding[..]].concat(),
+            n if n == spatial * 2 => c.padding.to_vec(),
+            _ => continue,
+        };
+        let padding = (0..spatial * 2)
+            .map(|i| {
+                let axis = if i < spatial {
+                    2 + i
+                } else {
+                    rank + 2 + i - spatial
+                };
+                conv_padding[i] + pads[axis] as usize
+            })
+            .collect::<Vec<_>>();
+
+        // Pad+Conv Detected!
+
+        list.push((pad_id, conv_id, padding));
+        delete_list.push(pad_id);
+    }
+
+    let count = list.len();
+    let mut replaced = vec![];
+
+    for (pad_id, conv_id, padding) in list {
+        let pad = &model.graph.nodes[pad_id];
+        let data = pad.inputs[Op::PAD_IN_DATA];
+        replaced.extend(pad.inputs[Op::PAD_IN_DATA + 1..].iter().copied());
+        let conv = &mut model.graph.nodes[conv_id];
+        conv.inputs[Op::CONV2D_IN] = data;
+        if let Op::Conv2d(Conv2d {
+            padding: ref mut p, ..
+        }) = conv.op
+        {
+            *p = padding.into();
+        }
+    }
+
+    for node in delete_list {
+        model.graph.nodes[node].deleted = true
+    }
+
+    model.remove_unnecessary_nodes();
+
+    // Remove the pads and the constant value if no longer used.
+    let value_users = model.get_value_users();
+    for id in replaced {
+        if !value_users.contains_key(&id) && !model.graph.outputs.contains(&id) {
+            model.graph.inits.remove(&id);
+        }
+    }
+
+    log::info!("fuse_pad_conv({count}): {:?}", start.elapsed());
+}
+
+/// Returns the pads of every axis of `pad`, or `None` if they aren't constant or the constant
+/// value isn't zero.
+fn pads_of(model: &Model, pad: &Node, rank: usize) -> Option<Vec<i64>> {
+    let Op::Pad(ref p) = pad.op else {
+        return None;
+    };
+    // Omitted optional inputs are named "".
+    let input = |i: usize| -> Option<Option<&Tensor>> {
+        let Some(id) = pad.inputs.get(i) else {
+            return Some(None);
+        };
+        if is_omitted(model, *id) {
+            return Some(None);
+        }
+        model.graph.inits.get(id).map(Some)
+    };
+
+    // From opset version 11, pads and the constant value are given as inputs.
+    let mut pads = match input(Op::PAD_IN_PADS)? {
+        Some(pads) if pads.elem_ty().is_i64() => pads.data::<i64>().to_vec(),
+        Some(_) => return None,
+        None => p.pads.clone(),
+    };
+    if let Some(value) = input(Op::PAD_IN_CONSTANT_VALUE)? {
+        if value.data_as_bytes().iter().any(|&b| b != 0) {
+            return None;
+        }
+    }
+    if let Some(axes) = input(Op::PAD_IN_AXES)? {
+        let axes = match axes.elem_ty() {
+            ty if ty.is_i64() => axes.data::<i64>().to_vec(),
+            _ => axes.data::<i32>().iter().map(|&a| a as i64).collect(),
+        };
+        if pads.len() != axes.len() * 2 {
+            return None;
+        }
+        let mut full = vec![0; rank * 2];
+        for (i, &axis) in axes.iter().enumerate() {
+            let axis = if axis < 0 { axis + rank as i64 } else { axis };
+            if !(0..rank as i64).contains(&axis) {
+                return None;
+            }
+            full[axis as usize] = pads[i];
+            full[rank + axis as usize] = pads[axes.len() + i];
+        }
+        pads = full;
+    }
+    Some(pads)
+}
+
+fn is_omitted(model: &Model, id: ValueId) -> bool {
+    model.graph.values.inner()[id].name.as_deref() == Some("")
+}
+
+#[test]
+fn fold_pad_into_conv() {
+    use crate::op::Pad;
+
+    let mut model = Model::default();
+    let x = model.graph.values.new_val_named("x");
+    let pads = model.graph.values.new_val_named("pads");
+    let value = model.graph.values.new_val_named("value");
+    let axes = model.graph.values.new_val_named("axes");
+    let w = model.graph.values.new_val_named("w");
+    let padded = model.graph.values.new_val_named("padded");
+    let y = model.graph.values.new_val_named("y");
+    model
+        .graph
+        .inits
+        .insert(pads, Tensor::new(vec![4].into(), vec![1i64, 2, 3, 0]));
+    model
+        .graph
+        .inits
+        .insert(value, Tensor::new(vec![].into(), vec![0.0f32]));
+    model
+        .graph
+        .inits
+        .insert(axes, Tensor::new(vec![2].into(), vec![-2i64, 3]));
+    model
+        .graph
+        .inits
+        .insert(w, Tensor::new(vec![1, 1, 3, 3].into(), vec![1.0f32; 9]));
+    model.graph.add_node(
+        Node::new(Op::Pad(Pad {
+            mode: "constant".into(),
+            ..Default::default()
+        }))
+        .with_ins(vec![x, pads, value, axes])
+        .with_out(padded),
+    );
+    model.graph.add_node(
+        Node::new(Op::Conv2d(Conv2d {
+            padding: vec![1, 1].into(),
+            group: 1,
+            ..Default::default()
+        }))
+        .with_ins(vec![padded, w])
+        .with_out(y),
+    );
+    model.graph.inputs.push(x);
+    model.graph.outputs.push(y);
+
+    fuse_pad_conv(&mut model);
+
+    assert_eq!(model.graph.nodes.len(), 1);
+    let (_, conv) = model.graph.nodes.iter().next().unwrap();
+    assert_eq!(conv.inputs, vec![x, w]);
+    let Op::Conv2d(ref c) = conv.op else {
+        panic!("Conv2d must remain")
+    };
+    // `[h_begin, w_begin, h_end, w_end]`
+    assert_eq!(c.padding.as_slice(), &[2, 3, 4, 1]);
+    assert_eq!(model.graph.inits.len(), 1);
+}
+
+#[test]
+fn keep_non_zero_pad() {
+    use crate::op::Pad;
+
+    for (mode, value) in [("constant", 1.0), ("reflect", 0.0)] {
+        let mut model = Model::default();
+        let x = model.graph.values.new_val_named("x");
+        let padded = model.graph.values.new_val_named("padded");
+        let w = model.graph.values.new_val_named("w");
+        let y = model.graph.values.new_val_named("y");
+        model
+            .graph
+            .inits
+            .insert(w, Tensor::new(vec![1, 1, 1, 1].into(), vec![1.0f32]));
+        model.graph.add_node(
+            Node::new(Op::Pad(Pad {
+                mode: mode.into(),
+                pads: vec![0, 0, 1, 1, 0, 0, 1, 1],
+                value,
+            }))
+            .with_in(x)
+            .with_out(padded),
+        );
+        model.graph.add_node(
+            Node::new(Op::Conv2d(Conv2d {
+                group: 1,
+                ..Default::default()
+            }))
+            .with_ins(vec![padded, w])
+            .with_out(y),
+        );
+        model.graph.inputs.push(x);
+        model.graph.outputs.push(y);
+
+        fuse_pad_conv(&mut model);
+
+        assert_eq!(model.graph.nodes.len(), 2, "{mode} {value}");
+    }
+}
@@ -0,0 +1,415 @@
+use std::{
+    fmt,
+    time::{Duration, Instant},
+};
+
+use thiserror::Error;
+
+use crate::{
+    analysis::{shape::ShapeError, verify::VerifyError},
+    model::Model,
+    op::Op,
+    value::ValueId,
+};
+
+use super::{
+    common_subexpr_elimination::eliminate_common_subexprs, constant_folding::fold_constants,
+    conv_act_fusion::fuse_conv_act, conv_bn_fusion::fuse_conv_bn,
+    dead_code_elimination::eliminate_dead_code, fast_gelu_fusion::fuse_fast_gelu,
+    gelu_fusion::fuse_gelu, layer_norm_fusion::fuse_layer_norm, pad_conv_fusion::fuse_pad_conv,
+};
+
+/// How aggressively a model is optimized.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum OptimizationLevel {
+    /// No optimization.
+    O0,
+    /// Graph cleanups: constant folding, common subexpression elimination and dead code elimination.
+    O1,
+    /// O1 plus operator fusions.
+    O2,
+    /// O2 repeated until the model stops changing.
+    O3,
+}
+
+#[derive(Debug, Clone, Error)]
+pub enum PassError {
+    #[error("Shape: {0}")]
+    Shape(#[from] ShapeError),
+
+    /// The model didn't pass verification after running `pass`.
+    #[error("Model is broken after {pass}: {}", errors.iter().map(ToString::to_string).collect::<Vec<_>>().join("; "))]
+    Verify {
+        pass: &'static str,
+        errors: Vec<VerifyError>,
+    },
+}
+
+type PassFn = Box<dyn Fn(&mut Model) -> Result<(), ShapeError> + Send + Sync>;
+
+struct Pass {
+    name: &'static str,
+    run: PassFn,
+}
+
+/// Runs a sequence of named optimization passes on a model.
+pub struct PassManager {
+    passes: Vec<Pass>,
+    fixed_point: bool,
+    max_iterations: usize,
+    verify: bool,
+}
+
+/// Statistics of a single pass accumulated over all iterations.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PassStats {
+    pub name: &'static str,
+    pub runs: usize,
+    pub elapsed: Duration,
+    pub removed_nodes: usize,
+}
+
+/// What `PassManager::run` did to a model.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PassReport {
+    pub iterations: usize,
+    pub passes: Vec<PassStats>,
+}
+
+impl PassManager {
+    pub const fn new() -> Self {
+        Self {
+            passes: Vec::new(),
+            fixed_point: false,
+            max_iterations: 8,
+            verify: false,
+        }
+    }
+
+    /// Creates a pass manager with the passes enabled at `level`.
+    pub fn with_level(level: OptimizationLevel) -> Self {
+        let mut pm = Self::new();
+        if level >= OptimizationLevel::O1 {
+            pm = pm
+                .add_pass("fold_constants", infallible(fold_constants))
+                .add_pass(
+                    "eliminate_common_subexprs",
+                    infallible(eliminate_common_subexprs),
+                );
+        }
+        if level >= OptimizationLevel::O2 {
+            pm = pm
+                .add_pass("fuse_layer_norm", infallible(fuse_layer_norm))
+                .add_pass("fuse_gelu", infallible(fuse_gelu))
+                .add_pass("fuse_fast_gelu", infallible(fuse_fast_gelu))
+                .add_pass("fuse_pad_conv", infallible(fuse_pad_conv))
+                // `fuse_conv_bn` must precede `fuse_conv_act`.
+                .add_pass("fuse_conv_bn", infallible(fuse_conv_bn))
+                .add_pass("fuse_conv_act", infallible(fuse_conv_act));
+        }
+        if level >= OptimizationLevel::O1 {
+            pm = pm.add_pass(
+                "eliminate_dead_code",
+                infallible(|m| {
+                    eliminate_dead_code(m);
+                }),
+            );
+        }
+        pm.with_fixed_point(level >= OptimizationLevel::O3)
+    }
+
+    /// Appends a pass. Passes run in the order they are added.
+    pub fn add_pass(
+        mut self,
+        name: &'static str,
+        pass: impl Fn(&mut Model) -> Result<(), ShapeError> + Send + Sync + 'static,
+    ) -> Self {
+        self.passes.push(Pass {
+            name,
+            run: Box::new(pass),
+        });
+        self
+    }
+
+    /// If enabled, the whole pipeline is repeated until the model stops changing
+    /// or `max_iterations` is reached.
+    pub const fn with_fixed_point(mut self, fixed_point: bool) -> Self {
+        self.fixed_point = fixed_point;
+        self
+    }
+
+    pub const fn with_max_iterations(mut self, max_iterations: usize) -> Self {
+        self.max_iterations = max_iterations;
+        self
+    }
+
+    /// If enabled, `Model::verify` runs on the input model and after every pass,
+    /// so that a pass that breaks the model is reported by name. Useful for debugging passes.
+    pub const fn with_verification(mut self, verify: bool) -> Self {
+        self.verify = verify;
+        self
+    }
+
+    pub fn pass_names(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.passes.iter().map(|p| p.name)
+    }
+
+    pub fn run(&self, model: &mut Model) -> Result<PassReport, PassError> {
+        let mut report = PassReport {
+            iterations: 0,
+            passes: self
+                .passes
+                .iter()
+                .map(|p| PassStats {
+                    name: p.name,
+                    runs: 0,
+                    elapsed: Duration::ZERO,
+                    removed_nodes: 0,
+                })
+                .collect(),
+        };
+
+        if self.verify && !self.passes.is_empty() {
+            verify(model, "input")?;
+        }
+
+        while report.iterations < self.max_iterations.max(1) {
+            report.iterations += 1;
+            let before = fingerprint(model);
+
+            for (pass, stats) in self.passes.iter().zip(report.passes.iter_mut()) {
+                let num_nodes = model.graph.nodes.len();
+                let start = Instant::now();
+                (pass.run)(model)?;
+                stats.elapsed += start.elapsed();
+                stats.runs += 1;
+                stats.removed_nodes += num_nodes.saturating_sub(model.graph.nodes.len());
+                if self.verify {
+                    verify(model, pass.name)?;
+                }
+            }
+
+            if !self.fixed_point || fingerprint(model) == before {
+                break;
+            }
+        }
+
+        log::info!("{report}");
+
+        Ok(report)
+    }
+}
+
+impl Default for PassManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PassReport {
+    pub fn total_elapsed(&self) -> Duration {
+        self.passes.iter().map(|p| p.elapsed).sum()
+    }
+}
+
+impl fmt::Display for PassReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Optimized in {} iteration(s), {:?}:",
+            self.iterations,
+            self.total_elapsed()
+        )?;
+        for p in &self.passes {
+            writeln!(
+                f,
+                "  {:<28} runs: {:>2}  removed nodes: {:>5}  {:?}",
+                p.name, p.runs, p.removed_nodes, p.elapsed
+            )?;
+        }
+        Ok(())
+    }
+}
+
+fn verify(model: &Model, pass: &'static str) -> Result<(), PassError> {
+    model
+        .verify()
+        .map_err(|errors| PassError::Verify { pass, errors })
+}
+
+fn infallible(
+    pass: fn(&mut Model),
+) -> impl Fn(&mut Model) -> Result<(), ShapeError> + Send + Sync + 'static {
+    move |model| {
+        pass(model);
+        Ok(())
+    }
+}
+
+/// What passes may change in a model, to detect whether it reached a fixed point.
+/// Values are identified by their indices as passes may rebuild the value arena.
+#[derive(PartialEq)]
+struct Fingerprint {
+    /// Live nodes with their inputs and outputs.
+    nodes: Vec<(Op, Vec<usize>, Vec<usize>)>,
+    outputs: Vec<usize>,
+    /// Initializers and the addresses of their data, so that replaced tensors are detected.
+    inits: Vec<(usize, *const u8)>,
+}
+
+fn fingerprint(model: &Model) -> Fingerprint {
+    let indices = |ids: &[ValueId]| ids.iter().map(|id| id.index()).collect::<Vec<_>>();
+    let mut inits = model
+        .graph
+        .inits
+        .iter()
+        .map(|(id, t)| (id.index(), t.data_as_ptr()))
+        .collect::<Vec<_>>();
+    inits.sort_unstable();
+    Fingerprint {
+        nodes: model
+            .graph
+            .nodes
+            .iter()
+            .filter(|(_, n)| !n.deleted)
+            .map(|(_, n)| (n.op.clone(), indices(&n.inputs), indices(&n.outputs)))
+            .collect(),
+        outputs: indices(&model.graph.outputs),
+        inits,
+    }
+}
+
+#[test]
+fn run_levels() {
+    use crate::{node::Node, tensor::Tensor};
+
+    let build = || {
+        let mut model = Model::default();
+        let x = model.graph.values.new_val_named("x");
+        let c = model.graph.values.new_val_named("c");
+        let c2 = model.graph.values.new_val_named("c2");
+        let a0 = model.graph.values.new_val_named("a0");
+        let a1 = model.graph.values.new_val_named("a1");
+        let y = model.graph.values.new_val_named("y");
+        model
+            .graph
+            .inits
+            .insert(c, Tensor::new(vec![1].into(), vec![2.0f32]));
+        model
+            .graph
+            .add_node(Node::new(Op::Mul).with_ins(vec![c, c]).with_out(c2));
+        model
+            .graph
+            .add_node(Node::new(Op::Add).with_ins(vec![x, c2]).with_out(a0));
+        model
+            .graph
+            .add_node(Node::new(Op::Add).with_ins(vec![x, c2]).with_out(a1));
+        model
+            .graph
+            .add_node(Node::new(Op::Mul).with_ins(vec![a0, a1]).with_out(y));
+        model.graph.inputs.push(x);
+        model.graph.outputs.push(y);
+        model
+    };
+
+    let mut model = build();
+    let report = PassManager::with_level(OptimizationLevel::O0)
+        .run(&mut model)
+        .unwrap();
+    assert!(report.passes.is_empty());
+    assert_eq!(model.graph.nodes.len(), 4);
+
+    let mut model = build();
+    let report = PassManager::with_level(OptimizationLevel::O1)
+        .run(&mut model)
+        .unwrap();
+    assert_eq!(report.iterations, 1);
+    assert_eq!(model.graph.nodes.len(), 2);
+    assert_eq!(
+        report.passes.iter().map(|p| p.removed_nodes).sum::<usize>(),
+        2
+    );
+
+    let mut model = build();
+    let report = PassManager::with_level(OptimizationLevel::O3)
+        .run(&mut model)
+        .unwrap();
+    assert_eq!(report.iterations, 2);
+    assert_eq!(model.graph.nodes.len(), 2);
+    assert!(report.passes.iter().all(|p| p.runs == 2));
+}
+
+#[test]
+fn fixed_point_detects_rewritten_ops() {
+    use crate::node::Node;
+
+    let mut model = Model::default();
+    let x = model.graph.values.new_val_named("x");
+    let y = model.graph.values.new_val_named("y");
+    let z = model.graph.values.new_val_named("z");
+    model
+        .graph
+        .add_node(Node::new(Op::ReLU).with_in(x).with_out(y));
+    model
+        .graph
+        .add_node(Node::new(Op::ReLU).with_in(y).with_out(z));
+    model.graph.inputs.push(x);
+    model.graph.outputs.push(z);
+
+    // Rewrites one ReLU per run without changing the number of nodes or values.
+    let report = PassManager::new()
+        .add_pass("relu_to_sigmoid", |m| {
+            if let Some((_, node)) = m
+                .graph
+                .nodes
+                .iter_mut()
+                .find(|(_, n)| matches!(n.op, Op::ReLU))
+            {
+                node.op = Op::Sigmoid;
+            }
+            Ok(())
+        })
+        .with_fixed_point(true)
+        .run(&mut model)
+        .unwrap();
+    assert_eq!(report.iterations, 3);
+    assert!(model
+        .graph
+        .nodes
+        .iter()
+        .all(|(_, n)| matches!(n.op, Op::Sigmoid)));
+}
+
+#[test]
+fn verify_after_each_pass() {
+    use crate::node::Node;
+
+    let mut model = Model::default();
+    let x = model.graph.values.new_val_named("x");
+    let y = model.graph.values.new_val_named("y");
+    model
+        .graph
+        .add_node(Node::new(Op::ReLU).with_in(x).with_out(y));
+    model.graph.inputs.push(x);
+    model.graph.outputs.push(y);
+
+    let pm = PassManager::new()
+        .add_pass("nop", |_| Ok(()))
+        .add_pass("break_relu", |m| {
+            let (_, relu) = m.graph.nodes.iter_mut().next().unwrap();
+            relu.inputs.push(relu.inputs[0]);
+            Ok(())
+        })
+        .with_verification(true);
+    let Err(PassError::Verify { pass, errors }) = pm.run(&mut model.clone()) else {
+        panic!()
+    };
+    assert_eq!(pass, "break_relu");
+    assert!(matches!(
+        errors[..],
+        [VerifyError::InputArity { actual: 2, .. }]
+    ));
+
+    // Without verification, the broken model goes through.
+    assert!(pm.with_verification(false).run(&mut model).is_ok());
+}
@@ -0,0 +1,649 @@
+//! Post-training static quantization.
+//!
+//! `quantize` rewrites `Conv2d`, `MatMul` and `Gemm` into the QDQ format: activations go through
+//! `QuantizeLinear`+`DequantizeLinear` pairs whose parameters come from calibrated ranges,
+//! and weights are stored as integers followed by `DequantizeLinear`.
+//! The result is still an ordinary ONNX model that can be saved with `save_onnx`.
+
+use std::{borrow::Cow, time::Instant};
+
+use rustc_hash::{FxHashMap, FxHashSet};
+use thiserror::Error;
+
+use crate::{
+    fixed_dim::FixedDimensions,
+    model::Model,
+    node::Node,
+    op::{DequantizeLinear, Op, QuantizeLinear},
+    tensor::{Tensor, TensorElemType, TypedShape},
+    value::ValueId,
+};
+
+#[derive(Debug, Clone, Error)]
+pub enum QuantizeError {
+    #[error("Something went wrong: {0}")]
+    Message(Cow<'static, str>),
+}
+
+/// The range of values observed during calibration.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Range {
+    pub min: f32,
+    pub max: f32,
+}
+
+/// Calibrated ranges of activations. A value with a single range is quantized per tensor,
+/// otherwise it has one range for each channel (axis 1).
+pub type CalibrationTable = FxHashMap<ValueId, Vec<Range>>;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuantizationConfig {
+    activation_type: TensorElemType,
+    weight_type: TensorElemType,
+    per_channel_weights: bool,
+    per_channel_activations: bool,
+}
+
+/// `x ≈ (q - zero_point) * scale`
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Params {
+    scale: f32,
+    zero_point: i32,
+}
+
+impl Range {
+    pub const fn new(min: f32, max: f32) -> Self {
+        Self { min, max }
+    }
+
+    /// Returns the smallest range containing both `self` and `other`.
+    pub fn union(self, other: Self) -> Self {
+        Self {
+            min: self.min.min(other.min),
+            max: self.max.max(other.max),
+        }
+    }
+}
+
+impl QuantizationConfig {
+    pub const fn new() -> Self {
+        Self {
+            activation_type: TensorElemType::U8,
+            weight_type: TensorElemType::I8,
+            per_channel_weights: true,
+            per_channel_activations: false,
+        }
+    }
+
+    /// Either `U8` or `I8`. Activations are quantized asymmetrically.
+    pub const fn with_activation_type(mut self, activation_type: TensorElemType) -> Self {
+        self.activation_type = activation_type;
+        self
+    }
+
+    /// Either `I8` or `U8`. Weights are quantized symmetrically.
+    pub const fn with_weight_type(mut self, weight_type: TensorElemType) -> Self {
+        self.weight_type = weight_type;
+        self
+    }
+
+    /// Per-channel weights need opset 13. Older models fall back to per-tensor weights.
+    pub const fn with_per_channel_weights(mut self, per_channel_weights: bool) -> Self {
+        self.per_channel_weights = per_channel_weights;
+        self
+    }
+
+    /// If enabled, activations are calibrated for each channel (axis 1). Needs opset 13.
+    pub const fn with_per_channel_activations(mut self, per_channel_activations: bool) -> Self {
+        self.per_channel_activations = per_channel_activations;
+        self
+    }
+
+    pub const fn per_channel_activations(&self) -> bool {
+        self.per_channel_activations
+    }
+}
+
+impl Default for QuantizationConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Returns the activations whose ranges `quantize` needs: the inputs and outputs of
+/// quantizable nodes, except for initializers.
+pub fn calibration_targets(model: &Model) -> Vec<ValueId> {
+    let mut targets = vec![];
+    let mut seen = FxHashSet::default();
+    for node_id in model.topo_sort_nodes() {
+        let node = &model.graph.nodes[node_id];
+        if quantizable_inputs(&node.op).is_none() {
+            continue;
+        }
+        for &id in node.inputs.iter().chain(node.outputs.iter()) {
+            if !model.graph.inits.contains_key(&id) && seen.insert(id) {
+                targets.push(id);
+            }
+        }
+    }
+    targets
+}
+
+/// Rewrites `Conv2d` (without fused activation), `MatMul` and `Gemm` nodes into the QDQ format
+/// using the calibrated ranges in `table`. Nodes with an activation input missing from `table`
+/// are left in f32. Returns the number of quantized nodes.
+pub fn quantize(
+    model: &mut Model,
+    table: &CalibrationTable,
+    config: &QuantizationConfig,
+) -> Result<usize, QuantizeError> {
+    let start = Instant::now();
+
+    for ty in [config.activation_type, config.weight_type] {
+        if !ty.is_i8() && !ty.is_u8() {
+            return Err(QuantizeError::Message(
+                format!("Cannot quantize to {ty:?}").into(),
+            ));
+        }
+    }
+    if model.opset_version < 10 {
+        return Err(QuantizeError::Message(
+            format!(
+                "QuantizeLinear needs opset 10, but the model has opset {}",
+                model.opset_version
+            )
+            .into(),
+        ));
+    }
+    if model.opset_version < 13 && table.values().any(|ranges| ranges.len() > 1) {
+        return Err(QuantizeError::Message(
+            "Per-channel activations need opset 13".into(),
+        ));
+    }
+
+    let mut q = Quantizer {
+        per_channel_weights: config.per_channel_weights && model.opset_version >= 13,
+        model,
+        table,
+        config,
+        activations: FxHashMap::default(),
+        weights: FxHashMap::default(),
+        qdq_values: FxHashSet::default(),
+        replaced: vec![],
+    };
+    let mut count = 0;
+
+    for node_id in q.model.topo_sort_nodes() {
+        let node = &q.model.graph.nodes[node_id];
+        let Some((inputs, bias)) = quantizable_inputs(&node.op) else {
+            continue;
+        };
+        let node_inputs = node.inputs.clone();
+        let output = node.outputs[0];
+        let is_weight = |q: &Quantizer, id: &ValueId| {
+            q.model
+                .graph
+                .inits
+                .get(id)
+                .map_or(false, |t| t.elem_ty().is_f32())
+        };
+        if !inputs
+            .iter()
+            .all(|&(i, _)| is_weight(&q, &node_inputs[i]) || q.table.contains_key(&node_inputs[i]))
+        {
+            continue;
+        }
+
+        let mut new_inputs = node_inputs.clone();
+        let mut x_params = None;
+        let mut w_params = None;
+        for (i, axis) in inputs {
+            let id = node_inputs[i];
+            if is_weight(&q, &id) {
+                let (dq, params) = q.dequantize_weight(id, axis);
+                new_inputs[i] = dq;
+                // The bias follows the output channels of the second input.
+                if i == 1 {
+                    w_params = Some(params);
+                }
+            } else {
+                let (dq, params) = q.dequantize_activation(id);
+                new_inputs[i] = dq;
+                x_params = params;
+            }
+        }
+
+        // The bias is quantized to i32 with `scale = x_scale * w_scale` so that it can be added
+        // to the accumulator of an integer kernel.
+        if let (Some(i), Some(x), Some(w)) = (bias, x_params, w_params) {
+            if let Some(&b) = node_inputs.get(i) {
+                let per_channel_of_w =
+                    |t: &Tensor| t.dims().len() == 1 && (w.len() == 1 || t.dims()[0] == w.len());
+                if q.model.graph.inits.get(&b).map_or(false, per_channel_of_w) && is_weight(&q, &b)
+                {
+                    new_inputs[i] = q.dequantize_bias(b, x, &w);
+                }
+            }
+        }
+
+        q.model.graph.nodes[node_id].inputs = new_inputs;
+
+        if let Some(ranges) = q.table.get(&output) {
+            let name = q.name(output);
+            let float = q.model.graph.values.new_val_named(format!("{name}_float"));
+            let params = q.activation_params(ranges);
+            q.model.graph.nodes[node_id].outputs[0] = float;
+            q.insert_qdq(float, output, &name, &params);
+            q.qdq_values.insert(output);
+        }
+
+        count += 1;
+    }
+
+    let replaced = q.replaced;
+    let value_users = model.get_value_users();
+    for id in replaced {
+        if !value_users.contains_key(&id) && !model.graph.outputs.contains(&id) {
+            model.graph.inits.remove(&id);
+        }
+    }
+
+    log::info!("quantize({count}): {:?}", start.elapsed());
+
+    Ok(count)
+}
+
+/// Returns the inputs of `op` to quantize with the channel axis used if they are weights,
+/// and the input index of the bias.
+#[allow(clippy::type_complexity)]
+fn quantizable_inputs(op: &Op) -> Option<(Vec<(usize, Option<usize>)>, Option<usize>)> {
+    match op {
+        Op::Conv2d(c) if c.activation.is_none() => Some((
+            vec![(Op::CONV2D_IN, None), (Op::CONV2D_WEIGHT, Some(0))],
+            Some(Op::CONV2D_BIAS),
+        )),
+        // Weights of `MatMul` are quantized per row of A or per column of B.
+        Op::MatMul => Some((
+            vec![(Op::MATMUL_IN_A, Some(0)), (Op::MATMUL_IN_B, Some(1))],
+            None,
+        )),
+        Op::Gemm(g) => Some((
+            vec![
+                (Op::GEMM_IN_A, Some(g.trans_a as usize)),
+                (Op::GEMM_IN_B, Some(!g.trans_b as usize)),
+            ],
+            Some(Op::GEMM_IN_C),
+        )),
+        _ => None,
+    }
+}
+
+struct Quantizer<'a> {
+    model: &'a mut Model,
+    table: &'a CalibrationTable,
+    config: &'a QuantizationConfig,
+    per_channel_weights: bool,
+    /// Activation -> its `DequantizeLinear` output and parameters if per tensor.
+    activations: FxHashMap<ValueId, (ValueId, Option<Params>)>,
+    /// Weight -> its `DequantizeLinear` output and parameters.
+    weights: FxHashMap<ValueId, (ValueId, Vec<Params>)>,
+    /// Outputs of `DequantizeLinear` that replaced quantized node outputs.
+    qdq_values: FxHashSet<ValueId>,
+    /// Initializers replaced by quantized ones.
+    replaced: Vec<ValueId>,
+}
+
+impl Quantizer<'_> {
+    fn name(&self, id: ValueId) -> String {
+        self.model.graph.values[id]
+            .name
+            .clone()
+            .unwrap_or_else(|| format!("value.{}", id.index()))
+    }
+
+    fn activation_params(&self, ranges: &[Range]) -> Vec<Params> {
+        ranges
+            .iter()
+            .map(|&r| Params::asymmetric(r, self.config.activation_type))
+            .collect()
+    }
+
+    /// Returns the value to use instead of activation `id` and its parameters if per tensor.
+    fn dequantize_activation(&mut self, id: ValueId) -> (ValueId, Option<Params>) {
+        if let Some(&dq) = self.activations.get(&id) {
+            return dq;
+        }
+        let params = self.activation_params(&self.table[&id]);
+        let per_tensor = (params.len() == 1).then_some(params[0]);
+        // The output of a quantized node already went through QDQ.
+        if self.qdq_values.contains(&id) {
+            return (id, per_tensor);
+        }
+        let name = self.name(id);
+        let dq = self
+            .model
+            .graph
+            .values
+            .new_val_named(format!("{name}_dequantized"));
+        self.insert_qdq(id, dq, &name, &params);
+        self.activations.insert(id, (dq, per_tensor));
+        (dq, per_tensor)
+    }
+
+    /// Replaces f32 weight `id` with an integer initializer followed by `DequantizeLinear`.
+    fn dequantize_weight(&mut self, id: ValueId, axis: Option<usize>) -> (ValueId, Vec<Params>) {
+        if let Some(dq) = self.weights.get(&id) {
+            return dq.clone();
+        }
+        let tensor = &self.model.graph.inits[&id];
+        let ty = self.config.weight_type;
+        let axis = axis.filter(|&axis| {
+            self.per_channel_weights && axis < tensor.dims().len() && tensor.dims().len() >= 2
+        });
+        let params = match axis {
+            Some(axis) => tensor
+                .statistics_along_axis::<f32>(axis)
+                .iter()
+                .map(|s| Params::symmetric(Range::new(s.min, s.max), ty))
+                .collect::<Vec<_>>(),
+            None => {
+                let s = tensor.statistics::<f32>();
+                vec![Params::symmetric(Range::new(s.min, s.max), ty)]
+            }
+        };
+        let axis = axis.unwrap_or(1);
+        let quantized = quantize_tensor(tensor, &params, axis, ty);
+
+        let name = self.name(id);
+        let q = self.add_init(format!("{name}_quantized"), quantized);
+        let dq = self
+            .model
+            .graph
+            .values
+            .new_val_named(format!("{name}_dequantized"));
+        self.add_dequantize(q, dq, &name, &params, ty, axis);
+        self.weights.insert(id, (dq, params.clone()));
+        self.replaced.push(id);
+        (dq, params)
+    }
+
+    /// Replaces f32 bias `id` with an i32 initializer followed by `DequantizeLinear`.
+    fn dequantize_bias(&mut self, id: ValueId, x: Params, w: &[Params]) -> ValueId {
+        let params = w
+            .iter()
+            .map(|w| Params {
+                scale: x.scale * w.scale,
+                zero_point: 0,
+            })
+            .collect::<Vec<_>>();
+        let tensor = &self.model.graph.inits[&id];
+        let quantized = quantize_tensor(tensor, &params, 0, TensorElemType::I32);
+
+        let name = self.name(id);
+        let q = self.add_init(format!("{name}_quantized"), quantized);
+        let dq = self
+            .model
+            .graph
+            .values
+            .new_val_named(format!("{name}_dequantized"));
+        self.add_dequantize(q, dq, &name, &params, TensorElemType::I32, 0);
+        self.replaced.push(id);
+        dq
+    }
+
+    /// Inserts `x -> QuantizeLinear -> DequantizeLinear -> y`.
+    fn insert_qdq(&mut self, x: ValueId, y: ValueId, name: &str, params: &[Params]) {
+        let ty = self.config.activation_type;
+        let (scale, zero_point) = self.add_params(name, params, ty);
+        let q = self
+            .model
+            .graph
+            .values
+            .new_val_named(format!("{name}_quantized"));
+        self.model.graph.add_node(
+            Node::new(Op::QuantizeLinear(QuantizeLinear { axis: 1 }))
+                .with_name(format!("{name}_QuantizeLinear"))
+                .with_ins(vec![x, scale, zero_point])
+                .with_out(q),
+        );
+        self.model.graph.add_node(
+            Node::new(Op::DequantizeLinear(DequantizeLinear { axis: 1 }))
+                .with_name(format!("{name}_DequantizeLinear"))
+                .with_ins(vec![q, scale, zero_point])
+                .with_out(y),
+        );
+    }
+
+    fn add_dequantize(
+        &mut self,
+        x: ValueId,
+        y: ValueId,
+        name: &str,
+        params: &[Params],
+        ty: TensorElemType,
+        axis: usize,
+    ) {
+        let (scale, zero_point) = self.add_params(name, params, ty);
+        self.model.graph.add_node(
+            Node::new(Op::DequantizeLinear(DequantizeLinear { axis: axis as i64 }))
+                .with_name(format!("{name}_DequantizeLinear"))
+                .with_ins(vec![x, scale, zero_point])
+                .with_out(y),
+        );
+    }
+
+    /// Adds initializers for the scales and zero points. They are scalars if per tensor.
+    fn add_params(
+        &mut self,
+        name: &str,
+        params: &[Params],
+        ty: TensorElemType,
+    ) -> (ValueId, ValueId) {
+        let dims: FixedDimensions = if params.len() == 1 {
+            vec![].into()
+        } else {
+            vec![params.len()].into()
+        };
+        let scale = Tensor::new(
+            dims.clone(),
+            params.iter().map(|p| p.scale).collect::<Vec<_>>(),
+        );
+        let zero_point = int_tensor(
+            dims,
+            params.iter().map(|p| p.zero_point).collect::<Vec<_>>(),
+            ty,
+        );
+        (
+            self.add_init(format!("{name}_scale"), scale),
+            self.add_init(format!("{name}_zero_point"), zero_point),
+        )
+    }
+
+    fn add_init(&mut self, name: String, tensor: Tensor) -> ValueId {
+        let shape = TypedShape::new(tensor.dims().clone().into(), tensor.elem_ty());
+        let id = self
+            .model
+            .graph
+            .values
+            .new_val_named_and_shaped(name, shape);
+        self.model.graph.inits.insert(id, tensor);
+        id
+    }
+}
+
+impl Params {
+    /// Maps `range` onto the whole range of `ty`.
+    fn asymmetric(range: Range, ty: TensorElemType) -> Self {
+        let (qmin, qmax) = int_range(ty);
+        // Zero must be exactly representable (e.g. for zero padding).
+        let (min, max) = (range.min.min(0.), range.max.max(0.));
+        let scale = non_zero((max - min) / (qmax - qmin) as f32);
+        let zero_point = (qmin as f32 - min / scale)
+            .round()
+            .clamp(qmin as f32, qmax as f32) as i32;
+        Self { scale, zero_point }
+    }
+
+    /// Maps `range` onto `ty` keeping zero at the center of `ty`.
+    fn symmetric(range: Range, ty: TensorElemType) -> Self {
+        let (qmin, qmax) = int_range(ty);
+        let zero_point = (qmin + qmax + 1) / 2;
+        let absmax = range.min.abs().max(range.max.abs());
+        let scale = non_zero(absmax / (qmax - zero_point) as f32);
+        Self { scale, zero_point }
+    }
+}
+
+fn int_range(ty: TensorElemType) -> (i32, i32) {
+    match ty {
+        TensorElemType::I8 => (i8::MIN as i32, i8::MAX as i32),
+        TensorElemType::U8 => (u8::MIN as i32, u8::MAX as i32),
+        TensorElemType::I32 => (i32::MIN, i32::MAX),
+        _ => unreachable!(),
+    }
+}
+
+/// All-zero tensors would give a zero scale.
+fn non_zero(scale: f32) -> f32 {
+    if scale > 0. && scale.is_finite() {
+        scale
+    } else {
+        1.
+    }
+}
+
+fn quantize_tensor(tensor: &Tensor, params: &[Params], axis: usize, ty: TensorElemType) -> Tensor {
+    let (num, inner) = if params.len() == 1 {
+        (1, 1)
+    } else {
+        (
+            tensor.dims()[axis],
+            tensor.dims()[axis + 1..].iter().product::<usize>(),
+        )
+    };
+    let (qmin, qmax) = int_range(ty);
+    let data = tensor
+        .data::<f32>()
+        .iter()
+        .enumerate()
+        .map(|(i, &x)| {
+            let p = params[(i / inner) % num];
+            ((x / p.scale).round_ties_even() as f64 + p.zero_point as f64)
+                .clamp(qmin as f64, qmax as f64) as i32
+        })
+        .collect();
+    int_tensor(tensor.dims().clone(), data, ty)
+}
+
+fn int_tensor(dims: FixedDimensions, data: Vec<i32>, ty: TensorElemType) -> Tensor {
+    match ty {
+        TensorElemType::I8 => Tensor::new(dims, data.into_iter().map(|x| x as i8).collect()),
+        TensorElemType::U8 => Tensor::new(dims, data.into_iter().map(|x| x as u8).collect()),
+        TensorElemType::I32 => Tensor::new(dims, data),
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn quantize_conv() {
+    use crate::{onnx::load::load_onnx, onnx::save::save_onnx, op::Conv2d};
+
+    let shape =
+        |dims: Vec<usize>| TypedShape::new(FixedDimensions::from(dims).into(), TensorElemType::F32);
+    let mut model = Model {
+        opset_version: 13,
+        ..Default::default()
+    };
+    let x = model
+        .graph
+        .values
+        .new_val_named_and_shaped("x", shape(vec![1, 1, 2, 2]));
+    let w = model
+        .graph
+        .values
+        .new_val_named_and_shaped("w", shape(vec![2, 1, 1, 1]));
+    let b = model
+        .graph
+        .values
+        .new_val_named_and_shaped("b", shape(vec![2]));
+    let y = model
+        .graph
+        .values
+        .new_val_named_and_shaped("y", shape(vec![1, 2, 2, 2]));
+    model
+        .graph
+        .inits
+        .insert(w, Tensor::new(vec![2, 1, 1, 1].into(), vec![0.5f32, -2.0]));
+    model
+        .graph
+        .inits
+        .insert(b, Tensor::new(vec![2].into(), vec![0.25f32, 1.0]));
+    model.graph.add_node(
+        Node::new(Op::Conv2d(Conv2d {
+            kernel_shape: vec![1, 1].into(),
+            strides: vec![1, 1].into(),
+            padding: vec![0, 0, 0, 0].into(),
+            dilations: vec![1, 1].into(),
+            group: 1,
+            ..Default::default()
+        }))
+        .with_ins(vec![x, w, b])
+        .with_out(y),
+    );
+    model.graph.inputs.push(x);
+    model.graph.outputs.push(y);
+
+    let table = CalibrationTable::from_iter([
+        (x, vec![Range::new(0.0, 2.55)]),
+        (y, vec![Range::new(-2.55, 7.65)]),
+    ]);
+    let count = quantize(&mut model, &table, &QuantizationConfig::new()).unwrap();
+    assert_eq!(count, 1);
+    assert!(model.verify().is_ok());
+
+    let ops = |model: &Model| {
+        model
+            .topo_sort_nodes()
+            .into_iter()
+            .map(|id| model.graph.nodes[id].op.name())
+            .filter(|&name| name == "QuantizeLinear" || name == "DequantizeLinear")
+            .count()
+    };
+    // QDQ for x and y, and DequantizeLinear for w and b.
+    assert_eq!(ops(&model), 6);
+    assert!(!model.graph.inits.contains_key(&w));
+    assert!(!model.graph.inits.contains_key(&b));
+    assert_eq!(model.graph.outputs, vec![y]);
+
+    fn init<'a>(model: &'a Model, name: &str) -> &'a Tensor {
+        &model.graph.inits[&model.lookup_named_value(name).unwrap()]
+    }
+    // x: scale = 2.55 / 255, zero point = 0
+    assert_eq!(init(&model, "x_zero_point").data::<u8>(), &[0]);
+    // w: symmetric per output channel.
+    assert_eq!(init(&model, "w_quantized").data::<i8>(), &[127, -127]);
+    assert!(init(&model, "w_scale").allclose(&[0.5f32 / 127., 2.0 / 127.]));
+    // b: scale = x_scale * w_scale
+    assert_eq!(init(&model, "b_quantized").data::<i32>(), &[6350, 6350]);
+    // y: scale = 10.2 / 255, zero point = round(2.55 / scale)
+    assert_eq!(init(&model, "y_zero_point").data::<u8>(), &[64]);
+
+    let path = std::env::temp_dir().join("altius_quantize_conv.onnx");
+    save_onnx(&model, &path).unwrap();
+    let loaded = load_onnx(&path).unwrap();
+    let _ = std::fs::remove_file(&path);
+    assert_eq!(ops(&loaded), 6);
+    let w = loaded.lookup_named_value("w_quantized").unwrap();
+    assert_eq!(loaded.graph.inits[&w].data::<i8>(), &[127, -127]);
+    let dq = loaded
+        .graph
+        .nodes
+        .iter()
+        .find(|(_, n)| n.inputs.first() == Some(&w))
+        .unwrap()
+        .1;
+    assert!(matches!(
+        dq.op,
+        Op::DequantizeLinear(DequantizeLinear { axis: 0 })
+    ));
+}
@@ -4,6 +4,7 @@ use crate::{
     dim::{Dimension, Dimensions},
     fixed_dim::{FixedDimension, FixedDimensions},
 };
+use half::{bf16, f16};
 use ndarray::{CowArray, IxDyn};
 use rand::{
     distributions::Standard, prelude::Distribution, rngs::StdRng, thread_rng, Rng, SeedableRng,
@@ -12,11 +13,14 @@ use rand::{
 thread_local!(static RNG: RefCell<StdRng> =
     RefCell::new(StdRng::from_rng(thread_rng()).expect("Failed to seed StdRng.")));
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Clone)]
 pub struct Tensor {
     dims: FixedDimensions,
     stride: FixedDimensions,
     data: Arc<Vec<u8>>,
+    /// Byte offset of the elements in `data` if the tensor is a view of an arena shared with
+    /// other tensors. See [`Tensor::view`].
+    offset: Option<usize>,
     elem_ty: TensorElemType,
 }
 
@@ -47,6 +51,10 @@ where
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum TensorElemType {
     Bool,
+    I8,
+    U8,
+    F16,
+    BF16,
     F32,
     I32,
     I64,
@@ -71,6 +79,7 @@ impl Tensor {
                     data.capacity() * std::mem::size_of::<T>(),
                 )
             }),
+            offset: None,
             dims,
         }
     }
@@ -83,6 +92,7 @@ impl Tensor {
             data: Arc::new(unsafe {
                 Vec::from_raw_parts(data.as_ptr() as *mut u8, data.len(), data.capacity())
             }),
+            offset: None,
             dims,
         }
     }
@@ -97,6 +107,10 @@ impl Tensor {
     pub fn empty_of_type(ty: TensorElemType, dims: FixedDimensions) -> Self {
         match ty {
             TensorElemType::Bool => Self::new::<bool>(dims, vec![]),
+            TensorElemType::I8 => Self::new::<i8>(dims, vec![]),
+            TensorElemType::U8 => Self::new::<u8>(dims, vec![]),
+            TensorElemType::F16 => Self::new::<f16>(dims, vec![]),
+            TensorElemType::BF16 => Self::new::<bf16>(dims, vec![]),
             TensorElemType::F32 => Self::new::<f32>(dims, vec![]),
             TensorElemType::I32 => Self::new::<i32>(dims, vec![]),
             TensorElemType::I64 => Self::new::<i64>(dims, vec![]),
@@ -106,7 +120,11 @@ impl Tensor {
     pub fn zeros_of_type(ty: TensorElemType, dims: FixedDimensions) -> Self {
         let total_elems = dims.total_elems();
         match ty {
-            TensorElemType::Bool => Self::new(dims, vec![0u8; total_elems]),
+            TensorElemType::Bool => Self::new(dims, vec![false; total_elems]),
+            TensorElemType::I8 => Self::new(dims, vec![0i8; total_elems]),
+            TensorElemType::U8 => Self::new(dims, vec![0u8; total_elems]),
+            TensorElemType::F16 => Self::new(dims, vec![f16::ZERO; total_elems]),
+            TensorElemType::BF16 => Self::new(dims, vec![bf16::ZERO; total_elems]),
             TensorElemType::F32 => Self::new(dims, vec![0.0f32; total_elems]),
             TensorElemType::I32 => Self::new(dims, vec![0i32; total_elems]),
             TensorElemType::I64 => Self::new(dims, vec![0i64; total_elems]),
@@ -124,7 +142,12 @@ impl Tensor {
 
         let total_elems = dims.total_elems();
         match ty {
-            TensorElemType::Bool => Self::new(dims, uninit_of::<u8>(total_elems)),
+            // Any bit pattern other than 0 and 1 is an invalid `bool`.
+            TensorElemType::Bool => Self::new(dims, vec![false; total_elems]),
+            TensorElemType::I8 => Self::new(dims, uninit_of::<i8>(total_elems)),
+            TensorElemType::U8 => Self::new(dims, uninit_of::<u8>(total_elems)),
+            TensorElemType::F16 => Self::new(dims, uninit_of::<f16>(total_elems)),
+            TensorElemType::BF16 => Self::new(dims, uninit_of::<bf16>(total_elems)),
             TensorElemType::F32 => Self::new(dims, uninit_of::<f32>(total_elems)),
             TensorElemType::I32 => Self::new(dims, uninit_of::<i32>(total_elems)),
             TensorElemType::I64 => Self::new(dims, uninit_of::<i64>(total_elems)),
@@ -178,6 +201,22 @@ impl Tensor {
         let total_elems = dims.total_elems();
         match ty {
             TensorElemType::Bool => Self::new(dims, new::<bool>(total_elems)),
+            TensorElemType::I8 => Self::new(dims, new::<i8>(total_elems)),
+            TensorElemType::U8 => Self::new(dims, new::<u8>(total_elems)),
+            TensorElemType::F16 => Self::new(
+                dims,
+                new::<f32>(total_elems)
+                    .into_iter()
+                    .map(f16::from_f32)
+                    .collect(),
+            ),
+            TensorElemType::BF16 => Self::new(
+                dims,
+                new::<f32>(total_elems)
+                    .into_iter()
+                    .map(bf16::from_f32)
+                    .collect(),
+            ),
             TensorElemType::F32 => Self::new(dims, new::<f32>(total_elems)),
             TensorElemType::I32 => Self::new(dims, new::<i32>(total_elems)),
             TensorElemType::I64 => Self::new(dims, new::<i64>(total_elems)),
@@ -197,6 +236,53 @@ impl Tensor {
                 data.capacity() * std::mem::size_of::<T>(),
             )
         });
+        self.offset = None;
+    }
+
+    /// Returns a tensor of given type and shape whose elements are the bytes of `self` from
+    /// `offset`, without copying them. Writing to either tensor is seen by the other.
+    /// This is used to place tensors in a preallocated arena.
+    ///
+    /// # Safety
+    ///
+    /// Views are written through the elements shared with `self`, so the caller must make
+    /// sure that bytes in use by one view are never accessed through `self` or another view.
+    /// Overlapping views may coexist only if their uses don't interleave, as for values placed
+    /// by a memory plan, whose regions are reused only after their previous values are dead.
+    pub unsafe fn view(
+        &self,
+        offset: usize,
+        elem_ty: TensorElemType,
+        dims: FixedDimensions,
+    ) -> Self {
+        assert!(offset + dims.total_elems() * elem_ty.size() <= self.data_as_bytes().len());
+        let start = self.offset.unwrap_or(0) + offset;
+        assert_eq!(
+            (self.data.as_ptr() as usize + start) % elem_ty.size(),
+            0,
+            "Misaligned view"
+        );
+        Self {
+            stride: compute_strides(&dims),
+            dims,
+            data: self.data.clone(),
+            offset: Some(start),
+            elem_ty,
+        }
+    }
+
+    /// Returns true if the tensor is a view of an arena created by [`Tensor::view`].
+    pub fn is_view(&self) -> bool {
+        self.offset.is_some()
+    }
+
+    /// Copies the elements of a view into a buffer of its own, so that they outlive the
+    /// arena region. Does nothing if the tensor isn't a view.
+    pub fn detach(&mut self) {
+        if self.offset.is_some() {
+            self.data = Arc::new(self.data_as_bytes().to_vec());
+            self.offset = None;
+        }
     }
 
     // pub fn reshape_into(mut self, dims: FixedDimensions) -> Self {
@@ -307,38 +393,64 @@ impl Tensor {
 
     pub fn data<T: TensorElemTypeExt>(&self) -> &[T] {
         assert_eq!(self.elem_ty, T::get_type());
+        let bytes = self.data_as_bytes();
         unsafe {
             std::slice::from_raw_parts(
-                self.data.as_ptr() as *const T,
-                self.data.len() / std::mem::size_of::<T>(),
+                bytes.as_ptr() as *const T,
+                bytes.len() / std::mem::size_of::<T>(),
             )
         }
     }
 
     pub fn data_mut<T: TensorElemTypeExt>(&mut self) -> &mut [T] {
         assert_eq!(self.elem_ty, T::get_type());
+        let bytes = self.data_as_bytes();
         unsafe {
             std::slice::from_raw_parts_mut(
-                self.data.as_ptr() as *mut T,
-                self.data.len() / std::mem::size_of::<T>(),
+                bytes.as_ptr() as *mut T,
+                bytes.len() / std::mem::size_of::<T>(),
             )
         }
     }
 
     pub fn data_as_ptr(&self) -> *const u8 {
-        self.data.as_ptr()
+        self.data_as_bytes().as_ptr()
     }
 
     pub fn data_as_mut_ptr(&mut self) -> *mut u8 {
-        self.data.as_ptr() as *mut u8
+        self.data_as_bytes().as_ptr() as *mut u8
+    }
+
+    pub fn data_as_bytes_mut(&mut self) -> &mut [u8] {
+        let bytes = self.data_as_bytes();
+        unsafe { std::slice::from_raw_parts_mut(bytes.as_ptr() as *mut u8, bytes.len()) }
     }
 
     pub fn data_as_bytes(&self) -> &[u8] {
-        self.data.as_ref()
+        match self.offset {
+            Some(offset) => {
+                &self.data[offset..offset + self.dims.total_elems() * self.elem_ty.size()]
+            }
+            None => self.data.as_ref(),
+        }
     }
 
+    /// Makes the tensor hold the elements of `other`. They are shared unless either tensor is
+    /// a view of an arena, whose elements are valid only while its region is assigned.
     pub fn copy_data_from(&mut self, other: &Self) {
-        self.data = other.data.clone()
+        match (self.offset, other.offset) {
+            (None, None) => self.data = other.data.clone(),
+            (Some(_), _) if self.data_as_bytes().len() == other.data_as_bytes().len() => {
+                let len = other.data_as_bytes().len();
+                unsafe {
+                    std::ptr::copy_nonoverlapping(other.data_as_ptr(), self.data_as_mut_ptr(), len)
+                }
+            }
+            _ => {
+                self.data = Arc::new(other.data_as_bytes().to_vec());
+                self.offset = None;
+            }
+        }
     }
 
     pub fn elem_ty(&self) -> TensorElemType {
@@ -362,8 +474,29 @@ impl Tensor {
         x.iter().zip(other.iter()).all(|(&x, &y)| T::close(x, y))
     }
 
+    /// Converts a floating-point tensor (`F16`, `BF16` or `F32`) to another floating-point type.
+    /// Returns `None` if either type is not a floating-point type.
+    pub fn cast_float(&self, to: TensorElemType) -> Option<Self> {
+        let data: Vec<f32> = match self.elem_ty {
+            TensorElemType::F32 if to.is_f32() => return Some(self.clone()),
+            TensorElemType::F16 if to.is_f16() => return Some(self.clone()),
+            TensorElemType::BF16 if to.is_bf16() => return Some(self.clone()),
+            TensorElemType::F32 => self.data::<f32>().to_vec(),
+            TensorElemType::F16 => self.data::<f16>().iter().map(|x| x.to_f32()).collect(),
+            TensorElemType::BF16 => self.data::<bf16>().iter().map(|x| x.to_f32()).collect(),
+            _ => return None,
+        };
+        let dims = self.dims.clone();
+        Some(match to {
+            TensorElemType::F32 => Self::new(dims, data),
+            TensorElemType::F16 => Self::new(dims, data.into_iter().map(f16::from_f32).collect()),
+            TensorElemType::BF16 => Self::new(dims, data.into_iter().map(bf16::from_f32).collect()),
+            _ => return None,
+        })
+    }
+
     pub fn verify(&self) -> bool {
-        self.data.len() / self.elem_ty.size() == self.dims.total_elems()
+        self.data_as_bytes().len() / self.elem_ty.size() == self.dims.total_elems()
     }
 
     pub fn strides_for_broadcasting(&self, dims: &[FixedDimension]) -> Option<FixedDimensions> {
@@ -422,6 +555,30 @@ impl Tensor {
             mean: data.iter().sum::<T>().into() / data.len() as f64,
         }
     }
+
+    /// Computes `Statistics` for each index along `axis` (e.g. for each channel).
+    pub fn statistics_along_axis<'a, T>(&'a self, axis: usize) -> Vec<Statistics<T>>
+    where
+        T: TensorElemTypeExt + Sum<&'a T> + 'a + Into<f64>,
+    {
+        let data = self.data::<T>();
+        let num = self.dims[axis];
+        let inner = self.dims[axis + 1..].iter().product::<usize>().max(1);
+        (0..num)
+            .map(|i| {
+                let slice = || data.chunks(inner).skip(i).step_by(num).flatten();
+                Statistics {
+                    max: *slice()
+                        .max_by(|x, y| x.partial_cmp(y).unwrap_or(Ordering::Equal))
+                        .unwrap_or(&T::zero()),
+                    min: *slice()
+                        .min_by(|x, y| x.partial_cmp(y).unwrap_or(Ordering::Equal))
+                        .unwrap_or(&T::zero()),
+                    mean: slice().sum::<T>().into() / (data.len() / num) as f64,
+                }
+            })
+            .collect()
+    }
 }
 
 impl TypedFixedShape {
@@ -454,7 +611,11 @@ impl From<FixedDimensions> for Dimensions {
 impl TensorElemType {
     pub fn size(&self) -> usize {
         match self {
-            TensorElemType::Bool => std::mem::size_of::<u8>(),
+            TensorElemType::Bool => std::mem::size_of::<bool>(),
+            TensorElemType::I8 => std::mem::size_of::<i8>(),
+            TensorElemType::U8 => std::mem::size_of::<u8>(),
+            TensorElemType::F16 => std::mem::size_of::<f16>(),
+            TensorElemType::BF16 => std::mem::size_of::<bf16>(),
             TensorElemType::F32 => std::mem::size_of::<f32>(),
             TensorElemType::I32 => std::mem::size_of::<i32>(),
             TensorElemType::I64 => std::mem::size_of::<i64>(),
@@ -465,10 +626,31 @@ impl TensorElemType {
         matches!(self, Self::Bool)
     }
 
+    pub fn is_i8(&self) -> bool {
+        matches!(self, Self::I8)
+    }
+
+    pub fn is_u8(&self) -> bool {
+        matches!(self, Self::U8)
+    }
+
+    pub fn is_f16(&self) -> bool {
+        matches!(self, Self::F16)
+    }
+
+    pub fn is_bf16(&self) -> bool {
+        matches!(self, Self::BF16)
+    }
+
     pub fn is_f32(&self) -> bool {
         matches!(self, Self::F32)
     }
 
+    /// Returns true if the type is `F16` or `BF16`.
+    pub fn is_half(&self) -> bool {
+        matches!(self, Self::F16 | Self::BF16)
+    }
+
     pub fn is_i32(&self) -> bool {
         matches!(self, Self::I32)
     }
@@ -478,9 +660,23 @@ impl TensorElemType {
     }
 }
 
+impl TensorElemTypeExt for i8 {
+    fn get_type() -> TensorElemType {
+        TensorElemType::I8
+    }
+
+    fn zero() -> Self {
+        0
+    }
+
+    fn close(a: Self, b: Self) -> bool {
+        a == b
+    }
+}
+
 impl TensorElemTypeExt for u8 {
     fn get_type() -> TensorElemType {
-        TensorElemType::Bool
+        TensorElemType::U8
     }
 
     fn zero() -> Self {
@@ -523,6 +719,44 @@ impl TensorElemTypeExt for f32 {
     }
 }
 
+impl TensorElemTypeExt for f16 {
+    fn get_type() -> TensorElemType {
+        TensorElemType::F16
+    }
+
+    fn zero() -> Self {
+        f16::ZERO
+    }
+
+    fn close(a: Self, b: Self) -> bool {
+        // f16 has an 11-bit significand.
+        let (a, b) = (a.to_f32(), b.to_f32());
+        let atol = 1e-3;
+        let rtol = 1e-3;
+        ((a - b).abs() <= (atol + rtol * b.abs()))
+            || (a.is_infinite() && b.is_infinite() && a.is_sign_positive() == b.is_sign_positive())
+    }
+}
+
+impl TensorElemTypeExt for bf16 {
+    fn get_type() -> TensorElemType {
+        TensorElemType::BF16
+    }
+
+    fn zero() -> Self {
+        bf16::ZERO
+    }
+
+    fn close(a: Self, b: Self) -> bool {
+        // bf16 has an 8-bit significand.
+        let (a, b) = (a.to_f32(), b.to_f32());
+        let atol = 1e-2;
+        let rtol = 1e-2;
+        ((a - b).abs() <= (atol + rtol * b.abs()))
+            || (a.is_infinite() && b.is_infinite() && a.is_sign_positive() == b.is_sign_positive())
+    }
+}
+
 impl TensorElemTypeExt for i32 {
     fn get_type() -> TensorElemType {
         TensorElemType::I32
@@ -559,6 +793,27 @@ fn compute_strides(dims: &FixedDimensions) -> FixedDimensions {
     strides.into()
 }
 
+impl PartialEq for Tensor {
+    fn eq(&self, other: &Self) -> bool {
+        self.dims == other.dims
+            && self.elem_ty == other.elem_ty
+            && self.data_as_bytes() == other.data_as_bytes()
+    }
+}
+
+impl Eq for Tensor {}
+
+impl fmt::Debug for Tensor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Tensor")
+            .field("dims", &self.dims)
+            .field("stride", &self.stride)
+            .field("data", &self.data_as_bytes())
+            .field("elem_ty", &self.elem_ty)
+            .finish()
+    }
+}
+
 impl fmt::Display for Tensor {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         fn dump<T: TensorElemTypeExt + fmt::Debug>(
@@ -583,10 +838,14 @@ impl fmt::Display for Tensor {
 
         write!(f, "Tensor({:?}, {:?}, ", self.dims, self.elem_ty)?;
         match self.elem_ty {
+            TensorElemType::F16 => dump(f, self.data::<f16>())?,
+            TensorElemType::BF16 => dump(f, self.data::<bf16>())?,
             TensorElemType::F32 => dump(f, self.data::<f32>())?,
             TensorElemType::I32 => dump(f, self.data::<i32>())?,
             TensorElemType::I64 => dump(f, self.data::<i64>())?,
             TensorElemType::Bool => dump(f, self.data::<bool>())?,
+            TensorElemType::I8 => dump(f, self.data::<i8>())?,
+            TensorElemType::U8 => dump(f, self.data::<u8>())?,
         }
         write!(f, ")")
     }
@@ -649,10 +908,18 @@ fn test_zeros() {
 #[test]
 fn test_tensor_elem_type() {
     assert_eq!(TensorElemType::Bool.size(), 1);
+    assert_eq!(TensorElemType::I8.size(), 1);
+    assert_eq!(TensorElemType::U8.size(), 1);
+    assert_eq!(TensorElemType::F16.size(), 2);
+    assert_eq!(TensorElemType::BF16.size(), 2);
     assert_eq!(TensorElemType::F32.size(), 4);
     assert_eq!(TensorElemType::I32.size(), 4);
     assert_eq!(TensorElemType::I64.size(), 8);
     assert!(TensorElemType::Bool.is_bool());
+    assert!(TensorElemType::I8.is_i8());
+    assert!(TensorElemType::U8.is_u8());
+    assert!(TensorElemType::F16.is_f16());
+    assert!(TensorElemType::BF16.is_bf16());
     assert!(TensorElemType::F32.is_f32());
     assert!(TensorElemType::I32.is_i32());
     assert!(TensorElemType::I64.is_i64());
@@ -660,7 +927,11 @@ fn test_tensor_elem_type() {
 
 #[test]
 fn test_tensor_elem_type_ext() {
-    assert!(<u8 as TensorElemTypeExt>::get_type().is_bool());
+    assert!(<bool as TensorElemTypeExt>::get_type().is_bool());
+    assert!(<i8 as TensorElemTypeExt>::get_type().is_i8());
+    assert!(<u8 as TensorElemTypeExt>::get_type().is_u8());
+    assert!(<f16 as TensorElemTypeExt>::get_type().is_f16());
+    assert!(<bf16 as TensorElemTypeExt>::get_type().is_bf16());
     assert!(<f32 as TensorElemTypeExt>::get_type().is_f32());
     assert!(<i32 as TensorElemTypeExt>::get_type().is_i32());
     assert!(<i64 as TensorElemTypeExt>::get_type().is_i64());
@@ -683,6 +954,8 @@ fn test_tensor_rand() {
     check::<i32>();
     check::<i64>();
     check::<bool>();
+    check::<i8>();
+    check::<u8>();
 }
 
 #[test]
@@ -696,8 +969,82 @@ fn test_tensor_rand_of_type() {
         assert_ne!(x, y);
     }
 
+    check(F16);
+    check(BF16);
     check(F32);
     check(I32);
     check(I64);
     check(Bool);
+    check(I8);
+    check(U8);
+}
+
+#[test]
+fn test_statistics_along_axis() {
+    let t = Tensor::new(
+        vec![2, 2, 2].into(),
+        vec![1.0f32, 2.0, -3.0, 4.0, 5.0, -6.0, 7.0, 8.0],
+    );
+    let stats = t.statistics_along_axis::<f32>(1);
+    assert_eq!(stats.len(), 2);
+    assert_eq!(
+        (stats[0].min, stats[0].max, stats[0].mean),
+        (-6.0, 5.0, 0.5)
+    );
+    assert_eq!(
+        (stats[1].min, stats[1].max, stats[1].mean),
+        (-3.0, 8.0, 4.0)
+    );
+}
+
+#[test]
+fn test_cast_float() {
+    let x = Tensor::new(vec![4].into(), vec![1.0f32, -0.5, 3.140625, 65504.0]);
+    let h = x.cast_float(TensorElemType::F16).unwrap();
+    assert_eq!(h.elem_ty(), TensorElemType::F16);
+    assert_eq!(h.data_as_bytes().len(), 8);
+    assert!(h
+        .cast_float(TensorElemType::F32)
+        .unwrap()
+        .allclose(x.data::<f32>()));
+    let b = h.cast_float(TensorElemType::BF16).unwrap();
+    assert!(b.allclose(&[1.0f32, -0.5, 3.140625, 65504.0].map(bf16::from_f32)));
+    assert!(Tensor::zeros::<i64>(vec![1].into())
+        .cast_float(TensorElemType::F32)
+        .is_none());
+}
+
+#[test]
+fn test_view() {
+    let arena = Tensor::zeros::<i64>(vec![4].into());
+    // SAFETY: `a` and `b` don't overlap.
+    let mut a = unsafe { arena.view(0, TensorElemType::F32, vec![2, 2].into()) };
+    let mut b = unsafe { arena.view(16, TensorElemType::I32, vec![4].into()) };
+    assert!(a.is_view() && !arena.is_view());
+    a.data_mut::<f32>().copy_from_slice(&[1., 2., 3., 4.]);
+    b.data_mut::<i32>().copy_from_slice(&[5, 6, 7, 8]);
+    assert_eq!(a.data::<f32>(), &[1., 2., 3., 4.]);
+    assert_eq!(b.data_as_bytes().len(), 16);
+    assert_eq!(a, Tensor::new(vec![2, 2].into(), vec![1.0f32, 2., 3., 4.]));
+    drop(a);
+
+    // Copying into a view writes to the arena instead of sharing the elements.
+    // SAFETY: `c` takes the region of `a`, which is no longer used.
+    let mut c = unsafe { arena.view(0, TensorElemType::I32, vec![4].into()) };
+    c.copy_data_from(&b);
+    assert!(c.is_view());
+    assert_eq!(c.data_as_ptr(), arena.data_as_ptr());
+    assert_eq!(c.data::<i32>(), &[5, 6, 7, 8]);
+
+    // A detached tensor keeps its elements even if the arena is overwritten.
+    let mut d = Tensor::zeros::<i32>(vec![4].into());
+    d.copy_data_from(&b);
+    b.detach();
+    // SAFETY: `b` no longer uses the region.
+    unsafe { arena.view(16, TensorElemType::I32, vec![4].into()) }
+        .data_mut::<i32>()
+        .fill(0);
+    assert!(!b.is_view() && !d.is_view());
+    assert_eq!(b.data::<i32>(), &[5, 6, 7, 8]);
+    assert_eq!(d.data::<i32>(), &[5, 6, 7, 8]);
 }
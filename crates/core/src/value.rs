@@ -12,7 +12,7 @@ pub struct Value {
     pub shape: Option<TypedShape>,
 }
 
-#[derive(Debug, Default, Clone)]
+#[derive(Debug, Default, Clone, PartialEq)]
 pub struct ValueArena(Arena<Value>);
 
 impl ValueArena {
@@ -18,9 +18,7 @@ pub struct Opt {
 }
 
 fn main() {
-    use altius_core::optimize::elemwise_fusion::fuse_elemwise_ops;
-    use altius_core::optimize::gelu_fusion::fuse_gelu;
-    use altius_core::optimize::layer_norm_fusion::fuse_layer_norm;
+    use altius_core::optimize::pass_manager::OptimizationLevel;
     use altius_core::{onnx::load_onnx, tensor::Tensor};
     use altius_session_cpu::CPUSessionBuilder;
     use std::cmp::Ordering;
@@ -32,10 +30,7 @@ fn main() {
 
     let opt = Opt::from_args();
     let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("../../models");
-    let mut model = load_onnx(root.join("vit_b_16.onnx")).expect("Failed to load model");
-    fuse_layer_norm(&mut model);
-    fuse_gelu(&mut model);
-    fuse_elemwise_ops(&mut model).unwrap();
+    let model = load_onnx(root.join("vit_b_16.onnx")).expect("Failed to load model");
 
     let image = image::open(root.join("cat.png")).unwrap().to_rgb8();
     let resized = image::imageops::resize(&image, 224, 224, image::imageops::FilterType::Triangle);
@@ -48,6 +43,7 @@ fn main() {
 
     let i = CPUSessionBuilder::new(model)
         .with_profiling_enabled(opt.profile)
+        .with_optimization_level(OptimizationLevel::O2)
         .with_intra_op_num_threads(opt.threads)
         .build()
         .unwrap();
@@ -1,4 +1,11 @@
-use altius_core::{analysis::shape::infer_shapes, model::Model};
+use altius_core::{
+    analysis::shape::infer_shapes,
+    model::Model,
+    optimize::{
+        elemwise_fusion::fuse_elemwise_ops,
+        pass_manager::{OptimizationLevel, PassManager},
+    },
+};
 use rustc_hash::FxHashMap;
 
 use altius_session::SessionError;
@@ -9,6 +16,8 @@ pub struct CPUSessionBuilder {
     model: Model,
     intra_op_num_threads: usize,
     enable_profiling: bool,
+    optimization_level: OptimizationLevel,
+    verify_graph: bool,
 }
 
 impl CPUSessionBuilder {
@@ -17,6 +26,8 @@ impl CPUSessionBuilder {
             model,
             intra_op_num_threads: 1,
             enable_profiling: false,
+            optimization_level: OptimizationLevel::O0,
+            verify_graph: cfg!(debug_assertions),
         }
     }
 
@@ -30,11 +41,39 @@ impl CPUSessionBuilder {
         self
     }
 
-    pub fn build(self) -> Result<CPUSession, SessionError> {
+    /// Optimizes the model at `optimization_level` before building a session.
+    /// Defaults to `OptimizationLevel::O0`, which leaves the model as it is.
+    pub const fn with_optimization_level(mut self, optimization_level: OptimizationLevel) -> Self {
+        self.optimization_level = optimization_level;
+        self
+    }
+
+    /// Verifies the model before and after every optimization pass, so that a pass breaking
+    /// the model is reported by name. Enabled by default in debug builds.
+    pub const fn with_graph_verification(mut self, verify_graph: bool) -> Self {
+        self.verify_graph = verify_graph;
+        self
+    }
+
+    pub fn build(mut self) -> Result<CPUSession, SessionError> {
+        let mut pm =
+            PassManager::with_level(self.optimization_level).with_verification(self.verify_graph);
+        if self.optimization_level >= OptimizationLevel::O2 {
+            // Element-wise fusion is only supported by this backend.
+            pm = pm.add_pass("fuse_elemwise_ops", fuse_elemwise_ops);
+        }
+        pm.run(&mut self.model)?;
+
         let mut inferred_shapes = FxHashMap::default();
         let mut value_shapes = FxHashMap::default();
         infer_shapes(&self.model, &mut inferred_shapes, &mut value_shapes)?;
 
+        if value_shapes.values().any(|s| s.elem_ty.is_half()) {
+            return Err(SessionError::Message(
+                "Half-precision tensors are not supported by the CPU backend yet".into(),
+            ));
+        }
+
         let mut profile_symbols = FxHashMap::default();
         let product = Translator::new(&self.model, &inferred_shapes, &value_shapes)?
             .with_profiling_enabled(self.enable_profiling)
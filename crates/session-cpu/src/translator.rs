@@ -1,9 +1,7 @@
 use std::{
-    collections::BTreeMap,
     fs::{create_dir_all, remove_file, File},
     io::{BufWriter, Write},
     mem::{size_of, ManuallyDrop},
-    ops::Range,
     path::PathBuf,
     sync::{
         atomic::{AtomicUsize, Ordering},
@@ -15,14 +13,18 @@ use altius_core::{
     model::Model,
     node::{Node, NodeId},
     op::{
-        BatchNormalization, Cast, Concat, Conv2d, Flatten, FusedActivation, FusedElemwise, Gather,
-        Gemm, HardSigmoid, LayerNormalization, MaxPool, Op, ReduceMax, ReduceMean, Resize, Softmax,
-        Split, Transpose,
+        ArgReduce, BatchNormalization, Cast, Concat, Conv2d, ConvTranspose, DepthToSpace, Elu,
+        Flatten, FusedActivation, FusedElemwise, Gather, Gemm, HardSigmoid, LayerNormalization, Op,
+        Pad, Pool, Reduce, Resize, Selu, Softmax, SpaceToDepth, Split, Transpose,
     },
     tensor::{TensorElemType, TypedFixedShape},
     value::ValueId,
 };
-use altius_session::{plan::create_execution_plan, SessionError};
+use altius_session::{
+    plan::{create_execution_plan, create_memory_plan},
+    resize::compute_taps,
+    SessionError,
+};
 use cranelift::prelude::{InstBuilder, IntCC, Type, Variable};
 use cranelift::{
     codegen::settings::Configurable,
@@ -32,7 +34,7 @@ use cranelift_codegen::{
     entity::EntityRef,
     ir::{
         self,
-        types::{F32, I32, I64, I8},
+        types::{F32, I16, I32, I64, I8},
         AbiParam, Function, MemFlags, Value,
     },
     Context,
@@ -50,8 +52,6 @@ pub(super) struct Translator<'a> {
     value_shapes: &'a HashMap<ValueId, TypedFixedShape>,
     created_kernels: Vec<String>,
     created_kernel_protos: Vec<String>,
-    reshaped_values: HashSet<ValueId>,
-    propagated_inits: HashSet<ValueId>,
     pub used_op_names: HashSet<String>,
     pub target_dir: PathBuf,
     enable_profiling: bool,
@@ -73,14 +73,6 @@ struct CraneliftCtx {
     num_vars: usize,
 }
 
-/// Manages memory regions for temporary tensors.
-#[derive(Default)]
-struct Regions {
-    start_to_region: BTreeMap<usize, Range<usize>>,
-    val_to_start: HashMap<ValueId, usize>,
-    max_allocated: usize,
-}
-
 impl<'a> Translator<'a> {
     pub fn new(
         model: &'a Model,
@@ -110,8 +102,6 @@ impl<'a> Translator<'a> {
             value_shapes,
             created_kernels: Vec::new(),
             created_kernel_protos: Vec::new(),
-            reshaped_values: HashSet::default(),
-            propagated_inits: HashSet::default(),
             used_op_names: HashSet::default(),
             target_dir,
             enable_profiling: false,
@@ -289,44 +279,47 @@ impl<'a> Translator<'a> {
         let main_file = self.create_file("main.c")?;
         let mut writer = BufWriter::new(main_file);
 
-        let execution_plans = create_execution_plan(self.model);
+        let mut execution_plans = create_execution_plan(self.model);
+
+        // Outputs of these ops are pointers into their inputs, which are therefore never freed.
+        let (aliases, aliased): (HashSet<ValueId>, HashSet<ValueId>) = execution_plans
+            .iter()
+            .map(|plan| &self.model.graph.nodes[plan.node_id])
+            .filter(|node| matches!(node.op, Op::Reshape | Op::Squeeze(_) | Op::Unsqueeze(_)))
+            .map(|node| (node.outputs[0], node.inputs[0]))
+            .unzip();
+        for plan in &mut execution_plans {
+            plan.free_vals.retain(|id| !aliased.contains(id));
+        }
+        let memory = create_memory_plan(self.model, &execution_plans, |id| {
+            (!aliases.contains(&id)).then(|| {
+                let shape = &self.value_shapes[&id];
+                shape.dims.total_elems() * shape.elem_ty.size()
+            })
+        });
 
         let mut created_calls = vec![];
         let mut created_tmp_values = vec![];
         let mut created_extern_values = vec![];
-        let mut regions = Regions::default();
 
-        for plan in execution_plans {
+        for plan in &execution_plans {
             let node = &self.model.graph.nodes[plan.node_id];
-            // Allocate temporary tensors.
+            // Place temporary tensors.
             for output in node
                 .outputs
                 .iter()
-                .filter(|id| !self.model.graph.outputs.contains(id))
+                .filter(|id| !self.model.graph.outputs.contains(id) && !aliases.contains(id))
             {
-                if matches!(node.op, Op::Reshape | Op::Squeeze(_) | Op::Unsqueeze(_)) {
-                    continue;
-                }
-                let shape = &self.value_shapes[output];
-                let ty = get_c_type(shape.elem_ty);
+                // Values of zero bytes are not planned but still need a valid pointer.
+                let offset = memory.offsets.get(output).copied().unwrap_or(0);
+                let ty = get_c_type(self.value_shapes[output].elem_ty);
                 let name = self.value_name(*output);
-                let size = shape.dims.total_elems() * shape.elem_ty.size();
-                let region = regions.alloc(*output, size);
-                let offset = region.start;
                 created_calls.push(format!(
                     "{name} = ({ty} *)((char *)global_memory + {offset});",
                 ));
             }
 
             self.translate_node(plan.node_id, &mut created_calls)?;
-
-            // Free tensors.
-            for free in plan.free_vals.iter() {
-                if self.reshaped_values.contains(free) || self.propagated_inits.contains(free) {
-                    continue;
-                }
-                regions.free(*free);
-            }
         }
 
         for (&id, shape) in self.value_shapes {
@@ -444,7 +437,7 @@ static struct timespec now() {{
 }}
 
 "#,
-                    max_allocated = regions.max_allocated
+                    max_allocated = memory.arena_size
                 )
                 .as_bytes(),
             )?;
@@ -563,13 +556,6 @@ static struct timespec now() {{
 
         if matches!(op, Op::Reshape | Op::Squeeze(_) | Op::Unsqueeze(_)) {
             // TODO: Support 'Flatten'.
-            self.reshaped_values.insert(node.inputs[0]);
-            if self.model.graph.inputs.contains(&node.inputs[0])
-                || self.model.graph.inits.contains_key(&node.inputs[0])
-                || self.propagated_inits.contains(&node.inputs[0])
-            {
-                self.propagated_inits.insert(node.outputs[0]);
-            }
             let ty = get_c_type(inputs[0].elem_ty);
             created_calls.push(format!(
                 "{} = ({ty} *){};",
@@ -608,21 +594,72 @@ elapsed_{opname} += end_in_sec - start_in_sec;",
 
         let kernel = match op {
             Op::Conv2d(ref c) => self.translate_conv2d(c, &args, &inputs, outputs)?,
+            Op::ConvTranspose(ref c) => {
+                self.translate_conv_transpose(c, &args, &inputs, outputs)?
+            }
             Op::HardSigmoid(ref h) => self.translate_hard_sigmoid(h, &args, &inputs, outputs)?,
             Op::Add => self.translate_bin_op("+", &args, &inputs, outputs)?,
             Op::Sub => self.translate_bin_op("-", &args, &inputs, outputs)?,
             Op::Mul => self.translate_bin_op("*", &args, &inputs, outputs)?,
             Op::Div => self.translate_bin_op("/", &args, &inputs, outputs)?,
+            Op::Equal => self.translate_bin_op("==", &args, &inputs, outputs)?,
+            Op::Less => self.translate_bin_op("<", &args, &inputs, outputs)?,
+            Op::LessOrEqual => self.translate_bin_op("<=", &args, &inputs, outputs)?,
             Op::Greater => self.translate_bin_op(">", &args, &inputs, outputs)?,
+            Op::GreaterOrEqual => self.translate_bin_op(">=", &args, &inputs, outputs)?,
+            // Booleans are stored as 0 or 1.
+            Op::And => self.translate_bin_op("&", &args, &inputs, outputs)?,
+            Op::Or => self.translate_bin_op("|", &args, &inputs, outputs)?,
+            Op::Xor => self.translate_bin_op("^", &args, &inputs, outputs)?,
+            Op::Not => self.translate_not(&args, &inputs, outputs)?,
             Op::Pow => self.translate_pow(node, &args, &inputs, outputs)?,
             Op::Sqrt => self.translate_sqrt(&args, &inputs, outputs)?,
             Op::ReLU => self.translate_relu(&args, &inputs, outputs)?,
             Op::Erf => self.translate_erf(&args, &inputs, outputs)?,
             Op::Sigmoid => self.translate_sigmoid(&args, &inputs, outputs)?,
             Op::Tanh => self.translate_tanh(&args, &inputs, outputs)?,
+            Op::Abs
+            | Op::Neg
+            | Op::Log
+            | Op::Reciprocal
+            | Op::Floor
+            | Op::Ceil
+            | Op::Sin
+            | Op::Cos
+            | Op::Softplus
+            | Op::Softsign
+            | Op::Elu(_)
+            | Op::Selu(_)
+            | Op::Mish
+            | Op::HardSwish
+            | Op::Sign => self.translate_unary(op, &args, &inputs, outputs)?,
+            Op::PRelu => self.translate_prelu(&args, &inputs, outputs)?,
             Op::Where => self.translate_where(&args, &inputs, outputs)?,
-            Op::GlobalAveragePool => self.translate_gavg_pool(&args, &inputs, outputs)?,
+            Op::GlobalAveragePool => self.translate_global_pool(false, &args, &inputs, outputs)?,
+            Op::GlobalMaxPool => self.translate_global_pool(true, &args, &inputs, outputs)?,
             Op::MaxPool(ref m) => self.translate_max_pool(m, &args, &inputs, outputs)?,
+            Op::AveragePool(ref p) => self.translate_pool(
+                p,
+                "0.0f",
+                "acc += in[i];",
+                if p.count_include_pad {
+                    "padded_count == 0 ? 0.0f : acc / padded_count;"
+                } else {
+                    "count == 0 ? 0.0f : acc / count;"
+                },
+                &args,
+                &inputs,
+                outputs,
+            )?,
+            Op::LpPool(ref p) => self.translate_pool(
+                p,
+                "0.0f",
+                &format!("acc += powf(fabsf(in[i]), {});", p.p),
+                &format!("powf(acc, 1.0f / {});", p.p),
+                &args,
+                &inputs,
+                outputs,
+            )?,
             Op::Reshape => self.translate_reshape(&args, &inputs, outputs)?,
             Op::MatMul => self.translate_mat_mul(&args, &inputs, outputs)?,
             Op::Flatten(ref f) => self.translate_flatten(f, &args, &inputs, outputs)?,
@@ -633,20 +670,46 @@ elapsed_{opname} += end_in_sec - start_in_sec;",
             Op::Expand => self.translate_expand(&args, &inputs, outputs)?,
             Op::Concat(ref c) => self.translate_concat(c, &args, &inputs, outputs)?,
             Op::Gather(ref g) => self.translate_gather(g, &args, &inputs, outputs)?,
-            Op::ReduceMean(ref r) => self.translate_reduce_mean(r, &args, &inputs, outputs)?,
-            Op::ReduceMax(ref r) => self.translate_reduce_max(r, &args, &inputs, outputs)?,
+            Op::ReduceMin(ref r)
+            | Op::ReduceMax(ref r)
+            | Op::ReduceMean(ref r)
+            | Op::ReduceSum(ref r)
+            | Op::ReduceProd(ref r)
+            | Op::ReduceL1(ref r)
+            | Op::ReduceL2(ref r)
+            | Op::ReduceLogSum(ref r)
+            | Op::ReduceLogSumExp(ref r)
+            | Op::ReduceSumSquare(ref r) => {
+                self.translate_reduce(op, r, &args, &inputs, outputs)?
+            }
+            Op::ArgMax(ref a) | Op::ArgMin(ref a) => {
+                self.translate_arg_reduce(op, a, &args, &inputs, outputs)?
+            }
             Op::Softmax(ref s) => self.translate_softmax(s, &args, &inputs, outputs)?,
             Op::BatchNormalization(ref b) => {
                 self.translate_batch_norm(b, &args, &inputs, outputs)?
             }
             Op::LayerNormalization(l) => self.translate_layer_norm(l, &args, &inputs, outputs)?,
+            Op::InstanceNormalization(n) => {
+                self.translate_group_norm(None, n.epsilon, &args, &inputs, outputs)?
+            }
+            Op::GroupNormalization(n) => self.translate_group_norm(
+                Some(n.num_groups as usize),
+                n.epsilon,
+                &args,
+                &inputs,
+                outputs,
+            )?,
             Op::Gelu => self.translate_gelu(&args, &inputs, outputs)?,
             Op::Unsqueeze(_) => String::new(), // nop
             Op::Squeeze(_) => String::new(),   // nop
             Op::Split(ref s) => self.translate_split(s, &args, &inputs, outputs)?,
             Op::Slice => self.translate_slice(&args, &inputs, outputs)?,
             Op::Cast(ref c) => self.translate_cast(c, &args, &inputs, outputs)?,
-            Op::Resize(ref r) => self.translate_resize(r, &args, &inputs, outputs)?,
+            Op::Resize(ref r) => self.translate_resize(node, r, &args, &inputs, outputs)?,
+            Op::Pad(ref p) => self.translate_pad(p, &args, &inputs, outputs)?,
+            Op::DepthToSpace(ref d) => self.translate_depth_to_space(d, &args, &inputs, outputs)?,
+            Op::SpaceToDepth(ref s) => self.translate_space_to_depth(s, &args, &inputs, outputs)?,
             Op::FusedElemwise(ref f) => {
                 self.translate_fused_elemwise(f, &args, &inputs, outputs)?
             }
@@ -717,31 +780,18 @@ elapsed_{opname} += end_in_sec - start_in_sec;",
         let stride = &op.strides;
         let dilations = &op.dilations;
 
+        let rank = kernel.len();
         let batch_size = input.dims[0]; // .dims()[0];
         let input_c = input.dims[1];
-        let input_h = input.dims[2];
-        let input_w = input.dims[3];
-        let input_hw = input_h * input_w;
-        let output_h = output.dims[2];
-        let output_w = output.dims[3];
-        let output_hw = output_h * output_w;
-        let _dilation = 1;
+        let input_size = input.dims[2..].iter().product::<usize>();
+        let output_size = output.dims[2..].iter().product::<usize>();
+        let kernel_size = kernel.iter().product::<usize>();
         let group = op.group as usize;
         let in_c_per_g = input_c / group;
         let out_c_per_g = output.dims[1] / group;
-        let stride_h = stride[0];
-        let stride_w = stride[1];
-        let dilation_h = dilations[0];
-        let dilation_w = dilations[1];
-        let kernel_h = kernel[0];
-        let kernel_w = kernel[1];
 
-        assert_eq!(dilations.len(), 2);
-        assert!(padding.len() == 4);
-        let pad_t = padding[0];
-        let pad_l = padding[1];
-        let _pad_b = padding[2];
-        let _pad_r = padding[3];
+        assert_eq!(dilations.len(), rank);
+        assert!(padding.len() == rank * 2);
 
         log::debug!("kernel: {:?}", kernel);
 
@@ -755,10 +805,10 @@ elapsed_{opname} += end_in_sec - start_in_sec;",
         for (int g = 0; g < {group}; g++) {{
             for (int oc = 0; oc < {out_c_per_g}; oc++) {{
                 const float bias = *bias_ptr;
-                for (int o = 0; o < {output_h} * {output_w}; o++) {{
+                for (int o = 0; o < {output_size}; o++) {{
                     output_ptr[o] = bias;
                 }}
-                output_ptr += {output_h} * {output_w};
+                output_ptr += {output_size};
                 bias_ptr++;
             }}
         }}
@@ -777,36 +827,26 @@ elapsed_{opname} += end_in_sec - start_in_sec;",
             )
         };
 
-        let code_im2col = if pad_t == 0
-            && pad_l == 0
-            && stride_h == 1
-            && stride_w == 1
-            && dilation_h == 1
-            && dilation_w == 1
+        let code_im2col = if kernel_size == 1
+            && padding.iter().all(|&p| p == 0)
+            && stride.iter().all(|&s| s == 1)
         {
             let input_name = &input_names[0];
+            let size = batch_size * input_c * input_size;
 
-            format!("float *col =
-    (float *)malloc(sizeof(float) * {batch_size} * {input_c} * {output_h} * {output_w} * {kernel_h} * {kernel_w});
-
-{{
-    int outer = {batch_size} * {input_c};
-    float *input_ptr = (float *){input_name};
-    float *col_ptr = (float *)col;
-
-    while (outer > 0) {{
-        int inner = {kernel_h} * {kernel_w};
-        while (inner > 0) {{
-            memcpy(col_ptr, input_ptr, sizeof(float) * {input_hw});
-            col_ptr += {output_hw};
-            inner -= 1;
-        }}
-        input_ptr += {input_hw};
-        outer -= 1;
-    }}
-}}
-")
-        } else if dilation_h == 1 && dilation_w == 1 {
+            format!(
+                "float *col = (float *)malloc(sizeof(float) * {size});
+memcpy(col, {input_name}, sizeof(float) * {size});
+"
+            )
+        } else if rank == 2 && dilations.iter().all(|&d| d == 1) {
+            let [input_h, input_w] = input.dims[2..].try_into().unwrap();
+            let [output_h, output_w] = output.dims[2..].try_into().unwrap();
+            let [kernel_h, kernel_w] = kernel.to_fixed_dims::<2>();
+            let [stride_h, stride_w] = stride.to_fixed_dims::<2>();
+            let (pad_t, pad_l) = (padding[0], padding[1]);
+            let input_hw = input_h * input_w;
+            let output_hw = output_h * output_w;
             let input_name = &input_names[0];
             let num_threads = self.intra_op_num_threads;
 
@@ -825,7 +865,7 @@ elapsed_{opname} += end_in_sec - start_in_sec;",
         for (int fy = 0; fy < {kernel_h}; fy++) {{
             for (int fx = 0; fx < {kernel_w}; fx++) {{
                 for (int oh = 0; oh < {output_h}; oh++) {{
-                    float *col = &col_ptr[oh * {output_h}];
+                    float *col = &col_ptr[oh * {output_w}];
                     const int ih = fy + oh * {stride_h};
 
                     if ({pad_t} > ih || ih >= {input_h} + {pad_t}) {{
@@ -864,13 +904,57 @@ elapsed_{opname} += end_in_sec - start_in_sec;",
     }}
 }}")
         } else {
-            todo!();
+            let input_name = &input_names[0];
+            let num_threads = self.intra_op_num_threads;
+            let output_dims = &output.dims[2..];
+            let input_dims = &input.dims[2..];
+
+            let mut loops = String::new();
+            for (i, &k) in kernel.iter().enumerate() {
+                loops.push_str(&format!("for (int k{i} = 0; k{i} < {k}; k{i}++)\n"));
+            }
+            for (i, &o) in output_dims.iter().enumerate() {
+                loops.push_str(&format!("for (int o{i} = 0; o{i} < {o}; o{i}++)\n"));
+            }
+            let coords = (0..rank)
+                .map(|i| {
+                    format!(
+                        "const int x{i} = o{i} * {} + k{i} * {} - {};",
+                        stride[i], dilations[i], padding[i]
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+            let in_range = (0..rank)
+                .map(|i| format!("x{i} >= 0 && x{i} < {}", input_dims[i]))
+                .collect::<Vec<_>>()
+                .join(" && ");
+            let offset = (0..rank).fold("0".to_string(), |acc, i| {
+                format!("({acc}) * {} + x{i}", input_dims[i])
+            });
+
+            format!(
+                "float *col =
+    (float *)malloc(sizeof(float) * {batch_size} * {input_c} * {kernel_size} * {output_size});
+
+{{
+    #pragma omp parallel for num_threads({num_threads})
+    for (int outer = 0; outer < {batch_size} * {input_c}; outer++) {{
+        const float *input_ptr = (float *){input_name} + outer * {input_size};
+        float *col_ptr = col + outer * {kernel_size} * {output_size};
+{loops}{{
+{coords}
+*col_ptr++ = ({in_range}) ? input_ptr[{offset}] : 0.0f;
+}}
+    }}
+}}"
+            )
         };
 
-        let col_stride = in_c_per_g * kernel[0] * kernel[1] * output_h * output_w;
-        let weight_stride = out_c_per_g * in_c_per_g * kernel[0] * kernel[1];
-        let output_stride = out_c_per_g * output_hw;
-        let k = in_c_per_g * kernel[0] * kernel[1];
+        let col_stride = in_c_per_g * kernel_size * output_size;
+        let weight_stride = out_c_per_g * in_c_per_g * kernel_size;
+        let output_stride = out_c_per_g * output_size;
+        let k = in_c_per_g * kernel_size;
         let outer = (batch_size * group) / in_c_per_g;
         let weight_name = &input_names[1];
         let output_name = &output_names[0];
@@ -908,8 +992,8 @@ elapsed_{opname} += end_in_sec - start_in_sec;",
 
     do {{
         cblas_sgemm(CblasRowMajor, CblasNoTrans, CblasNoTrans,
-            {out_c_per_g}, {output_hw}, {k}, 1.,
-            weight_ptr, {k}, col_ptr, {output_hw}, 1., output_ptr, {output_hw});
+            {out_c_per_g}, {output_size}, {k}, 1.,
+            weight_ptr, {k}, col_ptr, {output_size}, 1., output_ptr, {output_size});
 
         weight_ptr += {weight_stride};
         output_ptr += {output_stride};
@@ -935,6 +1019,95 @@ free(col);",
         Ok(kernel)
     }
 
+    fn translate_conv_transpose(
+        &mut self,
+        op: &ConvTranspose,
+        args: &[String],
+        inputs: &[&TypedFixedShape],
+        outputs: &[TypedFixedShape],
+    ) -> Result<String, SessionError> {
+        let input_names = &args[..inputs.len()];
+        let input_name = &input_names[Op::CONVTRANSPOSE_IN];
+        let weight_name = &input_names[Op::CONVTRANSPOSE_WEIGHT];
+        let output_name = &args[inputs.len()];
+
+        let input = inputs[Op::CONVTRANSPOSE_IN];
+        let output = &outputs[Op::CONVTRANSPOSE_OUT];
+        let rank = op.kernel_shape.len();
+        let input_dims = &input.dims[2..];
+        let output_dims = &output.dims[2..];
+        let batch_size = input.dims[0];
+        let group = op.group as usize;
+        let in_c_per_g = input.dims[1] / group;
+        let out_c_per_g = output.dims[1] / group;
+        let input_size = input_dims.iter().product::<usize>();
+        let output_size = output_dims.iter().product::<usize>();
+        let kernel_size = op.kernel_shape.iter().product::<usize>();
+
+        let bias = input_names
+            .get(Op::CONVTRANSPOSE_BIAS)
+            .map_or("0.0f".to_string(), |bias| format!("{bias}[c]"));
+
+        let mut loops = String::new();
+        for (i, &x) in input_dims.iter().enumerate() {
+            loops.push_str(&format!("for (int x{i} = 0; x{i} < {x}; x{i}++)\n"));
+        }
+        for (i, &k) in op.kernel_shape.iter().enumerate() {
+            loops.push_str(&format!("for (int k{i} = 0; k{i} < {k}; k{i}++)\n"));
+        }
+        let coords = (0..rank)
+            .map(|i| {
+                format!(
+                    "const int y{i} = x{i} * {} + k{i} * {} - {};",
+                    op.strides[i], op.dilations[i], op.padding[i]
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        let in_range = (0..rank)
+            .map(|i| format!("y{i} >= 0 && y{i} < {}", output_dims[i]))
+            .collect::<Vec<_>>()
+            .join(" && ");
+        let row_major = |var: &str, dims: &[usize]| {
+            (0..rank).fold("0".to_string(), |acc, i| {
+                format!("({acc}) * {} + {var}{i}", dims[i])
+            })
+        };
+        let x_offset = row_major("x", input_dims);
+        let y_offset = row_major("y", output_dims);
+        let k_offset = row_major("k", &op.kernel_shape);
+
+        let kernel = format!(
+            "for (int b = 0; b < {batch_size}; b++) {{
+    for (int c = 0; c < {out_c}; c++) {{
+        float *output_ptr = {output_name} + (b * {out_c} + c) * {output_size};
+        for (int i = 0; i < {output_size}; i++) {{
+            output_ptr[i] = {bias};
+        }}
+    }}
+    for (int g = 0; g < {group}; g++) {{
+        for (int ic = 0; ic < {in_c_per_g}; ic++) {{
+            const float *input_ptr = {input_name} + (b * {in_c} + g * {in_c_per_g} + ic) * {input_size};
+            const float *weight_ptr = {weight_name} + (g * {in_c_per_g} + ic) * {out_c_per_g} * {kernel_size};
+            for (int oc = 0; oc < {out_c_per_g}; oc++) {{
+                float *output_ptr = {output_name} + (b * {out_c} + g * {out_c_per_g} + oc) * {output_size};
+{loops}{{
+{coords}
+if ({in_range}) {{
+    output_ptr[{y_offset}] += input_ptr[{x_offset}] * weight_ptr[oc * {kernel_size} + {k_offset}];
+}}
+}}
+            }}
+        }}
+    }}
+}}",
+            out_c = output.dims[1],
+            in_c = input.dims[1],
+        );
+
+        Ok(kernel)
+    }
+
     fn translate_hard_sigmoid(
         &mut self,
         hs: &HardSigmoid,
@@ -994,7 +1167,9 @@ for (int i = 0; i < {size}; i++) {{
                         Op::Div => format!("({} / {})", args[0], args[1]),
                         Op::Sqrt => format!("sqrtf({})", args[0]),
                         Op::ReLU => format!("fmaxf({}, 0.0f)", args[0]),
-                        _ => todo!("{:?} is not yet supported", op),
+                        Op::PRelu => format!("({0} < 0.0f ? {0} * {1} : {0})", args[0], args[1]),
+                        op => unary_expr(op, &args[0])
+                            .unwrap_or_else(|| todo!("{:?} is not yet supported", op)),
                     };
                     assert_eq!(op_outs.len(), 1);
                     (expr, Some(op_outs[0]))
@@ -1050,7 +1225,10 @@ for (int i{i} = 0; i{i} < {odim}; i{i}++) {{
                                         _ => format!("powf({out}, *input_{opr_idx}_ptr_{i})"),
                                     },
                                     Op::Sqrt => format!("sqrtf({out})"),
-                                    _ => todo!("{op:?}"),
+                                    Op::PRelu => format!(
+                                        "({out} < 0.0f ? {out} * *input_{opr_idx}_ptr_{i} : {out})"
+                                    ),
+                                    op => unary_expr(op, &out).unwrap_or_else(|| todo!("{op:?}")),
                                 };
                                 opr_idx += inputs.len() - 1;
                             }
@@ -1312,6 +1490,100 @@ for (int i = 0; i < {size}; i++) {{
         Ok(kernel)
     }
 
+    fn translate_not(
+        &mut self,
+        args: &[String],
+        inputs: &[&TypedFixedShape],
+        outputs: &[TypedFixedShape],
+    ) -> Result<String, SessionError> {
+        let input_name = &args[0];
+        let output_name = &args[1];
+
+        assert_eq!(inputs[0].dims, outputs[0].dims);
+
+        let kernel = format!(
+            "for (int i = 0; i < {size}; i++) {{
+    {output_name}[i] = !{input_name}[i];
+}}",
+            size = outputs[0].dims.total_elems(),
+        );
+
+        Ok(kernel)
+    }
+
+    fn translate_unary(
+        &mut self,
+        op: &Op,
+        args: &[String],
+        inputs: &[&TypedFixedShape],
+        outputs: &[TypedFixedShape],
+    ) -> Result<String, SessionError> {
+        let input_name = &args[..inputs.len()][0];
+        let output_name = &args[inputs.len()..][0];
+        let size = outputs[0].dims.total_elems();
+        let num_threads = self.intra_op_num_threads;
+        let expr = match unary_expr(op, "x") {
+            Some(expr) if inputs[0].elem_ty.is_f32() => expr,
+            _ => {
+                return Err(SessionError::Message(
+                    format!("{}: Unsupported type {:?}", op.name(), inputs[0].elem_ty).into(),
+                ))
+            }
+        };
+        let kernel = format!(
+            "#pragma omp parallel for num_threads({num_threads})
+#pragma clang loop vectorize(enable)
+for (int i = 0; i < {size}; i++) {{
+    const float x = {input_name}[i];
+    {output_name}[i] = {expr};
+}}"
+        );
+        Ok(kernel)
+    }
+
+    fn translate_prelu(
+        &mut self,
+        args: &[String],
+        inputs: &[&TypedFixedShape],
+        outputs: &[TypedFixedShape],
+    ) -> Result<String, SessionError> {
+        let input_name = &args[0];
+        let slope_name = &args[1];
+        let output_name = &args[inputs.len()..][0];
+        let size = outputs[0].dims.total_elems();
+        let num_threads = self.intra_op_num_threads;
+        let slope_strides = inputs[1]
+            .dims
+            .strides_for_broadcasting_to(&outputs[0].dims)
+            .ok_or_else(|| {
+                SessionError::Message(
+                    format!(
+                        "PRelu: Cannot broadcast slope {:?} to {:?}",
+                        inputs[1].dims, outputs[0].dims
+                    )
+                    .into(),
+                )
+            })?;
+        // The index of the slope for the output index `i`.
+        let slope_index = outputs[0]
+            .dims
+            .iter()
+            .zip(outputs[0].dims.strides().iter())
+            .zip(slope_strides.iter())
+            .filter(|(_, &sstr)| sstr != 0)
+            .map(|((odim, ostr), sstr)| format!("i / {ostr} % {odim} * {sstr}"))
+            .reduce(|a, b| format!("{a} + {b}"))
+            .unwrap_or_else(|| "0".to_string());
+        let kernel = format!(
+            "#pragma omp parallel for num_threads({num_threads})
+for (int i = 0; i < {size}; i++) {{
+    const float x = {input_name}[i];
+    {output_name}[i] = x < 0.0f ? x * {slope_name}[{slope_index}] : x;
+}}"
+        );
+        Ok(kernel)
+    }
+
     fn translate_relu(
         &mut self,
         args: &[String],
@@ -1518,8 +1790,9 @@ float *output_ptr = {};\n",
         Ok(kernel)
     }
 
-    fn translate_gavg_pool(
+    fn translate_global_pool(
         &mut self,
+        max: bool,
         args: &[String],
         inputs: &[&TypedFixedShape],
         outputs: &[TypedFixedShape],
@@ -1530,32 +1803,115 @@ float *output_ptr = {};\n",
         let input = inputs[0];
         let output = &outputs[0];
 
-        assert!(input.dims.len() == 4);
-        assert!(output.dims.len() == 4);
-
-        let Some(&[n, c, h, w]) = input.dims.get(0..4) else {
+        if input.dims.len() < 3 {
             return Err(SessionError::Message(
-                "Input must be four dimensions".into(),
+                "Input must be at least three dimensions".into(),
             ));
-        };
-        let Some(&[isn, isc, _, _]) = input.dims.strides().get(0..4) else {
-            panic!()
-        };
-        let area = h * w;
-        let osn = output.dims.strides()[0];
+        }
+        let outer = output.dims.total_elems();
+        let area = input.dims[2..].iter().product::<usize>();
 
-        let kernel = format!(
-            "for (int n = 0; n < {n}; n++) {{
-    for (int c = 0; c < {c}; c++) {{
-        float sum = 0.0;
-        for (int h = 0; h < {h}; h++) {{
-            for (int w = 0; w < {w}; w++) {{
-                sum += {input_name}[n * {isn} + c * {isc} + h * {w} + w];
-            }}
-        }}
-        {output_name}[n * {osn} + c] = sum / {area};
+        let kernel = if max {
+            format!(
+                "for (int i = 0; i < {outer}; i++) {{
+    float max = -INFINITY;
+    for (int j = 0; j < {area}; j++) {{
+        max = fmaxf(max, {input_name}[i * {area} + j]);
     }}
+    {output_name}[i] = max;
 }}"
+            )
+        } else {
+            format!(
+                "for (int i = 0; i < {outer}; i++) {{
+    float sum = 0.0;
+    for (int j = 0; j < {area}; j++) {{
+        sum += {input_name}[i * {area} + j];
+    }}
+    {output_name}[i] = sum / {area};
+}}"
+            )
+        };
+
+        Ok(kernel)
+    }
+
+    /// Translates an N-D pool. For each output element, `accum` runs on every input element
+    /// `in[i]` of its window, accumulating into `acc`, and `finish` gives the output value.
+    /// `count` and `padded_count` are the numbers of the window elements without and with pads.
+    #[allow(clippy::too_many_arguments)]
+    fn translate_pool(
+        &mut self,
+        pool: &Pool,
+        init: &str,
+        accum: &str,
+        finish: &str,
+        args: &[String],
+        inputs: &[&TypedFixedShape],
+        outputs: &[TypedFixedShape],
+    ) -> Result<String, SessionError> {
+        let input_name = &args[0];
+        let output_name = &args[inputs.len()];
+
+        let input = &inputs[0].dims[2..];
+        let output = &outputs[0].dims;
+        let rank = input.len();
+        let outer = output[0] * output[1];
+        let output = &output[2..];
+        let input_size = input.iter().product::<usize>();
+        let output_size = output.iter().product::<usize>();
+
+        let mut loops = String::new();
+        let mut closes = String::new();
+        for (i, &o) in output.iter().enumerate() {
+            loops.push_str(&format!("for (int o{i} = 0; o{i} < {o}; o{i}++) {{\n"));
+            closes.push_str("}\n");
+        }
+        loops.push_str(&format!(
+            "float acc = {init};\nint count = 0, padded_count = 0, index = 0, index_col = 0;\n"
+        ));
+        let mut window = String::new();
+        for i in 0..rank {
+            window.push_str(&format!(
+                "for (int k{i} = 0; k{i} < {k}; k{i}++) {{
+int x{i} = o{i} * {s} + k{i} * {d} - {pad};
+if (x{i} >= {end}) continue;\n",
+                k = pool.kernel_shape[i],
+                s = pool.strides[i],
+                d = pool.dilations[i],
+                pad = pool.padding[i],
+                end = input[i] + pool.padding[rank + i],
+            ));
+        }
+        let in_range = (0..rank)
+            .map(|i| format!("x{i} < 0 || x{i} >= {}", input[i]))
+            .collect::<Vec<_>>()
+            .join(" || ");
+        let row_major = (0..rank).fold("0".to_string(), |acc, i| {
+            format!("({acc}) * {} + x{i}", input[i])
+        });
+        let col_major = (0..rank).rev().fold("0".to_string(), |acc, i| {
+            format!("({acc}) * {} + x{i}", input[i])
+        });
+        window.push_str(&format!(
+            "padded_count++;
+if ({in_range}) continue;
+const int i = {row_major}, i_col = {col_major};
+count++;
+{accum}\n"
+        ));
+        for _ in 0..rank {
+            window.push_str("}\n");
+        }
+
+        let kernel = format!(
+            "for (int nc = 0; nc < {outer}; nc++) {{
+const float *in = {input_name} + nc * {input_size};
+float *out = {output_name} + nc * {output_size};
+int o = 0;
+{loops}{window}out[o] = {finish}
+o++;
+{closes}}}"
         );
 
         Ok(kernel)
@@ -1563,7 +1919,7 @@ float *output_ptr = {};\n",
 
     fn translate_max_pool(
         &mut self,
-        maxpool: &MaxPool,
+        maxpool: &Pool,
         args: &[String],
         inputs: &[&TypedFixedShape],
         outputs: &[TypedFixedShape],
@@ -1576,6 +1932,31 @@ float *output_ptr = {};\n",
         let input = inputs[Op::MAXPOOL_IN];
         let output = &outputs[Op::MAXPOOL_OUT];
 
+        if input.dims.len() != 4 || outputs.len() > 1 || maxpool.dilations.iter().any(|&d| d != 1) {
+            let input_size = input.dims[2..].iter().product::<usize>();
+            let indices = match output_names.get(Op::MAXPOOL_OUT_INDICES) {
+                Some(name) if maxpool.storage_order == 1 => {
+                    format!("{name}[o] = nc * {input_size} + index_col;")
+                }
+                Some(name) => format!("{name}[o] = nc * {input_size} + index;"),
+                None => String::new(),
+            };
+            let accum = "if (count == 1 || in[i] > acc) {
+    acc = in[i];
+    index = i;
+    index_col = i_col;
+}";
+            return self.translate_pool(
+                maxpool,
+                "-INFINITY",
+                accum,
+                &format!("count == 0 ? 0.0f : acc;\n{indices}"),
+                args,
+                inputs,
+                outputs,
+            );
+        }
+
         let kernel = &maxpool.kernel_shape;
         let stride = &maxpool.strides;
 
@@ -2692,77 +3073,124 @@ for (int i = 0; i < {outer}; i++) {{
         Ok("/* Cranelift */".to_string())
     }
 
-    fn translate_reduce_mean(
+    fn translate_reduce(
         &mut self,
-        rmean: &ReduceMean,
+        op: &Op,
+        reduce: &Reduce,
         args: &[String],
         inputs: &[&TypedFixedShape],
         outputs: &[TypedFixedShape],
     ) -> Result<String, SessionError> {
-        let input = inputs[0];
-        let output = &outputs[0];
-        let input_name = &args[0];
-        let output_name = &args[1];
+        let input = inputs[Op::REDUCE_IN];
+        let input_name = &args[Op::REDUCE_IN];
+        let output_name = &args[inputs.len()..][Op::REDUCE_OUT];
+        if !input.elem_ty.is_f32() {
+            return Err(SessionError::Message(
+                format!("{}: Unsupported type {:?}", op.name(), input.elem_ty).into(),
+            ));
+        }
 
-        let axes = rmean
-            .axes
+        // `reduce.axes` are normalized in shape inference.
+        let (reduced, kept): (Vec<_>, Vec<_>) = input
+            .dims
             .iter()
-            .map(|&axis| {
-                if axis < 0 {
-                    (input.dims.len() as i64 + axis) as usize
-                } else {
-                    axis as usize
-                }
-            })
-            .collect::<Vec<_>>();
-        assert!(
-            axes.iter()
-                .all(|&axis| axis >= input.dims.len() - axes.len()),
-            "Axes must specify innermost dimensions"
-        );
-        assert!(rmean.keep_dims);
+            .zip(input.dims.strides().iter())
+            .enumerate()
+            .map(|(axis, (&dim, &stride))| (axis, (dim, stride)))
+            .partition(|(axis, _)| reduce.axes.contains(&(*axis as i64)));
+        let reduced = reduced.into_iter().map(|(_, a)| a).collect::<Vec<_>>();
+        let kept = kept.into_iter().map(|(_, a)| a).collect::<Vec<_>>();
+        let len = reduced.iter().map(|(dim, _)| dim).product::<usize>();
+
+        let (init, accum, finish) = match op {
+            Op::ReduceMin(_) => ("float acc = INFINITY;", "acc = fminf(acc, x);", "acc".into()),
+            Op::ReduceMax(_) => ("float acc = -INFINITY;", "acc = fmaxf(acc, x);", "acc".into()),
+            Op::ReduceMean(_) => ("float acc = 0.0f;", "acc += x;", format!("acc / {len}")),
+            Op::ReduceSum(_) => ("float acc = 0.0f;", "acc += x;", "acc".into()),
+            Op::ReduceProd(_) => ("float acc = 1.0f;", "acc *= x;", "acc".into()),
+            Op::ReduceL1(_) => ("float acc = 0.0f;", "acc += fabsf(x);", "acc".into()),
+            Op::ReduceL2(_) => ("float acc = 0.0f;", "acc += x * x;", "sqrtf(acc)".into()),
+            Op::ReduceLogSum(_) => ("float acc = 0.0f;", "acc += x;", "logf(acc)".into()),
+            // Keeps the running maximum not to overflow.
+            Op::ReduceLogSumExp(_) => (
+                "float acc = 0.0f, max = -INFINITY;",
+                "if (x > max) { acc = acc * expf(max - x) + 1.0f; max = x; } else { acc += expf(x - max); }",
+                "max + logf(acc)".into(),
+            ),
+            Op::ReduceSumSquare(_) => ("float acc = 0.0f;", "acc += x * x;", "acc".into()),
+            _ => {
+                return Err(SessionError::Message(
+                    format!("{}: Unsupported reduce op", op.name()).into(),
+                ))
+            }
+        };
 
         let kernel = format!(
-            "for (int i = 0; i < {batch}; i++) {{
-    const float *input_ptr = {input_name} + i * {axis_len};
-    float sum = 0.f;
+            "#pragma omp parallel for num_threads({num_threads})
+for (int o = 0; o < {size}; o++) {{
+    const float *in = {input_name} + {outer};
+    {init}
     #pragma clang loop vectorize(enable)
-    for (int j = 0; j < {axis_len}; j++) {{
-        sum += input_ptr[j];
+    for (int r = 0; r < {len}; r++) {{
+        const float x = in[{inner}];
+        {accum}
     }}
-    {output_name}[i] = sum * (1.f / {axis_len});
+    {output_name}[o] = {finish};
 }}",
-            batch = output.dims.total_elems(),
-            axis_len = input.dims[axes[0]..].iter().product::<usize>(),
+            num_threads = self.intra_op_num_threads,
+            size = outputs[0].dims.total_elems(),
+            outer = index_expr("o", &kept),
+            inner = index_expr("r", &reduced),
         );
 
         Ok(kernel)
     }
 
-    fn translate_reduce_max(
+    fn translate_arg_reduce(
         &mut self,
-        rmax: &ReduceMax,
+        op: &Op,
+        arg: &ArgReduce,
         args: &[String],
         inputs: &[&TypedFixedShape],
-        _outputs: &[TypedFixedShape],
+        outputs: &[TypedFixedShape],
     ) -> Result<String, SessionError> {
-        // TODO: Current implementation only supports reduction over all axes.
+        let input = inputs[Op::ARGREDUCE_IN];
+        let input_name = &args[Op::ARGREDUCE_IN];
+        let output_name = &args[inputs.len()..][Op::ARGREDUCE_OUT];
+        if !input.elem_ty.is_f32() {
+            return Err(SessionError::Message(
+                format!("{}: Unsupported type {:?}", op.name(), input.elem_ty).into(),
+            ));
+        }
 
-        let input = inputs[0];
-        let input_name = &args[0];
-        let output_name = &args[1];
-        assert!(rmax.axes.is_empty());
-        assert!(!rmax.keep_dims);
+        // `arg.axis` is normalized in shape inference.
+        let axis = arg.axis as usize;
+        let len = input.dims[axis];
+        let inner = input.dims[axis + 1..].iter().product::<usize>();
+        let cmp = match (op, arg.select_last_index) {
+            (Op::ArgMax(_), false) => ">",
+            (Op::ArgMax(_), true) => ">=",
+            (_, false) => "<",
+            (_, true) => "<=",
+        };
 
-        let outer = input.dims.total_elems();
         let kernel = format!(
-            "float max = -INFINITY;
-#pragma clang loop vectorize(enable)
-for (int i = 0; i < {outer}; i++) {{
-    max = fmaxf({input_name}[i], max);
-}}
-{output_name}[0] = max;
-"
+            "#pragma omp parallel for num_threads({num_threads})
+for (int o = 0; o < {size}; o++) {{
+    const float *in = {input_name} + o / {inner} * {outer_stride} + o % {inner};
+    float best = in[0];
+    int64_t index = 0;
+    for (int r = 1; r < {len}; r++) {{
+        if (in[r * {inner}] {cmp} best) {{
+            best = in[r * {inner}];
+            index = r;
+        }}
+    }}
+    {output_name}[o] = index;
+}}",
+            num_threads = self.intra_op_num_threads,
+            size = outputs[0].dims.total_elems(),
+            outer_stride = len * inner,
         );
 
         Ok(kernel)
@@ -2967,6 +3395,78 @@ for (int i = 0; i < {batch}; i++) {{
         Ok(kernel)
     }
 
+    /// Translates `InstanceNormalization` (a group per channel) and `GroupNormalization`.
+    fn translate_group_norm(
+        &mut self,
+        groups: Option<usize>,
+        epsilon: f32,
+        args: &[String],
+        inputs: &[&TypedFixedShape],
+        outputs: &[TypedFixedShape],
+    ) -> Result<String, SessionError> {
+        let data = inputs[Op::NORM_IN_X];
+        let scale = inputs[Op::NORM_IN_SCALE];
+        let output = &outputs[Op::NORM_OUT];
+        let data_name = &args[Op::NORM_IN_X];
+        let scale_name = &args[Op::NORM_IN_SCALE];
+        let bias_name = &args[Op::NORM_IN_B];
+        let output_name = &args[inputs.len()..][0];
+
+        assert!(data.elem_ty.is_f32(), "Input data type must be f32.");
+        assert_eq!(data.dims.total_elems(), output.dims.total_elems());
+
+        let channels = data.dims[1];
+        let groups = groups.unwrap_or(channels);
+        let spatial = data.dims[2..].iter().product::<usize>();
+        let channels_per_group = channels / groups;
+        let group_len = channels_per_group * spatial;
+        if group_len == 0 {
+            return Ok(String::new());
+        }
+        // Scale and bias of GroupNormalization are given per group until opset version 21.
+        let param = if scale.dims.total_elems() == channels {
+            format!("g % {groups} * {channels_per_group} + c")
+        } else {
+            format!("g % {groups}")
+        };
+
+        let kernel = format!(
+            "#pragma omp parallel for num_threads({th})
+for (int g = 0; g < {num_groups}; g++) {{
+    float sum = 0.0;
+    const float *data = {data_name} + g * {group_len};
+    float *output = {output_name} + g * {group_len};
+#pragma clang loop vectorize(enable)
+    for (int j = 0; j < {group_len}; j++) {{
+        sum = sum + data[j];
+    }}
+    const float mean = sum * {inv_group_len};
+    float sum_squares = 0.0;
+#pragma clang loop vectorize(enable)
+    for (int j = 0; j < {group_len}; j++) {{
+        const float x = data[j] - mean;
+        output[j] = x;
+        sum_squares += x * x;
+    }}
+    const float inv_std = 1.0 / sqrtf(sum_squares * {inv_group_len} + {epsilon:e});
+    for (int c = 0; c < {channels_per_group}; c++) {{
+        const float scale = {scale_name}[{param}] * inv_std;
+        const float bias = {bias_name}[{param}];
+        float *out = output + c * {spatial};
+#pragma clang loop vectorize(enable)
+        for (int j = 0; j < {spatial}; j++) {{
+            out[j] = out[j] * scale + bias;
+        }}
+    }}
+}}",
+            th = self.intra_op_num_threads,
+            num_groups = data.dims.total_elems() / group_len,
+            inv_group_len = (group_len as f32).recip(),
+        );
+
+        Ok(kernel)
+    }
+
     fn translate_gelu(
         &mut self,
         args: &[String],
@@ -3219,51 +3719,317 @@ for (int i = effective_starts[0]; i < effective_ends[0]; i += /*effective_steps[
 
     fn translate_resize(
         &mut self,
+        node: &Node,
         resize: &Resize,
         args: &[String],
         inputs: &[&TypedFixedShape],
         outputs: &[TypedFixedShape],
     ) -> Result<String, SessionError> {
-        let input_names = &args[0..inputs.len()];
-        let output_names = &args[inputs.len()..];
-
-        let input = inputs[0];
-        // let sizes = inputs[1];
-        let output = &outputs[0];
+        let input_name = &args[Op::RESIZE_IN_X];
+        let output_name = &args[inputs.len()];
+        let input = inputs[Op::RESIZE_IN_X];
+        let output = &outputs[Op::RESIZE_OUT];
+        let rank = input.dims.len();
+
+        // Taps are computed ahead of time, so roi and scales must be constant if any.
+        let constant = |i: usize| match inputs.get(i) {
+            Some(shape) if shape.dims.total_elems() > 0 => self
+                .model
+                .graph
+                .inits
+                .get(&node.inputs[i])
+                .map(Some)
+                .ok_or_else(|| {
+                    SessionError::Message("Resize: roi and scales must be constant".into())
+                }),
+            _ => Ok(None),
+        };
+        let roi = constant(Op::RESIZE_IN_ROI)?;
+        let scales = constant(Op::RESIZE_IN_SCALES)?;
+        let taps = compute_taps(resize, &input.dims, &output.dims, roi, scales)?;
 
-        assert!(matches!(inputs.len(), 3 | 4));
+        let nearest = resize.mode == "nearest";
+        if !nearest && !input.elem_ty.is_f32() {
+            return Err(SessionError::Message(
+                format!(
+                    "Resize: Unsupported type {:?} for mode '{}'",
+                    input.elem_ty, resize.mode
+                )
+                .into(),
+            ));
+        }
 
-        let batch_size = input.dims[0];
-        let input_c = input.dims[1];
-        let input_h = input.dims[2];
-        let input_w = input.dims[3];
-        let output_h = output.dims[2];
-        let output_w = output.dims[3];
-        let input_hw = input_h * input_w;
-        let output_hw = output_h * output_w;
-        let outer = batch_size * input_c;
+        // For each axis, `count{i}` is the number of taps of each output index (0 if the index
+        // takes `extrapolation_value`), and `offset{i}` and `weight{i}` are those of the taps,
+        // `width{i}` per output index.
+        let mut tables = String::new();
+        for (i, (taps, &stride)) in taps.iter().zip(input.dims.strides().iter()).enumerate() {
+            let width = taps.iter().flatten().map(Vec::len).max().unwrap_or(1);
+            let mut counts = vec![];
+            let mut offsets = vec![];
+            let mut weights = vec![];
+            for tap in taps {
+                let tap = tap.as_deref().unwrap_or_default();
+                counts.push(tap.len().to_string());
+                for k in 0..width {
+                    let (index, weight) = tap.get(k).copied().unwrap_or((0, 0.));
+                    offsets.push((index * stride).to_string());
+                    weights.push(format!("{weight:e}f"));
+                }
+            }
+            tables.push_str(&format!(
+                "enum {{ width{i} = {width} }};
+static const int count{i}[] = {{{}}};
+static const int offset{i}[] = {{{}}};\n",
+                counts.join(", "),
+                offsets.join(", ")
+            ));
+            if !nearest {
+                tables.push_str(&format!(
+                    "static const float weight{i}[] = {{{}}};\n",
+                    weights.join(", ")
+                ));
+            }
+        }
 
-        let scale = output_h as f32 / input_h as f32;
+        let mut loops = String::new();
+        let mut closes = String::new();
+        for (i, &o) in output.dims.iter().enumerate() {
+            loops.push_str(&format!("for (int o{i} = 0; o{i} < {o}; o{i}++) {{\n"));
+            closes.push_str("}\n");
+        }
+        let outside = (0..rank)
+            .map(|i| format!("count{i}[o{i}] == 0"))
+            .collect::<Vec<_>>()
+            .join(" || ");
+        let ty = get_c_type(input.elem_ty);
+        let extrapolation = if input.elem_ty.is_f32() {
+            format!("{:e}f", resize.extrapolation_value)
+        } else {
+            format!("({ty}){}", resize.extrapolation_value)
+        };
 
-        if resize.mode == "nearest" {
-            let kernel = format!("for (int i = 0; i < {outer}; i++) {{
-    for (int h = 0; h < {output_h}; h++) {{
-        const int ih = ((int)((float)h / (float){scale})) * {input_w};
-        for (int w = 0; w < {output_w}; w++) {{
-            const int iw = (float)w / (float){scale};
-            {output_name}[i * {output_hw} + h * {output_w} + w] = {input_name}[i * {input_hw} + ih + iw];
-        }}
-    }}
-}}",
-                input_name = input_names[0],
-                output_name = output_names[0]
-            );
-            Ok(kernel)
+        let body = if nearest {
+            let offset = (0..rank)
+                .map(|i| format!("offset{i}[o{i} * width{i}]"))
+                .collect::<Vec<_>>()
+                .join(" + ");
+            format!("*out++ = {input_name}[{offset}];\n")
         } else {
-            Err(SessionError::Message(
-                format!("Resize: mode '{}' not supported", resize.mode).into(),
-            ))
+            let mut sum = String::from("float acc = 0.0f;\n");
+            for i in 0..rank {
+                let (p, w) = if i == 0 {
+                    ("0".to_string(), "1.0f".to_string())
+                } else {
+                    (format!("p{}", i - 1), format!("w{}", i - 1))
+                };
+                sum.push_str(&format!(
+                    "for (int k{i} = 0; k{i} < count{i}[o{i}]; k{i}++) {{
+const int p{i} = {p} + offset{i}[o{i} * width{i} + k{i}];
+const float w{i} = {w} * weight{i}[o{i} * width{i} + k{i}];\n"
+                ));
+            }
+            sum.push_str(&format!(
+                "acc += w{last} * {input_name}[p{last}];\n",
+                last = rank - 1
+            ));
+            for _ in 0..rank {
+                sum.push_str("}\n");
+            }
+            sum.push_str("*out++ = acc;\n");
+            sum
+        };
+
+        let kernel = format!(
+            "{tables}{ty} *out = {output_name};
+{loops}if ({outside}) {{
+*out++ = {extrapolation};
+continue;
+}}
+{body}{closes}"
+        );
+
+        Ok(kernel)
+    }
+
+    fn translate_pad(
+        &mut self,
+        pad: &Pad,
+        args: &[String],
+        inputs: &[&TypedFixedShape],
+        outputs: &[TypedFixedShape],
+    ) -> Result<String, SessionError> {
+        let input_name = &args[Op::PAD_IN_DATA];
+        let output_name = &args[inputs.len()..][0];
+        let input = inputs[Op::PAD_IN_DATA];
+        let output = &outputs[Op::PAD_OUT];
+        let rank = input.dims.len();
+        // Pads are normalized in shape inference, so they cover every axis here.
+        assert_eq!(pad.pads.len(), rank * 2);
+
+        let ty = get_c_type(input.elem_ty);
+        let value = match inputs.get(Op::PAD_IN_CONSTANT_VALUE) {
+            Some(shape) if shape.dims.total_elems() > 0 => {
+                format!("{}[0]", args[Op::PAD_IN_CONSTANT_VALUE])
+            }
+            _ if input.elem_ty.is_f32() => format!("{:e}f", pad.value),
+            _ => format!("({ty}){}", pad.value),
+        };
+
+        // For each axis, `i` is the input index of the output index `o`, which takes `value`
+        // if out of range in the constant mode.
+        let mut offset = String::new();
+        let in_strides = input.dims.strides();
+        let out_strides = output.dims.strides();
+        for axis in 0..rank {
+            let (len, out_len) = (input.dims[axis] as i64, output.dims[axis]);
+            let (begin, stride) = (pad.pads[axis], in_strides[axis]);
+            let out_stride = out_strides[axis];
+            let index = format!("(int64_t)(o / {out_stride} % {out_len})");
+            if begin == 0 && len as usize == out_len {
+                offset.push_str(&format!("offset += {index} * {stride};\n"));
+                continue;
+            }
+            let source = match pad.mode.as_str() {
+                "constant" => format!("if (i < 0 || i >= {len}) {{\n    inside = 0;\n}}"),
+                "edge" => format!("i = i < 0 ? 0 : (i >= {len} ? {} : i);", len - 1),
+                "wrap" => format!("i = (i % {len} + {len}) % {len};"),
+                "reflect" if len == 1 => "i = 0;".to_string(),
+                "reflect" => {
+                    let period = 2 * (len - 1);
+                    format!(
+                        "i = (i % {period} + {period}) % {period};
+if (i >= {len}) {{
+    i = {period} - i;
+}}"
+                    )
+                }
+                mode => {
+                    return Err(SessionError::Message(
+                        format!("Pad: Unknown mode {mode}").into(),
+                    ))
+                }
+            };
+            let shift = if begin < 0 {
+                format!("+ {}", -begin)
+            } else {
+                format!("- {begin}")
+            };
+            offset.push_str(&format!(
+                "{{
+    int64_t i = {index} {shift};
+{source}
+    offset += i * {stride};
+}}\n",
+                source = indent_all_by(4, source)
+            ));
         }
+
+        let kernel = format!(
+            "#pragma omp parallel for num_threads({th})
+for (int o = 0; o < {size}; o++) {{
+    int64_t offset = 0;
+    int inside = 1;
+{offset}
+    {output_name}[o] = inside ? {input_name}[offset] : {value};
+}}",
+            th = self.intra_op_num_threads,
+            size = output.dims.total_elems(),
+            offset = indent_all_by(4, offset.trim_end().to_string()),
+        );
+
+        Ok(kernel)
+    }
+
+    fn translate_depth_to_space(
+        &mut self,
+        d2s: &DepthToSpace,
+        args: &[String],
+        inputs: &[&TypedFixedShape],
+        outputs: &[TypedFixedShape],
+    ) -> Result<String, SessionError> {
+        let input = inputs[Op::DEPTHTOSPACE_IN];
+        let b = d2s.blocksize as usize;
+        let &[n, c, h, w] = input.dims.as_slice() else {
+            return Err(SessionError::Message(
+                "DepthToSpace: Input must be 4-D".into(),
+            ));
+        };
+        let c = c / (b * b);
+        let (hw, chw) = (h * w, c * h * w);
+        // The dimensions and strides of the input in the order of output elements, which are
+        // laid out as `[n, c, h, b1, w, b2]`.
+        let view = match d2s.mode.as_str() {
+            // The input channels are laid out as `[b1, b2, c]`.
+            "DCR" => [
+                (n, b * b * chw),
+                (c, hw),
+                (h, w),
+                (b, b * chw),
+                (w, 1),
+                (b, chw),
+            ],
+            // The input channels are laid out as `[c, b1, b2]`.
+            _ => [
+                (n, b * b * chw),
+                (c, b * b * hw),
+                (h, w),
+                (b, b * hw),
+                (w, 1),
+                (b, hw),
+            ],
+        };
+
+        Ok(self.translate_view(&view, args, inputs, &outputs[Op::DEPTHTOSPACE_OUT]))
+    }
+
+    fn translate_space_to_depth(
+        &mut self,
+        s2d: &SpaceToDepth,
+        args: &[String],
+        inputs: &[&TypedFixedShape],
+        outputs: &[TypedFixedShape],
+    ) -> Result<String, SessionError> {
+        let input = inputs[Op::SPACETODEPTH_IN];
+        let b = s2d.blocksize as usize;
+        let &[n, c, h, w] = input.dims.as_slice() else {
+            return Err(SessionError::Message(
+                "SpaceToDepth: Input must be 4-D".into(),
+            ));
+        };
+        // The output channels are laid out as `[b1, b2, c]` and each input spatial axis as
+        // `[h, b1]` and `[w, b2]`.
+        let view = [
+            (n, c * h * w),
+            (b, w),
+            (b, 1),
+            (c, h * w),
+            (h / b, b * w),
+            (w / b, b),
+        ];
+
+        Ok(self.translate_view(&view, args, inputs, &outputs[Op::SPACETODEPTH_OUT]))
+    }
+
+    /// Copies the elements of the input seen through `view` of `(dim, stride)` into the output.
+    fn translate_view(
+        &self,
+        view: &[(usize, usize)],
+        args: &[String],
+        inputs: &[&TypedFixedShape],
+        output: &TypedFixedShape,
+    ) -> String {
+        format!(
+            "#pragma omp parallel for num_threads({th})
+for (int o = 0; o < {size}; o++) {{
+    {output_name}[o] = {input_name}[{index}];
+}}",
+            th = self.intra_op_num_threads,
+            size = output.dims.total_elems(),
+            input_name = args[0],
+            output_name = args[inputs.len()..][0],
+            index = index_expr("o", view),
+        )
     }
 
     fn create_file(&self, name: &str) -> Result<File, SessionError> {
@@ -3317,67 +4083,26 @@ impl CraneliftCtx {
     }
 }
 
-impl Regions {
-    fn alloc(&mut self, id: ValueId, size: usize) -> Range<usize> {
-        let region = self.find_first_free_region(size);
-        self.val_to_start.insert(id, region.start);
-        self.start_to_region.insert(region.start, region.clone());
-        self.max_allocated = self
-            .max_allocated
-            .max(self.start_to_region.last_key_value().unwrap().1.end);
-        region
-    }
-
-    fn free(&mut self, id: ValueId) {
-        if let Some(start) = self.val_to_start.remove(&id) {
-            self.start_to_region.remove(&start).unwrap();
-        }
-    }
-
-    fn find_first_free_region(&self, size: usize) -> Range<usize> {
-        // Sorted by start offset
-        let regions = self.start_to_region.values().collect::<Vec<_>>();
-        log::debug!(
-            "regions: {regions:?} (count: {count})",
-            count = regions.len()
-        );
-        fn roundup(x: usize) -> usize {
-            let mask = 32 - 1;
-            (x + mask) & !mask
-        }
-        match self.start_to_region.len() {
-            0 => 0..roundup(size),
-            1 if regions[0].start >= size => 0..roundup(size),
-            1 => roundup(regions[0].end)..roundup(regions[0].end + size),
-            _ => {
-                for r in regions.windows(2) {
-                    let [cur, next] = [r[0], r[1]];
-                    if next.start - cur.end >= size {
-                        return roundup(cur.end)..roundup(cur.end + size);
-                    }
-                }
-                let last = regions.last().unwrap();
-                roundup(last.end)..roundup(last.end + size)
-            }
-        }
-    }
-}
-
 const fn get_clif_type(t: TensorElemType) -> Type {
     match t {
         TensorElemType::F32 => F32,
         TensorElemType::I32 => I32,
         TensorElemType::I64 => I64,
         TensorElemType::Bool => I8,
+        TensorElemType::I8 | TensorElemType::U8 => I8,
+        // Only used as storage. See `CPUSessionBuilder::build`.
+        TensorElemType::F16 | TensorElemType::BF16 => I16,
     }
 }
 
 fn value_name(model: &Model, id: ValueId) -> String {
     let value = &model.graph.values.inner()[id];
+    // Omitted optional values are named "".
     escape_name(
         value
             .name
             .clone()
+            .filter(|name| !name.is_empty())
             .unwrap_or_else(|| format!("Value_noname_{}", id.index())),
     )
 }
@@ -3408,12 +4133,75 @@ fn compute_sha1_from_files(paths: &[PathBuf]) -> Option<[u8; 20]> {
     hash.get(..20)?.try_into().ok()
 }
 
+/// Returns the C expression of the offset of the `var`-th element of the (dim, stride) axes,
+/// merging axes contiguous with each other.
+fn index_expr(var: &str, axes: &[(usize, usize)]) -> String {
+    let mut merged: Vec<(usize, usize)> = vec![];
+    for &(dim, stride) in axes.iter().filter(|(dim, _)| *dim != 1) {
+        match merged.last_mut() {
+            Some(last) if last.1 == dim * stride => *last = (last.0 * dim, stride),
+            _ => merged.push((dim, stride)),
+        }
+    }
+    let mut terms = vec![];
+    let mut step = 1;
+    for (i, &(dim, stride)) in merged.iter().enumerate().rev() {
+        let mut term = var.to_string();
+        if step != 1 {
+            term = format!("{term} / {step}");
+        }
+        if i != 0 {
+            term = format!("{term} % {dim}");
+        }
+        if stride != 1 {
+            term = format!("{term} * {stride}");
+        }
+        terms.push(term);
+        step *= dim;
+    }
+    terms.reverse();
+    if terms.is_empty() {
+        "0".to_string()
+    } else {
+        terms.join(" + ")
+    }
+}
+
+/// Returns the C expression of the unary math `op` applied to `x`, or `None` if `op` isn't one.
+fn unary_expr(op: &Op, x: &str) -> Option<String> {
+    let expr = match op {
+        Op::Abs => format!("fabsf({x})"),
+        Op::Neg => format!("(-{x})"),
+        Op::Log => format!("logf({x})"),
+        Op::Reciprocal => format!("(1.0f / {x})"),
+        Op::Floor => format!("floorf({x})"),
+        Op::Ceil => format!("ceilf({x})"),
+        Op::Sin => format!("sinf({x})"),
+        Op::Cos => format!("cosf({x})"),
+        Op::Softplus => format!("(fmaxf({x}, 0.0f) + log1pf(expf(-fabsf({x}))))"),
+        Op::Softsign => format!("({x} / (1.0f + fabsf({x})))"),
+        Op::Elu(Elu { alpha }) => format!("({x} < 0.0f ? {alpha:?}f * expm1f({x}) : {x})"),
+        Op::Selu(Selu { alpha, gamma }) => {
+            format!("({gamma:?}f * ({x} <= 0.0f ? {alpha:?}f * expm1f({x}) : {x}))")
+        }
+        Op::Mish => format!("({x} * tanhf(log1pf(expf({x}))))"),
+        Op::HardSwish => format!("({x} * fminf(1.0f, fmaxf(0.0f, {x} / 6.0f + 0.5f)))"),
+        Op::Sign => format!("(float)(({x} > 0.0f) - ({x} < 0.0f))"),
+        _ => return None,
+    };
+    Some(expr)
+}
+
 const fn get_c_type(ty: TensorElemType) -> &'static str {
     match ty {
         TensorElemType::F32 => "float",
         TensorElemType::I32 => "int32_t",
         TensorElemType::I64 => "int64_t",
         TensorElemType::Bool => "unsigned char",
+        TensorElemType::I8 => "int8_t",
+        TensorElemType::U8 => "uint8_t",
+        // Only used as storage. See `CPUSessionBuilder::build`.
+        TensorElemType::F16 | TensorElemType::BF16 => "uint16_t",
     }
 }
 
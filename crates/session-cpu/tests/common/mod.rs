@@ -0,0 +1,164 @@
+use std::path::Path;
+
+use altius_core::{
+    analysis::shape::infer_shapes,
+    model::Model,
+    node::Node,
+    onnx::{load_onnx, save::save_onnx},
+    op::Op,
+    tensor::{Tensor, TensorElemType, TypedShape},
+};
+use altius_session_cpu::CPUSessionBuilder;
+use ndarray::{CowArray, IxDyn};
+use ort::{Environment, ExecutionProvider, SessionBuilder, Value};
+use rustc_hash::FxHashMap;
+
+/// Builds a model of a single `op` whose inputs are graph inputs shaped like `inputs`,
+/// followed by `inits` given as initializers. `None` in `inits` is an omitted optional input,
+/// and trailing ones are dropped.
+pub fn model(
+    opset_version: i64,
+    op: Op,
+    inputs: &[&Tensor],
+    mut inits: Vec<Option<Tensor>>,
+    num_outputs: usize,
+) -> Model {
+    while matches!(inits.last(), Some(None)) {
+        inits.pop();
+    }
+    let mut model = Model {
+        opset_version,
+        ..Default::default()
+    };
+    let mut ins = vec![];
+    for (i, x) in inputs.iter().enumerate() {
+        let id = model.graph.values.new_val_named_and_shaped(
+            format!("x{i}"),
+            TypedShape::new(x.dims().clone().into(), x.elem_ty()),
+        );
+        model.graph.inputs.push(id);
+        ins.push(id);
+    }
+    let omitted = model.graph.values.new_val_named("");
+    for (i, init) in inits.into_iter().enumerate() {
+        ins.push(init.map_or(omitted, |init| {
+            let id = model.graph.values.new_val_named_and_shaped(
+                format!("c{i}"),
+                TypedShape::new(init.dims().clone().into(), init.elem_ty()),
+            );
+            model.graph.inits.insert(id, init);
+            id
+        }));
+    }
+    let outs = (0..num_outputs)
+        .map(|i| model.graph.values.new_val_named(format!("y{i}")))
+        .collect::<Vec<_>>();
+    model.graph.outputs.clone_from(&outs);
+    model
+        .graph
+        .add_node(Node::new(op).with_ins(ins).with_outs(outs));
+    model
+}
+
+/// `dims` of uniform random values in `[lo, hi)`.
+pub fn rand(dims: Vec<usize>, lo: f32, hi: f32) -> Tensor {
+    let x = Tensor::rand_of_type(TensorElemType::F32, dims.into());
+    let data = x.data::<f32>().iter().map(|x| lo + x * (hi - lo)).collect();
+    Tensor::new(x.dims().clone(), data)
+}
+
+/// Runs `model` on both onnxruntime and `CPUSession`, and checks that every output agrees.
+/// `f32` outputs may differ by `eps` relative to their magnitude (or absolutely below 1).
+pub fn assert_same_as_ort(mut model: Model, inputs: Vec<Tensor>, eps: f32) {
+    let _ = env_logger::try_init();
+
+    // Saved graph outputs need their shapes.
+    let mut value_shapes = FxHashMap::default();
+    infer_shapes(&model, &mut FxHashMap::default(), &mut value_shapes).unwrap();
+    for &id in &model.graph.outputs {
+        let shape = &value_shapes[&id];
+        model.graph.values.inner_mut()[id].shape =
+            Some(TypedShape::new(shape.dims.clone().into(), shape.elem_ty));
+    }
+
+    let path = tempfile::NamedTempFile::new().unwrap();
+    let path = path.path();
+    save_onnx(&model, path).unwrap();
+
+    let output_types = model
+        .graph
+        .outputs
+        .iter()
+        .map(|id| value_shapes[id].elem_ty)
+        .collect::<Vec<_>>();
+    let ort_outputs = run_ort(path, &inputs, &output_types);
+
+    let sess = CPUSessionBuilder::new(load_onnx(path).unwrap())
+        .build()
+        .unwrap();
+    let altius_outputs = sess.run(inputs).unwrap();
+
+    assert_eq!(ort_outputs.len(), altius_outputs.len());
+    for (ort, altius) in ort_outputs.iter().zip(altius_outputs.iter()) {
+        assert_eq!(ort.dims(), altius.dims());
+        match altius.elem_ty() {
+            TensorElemType::F32 => {
+                ort.data::<f32>()
+                    .iter()
+                    .zip(altius.data::<f32>())
+                    .for_each(|(ort, altius)| {
+                        assert!(
+                            (ort - altius).abs() <= eps * ort.abs().max(1.0),
+                            "{} != {}",
+                            ort,
+                            altius
+                        );
+                    })
+            }
+            TensorElemType::I64 => assert_eq!(ort.data::<i64>(), altius.data::<i64>()),
+            ty => panic!("Unsupported output type {ty:?}"),
+        }
+    }
+}
+
+fn run_ort(path: &Path, inputs: &[Tensor], output_types: &[TensorElemType]) -> Vec<Tensor> {
+    let env = Environment::builder()
+        .with_execution_providers(&[ExecutionProvider::CPU(Default::default())])
+        .build()
+        .unwrap()
+        .into_arc();
+    let sess = SessionBuilder::new(&env)
+        .unwrap()
+        .with_model_from_file(path)
+        .unwrap();
+    let arrays = inputs
+        .iter()
+        .map(|x| {
+            CowArray::from(x.data::<f32>())
+                .into_shape(IxDyn(x.dims().as_slice()))
+                .unwrap()
+        })
+        .collect::<Vec<_>>();
+    let values = arrays
+        .iter()
+        .map(|x| Value::from_array(sess.allocator(), x).unwrap())
+        .collect::<Vec<_>>();
+    sess.run(values)
+        .unwrap()
+        .iter()
+        .zip(output_types)
+        .map(|(y, ty)| match ty {
+            TensorElemType::F32 => {
+                let y = y.try_extract::<f32>().unwrap();
+                let y = y.view();
+                Tensor::new(y.shape().to_vec().into(), y.iter().copied().collect())
+            }
+            TensorElemType::I64 => {
+                let y = y.try_extract::<i64>().unwrap();
+                let y = y.view();
+                Tensor::new(y.shape().to_vec().into(), y.iter().copied().collect())
+            }
+            ty => panic!("Unsupported output type {ty:?}"),
+        })
+        .collect()
+}
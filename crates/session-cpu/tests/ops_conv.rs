@@ -74,6 +74,7 @@ impl<'a> Exporter<'a> {
         let mut model = Model {
             graph: Graph::default(),
             opset_version: 12,
+            ..Default::default()
         };
         let x = model.graph.values.new_val_named_and_shaped(
             "x",
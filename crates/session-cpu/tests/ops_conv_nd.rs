@@ -0,0 +1,167 @@
+mod common;
+
+use altius_core::op::{Conv2d, ConvTranspose, Op};
+use common::{assert_same_as_ort, model, rand};
+
+/// Runs `op` on `x` shaped like `x_dims`, weight shaped like `w_dims` and an optional bias.
+fn run(op: Op, x_dims: Vec<usize>, w_dims: Vec<usize>, bias: Option<usize>) {
+    let x = rand(x_dims, -1., 1.);
+    let w = rand(w_dims, -1., 1.);
+    let b = bias.map(|len| rand(vec![len], -1., 1.));
+    assert_same_as_ort(model(12, op, &[&x], vec![Some(w), b], 1), vec![x], 1e-4);
+}
+
+fn conv(kernel_shape: Vec<usize>) -> Conv2d {
+    Conv2d {
+        auto_pad: "NOTSET".into(),
+        kernel_shape: kernel_shape.into(),
+        group: 1,
+        ..Default::default()
+    }
+}
+
+fn conv_transpose(kernel_shape: Vec<usize>) -> ConvTranspose {
+    ConvTranspose {
+        auto_pad: "NOTSET".into(),
+        kernel_shape: kernel_shape.into(),
+        group: 1,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn cpu_ops_conv1d() {
+    run(
+        Op::Conv2d(conv(vec![3])),
+        vec![1, 2, 17],
+        vec![4, 2, 3],
+        None,
+    );
+    run(
+        Op::Conv2d(Conv2d {
+            strides: vec![2].into(),
+            padding: vec![1, 2].into(),
+            dilations: vec![2].into(),
+            ..conv(vec![3])
+        }),
+        vec![2, 3, 20],
+        vec![6, 3, 3],
+        Some(6),
+    );
+    run(
+        Op::Conv2d(Conv2d {
+            group: 2,
+            auto_pad: "SAME_UPPER".into(),
+            ..conv(vec![4])
+        }),
+        vec![1, 4, 13],
+        vec![6, 2, 4],
+        Some(6),
+    );
+}
+
+#[test]
+fn cpu_ops_conv3d() {
+    run(
+        Op::Conv2d(conv(vec![3, 3, 3])),
+        vec![1, 2, 5, 6, 7],
+        vec![3, 2, 3, 3, 3],
+        None,
+    );
+    run(
+        Op::Conv2d(Conv2d {
+            strides: vec![1, 2, 2].into(),
+            padding: vec![1, 0, 1, 1, 0, 1].into(),
+            dilations: vec![1, 1, 2].into(),
+            ..conv(vec![2, 3, 3])
+        }),
+        vec![2, 2, 4, 8, 9],
+        vec![4, 2, 2, 3, 3],
+        Some(4),
+    );
+    run(
+        Op::Conv2d(Conv2d {
+            group: 2,
+            ..conv(vec![1, 2, 2])
+        }),
+        vec![1, 4, 3, 4, 4],
+        vec![4, 2, 1, 2, 2],
+        Some(4),
+    );
+}
+
+#[test]
+fn cpu_ops_conv_transpose() {
+    run(
+        Op::ConvTranspose(conv_transpose(vec![3, 3])),
+        vec![1, 2, 5, 5],
+        vec![2, 3, 3, 3],
+        None,
+    );
+    run(
+        Op::ConvTranspose(ConvTranspose {
+            strides: vec![3, 2].into(),
+            padding: vec![1, 2, 1, 2].into(),
+            ..conv_transpose(vec![3, 3])
+        }),
+        vec![1, 1, 3, 3],
+        vec![1, 2, 3, 3],
+        Some(2),
+    );
+    run(
+        Op::ConvTranspose(ConvTranspose {
+            strides: vec![3, 2].into(),
+            output_padding: vec![1, 1].into(),
+            ..conv_transpose(vec![3, 3])
+        }),
+        vec![2, 1, 3, 4],
+        vec![1, 2, 3, 3],
+        None,
+    );
+    run(
+        Op::ConvTranspose(ConvTranspose {
+            strides: vec![2, 2].into(),
+            output_shape: vec![10, 9].into(),
+            ..conv_transpose(vec![3, 3])
+        }),
+        vec![1, 2, 4, 4],
+        vec![2, 3, 3, 3],
+        Some(3),
+    );
+    run(
+        Op::ConvTranspose(ConvTranspose {
+            dilations: vec![2, 1].into(),
+            group: 2,
+            ..conv_transpose(vec![2, 3])
+        }),
+        vec![1, 4, 4, 5],
+        vec![4, 3, 2, 3],
+        Some(6),
+    );
+}
+
+#[test]
+fn cpu_ops_conv_transpose_1d_and_3d() {
+    run(
+        Op::ConvTranspose(ConvTranspose {
+            strides: vec![2].into(),
+            dilations: vec![2].into(),
+            group: 2,
+            output_shape: vec![16].into(),
+            ..conv_transpose(vec![3])
+        }),
+        vec![1, 4, 7],
+        vec![4, 1, 3],
+        Some(2),
+    );
+    run(
+        Op::ConvTranspose(ConvTranspose {
+            strides: vec![2, 1, 2].into(),
+            padding: vec![0, 1, 1, 1, 0, 0].into(),
+            ..conv_transpose(vec![2, 3, 2])
+        }),
+        vec![1, 2, 3, 4, 3],
+        vec![2, 2, 2, 3, 2],
+        Some(2),
+    );
+}
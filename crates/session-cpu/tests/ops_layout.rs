@@ -0,0 +1,63 @@
+mod common;
+
+use altius_core::{
+    op::{DepthToSpace, Op, Pad, SpaceToDepth},
+    tensor::Tensor,
+};
+use common::{assert_same_as_ort, model, rand};
+
+/// Pads `x` by `pads`, padding only `axes` if given.
+fn run_pad(mode: &str, x: &Tensor, pads: Vec<i64>, value: Option<f32>, axes: Option<Vec<i64>>) {
+    let op = Op::Pad(Pad {
+        mode: mode.into(),
+        ..Default::default()
+    });
+    let inits = vec![
+        Some(Tensor::new(vec![pads.len()].into(), pads)),
+        value.map(|value| Tensor::new(vec![].into(), vec![value])),
+        axes.map(|axes| Tensor::new(vec![axes.len()].into(), axes)),
+    ];
+    assert_same_as_ort(model(18, op, &[x], inits, 1), vec![x.clone()], 0.);
+}
+
+#[test]
+fn cpu_ops_pad() {
+    let x = rand(vec![2, 3, 4, 5], -1., 1.);
+    for mode in ["constant", "edge", "reflect"] {
+        run_pad(mode, &x, vec![0, 1, 2, 3, 1, 0, 3, 2], None, None);
+        run_pad(mode, &x, vec![1, 2, 2, 1], None, Some(vec![1, -1]));
+    }
+    run_pad(
+        "constant",
+        &x,
+        vec![0, 0, 2, 1, 0, 1, 0, 3],
+        Some(1.5),
+        None,
+    );
+    // Negative pads crop the input.
+    run_pad("constant", &x, vec![0, -1, 2, -2, 0, 1, -1, 1], None, None);
+
+    let x = rand(vec![1, 6], -1., 1.);
+    run_pad("reflect", &x, vec![0, 5, 0, 5], None, None);
+}
+
+#[test]
+fn cpu_ops_depth_to_space() {
+    let x = rand(vec![2, 8, 3, 4], -1., 1.);
+    for mode in ["DCR", "CRD"] {
+        let op = Op::DepthToSpace(DepthToSpace {
+            blocksize: 2,
+            mode: mode.into(),
+        });
+        assert_same_as_ort(model(13, op, &[&x], vec![], 1), vec![x.clone()], 0.);
+    }
+}
+
+#[test]
+fn cpu_ops_space_to_depth() {
+    for (dims, blocksize) in [(vec![2, 3, 4, 6], 2), (vec![1, 2, 6, 9], 3)] {
+        let x = rand(dims, -1., 1.);
+        let op = Op::SpaceToDepth(SpaceToDepth { blocksize });
+        assert_same_as_ort(model(13, op, &[&x], vec![], 1), vec![x], 0.);
+    }
+}
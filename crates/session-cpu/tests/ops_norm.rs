@@ -0,0 +1,43 @@
+mod common;
+
+use altius_core::op::{GroupNormalization, InstanceNormalization, Op};
+use common::{assert_same_as_ort, model, rand};
+
+#[test]
+fn cpu_ops_instance_norm() {
+    for (dims, epsilon) in [(vec![2, 3, 4, 5], 1e-5), (vec![1, 4, 7], 0.1)] {
+        let x = rand(dims.clone(), -2., 2.);
+        let scale = rand(vec![dims[1]], 0.5, 1.5);
+        let b = rand(vec![dims[1]], -1., 1.);
+        let op = Op::InstanceNormalization(InstanceNormalization { epsilon });
+        assert_same_as_ort(
+            model(6, op, &[&x], vec![Some(scale), Some(b)], 1),
+            vec![x],
+            1e-4,
+        );
+    }
+}
+
+#[test]
+fn cpu_ops_group_norm() {
+    for (dims, num_groups) in [
+        (vec![2, 6, 4, 5], 3),
+        (vec![1, 4, 7], 4),
+        (vec![2, 4, 3, 3], 1),
+    ] {
+        let x = rand(dims, -2., 2.);
+        // Scale and bias are given per group until opset version 21.
+        let scale = rand(vec![num_groups], 0.5, 1.5);
+        let b = rand(vec![num_groups], -1., 1.);
+        let op = Op::GroupNormalization(GroupNormalization {
+            epsilon: 1e-3,
+            num_groups: num_groups as i64,
+            stash_type: 1,
+        });
+        assert_same_as_ort(
+            model(18, op, &[&x], vec![Some(scale), Some(b)], 1),
+            vec![x],
+            1e-4,
+        );
+    }
+}
@@ -0,0 +1,133 @@
+mod common;
+
+use altius_core::op::{Op, Pool};
+use common::{assert_same_as_ort, model, rand};
+
+fn run(op: Op, dims: Vec<usize>, num_outputs: usize) {
+    let x = rand(dims, -1., 1.);
+    assert_same_as_ort(model(12, op, &[&x], vec![], num_outputs), vec![x], 1e-5);
+}
+
+fn pool(kernel_shape: Vec<usize>) -> Pool {
+    let rank = kernel_shape.len();
+    Pool {
+        auto_pad: "NOTSET".into(),
+        kernel_shape: kernel_shape.into(),
+        strides: vec![1; rank].into(),
+        padding: vec![0; rank * 2].into(),
+        p: 2,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn cpu_ops_max_pool() {
+    run(
+        Op::MaxPool(Pool {
+            strides: vec![2, 2].into(),
+            padding: vec![1, 1, 1, 1].into(),
+            ..pool(vec![3, 3])
+        }),
+        vec![1, 3, 9, 9],
+        1,
+    );
+    run(
+        Op::MaxPool(Pool {
+            dilations: vec![2, 2].into(),
+            ..pool(vec![2, 2])
+        }),
+        vec![1, 2, 7, 7],
+        1,
+    );
+    run(
+        Op::MaxPool(Pool {
+            ceil_mode: true,
+            strides: vec![2, 2].into(),
+            ..pool(vec![3, 3])
+        }),
+        vec![1, 2, 8, 8],
+        1,
+    );
+    run(
+        Op::MaxPool(Pool {
+            auto_pad: "SAME_UPPER".into(),
+            strides: vec![2].into(),
+            ..pool(vec![2])
+        }),
+        vec![2, 3, 11],
+        1,
+    );
+    run(Op::MaxPool(pool(vec![2, 2, 2])), vec![1, 2, 4, 5, 6], 1);
+}
+
+#[test]
+fn cpu_ops_max_pool_indices() {
+    for storage_order in [0, 1] {
+        run(
+            Op::MaxPool(Pool {
+                storage_order,
+                strides: vec![2, 2].into(),
+                ..pool(vec![2, 2])
+            }),
+            vec![2, 3, 6, 4],
+            2,
+        );
+    }
+}
+
+#[test]
+fn cpu_ops_average_pool() {
+    for count_include_pad in [false, true] {
+        run(
+            Op::AveragePool(Pool {
+                count_include_pad,
+                padding: vec![1, 2, 1, 2].into(),
+                ..pool(vec![3, 3])
+            }),
+            vec![1, 3, 8, 8],
+            1,
+        );
+    }
+    run(
+        Op::AveragePool(Pool {
+            ceil_mode: true,
+            strides: vec![2].into(),
+            ..pool(vec![3])
+        }),
+        vec![1, 2, 10],
+        1,
+    );
+    run(
+        Op::AveragePool(Pool {
+            auto_pad: "SAME_LOWER".into(),
+            ..pool(vec![2, 3, 2])
+        }),
+        vec![1, 2, 3, 4, 5],
+        1,
+    );
+}
+
+#[test]
+fn cpu_ops_lp_pool() {
+    for p in [1, 2, 3] {
+        run(
+            Op::LpPool(Pool {
+                p,
+                strides: vec![2, 1].into(),
+                padding: vec![0, 1, 0, 1].into(),
+                ..pool(vec![2, 3])
+            }),
+            vec![1, 3, 6, 6],
+            1,
+        );
+    }
+    run(Op::LpPool(pool(vec![3])), vec![2, 2, 9], 1);
+}
+
+#[test]
+fn cpu_ops_global_pool() {
+    for dims in [vec![2, 3, 7], vec![1, 4, 5, 6], vec![1, 2, 3, 4, 5]] {
+        run(Op::GlobalMaxPool, dims.clone(), 1);
+        run(Op::GlobalAveragePool, dims, 1);
+    }
+}
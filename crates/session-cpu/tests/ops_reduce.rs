@@ -0,0 +1,88 @@
+mod common;
+
+use altius_core::{
+    op::{ArgReduce, Op, Reduce},
+    tensor::Tensor,
+};
+use common::{assert_same_as_ort, model, rand};
+
+const DIMS: [usize; 4] = [2, 3, 4, 5];
+
+/// Every reduce op with `reduce`, on inputs within the domain of the op.
+fn reduce_ops(reduce: Reduce) -> Vec<(Op, f32, f32)> {
+    vec![
+        (Op::ReduceMin(reduce.clone()), -1., 1.),
+        (Op::ReduceMax(reduce.clone()), -1., 1.),
+        (Op::ReduceMean(reduce.clone()), -1., 1.),
+        (Op::ReduceSum(reduce.clone()), -1., 1.),
+        (Op::ReduceProd(reduce.clone()), 0.5, 1.5),
+        (Op::ReduceL1(reduce.clone()), -1., 1.),
+        (Op::ReduceL2(reduce.clone()), -1., 1.),
+        (Op::ReduceLogSum(reduce.clone()), 0.1, 1.),
+        (Op::ReduceLogSumExp(reduce.clone()), -10., 10.),
+        (Op::ReduceSumSquare(reduce), -1., 1.),
+    ]
+}
+
+#[test]
+fn cpu_ops_reduce() {
+    for (axes, keep_dims) in [
+        (vec![1], true),
+        (vec![-1], false),
+        (vec![0, 2], true),
+        (vec![1, 3], false),
+        (vec![], true),
+        (vec![], false),
+    ] {
+        let reduce = Reduce {
+            axes,
+            keep_dims,
+            noop_with_empty_axes: false,
+        };
+        for (op, lo, hi) in reduce_ops(reduce) {
+            let x = rand(DIMS.to_vec(), lo, hi);
+            assert_same_as_ort(model(12, op, &[&x], vec![], 1), vec![x], 1e-4);
+        }
+    }
+}
+
+#[test]
+fn cpu_ops_reduce_axes_input() {
+    for (axes, noop_with_empty_axes) in [(vec![2i64, -3], false), (vec![], false), (vec![], true)] {
+        let reduce = Reduce {
+            axes: vec![],
+            keep_dims: true,
+            noop_with_empty_axes,
+        };
+        for (op, lo, hi) in reduce_ops(reduce) {
+            let x = rand(DIMS.to_vec(), lo, hi);
+            let axes =
+                (!axes.is_empty()).then(|| Tensor::new(vec![axes.len()].into(), axes.clone()));
+            assert_same_as_ort(model(18, op, &[&x], vec![axes], 1), vec![x], 1e-4);
+        }
+    }
+}
+
+#[test]
+fn cpu_ops_arg_reduce() {
+    // Few distinct values so that `select_last_index` matters.
+    let x = rand(DIMS.to_vec(), 0., 3.);
+    let x = Tensor::new(
+        x.dims().clone(),
+        x.data::<f32>().iter().map(|x| x.floor()).collect(),
+    );
+    for axis in [0, 1, -1] {
+        for keep_dims in [false, true] {
+            for select_last_index in [false, true] {
+                let arg = ArgReduce {
+                    axis,
+                    keep_dims,
+                    select_last_index,
+                };
+                for op in [Op::ArgMax(arg.clone()), Op::ArgMin(arg)] {
+                    assert_same_as_ort(model(12, op, &[&x], vec![], 1), vec![x.clone()], 0.);
+                }
+            }
+        }
+    }
+}
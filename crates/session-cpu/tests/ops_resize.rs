@@ -0,0 +1,147 @@
+mod common;
+
+use altius_core::{
+    op::{Op, Resize},
+    tensor::Tensor,
+};
+use common::{assert_same_as_ort, model, rand};
+
+/// Resizes `x` shaped like `dims` by either `scales` or `sizes`.
+fn run(
+    opset_version: i64,
+    resize: Resize,
+    dims: Vec<usize>,
+    roi: Option<Vec<f32>>,
+    scales: Option<Vec<f32>>,
+    sizes: Option<Vec<i64>>,
+) {
+    let x = rand(dims, -1., 1.);
+    let inits = vec![
+        roi.map(|roi| Tensor::new(vec![roi.len()].into(), roi)),
+        scales.map(|scales| Tensor::new(vec![scales.len()].into(), scales)),
+        sizes.map(|sizes| Tensor::new(vec![sizes.len()].into(), sizes)),
+    ];
+    let op = Op::Resize(resize);
+    assert_same_as_ort(model(opset_version, op, &[&x], inits, 1), vec![x], 1e-4);
+}
+
+fn resize(mode: &str, coordinate_transformation_mode: &str) -> Resize {
+    Resize {
+        coordinate_transformation_mode: coordinate_transformation_mode.into(),
+        cubic_coeff_a: -0.75,
+        exclude_outside: 0,
+        extrapolation_value: 0.,
+        mode: mode.into(),
+        nearest_mode: "round_prefer_floor".into(),
+    }
+}
+
+const COORDINATE_TRANSFORMATION_MODES: [&str; 4] = [
+    "half_pixel",
+    "pytorch_half_pixel",
+    "align_corners",
+    "asymmetric",
+];
+
+/// Upsamples and downsamples by `scales`, and resizes to `sizes` including an axis of size 1.
+fn run_each_coordinate_transformation_mode(resize: impl Fn(&str) -> Resize) {
+    for mode in COORDINATE_TRANSFORMATION_MODES {
+        run(
+            13,
+            resize(mode),
+            vec![1, 2, 4, 5],
+            None,
+            Some(vec![1., 1., 1.5, 2.]),
+            None,
+        );
+        run(
+            13,
+            resize(mode),
+            vec![2, 1, 6, 7],
+            None,
+            Some(vec![1., 1., 0.5, 0.6]),
+            None,
+        );
+        run(
+            13,
+            resize(mode),
+            vec![1, 2, 5, 4],
+            None,
+            None,
+            Some(vec![1, 2, 7, 1]),
+        );
+    }
+    run(
+        19,
+        resize("half_pixel_symmetric"),
+        vec![1, 2, 4, 5],
+        None,
+        Some(vec![1., 1., 1.5, 0.8]),
+        None,
+    );
+    run(
+        13,
+        Resize {
+            extrapolation_value: 10.,
+            ..resize("tf_crop_and_resize")
+        },
+        vec![1, 2, 4, 5],
+        Some(vec![0., 0., 0.4, -0.2, 1., 1., 0.6, 1.3]),
+        None,
+        Some(vec![1, 2, 3, 6]),
+    );
+}
+
+#[test]
+fn cpu_ops_resize_nearest() {
+    for nearest_mode in ["round_prefer_floor", "round_prefer_ceil", "floor", "ceil"] {
+        run_each_coordinate_transformation_mode(|mode| Resize {
+            nearest_mode: nearest_mode.into(),
+            ..resize("nearest", mode)
+        });
+    }
+}
+
+#[test]
+fn cpu_ops_resize_linear() {
+    run_each_coordinate_transformation_mode(|mode| resize("linear", mode));
+}
+
+#[test]
+fn cpu_ops_resize_cubic() {
+    for (cubic_coeff_a, exclude_outside) in [(-0.75, 0), (-0.5, 0), (-0.75, 1)] {
+        run_each_coordinate_transformation_mode(|mode| Resize {
+            cubic_coeff_a,
+            exclude_outside,
+            ..resize("cubic", mode)
+        });
+    }
+}
+
+#[test]
+fn cpu_ops_resize_nd() {
+    run(
+        13,
+        resize("nearest", "asymmetric"),
+        vec![2, 3, 2, 3, 4],
+        None,
+        Some(vec![1., 1., 2., 1.5, 0.5]),
+        None,
+    );
+    run(
+        13,
+        resize("linear", "half_pixel"),
+        vec![1, 2, 3, 4, 5],
+        None,
+        Some(vec![1., 1., 2., 1.5, 0.6]),
+        None,
+    );
+    run(
+        13,
+        resize("linear", "align_corners"),
+        vec![4, 6],
+        None,
+        None,
+        Some(vec![7, 3]),
+    );
+}
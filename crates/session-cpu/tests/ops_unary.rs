@@ -0,0 +1,53 @@
+mod common;
+
+use altius_core::op::{Elu, Op, Selu};
+use common::{assert_same_as_ort, model, rand};
+
+fn run(op: Op, lo: f32, hi: f32) {
+    let x = rand(vec![3, 4, 5], lo, hi);
+    assert_same_as_ort(model(18, op, &[&x], vec![], 1), vec![x], 1e-4);
+}
+
+#[test]
+fn cpu_ops_unary() {
+    for op in [
+        Op::Abs,
+        Op::Neg,
+        Op::Floor,
+        Op::Ceil,
+        Op::Sin,
+        Op::Cos,
+        Op::Softplus,
+        Op::Softsign,
+        Op::Elu(Elu::default()),
+        Op::Elu(Elu { alpha: 0.5 }),
+        Op::Selu(Selu::default()),
+        Op::Selu(Selu {
+            alpha: 2.0,
+            gamma: 0.5,
+        }),
+        Op::Mish,
+        Op::HardSwish,
+        Op::Sign,
+    ] {
+        run(op, -4., 4.);
+    }
+    // Defined only for positive inputs, or away from 0.
+    run(Op::Log, 0.1, 4.);
+    run(Op::Reciprocal, 0.25, 4.);
+    run(Op::Reciprocal, -4., -0.25);
+}
+
+#[test]
+fn cpu_ops_prelu() {
+    let x = rand(vec![2, 3, 4, 5], -1., 1.);
+    // Slopes broadcast unidirectionally to the input.
+    for dims in [vec![1], vec![3, 1, 1], vec![5], vec![2, 3, 4, 5]] {
+        let slope = rand(dims, -0.5, 0.5);
+        assert_same_as_ort(
+            model(16, Op::PRelu, &[&x], vec![Some(slope)], 1),
+            vec![x.clone()],
+            1e-5,
+        );
+    }
+}
@@ -1,5 +1,4 @@
-use altius_core::optimize::gelu_fusion::fuse_gelu;
-use altius_core::optimize::layer_norm_fusion::fuse_layer_norm;
+use altius_core::optimize::pass_manager::OptimizationLevel;
 use altius_core::{onnx::load_onnx, tensor::Tensor};
 use altius_session_interpreter::InterpreterSessionBuilder;
 use std::cmp::Ordering;
@@ -23,10 +22,8 @@ fn main() {
 
     let opt = Opt::from_args();
     let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("../../models");
-    let mut model = load_onnx(root.join("deit.onnx"))
+    let model = load_onnx(root.join("deit.onnx"))
         .expect("Failed to load model. Have you run altius-py/deit.py?");
-    fuse_gelu(&mut model);
-    fuse_layer_norm(&mut model);
 
     let image = image::open(root.join("cat.png")).unwrap().to_rgb8();
     let resized = image::imageops::resize(&image, 224, 224, image::imageops::FilterType::Triangle);
@@ -39,6 +36,7 @@ fn main() {
 
     let i = InterpreterSessionBuilder::new(model)
         .with_profiling_enabled(opt.profile)
+        .with_optimization_level(OptimizationLevel::O2)
         .build()
         .unwrap();
     for _ in 0..opt.iters {
@@ -1,5 +1,5 @@
 use altius_core::onnx::load_onnx;
-use altius_core::optimize::gelu_fusion::fuse_gelu;
+use altius_core::optimize::pass_manager::OptimizationLevel;
 use altius_core::tensor::{Tensor, TensorElemType};
 use altius_session_interpreter::InterpreterSessionBuilder;
 use std::path::PathBuf;
@@ -31,11 +31,9 @@ fn main() {
 
     log::info!("load onnx: start ({:?})", opt.onnx_path);
     let start = Instant::now();
-    let mut model = load_onnx(opt.onnx_path).unwrap();
+    let model = load_onnx(opt.onnx_path).unwrap();
     log::info!("load onnx: finished in {:?}", start.elapsed());
 
-    fuse_gelu(&mut model);
-
     log::info!(
         "create session: start (profile={:?}, threads={})",
         opt.profile,
@@ -44,6 +42,7 @@ fn main() {
     let start = Instant::now();
     let sess = InterpreterSessionBuilder::new(model)
         .with_profiling_enabled(opt.profile)
+        .with_optimization_level(OptimizationLevel::O2)
         .with_intra_op_num_threads(opt.threads)
         .build()
         .unwrap();
@@ -103,7 +102,13 @@ fn main() {
                 format!("{:?}", output.statistics::<i32>())
             }
             TensorElemType::I64 => "no stats".to_string(),
-            TensorElemType::Bool => "no stats".to_string(),
+            TensorElemType::I8 | TensorElemType::U8 | TensorElemType::Bool => {
+                "no stats".to_string()
+            }
+            TensorElemType::F16 | TensorElemType::BF16 => {
+                let output = output.cast_float(TensorElemType::F32).unwrap();
+                format!("{:?}", output.statistics::<f32>())
+            }
         };
         log::info!(
             "output({i}, name={}, ty={:?}, shape={:?}): {}",
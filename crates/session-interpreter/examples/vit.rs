@@ -1,4 +1,4 @@
-use altius_core::optimize::gelu_fusion::fuse_gelu;
+use altius_core::optimize::pass_manager::OptimizationLevel;
 use altius_core::{onnx::load_onnx, tensor::Tensor};
 use altius_session_interpreter::InterpreterSessionBuilder;
 use std::cmp::Ordering;
@@ -22,8 +22,7 @@ fn main() {
 
     let opt = Opt::from_args();
     let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("../../models");
-    let mut model = load_onnx(root.join("vit_b_16.onnx")).unwrap();
-    fuse_gelu(&mut model);
+    let model = load_onnx(root.join("vit_b_16.onnx")).unwrap();
 
     let image = image::open(root.join("cat.png")).unwrap().to_rgb8();
     let resized = image::imageops::resize(&image, 224, 224, image::imageops::FilterType::Triangle);
@@ -36,6 +35,7 @@ fn main() {
 
     let i = InterpreterSessionBuilder::new(model)
         .with_profiling_enabled(opt.profile)
+        .with_optimization_level(OptimizationLevel::O2)
         .with_intra_op_num_threads(16)
         .build()
         .unwrap();
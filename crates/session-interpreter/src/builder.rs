@@ -1,11 +1,24 @@
-use altius_core::{analysis::shape::infer_shapes, model::Model, tensor::Tensor};
+use std::sync::{Arc, Mutex};
+
+use altius_core::{
+    analysis::shape::infer_shapes,
+    custom_op::CustomOp,
+    dim::Dimension,
+    model::Model,
+    op::Op,
+    optimize::pass_manager::{OptimizationLevel, PassManager},
+    tensor::Tensor,
+};
 use altius_session::{plan::create_execution_plan, SessionError};
 use rustc_hash::FxHashMap;
 use thread_local::ThreadLocal;
 
 #[cfg(feature = "cuda")]
 use super::session::SafeCudnnContext;
-use super::{session::InterpreterSession, thread::ThreadCtx};
+use super::{
+    session::{InterpreterSession, ShapedPlan},
+    thread::ThreadCtx,
+};
 #[cfg(feature = "cuda")]
 use cudnn::CudnnContext;
 
@@ -13,6 +26,8 @@ pub struct InterpreterSessionBuilder {
     model: Model,
     intra_op_num_threads: usize,
     enable_profiling: bool,
+    optimization_level: OptimizationLevel,
+    verify_graph: bool,
 }
 
 impl InterpreterSessionBuilder {
@@ -21,6 +36,8 @@ impl InterpreterSessionBuilder {
             model,
             intra_op_num_threads: 1,
             enable_profiling: false,
+            optimization_level: OptimizationLevel::O0,
+            verify_graph: cfg!(debug_assertions),
         }
     }
 
@@ -34,13 +51,101 @@ impl InterpreterSessionBuilder {
         self
     }
 
+    /// Optimizes the model at `optimization_level` before building a session.
+    /// Defaults to `OptimizationLevel::O0`, which leaves the model as it is.
+    pub const fn with_optimization_level(mut self, optimization_level: OptimizationLevel) -> Self {
+        self.optimization_level = optimization_level;
+        self
+    }
+
+    /// Verifies the model before and after every optimization pass, so that a pass breaking
+    /// the model is reported by name. Enabled by default in debug builds.
+    pub const fn with_graph_verification(mut self, verify_graph: bool) -> Self {
+        self.verify_graph = verify_graph;
+        self
+    }
+
+    /// Registers `op` to compute `Op::Custom` nodes of `domain` and `op_type`.
+    pub fn with_custom_op(
+        mut self,
+        domain: impl Into<String>,
+        op_type: impl Into<String>,
+        op: impl CustomOp + 'static,
+    ) -> Self {
+        self.model
+            .custom_ops
+            .register(domain, op_type, Arc::new(op));
+        self
+    }
+
     pub fn build(self) -> Result<InterpreterSession, SessionError> {
-        let model = self.model;
+        let tctx = ThreadCtx::new_with_num_threads(self.intra_op_num_threads);
+        self.build_with_thread_ctx(tctx)
+    }
+
+    /// Builds a session sharing `tctx`, which subgraphs of control-flow ops do with their parents.
+    fn build_with_thread_ctx(self, tctx: ThreadCtx) -> Result<InterpreterSession, SessionError> {
+        let mut model = self.model;
+        for (_, node) in model.graph.nodes.iter().filter(|(_, n)| !n.deleted) {
+            if let Op::Custom(custom) = &node.op {
+                if model.custom_ops.get(custom).is_none() {
+                    return Err(SessionError::Message(
+                        format!(
+                            "No custom op is registered for {}.{}",
+                            custom.domain, custom.op_type
+                        )
+                        .into(),
+                    ));
+                }
+            }
+        }
         let enable_profiling = self.enable_profiling;
         let intra_op_num_threads = self.intra_op_num_threads;
 
+        PassManager::with_level(self.optimization_level)
+            .with_verification(self.verify_graph)
+            .run(&mut model)?;
+
+        // Symbols of dynamic dimensions in graph inputs. If there are any, shapes are
+        // resolved on each run once the actual input shapes are known.
+        let mut dynamic_symbols = vec![];
+        for &id in &model.graph.inputs {
+            let Some(shape) = &model.graph.values[id].shape else {
+                continue;
+            };
+            for dim in shape.dims.iter() {
+                if let Dimension::Dynamic(sym) = dim {
+                    if !dynamic_symbols.contains(sym) {
+                        dynamic_symbols.push(sym.clone());
+                    }
+                }
+            }
+        }
+
         let mut inferred_shapes = FxHashMap::default();
-        infer_shapes(&model, &mut inferred_shapes, &mut FxHashMap::default())?;
+        if dynamic_symbols.is_empty() {
+            infer_shapes(&model, &mut inferred_shapes, &mut FxHashMap::default())?;
+        }
+
+        let mut subgraph_sessions = FxHashMap::default();
+        for (node_id, node) in model.graph.nodes.iter().filter(|(_, n)| !n.deleted) {
+            let sessions = node
+                .op
+                .subgraphs()
+                .into_iter()
+                .map(|graph| {
+                    InterpreterSessionBuilder::new(model.subgraph_model(graph))
+                        .with_intra_op_num_threads(intra_op_num_threads)
+                        .with_profiling_enabled(enable_profiling)
+                        .with_optimization_level(self.optimization_level)
+                        .with_graph_verification(self.verify_graph)
+                        .build_with_thread_ctx(tctx.clone())
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            if !sessions.is_empty() {
+                subgraph_sessions.insert(node_id, sessions);
+            }
+        }
 
         #[cfg(target_os = "linux")]
         {
@@ -51,16 +156,22 @@ impl InterpreterSessionBuilder {
             unsafe { bli_thread_set_num_threads(intra_op_num_threads) };
         }
 
+        let execution_plans = create_execution_plan(&model);
+        let plan = ShapedPlan::new(&model, &execution_plans, inferred_shapes);
+
         Ok(InterpreterSession {
             #[cfg(feature = "cuda")]
             cudnn_ctx: SafeCudnnContext(CudnnContext::new().expect("cudnn context init failed")),
-            execution_plans: create_execution_plan(&model),
+            execution_plans,
             model,
-            inferred_shapes,
+            plan: Arc::new(plan),
+            dynamic_symbols,
+            plan_cache: Mutex::new(FxHashMap::default()),
             enable_profiling,
             values: ThreadLocal::new(),
             dummy_value: Tensor::zeros::<f32>(vec![0].into()),
-            tctx: ThreadCtx::new_with_num_threads(intra_op_num_threads),
+            tctx,
+            subgraph_sessions,
         })
     }
 }
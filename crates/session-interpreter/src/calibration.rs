@@ -0,0 +1,111 @@
+use altius_core::{
+    model::Model,
+    quantize::{calibration_targets, quantize, CalibrationTable, QuantizationConfig, Range},
+    tensor::Tensor,
+    value::ValueId,
+};
+use altius_session::SessionError;
+
+use super::{builder::InterpreterSessionBuilder, session::InterpreterSession};
+
+/// Collects the ranges of activations needed by `altius_core::quantize::quantize`
+/// by running calibration batches through an `InterpreterSession`.
+pub struct Calibrator {
+    session: InterpreterSession,
+    /// Calibration targets and their indices in the outputs of `session`.
+    targets: Vec<(ValueId, usize)>,
+    per_channel: bool,
+    table: CalibrationTable,
+}
+
+impl Calibrator {
+    pub fn new(model: &Model, config: &QuantizationConfig) -> Result<Self, SessionError> {
+        let mut model = model.clone();
+        let mut targets = vec![];
+        for id in calibration_targets(&model) {
+            // Make every target a graph output so that `run` returns it.
+            let idx = match model.graph.outputs.iter().position(|&o| o == id) {
+                Some(idx) => idx,
+                None => {
+                    model.graph.outputs.push(id);
+                    model.graph.outputs.len() - 1
+                }
+            };
+            targets.push((id, idx));
+        }
+
+        Ok(Self {
+            session: InterpreterSessionBuilder::new(model).build()?,
+            targets,
+            per_channel: config.per_channel_activations(),
+            table: CalibrationTable::default(),
+        })
+    }
+
+    /// Runs a batch and widens the ranges to cover what it produced.
+    pub fn calibrate(&mut self, inputs: Vec<Tensor>) -> Result<(), SessionError> {
+        let outputs = self.session.run(inputs)?;
+        for &(id, idx) in &self.targets {
+            let tensor = &outputs[idx];
+            if !tensor.elem_ty().is_f32() || tensor.dims().total_elems() == 0 {
+                continue;
+            }
+            let ranges = if self.per_channel && tensor.dims().len() >= 2 {
+                tensor
+                    .statistics_along_axis::<f32>(1)
+                    .iter()
+                    .map(|s| Range::new(s.min, s.max))
+                    .collect::<Vec<_>>()
+            } else {
+                let s = tensor.statistics::<f32>();
+                vec![Range::new(s.min, s.max)]
+            };
+            match self.table.get_mut(&id) {
+                Some(old) if old.len() == ranges.len() => {
+                    for (old, new) in old.iter_mut().zip(ranges) {
+                        *old = old.union(new);
+                    }
+                }
+                Some(old) => {
+                    return Err(SessionError::Message(
+                        format!(
+                            "Number of channels changed from {} to {} during calibration",
+                            old.len(),
+                            ranges.len()
+                        )
+                        .into(),
+                    ))
+                }
+                None => {
+                    self.table.insert(id, ranges);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn table(&self) -> &CalibrationTable {
+        &self.table
+    }
+
+    pub fn into_table(self) -> CalibrationTable {
+        self.table
+    }
+}
+
+/// Quantizes `model` with ranges calibrated on `batches`. Each batch is a list of graph inputs.
+/// The returned model can be saved with `altius_core::onnx::save_onnx`.
+pub fn quantize_static(
+    model: &Model,
+    batches: impl IntoIterator<Item = Vec<Tensor>>,
+    config: &QuantizationConfig,
+) -> Result<Model, SessionError> {
+    let mut calibrator = Calibrator::new(model, config)?;
+    for inputs in batches {
+        calibrator.calibrate(inputs)?;
+    }
+
+    let mut model = model.clone();
+    quantize(&mut model, calibrator.table(), config)?;
+    Ok(model)
+}
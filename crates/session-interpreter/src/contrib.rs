@@ -0,0 +1,229 @@
+//! Kernels for ONNX Runtime's contrib operators (`com.microsoft`).
+
+use std::borrow::Cow;
+
+use altius_core::{
+    op::{Attention, FusedMatMul, Op},
+    tensor::Tensor,
+};
+use altius_session::SessionError;
+
+use super::{gemm::sgemm, quantize::batch_offsets};
+
+pub fn compute_fused_mat_mul(
+    op: &FusedMatMul,
+    inputs: &[&Tensor],
+    outputs: &mut [Tensor],
+) -> Result<(), SessionError> {
+    let a = inputs[Op::FUSEDMATMUL_IN_A];
+    let b = inputs[Op::FUSEDMATMUL_IN_B];
+    let output = &mut outputs[Op::FUSEDMATMUL_OUT];
+    let (a_dims, b_dims) = (a.dims().as_slice(), b.dims().as_slice());
+    if a_dims.len() < 2 || b_dims.len() < 2 {
+        return Err(SessionError::Message(
+            format!("FusedMatMul: Unsupported shapes: {a_dims:?}, {b_dims:?}").into(),
+        ));
+    }
+
+    let (a_batch, &[a_rows, a_cols]) = a_dims.split_at(a_dims.len() - 2) else {
+        unreachable!()
+    };
+    let (b_batch, &[b_rows, b_cols]) = b_dims.split_at(b_dims.len() - 2) else {
+        unreachable!()
+    };
+    let (m, k) = if op.trans_a {
+        (a_cols, a_rows)
+    } else {
+        (a_rows, a_cols)
+    };
+    let n = if op.trans_b { b_rows } else { b_cols };
+
+    let out_dims = output.dims().clone();
+    let batch_dims = &out_dims[..out_dims.len() - 2];
+    let a_offsets = batch_offsets(batch_dims, a_batch);
+    let b_offsets = batch_offsets(batch_dims, b_batch);
+    let a_data = a.data::<f32>();
+    let b_data = b.data::<f32>();
+    for ((c, a_offset), b_offset) in output
+        .data_mut::<f32>()
+        .chunks_mut(m * n)
+        .zip(a_offsets)
+        .zip(b_offsets)
+    {
+        let a = &a_data[a_offset * m * k..][..m * k];
+        let b = &b_data[b_offset * k * n..][..k * n];
+        let a = if op.trans_a {
+            Cow::Owned(transpose(a, k, m))
+        } else {
+            Cow::Borrowed(a)
+        };
+        let b = if op.trans_b {
+            Cow::Owned(transpose(b, n, k))
+        } else {
+            Cow::Borrowed(b)
+        };
+        sgemm(m, k, n, op.alpha, &a, k, &b, n, 0., c, n);
+    }
+
+    Ok(())
+}
+
+/// Multi-head self-attention over a single projection of Q, K and V.
+pub fn compute_attention(
+    op: &Attention,
+    inputs: &[&Tensor],
+    outputs: &mut [Tensor],
+) -> Result<(), SessionError> {
+    let input = inputs[Op::ATTENTION_IN_INPUT];
+    let weights = inputs[Op::ATTENTION_IN_WEIGHTS];
+    // Omitted inputs have no elements.
+    let bias = inputs
+        .get(Op::ATTENTION_IN_BIAS)
+        .filter(|b| b.dims().total_elems() > 0);
+    let mask = inputs
+        .get(Op::ATTENTION_IN_MASK_INDEX)
+        .filter(|m| m.dims().total_elems() > 0);
+
+    let [batch, seq_len, input_hidden_size] = input.fixed_dims::<3>();
+    let total_hidden_size = weights.dims()[1];
+    let (q_hidden_size, k_hidden_size, v_hidden_size) = match op.qkv_hidden_sizes[..] {
+        [] => (
+            total_hidden_size / 3,
+            total_hidden_size / 3,
+            total_hidden_size / 3,
+        ),
+        [q, k, v] => (q, k, v),
+        _ => unreachable!("Checked by Model::verify"),
+    };
+    if q_hidden_size != k_hidden_size
+        || q_hidden_size + k_hidden_size + v_hidden_size != total_hidden_size
+    {
+        return Err(SessionError::Message(
+            format!(
+                "Attention: Invalid hidden sizes of Q, K and V: {:?} for weights of {:?}",
+                (q_hidden_size, k_hidden_size, v_hidden_size),
+                weights.dims()
+            )
+            .into(),
+        ));
+    }
+    if op.num_heads == 0 || q_hidden_size % op.num_heads != 0 || v_hidden_size % op.num_heads != 0 {
+        return Err(SessionError::Message(
+            format!(
+                "Attention: Hidden sizes of Q, K and V: {:?} are not divisible by num_heads: {}",
+                (q_hidden_size, k_hidden_size, v_hidden_size),
+                op.num_heads
+            )
+            .into(),
+        ));
+    }
+    let head_size = q_hidden_size / op.num_heads;
+    let v_head_size = v_hidden_size / op.num_heads;
+    let scale = op.scale.unwrap_or(1. / (head_size as f32).sqrt());
+
+    let mask = match mask {
+        Some(mask) if !mask.elem_ty().is_i32() => {
+            return Err(SessionError::Message(
+                "Attention: mask_index must be int32".into(),
+            ))
+        }
+        Some(mask) => Some((mask.dims().as_slice(), mask.data::<i32>())),
+        None => None,
+    };
+    // Returns true if the query `i` must not attend to the key `j` in the batch `b`.
+    let is_masked = |b: usize, i: usize, j: usize| -> Result<bool, SessionError> {
+        if op.unidirectional && j > i {
+            return Ok(true);
+        }
+        Ok(match mask {
+            None => false,
+            // End positions of each sequence.
+            Some((&[n], mask)) if n == batch => j as i32 >= mask[b],
+            Some((&[n, s], mask)) if n == batch && s == seq_len => mask[b * seq_len + j] == 0,
+            Some((&[n, s0, s1], mask)) if n == batch && s0 == seq_len && s1 == seq_len => {
+                mask[(b * seq_len + i) * seq_len + j] == 0
+            }
+            Some((dims, _)) => {
+                return Err(SessionError::Message(
+                    format!("Attention: Unsupported mask_index shape {dims:?}").into(),
+                ))
+            }
+        })
+    };
+
+    // [batch * seq_len, total_hidden_size]
+    let mut qkv = vec![0f32; batch * seq_len * total_hidden_size];
+    if let Some(bias) = bias {
+        for row in qkv.chunks_mut(total_hidden_size) {
+            row.copy_from_slice(bias.data::<f32>());
+        }
+    }
+    sgemm(
+        batch * seq_len,
+        input_hidden_size,
+        total_hidden_size,
+        1.,
+        input.data::<f32>(),
+        input_hidden_size,
+        weights.data::<f32>(),
+        total_hidden_size,
+        if bias.is_some() { 1. } else { 0. },
+        &mut qkv,
+        total_hidden_size,
+    );
+
+    let output = outputs[Op::ATTENTION_OUT].data_mut::<f32>();
+    let mut probs = vec![0f32; seq_len];
+    for b in 0..batch {
+        let qkv = &qkv[b * seq_len * total_hidden_size..][..seq_len * total_hidden_size];
+        let row =
+            |s: usize, offset: usize, size: usize| &qkv[s * total_hidden_size + offset..][..size];
+        for h in 0..op.num_heads {
+            let (q_offset, k_offset, v_offset) = (
+                h * head_size,
+                q_hidden_size + h * head_size,
+                q_hidden_size + k_hidden_size + h * v_head_size,
+            );
+            for i in 0..seq_len {
+                let q = row(i, q_offset, head_size);
+                for (j, p) in probs.iter_mut().enumerate() {
+                    let k = row(j, k_offset, head_size);
+                    let score = q.iter().zip(k).map(|(q, k)| q * k).sum::<f32>() * scale;
+                    *p = if is_masked(b, i, j)? {
+                        score + op.mask_filter_value
+                    } else {
+                        score
+                    };
+                }
+                let max = probs.iter().fold(f32::NEG_INFINITY, |m, &p| m.max(p));
+                let mut sum = 0.;
+                for p in probs.iter_mut() {
+                    *p = (*p - max).exp();
+                    sum += *p;
+                }
+
+                let out = &mut output[(b * seq_len + i) * v_hidden_size + h * v_head_size..]
+                    [..v_head_size];
+                out.fill(0.);
+                for (j, p) in probs.iter().enumerate() {
+                    for (o, v) in out.iter_mut().zip(row(j, v_offset, v_head_size)) {
+                        *o += p / sum * v;
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Transposes a `rows` x `cols` row-major matrix.
+fn transpose(x: &[f32], rows: usize, cols: usize) -> Vec<f32> {
+    let mut y = vec![0.; x.len()];
+    for r in 0..rows {
+        for c in 0..cols {
+            y[c * rows + r] = x[r * cols + c];
+        }
+    }
+    y
+}
@@ -0,0 +1,101 @@
+//! Kernels for control-flow ops, which run their subgraphs in nested sessions.
+
+use altius_core::{
+    op::{If, Loop, Op},
+    tensor::{Tensor, TensorElemType},
+};
+use altius_session::SessionError;
+
+use super::session::InterpreterSession;
+
+pub fn compute_loop(
+    op: &Loop,
+    body: &InterpreterSession,
+    inputs: &[&Tensor],
+) -> Result<Vec<Tensor>, SessionError> {
+    // Omitted inputs have no elements.
+    let given = |i: usize| inputs.get(i).filter(|t| t.dims().total_elems() > 0);
+    let max_trip_count = given(Op::LOOP_IN_M).map_or(i64::MAX, |m| m.data::<i64>()[0]);
+    let mut cond = given(Op::LOOP_IN_COND).map_or(true, |c| c.data::<bool>()[0]);
+
+    let num_carried = op.body.inputs.len() - 2;
+    let num_scans = op.body.outputs.len() - 1 - num_carried;
+    let (v_initial, outer_values) = inputs[Op::LOOP_IN_V_INITIAL..].split_at(num_carried);
+    let mut carried = v_initial.iter().map(|&v| v.clone()).collect::<Vec<_>>();
+    let mut scans = vec![vec![]; num_scans];
+
+    body.with_outer_values(outer_values, || {
+        let mut i = 0;
+        while i < max_trip_count && cond {
+            let mut body_inputs = vec![
+                Tensor::new(vec![].into(), vec![i]),
+                Tensor::new(vec![].into(), vec![cond]),
+            ];
+            body_inputs.append(&mut carried);
+            let mut outputs = body.run(body_inputs)?.into_iter();
+            cond = outputs.next().unwrap().data::<bool>()[0];
+            carried = outputs.by_ref().take(num_carried).collect();
+            for (scan, output) in scans.iter_mut().zip(outputs) {
+                scan.push(output);
+            }
+            i += 1;
+        }
+        Ok::<_, SessionError>(())
+    })?;
+
+    let mut outputs = carried;
+    for (scan, &id) in scans.into_iter().zip(&op.body.outputs[1 + num_carried..]) {
+        outputs.push(stack(scan, || {
+            body.model().graph.values[id]
+                .shape
+                .as_ref()
+                .map_or(TensorElemType::F32, |s| s.elem_ty)
+        })?);
+    }
+    Ok(outputs)
+}
+
+pub fn compute_if(
+    op: &If,
+    branches: &[InterpreterSession],
+    inputs: &[&Tensor],
+) -> Result<Vec<Tensor>, SessionError> {
+    let cond = inputs[Op::IF_IN_COND].data::<bool>()[0];
+    let (then_values, else_values) =
+        inputs[Op::IF_IN_COND + 1..].split_at(op.then_branch.outer_inputs.len());
+    let (branch, outer_values) = if cond {
+        (&branches[0], then_values)
+    } else {
+        (&branches[1], else_values)
+    };
+    branch.with_outer_values(outer_values, || branch.run(vec![]))
+}
+
+/// Stacks `tensors` of the same shape along a new first axis.
+/// `elem_ty` gives the element type of an empty result.
+fn stack(
+    tensors: Vec<Tensor>,
+    elem_ty: impl FnOnce() -> TensorElemType,
+) -> Result<Tensor, SessionError> {
+    let Some(first) = tensors.first() else {
+        return Ok(Tensor::empty_of_type(elem_ty(), vec![0].into()));
+    };
+    let (dims, elem_ty) = (first.dims().clone(), first.elem_ty());
+    let mut data = Vec::with_capacity(first.data_as_bytes().len() * tensors.len());
+    for t in &tensors {
+        if t.dims() != &dims || t.elem_ty() != elem_ty {
+            return Err(SessionError::Message(
+                format!(
+                    "Loop: Scan outputs must have the same shape but got {:?} and {:?}",
+                    dims,
+                    t.dims()
+                )
+                .into(),
+            ));
+        }
+        data.extend_from_slice(t.data_as_bytes());
+    }
+    let mut stacked_dims = vec![tensors.len()];
+    stacked_dims.extend_from_slice(dims.as_slice());
+    Ok(Tensor::new_from_raw(stacked_dims.into(), elem_ty, data))
+}
@@ -33,31 +33,18 @@ pub fn compute(ctx: &mut Conv2dCtx) {
     let stride = &ctx.op.strides;
     let dilations = &ctx.op.dilations;
 
+    let rank = kernel.len();
     let batch_size = input.dims()[0];
     let input_c = input.dims()[1];
-    let input_h = input.dims()[2];
-    let input_w = input.dims()[3];
-    let input_hw = input_h * input_w;
-    let output_h = output.dims()[2];
-    let output_w = output.dims()[3];
-    let output_hw = output_h * output_w;
-    let _dilation = 1;
+    let input_size = input.dims()[2..].iter().product::<usize>();
+    let output_size = output.dims()[2..].iter().product::<usize>();
+    let kernel_size = kernel.iter().product::<usize>();
     let group = ctx.op.group as usize;
     let in_c_per_g = input_c / group;
     let out_c_per_g = output.dims()[1] / group;
-    let stride_h = stride[0];
-    let stride_w = stride[1];
-    let dilation_h = dilations[0];
-    let dilation_w = dilations[1];
-    let kernel_h = kernel[0];
-    let kernel_w = kernel[1];
-
-    assert_eq!(dilations.len(), 2);
-    assert!(padding.len() == 4);
-    let pad_t = padding[0];
-    let pad_l = padding[1];
-    let _pad_b = padding[2];
-    let _pad_r = padding[3];
+
+    assert_eq!(dilations.len(), rank);
+    assert_eq!(padding.len(), rank * 2);
 
     if let Some(bias) = ctx.inputs.get(Op::CONV2D_BIAS) {
         let mut output_ptr = output.data_mut::<f32>().as_mut_ptr();
@@ -66,7 +53,7 @@ pub fn compute(ctx: &mut Conv2dCtx) {
             let mut bias_ptr = bias_ptr_;
             for _ in 0..group {
                 for _ in 0..out_c_per_g {
-                    for _ in 0..output_hw {
+                    for _ in 0..output_size {
                         unsafe { *output_ptr = *bias_ptr };
                         output_ptr = unsafe { output_ptr.add(1) };
                     }
@@ -78,74 +65,70 @@ pub fn compute(ctx: &mut Conv2dCtx) {
         output.data_mut::<f32>().fill(0.);
     }
 
-    let mut col = Tensor::uninit::<f32>(
-        vec![
-            batch_size, input_c, kernel[0], kernel[1], output_h, output_w,
-        ]
-        .into(),
-    );
-
-    assert!(input.dims().len() == 4);
-
-    if pad_t == 0
-        && pad_l == 0
-        && stride_h == 1
-        && stride_w == 1
-        && dilation_h == 1
-        && dilation_w == 1
-    {
-        let mut col_ptr = col.data_mut::<f32>().as_mut_ptr();
-        let mut input_ptr = input.data::<f32>().as_ptr();
+    let mut col = Tensor::uninit::<f32>(vec![batch_size, input_c, kernel_size, output_size].into());
 
-        let mut outer = batch_size * input_c;
-        let inner = kernel_h * kernel_w;
+    if kernel_size == 1 && padding.iter().all(|&p| p == 0) && stride.iter().all(|&s| s == 1) {
         // Simple case
-        while outer > 0 {
-            let mut inner = inner;
-            while inner > 0 {
-                unsafe { std::ptr::copy_nonoverlapping(input_ptr, col_ptr, input_hw) }
-                col_ptr = unsafe { col_ptr.add(output_hw) };
-                inner -= 1
-            }
-            input_ptr = unsafe { input_ptr.add(input_hw) };
-            outer -= 1
-        }
-    } else if dilation_h == 1 && dilation_w == 1 {
+        col.data_mut::<f32>().copy_from_slice(input.data::<f32>());
+    } else if rank == 2 {
+        let [input_h, input_w] = input.dims()[2..].try_into().unwrap();
+        let [output_h, output_w] = output.dims()[2..].try_into().unwrap();
+        let [kernel_h, kernel_w] = kernel.to_fixed_dims::<2>();
+        let [stride_h, stride_w] = stride.to_fixed_dims::<2>();
+        let [dilation_h, dilation_w] = dilations.to_fixed_dims::<2>();
+        let pad_t = padding[0];
+        let pad_l = padding[1];
         let col = col.data_mut::<f32>();
         let input = input.data::<f32>();
         ctx.tctx.scope(|scope| {
-            col.chunks_mut(output_hw * kernel_h * kernel_w)
-                .zip(input.chunks(input_hw))
+            col.chunks_mut(output_size * kernel_size)
+                .zip(input.chunks(input_size))
                 .for_each(|(col, input)| {
                     scope.spawn(move || {
-                        im2col(
-                            input_h, input_w, output_h, output_w, stride_h, stride_w, pad_t, pad_l,
-                            kernel_h, kernel_w, input, col,
-                        )
+                        if dilation_h == 1 && dilation_w == 1 {
+                            im2col(
+                                input_h, input_w, output_h, output_w, stride_h, stride_w, pad_t,
+                                pad_l, kernel_h, kernel_w, input, col,
+                            )
+                        } else {
+                            im2col_with_dilation(
+                                input_h, input_w, output_h, output_w, stride_h, stride_w,
+                                dilation_h, dilation_w, pad_t, pad_l, kernel_h, kernel_w, input,
+                                col,
+                            )
+                        }
                     })
                 })
         })
     } else {
+        let input_dims = &input.dims()[2..];
+        let output_dims = &output.dims()[2..];
         let col = col.data_mut::<f32>();
         let input = input.data::<f32>();
         ctx.tctx.scope(|scope| {
-            col.chunks_mut(output_hw * kernel_h * kernel_w)
-                .zip(input.chunks(input_hw))
+            col.chunks_mut(output_size * kernel_size)
+                .zip(input.chunks(input_size))
                 .for_each(|(col, input)| {
                     scope.spawn(move || {
-                        im2col_with_dilation(
-                            input_h, input_w, output_h, output_w, stride_h, stride_w, dilation_h,
-                            dilation_w, pad_t, pad_l, kernel_h, kernel_w, input, col,
+                        im2col_nd(
+                            input_dims,
+                            output_dims,
+                            kernel,
+                            stride,
+                            dilations,
+                            &padding[..rank],
+                            input,
+                            col,
                         )
                     })
                 })
         })
     }
 
-    let col_stride = in_c_per_g * kernel[0] * kernel[1] * output_h * output_w;
-    let weight_stride = out_c_per_g * in_c_per_g * kernel[0] * kernel[1];
-    let output_stride = out_c_per_g * output_hw;
-    let k = in_c_per_g * kernel[0] * kernel[1];
+    let col_stride = in_c_per_g * kernel_size * output_size;
+    let weight_stride = out_c_per_g * in_c_per_g * kernel_size;
+    let output_stride = out_c_per_g * output_size;
+    let k = in_c_per_g * kernel_size;
     let col = col.data::<f32>();
     let weight = weight.data::<f32>();
     let output = output.data_mut::<f32>();
@@ -166,17 +149,17 @@ pub fn compute(ctx: &mut Conv2dCtx) {
                     matrixmultiply::sgemm(
                         out_c_per_g,
                         k,
-                        output_hw,
+                        output_size,
                         1.0,
                         weight_ptr,
                         k as isize,
                         1,
                         col_ptr,
-                        output_hw as isize,
+                        output_size as isize,
                         1,
                         1.0,
                         output_ptr,
-                        output_hw as isize,
+                        output_size as isize,
                         1,
                     );
                     #[cfg(feature = "cblas")]
@@ -186,16 +169,16 @@ pub fn compute(ctx: &mut Conv2dCtx) {
                             cblas_sys::CblasNoTrans,
                             cblas_sys::CblasNoTrans,
                             out_c_per_g as i32,
-                            output_hw as i32,
+                            output_size as i32,
                             k as i32,
                             1.0f32,
                             weight_ptr,
                             k as i32,
                             col_ptr as *const _,
-                            output_hw as i32,
+                            output_size as i32,
                             1.0f32,
                             output_ptr,
-                            output_hw as i32,
+                            output_size as i32,
                         );
                     }
                 }
@@ -207,9 +190,19 @@ pub fn compute(ctx: &mut Conv2dCtx) {
             }
         }
     });
+
+    use altius_core::op::{FusedActivation, HardSigmoid};
+    match ctx.op.activation {
+        Some(FusedActivation::Relu) => output.iter_mut().for_each(|o| *o = o.max(0.0)),
+        Some(FusedActivation::HardSigmoid(HardSigmoid { alpha, beta })) => output
+            .iter_mut()
+            .for_each(|o| *o = (*o * alpha + beta).clamp(0.0, 1.0)),
+        None => {}
+    }
 }
 
 #[inline(never)]
+#[allow(clippy::needless_range_loop)]
 fn im2col(
     input_h: usize,
     input_w: usize,
@@ -229,7 +222,7 @@ fn im2col(
     for fy in 0..kernel_h {
         for fx in 0..kernel_w {
             for oh in 0..output_h {
-                let col = &mut col[oh * output_h..];
+                let col = &mut col[oh * output_w..];
                 let ih = fy + oh * stride_h;
 
                 if pad_t > ih || ih >= input_h + pad_t {
@@ -291,7 +284,7 @@ fn im2col_with_dilation(
     for fy in 0..kernel_h {
         for fx in 0..kernel_w {
             for oh in 0..output_h {
-                let col = &mut col[oh * output_h..];
+                let col = &mut col[oh * output_w..];
                 let ih = fy * dilation_h + oh * stride_h;
 
                 if pad_t > ih || ih >= input_h + pad_t {
@@ -329,6 +322,51 @@ fn im2col_with_dilation(
     }
 }
 
+/// im2col over any number of spatial axes. `pads` are the beginning pads of the axes.
+fn im2col_nd(
+    input_dims: &[usize],
+    output_dims: &[usize],
+    kernel: &[usize],
+    strides: &[usize],
+    dilations: &[usize],
+    pads: &[usize],
+    input: &[f32],
+    col: &mut [f32],
+) {
+    let rank = input_dims.len();
+    let output_size = output_dims.iter().product::<usize>();
+    let mut k_coord = vec![0; rank];
+    for col in col.chunks_mut(output_size) {
+        let mut o_coord = vec![0; rank];
+        for c in col.iter_mut() {
+            let mut offset = Some(0);
+            for i in 0..rank {
+                let x = (o_coord[i] * strides[i] + k_coord[i] * dilations[i]).checked_sub(pads[i]);
+                offset = match (offset, x) {
+                    (Some(offset), Some(x)) if x < input_dims[i] => {
+                        Some(offset * input_dims[i] + x)
+                    }
+                    _ => None,
+                };
+            }
+            *c = offset.map_or(0., |i| input[i]);
+            next_coord(&mut o_coord, output_dims);
+        }
+        next_coord(&mut k_coord, kernel);
+    }
+}
+
+/// Advances `coord` to the next one within `dims` in row-major order.
+pub(crate) fn next_coord(coord: &mut [usize], dims: &[usize]) {
+    for i in (0..coord.len()).rev() {
+        coord[i] += 1;
+        if coord[i] < dims[i] {
+            return;
+        }
+        coord[i] = 0;
+    }
+}
+
 #[cfg(feature = "cuda")]
 pub fn compute(ctx: &mut Conv2dCtx) {
     let conv = ctx.op;
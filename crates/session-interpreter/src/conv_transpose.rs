@@ -0,0 +1,126 @@
+//! A kernel for ConvTranspose over any number of spatial axes, computed as a GEMM of the
+//! transposed weights and the input followed by col2im.
+
+use altius_core::{
+    op::{ConvTranspose, Op},
+    tensor::Tensor,
+};
+use altius_session::SessionError;
+
+use super::{conv2d::next_coord, gemm::sgemm};
+
+pub fn compute(
+    op: &ConvTranspose,
+    inputs: &[&Tensor],
+    outputs: &mut [Tensor],
+) -> Result<(), SessionError> {
+    let input = inputs[Op::CONVTRANSPOSE_IN];
+    let weight = inputs[Op::CONVTRANSPOSE_WEIGHT];
+    let output = &mut outputs[Op::CONVTRANSPOSE_OUT];
+    if !input.elem_ty().is_f32() || !weight.elem_ty().is_f32() {
+        return Err(SessionError::Message(
+            format!(
+                "ConvTranspose: Unsupported types {:?} and {:?}",
+                input.elem_ty(),
+                weight.elem_ty()
+            )
+            .into(),
+        ));
+    }
+
+    let batch_size = input.dims()[0];
+    let input_c = input.dims()[1];
+    let output_c = output.dims()[1];
+    let group = op.group as usize;
+    let in_c_per_g = input_c / group;
+    let out_c_per_g = output_c / group;
+    let input_size = input.dims()[2..].iter().product::<usize>();
+    let output_dims = output.dims()[2..].to_vec();
+    let output_size = output_dims.iter().product::<usize>();
+    let kernel_size = op.kernel_shape.iter().product::<usize>();
+    let rows = out_c_per_g * kernel_size;
+
+    let output_data = output.data_mut::<f32>();
+    match inputs.get(Op::CONVTRANSPOSE_BIAS) {
+        Some(bias) => {
+            let bias = bias.data::<f32>();
+            for (channel, &b) in output_data.chunks_mut(output_size).zip(bias.iter().cycle()) {
+                channel.fill(b);
+            }
+        }
+        None => output_data.fill(0.),
+    }
+
+    // The weights of each group are transposed from [in_c_per_g, rows] into [rows, in_c_per_g].
+    let weight = weight.data::<f32>();
+    let mut weight_t = vec![0.; weight.len()];
+    for (w_t, w) in weight_t
+        .chunks_mut(rows * in_c_per_g)
+        .zip(weight.chunks(rows * in_c_per_g))
+    {
+        for (ic, w) in w.chunks(rows).enumerate() {
+            for (r, &w) in w.iter().enumerate() {
+                w_t[r * in_c_per_g + ic] = w;
+            }
+        }
+    }
+
+    let input_dims = &input.dims()[2..];
+    let input = input.data::<f32>();
+    let mut col = vec![0.; rows * input_size];
+    for (i, (input, output)) in input
+        .chunks(in_c_per_g * input_size)
+        .zip(output_data.chunks_mut(out_c_per_g * output_size))
+        .take(batch_size * group)
+        .enumerate()
+    {
+        let g = i % group;
+        let w_t = &weight_t[g * rows * in_c_per_g..(g + 1) * rows * in_c_per_g];
+        sgemm(
+            rows, in_c_per_g, input_size, 1., w_t, in_c_per_g, input, input_size, 0., &mut col,
+            input_size,
+        );
+        col2im(op, &col, input_dims, &output_dims, output);
+    }
+
+    Ok(())
+}
+
+/// Adds each row of `col`, which holds a kernel element of an output channel for each
+/// input element, to the output elements it contributes to.
+fn col2im(
+    op: &ConvTranspose,
+    col: &[f32],
+    input_dims: &[usize],
+    output_dims: &[usize],
+    output: &mut [f32],
+) {
+    let rank = input_dims.len();
+    let input_size = input_dims.iter().product::<usize>();
+    let output_size = output_dims.iter().product::<usize>();
+    let kernel_size = op.kernel_shape.iter().product::<usize>();
+
+    let mut k_coord = vec![0; rank];
+    for (row, col) in col.chunks(input_size).enumerate() {
+        let output = &mut output[row / kernel_size * output_size..][..output_size];
+        let mut x_coord = vec![0; rank];
+        for &v in col {
+            let mut offset = Some(0);
+            for i in 0..rank {
+                let o = (x_coord[i] * op.strides[i] + k_coord[i] * op.dilations[i])
+                    .checked_sub(op.padding[i]);
+                offset = match (offset, o) {
+                    (Some(offset), Some(o)) if o < output_dims[i] => {
+                        Some(offset * output_dims[i] + o)
+                    }
+                    _ => None,
+                };
+            }
+            if let Some(offset) = offset {
+                output[offset] += v;
+            }
+            next_coord(&mut x_coord, input_dims);
+        }
+        next_coord(&mut k_coord, &op.kernel_shape);
+    }
+}
@@ -0,0 +1,372 @@
+//! Kernels of binary and ternary elementwise ops, whose inputs are broadcast to the output
+//! in the multidirectional (NumPy-style) way.
+//! <https://github.com/onnx/onnx/blob/main/docs/Broadcasting.md>
+
+use altius_core::tensor::{Tensor, TensorElemType, TensorElemTypeExt};
+use altius_session::SessionError;
+
+use super::{conv2d::next_coord, thread::ThreadCtx};
+
+/// Walks the output row by row, where a row is a run of elements along the innermost axis,
+/// together with the offsets of the elements of `N` inputs broadcast to it.
+struct Broadcast<const N: usize> {
+    /// The output dimensions without axes of size 1, and with contiguous axes coalesced.
+    dims: Vec<usize>,
+    /// The strides of each input along `dims`. Broadcast axes have the stride 0.
+    strides: Vec<[usize; N]>,
+}
+
+impl<const N: usize> Broadcast<N> {
+    fn new(name: &str, inputs: [&Tensor; N], output: &Tensor) -> Result<Self, SessionError> {
+        let output_dims = output.dims();
+        let mut input_strides = Vec::with_capacity(N);
+        for input in inputs {
+            match input.strides_for_broadcasting(output_dims) {
+                Some(strides) => input_strides.push(strides),
+                None => {
+                    return Err(SessionError::Message(
+                        format!(
+                            "{name}: Cannot broadcast {:?} to {output_dims:?}",
+                            input.dims()
+                        )
+                        .into(),
+                    ))
+                }
+            }
+        }
+
+        let mut dims: Vec<usize> = vec![];
+        let mut strides: Vec<[usize; N]> = vec![];
+        for (axis, &dim) in output_dims.iter().enumerate() {
+            if dim == 1 {
+                continue;
+            }
+            let stride: [usize; N] = std::array::from_fn(|i| input_strides[i][axis]);
+            match (dims.last_mut(), strides.last_mut()) {
+                // The axis is merged into the previous one if every input is contiguous over them.
+                (Some(last_dim), Some(last_stride))
+                    if last_stride.iter().zip(&stride).all(|(&l, &s)| l == s * dim) =>
+                {
+                    *last_dim *= dim;
+                    *last_stride = stride;
+                }
+                _ => {
+                    dims.push(dim);
+                    strides.push(stride);
+                }
+            }
+        }
+        if dims.is_empty() {
+            dims.push(1);
+            strides.push([0; N]);
+        }
+
+        Ok(Self { dims, strides })
+    }
+
+    /// Calls `f` with the input offsets of each output row, along with the row.
+    fn for_each_row<T: TensorElemTypeExt>(
+        &self,
+        output: &mut [T],
+        mut f: impl FnMut([usize; N], &mut [T]),
+    ) {
+        let (outer_dims, [row_len]) = self.dims.split_at(self.dims.len() - 1) else {
+            unreachable!()
+        };
+        let outer_strides = &self.strides[..outer_dims.len()];
+        let mut coord = vec![0; outer_dims.len()];
+        for row in output.chunks_mut(*row_len) {
+            let mut offsets = [0; N];
+            for (&c, stride) in coord.iter().zip(outer_strides) {
+                for (offset, &stride) in offsets.iter_mut().zip(stride) {
+                    *offset += c * stride;
+                }
+            }
+            f(offsets, row);
+            next_coord(&mut coord, outer_dims);
+        }
+    }
+
+    /// The strides of the inputs along rows.
+    fn row_strides(&self) -> [usize; N] {
+        self.strides[self.strides.len() - 1]
+    }
+}
+
+/// The number of elements computed by a task when the inputs need no broadcasting.
+const CHUNK: usize = 100000;
+
+/// Computes `f` of the elements of the two inputs broadcast to each other.
+pub fn binary<A, B, O>(
+    tctx: &ThreadCtx,
+    name: &str,
+    inputs: &[&Tensor],
+    output: &mut Tensor,
+    f: impl Fn(A, B) -> O + Sync,
+) -> Result<(), SessionError>
+where
+    A: TensorElemTypeExt + Sync,
+    B: TensorElemTypeExt + Sync,
+    O: TensorElemTypeExt + Send,
+{
+    let (input_a, input_b) = (inputs[0], inputs[1]);
+
+    if input_a.dims() == output.dims() && input_b.dims() == output.dims() {
+        let f = &f;
+        tctx.scope(|scope| {
+            input_a
+                .data::<A>()
+                .chunks(CHUNK)
+                .zip(input_b.data::<B>().chunks(CHUNK))
+                .zip(output.data_mut::<O>().chunks_mut(CHUNK))
+                .for_each(|((a, b), o)| {
+                    scope.spawn(move || {
+                        for ((&a, &b), o) in a.iter().zip(b).zip(o) {
+                            // Auto-vectorized by LLVM
+                            *o = f(a, b);
+                        }
+                    });
+                });
+        });
+        return Ok(());
+    }
+
+    let broadcast = Broadcast::new(name, [input_a, input_b], output)?;
+    let a = input_a.data::<A>();
+    let b = input_b.data::<B>();
+    let [a_stride, b_stride] = broadcast.row_strides();
+    broadcast.for_each_row(output.data_mut::<O>(), |[a_offset, b_offset], o| {
+        let len = o.len();
+        match (a_stride, b_stride) {
+            (1, 1) => {
+                let (a, b) = (&a[a_offset..][..len], &b[b_offset..][..len]);
+                for ((&a, &b), o) in a.iter().zip(b).zip(o) {
+                    *o = f(a, b);
+                }
+            }
+            (1, 0) => {
+                let b = b[b_offset];
+                for (&a, o) in a[a_offset..][..len].iter().zip(o) {
+                    *o = f(a, b);
+                }
+            }
+            (0, 1) => {
+                let a = a[a_offset];
+                for (&b, o) in b[b_offset..][..len].iter().zip(o) {
+                    *o = f(a, b);
+                }
+            }
+            _ => {
+                for (i, o) in o.iter_mut().enumerate() {
+                    *o = f(a[a_offset + i * a_stride], b[b_offset + i * b_stride]);
+                }
+            }
+        }
+    });
+
+    Ok(())
+}
+
+/// Computes `f` of the elements of the three inputs broadcast to each other.
+pub fn ternary<A, B, C, O>(
+    name: &str,
+    inputs: &[&Tensor],
+    output: &mut Tensor,
+    f: impl Fn(A, B, C) -> O,
+) -> Result<(), SessionError>
+where
+    A: TensorElemTypeExt,
+    B: TensorElemTypeExt,
+    C: TensorElemTypeExt,
+    O: TensorElemTypeExt,
+{
+    let broadcast = Broadcast::new(name, [inputs[0], inputs[1], inputs[2]], output)?;
+    let a = inputs[0].data::<A>();
+    let b = inputs[1].data::<B>();
+    let c = inputs[2].data::<C>();
+    let [a_stride, b_stride, c_stride] = broadcast.row_strides();
+    broadcast.for_each_row(
+        output.data_mut::<O>(),
+        |[a_offset, b_offset, c_offset], o| {
+            for (i, o) in o.iter_mut().enumerate() {
+                *o = f(
+                    a[a_offset + i * a_stride],
+                    b[b_offset + i * b_stride],
+                    c[c_offset + i * c_stride],
+                );
+            }
+        },
+    );
+
+    Ok(())
+}
+
+/// Expands `$body` with the type alias `$t` bound to the Rust type of `$ty`,
+/// for each of the listed element types.
+macro_rules! dispatch {
+    ($name:expr, $ty:expr, |$t:ident| $body:expr, [$($elem:ident => $rust:ty),*]) => {
+        match $ty {
+            $(TensorElemType::$elem => {
+                type $t = $rust;
+                $body
+            })*
+            ty => Err(SessionError::Message(
+                format!("{}: Unsupported type {ty:?}", $name).into(),
+            )),
+        }
+    };
+}
+
+/// `dispatch!` over the numeric types.
+macro_rules! numeric {
+    ($name:expr, $ty:expr, |$t:ident| $body:expr) => {
+        dispatch!($name, $ty, |$t| $body, [F32 => f32, I64 => i64, I32 => i32, I8 => i8, U8 => u8])
+    };
+}
+
+macro_rules! op_arithmetic {
+    ($name:ident, $op_name:literal, $op:tt) => {
+        pub fn $name(
+            tctx: &ThreadCtx,
+            inputs: &[&Tensor],
+            outputs: &mut [Tensor],
+        ) -> Result<(), SessionError> {
+            numeric!($op_name, inputs[0].elem_ty(), |T| {
+                binary(tctx, $op_name, inputs, &mut outputs[0], |a: T, b: T| a $op b)
+            })
+        }
+    };
+}
+
+op_arithmetic!(compute_add, "Add", +);
+op_arithmetic!(compute_sub, "Sub", -);
+op_arithmetic!(compute_mul, "Mul", *);
+op_arithmetic!(compute_div, "Div", /);
+
+macro_rules! op_comparison {
+    ($name:ident, $op_name:literal, $op:tt) => {
+        pub fn $name(
+            tctx: &ThreadCtx,
+            inputs: &[&Tensor],
+            outputs: &mut [Tensor],
+        ) -> Result<(), SessionError> {
+            dispatch!($op_name, inputs[0].elem_ty(), |T| {
+                binary(tctx, $op_name, inputs, &mut outputs[0], |a: T, b: T| a $op b)
+            }, [F32 => f32, I64 => i64, I32 => i32, I8 => i8, U8 => u8, Bool => bool])
+        }
+    };
+}
+
+op_comparison!(compute_equal, "Equal", ==);
+op_comparison!(compute_less, "Less", <);
+op_comparison!(compute_less_or_equal, "LessOrEqual", <=);
+op_comparison!(compute_greater, "Greater", >);
+op_comparison!(compute_greater_or_equal, "GreaterOrEqual", >=);
+
+macro_rules! op_logical {
+    ($name:ident, $op_name:literal, $op:tt) => {
+        pub fn $name(
+            tctx: &ThreadCtx,
+            inputs: &[&Tensor],
+            outputs: &mut [Tensor],
+        ) -> Result<(), SessionError> {
+            dispatch!($op_name, inputs[0].elem_ty(), |T| {
+                binary(tctx, $op_name, inputs, &mut outputs[0], |a: T, b: T| a $op b)
+            }, [Bool => bool])
+        }
+    };
+}
+
+op_logical!(compute_and, "And", &);
+op_logical!(compute_or, "Or", |);
+op_logical!(compute_xor, "Xor", ^);
+
+pub fn compute_not(inputs: &[&Tensor], outputs: &mut [Tensor]) -> Result<(), SessionError> {
+    let input = inputs[0];
+    if !input.elem_ty().is_bool() {
+        return Err(SessionError::Message(
+            format!("Not: Unsupported type {:?}", input.elem_ty()).into(),
+        ));
+    }
+    for (&i, o) in input
+        .data::<bool>()
+        .iter()
+        .zip(outputs[0].data_mut::<bool>())
+    {
+        *o = !i;
+    }
+    Ok(())
+}
+
+pub fn compute_pow(
+    tctx: &ThreadCtx,
+    inputs: &[&Tensor],
+    outputs: &mut [Tensor],
+) -> Result<(), SessionError> {
+    let output = &mut outputs[0];
+    match (inputs[0].elem_ty(), inputs[1].elem_ty()) {
+        (TensorElemType::F32, TensorElemType::F32) if inputs[1].dims().is_scalar() => {
+            // Squaring is common, e.g. in layer normalization.
+            match inputs[1].data::<f32>()[0] {
+                2. => binary(tctx, "Pow", inputs, output, |a: f32, _: f32| a * a),
+                _ => binary(tctx, "Pow", inputs, output, |a: f32, b: f32| a.powf(b)),
+            }
+        }
+        (TensorElemType::F32, TensorElemType::F32) => {
+            binary(tctx, "Pow", inputs, output, |a: f32, b: f32| a.powf(b))
+        }
+        (TensorElemType::F32, ty) => dispatch!("Pow", ty, |T| {
+            binary(tctx, "Pow", inputs, output, |a: f32, b: T| a.powf(b as f32))
+        }, [I64 => i64, I32 => i32, I8 => i8, U8 => u8]),
+        (TensorElemType::I64, ty) => numeric!("Pow", ty, |T| {
+            binary(tctx, "Pow", inputs, output, |a: i64, b: T| {
+                (a as f64).powf(b as f64) as i64
+            })
+        }),
+        (TensorElemType::I32, ty) => numeric!("Pow", ty, |T| {
+            binary(tctx, "Pow", inputs, output, |a: i32, b: T| {
+                (a as f64).powf(b as f64) as i32
+            })
+        }),
+        (ty, _) => Err(SessionError::Message(
+            format!("Pow: Unsupported type {ty:?}").into(),
+        )),
+    }
+}
+
+pub fn compute_prelu(
+    tctx: &ThreadCtx,
+    inputs: &[&Tensor],
+    outputs: &mut [Tensor],
+) -> Result<(), SessionError> {
+    dispatch!("PRelu", inputs[0].elem_ty(), |T| {
+        binary(tctx, "PRelu", inputs, &mut outputs[0], |x: T, slope: T| {
+            if x < T::zero() {
+                x * slope
+            } else {
+                x
+            }
+        })
+    }, [F32 => f32, I64 => i64, I32 => i32])
+}
+
+pub fn compute_where(inputs: &[&Tensor], outputs: &mut [Tensor]) -> Result<(), SessionError> {
+    if !inputs[0].elem_ty().is_bool() {
+        return Err(SessionError::Message(
+            format!(
+                "Where: Unsupported condition type {:?}",
+                inputs[0].elem_ty()
+            )
+            .into(),
+        ));
+    }
+    dispatch!("Where", inputs[1].elem_ty(), |T| {
+        ternary("Where", inputs, &mut outputs[0], |c: bool, x: T, y: T| {
+            if c {
+                x
+            } else {
+                y
+            }
+        })
+    }, [F32 => f32, I64 => i64, I32 => i32, I8 => i8, U8 => u8, Bool => bool])
+}
@@ -1,7 +1,9 @@
 use std::{
+    f32::consts::{FRAC_2_PI, LN_2},
     mem::transmute,
     simd::{
-        num::SimdUint,
+        cmp::{SimdPartialEq, SimdPartialOrd},
+        num::{SimdInt, SimdUint},
         prelude::{Simd, SimdFloat, SimdOrd},
         StdFloat,
     },
@@ -351,3 +353,230 @@ pub fn fast_gelu(mut output: &mut [f32], mut input: &[f32]) {
         *out = o
     }
 }
+
+const SIMD_LEN: usize = 8;
+
+type Vector = Simd<f32, SIMD_LEN>;
+
+/// Applies `f` to every `SIMD_LEN` elements of `input`.
+/// The remainder is padded with zeros to be computed in the same way.
+#[inline(always)]
+fn map(output: &mut [f32], input: &[f32], f: impl Fn(Vector) -> Vector) {
+    assert_eq!(input.len(), output.len());
+
+    for (output, input) in output.chunks_mut(SIMD_LEN).zip(input.chunks(SIMD_LEN)) {
+        let len = input.len();
+        let vals = if len == SIMD_LEN {
+            Vector::from_slice(input)
+        } else {
+            let mut vals = [0.; SIMD_LEN];
+            vals[..len].copy_from_slice(input);
+            Vector::from_array(vals)
+        };
+        output.copy_from_slice(&f(vals).as_array()[..len]);
+    }
+}
+
+/// Computes `e^x` in the same way as `fast_sigmoid`.
+#[inline(always)]
+fn exp(vals: Vector) -> Vector {
+    const LOWER_RANGE: f32 = -103.9720840454f32;
+    const UPPER_RANGE: f32 = 88.7762626647950f32;
+    const ROUNDING_BIAS: f32 = 12582912.0f32;
+    const LOG2RECIPROCAL: f32 = 1.44269504088896341f32;
+    const LOG2HIGH: f32 = -6.93145752e-1f32;
+    const LOG2LOW: f32 = -1.42860677e-6f32;
+    const POLY_0: f32 = 0.0013780593872f32;
+    const POLY_1: f32 = 0.0083731245250f32;
+    const POLY_2: f32 = 0.0416695363820f32;
+    const POLY_3: f32 = 0.1666647195816f32;
+    const POLY_4: f32 = 0.4999998509884f32;
+    const POLY_56: f32 = 1.0000000000000f32;
+    const MINIMUM_EXPONENT: i32 = -1056964608i32;
+    const MAXIMUM_EXPONENT: i32 = 0x3F800000i32;
+
+    let vals = vals.simd_clamp(Simd::splat(LOWER_RANGE), Simd::splat(UPPER_RANGE));
+
+    let biased = vals.mul_add(Simd::splat(LOG2RECIPROCAL), Simd::splat(ROUNDING_BIAS));
+    let m = biased - Simd::splat(ROUNDING_BIAS);
+
+    let vals = m.mul_add(Simd::splat(LOG2HIGH), vals);
+    let vals = m.mul_add(Simd::splat(LOG2LOW), vals);
+
+    let overflow = biased.to_bits().cast::<i32>() << Simd::splat(23);
+    let normal = overflow.simd_min(Simd::splat(MAXIMUM_EXPONENT));
+    let normal = normal.simd_max(Simd::splat(MINIMUM_EXPONENT));
+    let overflow = overflow - normal;
+    let overflow = overflow + Simd::splat(MAXIMUM_EXPONENT);
+    let normal = normal + Simd::splat(MAXIMUM_EXPONENT);
+
+    let p = Simd::splat(POLY_0);
+    let p = p.mul_add(vals, Simd::splat(POLY_1));
+    let p = p.mul_add(vals, Simd::splat(POLY_2));
+    let p = p.mul_add(vals, Simd::splat(POLY_3));
+    let p = p.mul_add(vals, Simd::splat(POLY_4));
+    let p = p.mul_add(vals, Simd::splat(POLY_56));
+
+    let overflow = Vector::from_bits(overflow.cast::<u32>());
+    let vals = vals * overflow;
+    let p = p.mul_add(vals, overflow);
+    p * Vector::from_bits(normal.cast::<u32>())
+}
+
+/// Computes `ln(x)` by splitting `x` into `m * 2^k` where `m` is in `[sqrt(1/2), sqrt(2))`,
+/// and `ln(m)` into the series of `2 * atanh((m - 1) / (m + 1))`.
+#[inline(always)]
+fn ln(x: Vector) -> Vector {
+    const SQRT_HALF_BITS: u32 = 0x3F3504F3;
+    const MANTISSA_MASK: u32 = 0x007FFFFF;
+
+    let bits = x.to_bits() - Simd::splat(SQRT_HALF_BITS);
+    let k = (bits.cast::<i32>() >> Simd::splat(23)).cast::<f32>();
+    let m = Vector::from_bits((bits & Simd::splat(MANTISSA_MASK)) + Simd::splat(SQRT_HALF_BITS));
+
+    let f = m - Simd::splat(1.);
+    let s = f / (f + Simd::splat(2.));
+    let z = s * s;
+    let p = z.mul_add(Simd::splat(1. / 9.), Simd::splat(1. / 7.));
+    let p = p.mul_add(z, Simd::splat(1. / 5.));
+    let p = p.mul_add(z, Simd::splat(1. / 3.));
+    let p = p.mul_add(z, Simd::splat(1.));
+    let y = k.mul_add(Simd::splat(LN_2), Simd::splat(2.) * s * p);
+
+    // Zeros, negatives, subnormals, infinities and NaNs are left to the standard library.
+    let normal = x.simd_ge(Simd::splat(f32::MIN_POSITIVE)) & x.is_finite();
+    if normal.all() {
+        y
+    } else {
+        normal.select(y, Vector::from_array(x.to_array().map(f32::ln)))
+    }
+}
+
+/// Computes `sin(x)`, or `cos(x)` if `cos` is true, reducing `x` into `[-pi/4, pi/4]`
+/// by a multiple of `pi/2`.
+#[inline(always)]
+fn sin_cos(x: Vector, cos: bool) -> Vector {
+    // `pi/2` split into the nearest `f32` and the rest, for the reduction to be exact.
+    const PI_2_HIGH: f32 = 1.5707963705062866f32;
+    const PI_2_LOW: f32 = -4.371139000186243e-8f32;
+    // The reduction loses precision beyond.
+    const REDUCIBLE_RANGE: f32 = 65536f32;
+    const SIN_0: f32 = -1.9515295891e-4f32;
+    const SIN_1: f32 = 8.3321608736e-3f32;
+    const SIN_2: f32 = -1.6666654611e-1f32;
+    const COS_0: f32 = 2.443315711809948e-5f32;
+    const COS_1: f32 = -1.388731625493765e-3f32;
+    const COS_2: f32 = 4.166664568298827e-2f32;
+
+    let n = (x * Simd::splat(FRAC_2_PI)).round();
+    let r = n.mul_add(Simd::splat(-PI_2_HIGH), x);
+    let r = n.mul_add(Simd::splat(-PI_2_LOW), r);
+    let z = r * r;
+
+    let sin_r = z.mul_add(Simd::splat(SIN_0), Simd::splat(SIN_1));
+    let sin_r = sin_r.mul_add(z, Simd::splat(SIN_2));
+    let sin_r = (sin_r * z).mul_add(r, r);
+
+    let cos_r = z.mul_add(Simd::splat(COS_0), Simd::splat(COS_1));
+    let cos_r = cos_r.mul_add(z, Simd::splat(COS_2));
+    let cos_r = (cos_r * z).mul_add(z, z.mul_add(Simd::splat(-0.5), Simd::splat(1.)));
+
+    // The quadrant of `x`, given `cos(x) = sin(x + pi/2)`.
+    let q = n.cast::<i32>() + Simd::splat(cos as i32);
+    let y = (q & Simd::splat(1))
+        .simd_eq(Simd::splat(1))
+        .select(cos_r, sin_r);
+    let y = (q & Simd::splat(2)).simd_eq(Simd::splat(2)).select(-y, y);
+
+    let reducible = x.abs().simd_le(Simd::splat(REDUCIBLE_RANGE));
+    if reducible.all() {
+        y
+    } else {
+        let f = if cos { f32::cos } else { f32::sin };
+        reducible.select(y, Vector::from_array(x.to_array().map(f)))
+    }
+}
+
+pub fn fast_abs(output: &mut [f32], input: &[f32]) {
+    map(output, input, |x| x.abs())
+}
+
+pub fn fast_neg(output: &mut [f32], input: &[f32]) {
+    map(output, input, |x| -x)
+}
+
+pub fn fast_log(output: &mut [f32], input: &[f32]) {
+    map(output, input, ln)
+}
+
+pub fn fast_reciprocal(output: &mut [f32], input: &[f32]) {
+    map(output, input, |x| Simd::splat(1.) / x)
+}
+
+pub fn fast_floor(output: &mut [f32], input: &[f32]) {
+    map(output, input, |x| x.floor())
+}
+
+pub fn fast_ceil(output: &mut [f32], input: &[f32]) {
+    map(output, input, |x| x.ceil())
+}
+
+pub fn fast_sin(output: &mut [f32], input: &[f32]) {
+    map(output, input, |x| sin_cos(x, false))
+}
+
+pub fn fast_cos(output: &mut [f32], input: &[f32]) {
+    map(output, input, |x| sin_cos(x, true))
+}
+
+/// `ln(1 + e^x)`, computed as `max(x, 0) + ln(1 + e^-|x|)` not to overflow.
+pub fn fast_softplus(output: &mut [f32], input: &[f32]) {
+    map(output, input, |x| {
+        x.simd_max(Simd::splat(0.)) + ln(exp(-x.abs()) + Simd::splat(1.))
+    })
+}
+
+pub fn fast_softsign(output: &mut [f32], input: &[f32]) {
+    map(output, input, |x| x / (x.abs() + Simd::splat(1.)))
+}
+
+pub fn fast_elu(output: &mut [f32], input: &[f32], alpha: f32) {
+    map(output, input, |x| {
+        let neg = Simd::splat(alpha) * (exp(x) - Simd::splat(1.));
+        x.simd_lt(Simd::splat(0.)).select(neg, x)
+    })
+}
+
+pub fn fast_selu(output: &mut [f32], input: &[f32], alpha: f32, gamma: f32) {
+    map(output, input, |x| {
+        let neg = Simd::splat(alpha) * (exp(x) - Simd::splat(1.));
+        Simd::splat(gamma) * x.simd_le(Simd::splat(0.)).select(neg, x)
+    })
+}
+
+/// `x * tanh(ln(1 + e^x))`, computed as `x * n / (n + 2)` where `n = e^x * (e^x + 2)`.
+pub fn fast_mish(output: &mut [f32], input: &[f32]) {
+    // `n / (n + 2)` is 1 in `f32` beyond.
+    const UPPER_RANGE: f32 = 20f32;
+
+    map(output, input, |x| {
+        let e = exp(x.simd_min(Simd::splat(UPPER_RANGE)));
+        let n = e * (e + Simd::splat(2.));
+        x * n / (n + Simd::splat(2.))
+    })
+}
+
+pub fn fast_hard_swish(output: &mut [f32], input: &[f32]) {
+    map(output, input, |x| {
+        x * x
+            .mul_add(Simd::splat(1. / 6.), Simd::splat(0.5))
+            .simd_clamp(Simd::splat(0.), Simd::splat(1.))
+    })
+}
+
+pub fn fast_sign(output: &mut [f32], input: &[f32]) {
+    map(output, input, |x| {
+        let sign = x.simd_lt(Simd::splat(0.)).select(Simd::splat(-1.), x);
+        x.simd_gt(Simd::splat(0.)).select(Simd::splat(1.), sign)
+    })
+}
@@ -0,0 +1,510 @@
+//! Kernels of indexing ops. Ops only moving elements copy them as bytes, so they work on
+//! tensors of any type.
+//! Axes are normalized in shape inference, so they are non-negative here.
+
+use std::cmp::Ordering;
+
+use altius_core::{
+    op::{CumSum, Gather, GatherElements, GatherND, OneHot, Op, ScatterReduction, TopK},
+    tensor::{Tensor, TensorElemType, TensorElemTypeExt},
+};
+use altius_session::SessionError;
+
+use super::conv2d::next_coord;
+
+pub fn compute_gather(
+    gather: &Gather,
+    inputs: &[&Tensor],
+    outputs: &mut [Tensor],
+) -> Result<(), SessionError> {
+    let data = inputs[Op::GATHER_IN_DATA];
+    let indices = to_i64(inputs[Op::GATHER_IN_INDICES], "Gather")?;
+    let output = &mut outputs[Op::GATHER_OUT];
+
+    let axis = gather.axis as usize;
+    let axis_len = data.dims()[axis];
+    let outer = data.dims()[..axis].iter().product::<usize>();
+    let inner = data.dims()[axis + 1..].iter().product::<usize>() * data.elem_ty().size();
+    let bytes = data.data_as_bytes();
+    let mut out = Vec::with_capacity(output.dims().total_elems() * data.elem_ty().size());
+    for o in 0..outer {
+        for &i in &indices {
+            let offset = (o * axis_len + index(i, axis_len, "Gather")?) * inner;
+            out.extend_from_slice(&bytes[offset..offset + inner]);
+        }
+    }
+    *output = Tensor::new_from_raw(output.dims().clone(), data.elem_ty(), out);
+
+    Ok(())
+}
+
+pub fn compute_gather_elements(
+    gather: &GatherElements,
+    inputs: &[&Tensor],
+    outputs: &mut [Tensor],
+) -> Result<(), SessionError> {
+    let data = inputs[Op::GATHERELEMENTS_IN_DATA];
+    let indices = inputs[Op::GATHERELEMENTS_IN_INDICES];
+    let output = &mut outputs[Op::GATHERELEMENTS_OUT];
+
+    let offsets = element_offsets(
+        data,
+        indices.dims(),
+        &to_i64(indices, "GatherElements")?,
+        gather.axis as usize,
+        "GatherElements",
+    )?;
+    let size = data.elem_ty().size();
+    let bytes = data.data_as_bytes();
+    let mut out = Vec::with_capacity(offsets.len() * size);
+    for offset in offsets {
+        out.extend_from_slice(&bytes[offset * size..(offset + 1) * size]);
+    }
+    *output = Tensor::new_from_raw(output.dims().clone(), data.elem_ty(), out);
+
+    Ok(())
+}
+
+pub fn compute_gather_nd(
+    gather: &GatherND,
+    inputs: &[&Tensor],
+    outputs: &mut [Tensor],
+) -> Result<(), SessionError> {
+    let data = inputs[Op::GATHERND_IN_DATA];
+    let indices = inputs[Op::GATHERND_IN_INDICES];
+    let output = &mut outputs[Op::GATHERND_OUT];
+
+    let (offsets, slice_len) =
+        slice_offsets(data, indices, gather.batch_dims as usize, "GatherND")?;
+    let size = data.elem_ty().size();
+    let slice_len = slice_len * size;
+    let bytes = data.data_as_bytes();
+    let mut out = Vec::with_capacity(offsets.len() * slice_len);
+    for offset in offsets {
+        out.extend_from_slice(&bytes[offset * size..][..slice_len]);
+    }
+    *output = Tensor::new_from_raw(output.dims().clone(), data.elem_ty(), out);
+
+    Ok(())
+}
+
+pub fn compute_scatter(
+    op: &Op,
+    inputs: &[&Tensor],
+    outputs: &mut [Tensor],
+) -> Result<(), SessionError> {
+    let data = inputs[Op::SCATTER_IN_DATA];
+    let indices = inputs[Op::SCATTER_IN_INDICES];
+    let updates = inputs[Op::SCATTER_IN_UPDATES];
+    let output = &mut outputs[Op::SCATTER_OUT];
+    let name = op.name();
+
+    let (offsets, slice_len, reduction) = match op {
+        Op::ScatterElements(scatter) => {
+            if updates.dims() != indices.dims() {
+                return Err(SessionError::Message(
+                    format!("{name}: Shapes of indices and updates differ").into(),
+                ));
+            }
+            let indices = to_i64(indices, name)?;
+            let axis = scatter.axis as usize;
+            let offsets = element_offsets(data, updates.dims(), &indices, axis, name)?;
+            (offsets, 1, scatter.reduction)
+        }
+        Op::ScatterND(scatter) => {
+            let (offsets, slice_len) = slice_offsets(data, indices, 0, name)?;
+            if offsets.len() * slice_len != updates.dims().total_elems() {
+                return Err(SessionError::Message(
+                    format!("{name}: Invalid shape of updates {:?}", updates.dims()).into(),
+                ));
+            }
+            (offsets, slice_len, scatter.reduction)
+        }
+        op => {
+            return Err(SessionError::Message(
+                format!("{} is not a scatter op", op.name()).into(),
+            ))
+        }
+    };
+
+    macro_rules! scatter {
+        ($ty:ty, $add:expr, $mul:expr, $max:expr, $min:expr) => {{
+            let f: fn($ty, $ty) -> $ty = match reduction {
+                ScatterReduction::None => |_, u| u,
+                ScatterReduction::Add => $add,
+                ScatterReduction::Mul => $mul,
+                ScatterReduction::Max => $max,
+                ScatterReduction::Min => $min,
+            };
+            let mut out = data.data::<$ty>().to_vec();
+            for (&offset, updates) in offsets.iter().zip(updates.data::<$ty>().chunks(slice_len)) {
+                for (o, &u) in out[offset..offset + slice_len].iter_mut().zip(updates) {
+                    *o = f(*o, u);
+                }
+            }
+            *output = Tensor::new(output.dims().clone(), out);
+        }};
+    }
+
+    match data.elem_ty() {
+        _ if reduction == ScatterReduction::None => {
+            let size = data.elem_ty().size();
+            let mut out = data.data_as_bytes().to_vec();
+            for (&offset, updates) in offsets
+                .iter()
+                .zip(updates.data_as_bytes().chunks(slice_len * size))
+            {
+                out[offset * size..(offset + slice_len) * size].copy_from_slice(updates);
+            }
+            *output = Tensor::new_from_raw(output.dims().clone(), data.elem_ty(), out);
+        }
+        TensorElemType::F32 => scatter!(f32, |a, u| a + u, |a, u| a * u, f32::max, f32::min),
+        TensorElemType::I64 => scatter!(
+            i64,
+            i64::wrapping_add,
+            i64::wrapping_mul,
+            i64::max,
+            i64::min
+        ),
+        TensorElemType::I32 => scatter!(
+            i32,
+            i32::wrapping_add,
+            i32::wrapping_mul,
+            i32::max,
+            i32::min
+        ),
+        TensorElemType::I8 => scatter!(i8, i8::wrapping_add, i8::wrapping_mul, i8::max, i8::min),
+        TensorElemType::U8 => scatter!(u8, u8::wrapping_add, u8::wrapping_mul, u8::max, u8::min),
+        ty => {
+            return Err(SessionError::Message(
+                format!(
+                    "{name}: Unsupported type {ty:?} for reduction '{}'",
+                    reduction.as_str()
+                )
+                .into(),
+            ))
+        }
+    }
+
+    Ok(())
+}
+
+pub fn compute_top_k(
+    topk: &TopK,
+    inputs: &[&Tensor],
+    outputs: &mut [Tensor],
+) -> Result<(), SessionError> {
+    let x = inputs[Op::TOPK_IN_X];
+
+    /// Selects the `k` elements along the axis in the order of `cmp`, where ties are broken
+    /// by their indices.
+    fn top_k<T: TensorElemTypeExt>(
+        topk: &TopK,
+        x: &Tensor,
+        outputs: &mut [Tensor],
+        cmp: fn(&T, &T) -> Ordering,
+    ) {
+        let axis = topk.axis as usize;
+        let len = x.dims()[axis];
+        let k = outputs[Op::TOPK_OUT_VALUES].dims()[axis];
+        let outer = x.dims()[..axis].iter().product::<usize>();
+        let inner = x.dims()[axis + 1..].iter().product::<usize>();
+        let x = x.data::<T>();
+        let mut values = Vec::with_capacity(outer * k * inner);
+        let mut indices = Vec::with_capacity(outer * k * inner);
+        let mut order = Vec::with_capacity(len);
+        let mut lane = vec![x.first().copied().unwrap_or_else(T::zero); len];
+        let mut top = vec![0; k * inner];
+        for o in 0..outer {
+            for i in 0..inner {
+                for (j, l) in lane.iter_mut().enumerate() {
+                    *l = x[(o * len + j) * inner + i];
+                }
+                order.clear();
+                order.extend(0..len);
+                let by = |&a: &usize, &b: &usize| {
+                    let ord = cmp(&lane[a], &lane[b]);
+                    if topk.largest { ord.reverse() } else { ord }.then(a.cmp(&b))
+                };
+                if k > 0 && k < len {
+                    order.select_nth_unstable_by(k - 1, by);
+                }
+                order[..k].sort_unstable_by(by);
+                for (j, &index) in order[..k].iter().enumerate() {
+                    top[j * inner + i] = index;
+                }
+            }
+            for &index in &top {
+                indices.push(index as i64);
+            }
+            for (j, &index) in top.iter().enumerate() {
+                values.push(x[(o * len + index) * inner + j % inner]);
+            }
+        }
+        let dims = outputs[Op::TOPK_OUT_VALUES].dims().clone();
+        outputs[Op::TOPK_OUT_VALUES] = Tensor::new(dims.clone(), values);
+        outputs[Op::TOPK_OUT_INDICES] = Tensor::new(dims, indices);
+    }
+
+    match x.elem_ty() {
+        TensorElemType::F32 => top_k::<f32>(topk, x, outputs, f32::total_cmp),
+        TensorElemType::I64 => top_k::<i64>(topk, x, outputs, i64::cmp),
+        TensorElemType::I32 => top_k::<i32>(topk, x, outputs, i32::cmp),
+        TensorElemType::I8 => top_k::<i8>(topk, x, outputs, i8::cmp),
+        TensorElemType::U8 => top_k::<u8>(topk, x, outputs, u8::cmp),
+        ty => {
+            return Err(SessionError::Message(
+                format!("TopK: Unsupported type {ty:?}").into(),
+            ))
+        }
+    }
+
+    Ok(())
+}
+
+pub fn compute_one_hot(
+    one_hot: &OneHot,
+    inputs: &[&Tensor],
+    outputs: &mut [Tensor],
+) -> Result<(), SessionError> {
+    let indices = inputs[Op::ONEHOT_IN_INDICES];
+    let values = inputs[Op::ONEHOT_IN_VALUES];
+    let output = &mut outputs[Op::ONEHOT_OUT];
+    if values.dims().total_elems() != 2 {
+        return Err(SessionError::Message(
+            "OneHot: Values must be [off_value, on_value]".into(),
+        ));
+    }
+
+    let dims = output.dims().as_slice();
+    let axis = one_hot.axis as usize;
+    let depth = dims[axis];
+    let inner = dims[axis + 1..].iter().product::<usize>();
+    let size = values.elem_ty().size();
+    let (off, on) = values.data_as_bytes().split_at(size);
+
+    let mut out = off.repeat(dims.iter().product());
+    for (n, &i) in to_i64(indices, "OneHot")?.iter().enumerate() {
+        // Indices out of range have no on value.
+        let i = if i < 0 { i + depth as i64 } else { i };
+        if (0..depth as i64).contains(&i) {
+            let offset = (n / inner * depth + i as usize) * inner + n % inner;
+            out[offset * size..(offset + 1) * size].copy_from_slice(on);
+        }
+    }
+    *output = Tensor::new_from_raw(output.dims().clone(), values.elem_ty(), out);
+
+    Ok(())
+}
+
+/// Computes NonZero, whose output shape depends on the values of its input.
+pub fn compute_non_zero(inputs: &[&Tensor]) -> Result<Vec<Tensor>, SessionError> {
+    let input = inputs[Op::NONZERO_IN];
+
+    fn non_zero<T: TensorElemTypeExt>(input: &Tensor) -> Vec<usize> {
+        let zero = T::zero();
+        input
+            .data::<T>()
+            .iter()
+            .enumerate()
+            .filter_map(|(i, &x)| (x != zero).then_some(i))
+            .collect()
+    }
+
+    let found = match input.elem_ty() {
+        TensorElemType::F32 => non_zero::<f32>(input),
+        TensorElemType::I64 => non_zero::<i64>(input),
+        TensorElemType::I32 => non_zero::<i32>(input),
+        TensorElemType::I8 => non_zero::<i8>(input),
+        TensorElemType::U8 => non_zero::<u8>(input),
+        TensorElemType::Bool => non_zero::<bool>(input),
+        ty => {
+            return Err(SessionError::Message(
+                format!("NonZero: Unsupported type {ty:?}").into(),
+            ))
+        }
+    };
+
+    // Each row holds the coordinates along an axis.
+    let dims = input.dims();
+    let mut output = vec![0i64; dims.len() * found.len()];
+    for (j, &i) in found.iter().enumerate() {
+        for (axis, &stride) in input.strides().iter().enumerate() {
+            output[axis * found.len() + j] = (i / stride % dims[axis]) as i64;
+        }
+    }
+
+    Ok(vec![Tensor::new(
+        vec![dims.len(), found.len()].into(),
+        output,
+    )])
+}
+
+pub fn compute_cum_sum(
+    cumsum: &CumSum,
+    inputs: &[&Tensor],
+    outputs: &mut [Tensor],
+) -> Result<(), SessionError> {
+    let x = inputs[Op::CUMSUM_IN_X];
+    let axis = to_i64(inputs[Op::CUMSUM_IN_AXIS], "CumSum")?[0];
+    let output = &mut outputs[Op::CUMSUM_OUT];
+    let rank = x.dims().len() as i64;
+    if !(-rank..rank).contains(&axis) {
+        return Err(SessionError::Message(
+            format!("CumSum: Axis {axis} is out of range").into(),
+        ));
+    }
+    let axis = if axis < 0 { axis + rank } else { axis } as usize;
+
+    fn cum_sum<T: TensorElemTypeExt + std::ops::Add<Output = T>>(
+        cumsum: &CumSum,
+        axis: usize,
+        x: &Tensor,
+        output: &mut Tensor,
+    ) {
+        let len = x.dims()[axis];
+        let inner = x.dims()[axis + 1..].iter().product::<usize>();
+        let x = x.data::<T>();
+        let output = output.data_mut::<T>();
+        for (x, output) in x.chunks(len * inner).zip(output.chunks_mut(len * inner)) {
+            for i in 0..inner {
+                let mut sum = T::zero();
+                for j in 0..len {
+                    let j = if cumsum.reverse { len - 1 - j } else { j };
+                    let k = j * inner + i;
+                    if cumsum.exclusive {
+                        output[k] = sum;
+                        sum = sum + x[k];
+                    } else {
+                        sum = sum + x[k];
+                        output[k] = sum;
+                    }
+                }
+            }
+        }
+    }
+
+    match x.elem_ty() {
+        TensorElemType::F32 => cum_sum::<f32>(cumsum, axis, x, output),
+        TensorElemType::I64 => cum_sum::<i64>(cumsum, axis, x, output),
+        TensorElemType::I32 => cum_sum::<i32>(cumsum, axis, x, output),
+        ty => {
+            return Err(SessionError::Message(
+                format!("CumSum: Unsupported type {ty:?}").into(),
+            ))
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the offsets of the `data` elements that `indices` along `axis` point to,
+/// for each element of a tensor of `dims`, as of `GatherElements` and `ScatterElements`.
+fn element_offsets(
+    data: &Tensor,
+    dims: &[usize],
+    indices: &[i64],
+    axis: usize,
+    name: &str,
+) -> Result<Vec<usize>, SessionError> {
+    if dims.len() != data.dims().len()
+        || dims
+            .iter()
+            .zip(data.dims().iter())
+            .enumerate()
+            .any(|(a, (&d, &dd))| a != axis && d > dd)
+    {
+        return Err(SessionError::Message(
+            format!(
+                "{name}: Indices {dims:?} don't fit in data {:?}",
+                data.dims()
+            )
+            .into(),
+        ));
+    }
+    let axis_len = data.dims()[axis];
+    let strides = data.strides();
+    let mut coord = vec![0; dims.len()];
+    let mut offsets = Vec::with_capacity(indices.len());
+    for &i in indices {
+        let offset = coord
+            .iter()
+            .zip(strides.iter())
+            .enumerate()
+            .map(|(a, (&c, &s))| if a == axis { 0 } else { c * s })
+            .sum::<usize>();
+        offsets.push(offset + index(i, axis_len, name)? * strides[axis]);
+        next_coord(&mut coord, dims);
+    }
+    Ok(offsets)
+}
+
+/// Returns the offsets of the `data` slices that the last axis of `indices` points to, and
+/// the length of the slices, as of `GatherND` and `ScatterND`.
+fn slice_offsets(
+    data: &Tensor,
+    indices: &Tensor,
+    batch_dims: usize,
+    name: &str,
+) -> Result<(Vec<usize>, usize), SessionError> {
+    let Some(&index_len) = indices.dims().last() else {
+        return Err(SessionError::Message(
+            format!("{name}: Indices must not be a scalar").into(),
+        ));
+    };
+    let dims = data.dims();
+    if batch_dims + index_len > dims.len() || dims[..batch_dims] != indices.dims()[..batch_dims] {
+        return Err(SessionError::Message(
+            format!(
+                "{name}: Invalid indices {:?} for data {dims:?}",
+                indices.dims()
+            )
+            .into(),
+        ));
+    }
+    let strides = &data.strides()[batch_dims..batch_dims + index_len];
+    let slice_len = dims[batch_dims + index_len..].iter().product::<usize>();
+    // The stride of a batch in `data`, and the number of slices in a batch.
+    let batch_stride = dims[batch_dims..].iter().product::<usize>();
+    let batch_len = indices.dims()[batch_dims..indices.dims().len() - 1]
+        .iter()
+        .product::<usize>();
+
+    let indices = to_i64(indices, name)?;
+    let mut offsets = Vec::with_capacity(indices.len() / index_len.max(1));
+    for (n, tuple) in indices.chunks(index_len.max(1)).enumerate() {
+        let mut offset = n.checked_div(batch_len).unwrap_or(0) * batch_stride;
+        for ((&i, &stride), &len) in tuple[..index_len]
+            .iter()
+            .zip(strides)
+            .zip(&dims[batch_dims..])
+        {
+            offset += index(i, len, name)? * stride;
+        }
+        offsets.push(offset);
+    }
+    Ok((offsets, slice_len))
+}
+
+/// Converts a possibly negative index into `0..len`.
+fn index(i: i64, len: usize, name: &str) -> Result<usize, SessionError> {
+    let j = if i < 0 { i + len as i64 } else { i };
+    if (0..len as i64).contains(&j) {
+        Ok(j as usize)
+    } else {
+        Err(SessionError::Message(
+            format!("{name}: Index {i} is out of range for {len} elements").into(),
+        ))
+    }
+}
+
+fn to_i64(t: &Tensor, name: &str) -> Result<Vec<i64>, SessionError> {
+    match t.elem_ty() {
+        TensorElemType::I64 => Ok(t.data::<i64>().to_vec()),
+        TensorElemType::I32 => Ok(t.data::<i32>().iter().map(|&i| i as i64).collect()),
+        TensorElemType::F32 => Ok(t.data::<f32>().iter().map(|&i| i as i64).collect()),
+        ty => Err(SessionError::Message(
+            format!("{name}: Unsupported index type {ty:?}").into(),
+        )),
+    }
+}
@@ -0,0 +1,244 @@
+//! Kernels of ops rearranging elements: `Pad`, `DepthToSpace` and `SpaceToDepth`.
+//! They copy elements as bytes, so they work on tensors of any type.
+
+use altius_core::{
+    op::{DepthToSpace, Op, Pad, SpaceToDepth},
+    tensor::{Tensor, TensorElemType},
+};
+use altius_session::SessionError;
+
+use super::conv2d::next_coord;
+
+/// Pads are normalized in shape inference, so they cover every axis here.
+pub fn compute_pad(
+    pad: &Pad,
+    inputs: &[&Tensor],
+    outputs: &mut [Tensor],
+) -> Result<(), SessionError> {
+    let data = inputs[Op::PAD_IN_DATA];
+    let output = &mut outputs[Op::PAD_OUT];
+    let ty = data.elem_ty();
+    let size = ty.size();
+
+    let value = match inputs
+        .get(Op::PAD_IN_CONSTANT_VALUE)
+        .filter(|v| v.dims().total_elems() > 0)
+    {
+        Some(v) if v.elem_ty() == ty => v.data_as_bytes()[..size].to_vec(),
+        Some(v) => {
+            return Err(SessionError::Message(
+                format!("Pad: Constant value of {:?} for {ty:?}", v.elem_ty()).into(),
+            ))
+        }
+        None => scalar_bytes(pad.value, ty),
+    };
+    let mode = match pad.mode.as_str() {
+        "constant" => Mode::Constant,
+        "reflect" => Mode::Reflect,
+        "edge" => Mode::Edge,
+        "wrap" => Mode::Wrap,
+        mode => {
+            return Err(SessionError::Message(
+                format!("Pad: Unknown mode {mode}").into(),
+            ))
+        }
+    };
+
+    let in_dims = data.dims().as_slice();
+    let out_dims = output.dims().as_slice().to_vec();
+    let rank = in_dims.len();
+    let bytes = data.data_as_bytes();
+    let mut out = Vec::with_capacity(output.dims().total_elems() * size);
+    let Some((&row_len, outer_dims)) = out_dims.split_last() else {
+        // A scalar has nothing to pad.
+        output.copy_data_from(data);
+        return Ok(());
+    };
+    if out_dims.contains(&0) {
+        *output = Tensor::new_from_raw(output.dims().clone(), ty, out);
+        return Ok(());
+    }
+
+    let strides = data.strides();
+    let (begin, in_len) = (pad.pads[rank - 1], in_dims[rank - 1] as i64);
+    let row_len = row_len as i64;
+    // Elements in `lo..hi` of each output row are copied at once from an input row.
+    let lo = begin.clamp(0, row_len);
+    let hi = (begin + in_len).clamp(lo, row_len);
+    let mut coord = vec![0; outer_dims.len()];
+    for _ in 0..outer_dims.iter().product::<usize>() {
+        let offset = coord.iter().enumerate().try_fold(0, |offset, (axis, &c)| {
+            let i = mode.source(c as i64 - pad.pads[axis], in_dims[axis] as i64)?;
+            Some(offset + i * strides[axis])
+        });
+        let Some(offset) = offset else {
+            out.extend(value.repeat(row_len as usize));
+            next_coord(&mut coord, outer_dims);
+            continue;
+        };
+        let row = &bytes[offset * size..][..in_len as usize * size];
+        let element = |j: i64| match mode.source(j - begin, in_len) {
+            Some(i) => &row[i * size..(i + 1) * size],
+            None => &value[..],
+        };
+        for j in 0..lo {
+            out.extend_from_slice(element(j));
+        }
+        if lo < hi {
+            out.extend_from_slice(&row[(lo - begin) as usize * size..(hi - begin) as usize * size]);
+        }
+        for j in hi..row_len {
+            out.extend_from_slice(element(j));
+        }
+        next_coord(&mut coord, outer_dims);
+    }
+    *output = Tensor::new_from_raw(output.dims().clone(), ty, out);
+
+    Ok(())
+}
+
+#[derive(Clone, Copy)]
+enum Mode {
+    Constant,
+    Reflect,
+    Edge,
+    Wrap,
+}
+
+impl Mode {
+    /// Returns the input index that the (possibly out-of-range) index `i` takes its element
+    /// from, or `None` if it takes the constant value.
+    fn source(self, i: i64, len: i64) -> Option<usize> {
+        if (0..len).contains(&i) {
+            return Some(i as usize);
+        }
+        let i = match self {
+            Mode::Constant => return None,
+            Mode::Edge => i.clamp(0, len - 1),
+            Mode::Wrap => i.rem_euclid(len),
+            Mode::Reflect if len == 1 => 0,
+            Mode::Reflect => {
+                let period = 2 * (len - 1);
+                let i = i.rem_euclid(period);
+                if i < len {
+                    i
+                } else {
+                    period - i
+                }
+            }
+        };
+        Some(i as usize)
+    }
+}
+
+/// Returns the bytes of `value` as an element of `ty`.
+fn scalar_bytes(value: f32, ty: TensorElemType) -> Vec<u8> {
+    match ty {
+        TensorElemType::I64 => (value as i64).to_le_bytes().to_vec(),
+        TensorElemType::I32 => (value as i32).to_le_bytes().to_vec(),
+        TensorElemType::I8 => (value as i8).to_le_bytes().to_vec(),
+        TensorElemType::U8 => (value as u8).to_le_bytes().to_vec(),
+        TensorElemType::Bool => vec![(value != 0.) as u8],
+        ty => Tensor::new(vec![].into(), vec![value])
+            .cast_float(ty)
+            .unwrap()
+            .data_as_bytes()
+            .to_vec(),
+    }
+}
+
+pub fn compute_depth_to_space(
+    d2s: &DepthToSpace,
+    inputs: &[&Tensor],
+    outputs: &mut [Tensor],
+) -> Result<(), SessionError> {
+    let input = inputs[Op::DEPTHTOSPACE_IN];
+    let b = d2s.blocksize as usize;
+    let &[n, c, h, w] = input.dims().as_slice() else {
+        return Err(SessionError::Message(
+            "DepthToSpace: Input must be 4-D".into(),
+        ));
+    };
+    let c = c / (b * b);
+    let (hw, chw) = (h * w, c * h * w);
+    // The dimensions and strides of the input in the order of output elements, which are
+    // laid out as `[n, c, h, b1, w, b2]`.
+    let view = match d2s.mode.as_str() {
+        // The input channels are laid out as `[b1, b2, c]`.
+        "DCR" => [
+            (n, b * b * chw),
+            (c, hw),
+            (h, w),
+            (b, b * chw),
+            (w, 1),
+            (b, chw),
+        ],
+        // The input channels are laid out as `[c, b1, b2]`.
+        _ => [
+            (n, b * b * chw),
+            (c, b * b * hw),
+            (h, w),
+            (b, b * hw),
+            (w, 1),
+            (b, hw),
+        ],
+    };
+    outputs[Op::DEPTHTOSPACE_OUT] = gather_view(input, &view, &outputs[Op::DEPTHTOSPACE_OUT]);
+
+    Ok(())
+}
+
+pub fn compute_space_to_depth(
+    s2d: &SpaceToDepth,
+    inputs: &[&Tensor],
+    outputs: &mut [Tensor],
+) -> Result<(), SessionError> {
+    let input = inputs[Op::SPACETODEPTH_IN];
+    let b = s2d.blocksize as usize;
+    let &[n, c, h, w] = input.dims().as_slice() else {
+        return Err(SessionError::Message(
+            "SpaceToDepth: Input must be 4-D".into(),
+        ));
+    };
+    // The output channels are laid out as `[b1, b2, c]` and each input spatial axis as
+    // `[h, b1]` and `[w, b2]`.
+    let view = [
+        (n, c * h * w),
+        (b, w),
+        (b, 1),
+        (c, h * w),
+        (h / b, b * w),
+        (w / b, b),
+    ];
+    outputs[Op::SPACETODEPTH_OUT] = gather_view(input, &view, &outputs[Op::SPACETODEPTH_OUT]);
+
+    Ok(())
+}
+
+/// Copies the elements of `input` seen through `view` of `(dim, stride)` into a tensor
+/// shaped like `output`.
+fn gather_view(input: &Tensor, view: &[(usize, usize)], output: &Tensor) -> Tensor {
+    let size = input.elem_ty().size();
+    let bytes = input.data_as_bytes();
+    let (dims, strides): (Vec<_>, Vec<_>) = view.iter().copied().unzip();
+    let Some((&row_len, outer_dims)) = dims.split_last() else {
+        return input.clone();
+    };
+    let row_stride = strides[strides.len() - 1] * size;
+    let mut out = Vec::with_capacity(output.dims().total_elems() * size);
+    let mut coord = vec![0; outer_dims.len()];
+    for _ in 0..outer_dims.iter().product::<usize>() {
+        let offset = coord
+            .iter()
+            .zip(&strides)
+            .map(|(c, s)| c * s)
+            .sum::<usize>()
+            * size;
+        for j in 0..row_len {
+            let from = offset + j * row_stride;
+            out.extend_from_slice(&bytes[from..from + size]);
+        }
+        next_coord(&mut coord, outer_dims);
+    }
+    Tensor::new_from_raw(output.dims().clone(), input.elem_ty(), out)
+}
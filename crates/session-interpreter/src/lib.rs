@@ -11,11 +11,27 @@ use blas_src; // For accelerate, this is necessary to link the library.
 use blis_src;
 
 mod builder;
+mod calibration;
+mod contrib;
+mod control_flow;
 mod conv2d;
+mod conv_transpose;
+mod elemwise;
 mod fast_math;
 mod gemm;
+mod indexing;
+mod layout;
+mod nms;
+mod norm;
+mod pool;
+mod quantize;
+mod reduce;
+mod resize;
+mod rnn;
 mod session;
 mod thread;
+mod unary;
 
 pub use builder::InterpreterSessionBuilder;
+pub use calibration::{quantize_static, Calibrator};
 pub use session::InterpreterSession;
@@ -0,0 +1,103 @@
+//! A kernel for NonMaxSuppression, whose output shape depends on the values of its inputs.
+
+use altius_core::{
+    op::{NonMaxSuppression, Op},
+    tensor::Tensor,
+};
+use altius_session::SessionError;
+
+pub fn compute_non_max_suppression(
+    op: &NonMaxSuppression,
+    inputs: &[&Tensor],
+) -> Result<Vec<Tensor>, SessionError> {
+    let boxes = inputs[Op::NMS_IN_BOXES];
+    let scores = inputs[Op::NMS_IN_SCORES];
+    if !boxes.elem_ty().is_f32() || !scores.elem_ty().is_f32() {
+        return Err(SessionError::Message(
+            format!(
+                "NonMaxSuppression: Unsupported types {:?} and {:?}",
+                boxes.elem_ty(),
+                scores.elem_ty()
+            )
+            .into(),
+        ));
+    }
+
+    // Omitted inputs have no elements.
+    let given = |i: usize| inputs.get(i).filter(|t| t.dims().total_elems() > 0);
+    let max_output_boxes_per_class = given(Op::NMS_IN_MAX_OUTPUT_BOXES_PER_CLASS)
+        .map_or(0, |t| t.data::<i64>()[0].max(0) as usize);
+    let iou_threshold = given(Op::NMS_IN_NMS_IOU_THRESHOLD).map_or(0., |t| t.data::<f32>()[0]);
+    let score_threshold = given(Op::NMS_IN_NMS_SCORE_THRESHOLD).map(|t| t.data::<f32>()[0]);
+
+    let [num_batches, num_boxes, _] = boxes.fixed_dims::<3>();
+    let [_, num_classes, _] = scores.fixed_dims::<3>();
+    let (boxes, scores) = (boxes.data::<f32>(), scores.data::<f32>());
+
+    let mut selected = vec![];
+    for batch in 0..num_batches {
+        let boxes = &boxes[batch * num_boxes * 4..(batch + 1) * num_boxes * 4];
+        let corners = boxes
+            .chunks(4)
+            .map(|b| to_corners(b, op.center_point_box))
+            .collect::<Vec<_>>();
+        for class in 0..num_classes {
+            let offset = (batch * num_classes + class) * num_boxes;
+            let scores = &scores[offset..offset + num_boxes];
+            let mut candidates = (0..num_boxes)
+                .filter(|&i| score_threshold.map_or(true, |th| scores[i] > th))
+                .collect::<Vec<_>>();
+            // The sort is stable so that boxes of the same score keep their order.
+            candidates.sort_by(|&a, &b| scores[b].total_cmp(&scores[a]));
+
+            let mut kept: Vec<usize> = vec![];
+            for i in candidates {
+                if kept.len() >= max_output_boxes_per_class {
+                    break;
+                }
+                if kept
+                    .iter()
+                    .all(|&k| iou(&corners[i], &corners[k]) <= iou_threshold)
+                {
+                    kept.push(i);
+                    selected.extend([batch as i64, class as i64, i as i64]);
+                }
+            }
+        }
+    }
+
+    Ok(vec![Tensor::new(
+        vec![selected.len() / 3, 3].into(),
+        selected,
+    )])
+}
+
+/// Converts a box into `[y_min, x_min, y_max, x_max]`.
+fn to_corners(b: &[f32], center_point_box: bool) -> [f32; 4] {
+    if center_point_box {
+        // `[x_center, y_center, width, height]`
+        let (half_w, half_h) = (b[2] / 2., b[3] / 2.);
+        [b[1] - half_h, b[0] - half_w, b[1] + half_h, b[0] + half_w]
+    } else {
+        // Any diagonal pair of corners is allowed.
+        [
+            b[0].min(b[2]),
+            b[1].min(b[3]),
+            b[0].max(b[2]),
+            b[1].max(b[3]),
+        ]
+    }
+}
+
+fn iou(a: &[f32; 4], b: &[f32; 4]) -> f32 {
+    let h = (a[2].min(b[2]) - a[0].max(b[0])).max(0.);
+    let w = (a[3].min(b[3]) - a[1].max(b[1])).max(0.);
+    let intersection = h * w;
+    let area = |c: &[f32; 4]| (c[2] - c[0]) * (c[3] - c[1]);
+    let union = area(a) + area(b) - intersection;
+    if union <= 0. {
+        0.
+    } else {
+        intersection / union
+    }
+}
@@ -0,0 +1,97 @@
+//! Kernels of `InstanceNormalization` and `GroupNormalization`, which normalize each group
+//! of channels (a single channel for the former) over the spatial axes.
+
+use altius_core::{
+    op::Op,
+    tensor::{Tensor, TensorElemType},
+};
+use altius_session::SessionError;
+
+use super::{
+    session::{fast_sum, fast_sum_squares},
+    thread::ThreadCtx,
+};
+
+pub fn compute(
+    tctx: &ThreadCtx,
+    op: &Op,
+    inputs: &[&Tensor],
+    outputs: &mut [Tensor],
+) -> Result<(), SessionError> {
+    let input = inputs[Op::NORM_IN_X];
+    let scale = inputs[Op::NORM_IN_SCALE].data::<f32>();
+    let bias = inputs[Op::NORM_IN_B].data::<f32>();
+    let output = &mut outputs[Op::NORM_OUT];
+    if input.elem_ty() != TensorElemType::F32 {
+        return Err(SessionError::Message(
+            format!("{}: Unsupported type {:?}", op.name(), input.elem_ty()).into(),
+        ));
+    }
+
+    let channels = input.dims()[1];
+    let (groups, epsilon) = match op {
+        Op::InstanceNormalization(n) => (channels, n.epsilon),
+        Op::GroupNormalization(n) => (n.num_groups as usize, n.epsilon),
+        op => {
+            return Err(SessionError::Message(
+                format!(
+                    "{} is not InstanceNormalization or GroupNormalization",
+                    op.name()
+                )
+                .into(),
+            ))
+        }
+    };
+    let spatial = input.dims()[2..].iter().product::<usize>();
+    let group_len = channels / groups * spatial;
+    if group_len == 0 {
+        return Ok(());
+    }
+    // Scale and bias of GroupNormalization are given per group until opset version 21.
+    let per_group = scale.len() != channels;
+
+    let input = input.data::<f32>();
+    let output = output.data_mut::<f32>();
+    let num_groups = input.len() / group_len;
+    let batch = num_groups.div_ceil(tctx.num_threads()).max(1);
+    let inv_len = (group_len as f32).recip();
+
+    tctx.scope(|scope| {
+        input
+            .chunks(group_len * batch)
+            .zip(output.chunks_mut(group_len * batch))
+            .enumerate()
+            .for_each(|(i, (input, output))| {
+                scope.spawn(move || {
+                    for (j, (input, output)) in input
+                        .chunks(group_len)
+                        .zip(output.chunks_mut(group_len))
+                        .enumerate()
+                    {
+                        let group = (i * batch + j) % groups;
+                        let mean = fast_sum(input) * inv_len;
+                        for (&x, o) in input.iter().zip(output.iter_mut()) {
+                            *o = x - mean;
+                        }
+                        let inv_std = fast_sum_squares(output)
+                            .mul_add(inv_len, epsilon)
+                            .sqrt()
+                            .recip();
+                        for (k, output) in output.chunks_mut(spatial).enumerate() {
+                            let c = if per_group {
+                                group
+                            } else {
+                                group * (channels / groups) + k
+                            };
+                            let (scale, bias) = (scale[c] * inv_std, bias[c]);
+                            for o in output {
+                                *o = o.mul_add(scale, bias);
+                            }
+                        }
+                    }
+                });
+            });
+    });
+
+    Ok(())
+}
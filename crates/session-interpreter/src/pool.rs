@@ -0,0 +1,326 @@
+//! Kernels for pooling ops. `Pool`s are expected to be normalized by shape inference,
+//! so that every spatial axis has its own strides, dilations and pads.
+
+use altius_core::{
+    op::{Op, Pool},
+    tensor::{Tensor, TensorElemType, TensorElemTypeExt},
+};
+use altius_session::SessionError;
+
+pub fn compute_max_pool(
+    pool: &Pool,
+    inputs: &[&Tensor],
+    outputs: &mut [Tensor],
+) -> Result<(), SessionError> {
+    let input = inputs[Op::MAXPOOL_IN];
+    if input.elem_ty().is_f32()
+        && input.dims().len() == 4
+        && outputs.len() == 1
+        && pool.dilations.iter().all(|&d| d == 1)
+    {
+        compute_max_pool_2d(pool, input, &mut outputs[Op::MAXPOOL_OUT]);
+        return Ok(());
+    }
+
+    let windows = Windows::new(pool, input.dims(), outputs[Op::MAXPOOL_OUT].dims());
+    match input.elem_ty() {
+        TensorElemType::F32 => max_pool::<f32>(&windows, pool, input, outputs),
+        TensorElemType::I8 => max_pool::<i8>(&windows, pool, input, outputs),
+        TensorElemType::U8 => max_pool::<u8>(&windows, pool, input, outputs),
+        ty => {
+            return Err(SessionError::Message(
+                format!("MaxPool: Unsupported type {ty:?}").into(),
+            ))
+        }
+    }
+    Ok(())
+}
+
+pub fn compute_average_pool(
+    pool: &Pool,
+    inputs: &[&Tensor],
+    outputs: &mut [Tensor],
+) -> Result<(), SessionError> {
+    let input = inputs[Op::AVERAGEPOOL_IN];
+    let output = &mut outputs[Op::AVERAGEPOOL_OUT];
+    if !input.elem_ty().is_f32() {
+        return Err(SessionError::Message(
+            format!("AveragePool: Unsupported type {:?}", input.elem_ty()).into(),
+        ));
+    }
+
+    let windows = Windows::new(pool, input.dims(), output.dims());
+    for (input, output) in input
+        .data::<f32>()
+        .chunks(windows.input_size)
+        .zip(output.data_mut::<f32>().chunks_mut(windows.output_size))
+    {
+        for ((out, window), &padded_size) in output
+            .iter_mut()
+            .zip(windows.offsets.iter())
+            .zip(windows.padded_sizes.iter())
+        {
+            let sum: f32 = window.iter().map(|&i| input[i]).sum();
+            let count = if pool.count_include_pad {
+                padded_size
+            } else {
+                window.len()
+            };
+            *out = if count == 0 { 0. } else { sum / count as f32 };
+        }
+    }
+
+    Ok(())
+}
+
+pub fn compute_lp_pool(
+    pool: &Pool,
+    inputs: &[&Tensor],
+    outputs: &mut [Tensor],
+) -> Result<(), SessionError> {
+    let input = inputs[Op::LPPOOL_IN];
+    let output = &mut outputs[Op::LPPOOL_OUT];
+    if !input.elem_ty().is_f32() {
+        return Err(SessionError::Message(
+            format!("LpPool: Unsupported type {:?}", input.elem_ty()).into(),
+        ));
+    }
+
+    let p = pool.p as f32;
+    let windows = Windows::new(pool, input.dims(), output.dims());
+    for (input, output) in input
+        .data::<f32>()
+        .chunks(windows.input_size)
+        .zip(output.data_mut::<f32>().chunks_mut(windows.output_size))
+    {
+        for (out, window) in output.iter_mut().zip(windows.offsets.iter()) {
+            let sum: f32 = window.iter().map(|&i| input[i].abs().powf(p)).sum();
+            *out = sum.powf(p.recip());
+        }
+    }
+
+    Ok(())
+}
+
+pub fn compute_global_average_pool(
+    inputs: &[&Tensor],
+    outputs: &mut [Tensor],
+) -> Result<(), SessionError> {
+    let input = inputs[Op::GLOBALAVERAGEPOOL_IN];
+    let output = &mut outputs[Op::GLOBALAVERAGEPOOL_OUT];
+    let area = input.dims()[2..].iter().product::<usize>();
+    if !input.elem_ty().is_f32() || area == 0 {
+        return Err(SessionError::Message(
+            format!("GlobalAveragePool: Unsupported input {:?}", input.dims()).into(),
+        ));
+    }
+
+    for (out, channel) in output
+        .data_mut::<f32>()
+        .iter_mut()
+        .zip(input.data::<f32>().chunks(area))
+    {
+        *out = channel.iter().sum::<f32>() / area as f32;
+    }
+
+    Ok(())
+}
+
+pub fn compute_global_max_pool(
+    inputs: &[&Tensor],
+    outputs: &mut [Tensor],
+) -> Result<(), SessionError> {
+    let input = inputs[Op::GLOBALMAXPOOL_IN];
+    let output = &mut outputs[Op::GLOBALMAXPOOL_OUT];
+    let area = input.dims()[2..].iter().product::<usize>();
+    if !input.elem_ty().is_f32() || area == 0 {
+        return Err(SessionError::Message(
+            format!("GlobalMaxPool: Unsupported input {:?}", input.dims()).into(),
+        ));
+    }
+
+    for (out, channel) in output
+        .data_mut::<f32>()
+        .iter_mut()
+        .zip(input.data::<f32>().chunks(area))
+    {
+        *out = channel.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+    }
+
+    Ok(())
+}
+
+/// The input elements pooled into each output element, shared by all the channels.
+struct Windows {
+    /// Offsets within a channel of the input elements each window covers.
+    offsets: Vec<Vec<usize>>,
+    /// The numbers of elements each window covers including pads, for `count_include_pad`.
+    padded_sizes: Vec<usize>,
+    /// The spatial dimensions of the input.
+    input_dims: Vec<usize>,
+    input_size: usize,
+    output_size: usize,
+}
+
+impl Windows {
+    fn new(pool: &Pool, input: &[usize], output: &[usize]) -> Self {
+        let (input, output) = (&input[2..], &output[2..]);
+        let rank = input.len();
+        let output_size = output.iter().product::<usize>();
+        let mut offsets = Vec::with_capacity(output_size);
+        let mut padded_sizes = Vec::with_capacity(output_size);
+
+        let mut coord = vec![0; rank];
+        for _ in 0..output_size {
+            let mut window = vec![0];
+            let mut padded_size = 1;
+            for i in 0..rank {
+                let len = input[i] as isize;
+                let xs = (0..pool.kernel_shape[i])
+                    .map(|k| {
+                        (coord[i] * pool.strides[i] + k * pool.dilations[i]) as isize
+                            - pool.padding[i] as isize
+                    })
+                    .collect::<Vec<_>>();
+                // Windows may go past the end pads with `ceil_mode`.
+                padded_size *= xs
+                    .iter()
+                    .filter(|&&x| x < len + pool.padding[rank + i] as isize)
+                    .count();
+                window = window
+                    .iter()
+                    .flat_map(|&offset| {
+                        xs.iter()
+                            .filter(move |&&x| (0..len).contains(&x))
+                            .map(move |&x| offset * len as usize + x as usize)
+                    })
+                    .collect();
+            }
+            offsets.push(window);
+            padded_sizes.push(padded_size);
+
+            // Advances `coord` in row-major order.
+            for i in (0..rank).rev() {
+                coord[i] += 1;
+                if coord[i] < output[i] {
+                    break;
+                }
+                coord[i] = 0;
+            }
+        }
+
+        Self {
+            offsets,
+            padded_sizes,
+            input_dims: input.to_vec(),
+            input_size: input.iter().product(),
+            output_size,
+        }
+    }
+
+    /// Converts a row-major `offset` within a channel into the column-major one.
+    fn to_column_major(&self, mut offset: usize) -> usize {
+        let mut coords = vec![0; self.input_dims.len()];
+        for (coord, &dim) in coords.iter_mut().zip(self.input_dims.iter()).rev() {
+            *coord = offset % dim;
+            offset /= dim;
+        }
+        coords
+            .iter()
+            .zip(self.input_dims.iter())
+            .rev()
+            .fold(0, |acc, (&coord, &dim)| acc * dim + coord)
+    }
+}
+
+fn max_pool<T: TensorElemTypeExt>(
+    windows: &Windows,
+    pool: &Pool,
+    input: &Tensor,
+    outputs: &mut [Tensor],
+) {
+    let (values, indices) = outputs.split_at_mut(Op::MAXPOOL_OUT_INDICES);
+    let values = values[Op::MAXPOOL_OUT].data_mut::<T>();
+    let mut indices = indices.first_mut().map(|i| i.data_mut::<i64>());
+
+    for (c, (input, values)) in input
+        .data::<T>()
+        .chunks(windows.input_size)
+        .zip(values.chunks_mut(windows.output_size))
+        .enumerate()
+    {
+        for (o, window) in windows.offsets.iter().enumerate() {
+            let argmax = window
+                .iter()
+                .copied()
+                .reduce(|max, i| if input[i] > input[max] { i } else { max });
+            values[o] = argmax.map_or(T::zero(), |i| input[i]);
+            if let Some(indices) = indices.as_mut() {
+                let argmax = argmax.unwrap_or(0);
+                let argmax = if pool.storage_order == 1 {
+                    windows.to_column_major(argmax)
+                } else {
+                    argmax
+                };
+                indices[c * windows.output_size + o] = (c * windows.input_size + argmax) as i64;
+            }
+        }
+    }
+}
+
+/// A fast path for 2D `MaxPool` over f32 without dilations.
+fn compute_max_pool_2d(pool: &Pool, input: &Tensor, output: &mut Tensor) {
+    let kernel = &pool.kernel_shape;
+    let stride = &pool.strides;
+
+    assert!(input.dims().len() == 4);
+    assert!(output.dims().len() == 4);
+
+    let padding = &pool.padding;
+    let batches = output.dims()[0];
+    let channels = output.dims()[1];
+    let outer = batches * channels;
+    let output_h = output.dims()[2];
+    let output_w = output.dims()[3];
+    let input_h = input.dims()[2];
+    let input_w = input.dims()[3];
+    let kernel_h = kernel[0];
+    let kernel_w = kernel[1];
+    let stride_h = stride[0];
+    let stride_w = stride[1];
+    let input_hw = input_h * input_w;
+    let output_hw = output_h * output_w;
+
+    let mut input = input.data::<f32>();
+    let mut output = output.data_mut::<f32>();
+
+    let pad_t = padding[0] as isize;
+    let pad_l = padding[1] as isize;
+
+    for _ in 0..outer {
+        let mut y = -pad_t;
+        for ay in 0..output_h {
+            let mut x = -pad_l;
+            let output = &mut output[ay * output_w..];
+            let fy_min = (-y).max(0) as usize;
+            let fy_max = kernel_h.min((input_h as isize - y) as usize);
+            for out in output.iter_mut().take(output_w) {
+                let mut max = f32::MIN;
+                let fx_min = (-x).max(0) as usize;
+                let fx_max = kernel_w.min((input_w as isize - x) as usize);
+                for fy in fy_min..fy_max {
+                    let oy = y + fy as isize;
+                    for fx in fx_min..fx_max {
+                        let ox = x + fx as isize;
+                        max = input[oy as usize * input_w + ox as usize].max(max);
+                    }
+                }
+                *out = if max == f32::MIN { 0.0 } else { max };
+                x += stride_w as isize
+            }
+            y += stride_h as isize
+        }
+        input = &input[input_hw..];
+        output = &mut output[output_hw..];
+    }
+}
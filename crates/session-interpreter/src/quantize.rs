@@ -0,0 +1,407 @@
+//! Kernels for quantized operators.
+//! Integer inputs are widened to i32 and accumulated in i32.
+
+use altius_core::{
+    fixed_dim::FixedDimensions,
+    op::{Conv2d, DequantizeLinear, Op, QuantizeLinear},
+    tensor::{Tensor, TensorElemType},
+};
+use altius_session::SessionError;
+
+pub fn compute_quantize_linear(
+    op: &QuantizeLinear,
+    inputs: &[&Tensor],
+    outputs: &mut [Tensor],
+) -> Result<(), SessionError> {
+    let x = inputs[Op::QUANTIZELINEAR_IN_X];
+    let scale = inputs[Op::QUANTIZELINEAR_IN_Y_SCALE].data::<f32>();
+    let zero_point = zero_point(inputs, Op::QUANTIZELINEAR_IN_Y_ZERO_POINT)?;
+    let output = &mut outputs[Op::QUANTIZELINEAR_OUT];
+    if !x.elem_ty().is_f32() {
+        return Err(SessionError::Message(
+            format!("QuantizeLinear: Unsupported input type {:?}", x.elem_ty()).into(),
+        ));
+    }
+
+    let (inner, num_params) = param_layout(x.dims(), op.axis, scale.len())?;
+    let values = x.data::<f32>().iter().enumerate().map(|(i, &x)| {
+        let p = (i / inner) % num_params;
+        (x / scale[p]).round_ties_even() as i32 + zero_point[p % zero_point.len()]
+    });
+    store_saturated(output, values)
+}
+
+pub fn compute_dequantize_linear(
+    op: &DequantizeLinear,
+    inputs: &[&Tensor],
+    outputs: &mut [Tensor],
+) -> Result<(), SessionError> {
+    let x = inputs[Op::DEQUANTIZELINEAR_IN_X];
+    let scale = inputs[Op::DEQUANTIZELINEAR_IN_X_SCALE].data::<f32>();
+    let zero_point = zero_point(inputs, Op::DEQUANTIZELINEAR_IN_X_ZERO_POINT)?;
+    let output = &mut outputs[Op::DEQUANTIZELINEAR_OUT];
+
+    let (inner, num_params) = param_layout(x.dims(), op.axis, scale.len())?;
+    for (i, (o, x)) in output
+        .data_mut::<f32>()
+        .iter_mut()
+        .zip(to_i32(x)?)
+        .enumerate()
+    {
+        let p = (i / inner) % num_params;
+        *o = (x - zero_point[p % zero_point.len()]) as f32 * scale[p];
+    }
+
+    Ok(())
+}
+
+pub fn compute_mat_mul_integer(
+    inputs: &[&Tensor],
+    outputs: &mut [Tensor],
+) -> Result<(), SessionError> {
+    let a = inputs[Op::MATMULINTEGER_IN_A];
+    let b = inputs[Op::MATMULINTEGER_IN_B];
+    let a_zero_point = zero_point(inputs, Op::MATMULINTEGER_IN_A_ZERO_POINT)?;
+    let b_zero_point = zero_point(inputs, Op::MATMULINTEGER_IN_B_ZERO_POINT)?;
+    let output = &mut outputs[Op::MATMULINTEGER_OUT];
+
+    let acc = integer_mat_mul(a, b, &a_zero_point, &b_zero_point, output.dims())?;
+    output.data_mut::<i32>().copy_from_slice(&acc);
+
+    Ok(())
+}
+
+pub fn compute_qlinear_mat_mul(
+    inputs: &[&Tensor],
+    outputs: &mut [Tensor],
+) -> Result<(), SessionError> {
+    let a = inputs[Op::QLINEARMATMUL_IN_A];
+    let a_scale = inputs[Op::QLINEARMATMUL_IN_A_SCALE].data::<f32>()[0];
+    let a_zero_point = zero_point(inputs, Op::QLINEARMATMUL_IN_A_ZERO_POINT)?;
+    let b = inputs[Op::QLINEARMATMUL_IN_B];
+    let b_scale = inputs[Op::QLINEARMATMUL_IN_B_SCALE].data::<f32>();
+    let b_zero_point = zero_point(inputs, Op::QLINEARMATMUL_IN_B_ZERO_POINT)?;
+    let y_scale = inputs[Op::QLINEARMATMUL_IN_Y_SCALE].data::<f32>()[0];
+    let y_zero_point = zero_point(inputs, Op::QLINEARMATMUL_IN_Y_ZERO_POINT)?[0];
+    let output = &mut outputs[Op::QLINEARMATMUL_OUT];
+
+    let acc = integer_mat_mul(a, b, &a_zero_point, &b_zero_point, output.dims())?;
+    // `b_scale` may be per output column.
+    let n = output.dims()[output.dims().len() - 1];
+    if b_scale.len() != 1 && b_scale.len() != n {
+        return Err(SessionError::Message(
+            format!("QLinearMatMul: Invalid b_scale length {}", b_scale.len()).into(),
+        ));
+    }
+    let multipliers = b_scale
+        .iter()
+        .map(|&b_scale| a_scale * b_scale / y_scale)
+        .collect::<Vec<_>>();
+    let values = acc
+        .iter()
+        .enumerate()
+        .map(|(i, &acc)| requantize(acc, multipliers[i % n % multipliers.len()], y_zero_point));
+    store_saturated(output, values)
+}
+
+pub fn compute_conv_integer(
+    op: &Conv2d,
+    inputs: &[&Tensor],
+    outputs: &mut [Tensor],
+) -> Result<(), SessionError> {
+    let x = inputs[Op::CONVINTEGER_IN_X];
+    let w = inputs[Op::CONVINTEGER_IN_W];
+    let x_zero_point = zero_point(inputs, Op::CONVINTEGER_IN_X_ZERO_POINT)?;
+    let w_zero_point = zero_point(inputs, Op::CONVINTEGER_IN_W_ZERO_POINT)?;
+    let output = &mut outputs[Op::CONVINTEGER_OUT];
+
+    let acc = integer_conv2d(op, x, w, x_zero_point[0], &w_zero_point, output.dims())?;
+    output.data_mut::<i32>().copy_from_slice(&acc);
+
+    Ok(())
+}
+
+pub fn compute_qlinear_conv(
+    op: &Conv2d,
+    inputs: &[&Tensor],
+    outputs: &mut [Tensor],
+) -> Result<(), SessionError> {
+    let x = inputs[Op::QLINEARCONV_IN_X];
+    let x_scale = inputs[Op::QLINEARCONV_IN_X_SCALE].data::<f32>()[0];
+    let x_zero_point = zero_point(inputs, Op::QLINEARCONV_IN_X_ZERO_POINT)?;
+    let w = inputs[Op::QLINEARCONV_IN_W];
+    let w_scale = inputs[Op::QLINEARCONV_IN_W_SCALE].data::<f32>();
+    let w_zero_point = zero_point(inputs, Op::QLINEARCONV_IN_W_ZERO_POINT)?;
+    let y_scale = inputs[Op::QLINEARCONV_IN_Y_SCALE].data::<f32>()[0];
+    let y_zero_point = zero_point(inputs, Op::QLINEARCONV_IN_Y_ZERO_POINT)?[0];
+    let bias = inputs
+        .get(Op::QLINEARCONV_IN_B)
+        .filter(|b| b.dims().total_elems() > 0)
+        .map(|b| b.data::<i32>());
+    let output = &mut outputs[Op::QLINEARCONV_OUT];
+
+    let mut acc = integer_conv2d(op, x, w, x_zero_point[0], &w_zero_point, output.dims())?;
+    // `w_scale` and `bias` may be per output channel.
+    let out_c = output.dims()[1];
+    let area = output.dims()[2] * output.dims()[3];
+    if w_scale.len() != 1 && w_scale.len() != out_c {
+        return Err(SessionError::Message(
+            format!("QLinearConv: Invalid w_scale length {}", w_scale.len()).into(),
+        ));
+    }
+    if let Some(bias) = bias {
+        for (i, acc) in acc.iter_mut().enumerate() {
+            *acc += bias[(i / area) % out_c];
+        }
+    }
+    let multipliers = w_scale
+        .iter()
+        .map(|&w_scale| x_scale * w_scale / y_scale)
+        .collect::<Vec<_>>();
+    let values = acc.iter().enumerate().map(|(i, &acc)| {
+        requantize(
+            acc,
+            multipliers[(i / area) % out_c % multipliers.len()],
+            y_zero_point,
+        )
+    });
+    store_saturated(output, values)
+}
+
+/// Computes `(a - a_zero_point) * (b - b_zero_point)` as `numpy.matmul` does.
+/// `a_zero_point` is either per-tensor or per-row of `a`,
+/// and `b_zero_point` is either per-tensor or per-column of `b`.
+fn integer_mat_mul(
+    a: &Tensor,
+    b: &Tensor,
+    a_zero_point: &[i32],
+    b_zero_point: &[i32],
+    output_dims: &FixedDimensions,
+) -> Result<Vec<i32>, SessionError> {
+    let a_dims = a.dims();
+    let b_dims = b.dims();
+    let m = a_dims[a_dims.len() - 2];
+    let k = a_dims[a_dims.len() - 1];
+    let n = b_dims[b_dims.len() - 1];
+    if (a_zero_point.len() != 1 && a_zero_point.len() != m)
+        || (b_zero_point.len() != 1 && b_zero_point.len() != n)
+    {
+        return Err(SessionError::Message(
+            "MatMul: Zero points must be per-tensor, per-row (A) or per-column (B)".into(),
+        ));
+    }
+
+    let a = to_i32(a)?
+        .into_iter()
+        .enumerate()
+        .map(|(i, x)| x - a_zero_point[(i / k) % m % a_zero_point.len()])
+        .collect::<Vec<_>>();
+    let b = to_i32(b)?
+        .into_iter()
+        .enumerate()
+        .map(|(i, x)| x - b_zero_point[i % n % b_zero_point.len()])
+        .collect::<Vec<_>>();
+
+    let mut output = vec![0i32; output_dims.total_elems()];
+    let batch_dims = &output_dims.as_slice()[..output_dims.len() - 2];
+    let a_offsets = batch_offsets(batch_dims, &a_dims.as_slice()[..a_dims.len() - 2]);
+    let b_offsets = batch_offsets(batch_dims, &b_dims.as_slice()[..b_dims.len() - 2]);
+    for ((c, a_offset), b_offset) in output.chunks_mut(m * n).zip(a_offsets).zip(b_offsets) {
+        let a = &a[a_offset * m * k..][..m * k];
+        let b = &b[b_offset * k * n..][..k * n];
+        gemm_i32(m, k, n, a, b, c);
+    }
+
+    Ok(output)
+}
+
+/// Computes a 2D convolution of `x - x_zero_point` and `w - w_zero_point` by im2col.
+/// `w_zero_point` is either per-tensor or per output channel.
+fn integer_conv2d(
+    op: &Conv2d,
+    x: &Tensor,
+    w: &Tensor,
+    x_zero_point: i32,
+    w_zero_point: &[i32],
+    output_dims: &FixedDimensions,
+) -> Result<Vec<i32>, SessionError> {
+    let [batch_size, input_c, input_h, input_w] = x.fixed_dims::<4>();
+    let [_, output_c, output_h, output_w] = output_dims.to_fixed_dims::<4>();
+    let [_, _, kernel_h, kernel_w] = w.fixed_dims::<4>();
+    let group = op.group as usize;
+    let in_c_per_g = input_c / group;
+    let out_c_per_g = output_c / group;
+    let output_hw = output_h * output_w;
+    let [stride_h, stride_w] = op.strides.to_fixed_dims::<2>();
+    let [dilation_h, dilation_w] = op.dilations.to_fixed_dims::<2>();
+    assert!(op.padding.len() == 4);
+    let pad_t = op.padding[0];
+    let pad_l = op.padding[1];
+    if w_zero_point.len() != 1 && w_zero_point.len() != output_c {
+        return Err(SessionError::Message(
+            format!(
+                "Conv: Invalid weight zero point length {}",
+                w_zero_point.len()
+            )
+            .into(),
+        ));
+    }
+
+    let x = to_i32(x)?;
+    let kernel_size = in_c_per_g * kernel_h * kernel_w;
+    let w = to_i32(w)?
+        .into_iter()
+        .enumerate()
+        .map(|(i, w)| w - w_zero_point[i / kernel_size % w_zero_point.len()])
+        .collect::<Vec<_>>();
+
+    let mut output = vec![0i32; output_dims.total_elems()];
+    let mut col = vec![0i32; kernel_size * output_hw];
+    for (x, output) in x
+        .chunks(input_c * input_h * input_w)
+        .zip(output.chunks_mut(output_c * output_hw))
+        .take(batch_size)
+    {
+        for g in 0..group {
+            // Padded elements are zero after subtracting the zero point.
+            for (row, col) in col.chunks_mut(output_hw).enumerate() {
+                let c = g * in_c_per_g + row / (kernel_h * kernel_w);
+                let fy = (row / kernel_w) % kernel_h;
+                let fx = row % kernel_w;
+                for oy in 0..output_h {
+                    for ox in 0..output_w {
+                        let y = (oy * stride_h + fy * dilation_h) as isize - pad_t as isize;
+                        let x_ = (ox * stride_w + fx * dilation_w) as isize - pad_l as isize;
+                        col[oy * output_w + ox] = if (0..input_h as isize).contains(&y)
+                            && (0..input_w as isize).contains(&x_)
+                        {
+                            x[(c * input_h + y as usize) * input_w + x_ as usize] - x_zero_point
+                        } else {
+                            0
+                        };
+                    }
+                }
+            }
+            let w = &w[g * out_c_per_g * kernel_size..][..out_c_per_g * kernel_size];
+            let output = &mut output[g * out_c_per_g * output_hw..][..out_c_per_g * output_hw];
+            gemm_i32(out_c_per_g, kernel_size, output_hw, w, &col, output);
+        }
+    }
+
+    Ok(output)
+}
+
+/// Computes `c = a * b` where `a` is `m`x`k` and `b` is `k`x`n`.
+fn gemm_i32(m: usize, k: usize, n: usize, a: &[i32], b: &[i32], c: &mut [i32]) {
+    c.fill(0);
+    for (a, c) in a.chunks(k).zip(c.chunks_mut(n)).take(m) {
+        for (&a, b) in a.iter().zip(b.chunks(n)) {
+            if a == 0 {
+                continue;
+            }
+            for (c, &b) in c.iter_mut().zip(b) {
+                *c += a * b;
+            }
+        }
+    }
+}
+
+/// Returns the offset (in matrices) of the operand matrix used for each matrix in the output
+/// when the batch dimensions `dims` are broadcast to `out_dims`.
+pub(super) fn batch_offsets(out_dims: &[usize], dims: &[usize]) -> Vec<usize> {
+    let total = out_dims.iter().product::<usize>();
+    let lead = out_dims.len() - dims.len();
+    (0..total)
+        .map(|mut i| {
+            let mut offset = 0;
+            let mut stride = 1;
+            for (axis, &out_dim) in out_dims.iter().enumerate().rev() {
+                let index = i % out_dim;
+                i /= out_dim;
+                if axis < lead {
+                    continue;
+                }
+                let dim = dims[axis - lead];
+                if dim != 1 {
+                    offset += index * stride;
+                }
+                stride *= dim;
+            }
+            offset
+        })
+        .collect()
+}
+
+/// Returns `(inner, num_params)` such that the quantization parameter of the `i`-th element
+/// of a tensor of `dims` is at `(i / inner) % num_params`.
+fn param_layout(
+    dims: &FixedDimensions,
+    axis: i64,
+    num_params: usize,
+) -> Result<(usize, usize), SessionError> {
+    if num_params == 1 {
+        return Ok((1, 1));
+    }
+    let axis = if axis < 0 {
+        (dims.len() as i64 + axis) as usize
+    } else {
+        axis as usize
+    };
+    if dims.get(axis) != Some(&num_params) {
+        return Err(SessionError::Message(
+            format!("Quantization parameters of length {num_params} don't match {dims:?} at axis {axis}")
+                .into(),
+        ));
+    }
+    Ok((dims.as_slice()[axis + 1..].iter().product(), num_params))
+}
+
+fn requantize(acc: i32, multiplier: f32, zero_point: i32) -> i32 {
+    (acc as f32 * multiplier).round_ties_even() as i32 + zero_point
+}
+
+/// Returns the zero point at `i`, which is zero if omitted.
+fn zero_point(inputs: &[&Tensor], i: usize) -> Result<Vec<i32>, SessionError> {
+    match inputs.get(i) {
+        Some(zp) if zp.dims().total_elems() > 0 => to_i32(zp),
+        _ => Ok(vec![0]),
+    }
+}
+
+fn to_i32(t: &Tensor) -> Result<Vec<i32>, SessionError> {
+    Ok(match t.elem_ty() {
+        TensorElemType::I8 => t.data::<i8>().iter().map(|&x| x as i32).collect(),
+        TensorElemType::U8 => t.data::<u8>().iter().map(|&x| x as i32).collect(),
+        TensorElemType::I32 => t.data::<i32>().to_vec(),
+        ty => {
+            return Err(SessionError::Message(
+                format!("Expected an integer tensor but got {ty:?}").into(),
+            ))
+        }
+    })
+}
+
+/// Stores `values` into an 8-bit integer tensor, saturating out-of-range values.
+fn store_saturated(
+    output: &mut Tensor,
+    values: impl Iterator<Item = i32>,
+) -> Result<(), SessionError> {
+    match output.elem_ty() {
+        TensorElemType::I8 => {
+            for (o, v) in output.data_mut::<i8>().iter_mut().zip(values) {
+                *o = v.clamp(i8::MIN as i32, i8::MAX as i32) as i8;
+            }
+        }
+        TensorElemType::U8 => {
+            for (o, v) in output.data_mut::<u8>().iter_mut().zip(values) {
+                *o = v.clamp(u8::MIN as i32, u8::MAX as i32) as u8;
+            }
+        }
+        ty => {
+            return Err(SessionError::Message(
+                format!("Unsupported quantized type {ty:?}").into(),
+            ))
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,284 @@
+//! Kernels of the `Reduce*` ops over any set of axes, and of `ArgMax` and `ArgMin`.
+//! Axes are normalized in shape inference, so they are sorted and non-negative here.
+
+use altius_core::{
+    op::{ArgReduce, Op},
+    tensor::{Tensor, TensorElemType, TensorElemTypeExt},
+};
+use altius_session::SessionError;
+
+use super::{
+    conv2d::next_coord,
+    session::{fast_sum, fast_sum_squares},
+};
+
+/// Computes `Min`, `Max`, `Sum`, `Mean`, `Prod`, `L1` and `SumSquare` reductions of integers.
+macro_rules! integer {
+    ($op:expr, $input:expr, $axes:expr, $output:expr, $ty:ty) => {{
+        let output = $output.data_mut::<$ty>();
+        match $op {
+            Op::ReduceMin(_) => fold($input, $axes, output, <$ty>::MAX, <$ty>::min),
+            Op::ReduceMax(_) => fold($input, $axes, output, <$ty>::MIN, <$ty>::max),
+            Op::ReduceSum(_) | Op::ReduceMean(_) => {
+                fold($input, $axes, output, 0, <$ty>::wrapping_add)
+            }
+            Op::ReduceProd(_) => fold($input, $axes, output, 1, <$ty>::wrapping_mul),
+            Op::ReduceL1(_) => fold($input, $axes, output, 0, |a, x: $ty| {
+                a.wrapping_add(x.wrapping_abs())
+            }),
+            Op::ReduceSumSquare(_) => fold($input, $axes, output, 0, |a, x: $ty| {
+                a.wrapping_add(x.wrapping_mul(x))
+            }),
+            op => {
+                return Err(SessionError::Message(
+                    format!("{}: Unsupported type {:?}", op.name(), $input.elem_ty()).into(),
+                ))
+            }
+        }
+        if matches!($op, Op::ReduceMean(_)) {
+            let count = reduced_len($input, $axes).max(1) as i64;
+            for o in output {
+                *o = (*o as i64 / count) as $ty;
+            }
+        }
+    }};
+}
+
+pub fn compute(op: &Op, inputs: &[&Tensor], outputs: &mut [Tensor]) -> Result<(), SessionError> {
+    let input = inputs[Op::REDUCE_IN];
+    let output = &mut outputs[Op::REDUCE_OUT];
+    let (Op::ReduceMin(r)
+    | Op::ReduceMax(r)
+    | Op::ReduceMean(r)
+    | Op::ReduceSum(r)
+    | Op::ReduceProd(r)
+    | Op::ReduceL1(r)
+    | Op::ReduceL2(r)
+    | Op::ReduceLogSum(r)
+    | Op::ReduceLogSumExp(r)
+    | Op::ReduceSumSquare(r)) = op
+    else {
+        return Err(SessionError::Message(
+            format!("{} is not a reduction", op.name()).into(),
+        ));
+    };
+    let axes = r.axes.as_slice();
+
+    match input.elem_ty() {
+        TensorElemType::F32 => reduce_f32(op, input, axes, output.data_mut::<f32>()),
+        TensorElemType::I64 => integer!(op, input, axes, output, i64),
+        TensorElemType::I32 => integer!(op, input, axes, output, i32),
+        TensorElemType::I8 => integer!(op, input, axes, output, i8),
+        TensorElemType::U8 if matches!(op, Op::ReduceMin(_)) => {
+            fold(input, axes, output.data_mut::<u8>(), u8::MAX, u8::min)
+        }
+        TensorElemType::U8 if matches!(op, Op::ReduceMax(_)) => {
+            fold(input, axes, output.data_mut::<u8>(), u8::MIN, u8::max)
+        }
+        ty => {
+            return Err(SessionError::Message(
+                format!("{}: Unsupported type {ty:?}", op.name()).into(),
+            ))
+        }
+    }
+
+    Ok(())
+}
+
+fn reduce_f32(op: &Op, input: &Tensor, axes: &[i64], output: &mut [f32]) {
+    match op {
+        Op::ReduceMin(_) => fold(input, axes, output, f32::INFINITY, f32::min),
+        Op::ReduceMax(_) => fold(input, axes, output, f32::NEG_INFINITY, f32::max),
+        Op::ReduceSum(_) | Op::ReduceMean(_) | Op::ReduceLogSum(_) => {
+            match trailing_len(input, axes) {
+                Some(len) => rows(input, len, output, fast_sum),
+                None => fold(input, axes, output, 0., |a, x: f32| a + x),
+            }
+        }
+        Op::ReduceSumSquare(_) | Op::ReduceL2(_) => match trailing_len(input, axes) {
+            Some(len) => rows(input, len, output, fast_sum_squares),
+            None => fold(input, axes, output, 0., |a, x: f32| a + x * x),
+        },
+        Op::ReduceProd(_) => fold(input, axes, output, 1., |a, x: f32| a * x),
+        Op::ReduceL1(_) => fold(input, axes, output, 0., |a, x: f32| a + x.abs()),
+        _ => {
+            // Keeps the running maximum `m` and the sum of `exp(x - m)` not to overflow.
+            let mut acc = vec![(0., 0.); output.len()];
+            fold(
+                input,
+                axes,
+                &mut acc,
+                (f32::NEG_INFINITY, 0.),
+                |(m, s), x: f32| {
+                    if x > m {
+                        (x, s * (m - x).exp() + 1.)
+                    } else {
+                        (m, s + (x - m).exp())
+                    }
+                },
+            );
+            for (o, (m, s)) in output.iter_mut().zip(acc) {
+                *o = m + s.ln();
+            }
+        }
+    }
+
+    match op {
+        Op::ReduceMean(_) => {
+            let r_count = 1. / reduced_len(input, axes) as f32;
+            output.iter_mut().for_each(|o| *o *= r_count);
+        }
+        Op::ReduceL2(_) => output.iter_mut().for_each(|o| *o = o.sqrt()),
+        Op::ReduceLogSum(_) => output.iter_mut().for_each(|o| *o = o.ln()),
+        _ => {}
+    }
+}
+
+/// Folds every input element with `f` into the output element it's reduced to,
+/// starting from `init`.
+fn fold<T: TensorElemTypeExt, A: Copy>(
+    input: &Tensor,
+    axes: &[i64],
+    output: &mut [A],
+    init: A,
+    f: impl Fn(A, T) -> A,
+) {
+    let dims = input.dims().as_slice();
+    let reduced = |axis: usize| axes.contains(&(axis as i64));
+    // Strides of the output seen as the input with reduced axes of length 1.
+    let mut out_strides = vec![0; dims.len()];
+    let mut stride = 1;
+    for axis in (0..dims.len()).rev() {
+        if !reduced(axis) {
+            out_strides[axis] = stride;
+            stride *= dims[axis];
+        }
+    }
+
+    output.fill(init);
+    let input = input.data::<T>();
+    let Some((&row_len, outer_dims)) = dims.split_last() else {
+        output[0] = f(init, input[0]);
+        return;
+    };
+    if input.is_empty() {
+        return;
+    }
+
+    // Rows along the last axis are either reduced into an element or folded into a row.
+    let last_reduced = reduced(dims.len() - 1);
+    let mut coord = vec![0; outer_dims.len()];
+    for row in input.chunks(row_len) {
+        let o = coord
+            .iter()
+            .zip(out_strides.iter())
+            .map(|(c, s)| c * s)
+            .sum::<usize>();
+        if last_reduced {
+            output[o] = row.iter().fold(output[o], |a, &x| f(a, x));
+        } else {
+            for (a, &x) in output[o..o + row_len].iter_mut().zip(row) {
+                *a = f(*a, x);
+            }
+        }
+        next_coord(&mut coord, outer_dims);
+    }
+}
+
+/// Reduces each contiguous run of `len` input elements into an output element with `f`.
+fn rows(input: &Tensor, len: usize, output: &mut [f32], f: fn(&[f32]) -> f32) {
+    if len == 0 {
+        output.fill(0.);
+        return;
+    }
+    for (o, row) in output.iter_mut().zip(input.data::<f32>().chunks(len)) {
+        *o = f(row);
+    }
+}
+
+/// Returns the number of elements reduced into each output element if `axes` are the
+/// trailing ones, which makes them contiguous.
+fn trailing_len(input: &Tensor, axes: &[i64]) -> Option<usize> {
+    let dims = input.dims();
+    let first = dims.len().checked_sub(axes.len())?;
+    (!axes.is_empty() && axes.iter().zip(first..).all(|(&a, i)| a as usize == i))
+        .then(|| dims[first..].iter().product())
+}
+
+/// Returns the number of elements reduced into each output element.
+fn reduced_len(input: &Tensor, axes: &[i64]) -> usize {
+    axes.iter().map(|&a| input.dims()[a as usize]).product()
+}
+
+pub fn compute_arg(
+    op: &Op,
+    inputs: &[&Tensor],
+    outputs: &mut [Tensor],
+) -> Result<(), SessionError> {
+    let input = inputs[Op::ARGREDUCE_IN];
+    let output = outputs[Op::ARGREDUCE_OUT].data_mut::<i64>();
+    let (arg, max) = match op {
+        Op::ArgMax(arg) => (arg, true),
+        Op::ArgMin(arg) => (arg, false),
+        op => {
+            return Err(SessionError::Message(
+                format!("{} is not ArgMax or ArgMin", op.name()).into(),
+            ))
+        }
+    };
+    if input.dims()[arg.axis as usize] == 0 {
+        return Err(SessionError::Message(
+            format!("{}: The axis to reduce is empty", op.name()).into(),
+        ));
+    }
+
+    match input.elem_ty() {
+        TensorElemType::F32 => arg_reduce::<f32>(arg, max, input, output),
+        TensorElemType::I64 => arg_reduce::<i64>(arg, max, input, output),
+        TensorElemType::I32 => arg_reduce::<i32>(arg, max, input, output),
+        TensorElemType::I8 => arg_reduce::<i8>(arg, max, input, output),
+        TensorElemType::U8 => arg_reduce::<u8>(arg, max, input, output),
+        ty => {
+            return Err(SessionError::Message(
+                format!("{}: Unsupported type {ty:?}", op.name()).into(),
+            ))
+        }
+    }
+
+    Ok(())
+}
+
+/// Finds the index of the maximum (or minimum if not `max`) element along the axis.
+fn arg_reduce<T: TensorElemTypeExt>(
+    arg: &ArgReduce,
+    max: bool,
+    input: &Tensor,
+    output: &mut [i64],
+) {
+    let dims = input.dims();
+    let axis = arg.axis as usize;
+    let len = dims[axis];
+    let inner = dims[axis + 1..].iter().product::<usize>();
+    if inner == 0 {
+        return;
+    }
+    let better = |x: T, best: T| {
+        (if max { x > best } else { x < best }) || (arg.select_last_index && x == best)
+    };
+
+    let input = input.data::<T>();
+    for (input, output) in input.chunks(len * inner).zip(output.chunks_mut(inner)) {
+        for (j, out) in output.iter_mut().enumerate() {
+            let mut best = input[j];
+            let mut index = 0;
+            for k in 1..len {
+                let x = input[k * inner + j];
+                if better(x, best) {
+                    best = x;
+                    index = k;
+                }
+            }
+            *out = index as i64;
+        }
+    }
+}
@@ -0,0 +1,158 @@
+//! A kernel for Resize over any number of axes, interpolating along every axis with
+//! the taps computed by `altius_session::resize`.
+
+use altius_core::{
+    op::{Op, Resize},
+    tensor::{Tensor, TensorElemType, TensorElemTypeExt},
+};
+use altius_session::{
+    resize::{compute_taps, Taps},
+    SessionError,
+};
+
+use super::{conv2d::next_coord, thread::ThreadCtx};
+
+pub fn compute(
+    tctx: &ThreadCtx,
+    resize: &Resize,
+    inputs: &[&Tensor],
+    outputs: &mut [Tensor],
+) -> Result<(), SessionError> {
+    let input = inputs[Op::RESIZE_IN_X];
+    let output = &mut outputs[Op::RESIZE_OUT];
+    // Omitted optional inputs are given as empty tensors.
+    let given = |i: usize| {
+        inputs
+            .get(i)
+            .copied()
+            .filter(|t| t.dims().total_elems() > 0)
+    };
+    if input.dims().is_empty() {
+        return Err(SessionError::Message(
+            "Resize: Scalars cannot be resized".into(),
+        ));
+    }
+    let taps = compute_taps(
+        resize,
+        input.dims(),
+        output.dims(),
+        given(Op::RESIZE_IN_ROI),
+        given(Op::RESIZE_IN_SCALES),
+    )?;
+    let extrapolation = resize.extrapolation_value;
+    match input.elem_ty() {
+        TensorElemType::F32 if resize.mode != "nearest" => {
+            interpolate(tctx, &taps, input, output, extrapolation)
+        }
+        TensorElemType::F32 => nearest(&taps, input, output, extrapolation),
+        TensorElemType::I64 => nearest(&taps, input, output, extrapolation as i64),
+        TensorElemType::I32 => nearest(&taps, input, output, extrapolation as i32),
+        TensorElemType::I8 => nearest(&taps, input, output, extrapolation as i8),
+        TensorElemType::U8 => nearest(&taps, input, output, extrapolation as u8),
+        ty => {
+            return Err(SessionError::Message(
+                format!("Resize: Unsupported type {ty:?} for mode '{}'", resize.mode).into(),
+            ))
+        }
+    }
+
+    Ok(())
+}
+
+/// Interpolates rows (the elements along the last axis) of the output in parallel.
+fn interpolate(
+    tctx: &ThreadCtx,
+    taps: &[Taps],
+    input: &Tensor,
+    output: &mut Tensor,
+    extrapolation: f32,
+) {
+    let (outer_taps, last_taps) = taps.split_at(taps.len() - 1);
+    let last_taps = &last_taps[0];
+    let input_strides = input.strides();
+    let (outer_strides, _) = input_strides.split_at(taps.len() - 1);
+    let outer_dims = &output.dims()[..taps.len() - 1].to_vec();
+    let row_len = last_taps.len();
+    let num_rows = outer_dims.iter().product::<usize>();
+    let input = input.data::<f32>();
+    let output = output.data_mut::<f32>();
+    if row_len == 0 {
+        return;
+    }
+
+    let rows_per_task = (num_rows / tctx.num_threads()).max(1);
+    tctx.scope(|scope| {
+        for (task, output) in output.chunks_mut(rows_per_task * row_len).enumerate() {
+            scope.spawn(move || {
+                let mut coord = row_coord(task * rows_per_task, outer_dims);
+                for output in output.chunks_mut(row_len) {
+                    let outer = coord
+                        .iter()
+                        .zip(outer_taps)
+                        .map(|(&c, taps)| taps[c].as_deref())
+                        .collect::<Option<Vec<_>>>();
+                    match outer {
+                        Some(outer) => {
+                            for (out, tap) in output.iter_mut().zip(last_taps) {
+                                *out = tap.as_ref().map_or(extrapolation, |tap| {
+                                    weighted_sum(&outer, outer_strides, tap, input)
+                                });
+                            }
+                        }
+                        None => output.fill(extrapolation),
+                    }
+                    next_coord(&mut coord, outer_dims);
+                }
+            });
+        }
+    });
+}
+
+/// Sums up the input elements of the `outer` taps, and the `last` ones along the last axis.
+fn weighted_sum(
+    outer: &[&[(usize, f32)]],
+    strides: &[usize],
+    last: &[(usize, f32)],
+    input: &[f32],
+) -> f32 {
+    match outer.split_first() {
+        Some((taps, outer)) => taps
+            .iter()
+            .map(|&(i, w)| w * weighted_sum(outer, &strides[1..], last, &input[i * strides[0]..]))
+            .sum(),
+        None => last.iter().map(|&(i, w)| w * input[i]).sum(),
+    }
+}
+
+/// Copies the input elements of the single taps, as of the `nearest` mode.
+fn nearest<T: TensorElemTypeExt>(
+    taps: &[Taps],
+    input: &Tensor,
+    output: &mut Tensor,
+    extrapolation: T,
+) {
+    let strides = input.strides().to_vec();
+    let dims = output.dims().to_vec();
+    let input = input.data::<T>();
+    let mut coord = vec![0; dims.len()];
+    for out in output.data_mut::<T>() {
+        let offset = coord
+            .iter()
+            .zip(taps)
+            .zip(strides.iter())
+            .map(|((&c, taps), stride)| taps[c].as_ref().map(|tap| tap[0].0 * stride))
+            .sum::<Option<usize>>();
+        *out = offset.map_or(extrapolation, |offset| input[offset]);
+        next_coord(&mut coord, &dims);
+    }
+}
+
+/// Converts a row index into the coordinate of the row in `dims`.
+fn row_coord(mut row: usize, dims: &[usize]) -> Vec<usize> {
+    let mut coord = vec![0; dims.len()];
+    for (c, &dim) in coord.iter_mut().zip(dims).rev() {
+        *c = row % dim;
+        row /= dim;
+    }
+    coord
+}
@@ -0,0 +1,455 @@
+//! Kernels for recurrent ops. Gate matmuls run on `sgemm` over all the batches at once.
+
+use altius_core::{
+    op::{Op, RnnDirection, GRU, LSTM, RNN},
+    tensor::Tensor,
+};
+use altius_session::SessionError;
+
+use super::gemm::sgemm;
+
+pub fn compute_rnn(
+    op: &RNN,
+    inputs: &[&Tensor],
+    outputs: &mut [Tensor],
+) -> Result<(), SessionError> {
+    let rec = Recurrence::new("RNN", inputs, op.direction, op.layout, op.clip)?;
+    let acts = Activation::parse(&op.activations, &op.activation_alpha, &op.activation_beta)?;
+    let (batch, hidden) = (rec.batch, rec.hidden);
+
+    let mut y = rec.zeros_y();
+    let mut h_all = rec.initial_state(inputs, Op::RNN_IN_INITIAL_H);
+    for d in 0..rec.num_directions() {
+        let f = acts[d];
+        let xw = rec.project_inputs(d, 1);
+        let r_t = rec.recurrent_weights(d, 1);
+        let rb = rec.recurrent_bias(d, 1);
+        let h = &mut h_all[d * batch * hidden..(d + 1) * batch * hidden];
+        let mut gates = vec![0.; batch * hidden];
+        for s in 0..rec.seq_len {
+            rec.recur(h, &r_t, hidden, &rb, &mut gates);
+            for b in 0..batch {
+                let Some(t) = rec.time(d, s, b) else {
+                    continue;
+                };
+                let x = &xw[(t * batch + b) * hidden..][..hidden];
+                let g = &gates[b * hidden..][..hidden];
+                let h = &mut h[b * hidden..][..hidden];
+                for j in 0..hidden {
+                    h[j] = f.apply(rec.clip(x[j] + g[j]));
+                }
+                rec.y_at(&mut y, t, d, b).copy_from_slice(h);
+            }
+        }
+    }
+
+    rec.write_outputs(y, vec![h_all], outputs);
+    Ok(())
+}
+
+pub fn compute_gru(
+    op: &GRU,
+    inputs: &[&Tensor],
+    outputs: &mut [Tensor],
+) -> Result<(), SessionError> {
+    let rec = Recurrence::new("GRU", inputs, op.direction, op.layout, op.clip)?;
+    let acts = Activation::parse(&op.activations, &op.activation_alpha, &op.activation_beta)?;
+    let (batch, hidden) = (rec.batch, rec.hidden);
+    let (h1, h2, h3) = (hidden, 2 * hidden, 3 * hidden);
+
+    let mut y = rec.zeros_y();
+    let mut h_all = rec.initial_state(inputs, Op::RNN_IN_INITIAL_H);
+    for d in 0..rec.num_directions() {
+        let (f, g) = (acts[2 * d], acts[2 * d + 1]);
+        // Gates are laid out as z, r and h.
+        let xw = rec.project_inputs(d, 3);
+        let r_t = rec.recurrent_weights(d, 3);
+        let rb = rec.recurrent_bias(d, 3);
+        let h = &mut h_all[d * batch * hidden..(d + 1) * batch * hidden];
+        let mut zr = vec![0.; batch * h2];
+        let mut hh = vec![0.; batch * h1];
+        let mut rh = vec![0.; batch * h1];
+        for s in 0..rec.seq_len {
+            rec.recur(h, &r_t, h3, &rb[..h2], &mut zr);
+            for b in 0..batch {
+                let Some(t) = rec.time(d, s, b) else {
+                    continue;
+                };
+                let x = &xw[(t * batch + b) * h3..][..h2];
+                for (zr, &x) in zr[b * h2..][..h2].iter_mut().zip(x) {
+                    *zr = f.apply(rec.clip(x + *zr));
+                }
+            }
+            if op.linear_before_reset {
+                // h = g(Xh + r * (H Rh^T + Rbh) + Wbh)
+                rec.recur(h, &r_t[h2..], h3, &rb[h2..], &mut hh);
+            } else {
+                // h = g(Xh + (r * H) Rh^T + Rbh + Wbh)
+                for b in 0..batch {
+                    for j in 0..hidden {
+                        rh[b * h1 + j] = zr[b * h2 + h1 + j] * h[b * h1 + j];
+                    }
+                }
+                rec.recur(&rh, &r_t[h2..], h3, &rb[h2..], &mut hh);
+            }
+            for b in 0..batch {
+                let Some(t) = rec.time(d, s, b) else {
+                    continue;
+                };
+                let x = &xw[(t * batch + b) * h3..][..h3];
+                let (z, r) = zr[b * h2..][..h2].split_at(h1);
+                let hh = &hh[b * h1..][..h1];
+                let h = &mut h[b * h1..][..h1];
+                for j in 0..hidden {
+                    let hh = if op.linear_before_reset {
+                        r[j] * hh[j]
+                    } else {
+                        hh[j]
+                    };
+                    let ht = g.apply(rec.clip(x[h2 + j] + hh));
+                    h[j] = (1. - z[j]) * ht + z[j] * h[j];
+                }
+                rec.y_at(&mut y, t, d, b).copy_from_slice(h);
+            }
+        }
+    }
+
+    rec.write_outputs(y, vec![h_all], outputs);
+    Ok(())
+}
+
+pub fn compute_lstm(
+    op: &LSTM,
+    inputs: &[&Tensor],
+    outputs: &mut [Tensor],
+) -> Result<(), SessionError> {
+    let rec = Recurrence::new("LSTM", inputs, op.direction, op.layout, op.clip)?;
+    let acts = Activation::parse(&op.activations, &op.activation_alpha, &op.activation_beta)?;
+    let (batch, hidden) = (rec.batch, rec.hidden);
+    let h4 = 4 * hidden;
+    let peepholes = given(inputs, Op::LSTM_IN_P).map(|p| p.data::<f32>());
+
+    let mut y = rec.zeros_y();
+    let mut h_all = rec.initial_state(inputs, Op::RNN_IN_INITIAL_H);
+    let mut c_all = rec.initial_state(inputs, Op::LSTM_IN_INITIAL_C);
+    for d in 0..rec.num_directions() {
+        let (f, g, hf) = (acts[3 * d], acts[3 * d + 1], acts[3 * d + 2]);
+        // Gates are laid out as i, o, f and c, and peepholes as i, o and f.
+        let xw = rec.project_inputs(d, 4);
+        let r_t = rec.recurrent_weights(d, 4);
+        let rb = rec.recurrent_bias(d, 4);
+        let zeros = vec![0.; 3 * hidden];
+        let p = peepholes.map_or(&zeros[..], |p| &p[d * 3 * hidden..(d + 1) * 3 * hidden]);
+        let (pi, po, pf) = (&p[..hidden], &p[hidden..2 * hidden], &p[2 * hidden..]);
+        let range = d * batch * hidden..(d + 1) * batch * hidden;
+        let (h, c) = (&mut h_all[range.clone()], &mut c_all[range]);
+        let mut gates = vec![0.; batch * h4];
+        for s in 0..rec.seq_len {
+            rec.recur(h, &r_t, h4, &rb, &mut gates);
+            for b in 0..batch {
+                let Some(t) = rec.time(d, s, b) else {
+                    continue;
+                };
+                let x = &xw[(t * batch + b) * h4..][..h4];
+                let gates = &gates[b * h4..][..h4];
+                let h = &mut h[b * hidden..][..hidden];
+                let c = &mut c[b * hidden..][..hidden];
+                for j in 0..hidden {
+                    let gate = |k: usize| x[k * hidden + j] + gates[k * hidden + j];
+                    let i = f.apply(rec.clip(gate(0) + pi[j] * c[j]));
+                    let forget = if op.input_forget {
+                        1. - i
+                    } else {
+                        f.apply(rec.clip(gate(2) + pf[j] * c[j]))
+                    };
+                    let cell = g.apply(rec.clip(gate(3)));
+                    c[j] = forget * c[j] + i * cell;
+                    let o = f.apply(rec.clip(gate(1) + po[j] * c[j]));
+                    h[j] = o * hf.apply(c[j]);
+                }
+                rec.y_at(&mut y, t, d, b).copy_from_slice(h);
+            }
+        }
+    }
+
+    rec.write_outputs(y, vec![h_all, c_all], outputs);
+    Ok(())
+}
+
+/// The inputs shared by recurrent ops, laid out as `layout = 0`.
+struct Recurrence<'a> {
+    seq_len: usize,
+    batch: usize,
+    input_size: usize,
+    hidden: usize,
+    direction: RnnDirection,
+    layout: i64,
+    clip: Option<f32>,
+    x: Vec<f32>,          // [seq_len, batch, input_size]
+    w: &'a [f32],         // [num_directions, num_gates * hidden, input_size]
+    r: &'a [f32],         // [num_directions, num_gates * hidden, hidden]
+    b: Option<&'a [f32]>, // [num_directions, 2 * num_gates * hidden]
+    seq_lens: Vec<usize>, // [batch]
+}
+
+impl<'a> Recurrence<'a> {
+    fn new(
+        name: &str,
+        inputs: &[&'a Tensor],
+        direction: RnnDirection,
+        layout: i64,
+        clip: Option<f32>,
+    ) -> Result<Self, SessionError> {
+        let x = inputs[Op::RNN_IN_X];
+        if !x.elem_ty().is_f32() {
+            return Err(SessionError::Message(
+                format!("{name}: Unsupported type {:?}", x.elem_ty()).into(),
+            ));
+        }
+        let [a, b, input_size] = x.fixed_dims::<3>();
+        let (seq_len, batch) = if layout == 0 { (a, b) } else { (b, a) };
+        let hidden = inputs[Op::RNN_IN_R].dims()[2];
+        let x = if layout == 0 {
+            x.data::<f32>().to_vec()
+        } else {
+            swap_axes(x.data::<f32>(), batch, seq_len, input_size)
+        };
+        let seq_lens = given(inputs, Op::RNN_IN_SEQUENCE_LENS).map_or_else(
+            || vec![seq_len; batch],
+            |l| l.data::<i32>().iter().map(|&l| l as usize).collect(),
+        );
+        Ok(Self {
+            seq_len,
+            batch,
+            input_size,
+            hidden,
+            direction,
+            layout,
+            clip,
+            x,
+            w: inputs[Op::RNN_IN_W].data::<f32>(),
+            r: inputs[Op::RNN_IN_R].data::<f32>(),
+            b: given(inputs, Op::RNN_IN_B).map(|b| b.data::<f32>()),
+            seq_lens,
+        })
+    }
+
+    fn num_directions(&self) -> usize {
+        self.direction.num_directions()
+    }
+
+    /// Returns the state given as the `i`-th input, laid out as `[num_directions, batch, hidden]`.
+    fn initial_state(&self, inputs: &[&Tensor], i: usize) -> Vec<f32> {
+        let nd = self.num_directions();
+        match given(inputs, i) {
+            Some(state) if self.layout == 0 => state.data::<f32>().to_vec(),
+            Some(state) => swap_axes(state.data::<f32>(), self.batch, nd, self.hidden),
+            None => vec![0.; nd * self.batch * self.hidden],
+        }
+    }
+
+    /// Computes `X W^T + Wb` of direction `d` for all the time steps at once.
+    /// The result is of shape `[seq_len * batch, num_gates * hidden]`.
+    fn project_inputs(&self, d: usize, num_gates: usize) -> Vec<f32> {
+        let (gh, input_size) = (num_gates * self.hidden, self.input_size);
+        let w_t = swap_axes(
+            &self.w[d * gh * input_size..(d + 1) * gh * input_size],
+            gh,
+            input_size,
+            1,
+        );
+        let mut xw = vec![0.; self.seq_len * self.batch * gh];
+        if let Some(b) = self.b {
+            for row in xw.chunks_mut(gh) {
+                row.copy_from_slice(&b[2 * d * gh..][..gh]);
+            }
+        }
+        sgemm(
+            self.seq_len * self.batch,
+            input_size,
+            gh,
+            1.,
+            &self.x,
+            input_size,
+            &w_t,
+            gh,
+            1.,
+            &mut xw,
+            gh,
+        );
+        xw
+    }
+
+    /// Returns `R^T` of direction `d`, of shape `[hidden, num_gates * hidden]`.
+    fn recurrent_weights(&self, d: usize, num_gates: usize) -> Vec<f32> {
+        let gh = num_gates * self.hidden;
+        let r = &self.r[d * gh * self.hidden..(d + 1) * gh * self.hidden];
+        swap_axes(r, gh, self.hidden, 1)
+    }
+
+    /// Returns `Rb` of direction `d`.
+    fn recurrent_bias(&self, d: usize, num_gates: usize) -> Vec<f32> {
+        let gh = num_gates * self.hidden;
+        self.b
+            .map_or_else(|| vec![0.; gh], |b| b[(2 * d + 1) * gh..][..gh].to_vec())
+    }
+
+    /// Computes `gates = h r_t + rb` for all the batches, where `r_t` has `ldb` columns
+    /// of which the first `rb.len()` are used.
+    fn recur(&self, h: &[f32], r_t: &[f32], ldb: usize, rb: &[f32], gates: &mut [f32]) {
+        let n = rb.len();
+        for row in gates.chunks_mut(n) {
+            row.copy_from_slice(rb);
+        }
+        sgemm(
+            self.batch,
+            self.hidden,
+            n,
+            1.,
+            h,
+            self.hidden,
+            r_t,
+            ldb,
+            1.,
+            gates,
+            n,
+        );
+    }
+
+    /// Returns the time index of batch `b` at step `s` in direction `d`,
+    /// or `None` if the step is past its sequence.
+    fn time(&self, d: usize, s: usize, b: usize) -> Option<usize> {
+        let len = self.seq_lens[b];
+        if s >= len {
+            return None;
+        }
+        let reverse = self.direction == RnnDirection::Reverse
+            || (self.direction == RnnDirection::Bidirectional && d == 1);
+        Some(if reverse { len - 1 - s } else { s })
+    }
+
+    fn clip(&self, x: f32) -> f32 {
+        self.clip.map_or(x, |c| x.clamp(-c, c))
+    }
+
+    /// Returns Y of shape `[seq_len, num_directions, batch, hidden]` filled with zeros,
+    /// which are left as they are past each sequence.
+    fn zeros_y(&self) -> Vec<f32> {
+        vec![0.; self.seq_len * self.num_directions() * self.batch * self.hidden]
+    }
+
+    fn y_at<'y>(&self, y: &'y mut [f32], t: usize, d: usize, b: usize) -> &'y mut [f32] {
+        let offset = ((t * self.num_directions() + d) * self.batch + b) * self.hidden;
+        &mut y[offset..offset + self.hidden]
+    }
+
+    /// Writes Y and the final states to `outputs`, laid out as `self.layout`.
+    fn write_outputs(&self, y: Vec<f32>, states: Vec<Vec<f32>>, outputs: &mut [Tensor]) {
+        let (seq_len, nd, batch, hidden) =
+            (self.seq_len, self.num_directions(), self.batch, self.hidden);
+        outputs[Op::RNN_OUT_Y] = if self.layout == 0 {
+            Tensor::new(vec![seq_len, nd, batch, hidden].into(), y)
+        } else {
+            Tensor::new(
+                vec![batch, seq_len, nd, hidden].into(),
+                swap_axes(&y, seq_len * nd, batch, hidden),
+            )
+        };
+        for (output, state) in outputs[Op::RNN_OUT_Y_H..].iter_mut().zip(states) {
+            *output = if self.layout == 0 {
+                Tensor::new(vec![nd, batch, hidden].into(), state)
+            } else {
+                Tensor::new(
+                    vec![batch, nd, hidden].into(),
+                    swap_axes(&state, nd, batch, hidden),
+                )
+            };
+        }
+    }
+}
+
+/// An activation function of recurrent ops.
+#[derive(Debug, Clone, Copy)]
+enum Activation {
+    Relu,
+    Tanh,
+    Sigmoid,
+    Affine(f32, f32),
+    LeakyRelu(f32),
+    ThresholdedRelu(f32),
+    ScaledTanh(f32, f32),
+    HardSigmoid(f32, f32),
+    Elu(f32),
+    Softsign,
+    Softplus,
+}
+
+impl Activation {
+    /// Parses `names`. `alpha` and `beta` are consumed in order by activations that take them.
+    fn parse(names: &[String], alpha: &[f32], beta: &[f32]) -> Result<Vec<Self>, SessionError> {
+        let (mut alpha, mut beta) = (alpha.iter().copied(), beta.iter().copied());
+        names
+            .iter()
+            .map(|name| {
+                Ok(match name.to_ascii_lowercase().as_str() {
+                    "relu" => Self::Relu,
+                    "tanh" => Self::Tanh,
+                    "sigmoid" => Self::Sigmoid,
+                    "affine" => Self::Affine(alpha.next().unwrap_or(1.), beta.next().unwrap_or(0.)),
+                    "leakyrelu" => Self::LeakyRelu(alpha.next().unwrap_or(0.01)),
+                    "thresholdedrelu" => Self::ThresholdedRelu(alpha.next().unwrap_or(1.)),
+                    "scaledtanh" => {
+                        Self::ScaledTanh(alpha.next().unwrap_or(1.), beta.next().unwrap_or(1.))
+                    }
+                    "hardsigmoid" => {
+                        Self::HardSigmoid(alpha.next().unwrap_or(0.2), beta.next().unwrap_or(0.5))
+                    }
+                    "elu" => Self::Elu(alpha.next().unwrap_or(1.)),
+                    "softsign" => Self::Softsign,
+                    "softplus" => Self::Softplus,
+                    _ => {
+                        return Err(SessionError::Message(
+                            format!("Unsupported activation: {name}").into(),
+                        ))
+                    }
+                })
+            })
+            .collect()
+    }
+
+    fn apply(self, x: f32) -> f32 {
+        match self {
+            Self::Relu => x.max(0.),
+            Self::Tanh => x.tanh(),
+            Self::Sigmoid => 1. / (1. + (-x).exp()),
+            Self::Affine(alpha, beta) => alpha * x + beta,
+            Self::LeakyRelu(alpha) if x < 0. => alpha * x,
+            Self::ThresholdedRelu(alpha) if x <= alpha => 0.,
+            Self::Elu(alpha) if x < 0. => alpha * (x.exp() - 1.),
+            Self::LeakyRelu(_) | Self::ThresholdedRelu(_) | Self::Elu(_) => x,
+            Self::ScaledTanh(alpha, beta) => alpha * (beta * x).tanh(),
+            Self::HardSigmoid(alpha, beta) => (alpha * x + beta).clamp(0., 1.),
+            Self::Softsign => x / (1. + x.abs()),
+            Self::Softplus => x.exp().ln_1p(),
+        }
+    }
+}
+
+/// Omitted inputs have no elements.
+fn given<'a>(inputs: &[&'a Tensor], i: usize) -> Option<&'a Tensor> {
+    inputs
+        .get(i)
+        .copied()
+        .filter(|t| t.dims().total_elems() > 0)
+}
+
+/// Transposes `data` of shape `[a, b, inner]` into `[b, a, inner]`.
+fn swap_axes(data: &[f32], a: usize, b: usize, inner: usize) -> Vec<f32> {
+    let mut swapped = Vec::with_capacity(data.len());
+    for j in 0..b {
+        for i in 0..a {
+            swapped.extend_from_slice(&data[(i * b + j) * inner..][..inner]);
+        }
+    }
+    swapped
+}
@@ -1,35 +1,46 @@
 #[cfg(feature = "cblas")]
 use super::gemm::sgemm2;
 use super::{
+    contrib, control_flow,
     conv2d::{self, Conv2dCtx},
+    conv_transpose, elemwise,
     fast_math::{fast_gelu, fast_sigmoid},
     gemm::sgemm,
+    indexing, layout, nms, norm, pool, quantize, reduce, resize, rnn,
     thread::ThreadCtx,
+    unary,
 };
 
 use crate::fast_math::fast_sum_exp;
 use altius_core::{
+    analysis::shape::infer_shapes_from_inputs,
+    fixed_dim::FixedDimensions,
     model::Model,
     node::{Node, NodeId},
     op::{
-        BatchNormalization, Cast, Concat, Flatten, Gather, Gemm, HardSigmoid, LayerNormalization,
-        LeakyReLU, MaxPool, Op, ReduceMax, ReduceMean, Resize, Softmax, Split, Squeeze, Transpose,
-        Unsqueeze,
+        BatchNormalization, Cast, Concat, Flatten, Gemm, HardSigmoid, LayerNormalization,
+        LeakyReLU, Op, Softmax, Split, Squeeze, Transpose, Unsqueeze,
     },
-    tensor::{Tensor, TensorElemType, TypedFixedShape},
+    tensor::{Tensor, TensorElemType, TensorElemTypeExt, TypedFixedShape},
     value::ValueId,
 };
-use altius_session::{plan::NodeExecutionPlan, SessionError};
+use altius_session::{
+    plan::{create_memory_plan, MemoryPlan, NodeExecutionPlan},
+    SessionError,
+};
 #[cfg(feature = "cuda")]
 use cudnn::CudnnContext;
+use half::{bf16, f16, slice::HalfFloatSliceExt};
 use ndarray::{s, ArrayView, ArrayView3, Axis, Dim, Ix};
 use rustc_hash::FxHashMap;
 use thread_local::ThreadLocal;
 
 use std::{
+    borrow::Cow,
     cell::RefCell,
     simd::num::SimdFloat,
     simd::{Simd, StdFloat},
+    sync::{Arc, Mutex},
     time::{Duration, Instant},
 };
 
@@ -46,16 +57,63 @@ mod cuda {
 #[cfg(feature = "cuda")]
 pub(super) use cuda::*;
 
+pub(super) type InferredShapes = FxHashMap<NodeId, (Op, Vec<TypedFixedShape>)>;
+
+/// Shapes inferred for a binding of input shapes, and the places in the arena of the values
+/// of those shapes.
+pub(super) struct ShapedPlan {
+    pub(super) shapes: InferredShapes,
+    pub(super) memory: MemoryPlan,
+    /// Types and shapes of the values in `memory`.
+    value_shapes: FxHashMap<ValueId, TypedFixedShape>,
+}
+
+/// Values held by a thread running a session.
+pub(super) struct Values {
+    /// Inputs and values computed by nodes. Initializers are read from the model instead.
+    tensors: FxHashMap<ValueId, Tensor>,
+    /// The arena that values planned by `plan` are views of.
+    arena: Tensor,
+    plan: Option<Arc<ShapedPlan>>,
+    buffers: NodeBuffers,
+}
+
+/// Buffers reused by every node so that steady-state runs don't allocate.
+#[derive(Default)]
+struct NodeBuffers {
+    /// Always empty between nodes. Only its capacity is reused; see `recycle`.
+    inputs: Vec<&'static Tensor>,
+    inputs_f32: Vec<&'static Tensor>,
+    outputs: Vec<Tensor>,
+    half_in_f32: F32Scratch,
+}
+
+/// f32 copies of the half-precision inputs and outputs of the node being computed in f32.
+/// Each position of inputs and outputs has an arena as large as the largest value it has held,
+/// and the copies are views of the arenas kept per node so that steady-state runs don't
+/// allocate. Only one node uses the arenas at a time.
+#[derive(Default)]
+struct F32Scratch {
+    arenas: Vec<Tensor>,
+    views: FxHashMap<(NodeId, usize), Tensor>,
+}
+
 pub struct InterpreterSession {
     pub(super) model: Model,
     #[cfg(feature = "cuda")]
     pub(super) cudnn_ctx: SafeCudnnContext,
     pub(super) execution_plans: Vec<NodeExecutionPlan>,
-    pub(super) inferred_shapes: FxHashMap<NodeId, (Op, Vec<TypedFixedShape>)>,
+    pub(super) plan: Arc<ShapedPlan>,
+    /// Symbols of dynamic dimensions in graph inputs.
+    pub(super) dynamic_symbols: Vec<String>,
+    /// Plans memoized per sizes bound to `dynamic_symbols`.
+    pub(super) plan_cache: Mutex<FxHashMap<Vec<usize>, Arc<ShapedPlan>>>,
     pub(super) enable_profiling: bool,
-    pub(super) values: ThreadLocal<RefCell<FxHashMap<ValueId, Tensor>>>,
+    pub(super) values: ThreadLocal<RefCell<Values>>,
     pub(super) dummy_value: Tensor,
     pub(super) tctx: ThreadCtx,
+    /// Sessions running the subgraphs of control-flow nodes.
+    pub(super) subgraph_sessions: FxHashMap<NodeId, Vec<InterpreterSession>>,
     #[cfg(feature = "x64-fusion")]
     asm_ops: Assmembler,
 }
@@ -73,11 +131,19 @@ impl InterpreterSession {
             log::debug!("Number of outputs: {}", self.model.graph.outputs.len());
         }
 
+        let plan = self.resolve_plan(&inputs)?;
+        let inferred_shapes = &plan.shapes;
         let mut profile = FxHashMap::default();
         let values = &mut *self
             .values
-            .get_or(|| RefCell::new(self.model.graph.inits.clone()))
+            .get_or(|| RefCell::new(Values::new()))
             .borrow_mut();
+        values.prepare(&plan);
+        let Values {
+            tensors: values,
+            buffers,
+            ..
+        } = values;
 
         // Set inputs.
         for (id, tensor) in self.model.graph.inputs.iter().zip(inputs) {
@@ -86,18 +152,15 @@ impl InterpreterSession {
 
         #[cfg(not(feature = "heavy-log"))]
         for node in &self.execution_plans {
-            self.run_node(&mut profile, values, node.node_id)?;
-
-            for val in &node.free_vals {
-                values.get_mut(val).unwrap().set_raw_vec::<u8>(Vec::new())
-            }
+            self.run_node(inferred_shapes, &mut profile, values, buffers, node.node_id)?;
+            free_values(values, &node.free_vals);
         }
 
         #[cfg(feature = "heavy-log")]
         for (i, node) in self.execution_plans.iter().enumerate() {
             let start = Instant::now();
 
-            self.run_node(&mut profile, values, node.node_id);
+            self.run_node(inferred_shapes, &mut profile, values, buffers, node.node_id)?;
 
             log::info!(
                 "{}/{} {}({}) {:?}",
@@ -111,9 +174,7 @@ impl InterpreterSession {
                 start.elapsed()
             );
 
-            for val in &node.free_vals {
-                values.get_mut(val).unwrap().set_raw_vec::<u8>(Vec::new())
-            }
+            free_values(values, &node.free_vals);
         }
 
         #[cfg(not(target_arch = "wasm32"))]
@@ -126,175 +187,645 @@ impl InterpreterSession {
             log::info!("Profile: {:#?}", profile);
         }
 
-        Ok(self
-            .model
-            .graph
+        let graph = &self.model.graph;
+        Ok(graph
             .outputs
             .iter()
-            .map(|id| values.remove(id).unwrap())
+            .map(|id| {
+                // Keep values that must survive across runs.
+                let mut output = if let Some(init) = graph.inits.get(id) {
+                    init.clone()
+                } else if graph.outer_inputs.contains(id) {
+                    values[id].clone()
+                } else {
+                    values.remove(id).unwrap()
+                };
+                // Outer values may be in the arena of the outer graph.
+                output.detach();
+                output
+            })
             .collect())
     }
 
+    /// Runs `f` with `outer_values` bound to the outer-scope values of this session's graph,
+    /// which is a subgraph of a control-flow op.
+    pub(super) fn with_outer_values<R>(
+        &self,
+        outer_values: &[&Tensor],
+        f: impl FnOnce() -> R,
+    ) -> R {
+        let values = self.values.get_or(|| RefCell::new(Values::new()));
+        let outer_inputs = &self.model.graph.outer_inputs;
+        values.borrow_mut().tensors.extend(
+            outer_inputs
+                .iter()
+                .zip(outer_values)
+                .map(|(&id, &value)| (id, value.clone())),
+        );
+        let result = f();
+        let mut values = values.borrow_mut();
+        for id in outer_inputs {
+            values.tensors.remove(id);
+        }
+        result
+    }
+
+    /// Returns the plan for the given inputs.
+    /// If the model has dynamic inputs, shapes are inferred and memory is planned once per
+    /// distinct binding of the dynamic dimensions and memoized.
+    fn resolve_plan(&self, inputs: &[Tensor]) -> Result<Arc<ShapedPlan>, SessionError> {
+        if self.dynamic_symbols.is_empty() {
+            return Ok(self.plan.clone());
+        }
+
+        let mut bindings = FxHashMap::default();
+        let mut input_shapes = FxHashMap::default();
+        for (&id, input) in self.model.graph.inputs.iter().zip(inputs) {
+            let Some(shape) = &self.model.graph.values[id].shape else {
+                continue;
+            };
+            if shape.elem_ty != input.elem_ty()
+                || !shape.dims.bind(input.dims().as_slice(), &mut bindings)
+            {
+                return Err(SessionError::Message(
+                    format!(
+                        "Input shape mismatch: expected {:?} of {:?}, got {:?} of {:?}",
+                        shape.dims,
+                        shape.elem_ty,
+                        input.dims(),
+                        input.elem_ty()
+                    )
+                    .into(),
+                ));
+            }
+            input_shapes.insert(
+                id,
+                TypedFixedShape::new(input.dims().clone(), input.elem_ty()),
+            );
+        }
+
+        let key = self
+            .dynamic_symbols
+            .iter()
+            .map(|sym| {
+                bindings.get(sym).copied().ok_or_else(|| {
+                    SessionError::Message(format!("Dimension '{sym}' is not given").into())
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut cache = self.plan_cache.lock().unwrap();
+        if let Some(plan) = cache.get(&key) {
+            return Ok(plan.clone());
+        }
+
+        let mut shapes = FxHashMap::default();
+        infer_shapes_from_inputs(
+            &self.model,
+            &input_shapes,
+            &mut shapes,
+            &mut FxHashMap::default(),
+        )?;
+        let plan = Arc::new(ShapedPlan::new(&self.model, &self.execution_plans, shapes));
+        cache.insert(key, plan.clone());
+        Ok(plan)
+    }
+
     fn run_node(
         &self,
+        inferred_shapes: &InferredShapes,
         profile: &mut FxHashMap<&'static str, Duration>,
         values: &mut FxHashMap<ValueId, Tensor>,
+        buffers: &mut NodeBuffers,
         node_id: NodeId,
     ) -> Result<(), SessionError> {
         let node = &self.model.graph.nodes[node_id];
-        let inputs = node
-            .inputs
-            .iter()
-            .map(|input| values.get(input).unwrap_or(&self.dummy_value))
-            .collect::<Vec<_>>();
+        // Output shapes of these ops are known only after running them.
+        if node.op.has_dynamic_output_shapes() {
+            let inputs = self.node_inputs(node, values, Vec::new());
+            #[cfg(not(target_arch = "wasm32"))]
+            let start = Instant::now();
+
+            let outputs = match node.op {
+                Op::Loop(ref l) => {
+                    control_flow::compute_loop(l, &self.subgraph_sessions[&node_id][0], &inputs)?
+                }
+                Op::If(ref i) => {
+                    control_flow::compute_if(i, &self.subgraph_sessions[&node_id], &inputs)?
+                }
+                Op::NonMaxSuppression(ref nms) => nms::compute_non_max_suppression(nms, &inputs)?,
+                Op::NonZero => indexing::compute_non_zero(&inputs)?,
+                _ => unreachable!(),
+            };
+
+            #[cfg(not(target_arch = "wasm32"))]
+            if self.enable_profiling {
+                *profile.entry(node.op.name()).or_insert(Duration::ZERO) += start.elapsed();
+            }
+
+            for (&val, mut output) in node.outputs.iter().zip(outputs) {
+                // Inputs passed through must not share their regions of the arena.
+                output.detach();
+                values.insert(val, output);
+            }
+            return Ok(());
+        }
+
         // Use inferred shapes if any.
-        let (op, output_shapes) = self.inferred_shapes.get(&node_id).cloned().map_or_else(
-            || {
+        let (op, output_shapes) = match inferred_shapes.get(&node_id) {
+            Some((op, output_shapes)) => (Cow::Borrowed(op), Cow::Borrowed(&output_shapes[..])),
+            None => {
                 let mut op = node.op.clone();
-                let output_shapes =
-                    self.model
-                        .compute_output_shapes(&mut op, &inputs, node.outputs.len());
-                output_shapes.map(|os| (op, os))
+                let output_shapes = self.model.compute_output_shapes(
+                    &mut op,
+                    &self.node_inputs(node, values, Vec::new()),
+                    node.outputs.len(),
+                )?;
+                (Cow::Owned(op), Cow::Owned(output_shapes))
+            }
+        };
+        // Planned outputs are written to their regions of the arena, which they kept since
+        // the last run.
+        let mut outputs = std::mem::take(&mut buffers.outputs);
+        outputs.extend(node.outputs.iter().zip(output_shapes.iter()).map(
+            |(id, TypedFixedShape { elem_ty, dims })| match values.remove(id) {
+                Some(mut output)
+                    if output.is_view()
+                        && output.elem_ty() == *elem_ty
+                        && output.dims() == dims =>
+                {
+                    if elem_ty.is_bool() {
+                        // Any bit pattern other than 0 and 1 is an invalid `bool`.
+                        let len = output.data_as_bytes().len();
+                        unsafe { std::ptr::write_bytes(output.data_as_mut_ptr(), 0, len) }
+                    }
+                    output
+                }
+                _ => Tensor::uninit_of_type(*elem_ty, dims.clone()),
             },
-            Ok,
-        )?;
-        let mut outputs = output_shapes
-            .into_iter()
-            .map(|TypedFixedShape { elem_ty, dims }| Tensor::uninit_of_type(elem_ty, dims))
-            .collect::<Vec<_>>();
+        ));
+        let inputs = self.node_inputs(node, values, recycle(std::mem::take(&mut buffers.inputs)));
 
         #[cfg(not(target_arch = "wasm32"))]
         let start = Instant::now();
 
         // Actual kernel runs here.
-        match op {
+        if computes_half_in_f32(&op) && inputs.iter().any(|i| i.elem_ty().is_half()) {
+            self.compute_half_in_f32(node_id, &op, &inputs, &mut outputs, buffers)?
+        } else {
+            self.compute(node, &op, &inputs, &mut outputs)?
+        }
+        buffers.inputs = recycle(inputs);
+
+        #[cfg(not(target_arch = "wasm32"))]
+        if self.enable_profiling {
+            let elapsed = start.elapsed();
+            *profile.entry(op.name()).or_insert(Duration::ZERO) += elapsed;
+        }
+
+        for (&val, output) in node.outputs.iter().zip(outputs.drain(..)) {
+            values.insert(val, output);
+        }
+        buffers.outputs = outputs;
+
+        Ok(())
+    }
+
+    /// Appends the inputs of `node` to `inputs`. Initializers are read from the model.
+    fn node_inputs<'a>(
+        &'a self,
+        node: &Node,
+        values: &'a FxHashMap<ValueId, Tensor>,
+        mut inputs: Vec<&'a Tensor>,
+    ) -> Vec<&'a Tensor> {
+        inputs.extend(node.inputs.iter().map(|input| {
+            values
+                .get(input)
+                .or_else(|| self.model.graph.inits.get(input))
+                .unwrap_or(&self.dummy_value)
+        }));
+        inputs
+    }
+
+    fn compute(
+        &self,
+        node: &Node,
+        op: &Op,
+        inputs: &[&Tensor],
+        outputs: &mut [Tensor],
+    ) -> Result<(), SessionError> {
+        match *op {
             Op::Conv2d(ref conv) => conv2d::compute(&mut Conv2dCtx {
                 #[cfg(feature = "cuda")]
                 cudnn: &self.cudnn_ctx,
                 op: conv,
-                inputs: &inputs,
-                outputs: &mut outputs,
+                inputs,
+                outputs,
                 tctx: &self.tctx,
             }),
-            Op::Add => compute_add(&self.tctx, &inputs, &mut outputs),
-            Op::Sub => compute_sub(&self.tctx, &inputs, &mut outputs),
-            Op::Mul => compute_mul(&self.tctx, &inputs, &mut outputs),
-            Op::Div => compute_div(&self.tctx, &inputs, &mut outputs),
-            Op::Greater => compute_greater(node, &inputs, &mut outputs),
-            Op::Pow => compute_pow(node, &inputs, &mut outputs),
-            Op::Sqrt => compute_sqrt(node, &inputs, &mut outputs),
-            Op::MaxPool(ref maxpool) => compute_max_pool(maxpool, &inputs, &mut outputs),
-            Op::GlobalAveragePool => compute_gavg_pool(node, &inputs, &mut outputs)?,
-            Op::Expand => compute_expand(&inputs, &mut outputs)?,
-            Op::Range => todo!(),
-            Op::Reshape => compute_reshape(node, &inputs, &mut outputs),
-            Op::Flatten(ref flatten) => compute_flatten(flatten, &inputs, &mut outputs),
-            Op::MatMul => compute_mat_mul(node, &inputs, &mut outputs),
-            Op::Gemm(ref gemm) => compute_gemm(gemm, &inputs, &mut outputs),
-            Op::ReLU => compute_relu(node, &inputs, &mut outputs),
-            Op::HardSigmoid(ref hs) => compute_hard_sigmoid(hs, &inputs, &mut outputs),
-            Op::LeakyReLU(ref leaky) => compute_leaky_relu(leaky, &inputs, &mut outputs),
-            Op::Gelu => compute_gelu(&self.tctx, &inputs, &mut outputs),
-            Op::Sigmoid => compute_sigmoid(&self.tctx, &inputs, &mut outputs),
-            Op::Erf => compute_erf(&inputs, &mut outputs),
-            Op::Tanh => compute_tanh(&inputs, &mut outputs),
-            Op::Clip => return Err(SessionError::Message("Clip: Kernel not implemented".into())),
-            Op::Where => compute_where(&inputs, &mut outputs),
-            Op::Softmax(ref softmax) => compute_softmax(&self.tctx, softmax, &inputs, &mut outputs),
-            Op::Resize(ref resize) => compute_resize(&self.tctx, resize, &inputs, &mut outputs),
-            Op::Concat(ref concat) => compute_concat(concat, &inputs, &mut outputs)?,
-            Op::Transpose(ref trans) => compute_transpose(trans, &inputs, &mut outputs),
-            Op::Squeeze(ref squeeze) => compute_squeeze(squeeze, &inputs, &mut outputs),
-            Op::Unsqueeze(ref unsqueeze) => compute_unsqueeze(unsqueeze, &inputs, &mut outputs),
-            Op::ReduceMin(_) => {
-                return Err(SessionError::Message(
-                    "ReduceMin: Kernel not implemented".into(),
-                ))
-            }
-            Op::ReduceMax(ref rmax) => compute_reduce_max(rmax, &inputs, &mut outputs),
-            Op::ReduceMean(ref rmean) => compute_reduce_mean(rmean, &inputs, &mut outputs),
-            Op::Round => {
-                return Err(SessionError::Message(
-                    "Round: Kernel not implemented".into(),
-                ))
-            }
-            Op::Exp => return Err(SessionError::Message("Exp: Kernel not implemented".into())),
-            Op::Loop => compute_loop(node, &inputs, &mut outputs),
-            Op::Tile => compute_tile(node, &inputs, &mut outputs),
-            Op::Cast(ref cast) => compute_cast(cast, &inputs, &mut outputs),
+            Op::ConvTranspose(ref conv) => conv_transpose::compute(conv, inputs, outputs)?,
+            Op::Add => elemwise::compute_add(&self.tctx, inputs, outputs)?,
+            Op::Sub => elemwise::compute_sub(&self.tctx, inputs, outputs)?,
+            Op::Mul => elemwise::compute_mul(&self.tctx, inputs, outputs)?,
+            Op::Div => elemwise::compute_div(&self.tctx, inputs, outputs)?,
+            Op::Pow => elemwise::compute_pow(&self.tctx, inputs, outputs)?,
+            Op::Equal => elemwise::compute_equal(&self.tctx, inputs, outputs)?,
+            Op::Less => elemwise::compute_less(&self.tctx, inputs, outputs)?,
+            Op::LessOrEqual => elemwise::compute_less_or_equal(&self.tctx, inputs, outputs)?,
+            Op::Greater => elemwise::compute_greater(&self.tctx, inputs, outputs)?,
+            Op::GreaterOrEqual => elemwise::compute_greater_or_equal(&self.tctx, inputs, outputs)?,
+            Op::And => elemwise::compute_and(&self.tctx, inputs, outputs)?,
+            Op::Or => elemwise::compute_or(&self.tctx, inputs, outputs)?,
+            Op::Xor => elemwise::compute_xor(&self.tctx, inputs, outputs)?,
+            Op::Not => elemwise::compute_not(inputs, outputs)?,
+            Op::Sqrt => compute_sqrt(node, inputs, outputs),
+            Op::MaxPool(ref pool) => pool::compute_max_pool(pool, inputs, outputs)?,
+            Op::AveragePool(ref pool) => pool::compute_average_pool(pool, inputs, outputs)?,
+            Op::LpPool(ref pool) => pool::compute_lp_pool(pool, inputs, outputs)?,
+            Op::GlobalAveragePool => pool::compute_global_average_pool(inputs, outputs)?,
+            Op::GlobalMaxPool => pool::compute_global_max_pool(inputs, outputs)?,
+            Op::Expand => compute_expand(inputs, outputs)?,
+            Op::Range => compute_range(inputs, outputs)?,
+            Op::Reshape => compute_reshape(node, inputs, outputs),
+            Op::Flatten(ref flatten) => compute_flatten(flatten, inputs, outputs),
+            Op::MatMul => compute_mat_mul(node, inputs, outputs),
+            Op::Gemm(ref gemm) => compute_gemm(gemm, inputs, outputs),
+            Op::ReLU => compute_relu(node, inputs, outputs),
+            Op::HardSigmoid(ref hs) => compute_hard_sigmoid(hs, inputs, outputs),
+            Op::LeakyReLU(ref leaky) => compute_leaky_relu(leaky, inputs, outputs),
+            Op::Gelu => compute_gelu(&self.tctx, inputs, outputs),
+            Op::Sigmoid => compute_sigmoid(&self.tctx, inputs, outputs),
+            Op::Erf => compute_erf(inputs, outputs),
+            Op::Tanh => compute_tanh(inputs, outputs),
+            Op::Abs
+            | Op::Neg
+            | Op::Log
+            | Op::Reciprocal
+            | Op::Floor
+            | Op::Ceil
+            | Op::Sin
+            | Op::Cos
+            | Op::Softplus
+            | Op::Softsign
+            | Op::Elu(_)
+            | Op::Selu(_)
+            | Op::Mish
+            | Op::HardSwish
+            | Op::Sign => unary::compute(&self.tctx, op, inputs, outputs)?,
+            Op::PRelu => elemwise::compute_prelu(&self.tctx, inputs, outputs)?,
+            Op::Clip => compute_clip(inputs, outputs)?,
+            Op::Where => elemwise::compute_where(inputs, outputs)?,
+            Op::Softmax(ref softmax) => compute_softmax(&self.tctx, softmax, inputs, outputs),
+            Op::Resize(ref resize) => resize::compute(&self.tctx, resize, inputs, outputs)?,
+            Op::Concat(ref concat) => compute_concat(concat, inputs, outputs)?,
+            Op::Transpose(ref trans) => compute_transpose(trans, inputs, outputs),
+            Op::Squeeze(ref squeeze) => compute_squeeze(squeeze, inputs, outputs),
+            Op::Unsqueeze(ref unsqueeze) => compute_unsqueeze(unsqueeze, inputs, outputs),
+            Op::ReduceMin(_)
+            | Op::ReduceMax(_)
+            | Op::ReduceMean(_)
+            | Op::ReduceSum(_)
+            | Op::ReduceProd(_)
+            | Op::ReduceL1(_)
+            | Op::ReduceL2(_)
+            | Op::ReduceLogSum(_)
+            | Op::ReduceLogSumExp(_)
+            | Op::ReduceSumSquare(_) => reduce::compute(op, inputs, outputs)?,
+            Op::ArgMax(_) | Op::ArgMin(_) => reduce::compute_arg(op, inputs, outputs)?,
+            Op::Round => compute_round(inputs, outputs),
+            Op::Exp => compute_exp(inputs, outputs),
+            Op::Tile => compute_tile(inputs, outputs),
+            Op::Cast(ref cast) => compute_cast(cast, inputs, outputs),
             Op::BatchNormalization(ref batchnorm) => {
-                compute_batch_normalization(batchnorm, &inputs, &mut outputs)
+                compute_batch_normalization(batchnorm, inputs, outputs)
             }
             Op::LayerNormalization(ref ln) => {
-                compute_layer_normalization(&self.tctx, ln, &inputs, &mut outputs)
+                compute_layer_normalization(&self.tctx, ln, inputs, outputs)
             }
-            Op::Split(ref split) => {
-                compute_split(self.model.opset_version, split, &inputs, &mut outputs)
+            Op::InstanceNormalization(_) | Op::GroupNormalization(_) => {
+                norm::compute(&self.tctx, op, inputs, outputs)?
             }
-            Op::Slice => compute_slice(node, &inputs, &mut outputs),
-            Op::Gather(ref gather) => compute_gather(gather, &inputs, &mut outputs),
-            Op::Shape(_) => compute_shape(&inputs, &mut outputs),
-            Op::NonMaxSuppression => {
-                return Err(SessionError::Message(
-                    "NonMaxSuppression: Kernel not implemented".into(),
-                ))
+            Op::LSTM(ref lstm) => rnn::compute_lstm(lstm, inputs, outputs)?,
+            Op::GRU(ref gru) => rnn::compute_gru(gru, inputs, outputs)?,
+            Op::RNN(ref rnn) => rnn::compute_rnn(rnn, inputs, outputs)?,
+            Op::Split(ref split) => compute_split(self.model.opset_version, split, inputs, outputs),
+            Op::Slice => compute_slice(node, inputs, outputs),
+            Op::Pad(ref pad) => layout::compute_pad(pad, inputs, outputs)?,
+            Op::DepthToSpace(ref d2s) => layout::compute_depth_to_space(d2s, inputs, outputs)?,
+            Op::SpaceToDepth(ref s2d) => layout::compute_space_to_depth(s2d, inputs, outputs)?,
+            Op::Gather(ref gather) => indexing::compute_gather(gather, inputs, outputs)?,
+            Op::GatherElements(ref gather) => {
+                indexing::compute_gather_elements(gather, inputs, outputs)?
             }
-            Op::Constant(_) => {
-                return Err(SessionError::Message(
-                    "Constant: Kernel not implemented".into(),
-                ))
+            Op::GatherND(ref gather) => indexing::compute_gather_nd(gather, inputs, outputs)?,
+            Op::ScatterElements(_) | Op::ScatterND(_) => {
+                indexing::compute_scatter(op, inputs, outputs)?
+            }
+            Op::TopK(ref topk) => indexing::compute_top_k(topk, inputs, outputs)?,
+            Op::OneHot(ref one_hot) => indexing::compute_one_hot(one_hot, inputs, outputs)?,
+            Op::CumSum(ref cumsum) => indexing::compute_cum_sum(cumsum, inputs, outputs)?,
+            Op::Shape(_) => compute_shape(inputs, outputs),
+            Op::Constant(ref constant) => outputs[0].copy_data_from(&constant.value),
+            Op::QuantizeLinear(ref quantize) => {
+                quantize::compute_quantize_linear(quantize, inputs, outputs)?
             }
-            Op::FusedElemwise(_) => {
-                unreachable!()
+            Op::DequantizeLinear(ref dequantize) => {
+                quantize::compute_dequantize_linear(dequantize, inputs, outputs)?
             }
+            Op::QLinearConv(ref conv) => quantize::compute_qlinear_conv(conv, inputs, outputs)?,
+            Op::QLinearMatMul => quantize::compute_qlinear_mat_mul(inputs, outputs)?,
+            Op::MatMulInteger => quantize::compute_mat_mul_integer(inputs, outputs)?,
+            Op::ConvInteger(ref conv) => quantize::compute_conv_integer(conv, inputs, outputs)?,
+            Op::FusedMatMul(ref matmul) => contrib::compute_fused_mat_mul(matmul, inputs, outputs)?,
+            Op::Attention(ref attention) => contrib::compute_attention(attention, inputs, outputs)?,
+            Op::Custom(ref custom) => {
+                let Some(custom_op) = self.model.custom_ops.get(custom) else {
+                    return Err(SessionError::Message(
+                        format!(
+                            "Custom: No op is registered for {}.{}",
+                            custom.domain, custom.op_type
+                        )
+                        .into(),
+                    ));
+                };
+                custom_op.compute(custom, inputs, outputs)?
+            }
+            Op::Loop(_)
+            | Op::If(_)
+            | Op::NonMaxSuppression(_)
+            | Op::NonZero
+            | Op::FusedElemwise(_) => unreachable!(),
         }
 
-        #[cfg(not(target_arch = "wasm32"))]
-        if self.enable_profiling {
-            let elapsed = start.elapsed();
-            *profile.entry(op.name()).or_insert(Duration::ZERO) += elapsed;
+        Ok(())
+    }
+
+    /// Runs the f32 kernel of `op` on half-precision tensors.
+    /// Inputs are widened to f32 and outputs are rounded back to their half-precision types.
+    fn compute_half_in_f32(
+        &self,
+        node_id: NodeId,
+        op: &Op,
+        inputs: &[&Tensor],
+        outputs: &mut [Tensor],
+        buffers: &mut NodeBuffers,
+    ) -> Result<(), SessionError> {
+        let node = &self.model.graph.nodes[node_id];
+        let scratch = &mut buffers.half_in_f32;
+        for (i, &input) in inputs.iter().enumerate() {
+            if input.elem_ty().is_half() {
+                widen(input, scratch.get(node_id, i, input.dims()));
+            }
+        }
+        // Half-precision outputs are swapped with f32 copies while the kernel runs.
+        for (i, output) in outputs.iter_mut().enumerate() {
+            if output.elem_ty().is_half() {
+                std::mem::swap(
+                    output,
+                    scratch.get(node_id, inputs.len() + i, output.dims()),
+                );
+            }
         }
 
-        for (&val, output) in node.outputs.iter().zip(outputs.into_iter()) {
-            values.insert(val, output);
+        let mut inputs_f32 = recycle(std::mem::take(&mut buffers.inputs_f32));
+        inputs_f32.extend(inputs.iter().enumerate().map(|(i, &t)| {
+            if t.elem_ty().is_half() {
+                &scratch.views[&(node_id, i)]
+            } else {
+                t
+            }
+        }));
+        let result = self.compute(node, op, &inputs_f32, outputs);
+        buffers.inputs_f32 = recycle(inputs_f32);
+
+        for (i, output) in outputs.iter_mut().enumerate() {
+            let key = (node_id, inputs.len() + i);
+            if let Some(half) = scratch
+                .views
+                .get_mut(&key)
+                .filter(|t| t.elem_ty().is_half())
+            {
+                narrow(output, half);
+                std::mem::swap(output, half);
+            }
         }
 
-        Ok(())
+        result
     }
 }
 
-fn compute_gavg_pool(
-    _node: &Node,
-    inputs: &[&Tensor],
-    outputs: &mut [Tensor],
-) -> Result<(), SessionError> {
-    let input = inputs[Op::GLOBALAVERAGEPOOL_IN];
-    let output = &mut outputs[Op::GLOBALAVERAGEPOOL_OUT];
+impl F32Scratch {
+    /// Returns the f32 copy of shape `dims` at the `pos`-th position of inputs and outputs
+    /// of `node_id`.
+    fn get(&mut self, node_id: NodeId, pos: usize, dims: &FixedDimensions) -> &mut Tensor {
+        if self.arenas.len() <= pos {
+            self.arenas
+                .resize_with(pos + 1, || Tensor::zeros::<i64>(vec![0].into()));
+        }
+        let size = dims.total_elems() * std::mem::size_of::<f32>();
+        if self.arenas[pos].data_as_bytes().len() < size {
+            self.arenas[pos] = Tensor::uninit::<i64>(vec![size.div_ceil(8)].into());
+            // Views of the old arena would keep it alive.
+            self.views.retain(|&(_, p), _| p != pos);
+        }
+        let arena = &self.arenas[pos];
+        let view = self
+            .views
+            .entry((node_id, pos))
+            // SAFETY: Views of an arena are used only by the node being computed, one per arena.
+            .or_insert_with(|| unsafe { arena.view(0, TensorElemType::F32, dims.clone()) });
+        // Kernels may replace their outputs with tensors outside the arena.
+        if !view.is_view() || view.dims() != dims {
+            // SAFETY: Same as above.
+            *view = unsafe { arena.view(0, TensorElemType::F32, dims.clone()) };
+        }
+        view
+    }
+}
 
-    assert!(input.dims().len() == 4);
-    assert!(output.dims().len() == 4);
+/// Returns the vector emptied, to be reused for references of another lifetime.
+fn recycle<'a>(mut refs: Vec<&Tensor>) -> Vec<&'a Tensor> {
+    refs.clear();
+    let mut refs = std::mem::ManuallyDrop::new(refs);
+    // SAFETY: The vector is empty, and references of any lifetime have the same layout.
+    unsafe { Vec::from_raw_parts(refs.as_mut_ptr().cast(), 0, refs.capacity()) }
+}
 
-    let Some(&[_, _, h, w]) = input.dims().get(0..4) else {
-        return Err(SessionError::Message(
-            "Input must be four dimensions".into(),
-        ));
-    };
-    let Some([isn, isc, _, _]) = input.strides().get(0..4) else {
-        panic!()
-    };
-    let area = h * w;
-    let osn = output.strides()[0];
-    let input = input.data::<f32>();
+/// Writes the elements of the half-precision tensor `half` to `output`.
+fn widen(half: &Tensor, output: &mut Tensor) {
     let output = output.data_mut::<f32>();
+    match half.elem_ty() {
+        TensorElemType::F16 => half.data::<f16>().convert_to_f32_slice(output),
+        TensorElemType::BF16 => half.data::<bf16>().convert_to_f32_slice(output),
+        _ => unreachable!(),
+    }
+}
 
-    for (o_channels, i_channels) in output.chunks_mut(osn).zip(input.chunks(*isn)) {
-        for (o_channel, i_channel) in o_channels.iter_mut().zip(i_channels.chunks(*isc)) {
-            let sum: f32 = i_channel.iter().sum();
-            *o_channel = sum / area as f32;
+/// Writes the elements of `input` to the half-precision tensor `output`, rounding them.
+fn narrow(input: &Tensor, output: &mut Tensor) {
+    let input = input.data::<f32>();
+    match output.elem_ty() {
+        TensorElemType::F16 => output.data_mut::<f16>().convert_from_f32_slice(input),
+        TensorElemType::BF16 => output.data_mut::<bf16>().convert_from_f32_slice(input),
+        _ => unreachable!(),
+    }
+}
+
+impl ShapedPlan {
+    pub(super) fn new(
+        model: &Model,
+        execution_plans: &[NodeExecutionPlan],
+        shapes: InferredShapes,
+    ) -> Self {
+        let mut value_shapes = FxHashMap::default();
+        for (node_id, (_, output_shapes)) in &shapes {
+            let node = &model.graph.nodes[*node_id];
+            // Kernels of these ops create their outputs.
+            if node.op.has_dynamic_output_shapes() {
+                continue;
+            }
+            value_shapes.extend(
+                node.outputs
+                    .iter()
+                    .copied()
+                    .zip(output_shapes.iter().cloned()),
+            );
+        }
+        let memory = create_memory_plan(model, execution_plans, |id| {
+            value_shapes
+                .get(&id)
+                .map(|shape| shape.dims.total_elems() * shape.elem_ty.size())
+        });
+        value_shapes.retain(|id, _| memory.offsets.contains_key(id));
+        Self {
+            shapes,
+            memory,
+            value_shapes,
         }
     }
+}
 
-    Ok(())
+impl Values {
+    fn new() -> Self {
+        Self {
+            tensors: FxHashMap::default(),
+            arena: Tensor::zeros::<i64>(vec![0].into()),
+            plan: None,
+            buffers: NodeBuffers::default(),
+        }
+    }
+
+    /// Places the values planned by `plan` in the arena unless they already are.
+    fn prepare(&mut self, plan: &Arc<ShapedPlan>) {
+        if self.plan.as_ref().is_some_and(|p| Arc::ptr_eq(p, plan)) {
+            return;
+        }
+        // Views placed by another plan may overlap with the new ones.
+        self.tensors.retain(|_, tensor| !tensor.is_view());
+        if self.arena.data_as_bytes().len() < plan.memory.arena_size {
+            // Elements of `i64` keep views of any type aligned.
+            self.arena = Tensor::uninit::<i64>(vec![plan.memory.arena_size.div_ceil(8)].into());
+        }
+        for (&id, &offset) in &plan.memory.offsets {
+            let TypedFixedShape { elem_ty, dims } = &plan.value_shapes[&id];
+            // SAFETY: The plan places values alive at the same time in disjoint regions.
+            let view = unsafe { self.arena.view(offset, *elem_ty, dims.clone()) };
+            self.tensors.insert(id, view);
+        }
+        self.plan = Some(plan.clone());
+    }
+}
+
+/// Frees values no longer used. Values in the arena are kept for the next run.
+fn free_values(values: &mut FxHashMap<ValueId, Tensor>, ids: &[ValueId]) {
+    for id in ids {
+        if !values[id].is_view() {
+            values.remove(id);
+        }
+    }
+}
+
+/// Returns true if `op` supports half-precision tensors by computing in f32.
+fn computes_half_in_f32(op: &Op) -> bool {
+    matches!(
+        op,
+        Op::Conv2d(_)
+            | Op::ConvTranspose(_)
+            | Op::Add
+            | Op::Sub
+            | Op::Mul
+            | Op::Div
+            | Op::Pow
+            | Op::Equal
+            | Op::Less
+            | Op::LessOrEqual
+            | Op::Greater
+            | Op::GreaterOrEqual
+            | Op::Sqrt
+            | Op::ReLU
+            | Op::LeakyReLU(_)
+            | Op::HardSigmoid(_)
+            | Op::Gelu
+            | Op::Sigmoid
+            | Op::Erf
+            | Op::Tanh
+            | Op::Abs
+            | Op::Neg
+            | Op::Log
+            | Op::Reciprocal
+            | Op::Floor
+            | Op::Ceil
+            | Op::Sin
+            | Op::Cos
+            | Op::Softplus
+            | Op::Softsign
+            | Op::Elu(_)
+            | Op::Selu(_)
+            | Op::Mish
+            | Op::HardSwish
+            | Op::PRelu
+            | Op::Sign
+            | Op::Clip
+            | Op::Round
+            | Op::Exp
+            | Op::ReduceMin(_)
+            | Op::Where
+            | Op::MatMul
+            | Op::Gemm(_)
+            | Op::Softmax(_)
+            | Op::BatchNormalization(_)
+            | Op::LayerNormalization(_)
+            | Op::InstanceNormalization(_)
+            | Op::GroupNormalization(_)
+            | Op::LSTM(_)
+            | Op::GRU(_)
+            | Op::RNN(_)
+            | Op::GlobalAveragePool
+            | Op::GlobalMaxPool
+            | Op::MaxPool(_)
+            | Op::AveragePool(_)
+            | Op::LpPool(_)
+            | Op::ReduceMax(_)
+            | Op::ReduceMean(_)
+            | Op::ReduceSum(_)
+            | Op::ReduceProd(_)
+            | Op::ReduceL1(_)
+            | Op::ReduceL2(_)
+            | Op::ReduceLogSum(_)
+            | Op::ReduceLogSumExp(_)
+            | Op::ReduceSumSquare(_)
+            | Op::ArgMax(_)
+            | Op::ArgMin(_)
+            | Op::ScatterElements(_)
+            | Op::ScatterND(_)
+            | Op::TopK(_)
+            | Op::CumSum(_)
+            | Op::Resize(_)
+            | Op::QuantizeLinear(_)
+            | Op::DequantizeLinear(_)
+            | Op::FusedMatMul(_)
+            | Op::Attention(_)
+    )
 }
 
 fn compute_expand(inputs: &[&Tensor], outputs: &mut [Tensor]) -> Result<(), SessionError> {
@@ -323,280 +854,6 @@ fn compute_expand(inputs: &[&Tensor], outputs: &mut [Tensor]) -> Result<(), Sess
     Ok(())
 }
 
-fn compute_max_pool(maxpool: &MaxPool, inputs: &[&Tensor], outputs: &mut [Tensor]) {
-    let input = inputs[Op::MAXPOOL_IN];
-    let output = &mut outputs[Op::MAXPOOL_OUT];
-
-    let kernel = &maxpool.kernel_shape;
-    let stride = &maxpool.strides;
-
-    assert!(input.dims().len() == 4);
-    assert!(output.dims().len() == 4);
-
-    let padding = &maxpool.padding;
-    let batches = output.dims()[0];
-    let channels = output.dims()[1];
-    let outer = batches * channels;
-    let output_h = output.dims()[2];
-    let output_w = output.dims()[3];
-    let input_h = input.dims()[2];
-    let input_w = input.dims()[3];
-    let kernel_h = kernel[0];
-    let kernel_w = kernel[1];
-    let stride_h = stride[0];
-    let stride_w = stride[1];
-    let input_hw = input_h * input_w;
-    let output_hw = output_h * output_w;
-
-    let mut input = input.data::<f32>();
-    let mut output = output.data_mut::<f32>();
-
-    let pad_t = padding[0] as isize;
-    let pad_l = padding[1] as isize;
-
-    for _ in 0..outer {
-        let mut y = -pad_t;
-        for ay in 0..output_h {
-            let mut x = -pad_l;
-            let output = &mut output[ay * output_w..];
-            let fy_min = (-y).max(0) as usize;
-            let fy_max = kernel_h.min((input_h as isize - y) as usize);
-            for out in output.iter_mut().take(output_w) {
-                let mut max = f32::MIN;
-                let fx_min = (-x).max(0) as usize;
-                let fx_max = kernel_w.min((input_w as isize - x) as usize);
-                for fy in fy_min..fy_max {
-                    let oy = y + fy as isize;
-                    for fx in fx_min..fx_max {
-                        let ox = x + fx as isize;
-                        max = input[oy as usize * input_w + ox as usize].max(max);
-                    }
-                }
-                *out = if max == f32::MIN { 0.0 } else { max };
-                x += stride_w as isize
-            }
-            y += stride_h as isize
-        }
-        input = &input[input_hw..];
-        output = &mut output[output_hw..];
-    }
-}
-
-macro_rules! op_bin_elemwise {
-    ($name:ident, $op:tt) => { paste::item! {
-        fn $name(tctx: &ThreadCtx, inputs: &[&Tensor], outputs: &mut [Tensor]) {
-            let input_a = inputs[0];
-            let input_b = inputs[1];
-            let output = &mut outputs[0];
-
-            let adims = input_a.dims();
-            let bdims = input_b.dims();
-
-            if adims == bdims {
-                let input_a = input_a.data::<f32>();
-                let input_b = input_b.data::<f32>();
-                let output = output.data_mut::<f32>();
-                let chunk = 100000;
-
-                tctx.scope(|scope| {
-                    input_a
-                        .chunks(chunk)
-                        .zip(input_b.chunks(chunk))
-                        .zip(output.chunks_mut(chunk))
-                        .for_each(|((input_a, input_b), output)| {
-                            scope.spawn(move || {
-                                for ((a, b), o) in
-                                    input_a.iter().zip(input_b.iter()).zip(output.iter_mut())
-                                {
-                                    // Auto-vectorized by LLVM
-                                    *o = a $op b;
-                                }
-                            });
-                        });
-                });
-
-                return;
-            }
-
-            if bdims.is_scalar() {
-                let b = input_b.data::<f32>()[0];
-                let output = output.data_mut::<f32>();
-
-                for (a, o) in input_a.data::<f32>().iter().zip(output.iter_mut()) {
-                    *o = a $op b;
-                }
-
-                return;
-            }
-
-            if adims.is_scalar() {
-                let a = input_a.data::<f32>()[0];
-                let output = output.data_mut::<f32>();
-
-                for (b, o) in input_b.data::<f32>().iter().zip(output.iter_mut()) {
-                    *o = a $op b;
-                }
-
-                return;
-            }
-
-            [< $name _general >](tctx, input_a, input_b, output);
-        }
-
-        fn [< $name _general >](_tctx: &ThreadCtx, input_a: &Tensor, input_b: &Tensor, output: &mut Tensor) {
-            fn compute(a_stride: &[usize],
-                    b_stride: &[usize],
-                    o_stride: &[usize],
-                    o_shape: &[usize],
-                    mut a: &[f32],
-                    mut b: &[f32],
-                    o: &mut [f32]) {
-                if a_stride.len() == 1 {
-                    let len = o_shape[0];
-                    let a_stride = a_stride[0];
-                    let b_stride = b_stride[0];
-
-                    if a_stride == 1 && b_stride == 1 {
-                        for (o, (a, b)) in o[..len].iter_mut().zip(a[..len].iter().zip(b[..len].iter())) {
-                            *o = a $op b;
-                        }
-                        return;
-                    }
-
-                    if a_stride == 0 && b_stride == 0 {
-                        let a = a[0];
-                        let b = b[0];
-                        for o in &mut o[..len] {
-                            *o = a $op b;
-                        }
-                        return;
-                    }
-
-                    if a_stride == 1 && b_stride == 0 {
-                        let b = b[0];
-                        for (o, a) in o[..len].iter_mut().zip(a[..len].iter()) {
-                            *o = a $op b;
-                        }
-                        return;
-                    }
-
-                    if a_stride == 0 && b_stride == 1 {
-                        let a = a[0];
-                        for (o, b) in o[..len].iter_mut().zip(b[..len].iter()) {
-                            *o = a $op b;
-                        }
-                        return;
-                    }
-
-                    for o in &mut o[..len] {
-                        *o = a[0] $op b[0];
-                        a = &a[a_stride..];
-                        b = &b[b_stride..];
-                    }
-                    return;
-                }
-
-                for i in 0..o_shape[0] {
-                    compute(&a_stride[1..],
-                            &b_stride[1..],
-                            &o_stride[1..],
-                            &o_shape[1..],
-                            &a[i * a_stride[0]..],
-                            &b[i * b_stride[0]..],
-                            &mut o[i * o_stride[0]..]);
-                }
-            }
-
-            let o_shape = output.dims().clone();
-            let o_stride = output.strides().to_vec();
-            let a_stride = input_a
-                .strides_for_broadcasting(&o_shape)
-                .unwrap();
-            let b_stride  = input_b
-                .strides_for_broadcasting(&o_shape)
-                .unwrap();
-            let input_a = input_a.data::<f32>();
-            let input_b = input_b.data::<f32>();
-            let output = output.data_mut::<f32>();
-
-            assert!(o_shape.len() > 1);
-
-            compute(&a_stride, &b_stride, &o_stride, &o_shape, &input_a, &input_b, output);
-        }
-    }};
-}
-
-op_bin_elemwise!(compute_add, +);
-op_bin_elemwise!(compute_sub, -);
-op_bin_elemwise!(compute_mul, *);
-op_bin_elemwise!(compute_div, /);
-
-fn compute_greater(_node: &Node, inputs: &[&Tensor], outputs: &mut [Tensor]) {
-    let input_0 = inputs[0];
-    let input_1 = inputs[1];
-    let output = &mut outputs[0];
-
-    if input_1.dims().is_scalar() {
-        let y = input_1.data::<f32>()[0];
-        for (&x, o) in input_0
-            .data::<f32>()
-            .iter()
-            .zip(output.data_mut::<bool>().iter_mut())
-        {
-            *o = x > y;
-        }
-        return;
-    }
-
-    todo!(
-        "A shape: {:?}, B shape: {:?}",
-        input_0.dims(),
-        input_1.dims()
-    )
-}
-
-fn compute_pow(_node: &Node, inputs: &[&Tensor], outputs: &mut [Tensor]) {
-    let input_a = inputs[0];
-    let input_b = inputs[1];
-    let output = outputs[0].data_mut();
-
-    if input_a.dims() == input_b.dims() {
-        for ((a, b), o) in input_a
-            .data::<f32>()
-            .iter()
-            .zip(input_b.data::<f32>().iter())
-            .zip(output.iter_mut())
-        {
-            *o = a.powf(*b);
-        }
-        return;
-    }
-
-    // TODO: We need multidirectional broadcast!
-
-    if input_b.dims().is_scalar() {
-        let b = input_b.data::<f32>()[0];
-
-        if b == 2. {
-            for (a, o) in input_a.data::<f32>().iter().zip(output.iter_mut()) {
-                *o = a * a;
-            }
-        } else {
-            for (a, o) in input_a.data::<f32>().iter().zip(output.iter_mut()) {
-                *o = a.powf(b);
-            }
-        }
-
-        return;
-    }
-
-    todo!(
-        "A shape: {:?}, B shape: {:?}",
-        input_a.dims(),
-        input_b.dims()
-    )
-}
-
 fn compute_sqrt(_node: &Node, inputs: &[&Tensor], outputs: &mut [Tensor]) {
     let input = inputs[0];
     let output = &mut outputs[0];
@@ -929,56 +1186,6 @@ fn compute_tanh(inputs: &[&Tensor], outputs: &mut [Tensor]) {
     tanh(output);
 }
 
-fn compute_where(inputs: &[&Tensor], outputs: &mut [Tensor]) {
-    let condition = inputs[0];
-    let x = inputs[1];
-    let y = inputs[2];
-    let output = &mut outputs[0];
-
-    // TODO: We should do better handling of broadcasting.
-    if condition.dims() == y.dims() && x.dims().is_scalar() {
-        let output = output.data_mut::<f32>();
-        let x = x.data::<f32>()[0];
-        let y = y.data::<f32>();
-        let condition = condition.data::<bool>();
-        for (&c, (&y, o)) in condition.iter().zip(y.iter().zip(output.iter_mut())) {
-            *o = if c { x } else { y }
-        }
-        return;
-    }
-
-    assert!(y.dims().is_scalar());
-    assert!(
-        x.dims().len() == 4
-            && condition.dims().len() == 4
-            && condition.dims()[0] == 1
-            && condition.dims()[1] == 1
-    );
-
-    let out0 = output.dims()[0];
-    let out1 = output.dims()[1];
-    let out2 = output.dims()[2];
-    let out3 = output.dims()[3];
-    let mut x = x.data::<f32>();
-    let y = y.data::<f32>()[0];
-    let condition = condition.data::<bool>();
-    let mut output = output.data_mut::<f32>();
-
-    for _o0 in 0..out0 {
-        for _o1 in 0..out1 {
-            for ((&c, &x), o) in condition
-                .iter()
-                .zip(x[..out2 * out3].iter())
-                .zip(output[..out2 * out3].iter_mut())
-            {
-                *o = if c { x } else { y };
-            }
-            x = &x[out2 * out3..];
-            output = &mut output[out2 * out3..];
-        }
-    }
-}
-
 fn compute_softmax(
     tctx: &ThreadCtx,
     softmax: &Softmax,
@@ -1017,7 +1224,7 @@ fn compute_softmax(
     });
 }
 
-fn fast_sum(mut slice: &[f32]) -> f32 {
+pub(crate) fn fast_sum(mut slice: &[f32]) -> f32 {
     const SIMD_LEN: usize = 8;
     let mut sum = Simd::<f32, SIMD_LEN>::splat(0f32);
     let mut len = slice.len();
@@ -1031,7 +1238,7 @@ fn fast_sum(mut slice: &[f32]) -> f32 {
     sum.reduce_sum() + slice.iter().sum::<f32>()
 }
 
-fn fast_sum_squares(mut slice: &[f32]) -> f32 {
+pub(crate) fn fast_sum_squares(mut slice: &[f32]) -> f32 {
     const SIMD_LEN: usize = 8;
     let mut sum = Simd::<f32, SIMD_LEN>::splat(0f32);
     let mut len = slice.len();
@@ -1046,83 +1253,6 @@ fn fast_sum_squares(mut slice: &[f32]) -> f32 {
     sum.reduce_sum() + slice.iter().map(|x| x * x).sum::<f32>()
 }
 
-fn compute_resize(tctx: &ThreadCtx, resize: &Resize, inputs: &[&Tensor], outputs: &mut [Tensor]) {
-    assert!(matches!(inputs.len(), 3 | 4));
-
-    let input = inputs[Op::RESIZE_IN_X];
-    // let sizes = inputs[Op::RESIZE_IN_SIZES];
-    let output = &mut outputs[Op::RESIZE_OUT];
-
-    let batch_size = input.dims()[0];
-    let input_c = input.dims()[1];
-    let input_h = input.dims()[2];
-    let input_w = input.dims()[3];
-    let output_h = output.dims()[2];
-    let output_w = output.dims()[3];
-    let input_hw = input_h * input_w;
-    let outer = batch_size * input_c;
-
-    let scale = output_h as f32 / input_h as f32;
-
-    if resize.mode == "nearest" {
-        let mut input = input.data::<f32>();
-        let mut output = output.data_mut::<f32>();
-
-        for _ in 0..outer {
-            for (h, o) in (0..output_h).zip(output.chunks_mut(output_w)) {
-                let ih = (h as f32 / scale) as usize;
-                let ihw = input_w * ih;
-                for (w, o) in (0..output_w).zip(o.iter_mut()) {
-                    let iw = (w as f32 / scale) as usize;
-                    *o = input[ihw + iw];
-                }
-            }
-            output = &mut output[output_h * output_w..];
-            input = &input[input_hw..];
-        }
-    } else {
-        log::info!("Resize: Current implementation uses bilinear interpolation!");
-
-        // TODO: Multi-threading could make the performance worse depending on height and/or width.
-        tctx.scope(|scope| {
-            let mut input = input.data::<f32>();
-            let mut output = output.data_mut::<f32>();
-
-            for _ in 0..outer {
-                for (h, o) in (0..output_h).zip(output.chunks_mut(output_w)) {
-                    let ihf = (h as f32 / scale - 0.5).max(0.);
-                    let ih = ihf as usize;
-                    let ih0 = ih.min(input_h - 1);
-                    let ih1 = (ih + 1).min(input_h - 1);
-                    let ih0w = input_w * ih0;
-                    let ih1w = input_w * ih1;
-                    scope.spawn(move || {
-                        for (w, o) in (0..output_w).zip(o.iter_mut()) {
-                            let iwf = (w as f32 / scale - 0.5).max(0.);
-                            let iw = iwf as usize;
-                            let iw0 = iw.min(input_w - 1);
-                            let iw1 = (iw + 1).min(input_w - 1);
-
-                            let v00 = input[ih0w + iw0];
-                            let v01 = input[ih0w + iw1];
-                            let v10 = input[ih1w + iw0];
-                            let v11 = input[ih1w + iw1];
-
-                            let hd = v00 + (v10 - v00) * (ihf - ih as f32);
-                            let hw = v01 + (v11 - v01) * (ihf - ih as f32);
-                            let r = hd + (hw - hd) * (iwf - iw as f32);
-
-                            *o = r
-                        }
-                    });
-                }
-                output = &mut output[output_h * output_w..];
-                input = &input[input_hw..];
-            }
-        });
-    }
-}
-
 fn compute_concat(
     concat: &Concat,
     inputs: &[&Tensor],
@@ -1281,68 +1411,150 @@ fn compute_unsqueeze(_: &Unsqueeze, inputs: &[&Tensor], outputs: &mut [Tensor])
     output.copy_data_from(input);
 }
 
-fn compute_reduce_max(rmax: &ReduceMax, inputs: &[&Tensor], outputs: &mut [Tensor]) {
-    let input = inputs[0];
-    let output = &mut outputs[0];
-    assert!(rmax.axes.is_empty());
-    assert!(!rmax.keep_dims);
+fn compute_clip(inputs: &[&Tensor], outputs: &mut [Tensor]) -> Result<(), SessionError> {
+    let input = inputs[Op::CLIP_IN];
+    let output = &mut outputs[Op::CLIP_OUT];
+
+    fn clip<T: TensorElemTypeExt>(inputs: &[&Tensor], output: &mut Tensor) {
+        // Omitted bounds have no elements.
+        let bound = |i: usize| {
+            inputs
+                .get(i)
+                .filter(|t| t.dims().total_elems() > 0)
+                .map(|t| t.data::<T>()[0])
+        };
+        let (min, max) = (bound(Op::CLIP_IN_MIN), bound(Op::CLIP_IN_MAX));
+        let input = inputs[Op::CLIP_IN].data::<T>();
+        for (&x, o) in input.iter().zip(output.data_mut::<T>().iter_mut()) {
+            *o = match (min, max) {
+                (Some(min), _) if x < min => min,
+                (_, Some(max)) if x > max => max,
+                _ => x,
+            };
+        }
+    }
 
-    let input = input.data::<f32>();
-    let output = output.data_mut::<f32>();
+    match input.elem_ty() {
+        TensorElemType::F32 => clip::<f32>(inputs, output),
+        TensorElemType::I32 => clip::<i32>(inputs, output),
+        TensorElemType::I64 => clip::<i64>(inputs, output),
+        TensorElemType::I8 => clip::<i8>(inputs, output),
+        TensorElemType::U8 => clip::<u8>(inputs, output),
+        ty => {
+            return Err(SessionError::Message(
+                format!("Clip: Unsupported type {ty:?}").into(),
+            ))
+        }
+    }
 
-    output[0] = *input
-        .iter()
-        .max_by(|a, b| a.partial_cmp(b).unwrap())
-        .unwrap();
+    Ok(())
 }
 
-fn compute_reduce_mean(rmean: &ReduceMean, inputs: &[&Tensor], outputs: &mut [Tensor]) {
-    let input = inputs[0];
-    let output = &mut outputs[0];
-    let axes = rmean
-        .axes
-        .iter()
-        .map(|&axis| {
-            if axis < 0 {
-                (input.dims().len() as i64 + axis) as usize
-            } else {
-                axis as usize
-            }
-        })
-        .collect::<Vec<_>>();
-    assert_eq!(axes.len(), 1);
+fn compute_round(inputs: &[&Tensor], outputs: &mut [Tensor]) {
+    let input = inputs[0].data::<f32>();
+    let output = outputs[0].data_mut::<f32>();
 
-    let axis = axes[0];
-    assert_eq!(input.dims().len(), 3);
-    assert_eq!(axis, 2);
-    assert!(rmean.keep_dims);
+    // Halves are rounded to the nearest even integer.
+    for (&i, o) in input.iter().zip(output.iter_mut()) {
+        *o = i.round_ties_even();
+    }
+}
 
-    let axis_len = input.dims()[2];
-    let input = input.data::<f32>();
-    let output = output.data_mut::<f32>();
-    let r_axis_len = 1.0 / axis_len as f32;
-
-    input
-        .chunks(axis_len)
-        .zip(output.iter_mut())
-        .for_each(|(input, output)| {
-            let sum = fast_sum(input);
-            *output = sum * r_axis_len;
-        });
+fn compute_exp(inputs: &[&Tensor], outputs: &mut [Tensor]) {
+    let input = inputs[0].data::<f32>();
+    let output = outputs[0].data_mut::<f32>();
+
+    for (&i, o) in input.iter().zip(output.iter_mut()) {
+        *o = i.exp();
+    }
 }
 
-fn compute_loop(_node: &Node, _inputs: &[&Tensor], _outputs: &mut [Tensor]) {
-    todo!("loop")
+fn compute_range(inputs: &[&Tensor], outputs: &mut [Tensor]) -> Result<(), SessionError> {
+    let output = &mut outputs[0];
+
+    fn range<T: TensorElemTypeExt>(
+        inputs: &[&Tensor],
+        output: &mut Tensor,
+        f: impl Fn(T, T, usize) -> T,
+    ) {
+        let (start, delta) = (inputs[0].data::<T>()[0], inputs[2].data::<T>()[0]);
+        for (i, o) in output.data_mut::<T>().iter_mut().enumerate() {
+            *o = f(start, delta, i);
+        }
+    }
+
+    match output.elem_ty() {
+        TensorElemType::F32 => range(inputs, output, |s: f32, d, i| s + i as f32 * d),
+        TensorElemType::I32 => range(inputs, output, |s: i32, d, i| s + i as i32 * d),
+        TensorElemType::I64 => range(inputs, output, |s: i64, d, i| s + i as i64 * d),
+        ty => {
+            return Err(SessionError::Message(
+                format!("Range: Unsupported type {ty:?}").into(),
+            ))
+        }
+    }
+
+    Ok(())
 }
 
-fn compute_tile(_node: &Node, _inputs: &[&Tensor], _outputs: &mut [Tensor]) {
-    todo!("tile")
+fn compute_tile(inputs: &[&Tensor], outputs: &mut [Tensor]) {
+    let input = inputs[Op::TILE_IN];
+    let repeats = inputs[Op::TILE_REPEATS].data::<i64>();
+    let output = &mut outputs[Op::TILE_OUT];
+
+    // Copies `input`, the bytes of a tensor of `dims`, tiled by `repeats` to the front of
+    // `output`, and returns the rest of `output`.
+    fn tile<'a>(
+        input: &[u8],
+        dims: &[usize],
+        repeats: &[i64],
+        output: &'a mut [u8],
+    ) -> &'a mut [u8] {
+        let Some((&len, dims)) = dims.split_first() else {
+            let (front, rest) = output.split_at_mut(input.len());
+            front.copy_from_slice(input);
+            return rest;
+        };
+        let mut output = output;
+        for _ in 0..repeats[0] {
+            for chunk in input.chunks(input.len() / len) {
+                output = tile(chunk, dims, &repeats[1..], output);
+            }
+        }
+        output
+    }
+
+    if output.dims().total_elems() == 0 {
+        return;
+    }
+    tile(
+        input.data_as_bytes(),
+        input.dims().as_slice(),
+        repeats,
+        output.data_as_bytes_mut(),
+    );
 }
 
 fn compute_cast(cast: &Cast, inputs: &[&Tensor], outputs: &mut [Tensor]) {
     let input = inputs[Op::CAST_IN];
     let output = &mut outputs[Op::CAST_OUT];
-    if input.elem_ty().is_i32() && cast.to.is_f32() {
+    if let Some(casted) = input.cast_float(cast.to) {
+        *output = casted;
+    } else if input.elem_ty().is_half() {
+        // Half-precision values go through f32.
+        let input = input.cast_float(TensorElemType::F32).unwrap();
+        compute_cast(cast, &[&input], outputs);
+    } else if cast.to.is_half() {
+        let mut output_f32 = [Tensor::uninit::<f32>(output.dims().clone())];
+        compute_cast(
+            &Cast {
+                to: TensorElemType::F32,
+            },
+            inputs,
+            &mut output_f32,
+        );
+        *output = output_f32[0].cast_float(cast.to).unwrap();
+    } else if input.elem_ty().is_i32() && cast.to.is_f32() {
         *output = Tensor::new(
             output.dims().clone(),
             input.data::<i32>().iter().map(|x| *x as f32).collect(),
@@ -1554,41 +1766,6 @@ fn compute_slice(_node: &Node, inputs: &[&Tensor], outputs: &mut [Tensor]) {
     }
 }
 
-fn compute_gather(gather: &Gather, inputs: &[&Tensor], outputs: &mut [Tensor]) {
-    let data = inputs[0];
-    let indices = inputs[1];
-    let output = &mut outputs[0];
-
-    assert!(gather.axis >= 0);
-    assert!(
-        indices.dims().is_scalar() || (indices.dims().len() == 2 && indices.dims()[0] == 1),
-        "Unsupported indices shape: {:?}",
-        indices.dims()
-    );
-
-    if indices.dims().is_scalar() {
-        let axis = gather.axis as usize;
-        data.dims()[..axis].iter().for_each(|&d| assert_eq!(d, 1));
-
-        let mut from = vec![0; data.dims().len()];
-        from[axis] = indices.data::<i64>()[0] as usize;
-        let gathered = &data.slice_at::<f32>(&from)[..data.strides()[axis]];
-        output.data_mut().copy_from_slice(gathered);
-    } else {
-        let axis = gather.axis as usize;
-        assert_eq!(axis, 0);
-
-        let indices = indices.data::<i64>();
-        for (&i, o) in indices
-            .iter()
-            .zip(output.data_mut::<f32>().chunks_mut(data.dims()[1]))
-        {
-            assert!(i >= 0);
-            o.copy_from_slice(&data.slice_at::<f32>(&[i as usize])[..data.dims()[1]]);
-        }
-    }
-}
-
 fn compute_shape(inputs: &[&Tensor], outputs: &mut [Tensor]) {
     let input = inputs[0];
     let output = &mut outputs[0];
@@ -4,6 +4,7 @@ use std::marker::PhantomData;
 #[cfg(not(target_arch = "wasm32"))]
 use threadpool::ThreadPool;
 
+#[derive(Clone)]
 pub struct ThreadCtx {
     #[cfg(not(target_arch = "wasm32"))]
     pub tp: ThreadPool,
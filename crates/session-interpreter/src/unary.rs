@@ -0,0 +1,100 @@
+//! Kernels of unary elementwise math ops, computed in parallel with the SIMD functions
+//! of `fast_math`.
+
+use altius_core::{
+    op::{Elu, Op, Selu},
+    tensor::{Tensor, TensorElemType},
+};
+use altius_session::SessionError;
+
+use super::{fast_math::*, thread::ThreadCtx};
+
+/// The granularity of the elements each thread computes, to keep SIMD lanes full.
+const ALIGN: usize = 8;
+
+type Kernel = Box<dyn Fn(&mut [f32], &[f32]) + Sync>;
+
+/// Computes `Abs`, `Neg` or `Sign` of integers.
+macro_rules! integer {
+    ($op:expr, $input:expr, $output:expr, $ty:ty) => {{
+        let f = match $op {
+            Op::Abs => <$ty>::wrapping_abs,
+            Op::Neg => <$ty>::wrapping_neg,
+            _ => <$ty>::signum,
+        };
+        for (&x, o) in $input.data::<$ty>().iter().zip($output.data_mut::<$ty>()) {
+            *o = f(x);
+        }
+    }};
+}
+
+pub fn compute(
+    tctx: &ThreadCtx,
+    op: &Op,
+    inputs: &[&Tensor],
+    outputs: &mut [Tensor],
+) -> Result<(), SessionError> {
+    let input = inputs[0];
+    let output = &mut outputs[0];
+
+    match (op, input.elem_ty()) {
+        (_, TensorElemType::F32) => {}
+        (Op::Abs | Op::Neg | Op::Sign, TensorElemType::I64) => {
+            integer!(op, input, output, i64);
+            return Ok(());
+        }
+        (Op::Abs | Op::Neg | Op::Sign, TensorElemType::I32) => {
+            integer!(op, input, output, i32);
+            return Ok(());
+        }
+        (Op::Abs | Op::Neg | Op::Sign, TensorElemType::I8) => {
+            integer!(op, input, output, i8);
+            return Ok(());
+        }
+        (op, ty) => {
+            return Err(SessionError::Message(
+                format!("{}: Unsupported type {ty:?}", op.name()).into(),
+            ))
+        }
+    }
+
+    let kernel: Kernel = match *op {
+        Op::Abs => Box::new(fast_abs),
+        Op::Neg => Box::new(fast_neg),
+        Op::Log => Box::new(fast_log),
+        Op::Reciprocal => Box::new(fast_reciprocal),
+        Op::Floor => Box::new(fast_floor),
+        Op::Ceil => Box::new(fast_ceil),
+        Op::Sin => Box::new(fast_sin),
+        Op::Cos => Box::new(fast_cos),
+        Op::Softplus => Box::new(fast_softplus),
+        Op::Softsign => Box::new(fast_softsign),
+        Op::Elu(Elu { alpha }) => Box::new(move |o, i| fast_elu(o, i, alpha)),
+        Op::Selu(Selu { alpha, gamma }) => Box::new(move |o, i| fast_selu(o, i, alpha, gamma)),
+        Op::Mish => Box::new(fast_mish),
+        Op::HardSwish => Box::new(fast_hard_swish),
+        Op::Sign => Box::new(fast_sign),
+        ref op => {
+            return Err(SessionError::Message(
+                format!("{} is not a unary math op", op.name()).into(),
+            ))
+        }
+    };
+    let kernel = &kernel;
+
+    let input = input.data::<f32>();
+    let output = output.data_mut::<f32>();
+    let chunk = input
+        .len()
+        .div_ceil(tctx.num_threads())
+        .next_multiple_of(ALIGN)
+        .max(ALIGN);
+    tctx.scope(|scope| {
+        input
+            .chunks(chunk)
+            .zip(output.chunks_mut(chunk))
+            .for_each(|(input, output)| scope.spawn(move || kernel(output, input)));
+    });
+
+    Ok(())
+}
@@ -0,0 +1,64 @@
+// Each test crate uses only some of these.
+#![allow(dead_code)]
+
+use altius_core::{model::Model, node::Node, op::Op, tensor::Tensor};
+use altius_session::SessionError;
+use altius_session_interpreter::InterpreterSessionBuilder;
+
+/// Runs a model of a single `op` node with `num_outputs` outputs, whose inputs are given
+/// at run time. `None` stands for an omitted optional input.
+pub fn try_run_outputs(
+    op: Op,
+    inputs: Vec<Option<Tensor>>,
+    num_outputs: usize,
+) -> Result<Vec<Tensor>, SessionError> {
+    let mut model = Model {
+        opset_version: 18,
+        ..Default::default()
+    };
+    let ins = inputs
+        .iter()
+        .enumerate()
+        .map(|(i, input)| match input {
+            Some(_) => model.graph.values.new_val_named(format!("x{i}")),
+            None => model.graph.values.new_val_named(""),
+        })
+        .collect::<Vec<_>>();
+    let outs = (0..num_outputs)
+        .map(|i| model.graph.values.new_val_named(format!("y{i}")))
+        .collect::<Vec<_>>();
+    model
+        .graph
+        .add_node(Node::new(op).with_ins(ins.clone()).with_outs(outs.clone()));
+    model.graph.inputs.extend(
+        ins.into_iter()
+            .zip(&inputs)
+            .filter_map(|(id, input)| input.as_ref().map(|_| id)),
+    );
+    model.graph.outputs.extend(outs);
+
+    let sess = InterpreterSessionBuilder::new(model).build()?;
+    sess.run(inputs.into_iter().flatten().collect())
+}
+
+pub fn run_outputs(op: Op, inputs: Vec<Tensor>, num_outputs: usize) -> Vec<Tensor> {
+    try_run_outputs(op, inputs.into_iter().map(Some).collect(), num_outputs).unwrap()
+}
+
+pub fn try_run(op: Op, inputs: Vec<Tensor>) -> Result<Tensor, SessionError> {
+    Ok(try_run_outputs(op, inputs.into_iter().map(Some).collect(), 1)?.remove(0))
+}
+
+pub fn run(op: Op, inputs: Vec<Tensor>) -> Tensor {
+    try_run(op, inputs).unwrap()
+}
+
+pub fn run_with_omitted(op: Op, inputs: Vec<Option<Tensor>>) -> Tensor {
+    try_run_outputs(op, inputs, 1).unwrap().remove(0)
+}
+
+/// A tensor of shape `dims` holding 0, 1, 2, ...
+pub fn iota(dims: &[usize]) -> Tensor {
+    let len = dims.iter().product::<usize>();
+    Tensor::new(dims.to_vec().into(), (0..len).map(|i| i as f32).collect())
+}
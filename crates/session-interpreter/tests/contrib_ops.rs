@@ -0,0 +1,216 @@
+use altius_core::{
+    fixed_dim::FixedDimensions,
+    model::Model,
+    node::Node,
+    op::{Attention, FusedMatMul, Op},
+    tensor::{Tensor, TensorElemType, TypedShape},
+    value::ValueId,
+};
+use altius_session_interpreter::InterpreterSessionBuilder;
+
+fn input(model: &mut Model, name: &str, dims: Vec<usize>, elem_ty: TensorElemType) -> ValueId {
+    let x = model.graph.values.new_val_named_and_shaped(
+        name,
+        TypedShape::new(FixedDimensions::from(dims).into(), elem_ty),
+    );
+    model.graph.inputs.push(x);
+    x
+}
+
+fn init(model: &mut Model, name: &str, tensor: Tensor) -> ValueId {
+    let id = model.graph.values.new_val_named(name);
+    model.graph.inits.insert(id, tensor);
+    id
+}
+
+fn data(len: usize, seed: usize) -> Vec<f32> {
+    (0..len)
+        .map(|i| ((i * 7 + seed) as f32).sin())
+        .collect::<Vec<_>>()
+}
+
+#[test]
+fn fused_mat_mul() {
+    // A: [2, k, m] (transposed), B: [n, k] (transposed)
+    let (m, k, n) = (2, 3, 4);
+    let mut model = Model::default();
+    let a = input(&mut model, "a", vec![2, k, m], TensorElemType::F32);
+    let b_data = data(n * k, 1);
+    let b = init(
+        &mut model,
+        "b",
+        Tensor::new(vec![n, k].into(), b_data.clone()),
+    );
+    let y = model.graph.values.new_val_named("y");
+    model.graph.add_node(
+        Node::new(Op::FusedMatMul(FusedMatMul {
+            alpha: 0.5,
+            trans_a: true,
+            trans_b: true,
+        }))
+        .with_ins(vec![a, b])
+        .with_out(y),
+    );
+    model.graph.outputs.push(y);
+
+    let sess = InterpreterSessionBuilder::new(model).build().unwrap();
+    let a_data = data(2 * k * m, 2);
+    let outputs = sess
+        .run(vec![Tensor::new(vec![2, k, m].into(), a_data.clone())])
+        .unwrap();
+
+    let mut expected = vec![];
+    for batch in 0..2 {
+        for i in 0..m {
+            for j in 0..n {
+                expected.push(
+                    (0..k)
+                        .map(|p| a_data[(batch * k + p) * m + i] * b_data[j * k + p])
+                        .sum::<f32>()
+                        * 0.5,
+                );
+            }
+        }
+    }
+    assert_eq!(outputs[0].dims(), &vec![2, m, n].into());
+    assert!(outputs[0].allclose(&expected));
+}
+
+#[test]
+fn attention() {
+    let (batch, seq_len, hidden_size, num_heads) = (2, 3, 4, 2);
+    let head_size = hidden_size / num_heads;
+    let mask_data = vec![1i32, 1, 0, 1, 0, 1];
+
+    let mut model = Model::default();
+    let x = input(
+        &mut model,
+        "x",
+        vec![batch, seq_len, hidden_size],
+        TensorElemType::F32,
+    );
+    let w_data = data(hidden_size * 3 * hidden_size, 3);
+    let b_data = data(3 * hidden_size, 4);
+    let w = init(
+        &mut model,
+        "w",
+        Tensor::new(vec![hidden_size, 3 * hidden_size].into(), w_data.clone()),
+    );
+    let b = init(
+        &mut model,
+        "b",
+        Tensor::new(vec![3 * hidden_size].into(), b_data.clone()),
+    );
+    let mask = init(
+        &mut model,
+        "mask",
+        Tensor::new(vec![batch, seq_len].into(), mask_data.clone()),
+    );
+    let y = model.graph.values.new_val_named("y");
+    model.graph.add_node(
+        Node::new(Op::Attention(Attention {
+            num_heads,
+            qkv_hidden_sizes: vec![],
+            unidirectional: true,
+            mask_filter_value: -10000.0,
+            scale: None,
+        }))
+        .with_ins(vec![x, w, b, mask])
+        .with_out(y),
+    );
+    model.graph.outputs.push(y);
+
+    let sess = InterpreterSessionBuilder::new(model).build().unwrap();
+    let x_data = data(batch * seq_len * hidden_size, 5);
+    let outputs = sess
+        .run(vec![Tensor::new(
+            vec![batch, seq_len, hidden_size].into(),
+            x_data.clone(),
+        )])
+        .unwrap();
+
+    // qkv[b][s] = x[b][s] @ w + bias
+    let qkv = |b: usize, s: usize, col: usize| {
+        (0..hidden_size)
+            .map(|p| {
+                x_data[(b * seq_len + s) * hidden_size + p] * w_data[p * 3 * hidden_size + col]
+            })
+            .sum::<f32>()
+            + b_data[col]
+    };
+    let mut expected = vec![0f32; batch * seq_len * hidden_size];
+    for b in 0..batch {
+        for h in 0..num_heads {
+            for i in 0..seq_len {
+                // Keys after the query or masked out are ignored.
+                let keys = (0..=i)
+                    .filter(|&j| mask_data[b * seq_len + j] != 0)
+                    .collect::<Vec<_>>();
+                let scores = keys
+                    .iter()
+                    .map(|&j| {
+                        (0..head_size)
+                            .map(|d| {
+                                qkv(b, i, h * head_size + d)
+                                    * qkv(b, j, hidden_size + h * head_size + d)
+                            })
+                            .sum::<f32>()
+                            / (head_size as f32).sqrt()
+                    })
+                    .collect::<Vec<_>>();
+                let max = scores.iter().fold(f32::NEG_INFINITY, |m, &s| m.max(s));
+                let sum = scores.iter().map(|s| (s - max).exp()).sum::<f32>();
+                for d in 0..head_size {
+                    expected[(b * seq_len + i) * hidden_size + h * head_size + d] = keys
+                        .iter()
+                        .zip(&scores)
+                        .map(|(&j, s)| {
+                            (s - max).exp() / sum * qkv(b, j, 2 * hidden_size + h * head_size + d)
+                        })
+                        .sum::<f32>();
+                }
+            }
+        }
+    }
+    assert_eq!(outputs[0].dims(), &vec![batch, seq_len, hidden_size].into());
+    assert!(outputs[0].allclose(&expected));
+}
+
+#[test]
+fn attention_invalid_num_heads() {
+    let hidden_size = 4;
+    for num_heads in [0, 3] {
+        let mut model = Model::default();
+        let x = input(
+            &mut model,
+            "x",
+            vec![1, 2, hidden_size],
+            TensorElemType::F32,
+        );
+        let w = init(
+            &mut model,
+            "w",
+            Tensor::new(
+                vec![hidden_size, 3 * hidden_size].into(),
+                data(hidden_size * 3 * hidden_size, 3),
+            ),
+        );
+        let y = model.graph.values.new_val_named("y");
+        model.graph.add_node(
+            Node::new(Op::Attention(Attention {
+                num_heads,
+                qkv_hidden_sizes: vec![],
+                unidirectional: false,
+                mask_filter_value: -10000.0,
+                scale: None,
+            }))
+            .with_ins(vec![x, w])
+            .with_out(y),
+        );
+        model.graph.outputs.push(y);
+
+        let sess = InterpreterSessionBuilder::new(model).build().unwrap();
+        let x = Tensor::new(vec![1, 2, hidden_size].into(), data(2 * hidden_size, 5));
+        assert!(sess.run(vec![x]).is_err(), "num_heads: {num_heads}");
+    }
+}
@@ -0,0 +1,176 @@
+use altius_core::{
+    fixed_dim::FixedDimensions,
+    graph::Graph,
+    model::Model,
+    node::Node,
+    onnx::{load_onnx, save::save_onnx},
+    op::{If, Loop, Op},
+    tensor::{Tensor, TensorElemType, TypedShape},
+    value::ValueId,
+};
+use altius_session_interpreter::InterpreterSessionBuilder;
+
+fn shape(dims: Vec<usize>, elem_ty: TensorElemType) -> TypedShape {
+    TypedShape::new(FixedDimensions::from(dims).into(), elem_ty)
+}
+
+fn init(graph: &mut Graph, name: &str, tensor: Tensor) -> ValueId {
+    let id = graph
+        .values
+        .new_val_named_and_shaped(name, shape(tensor.dims().to_vec(), tensor.elem_ty()));
+    graph.inits.insert(id, tensor);
+    id
+}
+
+fn scalar<T: altius_core::tensor::TensorElemTypeExt>(x: T) -> Tensor {
+    Tensor::new(vec![].into(), vec![x])
+}
+
+/// x = 0; do { x' = x + step; scan(x + x') } while x' < limit (at most 10 times)
+fn build_loop() -> Model {
+    let mut model = Model {
+        opset_version: 16,
+        ..Default::default()
+    };
+    let graph = &mut model.graph;
+    let step = graph
+        .values
+        .new_val_named_and_shaped("step", shape(vec![2], TensorElemType::F32));
+    let m = init(graph, "m", scalar(10i64));
+    let cond = init(graph, "cond", scalar(true));
+    let x0 = init(graph, "x0", Tensor::zeros::<f32>(vec![2].into()));
+    let limit = init(graph, "limit", scalar(2.5f32));
+    let x_final = graph
+        .values
+        .new_val_named_and_shaped("x_final", shape(vec![2], TensorElemType::F32));
+    let scan = graph
+        .values
+        .new_val_named_and_shaped("scan", shape(vec![3, 2], TensorElemType::F32));
+
+    let mut body = Graph::default();
+    let [iter_num, cond_in, x, step_in, limit_in, cond_out, x_out, scan_out] = [
+        "iter_num", "cond_in", "x", "step", "limit", "cond_out", "x_out", "scan_out",
+    ]
+    .map(|name| body.values.new_val_named(name));
+    body.inputs.extend([iter_num, cond_in, x]);
+    body.outer_inputs.extend([step_in, limit_in]);
+    body.add_node(
+        Node::new(Op::Add)
+            .with_ins(vec![x, step_in])
+            .with_out(x_out),
+    );
+    body.add_node(
+        Node::new(Op::Add)
+            .with_ins(vec![x, x_out])
+            .with_out(scan_out),
+    );
+    let x_out_first = body.values.new_val_named("x_out_first");
+    body.add_node(
+        Node::new(Op::Gather(altius_core::op::Gather { axis: 0 }))
+            .with_ins(vec![x_out, iter_num])
+            .with_out(x_out_first),
+    );
+    body.add_node(
+        Node::new(Op::Greater)
+            .with_ins(vec![limit_in, x_out_first])
+            .with_out(cond_out),
+    );
+    body.outputs.extend([cond_out, x_out, scan_out]);
+
+    graph.add_node(
+        Node::new(Op::Loop(Loop { body }))
+            .with_ins(vec![m, cond, x0, step, limit])
+            .with_outs(vec![x_final, scan]),
+    );
+    graph.inputs.push(step);
+    graph.outputs.extend([x_final, scan]);
+    model
+}
+
+/// then: a + b, else: a - b
+fn build_if() -> Model {
+    let mut model = Model {
+        opset_version: 16,
+        ..Default::default()
+    };
+    let graph = &mut model.graph;
+    let cond = graph
+        .values
+        .new_val_named_and_shaped("cond", shape(vec![], TensorElemType::Bool));
+    let a = graph
+        .values
+        .new_val_named_and_shaped("a", shape(vec![2], TensorElemType::F32));
+    let b = init(graph, "b", Tensor::new(vec![2].into(), vec![1.0f32, 2.0]));
+    let y = graph
+        .values
+        .new_val_named_and_shaped("y", shape(vec![2], TensorElemType::F32));
+
+    let branch = |op: Op| {
+        let mut g = Graph::default();
+        let [a, b, y] = ["a", "b", "y"].map(|name| g.values.new_val_named(name));
+        g.outer_inputs.extend([a, b]);
+        g.add_node(Node::new(op).with_ins(vec![a, b]).with_out(y));
+        g.outputs.push(y);
+        g
+    };
+    graph.add_node(
+        Node::new(Op::If(If {
+            then_branch: branch(Op::Add),
+            else_branch: branch(Op::Sub),
+        }))
+        .with_ins(vec![cond, a, b, a, b])
+        .with_out(y),
+    );
+    graph.inputs.extend([cond, a]);
+    graph.outputs.push(y);
+    model
+}
+
+fn run_loop(model: Model) {
+    assert!(model.verify().is_ok());
+    let sess = InterpreterSessionBuilder::new(model).build().unwrap();
+    // Runs twice to make sure outer-scope values are rebound.
+    for _ in 0..2 {
+        let step = Tensor::new(vec![2].into(), vec![1.0f32, 10.0]);
+        let outputs = sess.run(vec![step]).unwrap();
+        // Stops when the iteration-th element of x' reaches the limit:
+        // x' = [1, 10] (1 < 2.5), [2, 20] (20 >= 2.5)
+        assert!(outputs[0].allclose(&[2.0f32, 20.0]));
+        assert_eq!(outputs[1].dims(), &vec![2, 2].into());
+        assert!(outputs[1].allclose(&[1.0f32, 10.0, 3.0, 30.0]));
+    }
+}
+
+fn run_if(model: Model) {
+    assert!(model.verify().is_ok());
+    let sess = InterpreterSessionBuilder::new(model).build().unwrap();
+    let a = || Tensor::new(vec![2].into(), vec![5.0f32, 6.0]);
+    let y = sess.run(vec![scalar(true), a()]).unwrap().remove(0);
+    assert!(y.allclose(&[6.0f32, 8.0]));
+    let y = sess.run(vec![scalar(false), a()]).unwrap().remove(0);
+    assert!(y.allclose(&[4.0f32, 4.0]));
+}
+
+#[test]
+fn loop_with_scan_outputs() {
+    run_loop(build_loop());
+}
+
+#[test]
+fn if_branches() {
+    run_if(build_if());
+}
+
+#[test]
+fn save_and_load_subgraphs() {
+    for (name, model, run) in [
+        ("altius_loop.onnx", build_loop(), run_loop as fn(Model)),
+        ("altius_if.onnx", build_if(), run_if),
+    ] {
+        let path = std::env::temp_dir().join(name);
+        save_onnx(&model, &path).unwrap();
+        let loaded = load_onnx(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        run(loaded);
+    }
+}
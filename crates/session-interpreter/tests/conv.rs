@@ -0,0 +1,346 @@
+mod common;
+
+use altius_core::{
+    model::Model,
+    node::Node,
+    onnx::{load_onnx, save::save_onnx},
+    op::{Conv2d, ConvTranspose, Op},
+    tensor::{Tensor, TypedShape},
+};
+use altius_session_interpreter::InterpreterSessionBuilder;
+use common::iota;
+
+/// Builds a model of a single `op` node whose input X is a graph input and whose
+/// weight and optional bias are initializers.
+fn model(op: Op, x: &Tensor, w: Tensor, b: Option<Tensor>) -> Model {
+    let mut model = Model::default();
+    let input = model
+        .graph
+        .values
+        .new_val_named_and_shaped("x", TypedShape::new(x.dims().clone().into(), x.elem_ty()));
+    model.graph.inputs.push(input);
+    let mut inputs = vec![input];
+    for (name, init) in [("w", Some(w)), ("b", b)] {
+        let Some(init) = init else { continue };
+        let id = model.graph.values.new_val_named_and_shaped(
+            name,
+            TypedShape::new(init.dims().clone().into(), init.elem_ty()),
+        );
+        model.graph.inits.insert(id, init);
+        inputs.push(id);
+    }
+    let y = model.graph.values.new_val_named("y");
+    model.graph.outputs.push(y);
+    model
+        .graph
+        .add_node(Node::new(op).with_ins(inputs).with_out(y));
+    model
+}
+
+fn run(op: Op, x: Tensor, w: Tensor, b: Option<Tensor>) -> Tensor {
+    common::run(op, [Some(x), Some(w), b].into_iter().flatten().collect())
+}
+
+fn ones(dims: Vec<usize>) -> Tensor {
+    let len = dims.iter().product::<usize>();
+    Tensor::new(dims.into(), vec![1.0f32; len])
+}
+
+fn conv(auto_pad: &str) -> Conv2d {
+    Conv2d {
+        auto_pad: auto_pad.into(),
+        group: 1,
+        ..Default::default()
+    }
+}
+
+fn conv_transpose() -> ConvTranspose {
+    ConvTranspose {
+        auto_pad: "NOTSET".into(),
+        group: 1,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn conv1d() {
+    let x = Tensor::new(vec![1, 1, 5].into(), vec![1.0f32, 2., 3., 4., 5.]);
+    let w = Tensor::new(vec![1, 1, 3].into(), vec![1.0f32, 0., -1.]);
+    let y = run(
+        Op::Conv2d(Conv2d {
+            padding: vec![1, 1].into(),
+            ..conv("NOTSET")
+        }),
+        x.clone(),
+        w,
+        None,
+    );
+    assert_eq!(y.dims().as_slice(), &[1, 1, 5]);
+    assert_eq!(y.data::<f32>(), &[-2., -2., -2., -2., 4.]);
+
+    let w = Tensor::new(vec![1, 1, 2].into(), vec![1.0f32, 1.]);
+    let y = run(Op::Conv2d(conv("SAME_UPPER")), x.clone(), w.clone(), None);
+    assert_eq!(y.data::<f32>(), &[3., 5., 7., 9., 5.]);
+    let y = run(Op::Conv2d(conv("SAME_LOWER")), x, w, None);
+    assert_eq!(y.data::<f32>(), &[1., 3., 5., 7., 9.]);
+}
+
+#[test]
+fn conv1d_with_groups_and_bias() {
+    let x = Tensor::new(vec![1, 2, 3].into(), vec![1.0f32, 2., 3., 4., 5., 6.]);
+    let w = Tensor::new(vec![2, 1, 1].into(), vec![2.0f32, 3.]);
+    let b = Tensor::new(vec![2].into(), vec![1.0f32, -1.]);
+    let y = run(
+        Op::Conv2d(Conv2d {
+            group: 2,
+            ..conv("NOTSET")
+        }),
+        x,
+        w,
+        Some(b),
+    );
+    assert_eq!(y.data::<f32>(), &[3., 5., 7., 11., 14., 17.]);
+}
+
+#[test]
+fn conv2d_non_square() {
+    // The example of the ONNX backend test `conv_with_strides_padding`.
+    let x = iota(&[1, 1, 7, 5]);
+    let op = |padding: Vec<usize>| {
+        Op::Conv2d(Conv2d {
+            padding: padding.into(),
+            strides: vec![2, 2].into(),
+            ..conv("NOTSET")
+        })
+    };
+    let y = run(
+        op(vec![1, 1, 1, 1]),
+        x.clone(),
+        ones(vec![1, 1, 3, 3]),
+        None,
+    );
+    assert_eq!(y.dims().as_slice(), &[1, 1, 4, 3]);
+    #[rustfmt::skip]
+    assert_eq!(
+        y.data::<f32>(),
+        &[
+            12., 27., 24.,
+            63., 108., 81.,
+            123., 198., 141.,
+            112., 177., 124.,
+        ]
+    );
+
+    let y = run(op(vec![0, 0, 0, 0]), x, ones(vec![1, 1, 3, 3]), None);
+    assert_eq!(y.dims().as_slice(), &[1, 1, 3, 2]);
+    assert_eq!(y.data::<f32>(), &[54., 72., 144., 162., 234., 252.]);
+}
+
+#[test]
+fn conv2d_with_dilations() {
+    let y = run(
+        Op::Conv2d(Conv2d {
+            dilations: vec![2, 2].into(),
+            ..conv("NOTSET")
+        }),
+        iota(&[1, 1, 4, 4]),
+        ones(vec![1, 1, 2, 2]),
+        None,
+    );
+    assert_eq!(y.data::<f32>(), &[20., 24., 36., 40.]);
+}
+
+#[test]
+fn conv3d() {
+    let x = iota(&[1, 1, 3, 3, 3]);
+    let y = run(
+        Op::Conv2d(conv("NOTSET")),
+        x.clone(),
+        ones(vec![2, 1, 2, 2, 2]),
+        Some(Tensor::new(vec![2].into(), vec![0.0f32, 100.])),
+    );
+    assert_eq!(y.dims().as_slice(), &[1, 2, 2, 2, 2]);
+
+    let x = x.data::<f32>();
+    let mut expected = vec![];
+    for bias in [0., 100.] {
+        for (d, h, w) in (0..8).map(|i| (i / 4, i / 2 % 2, i % 2)) {
+            let window = (0..8).map(|k| (d + k / 4) * 9 + (h + k / 2 % 2) * 3 + w + k % 2);
+            expected.push(bias + window.map(|i| x[i]).sum::<f32>());
+        }
+    }
+    assert_eq!(y.data::<f32>(), expected);
+}
+
+#[test]
+fn conv_transpose_1d() {
+    // The example of the ONNX backend test `convtranspose_1d`.
+    let x = Tensor::new(vec![1, 1, 3].into(), vec![0.0f32, 1., 2.]);
+    let y = run(
+        Op::ConvTranspose(conv_transpose()),
+        x,
+        ones(vec![1, 2, 3]),
+        None,
+    );
+    assert_eq!(y.dims().as_slice(), &[1, 2, 5]);
+    assert_eq!(y.data::<f32>(), &[0., 1., 3., 3., 2., 0., 1., 3., 3., 2.]);
+
+    let x = Tensor::new(vec![1, 2, 2].into(), vec![1.0f32, 2., 3., 4.]);
+    let w = Tensor::new(vec![2, 1, 2].into(), vec![1.0f32, 1., 1., -1.]);
+    let y = run(
+        Op::ConvTranspose(ConvTranspose {
+            group: 2,
+            strides: vec![2].into(),
+            ..conv_transpose()
+        }),
+        x,
+        w,
+        Some(Tensor::new(vec![2].into(), vec![0.5f32, 0.])),
+    );
+    assert_eq!(y.data::<f32>(), &[1.5, 1.5, 2.5, 2.5, 3., -3., 4., -4.]);
+}
+
+#[test]
+fn conv_transpose_2d() {
+    // The example of the ONNX backend test `convtranspose`.
+    let y = run(
+        Op::ConvTranspose(conv_transpose()),
+        iota(&[1, 1, 3, 3]),
+        ones(vec![1, 2, 3, 3]),
+        None,
+    );
+    assert_eq!(y.dims().as_slice(), &[1, 2, 5, 5]);
+    #[rustfmt::skip]
+    let channel = [
+        0., 1., 3., 3., 2.,
+        3., 8., 15., 12., 7.,
+        9., 21., 36., 27., 15.,
+        9., 20., 33., 24., 13.,
+        6., 13., 21., 15., 8.,
+    ];
+    assert_eq!(&y.data::<f32>()[..25], &channel);
+    assert_eq!(&y.data::<f32>()[25..], &channel);
+}
+
+#[test]
+fn conv_transpose_pads_and_output_shape() {
+    // The examples of the ONNX backend tests `convtranspose_pads` and `convtranspose_output_shape`.
+    let y = run(
+        Op::ConvTranspose(ConvTranspose {
+            strides: vec![3, 2].into(),
+            padding: vec![1, 2, 1, 2].into(),
+            ..conv_transpose()
+        }),
+        iota(&[1, 1, 3, 3]),
+        ones(vec![1, 2, 3, 3]),
+        None,
+    );
+    assert_eq!(y.dims().as_slice(), &[1, 2, 7, 3]);
+    #[rustfmt::skip]
+    assert_eq!(
+        &y.data::<f32>()[..21],
+        &[
+            1., 1., 3.,
+            1., 1., 3.,
+            7., 4., 9.,
+            7., 4., 9.,
+            7., 4., 9.,
+            13., 7., 15.,
+            13., 7., 15.,
+        ]
+    );
+
+    #[rustfmt::skip]
+    let expected = [
+        0., 0., 1., 1., 3., 2., 2., 0.,
+        0., 0., 1., 1., 3., 2., 2., 0.,
+        0., 0., 1., 1., 3., 2., 2., 0.,
+        3., 3., 7., 4., 9., 5., 5., 0.,
+        3., 3., 7., 4., 9., 5., 5., 0.,
+        3., 3., 7., 4., 9., 5., 5., 0.,
+        6., 6., 13., 7., 15., 8., 8., 0.,
+        6., 6., 13., 7., 15., 8., 8., 0.,
+        6., 6., 13., 7., 15., 8., 8., 0.,
+        0., 0., 0., 0., 0., 0., 0., 0.,
+    ];
+    for op in [
+        ConvTranspose {
+            strides: vec![3, 2].into(),
+            output_shape: vec![10, 8].into(),
+            ..conv_transpose()
+        },
+        ConvTranspose {
+            strides: vec![3, 2].into(),
+            output_padding: vec![1, 1].into(),
+            ..conv_transpose()
+        },
+    ] {
+        let y = run(
+            Op::ConvTranspose(op),
+            iota(&[1, 1, 3, 3]),
+            ones(vec![1, 2, 3, 3]),
+            None,
+        );
+        assert_eq!(y.dims().as_slice(), &[1, 2, 10, 8]);
+        assert_eq!(&y.data::<f32>()[..80], &expected);
+    }
+}
+
+#[test]
+fn conv_transpose_same_upper() {
+    // The example of the ONNX backend test `convtranspose_autopad_same`.
+    let y = run(
+        Op::ConvTranspose(ConvTranspose {
+            auto_pad: "SAME_UPPER".into(),
+            strides: vec![2, 2].into(),
+            ..conv_transpose()
+        }),
+        iota(&[1, 1, 3, 3]),
+        ones(vec![1, 2, 3, 3]),
+        None,
+    );
+    assert_eq!(y.dims().as_slice(), &[1, 2, 6, 6]);
+    #[rustfmt::skip]
+    assert_eq!(
+        &y.data::<f32>()[..36],
+        &[
+            0., 0., 1., 1., 3., 2.,
+            0., 0., 1., 1., 3., 2.,
+            3., 3., 8., 5., 12., 7.,
+            3., 3., 7., 4., 9., 5.,
+            9., 9., 20., 11., 24., 13.,
+            6., 6., 13., 7., 15., 8.,
+        ]
+    );
+}
+
+#[test]
+fn save_and_load_conv_transpose() {
+    let op = Op::ConvTranspose(ConvTranspose {
+        dilations: vec![2].into(),
+        group: 2,
+        output_shape: vec![9].into(),
+        strides: vec![2].into(),
+        ..conv_transpose()
+    });
+    let x = iota(&[1, 2, 3]);
+    let w = ones(vec![2, 3, 2]);
+    let mut model = model(op.clone(), &x, w.clone(), None);
+    let expected = run(op.clone(), x.clone(), w, None);
+    assert_eq!(expected.dims().as_slice(), &[1, 6, 9]);
+    model.opset_version = 17;
+    // Saved graph outputs need their shapes.
+    model.graph.values.inner_mut()[model.graph.outputs[0]].shape = Some(TypedShape::new(
+        expected.dims().clone().into(),
+        expected.elem_ty(),
+    ));
+
+    let path = std::env::temp_dir().join("altius_save_and_load_conv_transpose.onnx");
+    save_onnx(&model, &path).unwrap();
+    let loaded = load_onnx(&path).unwrap();
+    let _ = std::fs::remove_file(&path);
+    let (_, node) = loaded.graph.nodes.iter().next().unwrap();
+    assert_eq!(node.op, op);
+    let sess = InterpreterSessionBuilder::new(loaded).build().unwrap();
+    assert_eq!(sess.run(vec![x]).unwrap().remove(0), expected);
+}
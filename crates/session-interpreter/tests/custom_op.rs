@@ -0,0 +1,156 @@
+use std::collections::BTreeMap;
+
+use altius_core::{
+    analysis::shape::ShapeError,
+    custom_op::{CustomOp, CustomOpError},
+    fixed_dim::FixedDimensions,
+    model::Model,
+    node::Node,
+    onnx::{load_onnx, save::save_onnx},
+    op::{Custom, CustomAttribute, Op},
+    tensor::{Tensor, TensorElemType, TypedFixedShape, TypedShape},
+};
+use altius_session_interpreter::InterpreterSessionBuilder;
+
+/// Y = X * alpha, where the last axis of X is repeated `repeats` times.
+struct ScaleRepeat;
+
+impl ScaleRepeat {
+    fn repeats(op: &Custom) -> usize {
+        match op.attrs.get("repeats") {
+            Some(&CustomAttribute::Int(r)) => r as usize,
+            _ => 1,
+        }
+    }
+}
+
+impl CustomOp for ScaleRepeat {
+    fn compute_output_shapes(
+        &self,
+        op: &Custom,
+        inputs: &[&Tensor],
+        _num_outputs: usize,
+    ) -> Result<Vec<TypedFixedShape>, ShapeError> {
+        let mut dims = inputs[0].dims().to_vec();
+        *dims.last_mut().unwrap() *= Self::repeats(op);
+        Ok(vec![TypedFixedShape::new(dims.into(), inputs[0].elem_ty())])
+    }
+
+    fn compute(
+        &self,
+        op: &Custom,
+        inputs: &[&Tensor],
+        outputs: &mut [Tensor],
+    ) -> Result<(), CustomOpError> {
+        let Some(&CustomAttribute::Float(alpha)) = op.attrs.get("alpha") else {
+            return Err(CustomOpError::Message("alpha is required".into()));
+        };
+        let x = inputs[0];
+        let last = *x.dims().as_slice().last().unwrap();
+        let repeats = Self::repeats(op);
+        for (x, y) in x
+            .data::<f32>()
+            .chunks(last)
+            .zip(outputs[0].data_mut::<f32>().chunks_mut(last * repeats))
+        {
+            for y in y.chunks_mut(last) {
+                for (y, x) in y.iter_mut().zip(x) {
+                    *y = x * alpha;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn build(attrs: BTreeMap<String, CustomAttribute>) -> Model {
+    let mut model = Model {
+        opset_version: 13,
+        ..Default::default()
+    };
+    let x = model.graph.values.new_val_named_and_shaped(
+        "x",
+        TypedShape::new(
+            FixedDimensions::from(vec![2, 2]).into(),
+            TensorElemType::F32,
+        ),
+    );
+    let y = model.graph.values.new_val_named_and_shaped(
+        "y",
+        TypedShape::new(
+            FixedDimensions::from(vec![2, 6]).into(),
+            TensorElemType::F32,
+        ),
+    );
+    model.graph.add_node(
+        Node::new(Op::Custom(Custom {
+            domain: "com.example".into(),
+            op_type: "ScaleRepeat".into(),
+            attrs,
+        }))
+        .with_in(x)
+        .with_out(y),
+    );
+    model.graph.inputs.push(x);
+    model.graph.outputs.push(y);
+    model
+}
+
+fn attrs() -> BTreeMap<String, CustomAttribute> {
+    BTreeMap::from([
+        ("alpha".to_string(), CustomAttribute::Float(2.0)),
+        ("repeats".to_string(), CustomAttribute::Int(3)),
+    ])
+}
+
+fn input() -> Tensor {
+    Tensor::new(vec![2, 2].into(), vec![1.0f32, 2.0, 3.0, 4.0])
+}
+
+#[test]
+fn run_custom_op() {
+    let sess = InterpreterSessionBuilder::new(build(attrs()))
+        .with_custom_op("com.example", "ScaleRepeat", ScaleRepeat)
+        .build()
+        .unwrap();
+    let y = sess.run(vec![input()]).unwrap().remove(0);
+    assert_eq!(y.dims(), &vec![2, 6].into());
+    assert!(y.allclose(&[2.0f32, 4.0, 2.0, 4.0, 2.0, 4.0, 6.0, 8.0, 6.0, 8.0, 6.0, 8.0]));
+}
+
+#[test]
+fn custom_op_errors() {
+    // Not registered.
+    assert!(InterpreterSessionBuilder::new(build(attrs()))
+        .build()
+        .is_err());
+
+    // The kernel fails.
+    let sess = InterpreterSessionBuilder::new(build(BTreeMap::new()))
+        .with_custom_op("com.example", "ScaleRepeat", ScaleRepeat)
+        .build()
+        .unwrap();
+    assert!(sess.run(vec![input()]).is_err());
+}
+
+#[test]
+fn save_and_load_custom_op() {
+    let path = std::env::temp_dir().join("altius_custom_op.onnx");
+    save_onnx(&build(attrs()), &path).unwrap();
+    let model = load_onnx(&path).unwrap();
+    let _ = std::fs::remove_file(&path);
+
+    assert_eq!(model.domain_opset_versions["com.example"], 1);
+    let (_, node) = model.graph.nodes.iter().next().unwrap();
+    let Op::Custom(custom) = &node.op else {
+        panic!("Expected a custom op, got {:?}", node.op);
+    };
+    assert_eq!(custom.attrs, attrs());
+
+    let sess = InterpreterSessionBuilder::new(model)
+        .with_custom_op("com.example", "ScaleRepeat", ScaleRepeat)
+        .build()
+        .unwrap();
+    let y = sess.run(vec![input()]).unwrap().remove(0);
+    assert_eq!(y.data::<f32>()[..6], [2.0, 4.0, 2.0, 4.0, 2.0, 4.0]);
+}
@@ -0,0 +1,71 @@
+use altius_core::{
+    dim::{Dimension, Dimensions},
+    model::Model,
+    node::Node,
+    op::Op,
+    tensor::{Tensor, TensorElemType, TypedShape},
+};
+use altius_session_interpreter::InterpreterSessionBuilder;
+
+fn mat_mul_add_model() -> Model {
+    let mut model = Model::default();
+    let x = model.graph.values.new_val_named_and_shaped(
+        "x",
+        TypedShape::new(
+            Dimensions(vec![
+                Dimension::Dynamic("batch".into()),
+                Dimension::Fixed(4),
+            ]),
+            TensorElemType::F32,
+        ),
+    );
+    let w = model.graph.values.new_val_named("w");
+    let b = model.graph.values.new_val_named("b");
+    let y = model.graph.values.new_val_named("y");
+    let z = model.graph.values.new_val_named("z");
+
+    model
+        .graph
+        .add_node(Node::new(Op::MatMul).with_ins(vec![x, w]).with_out(y));
+    model
+        .graph
+        .add_node(Node::new(Op::Add).with_ins(vec![y, b]).with_out(z));
+    model.graph.inputs.push(x);
+    model.graph.outputs.push(z);
+    model.graph.inits.insert(
+        w,
+        Tensor::new(vec![4, 3].into(), (0..12).map(|i| i as f32).collect()),
+    );
+    model
+        .graph
+        .inits
+        .insert(b, Tensor::new(vec![3].into(), vec![1.0f32, 2.0, 3.0]));
+    model
+}
+
+#[test]
+fn run_with_dynamic_batch() {
+    let sess = InterpreterSessionBuilder::new(mat_mul_add_model())
+        .build()
+        .unwrap();
+
+    for batch in [2, 5, 2] {
+        let x = Tensor::new(vec![batch, 4].into(), vec![1.0f32; batch * 4]);
+        let z = sess.run(vec![x]).unwrap();
+        assert_eq!(z[0].dims().as_slice(), &[batch, 3]);
+        assert_eq!(
+            z[0].data::<f32>(),
+            [19.0f32, 24.0, 29.0].repeat(batch).as_slice()
+        );
+    }
+}
+
+#[test]
+fn run_with_mismatched_input() {
+    let sess = InterpreterSessionBuilder::new(mat_mul_add_model())
+        .build()
+        .unwrap();
+
+    let x = Tensor::new(vec![2, 5].into(), vec![1.0f32; 10]);
+    assert!(sess.run(vec![x]).is_err());
+}
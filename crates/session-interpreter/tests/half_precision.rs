@@ -0,0 +1,94 @@
+use altius_core::{
+    fixed_dim::FixedDimensions,
+    model::Model,
+    node::Node,
+    op::{Cast, Conv2d, Op},
+    tensor::{Tensor, TensorElemType, TypedShape},
+};
+use altius_session_interpreter::InterpreterSessionBuilder;
+
+/// y = Cast<f32>(ReLU(Reshape(Conv(x, w), [2, 2]) @ m + b)) where everything but `y` is of `elem_ty`.
+fn half_model(elem_ty: TensorElemType) -> Model {
+    let mut model = Model::default();
+    let x = model.graph.values.new_val_named_and_shaped(
+        "x",
+        TypedShape::new(FixedDimensions::from(vec![1, 1, 2, 2]).into(), elem_ty),
+    );
+    let [w, m, b, s, c, c2, d, e, r, y] = ["w", "m", "b", "s", "c", "c2", "d", "e", "r", "y"]
+        .map(|name| model.graph.values.new_val_named(name));
+    for (id, dims, data) in [
+        (w, vec![1, 1, 1, 1], vec![2.0f32]),
+        (m, vec![2, 3], vec![1.0, 0.0, -1.0, 0.5, 1.0, 0.0]),
+        (b, vec![3], vec![0.25, -8.0, 1.0]),
+    ] {
+        let t = Tensor::new(dims.into(), data).cast_float(elem_ty).unwrap();
+        model.graph.inits.insert(id, t);
+    }
+    model
+        .graph
+        .inits
+        .insert(s, Tensor::new(vec![2].into(), vec![2i64, 2]));
+    model.graph.add_node(
+        Node::new(Op::Conv2d(Conv2d {
+            kernel_shape: vec![1, 1].into(),
+            strides: vec![1, 1].into(),
+            padding: vec![0, 0, 0, 0].into(),
+            dilations: vec![1, 1].into(),
+            group: 1,
+            ..Default::default()
+        }))
+        .with_ins(vec![x, w])
+        .with_out(c),
+    );
+    model
+        .graph
+        .add_node(Node::new(Op::Reshape).with_ins(vec![c, s]).with_out(c2));
+    model
+        .graph
+        .add_node(Node::new(Op::MatMul).with_ins(vec![c2, m]).with_out(d));
+    model
+        .graph
+        .add_node(Node::new(Op::Add).with_ins(vec![d, b]).with_out(e));
+    model
+        .graph
+        .add_node(Node::new(Op::ReLU).with_in(e).with_out(r));
+    model.graph.add_node(
+        Node::new(Op::Cast(Cast {
+            to: TensorElemType::F32,
+        }))
+        .with_in(r)
+        .with_out(y),
+    );
+    model.graph.inputs.push(x);
+    model.graph.outputs.push(y);
+    model
+}
+
+#[test]
+fn run_half_precision() {
+    // conv: [[2, 4], [6, 8]]
+    // matmul: [[4, 4, -2], [10, 8, -6]]
+    let expected = [4.25f32, 0.0, 0.0, 10.25, 0.0, 0.0];
+
+    for elem_ty in [TensorElemType::F16, TensorElemType::BF16] {
+        let sess = InterpreterSessionBuilder::new(half_model(elem_ty))
+            .build()
+            .unwrap();
+        // Weights stay in half precision.
+        assert!(sess
+            .model()
+            .graph
+            .inits
+            .values()
+            .filter(|t| !t.elem_ty().is_i64())
+            .all(|t| t.elem_ty() == elem_ty));
+
+        let x = Tensor::new(vec![1, 1, 2, 2].into(), vec![1.0f32, 2.0, 3.0, 4.0])
+            .cast_float(elem_ty)
+            .unwrap();
+        let y = sess.run(vec![x]).unwrap();
+        assert_eq!(y[0].elem_ty(), TensorElemType::F32);
+        assert_eq!(y[0].dims().as_slice(), &[2, 3]);
+        assert!(y[0].allclose(&expected), "{elem_ty:?}: {}", y[0]);
+    }
+}
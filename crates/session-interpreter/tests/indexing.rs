@@ -0,0 +1,226 @@
+mod common;
+
+use altius_core::{
+    op::{
+        CumSum, Gather, GatherElements, GatherND, OneHot, Op, ScatterElements, ScatterND,
+        ScatterReduction, TopK,
+    },
+    tensor::Tensor,
+};
+use common::{iota, run, run_outputs};
+
+#[test]
+fn gather() {
+    let data = iota(&[2, 3, 2]);
+    let indices = Tensor::new(vec![2, 2].into(), vec![2i64, 0, -1, 1]);
+    let y = run(Op::Gather(Gather { axis: 1 }), vec![data.clone(), indices]);
+    assert_eq!(y.dims().as_slice(), &[2, 2, 2, 2]);
+    #[rustfmt::skip]
+    assert_eq!(
+        y.data::<f32>(),
+        &[4., 5., 0., 1., 4., 5., 2., 3., 10., 11., 6., 7., 10., 11., 8., 9.]
+    );
+
+    // A scalar index removes the axis.
+    let index = Tensor::new(vec![].into(), vec![1i32]);
+    let y = run(Op::Gather(Gather { axis: -1 }), vec![data, index]);
+    assert_eq!(y.dims().as_slice(), &[2, 3]);
+    assert_eq!(y.data::<f32>(), &[1., 3., 5., 7., 9., 11.]);
+
+    // Elements of any type are gathered.
+    let data = Tensor::new(vec![3].into(), vec![7i64, 8, 9]);
+    let indices = Tensor::new(vec![2].into(), vec![2i64, 2]);
+    let y = run(Op::Gather(Gather { axis: 0 }), vec![data, indices]);
+    assert_eq!(y.data::<i64>(), &[9, 9]);
+}
+
+#[test]
+fn gather_elements() {
+    let data = iota(&[3, 3]);
+    let indices = Tensor::new(vec![2, 3].into(), vec![1i64, 2, 0, 2, 0, -3]);
+    let y = run(
+        Op::GatherElements(GatherElements { axis: 0 }),
+        vec![data.clone(), indices.clone()],
+    );
+    assert_eq!(y.dims().as_slice(), &[2, 3]);
+    assert_eq!(y.data::<f32>(), &[3., 7., 2., 6., 1., 2.]);
+
+    let y = run(
+        Op::GatherElements(GatherElements { axis: 1 }),
+        vec![data, indices],
+    );
+    assert_eq!(y.data::<f32>(), &[1., 2., 0., 5., 3., 3.]);
+}
+
+#[test]
+fn gather_nd() {
+    let data = iota(&[2, 2, 2]);
+    let indices = Tensor::new(vec![2, 2].into(), vec![0i64, 1, 1, 0]);
+    let y = run(
+        Op::GatherND(GatherND { batch_dims: 0 }),
+        vec![data.clone(), indices],
+    );
+    assert_eq!(y.dims().as_slice(), &[2, 2]);
+    assert_eq!(y.data::<f32>(), &[2., 3., 4., 5.]);
+
+    // Each batch is indexed by its own indices.
+    let indices = Tensor::new(vec![2, 1, 2].into(), vec![1i64, 0, 0, 1]);
+    let y = run(
+        Op::GatherND(GatherND { batch_dims: 1 }),
+        vec![data, indices],
+    );
+    assert_eq!(y.dims().as_slice(), &[2, 1]);
+    assert_eq!(y.data::<f32>(), &[2., 5.]);
+}
+
+#[test]
+fn scatter_elements() {
+    let data = Tensor::new(vec![2, 3].into(), vec![1.0f32; 6]);
+    let indices = Tensor::new(vec![2, 2].into(), vec![2i64, 0, 2, -1]);
+    let updates = Tensor::new(vec![2, 2].into(), vec![5.0f32, 6., 7., 8.]);
+    let scatter = |reduction| {
+        let op = Op::ScatterElements(ScatterElements { axis: 1, reduction });
+        run(op, vec![data.clone(), indices.clone(), updates.clone()])
+    };
+    assert_eq!(
+        scatter(ScatterReduction::None).data::<f32>(),
+        &[6., 1., 5., 1., 1., 8.]
+    );
+    // Updates to the same element are all reduced.
+    assert_eq!(
+        scatter(ScatterReduction::Add).data::<f32>(),
+        &[7., 1., 6., 1., 1., 16.]
+    );
+    assert_eq!(
+        scatter(ScatterReduction::Mul).data::<f32>(),
+        &[6., 1., 5., 1., 1., 56.]
+    );
+}
+
+#[test]
+fn scatter_nd() {
+    let data = Tensor::new(vec![4, 2].into(), vec![1i64, 2, 3, 4, 5, 6, 7, 8]);
+    let indices = Tensor::new(vec![3, 1].into(), vec![3i64, 0, 3]);
+    let updates = Tensor::new(vec![3, 2].into(), vec![10i64, 20, 30, 40, 50, 60]);
+    let scatter = |reduction| {
+        let op = Op::ScatterND(ScatterND { reduction });
+        run(op, vec![data.clone(), indices.clone(), updates.clone()])
+    };
+    assert_eq!(
+        scatter(ScatterReduction::Add).data::<i64>(),
+        &[31, 42, 3, 4, 5, 6, 67, 88]
+    );
+    assert_eq!(
+        scatter(ScatterReduction::Max).data::<i64>(),
+        &[30, 40, 3, 4, 5, 6, 50, 60]
+    );
+    assert_eq!(
+        scatter(ScatterReduction::Min).data::<i64>(),
+        &[1, 2, 3, 4, 5, 6, 7, 8]
+    );
+
+    // Indices of the full rank update single elements.
+    let data = iota(&[2, 2]);
+    let indices = Tensor::new(vec![2, 2].into(), vec![1i64, 0, 0, 1]);
+    let updates = Tensor::new(vec![2].into(), vec![-1.0f32, -2.]);
+    let y = run(
+        Op::ScatterND(ScatterND::default()),
+        vec![data, indices, updates],
+    );
+    assert_eq!(y.data::<f32>(), &[0., -2., -1., 3.]);
+}
+
+#[test]
+fn top_k() {
+    let x = Tensor::new(vec![2, 4].into(), vec![3.0f32, 1., 4., 1., 5., 9., 2., 6.]);
+    let k = Tensor::new(vec![1].into(), vec![3i64]);
+    let topk = |axis, largest| {
+        let op = Op::TopK(TopK {
+            axis,
+            largest,
+            sorted: true,
+        });
+        run_outputs(op, vec![x.clone(), k.clone()], 2)
+    };
+
+    let ys = topk(-1, true);
+    assert_eq!(ys[0].dims().as_slice(), &[2, 3]);
+    assert_eq!(ys[0].data::<f32>(), &[4., 3., 1., 9., 6., 5.]);
+    // Ties are broken by the smaller index.
+    assert_eq!(ys[1].data::<i64>(), &[2, 0, 1, 1, 3, 0]);
+
+    let ys = topk(1, false);
+    assert_eq!(ys[0].data::<f32>(), &[1., 1., 3., 2., 5., 6.]);
+    assert_eq!(ys[1].data::<i64>(), &[1, 3, 0, 2, 0, 3]);
+
+    let k = Tensor::new(vec![1].into(), vec![1i64]);
+    let op = Op::TopK(TopK {
+        axis: 0,
+        largest: true,
+        sorted: true,
+    });
+    let ys = run_outputs(op, vec![x, k], 2);
+    assert_eq!(ys[0].dims().as_slice(), &[1, 4]);
+    assert_eq!(ys[0].data::<f32>(), &[5., 9., 4., 6.]);
+    assert_eq!(ys[1].data::<i64>(), &[1, 1, 0, 1]);
+}
+
+#[test]
+fn one_hot() {
+    let indices = Tensor::new(vec![3].into(), vec![0i64, -1, 5]);
+    let depth = Tensor::new(vec![].into(), vec![3i64]);
+    let values = Tensor::new(vec![2].into(), vec![0.0f32, 2.]);
+    let y = run(
+        Op::OneHot(OneHot { axis: -1 }),
+        vec![indices.clone(), depth.clone(), values.clone()],
+    );
+    assert_eq!(y.dims().as_slice(), &[3, 3]);
+    // Indices out of range have no on value.
+    assert_eq!(y.data::<f32>(), &[2., 0., 0., 0., 0., 2., 0., 0., 0.]);
+
+    let y = run(Op::OneHot(OneHot { axis: 0 }), vec![indices, depth, values]);
+    assert_eq!(y.dims().as_slice(), &[3, 3]);
+    assert_eq!(y.data::<f32>(), &[2., 0., 0., 0., 0., 0., 0., 2., 0.]);
+}
+
+#[test]
+fn non_zero() {
+    let x = Tensor::new(vec![2, 3].into(), vec![0.0f32, 1., 0., 2., 0., 3.]);
+    let y = run(Op::NonZero, vec![x]);
+    assert_eq!(y.dims().as_slice(), &[2, 3]);
+    assert_eq!(y.data::<i64>(), &[0, 1, 1, 1, 0, 2]);
+
+    let x = Tensor::new(vec![3].into(), vec![0i64, 0, 0]);
+    let y = run(Op::NonZero, vec![x]);
+    assert_eq!(y.dims().as_slice(), &[1, 0]);
+}
+
+#[test]
+fn cum_sum() {
+    let x = iota(&[2, 3]);
+    let cumsum = |axis: i64, exclusive, reverse| {
+        let op = Op::CumSum(CumSum { exclusive, reverse });
+        let axis = Tensor::new(vec![].into(), vec![axis]);
+        run(op, vec![x.clone(), axis])
+    };
+    assert_eq!(
+        cumsum(1, false, false).data::<f32>(),
+        &[0., 1., 3., 3., 7., 12.]
+    );
+    assert_eq!(
+        cumsum(0, false, false).data::<f32>(),
+        &[0., 1., 2., 3., 5., 7.]
+    );
+    assert_eq!(
+        cumsum(-1, true, false).data::<f32>(),
+        &[0., 0., 1., 0., 3., 7.]
+    );
+    assert_eq!(
+        cumsum(1, false, true).data::<f32>(),
+        &[3., 3., 2., 12., 9., 5.]
+    );
+    assert_eq!(
+        cumsum(1, true, true).data::<f32>(),
+        &[3., 2., 0., 9., 5., 0.]
+    );
+}
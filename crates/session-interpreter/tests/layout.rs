@@ -0,0 +1,129 @@
+mod common;
+
+use altius_core::{
+    op::{DepthToSpace, Op, Pad, SpaceToDepth},
+    tensor::Tensor,
+};
+use common::{iota, run};
+
+fn pad(mode: &str) -> Op {
+    Op::Pad(Pad {
+        mode: mode.into(),
+        ..Default::default()
+    })
+}
+
+fn pads(pads: &[i64]) -> Tensor {
+    Tensor::new(vec![pads.len()].into(), pads.to_vec())
+}
+
+#[test]
+fn pad_modes() {
+    // [[0, 1, 2],
+    //  [3, 4, 5]]
+    let x = iota(&[2, 3]);
+    let p = pads(&[1, 2, 0, 1]);
+
+    let y = run(pad("constant"), vec![x.clone(), p.clone()]);
+    assert_eq!(y.dims().as_slice(), &[3, 6]);
+    #[rustfmt::skip]
+    assert_eq!(y.data::<f32>(), &[
+        0., 0., 0., 0., 0., 0.,
+        0., 0., 0., 1., 2., 0.,
+        0., 0., 3., 4., 5., 0.,
+    ]);
+
+    let value = Tensor::new(vec![].into(), vec![-1.0f32]);
+    let y = run(pad("constant"), vec![x.clone(), p.clone(), value]);
+    assert_eq!(&y.data::<f32>()[..7], &[-1., -1., -1., -1., -1., -1., -1.]);
+
+    let y = run(pad("reflect"), vec![x.clone(), p.clone()]);
+    #[rustfmt::skip]
+    assert_eq!(y.data::<f32>(), &[
+        5., 4., 3., 4., 5., 4.,
+        2., 1., 0., 1., 2., 1.,
+        5., 4., 3., 4., 5., 4.,
+    ]);
+
+    let y = run(pad("edge"), vec![x.clone(), p.clone()]);
+    #[rustfmt::skip]
+    assert_eq!(y.data::<f32>(), &[
+        0., 0., 0., 1., 2., 2.,
+        0., 0., 0., 1., 2., 2.,
+        3., 3., 3., 4., 5., 5.,
+    ]);
+
+    let y = run(pad("wrap"), vec![x, p]);
+    #[rustfmt::skip]
+    assert_eq!(y.data::<f32>(), &[
+        4., 5., 3., 4., 5., 3.,
+        1., 2., 0., 1., 2., 0.,
+        4., 5., 3., 4., 5., 3.,
+    ]);
+}
+
+#[test]
+fn pad_axes_and_crop() {
+    let x = iota(&[2, 2, 3]);
+    // Pads only the last axis, cropping its first element.
+    let axes = Tensor::new(vec![1].into(), vec![-1i64]);
+    let value = Tensor::new(vec![].into(), vec![9.0f32]);
+    let y = run(pad("constant"), vec![x, pads(&[-1, 2]), value, axes]);
+    assert_eq!(y.dims().as_slice(), &[2, 2, 4]);
+    #[rustfmt::skip]
+    assert_eq!(y.data::<f32>(), &[
+        1., 2., 9., 9., 4., 5., 9., 9.,
+        7., 8., 9., 9., 10., 11., 9., 9.,
+    ]);
+
+    // Elements of any type are padded.
+    let x = Tensor::new(vec![3].into(), vec![1i64, 2, 3]);
+    let y = run(pad("reflect"), vec![x, pads(&[2, 2])]);
+    assert_eq!(y.data::<i64>(), &[3, 2, 1, 2, 3, 2, 1]);
+}
+
+#[test]
+fn depth_to_space() {
+    let x = iota(&[1, 8, 2, 3]);
+    for mode in ["DCR", "CRD"] {
+        let op = Op::DepthToSpace(DepthToSpace {
+            blocksize: 2,
+            mode: mode.into(),
+        });
+        let y = run(op, vec![x.clone()]);
+        assert_eq!(y.dims().as_slice(), &[1, 2, 4, 6]);
+        let y = y.data::<f32>();
+        for c in 0..2 {
+            for h in 0..4 {
+                for w in 0..6 {
+                    let (b1, b2) = (h % 2, w % 2);
+                    let ic = match mode {
+                        "DCR" => (b1 * 2 + b2) * 2 + c,
+                        _ => c * 4 + b1 * 2 + b2,
+                    };
+                    let expected = (ic * 6 + h / 2 * 3 + w / 2) as f32;
+                    assert_eq!(y[(c * 4 + h) * 6 + w], expected, "{mode} {c} {h} {w}");
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn space_to_depth() {
+    let x = iota(&[2, 2, 4, 6]);
+    let y = run(
+        Op::SpaceToDepth(SpaceToDepth { blocksize: 2 }),
+        vec![x.clone()],
+    );
+    assert_eq!(y.dims().as_slice(), &[2, 8, 2, 3]);
+
+    // DepthToSpace in the DCR mode is the inverse.
+    let op = Op::DepthToSpace(DepthToSpace {
+        blocksize: 2,
+        mode: "DCR".into(),
+    });
+    let z = run(op, vec![y]);
+    assert_eq!(z.dims(), x.dims());
+    assert_eq!(z.data::<f32>(), x.data::<f32>());
+}
@@ -0,0 +1,133 @@
+use std::sync::{Arc, Mutex};
+
+use altius_core::{
+    analysis::shape::ShapeError,
+    custom_op::{CustomOp, CustomOpError},
+    fixed_dim::FixedDimensions,
+    model::Model,
+    node::Node,
+    op::{Custom, Op},
+    tensor::{Tensor, TensorElemType, TypedFixedShape, TypedShape},
+};
+use altius_session_interpreter::InterpreterSessionBuilder;
+
+/// Y = X, recording where the input and output live on each run.
+#[derive(Clone, Default)]
+struct Record {
+    /// `(input address, output address, whether the output is a view)` for each run.
+    runs: Arc<Mutex<Vec<(usize, usize, bool)>>>,
+}
+
+impl CustomOp for Record {
+    fn compute_output_shapes(
+        &self,
+        _op: &Custom,
+        inputs: &[&Tensor],
+        _num_outputs: usize,
+    ) -> Result<Vec<TypedFixedShape>, ShapeError> {
+        Ok(vec![TypedFixedShape::new(
+            inputs[0].dims().clone(),
+            inputs[0].elem_ty(),
+        )])
+    }
+
+    fn compute(
+        &self,
+        _op: &Custom,
+        inputs: &[&Tensor],
+        outputs: &mut [Tensor],
+    ) -> Result<(), CustomOpError> {
+        outputs[0]
+            .data_mut::<f32>()
+            .copy_from_slice(inputs[0].data::<f32>());
+        self.runs.lock().unwrap().push((
+            inputs[0].data_as_ptr() as usize,
+            outputs[0].data_as_ptr() as usize,
+            outputs[0].is_view(),
+        ));
+        Ok(())
+    }
+}
+
+const DIMS: [usize; 2] = [16, 256];
+
+/// `y = relu(record(x + b))`, all of `DIMS`.
+fn model() -> Model {
+    let mut model = Model {
+        opset_version: 13,
+        ..Default::default()
+    };
+    let x = model.graph.values.new_val_named_and_shaped(
+        "x",
+        TypedShape::new(
+            FixedDimensions::from(DIMS.to_vec()).into(),
+            TensorElemType::F32,
+        ),
+    );
+    let b = model.graph.values.new_val_named("b");
+    let [add, record] = ["add", "record"].map(|name| model.graph.values.new_val_named(name));
+    let y = model.graph.values.new_val_named("y");
+
+    model.graph.inits.insert(
+        b,
+        Tensor::new(
+            DIMS.to_vec().into(),
+            (0..DIMS[0] * DIMS[1])
+                .map(|i| (i % 7) as f32 - 3.0)
+                .collect(),
+        ),
+    );
+    model
+        .graph
+        .add_node(Node::new(Op::Add).with_ins(vec![x, b]).with_out(add));
+    model.graph.add_node(
+        Node::new(Op::Custom(Custom {
+            domain: "com.example".into(),
+            op_type: "Record".into(),
+            attrs: Default::default(),
+        }))
+        .with_in(add)
+        .with_out(record),
+    );
+    model
+        .graph
+        .add_node(Node::new(Op::ReLU).with_in(record).with_out(y));
+    model.graph.inputs.push(x);
+    model.graph.outputs.push(y);
+    model
+}
+
+#[test]
+fn intermediate_values_stay_in_arena() {
+    let record = Record::default();
+    let sess = InterpreterSessionBuilder::new(model())
+        .with_custom_op("com.example", "Record", record.clone())
+        .with_intra_op_num_threads(1)
+        .build()
+        .unwrap();
+
+    let len = DIMS[0] * DIMS[1];
+    let x = Tensor::new(
+        DIMS.to_vec().into(),
+        (0..len).map(|i| (i % 5) as f32 - 2.0).collect(),
+    );
+    let expected = (0..len)
+        .map(|i| ((i % 5) as f32 - 2.0 + (i % 7) as f32 - 3.0).max(0.0))
+        .collect::<Vec<_>>();
+
+    for _ in 0..3 {
+        let y = sess.run(vec![x.clone()]).unwrap();
+        assert_eq!(y[0].data::<f32>(), expected.as_slice());
+        // The graph output is handed over to the caller and must not alias the arena.
+        assert!(!y[0].is_view());
+    }
+
+    let runs = record.runs.lock().unwrap();
+    assert_eq!(runs.len(), 3);
+    let (input, output, is_view) = runs[0];
+    assert!(is_view);
+    // Both values are alive while the op runs, so they must not overlap.
+    assert!(input.abs_diff(output) >= len * std::mem::size_of::<f32>());
+    // Steady-state runs reuse the same regions.
+    assert!(runs.iter().all(|&run| run == (input, output, true)));
+}
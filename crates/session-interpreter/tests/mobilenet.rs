@@ -32,9 +32,9 @@ fn mobilenet() {
             .clone()
             .into_raw_vec()
             .into_iter()
-            .chain(image.clone().into_iter())
-            .chain(image.clone().into_iter())
-            .chain(image.into_iter())
+            .chain(image.clone())
+            .chain(image.clone())
+            .chain(image)
             .collect::<Vec<_>>(),
     );
 
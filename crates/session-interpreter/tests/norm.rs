@@ -0,0 +1,92 @@
+mod common;
+
+use altius_core::{
+    op::{GroupNormalization, InstanceNormalization, Op},
+    tensor::Tensor,
+};
+use common::run;
+
+fn input() -> Tensor {
+    let data = (0..48)
+        .map(|i| (i * 7 % 13) as f32 * 0.3 - 1.5)
+        .collect::<Vec<_>>();
+    Tensor::new(vec![2, 4, 2, 3].into(), data)
+}
+
+/// Normalizes each group of `channels_per_group` channels, and applies the scale and bias
+/// of each channel.
+fn reference(x: &Tensor, channels_per_group: usize, scale: &[f32], bias: &[f32]) -> Vec<f32> {
+    let channels = x.dims()[1];
+    let spatial = x.dims()[2..].iter().product::<usize>();
+    let group_len = channels_per_group * spatial;
+    let mut y = vec![];
+    for (g, group) in x.data::<f32>().chunks(group_len).enumerate() {
+        let mean = group.iter().sum::<f32>() / group_len as f32;
+        let var = group.iter().map(|x| (x - mean).powi(2)).sum::<f32>() / group_len as f32;
+        for (i, &x) in group.iter().enumerate() {
+            let c = (g * channels_per_group + i / spatial) % channels;
+            y.push((x - mean) / (var + 1e-5).sqrt() * scale[c] + bias[c]);
+        }
+    }
+    y
+}
+
+fn assert_close(y: &Tensor, expected: &[f32]) {
+    assert_eq!(y.data::<f32>().len(), expected.len());
+    for (&y, &e) in y.data::<f32>().iter().zip(expected) {
+        assert!(
+            (y - e).abs() <= 1e-4 + 1e-4 * e.abs(),
+            "{y} vs expected {e}"
+        );
+    }
+}
+
+#[test]
+fn instance_normalization() {
+    let scale = [1.0f32, 0.5, -2.0, 3.0];
+    let bias = [0.0f32, 1.0, -1.0, 0.25];
+    let y = run(
+        Op::InstanceNormalization(InstanceNormalization { epsilon: 1e-5 }),
+        vec![
+            input(),
+            Tensor::new(vec![4].into(), scale.to_vec()),
+            Tensor::new(vec![4].into(), bias.to_vec()),
+        ],
+    );
+    assert_eq!(y.dims(), input().dims());
+    assert_close(&y, &reference(&input(), 1, &scale, &bias));
+}
+
+#[test]
+fn group_normalization() {
+    let op = Op::GroupNormalization(GroupNormalization {
+        epsilon: 1e-5,
+        num_groups: 2,
+        stash_type: 1,
+    });
+
+    // From opset version 21, scale and bias are given per channel.
+    let scale = [1.0f32, 0.5, -2.0, 3.0];
+    let bias = [0.0f32, 1.0, -1.0, 0.25];
+    let y = run(
+        op.clone(),
+        vec![
+            input(),
+            Tensor::new(vec![4].into(), scale.to_vec()),
+            Tensor::new(vec![4].into(), bias.to_vec()),
+        ],
+    );
+    assert_close(&y, &reference(&input(), 2, &scale, &bias));
+
+    // Until then, they are given per group.
+    let y = run(
+        op,
+        vec![
+            input(),
+            Tensor::new(vec![2].into(), vec![2.0f32, -1.0]),
+            Tensor::new(vec![2].into(), vec![0.5f32, 0.0]),
+        ],
+    );
+    let expected = reference(&input(), 2, &[2., 2., -1., -1.], &[0.5, 0.5, 0., 0.]);
+    assert_close(&y, &expected);
+}
@@ -1,5 +1,14 @@
-use altius_core::{fixed_dim::FixedDimensions, model::Model, node::Node, op::Op, tensor::Tensor};
+mod common;
+
+use altius_core::{
+    fixed_dim::FixedDimensions,
+    model::Model,
+    node::Node,
+    op::Op,
+    tensor::{Tensor, TensorElemTypeExt},
+};
 use altius_session_interpreter::InterpreterSessionBuilder;
+use common::try_run;
 
 macro_rules! test_op {
     ($name:ident, $op:ident, $shape:expr) => {
@@ -74,6 +83,157 @@ test_op!(test_op_div_1, op_div, vec![1, 2]);
 test_op!(test_op_div_2, op_div, vec![3, 1, 10]);
 test_op!(test_op_div_3, op_div, vec![128, 3, 224, 224]);
 
+fn tensor<T: TensorElemTypeExt>(dims: Vec<usize>, data: Vec<T>) -> Tensor {
+    Tensor::new(dims.into(), data)
+}
+
+#[test]
+fn broadcast_ranks() {
+    let a = tensor(vec![2, 1, 3], vec![0.0f32, 1., 2., 3., 4., 5.]);
+    let b = tensor(vec![2, 1], vec![10.0f32, 20.]);
+    let y = try_run(Op::Add, vec![a, b]).unwrap();
+    assert_eq!(y.dims().as_slice(), &[2, 2, 3]);
+    assert_eq!(
+        y.data::<f32>(),
+        &[10., 11., 12., 20., 21., 22., 13., 14., 15., 23., 24., 25.]
+    );
+
+    // Rank 1 and scalars.
+    let a = tensor(vec![3], vec![1.0f32, 2., 3.]);
+    let b = tensor(vec![1], vec![2.0f32]);
+    let y = try_run(Op::Sub, vec![b, a]).unwrap();
+    assert_eq!(y.data::<f32>(), &[1., 0., -1.]);
+    let a = tensor(vec![], vec![6.0f32]);
+    let b = tensor(vec![], vec![3.0f32]);
+    let y = try_run(Op::Div, vec![a, b]).unwrap();
+    assert!(y.dims().is_scalar());
+    assert_eq!(y.data::<f32>(), &[2.]);
+
+    // Only the middle axis is broadcast.
+    let a = tensor(vec![2, 3, 2], (0..12).map(|i| i as f32).collect());
+    let b = tensor(vec![2, 1, 2], vec![1.0f32, 2., 3., 4.]);
+    let y = try_run(Op::Mul, vec![a, b]).unwrap();
+    assert_eq!(
+        y.data::<f32>(),
+        &[0., 2., 2., 6., 4., 10., 18., 28., 24., 36., 30., 44.]
+    );
+}
+
+#[test]
+fn broadcast_integers() {
+    let a = tensor(vec![2, 3], vec![1i64, 2, 3, 4, 5, 6]);
+    let b = tensor(vec![3], vec![10i64, 20, 30]);
+    let y = try_run(Op::Mul, vec![a, b]).unwrap();
+    assert_eq!(y.data::<i64>(), &[10, 40, 90, 40, 100, 180]);
+
+    let a = tensor(vec![2, 1], vec![7i32, -7]);
+    let b = tensor(vec![2], vec![2i32, 3]);
+    let y = try_run(Op::Div, vec![a, b]).unwrap();
+    assert_eq!(y.data::<i32>(), &[3, 2, -3, -2]);
+
+    let a = tensor(vec![1, 2], vec![1u8, 2]);
+    let b = tensor(vec![2, 1], vec![10u8, 20]);
+    let y = try_run(Op::Add, vec![a, b]).unwrap();
+    assert_eq!(y.data::<u8>(), &[11, 12, 21, 22]);
+}
+
+#[test]
+fn broadcast_incompatible() {
+    let a = tensor(vec![2, 3], vec![0.0f32; 6]);
+    let b = tensor(vec![2], vec![0.0f32; 2]);
+    assert!(try_run(Op::Add, vec![a, b]).is_err());
+}
+
+#[test]
+fn comparisons() {
+    let a = tensor(vec![3, 1], vec![1.0f32, 2., 3.]);
+    let b = tensor(vec![3], vec![1.0f32, 2., 3.]);
+    let cases: [(Op, [bool; 9]); 5] = [
+        (
+            Op::Equal,
+            [true, false, false, false, true, false, false, false, true],
+        ),
+        (
+            Op::Less,
+            [false, true, true, false, false, true, false, false, false],
+        ),
+        (
+            Op::LessOrEqual,
+            [true, true, true, false, true, true, false, false, true],
+        ),
+        (
+            Op::Greater,
+            [false, false, false, true, false, false, true, true, false],
+        ),
+        (
+            Op::GreaterOrEqual,
+            [true, false, false, true, true, false, true, true, true],
+        ),
+    ];
+    for (op, expected) in cases {
+        let name = op.name();
+        let y = try_run(op, vec![a.clone(), b.clone()]).unwrap();
+        assert_eq!(y.dims().as_slice(), &[3, 3]);
+        assert_eq!(y.data::<bool>(), &expected, "{name}");
+    }
+
+    let a = tensor(vec![2, 2], vec![1i64, 5, -3, 0]);
+    let b = tensor(vec![], vec![0i64]);
+    let y = try_run(Op::Greater, vec![a, b]).unwrap();
+    assert_eq!(y.data::<bool>(), &[true, true, false, false]);
+}
+
+#[test]
+fn logical() {
+    let a = tensor(vec![2, 1], vec![false, true]);
+    let b = tensor(vec![2], vec![false, true]);
+    let y = try_run(Op::And, vec![a.clone(), b.clone()]).unwrap();
+    assert_eq!(y.data::<bool>(), &[false, false, false, true]);
+    let y = try_run(Op::Or, vec![a.clone(), b.clone()]).unwrap();
+    assert_eq!(y.data::<bool>(), &[false, true, true, true]);
+    let y = try_run(Op::Xor, vec![a.clone(), b]).unwrap();
+    assert_eq!(y.data::<bool>(), &[false, true, true, false]);
+    let y = try_run(Op::Not, vec![a]).unwrap();
+    assert_eq!(y.data::<bool>(), &[true, false]);
+}
+
+#[test]
+fn pow() {
+    let a = tensor(vec![2, 2], vec![1.0f32, 2., 3., 4.]);
+    let b = tensor(vec![2], vec![2.0f32, 0.5]);
+    let y = try_run(Op::Pow, vec![a.clone(), b]).unwrap();
+    assert!(allclose(y.data::<f32>(), &[1., 2.0f32.sqrt(), 9., 2.]));
+
+    let b = tensor(vec![2, 1, 1], vec![3i64, -1]);
+    let y = try_run(Op::Pow, vec![a, b]).unwrap();
+    assert!(allclose(
+        y.data::<f32>(),
+        &[1., 8., 27., 64., 1., 0.5, 1. / 3., 0.25]
+    ));
+
+    let a = tensor(vec![3], vec![2i64, 3, 4]);
+    let b = tensor(vec![], vec![3i64]);
+    let y = try_run(Op::Pow, vec![a, b]).unwrap();
+    assert_eq!(y.data::<i64>(), &[8, 27, 64]);
+}
+
+#[test]
+fn where_() {
+    let cond = tensor(vec![2, 1], vec![true, false]);
+    let x = tensor(vec![3], vec![1i64, 2, 3]);
+    let y = tensor(vec![], vec![-1i64]);
+    let out = try_run(Op::Where, vec![cond, x, y]).unwrap();
+    assert_eq!(out.dims().as_slice(), &[2, 3]);
+    assert_eq!(out.data::<i64>(), &[1, 2, 3, -1, -1, -1]);
+
+    let cond = tensor(vec![1, 2, 1, 2], vec![true, false, false, true]);
+    let x = tensor(vec![2, 1, 1, 1], vec![1.0f32, 2.]);
+    let y = tensor(vec![2, 1, 2], vec![10.0f32, 20., 30., 40.]);
+    let out = try_run(Op::Where, vec![cond, x, y]).unwrap();
+    assert_eq!(out.dims().as_slice(), &[2, 2, 1, 2]);
+    assert_eq!(out.data::<f32>(), &[1., 20., 30., 1., 2., 20., 30., 2.]);
+}
+
 #[cfg(test)]
 fn allclose(x: &[f32], y: &[f32]) -> bool {
     let atol = 1e-5;
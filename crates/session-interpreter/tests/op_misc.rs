@@ -0,0 +1,221 @@
+mod common;
+
+use altius_core::{
+    op::{Constant, NonMaxSuppression, Op, Reduce},
+    tensor::{Tensor, TensorElemTypeExt},
+};
+use common::run_with_omitted;
+
+fn scalar<T: TensorElemTypeExt>(x: T) -> Option<Tensor> {
+    Some(Tensor::new(vec![].into(), vec![x]))
+}
+
+#[test]
+fn clip() {
+    let x = || {
+        Some(Tensor::new(
+            vec![5].into(),
+            vec![-2.0f32, -0.5, 0., 0.5, 2.],
+        ))
+    };
+    let y = run_with_omitted(Op::Clip, vec![x(), scalar(-1.0f32), scalar(1.0f32)]);
+    assert_eq!(y.data::<f32>(), &[-1., -0.5, 0., 0.5, 1.]);
+
+    let y = run_with_omitted(Op::Clip, vec![x(), None, scalar(0.0f32)]);
+    assert_eq!(y.data::<f32>(), &[-2., -0.5, 0., 0., 0.]);
+
+    let y = run_with_omitted(Op::Clip, vec![x()]);
+    assert_eq!(y.data::<f32>(), &[-2., -0.5, 0., 0.5, 2.]);
+
+    let x = Tensor::new(vec![2, 2].into(), vec![-5i64, 3, 10, 7]);
+    let y = run_with_omitted(Op::Clip, vec![Some(x), scalar(0i64), scalar(8i64)]);
+    assert_eq!(y.dims().as_slice(), &[2, 2]);
+    assert_eq!(y.data::<i64>(), &[0, 3, 8, 7]);
+}
+
+#[test]
+fn reduce_min() {
+    let x = || {
+        Some(Tensor::new(
+            vec![2, 3].into(),
+            vec![3.0f32, 1., 2., -1., 5., 0.],
+        ))
+    };
+    let y = run_with_omitted(
+        Op::ReduceMin(Reduce {
+            axes: vec![1],
+            keep_dims: true,
+            ..Default::default()
+        }),
+        vec![x()],
+    );
+    assert_eq!(y.dims().as_slice(), &[2, 1]);
+    assert_eq!(y.data::<f32>(), &[1., -1.]);
+
+    let y = run_with_omitted(
+        Op::ReduceMin(Reduce {
+            axes: vec![-2],
+            keep_dims: false,
+            ..Default::default()
+        }),
+        vec![x()],
+    );
+    assert_eq!(y.dims().as_slice(), &[3]);
+    assert_eq!(y.data::<f32>(), &[-1., 1., 0.]);
+
+    // Empty axes reduce all the axes.
+    let x = Tensor::new(vec![2, 2].into(), vec![4i64, -3, 8, 2]);
+    let y = run_with_omitted(
+        Op::ReduceMin(Reduce {
+            axes: vec![],
+            keep_dims: true,
+            ..Default::default()
+        }),
+        vec![Some(x)],
+    );
+    assert_eq!(y.dims().as_slice(), &[1, 1]);
+    assert_eq!(y.data::<i64>(), &[-3]);
+}
+
+#[test]
+fn round() {
+    let x = Tensor::new(vec![6].into(), vec![0.5f32, 1.5, 2.5, -0.5, -1.2, 2.7]);
+    let y = run_with_omitted(Op::Round, vec![Some(x)]);
+    assert_eq!(y.data::<f32>(), &[0., 2., 2., -0., -1., 3.]);
+}
+
+#[test]
+fn exp() {
+    let x = Tensor::new(vec![3].into(), vec![0.0f32, 1., -2.]);
+    let y = run_with_omitted(Op::Exp, vec![Some(x)]);
+    assert!(y.allclose(&[1.0f32, 1f32.exp(), (-2f32).exp()]));
+}
+
+#[test]
+fn range() {
+    let y = run_with_omitted(Op::Range, vec![scalar(1i64), scalar(10i64), scalar(3i64)]);
+    assert_eq!(y.data::<i64>(), &[1, 4, 7]);
+
+    let y = run_with_omitted(Op::Range, vec![scalar(10i32), scalar(4i32), scalar(-3i32)]);
+    assert_eq!(y.data::<i32>(), &[10, 7]);
+
+    let y = run_with_omitted(
+        Op::Range,
+        vec![scalar(0.0f32), scalar(1.0f32), scalar(0.25f32)],
+    );
+    assert_eq!(y.data::<f32>(), &[0., 0.25, 0.5, 0.75]);
+
+    let y = run_with_omitted(Op::Range, vec![scalar(5i64), scalar(1i64), scalar(1i64)]);
+    assert_eq!(y.dims().as_slice(), &[0]);
+}
+
+#[test]
+fn tile() {
+    let x = Tensor::new(vec![2, 2].into(), vec![1.0f32, 2., 3., 4.]);
+    let repeats = Tensor::new(vec![2].into(), vec![2i64, 3]);
+    let y = run_with_omitted(Op::Tile, vec![Some(x), Some(repeats)]);
+    assert_eq!(y.dims().as_slice(), &[4, 6]);
+    #[rustfmt::skip]
+    assert_eq!(
+        y.data::<f32>(),
+        &[
+            1., 2., 1., 2., 1., 2.,
+            3., 4., 3., 4., 3., 4.,
+            1., 2., 1., 2., 1., 2.,
+            3., 4., 3., 4., 3., 4.,
+        ]
+    );
+
+    let x = Tensor::new(vec![3].into(), vec![true, false, true]);
+    let repeats = Tensor::new(vec![1].into(), vec![2i64]);
+    let y = run_with_omitted(Op::Tile, vec![Some(x), Some(repeats)]);
+    assert_eq!(y.data::<bool>(), &[true, false, true, true, false, true]);
+}
+
+#[test]
+fn constant() {
+    let value = Tensor::new(vec![2].into(), vec![3i64, 4]);
+    let y = run_with_omitted(
+        Op::Constant(Constant {
+            value: value.clone(),
+        }),
+        vec![],
+    );
+    assert_eq!(y, value);
+}
+
+#[test]
+fn non_max_suppression() {
+    // The example of the ONNX backend test `nonmaxsuppression_suppress_by_IOU`.
+    #[rustfmt::skip]
+    let boxes = || Some(Tensor::new(
+        vec![1, 6, 4].into(),
+        vec![
+            0.0f32, 0.0, 1.0, 1.0,
+            0.0, 0.1, 1.0, 1.1,
+            0.0, -0.1, 1.0, 0.9,
+            0.0, 10.0, 1.0, 11.0,
+            0.0, 10.1, 1.0, 11.1,
+            0.0, 100.0, 1.0, 101.0,
+        ],
+    ));
+    let scores = || {
+        Some(Tensor::new(
+            vec![1, 1, 6].into(),
+            vec![0.9f32, 0.75, 0.6, 0.95, 0.5, 0.3],
+        ))
+    };
+    let nms = || {
+        Op::NonMaxSuppression(NonMaxSuppression {
+            center_point_box: false,
+        })
+    };
+
+    let y = run_with_omitted(
+        nms(),
+        vec![
+            boxes(),
+            scores(),
+            scalar(3i64),
+            scalar(0.5f32),
+            scalar(0.0f32),
+        ],
+    );
+    assert_eq!(y.dims().as_slice(), &[3, 3]);
+    assert_eq!(y.data::<i64>(), &[0, 0, 3, 0, 0, 0, 0, 0, 5]);
+
+    // Boxes scored 0.4 or lower are ignored.
+    let y = run_with_omitted(
+        nms(),
+        vec![
+            boxes(),
+            scores(),
+            scalar(3i64),
+            scalar(0.5f32),
+            scalar(0.4f32),
+        ],
+    );
+    assert_eq!(y.data::<i64>(), &[0, 0, 3, 0, 0, 0]);
+
+    // No boxes are selected without `max_output_boxes_per_class`.
+    let y = run_with_omitted(nms(), vec![boxes(), scores()]);
+    assert_eq!(y.dims().as_slice(), &[0, 3]);
+
+    #[rustfmt::skip]
+    let boxes = Tensor::new(
+        vec![1, 3, 4].into(),
+        vec![
+            0.5f32, 0.5, 1.0, 1.0,
+            0.5, 0.6, 1.0, 1.0,
+            0.5, 10.5, 1.0, 1.0,
+        ],
+    );
+    let scores = Tensor::new(vec![1, 1, 3].into(), vec![0.9f32, 0.95, 0.5]);
+    let y = run_with_omitted(
+        Op::NonMaxSuppression(NonMaxSuppression {
+            center_point_box: true,
+        }),
+        vec![Some(boxes), Some(scores), scalar(3i64), scalar(0.5f32)],
+    );
+    assert_eq!(y.data::<i64>(), &[0, 0, 1, 0, 0, 2]);
+}
@@ -0,0 +1,116 @@
+mod common;
+
+use altius_core::{
+    op::{Elu, Op, Selu},
+    tensor::{Tensor, TensorElemTypeExt},
+};
+use common::run;
+
+/// Inputs over a wide range, whose length isn't a multiple of SIMD lanes.
+fn inputs() -> Vec<f32> {
+    (-500..=500)
+        .map(|i| i as f32 * 0.0421)
+        .chain([0., -0., 1e-3, -1e-3, 30., -30., 100., -100., 1e4, -1e4])
+        .collect()
+}
+
+/// Checks `op` computes `f` of every element of `x`.
+fn check(op: Op, x: Vec<f32>, f: impl Fn(f32) -> f32) {
+    let name = op.name();
+    let y = run(op, vec![Tensor::new(vec![x.len()].into(), x.clone())]);
+    for (&x, &y) in x.iter().zip(y.data::<f32>()) {
+        let expected = f(x);
+        assert!(
+            (expected.is_nan() && y.is_nan())
+                || expected == y
+                || (y - expected).abs() <= 1e-5 + 1e-5 * expected.abs(),
+            "{name}({x}): {y} vs expected {expected}"
+        );
+    }
+}
+
+#[test]
+fn elementary() {
+    check(Op::Abs, inputs(), f32::abs);
+    check(Op::Neg, inputs(), |x| -x);
+    check(Op::Reciprocal, inputs(), f32::recip);
+    check(Op::Floor, inputs(), f32::floor);
+    check(Op::Ceil, inputs(), f32::ceil);
+    check(
+        Op::Sign,
+        inputs(),
+        |x| if x == 0. { 0. } else { x.signum() },
+    );
+}
+
+#[test]
+fn log() {
+    let mut x = inputs().into_iter().map(f32::abs).collect::<Vec<_>>();
+    x.extend([
+        0.,
+        -1.,
+        f32::MIN_POSITIVE,
+        1e-40,
+        f32::MAX,
+        f32::INFINITY,
+        f32::NAN,
+    ]);
+    check(Op::Log, x, f32::ln);
+}
+
+#[test]
+fn trigonometric() {
+    let mut x = inputs();
+    x.extend([1e6, -3e5, f32::INFINITY, f32::NAN]);
+    check(Op::Sin, x.clone(), f32::sin);
+    check(Op::Cos, x, f32::cos);
+}
+
+#[test]
+fn activations() {
+    let softplus = |x: f32| x.max(0.) + (-x.abs()).exp().ln_1p();
+    check(Op::Softplus, inputs(), softplus);
+    check(Op::Softsign, inputs(), |x| x / (1. + x.abs()));
+    check(Op::Mish, inputs(), |x| x * softplus(x).tanh());
+    check(Op::HardSwish, inputs(), |x| {
+        x * (x / 6. + 0.5).clamp(0., 1.)
+    });
+    check(Op::Elu(Elu { alpha: 0.5 }), inputs(), |x| {
+        if x < 0. {
+            0.5 * x.exp_m1()
+        } else {
+            x
+        }
+    });
+    let Selu { alpha, gamma } = Selu::default();
+    check(Op::Selu(Selu::default()), inputs(), |x| {
+        gamma * if x <= 0. { alpha * x.exp_m1() } else { x }
+    });
+}
+
+#[test]
+fn integers() {
+    let x = Tensor::new(vec![4].into(), vec![-3i64, 0, 5, i64::MIN]);
+    let y = run(Op::Abs, vec![x.clone()]);
+    assert_eq!(y.data::<i64>(), &[3, 0, 5, i64::MIN]);
+    let y = run(Op::Neg, vec![x.clone()]);
+    assert_eq!(y.data::<i64>(), &[3, 0, -5, i64::MIN]);
+    let y = run(Op::Sign, vec![x]);
+    assert_eq!(y.data::<i64>(), &[-1, 0, 1, -1]);
+}
+
+#[test]
+fn prelu() {
+    let x = Tensor::new(
+        vec![2, 3, 2].into(),
+        vec![-1.0f32, 1., -2., 2., -3., 3., -4., 4., -5., 5., -6., 6.],
+    );
+    // The slope is broadcast along the channel axis.
+    let slope = Tensor::new(vec![3, 1].into(), vec![0.1f32, 0.2, 0.3]);
+    let y = run(Op::PRelu, vec![x, slope]);
+    assert_eq!(y.dims().as_slice(), &[2, 3, 2]);
+    let expected = [-0.1f32, 1., -0.4, 2., -0.9, 3., -0.4, 4., -1., 5., -1.8, 6.];
+    for (&y, &e) in y.data::<f32>().iter().zip(expected.iter()) {
+        assert!(f32::close(y, e), "{y} vs {e}");
+    }
+}
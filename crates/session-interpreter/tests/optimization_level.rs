@@ -0,0 +1,196 @@
+use altius_core::{
+    fixed_dim::FixedDimensions,
+    model::Model,
+    node::Node,
+    op::{BatchNormalization, Conv2d, Op, Pad},
+    optimize::pass_manager::{OptimizationLevel, PassError},
+    tensor::{Tensor, TensorElemType, TypedShape},
+};
+use altius_session::SessionError;
+use altius_session_interpreter::InterpreterSessionBuilder;
+
+fn conv_bn_relu_model() -> Model {
+    let mut model = Model::default();
+    let x = model.graph.values.new_val_named_and_shaped(
+        "x",
+        TypedShape::new(
+            FixedDimensions::from(vec![1, 2, 3, 3]).into(),
+            TensorElemType::F32,
+        ),
+    );
+    let w = model.graph.values.new_val_named("w");
+    let conv_out = model.graph.values.new_val_named("conv_out");
+    let params @ [scale, b, mean, var] =
+        ["scale", "b", "mean", "var"].map(|name| model.graph.values.new_val_named(name));
+    let bn_out = model.graph.values.new_val_named("bn_out");
+    let y = model.graph.values.new_val_named("y");
+
+    model.graph.inits.insert(
+        w,
+        Tensor::new(vec![2, 2, 1, 1].into(), vec![1.0f32, -1.0, 0.5, 2.0]),
+    );
+    for (id, data) in params.into_iter().zip([
+        vec![2.0f32, 0.5],
+        vec![0.1, -3.0],
+        vec![0.5, 1.0],
+        vec![0.25, 4.0],
+    ]) {
+        model
+            .graph
+            .inits
+            .insert(id, Tensor::new(vec![2].into(), data));
+    }
+    model.graph.add_node(
+        Node::new(Op::Conv2d(Conv2d {
+            kernel_shape: vec![1, 1].into(),
+            strides: vec![1, 1].into(),
+            padding: vec![0, 0, 0, 0].into(),
+            dilations: vec![1, 1].into(),
+            group: 1,
+            ..Default::default()
+        }))
+        .with_ins(vec![x, w])
+        .with_out(conv_out),
+    );
+    model.graph.add_node(
+        Node::new(Op::BatchNormalization(BatchNormalization {
+            epsilon: 1e-5,
+            momentum: 0.9,
+            training_mode: false,
+        }))
+        .with_ins(vec![conv_out, scale, b, mean, var])
+        .with_out(bn_out),
+    );
+    model
+        .graph
+        .add_node(Node::new(Op::ReLU).with_in(bn_out).with_out(y));
+    model.graph.inputs.push(x);
+    model.graph.outputs.push(y);
+    model
+}
+
+#[test]
+fn same_results_across_levels() {
+    let x = Tensor::new(
+        vec![1, 2, 3, 3].into(),
+        (0..18).map(|i| i as f32 * 0.25 - 2.0).collect(),
+    );
+    let expected = InterpreterSessionBuilder::new(conv_bn_relu_model())
+        .build()
+        .unwrap()
+        .run(vec![x.clone()])
+        .unwrap();
+
+    for level in [
+        OptimizationLevel::O1,
+        OptimizationLevel::O2,
+        OptimizationLevel::O3,
+    ] {
+        let sess = InterpreterSessionBuilder::new(conv_bn_relu_model())
+            .with_optimization_level(level)
+            .build()
+            .unwrap();
+        if level >= OptimizationLevel::O2 {
+            assert_eq!(sess.model().graph.nodes.len(), 1);
+        }
+        let actual = sess.run(vec![x.clone()]).unwrap();
+        assert!(actual[0].allclose(expected[0].data::<f32>()));
+    }
+}
+
+fn pad_conv_model() -> Model {
+    let mut model = Model {
+        opset_version: 13,
+        ..Default::default()
+    };
+    let x = model.graph.values.new_val_named_and_shaped(
+        "x",
+        TypedShape::new(
+            FixedDimensions::from(vec![1, 1, 4, 5]).into(),
+            TensorElemType::F32,
+        ),
+    );
+    let pads = model.graph.values.new_val_named("pads");
+    let w = model.graph.values.new_val_named("w");
+    let padded = model.graph.values.new_val_named("padded");
+    let y = model.graph.values.new_val_named("y");
+
+    model.graph.inits.insert(
+        pads,
+        Tensor::new(vec![8].into(), vec![0i64, 0, 2, 0, 0, 0, 1, 3]),
+    );
+    model.graph.inits.insert(
+        w,
+        Tensor::new(vec![1, 1, 2, 2].into(), vec![1.0f32, -2.0, 0.5, 3.0]),
+    );
+    model.graph.add_node(
+        Node::new(Op::Pad(Pad {
+            mode: "constant".into(),
+            ..Default::default()
+        }))
+        .with_ins(vec![x, pads])
+        .with_out(padded),
+    );
+    model.graph.add_node(
+        Node::new(Op::Conv2d(Conv2d {
+            padding: vec![1, 0, 0, 1].into(),
+            group: 1,
+            ..Default::default()
+        }))
+        .with_ins(vec![padded, w])
+        .with_out(y),
+    );
+    model.graph.inputs.push(x);
+    model.graph.outputs.push(y);
+    model
+}
+
+#[test]
+fn pad_fused_into_conv() {
+    let x = Tensor::new(
+        vec![1, 1, 4, 5].into(),
+        (0..20).map(|i| i as f32 * 0.5 - 4.0).collect(),
+    );
+    let expected = InterpreterSessionBuilder::new(pad_conv_model())
+        .build()
+        .unwrap()
+        .run(vec![x.clone()])
+        .unwrap();
+
+    let sess = InterpreterSessionBuilder::new(pad_conv_model())
+        .with_optimization_level(OptimizationLevel::O2)
+        .build()
+        .unwrap();
+    assert_eq!(sess.model().graph.nodes.len(), 1);
+    let actual = sess.run(vec![x]).unwrap();
+    assert_eq!(actual[0].dims(), expected[0].dims());
+    assert!(actual[0].allclose(expected[0].data::<f32>()));
+}
+
+#[test]
+fn verify_graph_on_build() {
+    // ReLU takes a single input.
+    let mut model = Model::default();
+    let x = model.graph.values.new_val_named_and_shaped(
+        "x",
+        TypedShape::new(FixedDimensions::from(vec![2]).into(), TensorElemType::F32),
+    );
+    let y = model.graph.values.new_val_named("y");
+    model
+        .graph
+        .add_node(Node::new(Op::ReLU).with_ins(vec![x, x]).with_out(y));
+    model.graph.inputs.push(x);
+    model.graph.outputs.push(y);
+
+    let result = InterpreterSessionBuilder::new(model)
+        .with_optimization_level(OptimizationLevel::O1)
+        .with_graph_verification(true)
+        .build();
+    assert!(matches!(
+        result,
+        Err(SessionError::Optimize(PassError::Verify {
+            pass: "input",
+            ..
+        }))
+    ));
+}
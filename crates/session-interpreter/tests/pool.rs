@@ -0,0 +1,273 @@
+mod common;
+
+use altius_core::{
+    model::Model,
+    node::Node,
+    onnx::{load_onnx, save::save_onnx},
+    op::{Op, Pool},
+    tensor::{Tensor, TensorElemTypeExt, TypedShape},
+};
+use altius_session_interpreter::InterpreterSessionBuilder;
+
+/// Builds a model of a single pooling `op` whose input is shaped like `x`.
+fn model(op: Op, x: &Tensor, num_outputs: usize) -> Model {
+    let mut model = Model::default();
+    let input = model
+        .graph
+        .values
+        .new_val_named_and_shaped("x", TypedShape::new(x.dims().clone().into(), x.elem_ty()));
+    model.graph.inputs.push(input);
+    let outputs = (0..num_outputs)
+        .map(|i| model.graph.values.new_val_named(format!("y{i}")))
+        .collect::<Vec<_>>();
+    model.graph.outputs.clone_from(&outputs);
+    model
+        .graph
+        .add_node(Node::new(op).with_in(input).with_outs(outputs));
+    model
+}
+
+fn run(op: Op, x: Tensor, num_outputs: usize) -> Vec<Tensor> {
+    common::run_outputs(op, vec![x], num_outputs)
+}
+
+fn arange<T: TensorElemTypeExt>(dims: Vec<usize>, f: impl Fn(usize) -> T) -> Tensor {
+    let len = dims.iter().product();
+    Tensor::new(dims.into(), (0..len).map(f).collect())
+}
+
+fn pool(kernel_shape: Vec<usize>) -> Pool {
+    Pool {
+        auto_pad: "NOTSET".into(),
+        kernel_shape: kernel_shape.into(),
+        p: 2,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn max_pool_1d() {
+    let x = Tensor::new(vec![1, 1, 5].into(), vec![1.0f32, 3., 2., 5., 4.]);
+    let y = run(Op::MaxPool(pool(vec![2])), x.clone(), 1).remove(0);
+    assert_eq!(y.dims().as_slice(), &[1, 1, 4]);
+    assert_eq!(y.data::<f32>(), &[3., 3., 5., 5.]);
+
+    let y = run(
+        Op::MaxPool(Pool {
+            auto_pad: "SAME_UPPER".into(),
+            strides: vec![2].into(),
+            ..pool(vec![2])
+        }),
+        x,
+        1,
+    )
+    .remove(0);
+    assert_eq!(y.data::<f32>(), &[3., 5., 4.]);
+}
+
+#[test]
+fn max_pool_2d_with_dilations() {
+    let x = arange(vec![1, 1, 4, 4], |i| i as f32);
+    let y = run(
+        Op::MaxPool(Pool {
+            dilations: vec![2, 2].into(),
+            ..pool(vec![2, 2])
+        }),
+        x,
+        1,
+    )
+    .remove(0);
+    assert_eq!(y.dims().as_slice(), &[1, 1, 2, 2]);
+    assert_eq!(y.data::<f32>(), &[10., 11., 14., 15.]);
+}
+
+#[test]
+fn max_pool_ceil_mode() {
+    // The example of the ONNX backend test `maxpool_2d_ceil`.
+    let x = arange(vec![1, 1, 4, 4], |i| (i + 1) as f32);
+    let op = |ceil_mode| {
+        Op::MaxPool(Pool {
+            ceil_mode,
+            strides: vec![2, 2].into(),
+            ..pool(vec![3, 3])
+        })
+    };
+    let y = run(op(true), x.clone(), 1).remove(0);
+    assert_eq!(y.dims().as_slice(), &[1, 1, 2, 2]);
+    assert_eq!(y.data::<f32>(), &[11., 12., 15., 16.]);
+
+    let y = run(op(false), x, 1).remove(0);
+    assert_eq!(y.data::<f32>(), &[11.]);
+}
+
+#[test]
+fn max_pool_3d() {
+    let x = arange(vec![1, 2, 2, 2, 2], |i| (i as f32 * 5.).sin());
+    let y = run(Op::MaxPool(pool(vec![2, 2, 2])), x.clone(), 1).remove(0);
+    assert_eq!(y.dims().as_slice(), &[1, 2, 1, 1, 1]);
+    for (y, x) in y.data::<f32>().iter().zip(x.data::<f32>().chunks(8)) {
+        assert_eq!(*y, x.iter().copied().fold(f32::MIN, f32::max));
+    }
+}
+
+#[test]
+fn max_pool_indices() {
+    let x = || {
+        Tensor::new(
+            vec![1, 2, 2, 2].into(),
+            vec![1.0f32, 4., 3., 2., 5., 6., 8., 7.],
+        )
+    };
+    let ys = run(Op::MaxPool(pool(vec![2, 2])), x(), 2);
+    assert_eq!(ys[0].data::<f32>(), &[4., 8.]);
+    assert_eq!(ys[1].data::<i64>(), &[1, 6]);
+
+    let ys = run(
+        Op::MaxPool(Pool {
+            storage_order: 1,
+            ..pool(vec![2, 2])
+        }),
+        x(),
+        2,
+    );
+    assert_eq!(ys[1].data::<i64>(), &[2, 5]);
+}
+
+#[test]
+fn max_pool_i8() {
+    let x = Tensor::new(vec![1, 1, 4].into(), vec![-3i8, -1, 7, 2]);
+    let y = run(
+        Op::MaxPool(Pool {
+            strides: vec![2].into(),
+            ..pool(vec![2])
+        }),
+        x,
+        1,
+    )
+    .remove(0);
+    assert_eq!(y.data::<i8>(), &[-1, 7]);
+}
+
+#[test]
+fn average_pool() {
+    let x = arange(vec![1, 1, 3, 3], |i| (i + 1) as f32);
+    let op = |count_include_pad| {
+        Op::AveragePool(Pool {
+            count_include_pad,
+            padding: vec![1, 1, 1, 1].into(),
+            ..pool(vec![3, 3])
+        })
+    };
+    let y = run(op(false), x.clone(), 1).remove(0);
+    assert_eq!(y.dims().as_slice(), &[1, 1, 3, 3]);
+    assert!(y.allclose(&[3.0f32, 3.5, 4., 4.5, 5., 5.5, 6., 6.5, 7.]));
+
+    let y = run(op(true), x, 1).remove(0);
+    assert!(y.allclose(&[
+        12.0f32 / 9.,
+        21. / 9.,
+        16. / 9.,
+        27. / 9.,
+        45. / 9.,
+        33. / 9.,
+        24. / 9.,
+        39. / 9.,
+        28. / 9.
+    ]));
+}
+
+#[test]
+fn average_pool_ceil_mode() {
+    // The last window goes past the input, where no pads are counted.
+    let x = Tensor::new(vec![1, 1, 5].into(), vec![1.0f32, 2., 3., 4., 5.]);
+    let y = run(
+        Op::AveragePool(Pool {
+            ceil_mode: true,
+            count_include_pad: true,
+            strides: vec![2].into(),
+            ..pool(vec![2])
+        }),
+        x,
+        1,
+    )
+    .remove(0);
+    assert_eq!(y.dims().as_slice(), &[1, 1, 3]);
+    assert!(y.allclose(&[1.5f32, 3.5, 5.]));
+}
+
+#[test]
+fn lp_pool() {
+    let x = || Tensor::new(vec![1, 1, 4].into(), vec![3.0f32, -4., 0., 5.]);
+    let op = |p| {
+        Op::LpPool(Pool {
+            p,
+            strides: vec![2].into(),
+            ..pool(vec![2])
+        })
+    };
+    let y = run(op(2), x(), 1).remove(0);
+    assert!(y.allclose(&[5.0f32, 5.]));
+
+    let y = run(op(1), x(), 1).remove(0);
+    assert!(y.allclose(&[7.0f32, 5.]));
+}
+
+#[test]
+fn global_pools() {
+    let x = Tensor::new(vec![1, 2, 3].into(), vec![1.0f32, -2., 4., 0., 3., 6.]);
+    let y = run(Op::GlobalMaxPool, x.clone(), 1).remove(0);
+    assert_eq!(y.dims().as_slice(), &[1, 2, 1]);
+    assert_eq!(y.data::<f32>(), &[4., 6.]);
+
+    let y = run(Op::GlobalAveragePool, x, 1).remove(0);
+    assert!(y.allclose(&[1.0f32, 3.]));
+
+    let x = arange(vec![1, 1, 2, 2, 2], |i| i as f32);
+    let y = run(Op::GlobalAveragePool, x, 1).remove(0);
+    assert_eq!(y.dims().as_slice(), &[1, 1, 1, 1, 1]);
+    assert!(y.allclose(&[3.5f32]));
+}
+
+#[test]
+fn save_and_load_pools() {
+    let ops = [
+        Op::MaxPool(Pool {
+            ceil_mode: true,
+            dilations: vec![1, 2].into(),
+            padding: vec![0, 1, 1, 0].into(),
+            storage_order: 1,
+            strides: vec![2, 1].into(),
+            ..pool(vec![2, 2])
+        }),
+        Op::AveragePool(Pool {
+            auto_pad: "SAME_LOWER".into(),
+            count_include_pad: true,
+            ..pool(vec![3, 3])
+        }),
+        Op::LpPool(Pool {
+            p: 3,
+            ..pool(vec![2, 2])
+        }),
+    ];
+    let x = arange(vec![1, 2, 5, 5], |i| (i as f32).cos());
+    for op in ops {
+        let mut model = model(op.clone(), &x, 1);
+        let expected = run(op.clone(), x.clone(), 1).remove(0);
+        model.opset_version = 19;
+        // Saved graph outputs need their shapes.
+        model.graph.values.inner_mut()[model.graph.outputs[0]].shape = Some(TypedShape::new(
+            expected.dims().clone().into(),
+            expected.elem_ty(),
+        ));
+
+        let path = std::env::temp_dir().join(format!("altius_save_and_load_{}.onnx", op.name()));
+        save_onnx(&model, &path).unwrap();
+        let loaded = load_onnx(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        let (_, node) = loaded.graph.nodes.iter().next().unwrap();
+        assert_eq!(node.op, op);
+        let sess = InterpreterSessionBuilder::new(loaded).build().unwrap();
+        let actual = sess.run(vec![x.clone()]).unwrap().remove(0);
+        assert!(actual.allclose(expected.data::<f32>()));
+    }
+}
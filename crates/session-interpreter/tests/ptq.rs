@@ -0,0 +1,144 @@
+use altius_core::{
+    fixed_dim::FixedDimensions,
+    model::Model,
+    node::Node,
+    onnx::{load_onnx, save::save_onnx},
+    op::{Conv2d, Flatten, Gemm, Op},
+    quantize::QuantizationConfig,
+    tensor::{Tensor, TensorElemType, TypedShape},
+    value::ValueId,
+};
+use altius_session_interpreter::{quantize_static, InterpreterSessionBuilder};
+
+fn shape(dims: Vec<usize>) -> TypedShape {
+    TypedShape::new(FixedDimensions::from(dims).into(), TensorElemType::F32)
+}
+
+fn init(model: &mut Model, name: &str, dims: Vec<usize>, seed: usize) -> ValueId {
+    let len = dims.iter().product::<usize>();
+    let data = (0..len)
+        .map(|i| ((i * 7 + seed) as f32).sin() * 0.5)
+        .collect::<Vec<_>>();
+    let id = model
+        .graph
+        .values
+        .new_val_named_and_shaped(name, shape(dims.clone()));
+    model.graph.inits.insert(id, Tensor::new(dims.into(), data));
+    id
+}
+
+/// Conv -> ReLU -> Flatten -> Gemm -> MatMul
+fn build() -> Model {
+    let mut model = Model {
+        opset_version: 13,
+        ..Default::default()
+    };
+    let x = model
+        .graph
+        .values
+        .new_val_named_and_shaped("x", shape(vec![1, 2, 4, 4]));
+    let conv_w = init(&mut model, "conv_w", vec![3, 2, 3, 3], 1);
+    let conv_b = init(&mut model, "conv_b", vec![3], 2);
+    let gemm_b = init(&mut model, "gemm_b", vec![48, 5], 3);
+    let gemm_c = init(&mut model, "gemm_c", vec![5], 4);
+    let matmul_b = init(&mut model, "matmul_b", vec![5, 4], 5);
+    let [conv, relu, flatten, gemm] =
+        ["conv", "relu", "flatten", "gemm"].map(|name| model.graph.values.new_val_named(name));
+    let y = model
+        .graph
+        .values
+        .new_val_named_and_shaped("y", shape(vec![1, 4]));
+
+    model.graph.add_node(
+        Node::new(Op::Conv2d(Conv2d {
+            kernel_shape: vec![3, 3].into(),
+            strides: vec![1, 1].into(),
+            padding: vec![1, 1, 1, 1].into(),
+            dilations: vec![1, 1].into(),
+            group: 1,
+            ..Default::default()
+        }))
+        .with_ins(vec![x, conv_w, conv_b])
+        .with_out(conv),
+    );
+    model
+        .graph
+        .add_node(Node::new(Op::ReLU).with_in(conv).with_out(relu));
+    model.graph.add_node(
+        Node::new(Op::Flatten(Flatten { axis: 1 }))
+            .with_in(relu)
+            .with_out(flatten),
+    );
+    model.graph.add_node(
+        Node::new(Op::Gemm(Gemm {
+            alpha: 1.0,
+            beta: 1.0,
+            trans_a: false,
+            trans_b: false,
+        }))
+        .with_ins(vec![flatten, gemm_b, gemm_c])
+        .with_out(gemm),
+    );
+    model.graph.add_node(
+        Node::new(Op::MatMul)
+            .with_ins(vec![gemm, matmul_b])
+            .with_out(y),
+    );
+    model.graph.inputs.push(x);
+    model.graph.outputs.push(y);
+    model
+}
+
+fn input(seed: usize) -> Tensor {
+    Tensor::new(
+        vec![1, 2, 4, 4].into(),
+        (0..32)
+            .map(|i| ((i * 13 + seed * 5) as f32).cos() * 2.0)
+            .collect::<Vec<_>>(),
+    )
+}
+
+fn run(model: Model, x: Tensor) -> Tensor {
+    let sess = InterpreterSessionBuilder::new(model).build().unwrap();
+    sess.run(vec![x]).unwrap().remove(0)
+}
+
+#[test]
+fn quantize_static_close_to_f32() {
+    let model = build();
+    let batches = (0..8).map(|seed| vec![input(seed)]);
+    for config in [
+        QuantizationConfig::new(),
+        QuantizationConfig::new().with_per_channel_weights(false),
+        QuantizationConfig::new().with_activation_type(TensorElemType::I8),
+    ] {
+        let quantized = quantize_static(&model, batches.clone(), &config).unwrap();
+        let num_nodes = |m: &Model| m.graph.nodes.iter().filter(|(_, n)| !n.deleted).count();
+        assert!(num_nodes(&quantized) > num_nodes(&model));
+
+        for seed in 0..8 {
+            let expected = run(model.clone(), input(seed));
+            let actual = run(quantized.clone(), input(seed));
+            // Errors of a few quantization steps of the activations add up over three layers.
+            for (x, y) in expected.data::<f32>().iter().zip(actual.data::<f32>()) {
+                assert!((x - y).abs() < 0.1, "{x} vs {y}");
+            }
+        }
+    }
+}
+
+#[test]
+fn save_and_load_quantized_model() {
+    let model = build();
+    let batches = (0..4).map(|seed| vec![input(seed)]);
+    let quantized = quantize_static(&model, batches, &QuantizationConfig::new()).unwrap();
+
+    let path = std::env::temp_dir().join("altius_ptq.onnx");
+    save_onnx(&quantized, &path).unwrap();
+    let loaded = load_onnx(&path).unwrap();
+    let _ = std::fs::remove_file(&path);
+
+    let expected = run(quantized, input(0));
+    let actual = run(loaded, input(0));
+    assert!(actual.allclose(expected.data::<f32>()));
+}
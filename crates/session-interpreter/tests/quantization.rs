@@ -0,0 +1,281 @@
+use altius_core::{
+    fixed_dim::FixedDimensions,
+    model::Model,
+    node::Node,
+    op::{Conv2d, DequantizeLinear, Op, QuantizeLinear},
+    tensor::{Tensor, TensorElemType, TypedShape},
+    value::ValueId,
+};
+use altius_session_interpreter::InterpreterSessionBuilder;
+
+fn input(model: &mut Model, name: &str, dims: Vec<usize>, elem_ty: TensorElemType) -> ValueId {
+    let x = model.graph.values.new_val_named_and_shaped(
+        name,
+        TypedShape::new(FixedDimensions::from(dims).into(), elem_ty),
+    );
+    model.graph.inputs.push(x);
+    x
+}
+
+fn init(model: &mut Model, name: &str, tensor: Tensor) -> ValueId {
+    let id = model.graph.values.new_val_named(name);
+    model.graph.inits.insert(id, tensor);
+    id
+}
+
+fn scalar<T: altius_core::tensor::TensorElemTypeExt>(x: T) -> Tensor {
+    Tensor::new(vec![].into(), vec![x])
+}
+
+fn conv2d(group: i64, stride: usize, pad: usize) -> Conv2d {
+    Conv2d {
+        kernel_shape: vec![2, 2].into(),
+        strides: vec![stride, stride].into(),
+        padding: vec![pad; 4].into(),
+        dilations: vec![1, 1].into(),
+        group,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn quantize_dequantize() {
+    let mut model = Model::default();
+    let x = input(&mut model, "x", vec![1, 4], TensorElemType::F32);
+    let scale = init(&mut model, "scale", scalar(0.1f32));
+    let zp = init(&mut model, "zp", scalar(128u8));
+    let [q, y] = ["q", "y"].map(|name| model.graph.values.new_val_named(name));
+    model.graph.add_node(
+        Node::new(Op::QuantizeLinear(QuantizeLinear { axis: 1 }))
+            .with_ins(vec![x, scale, zp])
+            .with_out(q),
+    );
+    model.graph.add_node(
+        Node::new(Op::DequantizeLinear(DequantizeLinear { axis: 1 }))
+            .with_ins(vec![q, scale, zp])
+            .with_out(y),
+    );
+    model.graph.outputs.extend([q, y]);
+
+    let sess = InterpreterSessionBuilder::new(model).build().unwrap();
+    let x = Tensor::new(vec![1, 4].into(), vec![-1.0f32, 0.25, 0.26, 100.0]);
+    let outputs = sess.run(vec![x]).unwrap();
+    // Ties are rounded to even and out-of-range values saturate.
+    assert_eq!(outputs[0].data::<u8>(), &[118, 130, 131, 255]);
+    assert!(outputs[1].allclose(&[-1.0f32, 0.2, 0.3, 12.7]));
+}
+
+#[test]
+fn dequantize_per_axis() {
+    let mut model = Model::default();
+    let x = input(&mut model, "x", vec![2, 3], TensorElemType::I8);
+    let scale = init(
+        &mut model,
+        "scale",
+        Tensor::new(vec![2].into(), vec![0.5f32, 2.0]),
+    );
+    let zp = init(&mut model, "zp", Tensor::new(vec![2].into(), vec![1i8, -1]));
+    let y = model.graph.values.new_val_named("y");
+    model.graph.add_node(
+        Node::new(Op::DequantizeLinear(DequantizeLinear { axis: 0 }))
+            .with_ins(vec![x, scale, zp])
+            .with_out(y),
+    );
+    model.graph.outputs.push(y);
+
+    let sess = InterpreterSessionBuilder::new(model).build().unwrap();
+    let x = Tensor::new(vec![2, 3].into(), vec![1i8, 3, -127, -1, 0, 127]);
+    let outputs = sess.run(vec![x]).unwrap();
+    assert!(outputs[0].allclose(&[0.0f32, 1.0, -64.0, 0.0, 2.0, 256.0]));
+}
+
+#[test]
+fn qlinear_mat_mul() {
+    let mut model = Model::default();
+    let a = input(&mut model, "a", vec![1, 2], TensorElemType::U8);
+    let a_scale = init(&mut model, "a_scale", scalar(0.5f32));
+    let a_zp = init(&mut model, "a_zp", scalar(128u8));
+    let b = init(
+        &mut model,
+        "b",
+        Tensor::new(vec![2, 2].into(), vec![2i8, 4, 6, 8]),
+    );
+    let b_scale = init(&mut model, "b_scale", scalar(0.25f32));
+    let b_zp = init(&mut model, "b_zp", scalar(0i8));
+    let y_scale = init(&mut model, "y_scale", scalar(0.5f32));
+    let y_zp = init(&mut model, "y_zp", scalar(1i8));
+    let y = model.graph.values.new_val_named("y");
+    model.graph.add_node(
+        Node::new(Op::QLinearMatMul)
+            .with_ins(vec![a, a_scale, a_zp, b, b_scale, b_zp, y_scale, y_zp])
+            .with_out(y),
+    );
+    model.graph.outputs.push(y);
+
+    let sess = InterpreterSessionBuilder::new(model).build().unwrap();
+    // [1, -1] @ [[0.5, 1], [1.5, 2]] = [-1, -1]
+    let a = Tensor::new(vec![1, 2].into(), vec![130u8, 126]);
+    let outputs = sess.run(vec![a]).unwrap();
+    assert_eq!(outputs[0].elem_ty(), TensorElemType::I8);
+    assert_eq!(outputs[0].data::<i8>(), &[-1, -1]);
+}
+
+#[test]
+fn mat_mul_integer() {
+    let (m, k, n) = (2, 3, 2);
+    let mut model = Model::default();
+    let a = input(&mut model, "a", vec![2, m, k], TensorElemType::U8);
+    let b = init(
+        &mut model,
+        "b",
+        Tensor::new(vec![k, n].into(), vec![1i8, -2, 3, -4, 5, -6]),
+    );
+    let a_zp = init(&mut model, "a_zp", scalar(1u8));
+    // Per-column zero points of B.
+    let b_zp = init(
+        &mut model,
+        "b_zp",
+        Tensor::new(vec![n].into(), vec![0i8, 2]),
+    );
+    let y = model.graph.values.new_val_named("y");
+    model.graph.add_node(
+        Node::new(Op::MatMulInteger)
+            .with_ins(vec![a, b, a_zp, b_zp])
+            .with_out(y),
+    );
+    model.graph.outputs.push(y);
+
+    let sess = InterpreterSessionBuilder::new(model).build().unwrap();
+    let a_data = (0..2 * m * k)
+        .map(|i| (i * 37 % 256) as u8)
+        .collect::<Vec<_>>();
+    let b_data = [1i32, -2, 3, -4, 5, -6];
+    let outputs = sess
+        .run(vec![Tensor::new(vec![2, m, k].into(), a_data.clone())])
+        .unwrap();
+
+    let mut expected = vec![];
+    for batch in 0..2 {
+        for i in 0..m {
+            for j in 0..n {
+                expected.push(
+                    (0..k)
+                        .map(|p| {
+                            (a_data[(batch * m + i) * k + p] as i32 - 1)
+                                * (b_data[p * n + j] - [0, 2][j])
+                        })
+                        .sum::<i32>(),
+                );
+            }
+        }
+    }
+    assert_eq!(outputs[0].dims(), &vec![2, m, n].into());
+    assert_eq!(outputs[0].data::<i32>(), expected);
+}
+
+#[test]
+fn qlinear_conv() {
+    let mut model = Model::default();
+    let x = input(&mut model, "x", vec![1, 1, 3, 3], TensorElemType::U8);
+    let x_scale = init(&mut model, "x_scale", scalar(0.5f32));
+    let x_zp = init(&mut model, "x_zp", scalar(2u8));
+    let w = init(
+        &mut model,
+        "w",
+        Tensor::new(vec![2, 1, 2, 2].into(), vec![1i8, 0, 0, 1, 1, 1, 1, 1]),
+    );
+    // Per-channel weight scales.
+    let w_scale = init(
+        &mut model,
+        "w_scale",
+        Tensor::new(vec![2].into(), vec![0.25f32, 0.5]),
+    );
+    let w_zp = init(&mut model, "w_zp", scalar(0i8));
+    let y_scale = init(&mut model, "y_scale", scalar(1.0f32));
+    let y_zp = init(&mut model, "y_zp", scalar(10u8));
+    let bias = init(&mut model, "b", Tensor::new(vec![2].into(), vec![4i32, -8]));
+    let y = model.graph.values.new_val_named("y");
+    model.graph.add_node(
+        Node::new(Op::QLinearConv(conv2d(1, 1, 0)))
+            .with_ins(vec![
+                x, x_scale, x_zp, w, w_scale, w_zp, y_scale, y_zp, bias,
+            ])
+            .with_out(y),
+    );
+    model.graph.outputs.push(y);
+
+    let sess = InterpreterSessionBuilder::new(model).build().unwrap();
+    let x = Tensor::new(vec![1, 1, 3, 3].into(), (0..9u8).collect());
+    let outputs = sess.run(vec![x]).unwrap();
+    // Channel 0: ([0, 2, 6, 8] + 4) * 0.125 = [0.5, 0.75, 1.25, 1.5]
+    // Channel 1: ([0, 4, 12, 16] - 8) * 0.25 = [-2, -1, 1, 2]
+    assert_eq!(outputs[0].dims(), &vec![1, 2, 2, 2].into());
+    assert_eq!(outputs[0].data::<u8>(), &[10, 11, 11, 12, 8, 9, 11, 12]);
+}
+
+#[test]
+fn conv_integer_matches_f32_conv() {
+    let x_dims = vec![1, 4, 5, 5];
+    let w_dims = vec![4, 2, 2, 2];
+    let x_data = (0..100).map(|i| (i * 7 % 256) as u8).collect::<Vec<_>>();
+    let w_data = (0..32).map(|i| (i * 5 % 17) as i8 - 8).collect::<Vec<_>>();
+    let x_zp = 3u8;
+
+    let mut model = Model::default();
+    let x = input(&mut model, "x", x_dims.clone(), TensorElemType::U8);
+    let w = init(
+        &mut model,
+        "w",
+        Tensor::new(w_dims.clone().into(), w_data.clone()),
+    );
+    let zp = init(&mut model, "x_zp", scalar(x_zp));
+    let y = model.graph.values.new_val_named("y");
+    model.graph.add_node(
+        Node::new(Op::ConvInteger(conv2d(2, 2, 1)))
+            .with_ins(vec![x, w, zp])
+            .with_out(y),
+    );
+    model.graph.outputs.push(y);
+    let sess = InterpreterSessionBuilder::new(model).build().unwrap();
+    let actual = sess
+        .run(vec![Tensor::new(x_dims.clone().into(), x_data.clone())])
+        .unwrap();
+
+    // Padding with the zero point is padding with zero in f32.
+    let mut model = Model::default();
+    let x = input(&mut model, "x", x_dims.clone(), TensorElemType::F32);
+    let w = init(
+        &mut model,
+        "w",
+        Tensor::new(
+            w_dims.into(),
+            w_data.iter().map(|&w| w as f32).collect::<Vec<_>>(),
+        ),
+    );
+    let y = model.graph.values.new_val_named("y");
+    model.graph.add_node(
+        Node::new(Op::Conv2d(conv2d(2, 2, 1)))
+            .with_ins(vec![x, w])
+            .with_out(y),
+    );
+    model.graph.outputs.push(y);
+    let sess = InterpreterSessionBuilder::new(model).build().unwrap();
+    let expected = sess
+        .run(vec![Tensor::new(
+            x_dims.into(),
+            x_data
+                .iter()
+                .map(|&x| x as f32 - x_zp as f32)
+                .collect::<Vec<_>>(),
+        )])
+        .unwrap();
+
+    assert_eq!(actual[0].dims(), expected[0].dims());
+    assert!(expected[0].allclose(
+        &actual[0]
+            .data::<i32>()
+            .iter()
+            .map(|&x| x as f32)
+            .collect::<Vec<_>>()
+    ));
+}
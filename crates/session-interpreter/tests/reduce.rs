@@ -0,0 +1,203 @@
+mod common;
+
+use altius_core::{
+    op::{ArgReduce, Op, Reduce},
+    tensor::Tensor,
+};
+use common::run;
+
+fn input() -> Tensor {
+    let data = (0..24)
+        .map(|i| (i * 7 % 11) as f32 * 0.5 - 2.)
+        .collect::<Vec<_>>();
+    Tensor::new(vec![2, 3, 4].into(), data)
+}
+
+/// Groups the elements of `x` by the output element they are reduced to, and reduces
+/// each group with `f`.
+fn reference(x: &Tensor, axes: &[usize], f: &dyn Fn(&[f32]) -> f32) -> Vec<f32> {
+    let dims = x.dims();
+    let len = (0..dims.len())
+        .filter(|a| !axes.contains(a))
+        .map(|a| dims[a])
+        .product::<usize>();
+    let mut groups = vec![vec![]; len];
+    for (i, &v) in x.data::<f32>().iter().enumerate() {
+        let mut rest = i;
+        let mut coord = vec![0; dims.len()];
+        for a in (0..dims.len()).rev() {
+            coord[a] = rest % dims[a];
+            rest /= dims[a];
+        }
+        let group = (0..dims.len())
+            .filter(|a| !axes.contains(a))
+            .fold(0, |g, a| g * dims[a] + coord[a]);
+        groups[group].push(v);
+    }
+    groups.iter().map(|g| f(g)).collect()
+}
+
+/// A reduction op and its reference implementation.
+type Reduction = (fn(Reduce) -> Op, Box<dyn Fn(&[f32]) -> f32>);
+
+fn reductions() -> Vec<Reduction> {
+    let sum = |g: &[f32]| g.iter().sum::<f32>();
+    vec![
+        (
+            Op::ReduceMin,
+            Box::new(|g| g.iter().copied().fold(f32::INFINITY, f32::min)),
+        ),
+        (
+            Op::ReduceMax,
+            Box::new(|g| g.iter().copied().fold(f32::NEG_INFINITY, f32::max)),
+        ),
+        (Op::ReduceMean, Box::new(move |g| sum(g) / g.len() as f32)),
+        (Op::ReduceSum, Box::new(sum)),
+        (Op::ReduceProd, Box::new(|g| g.iter().product())),
+        (Op::ReduceL1, Box::new(|g| g.iter().map(|x| x.abs()).sum())),
+        (
+            Op::ReduceL2,
+            Box::new(|g| g.iter().map(|x| x * x).sum::<f32>().sqrt()),
+        ),
+        (Op::ReduceLogSum, Box::new(move |g| sum(g).ln())),
+        (
+            Op::ReduceLogSumExp,
+            Box::new(|g| g.iter().map(|x| x.exp()).sum::<f32>().ln()),
+        ),
+        (
+            Op::ReduceSumSquare,
+            Box::new(|g| g.iter().map(|x| x * x).sum()),
+        ),
+    ]
+}
+
+fn assert_close(y: &Tensor, expected: &[f32], what: &str) {
+    assert_eq!(y.data::<f32>().len(), expected.len(), "{what}");
+    for (&y, &e) in y.data::<f32>().iter().zip(expected) {
+        assert!(
+            (y.is_nan() && e.is_nan()) || y == e || (y - e).abs() <= 1e-4 + 1e-4 * e.abs(),
+            "{what}: {y} vs expected {e}"
+        );
+    }
+}
+
+#[test]
+fn all_reductions() {
+    let x = input();
+    for (op, f) in reductions() {
+        for (axes, normalized) in [
+            (vec![0], vec![0]),
+            (vec![1], vec![1]),
+            (vec![-1], vec![2]),
+            (vec![0, 2], vec![0, 2]),
+            (vec![2, -2], vec![1, 2]),
+            (vec![], vec![0, 1, 2]),
+        ] {
+            for keep_dims in [true, false] {
+                let op = op(Reduce {
+                    axes: axes.clone(),
+                    keep_dims,
+                    ..Default::default()
+                });
+                let what = format!("{}({axes:?}, keep_dims={keep_dims})", op.name());
+                let y = run(op, vec![x.clone()]);
+                let dims = (0..3)
+                    .filter_map(|a| match normalized.contains(&a) {
+                        true if keep_dims => Some(1),
+                        true => None,
+                        false => Some(x.dims()[a]),
+                    })
+                    .collect::<Vec<_>>();
+                assert_eq!(y.dims().as_slice(), &dims, "{what}");
+                assert_close(&y, &reference(&x, &normalized, &f), &what);
+            }
+        }
+    }
+}
+
+#[test]
+fn axes_input() {
+    // From opset version 18, axes are given as an input.
+    let axes = Tensor::new(vec![2].into(), vec![-1i64, 0]);
+    let y = run(
+        Op::ReduceSum(Reduce {
+            keep_dims: false,
+            ..Default::default()
+        }),
+        vec![input(), axes],
+    );
+    assert_eq!(y.dims().as_slice(), &[3]);
+    assert_close(
+        &y,
+        &reference(&input(), &[0, 2], &|g| g.iter().sum()),
+        "ReduceSum",
+    );
+}
+
+#[test]
+fn noop_with_empty_axes() {
+    let noop = Reduce {
+        keep_dims: true,
+        noop_with_empty_axes: true,
+        ..Default::default()
+    };
+    let x = input();
+    let y = run(Op::ReduceSum(noop.clone()), vec![x.clone()]);
+    assert_eq!(y.dims(), x.dims());
+    assert_eq!(y.data::<f32>(), x.data::<f32>());
+
+    // Each element is reduced by itself.
+    let y = run(Op::ReduceSumSquare(noop), vec![x.clone()]);
+    let squares = x.data::<f32>().iter().map(|x| x * x).collect::<Vec<_>>();
+    assert_eq!(y.data::<f32>(), &squares);
+}
+
+#[test]
+fn integers() {
+    let x = Tensor::new(vec![2, 3].into(), vec![3i64, -1, 4, 1, -5, 9]);
+    let reduce = Reduce {
+        axes: vec![1],
+        keep_dims: false,
+        ..Default::default()
+    };
+    let y = run(Op::ReduceSum(reduce.clone()), vec![x.clone()]);
+    assert_eq!(y.data::<i64>(), &[6, 5]);
+    let y = run(Op::ReduceProd(reduce.clone()), vec![x.clone()]);
+    assert_eq!(y.data::<i64>(), &[-12, -45]);
+    let y = run(Op::ReduceMean(reduce.clone()), vec![x.clone()]);
+    assert_eq!(y.data::<i64>(), &[2, 1]);
+    let y = run(Op::ReduceL1(reduce.clone()), vec![x.clone()]);
+    assert_eq!(y.data::<i64>(), &[8, 15]);
+    let y = run(Op::ReduceMax(reduce), vec![x]);
+    assert_eq!(y.data::<i64>(), &[4, 9]);
+
+    let x = Tensor::new(vec![4].into(), vec![1i32, 2, 3, 4]);
+    let y = run(Op::ReduceSumSquare(Reduce::default()), vec![x]);
+    assert_eq!(y.dims().as_slice(), &[] as &[usize]);
+    assert_eq!(y.data::<i32>(), &[30]);
+}
+
+#[test]
+fn arg_reductions() {
+    let x = Tensor::new(vec![2, 3].into(), vec![1.0f32, 5., 5., 7., -2., 7.]);
+    let arg = |axis, keep_dims, select_last_index| ArgReduce {
+        axis,
+        keep_dims,
+        select_last_index,
+    };
+
+    let y = run(Op::ArgMax(arg(1, true, false)), vec![x.clone()]);
+    assert_eq!(y.dims().as_slice(), &[2, 1]);
+    assert_eq!(y.data::<i64>(), &[1, 0]);
+    let y = run(Op::ArgMax(arg(-1, false, true)), vec![x.clone()]);
+    assert_eq!(y.dims().as_slice(), &[2]);
+    assert_eq!(y.data::<i64>(), &[2, 2]);
+    let y = run(Op::ArgMin(arg(0, false, false)), vec![x.clone()]);
+    assert_eq!(y.dims().as_slice(), &[3]);
+    assert_eq!(y.data::<i64>(), &[0, 1, 0]);
+
+    let x = Tensor::new(vec![4].into(), vec![2i64, 0, 3, 0]);
+    let y = run(Op::ArgMin(arg(0, true, true)), vec![x]);
+    assert_eq!(y.dims().as_slice(), &[1]);
+    assert_eq!(y.data::<i64>(), &[3]);
+}
@@ -0,0 +1,464 @@
+#![allow(clippy::excessive_precision)]
+
+use altius_core::{
+    model::Model,
+    node::Node,
+    onnx::{load_onnx, save::save_onnx},
+    op::{Op, Resize},
+    tensor::{Tensor, TypedShape},
+};
+use altius_session_interpreter::InterpreterSessionBuilder;
+
+/// Builds a model of a single `Resize` whose input is shaped like `x`.
+/// `roi`, `scales` and `sizes` are given as initializers, and omitted if `None`.
+fn model(
+    resize: Resize,
+    x: &Tensor,
+    roi: Option<Vec<f32>>,
+    scales: Option<Vec<f32>>,
+    sizes: Option<Vec<i64>>,
+) -> Model {
+    let mut model = Model::default();
+    let input = model
+        .graph
+        .values
+        .new_val_named_and_shaped("x", TypedShape::new(x.dims().clone().into(), x.elem_ty()));
+    model.graph.inputs.push(input);
+    let omitted = model.graph.values.new_val_named("");
+    let mut inputs = vec![input];
+    for (name, init) in [
+        (
+            "roi",
+            roi.map(|roi| Tensor::new(vec![roi.len()].into(), roi)),
+        ),
+        (
+            "scales",
+            scales.map(|scales| Tensor::new(vec![scales.len()].into(), scales)),
+        ),
+        (
+            "sizes",
+            sizes.map(|sizes| Tensor::new(vec![sizes.len()].into(), sizes)),
+        ),
+    ] {
+        inputs.push(init.map_or(omitted, |init| {
+            let id = model.graph.values.new_val_named_and_shaped(
+                name,
+                TypedShape::new(init.dims().clone().into(), init.elem_ty()),
+            );
+            model.graph.inits.insert(id, init);
+            id
+        }));
+    }
+    while inputs.last() == Some(&omitted) {
+        inputs.pop();
+    }
+    let output = model.graph.values.new_val_named("y");
+    model.graph.outputs.push(output);
+    model.graph.add_node(
+        Node::new(Op::Resize(resize))
+            .with_ins(inputs)
+            .with_out(output),
+    );
+    model
+}
+
+fn run(
+    resize: Resize,
+    x: Tensor,
+    roi: Option<Vec<f32>>,
+    scales: Option<Vec<f32>>,
+    sizes: Option<Vec<i64>>,
+) -> Tensor {
+    let sess = InterpreterSessionBuilder::new(model(resize, &x, roi, scales, sizes))
+        .build()
+        .unwrap();
+    sess.run(vec![x]).unwrap().remove(0)
+}
+
+fn resize(mode: &str, coordinate_transformation_mode: &str) -> Resize {
+    Resize {
+        coordinate_transformation_mode: coordinate_transformation_mode.into(),
+        cubic_coeff_a: -0.75,
+        exclude_outside: 0,
+        extrapolation_value: 0.,
+        mode: mode.into(),
+        nearest_mode: "round_prefer_floor".into(),
+    }
+}
+
+/// `[1, 1, h, w]` of `1, 2, 3, ...`.
+fn arange(h: usize, w: usize) -> Tensor {
+    Tensor::new(
+        vec![1, 1, h, w].into(),
+        (1..=h * w).map(|i| i as f32).collect(),
+    )
+}
+
+// The cases below follow the ONNX backend tests of the same names.
+
+#[test]
+fn resize_nearest() {
+    // resize_upsample_scales_nearest
+    let y = run(
+        resize("nearest", "half_pixel"),
+        arange(2, 2),
+        None,
+        Some(vec![1., 1., 2., 3.]),
+        None,
+    );
+    assert_eq!(y.dims().as_slice(), &[1, 1, 4, 6]);
+    #[rustfmt::skip]
+    assert_eq!(y.data::<f32>(), &[
+        1., 1., 1., 2., 2., 2.,
+        1., 1., 1., 2., 2., 2.,
+        3., 3., 3., 4., 4., 4.,
+        3., 3., 3., 4., 4., 4.,
+    ]);
+
+    // resize_downsample_scales_nearest
+    let y = run(
+        resize("nearest", "half_pixel"),
+        arange(2, 4),
+        None,
+        Some(vec![1., 1., 0.6, 0.6]),
+        None,
+    );
+    assert_eq!(y.data::<f32>(), &[1., 3.]);
+
+    // resize_downsample_sizes_nearest
+    let y = run(
+        resize("nearest", "half_pixel"),
+        arange(2, 4),
+        None,
+        None,
+        Some(vec![1, 1, 1, 3]),
+    );
+    assert_eq!(y.data::<f32>(), &[1., 2., 4.]);
+
+    // resize_upsample_sizes_nearest
+    let y = run(
+        resize("nearest", "half_pixel"),
+        arange(2, 2),
+        None,
+        None,
+        Some(vec![1, 1, 7, 8]),
+    );
+    let expected = [[1.; 4], [2.; 4]].concat().repeat(4);
+    let expected = [expected, [[3.; 4], [4.; 4]].concat().repeat(3)].concat();
+    assert_eq!(y.data::<f32>(), &expected);
+}
+
+#[test]
+fn resize_nearest_modes() {
+    let cases = [
+        // resize_upsample_sizes_nearest_ceil_half_pixel
+        ("ceil", "half_pixel", [0, 1, 1, 2, 2, 3, 3, 3]),
+        // resize_upsample_sizes_nearest_floor_align_corners
+        ("floor", "align_corners", [0, 0, 0, 1, 1, 2, 2, 3]),
+        // resize_upsample_sizes_nearest_round_prefer_ceil_asymmetric
+        ("round_prefer_ceil", "asymmetric", [0, 1, 1, 2, 2, 3, 3, 3]),
+    ];
+    for (nearest_mode, coordinate_transformation_mode, indices) in cases {
+        let y = run(
+            Resize {
+                nearest_mode: nearest_mode.into(),
+                ..resize("nearest", coordinate_transformation_mode)
+            },
+            arange(4, 4),
+            None,
+            None,
+            Some(vec![1, 1, 8, 8]),
+        );
+        let expected = indices
+            .iter()
+            .flat_map(|h| indices.iter().map(move |w| (h * 4 + w + 1) as f32))
+            .collect::<Vec<_>>();
+        assert_eq!(y.data::<f32>(), &expected, "{nearest_mode}");
+    }
+}
+
+#[test]
+fn resize_linear() {
+    // resize_upsample_scales_linear
+    let y = run(
+        resize("linear", "half_pixel"),
+        arange(2, 2),
+        None,
+        Some(vec![1., 1., 2., 2.]),
+        None,
+    );
+    #[rustfmt::skip]
+    assert!(y.allclose(&[
+        1.0f32, 1.25, 1.75, 2.,
+        1.5, 1.75, 2.25, 2.5,
+        2.5, 2.75, 3.25, 3.5,
+        3., 3.25, 3.75, 4.,
+    ]));
+
+    // resize_upsample_scales_linear_align_corners
+    let y = run(
+        resize("linear", "align_corners"),
+        arange(2, 2),
+        None,
+        Some(vec![1., 1., 2., 2.]),
+        None,
+    );
+    #[rustfmt::skip]
+    assert!(y.allclose(&[
+        1.0f32, 1.3333333, 1.6666667, 2.,
+        1.6666666, 2., 2.3333333, 2.6666667,
+        2.3333333, 2.6666665, 3., 3.3333333,
+        3., 3.3333333, 3.6666667, 4.,
+    ]));
+
+    // resize_downsample_scales_linear
+    let y = run(
+        resize("linear", "half_pixel"),
+        arange(2, 4),
+        None,
+        Some(vec![1., 1., 0.6, 0.6]),
+        None,
+    );
+    assert!(y.allclose(&[2.6666665f32, 4.3333331]));
+
+    // resize_downsample_scales_linear_align_corners, where the resized length is 2.4.
+    let y = run(
+        resize("linear", "align_corners"),
+        arange(2, 4),
+        None,
+        Some(vec![1., 1., 0.6, 0.6]),
+        None,
+    );
+    assert!(y.allclose(&[1.0f32, 3.142857]));
+
+    // resize_downsample_sizes_linear_pytorch_half_pixel
+    let y = run(
+        resize("linear", "pytorch_half_pixel"),
+        arange(4, 4),
+        None,
+        None,
+        Some(vec![1, 1, 3, 1]),
+    );
+    assert!(y.allclose(&[1.6666666f32, 7., 12.333333]));
+
+    // resize_downsample_scales_linear_half_pixel_symmetric
+    let y = run(
+        resize("linear", "half_pixel_symmetric"),
+        arange(1, 4),
+        None,
+        Some(vec![1., 1., 1., 0.6]),
+        None,
+    );
+    assert!(y.allclose(&[1.6666667f32, 3.3333333]));
+}
+
+#[test]
+fn resize_cubic() {
+    // resize_upsample_scales_cubic
+    let y = run(
+        resize("cubic", "half_pixel"),
+        arange(4, 4),
+        None,
+        Some(vec![1., 1., 2., 2.]),
+        None,
+    );
+    assert_eq!(y.dims().as_slice(), &[1, 1, 8, 8]);
+    #[rustfmt::skip]
+    assert!(y.allclose(&[
+        0.47265625f32, 0.76953125, 1.24609375, 1.875, 2.28125, 2.91015625, 3.38671875, 3.68359375,
+        1.66015625, 1.95703125, 2.43359375, 3.0625, 3.46875, 4.09765625, 4.57421875, 4.87109375,
+        3.56640625, 3.86328125, 4.33984375, 4.96875, 5.375, 6.00390625, 6.48046875, 6.77734375,
+        6.08203125, 6.37890625, 6.85546875, 7.484375, 7.890625, 8.51953125, 8.99609375, 9.29296875,
+        7.70703125, 8.00390625, 8.48046875, 9.109375, 9.515625, 10.14453125, 10.62109375, 10.91796875,
+        10.22265625, 10.51953125, 10.99609375, 11.625, 12.03125, 12.66015625, 13.13671875, 13.43359375,
+        12.12890625, 12.42578125, 12.90234375, 13.53125, 13.9375, 14.56640625, 15.04296875, 15.33984375,
+        13.31640625, 13.61328125, 14.08984375, 14.71875, 15.125, 15.75390625, 16.23046875, 16.52734375,
+    ]));
+
+    // resize_downsample_scales_cubic
+    let y = run(
+        resize("cubic", "half_pixel"),
+        arange(4, 4),
+        None,
+        Some(vec![1., 1., 0.8, 0.8]),
+        None,
+    );
+    #[rustfmt::skip]
+    assert!(y.allclose(&[
+        1.47119141f32, 2.78125, 4.08251953,
+        6.71142578, 8.02148438, 9.32275391,
+        11.91650391, 13.2265625, 14.52783203,
+    ]));
+
+    // resize_downsample_scales_cubic_align_corners
+    let y = run(
+        resize("cubic", "align_corners"),
+        arange(4, 4),
+        None,
+        Some(vec![1., 1., 0.8, 0.8]),
+        None,
+    );
+    #[rustfmt::skip]
+    assert!(y.allclose(&[
+        1.0f32, 2.39519159, 3.79038317,
+        6.58076634, 7.97595793, 9.37114951,
+        12.16153268, 13.55672427, 14.95191585,
+    ]));
+
+    // resize_downsample_sizes_cubic
+    let y = run(
+        resize("cubic", "half_pixel"),
+        arange(4, 4),
+        None,
+        None,
+        Some(vec![1, 1, 3, 3]),
+    );
+    #[rustfmt::skip]
+    assert!(y.allclose(&[
+        1.63078704f32, 3.00462963, 4.37847222,
+        7.12615741, 8.5, 9.87384259,
+        12.62152778, 13.99537037, 15.36921296,
+    ]));
+}
+
+#[test]
+fn resize_cubic_exclude_outside() {
+    let op = Resize {
+        cubic_coeff_a: -0.5,
+        exclude_outside: 1,
+        ..resize("cubic", "half_pixel")
+    };
+
+    // resize_downsample_scales_cubic_A_n0p5_exclude_outside
+    let y = run(
+        op.clone(),
+        arange(4, 4),
+        None,
+        Some(vec![1., 1., 0.8, 0.8]),
+        None,
+    );
+    #[rustfmt::skip]
+    assert!(y.allclose(&[
+        1.36812675f32, 2.6695014, 4.0133367,
+        6.57362535, 7.875, 9.2188353,
+        11.94896657, 13.25034122, 14.59417652,
+    ]));
+
+    // resize_upsample_scales_cubic_A_n0p5_exclude_outside
+    let y = run(op, arange(4, 4), None, Some(vec![1., 1., 2., 2.]), None);
+    assert!(
+        Tensor::new(vec![8].into(), y.data::<f32>()[..8].to_vec()).allclose(&[
+            0.55882353f32,
+            0.81494204,
+            1.35698249,
+            1.89705882,
+            2.39705882,
+            2.93713516,
+            3.47917561,
+            3.73529412
+        ])
+    );
+}
+
+#[test]
+fn resize_tf_crop_and_resize() {
+    let op = resize("linear", "tf_crop_and_resize");
+
+    // resize_tf_crop_and_resize
+    let y = run(
+        op.clone(),
+        arange(4, 4),
+        Some(vec![0., 0., 0.4, 0.6, 1., 1., 0.6, 0.8]),
+        None,
+        Some(vec![1, 1, 3, 3]),
+    );
+    #[rustfmt::skip]
+    assert!(y.allclose(&[
+        7.6000004f32, 7.9, 8.2,
+        8.8, 9.1, 9.400001,
+        10., 10.3, 10.6,
+    ]));
+
+    // resize_tf_crop_and_resize_extrapolation_value
+    let y = run(
+        Resize {
+            extrapolation_value: 10.,
+            ..op
+        },
+        arange(4, 4),
+        Some(vec![0., 0., 0.4, 0.6, 1., 1., 1.2, 1.7]),
+        None,
+        Some(vec![1, 1, 3, 3]),
+    );
+    #[rustfmt::skip]
+    assert!(y.allclose(&[
+        7.6f32, 10., 10.,
+        12.4, 10., 10.,
+        10., 10., 10.,
+    ]));
+}
+
+#[test]
+fn resize_nd() {
+    // Trilinear resizing of a 3D input agrees with resizing one axis at a time.
+    let x = Tensor::new(
+        vec![2, 3, 4].into(),
+        (0..24).map(|i| (i as f32 * 0.7).sin()).collect(),
+    );
+    let op = resize("linear", "half_pixel");
+    let y = run(op.clone(), x.clone(), None, None, Some(vec![3, 5, 2]));
+    assert_eq!(y.dims().as_slice(), &[3, 5, 2]);
+
+    let mut z = x;
+    for sizes in [vec![3, 3, 4], vec![3, 5, 4], vec![3, 5, 2]] {
+        z = run(op.clone(), z, None, None, Some(sizes));
+    }
+    assert!(y.allclose(z.data::<f32>()));
+
+    // Nearest works on integers too.
+    let x = Tensor::new(vec![1, 3].into(), vec![1i64, 2, 3]);
+    let y = run(
+        resize("nearest", "asymmetric"),
+        x,
+        None,
+        Some(vec![1., 2.]),
+        None,
+    );
+    assert_eq!(y.data::<i64>(), &[1, 1, 2, 2, 3, 3]);
+}
+
+#[test]
+fn save_and_load_resize() {
+    let op = Resize {
+        cubic_coeff_a: -0.5,
+        exclude_outside: 1,
+        extrapolation_value: 1.5,
+        nearest_mode: "floor".into(),
+        ..resize("cubic", "align_corners")
+    };
+    let x = arange(3, 3);
+    let mut model = model(op.clone(), &x, None, Some(vec![1., 1., 1.5, 2.]), None);
+    let expected = run(
+        op.clone(),
+        x.clone(),
+        None,
+        Some(vec![1., 1., 1.5, 2.]),
+        None,
+    );
+    model.opset_version = 19;
+    // Saved graph outputs need their shapes.
+    model.graph.values.inner_mut()[model.graph.outputs[0]].shape = Some(TypedShape::new(
+        expected.dims().clone().into(),
+        expected.elem_ty(),
+    ));
+
+    let path = std::env::temp_dir().join("altius_save_and_load_resize.onnx");
+    save_onnx(&model, &path).unwrap();
+    let loaded = load_onnx(&path).unwrap();
+    let _ = std::fs::remove_file(&path);
+    let (_, node) = loaded.graph.nodes.iter().next().unwrap();
+    assert_eq!(node.op, Op::Resize(op));
+    let sess = InterpreterSessionBuilder::new(loaded).build().unwrap();
+    let actual = sess.run(vec![x]).unwrap().remove(0);
+    assert!(actual.allclose(expected.data::<f32>()));
+}
@@ -0,0 +1,418 @@
+use altius_core::{
+    fixed_dim::FixedDimensions,
+    model::Model,
+    node::Node,
+    onnx::{load_onnx, save::save_onnx},
+    op::{Op, RnnDirection, GRU, LSTM, RNN},
+    tensor::{Tensor, TensorElemType, TypedShape},
+};
+use altius_session_interpreter::InterpreterSessionBuilder;
+
+/// Builds a model of a single recurrent `op` whose input X is of `x_dims`.
+/// `inits` follow X, where `None` stands for an omitted optional input.
+fn model(op: Op, x_dims: Vec<usize>, inits: Vec<Option<Tensor>>, num_outputs: usize) -> Model {
+    let mut model = Model::default();
+    let x = model.graph.values.new_val_named_and_shaped(
+        "x",
+        TypedShape::new(FixedDimensions::from(x_dims).into(), TensorElemType::F32),
+    );
+    model.graph.inputs.push(x);
+    let mut inputs = vec![x];
+    for (i, init) in inits.into_iter().enumerate() {
+        inputs.push(match init {
+            Some(tensor) => {
+                let id = model.graph.values.new_val_named_and_shaped(
+                    format!("i{i}"),
+                    TypedShape::new(tensor.dims().clone().into(), tensor.elem_ty()),
+                );
+                model.graph.inits.insert(id, tensor);
+                id
+            }
+            None => model.graph.values.new_val_named(""),
+        });
+    }
+    let outputs = (0..num_outputs)
+        .map(|i| model.graph.values.new_val_named(format!("y{i}")))
+        .collect::<Vec<_>>();
+    model.graph.outputs.clone_from(&outputs);
+    model
+        .graph
+        .add_node(Node::new(op).with_ins(inputs).with_outs(outputs));
+    model
+}
+
+fn run(model: Model, x: Tensor) -> Vec<Tensor> {
+    let sess = InterpreterSessionBuilder::new(model).build().unwrap();
+    sess.run(vec![x]).unwrap()
+}
+
+fn data(len: usize, seed: usize) -> Vec<f32> {
+    (0..len)
+        .map(|i| ((i * 7 + seed) as f32).sin() * 0.5)
+        .collect::<Vec<_>>()
+}
+
+fn tensor(dims: Vec<usize>, data: Vec<f32>) -> Option<Tensor> {
+    Some(Tensor::new(dims.into(), data))
+}
+
+fn sigmoid(x: f32) -> f32 {
+    1. / (1. + (-x).exp())
+}
+
+/// Computes `w x + r h + bias` for each row of `w` and `r`.
+fn affine(w: &[f32], x: &[f32], r: &[f32], h: &[f32], bias: &[f32]) -> Vec<f32> {
+    bias.iter()
+        .enumerate()
+        .map(|(j, b)| {
+            let wx = x.iter().zip(&w[j * x.len()..]).map(|(a, b)| a * b);
+            let rh = h.iter().zip(&r[j * h.len()..]).map(|(a, b)| a * b);
+            b + wx.sum::<f32>() + rh.sum::<f32>()
+        })
+        .collect()
+}
+
+fn assert_close(actual: &Tensor, expected: &[f32]) {
+    assert!(
+        actual.allclose(expected),
+        "actual: {:?} vs expected: {:?}",
+        actual.data::<f32>(),
+        expected
+    );
+}
+
+#[test]
+fn rnn_with_sequence_lens() {
+    // With the identity activation, h_t = 2 x_t + 1 + 0.5 h_{t-1}.
+    let rnn = |direction| {
+        Op::RNN(RNN {
+            activation_alpha: vec![1.],
+            activation_beta: vec![0.],
+            activations: vec!["Affine".into()],
+            direction,
+            hidden_size: Some(1),
+            ..Default::default()
+        })
+    };
+    let inits = || {
+        vec![
+            tensor(vec![1, 1, 1], vec![2.]),
+            tensor(vec![1, 1, 1], vec![0.5]),
+            tensor(vec![1, 2], vec![1., 0.]),
+            Some(Tensor::new(vec![2].into(), vec![3i32, 2])),
+        ]
+    };
+    // X is of shape [seq_len, batch, input_size].
+    let x = || Tensor::new(vec![3, 2, 1].into(), vec![1.0f32, 0., 2., 1., 3., 0.]);
+
+    let outputs = run(
+        model(rnn(RnnDirection::Forward), vec![3, 2, 1], inits(), 2),
+        x(),
+    );
+    assert_eq!(outputs[0].dims().as_slice(), &[3, 1, 2, 1]);
+    // Steps past the sequence of the second batch are zeros.
+    assert_close(&outputs[0], &[3., 1., 6.5, 3.5, 10.25, 0.]);
+    assert_close(&outputs[1], &[10.25, 3.5]);
+
+    let outputs = run(
+        model(rnn(RnnDirection::Reverse), vec![3, 2, 1], inits(), 2),
+        x(),
+    );
+    assert_close(&outputs[0], &[7.25, 2.5, 8.5, 3., 7., 0.]);
+    assert_close(&outputs[1], &[7.25, 2.5]);
+}
+
+#[test]
+fn bidirectional_rnn_with_batch_major_layout() {
+    let rnn = Op::RNN(RNN {
+        activation_alpha: vec![1., 1.],
+        activation_beta: vec![0., 0.],
+        activations: vec!["Affine".into(), "Affine".into()],
+        direction: RnnDirection::Bidirectional,
+        hidden_size: Some(1),
+        layout: 1,
+        ..Default::default()
+    });
+    let inits = vec![
+        tensor(vec![2, 1, 1], vec![2., 2.]),
+        tensor(vec![2, 1, 1], vec![0.5, 0.5]),
+        tensor(vec![2, 2], vec![1., 0., 1., 0.]),
+        None,
+        // [batch, num_directions, hidden_size]
+        tensor(vec![2, 2, 1], vec![1., 0., 0., 0.]),
+    ];
+    // X is of shape [batch, seq_len, input_size].
+    let x = Tensor::new(vec![2, 3, 1].into(), vec![1.0f32, 2., 3., 0., 1., 0.]);
+
+    let outputs = run(model(rnn, vec![2, 3, 1], inits, 2), x);
+    // Y is of shape [batch, seq_len, num_directions, hidden_size].
+    assert_eq!(outputs[0].dims().as_slice(), &[2, 3, 2, 1]);
+    #[rustfmt::skip]
+    assert_close(
+        &outputs[0],
+        &[
+            3.5, 7.25, 6.75, 8.5, 10.375, 7.,
+            1., 2.75, 3.5, 3.5, 2.75, 1.,
+        ],
+    );
+    assert_eq!(outputs[1].dims().as_slice(), &[2, 2, 1]);
+    assert_close(&outputs[1], &[10.375, 7.25, 2.75, 2.75]);
+}
+
+/// A naive LSTM of a single direction with the default activations.
+/// Returns Y of shape `[seq_len, batch, hidden]` and the final states.
+#[allow(clippy::too_many_arguments)]
+fn lstm_reference(
+    x: &[f32],
+    [seq_len, batch, input_size]: [usize; 3],
+    w: &[f32],
+    r: &[f32],
+    b: &[f32],
+    p: &[f32],
+    hidden: usize,
+    reverse: bool,
+) -> (Vec<f32>, Vec<f32>, Vec<f32>) {
+    let mut y = vec![0.; seq_len * batch * hidden];
+    let mut h = vec![0.; batch * hidden];
+    let mut c = vec![0.; batch * hidden];
+    let bias = (0..4 * hidden)
+        .map(|j| b[j] + b[4 * hidden + j])
+        .collect::<Vec<_>>();
+    for step in 0..seq_len {
+        let t = if reverse { seq_len - 1 - step } else { step };
+        for n in 0..batch {
+            let xt = &x[(t * batch + n) * input_size..][..input_size];
+            let hs = h[n * hidden..][..hidden].to_vec();
+            let gates = affine(w, xt, r, &hs, &bias);
+            for j in 0..hidden {
+                let c_prev = c[n * hidden + j];
+                let i = sigmoid(gates[j] + p[j] * c_prev);
+                let f = sigmoid(gates[2 * hidden + j] + p[2 * hidden + j] * c_prev);
+                let cell = f * c_prev + i * gates[3 * hidden + j].tanh();
+                let o = sigmoid(gates[hidden + j] + p[hidden + j] * cell);
+                c[n * hidden + j] = cell;
+                h[n * hidden + j] = o * cell.tanh();
+            }
+            y[(t * batch + n) * hidden..][..hidden].copy_from_slice(&h[n * hidden..][..hidden]);
+        }
+    }
+    (y, h, c)
+}
+
+#[test]
+fn lstm() {
+    let (seq_len, batch, input_size, hidden) = (3, 2, 3, 2);
+    let w = data(4 * hidden * input_size, 1);
+    let r = data(4 * hidden * hidden, 2);
+    let b = data(8 * hidden, 3);
+    let p = data(3 * hidden, 4);
+    let x = data(seq_len * batch * input_size, 5);
+
+    let op = Op::LSTM(LSTM {
+        activations: vec!["Sigmoid".into(), "Tanh".into(), "Tanh".into()],
+        hidden_size: Some(hidden),
+        ..Default::default()
+    });
+    let inits = vec![
+        tensor(vec![1, 4 * hidden, input_size], w.clone()),
+        tensor(vec![1, 4 * hidden, hidden], r.clone()),
+        tensor(vec![1, 8 * hidden], b.clone()),
+        None,
+        None,
+        None,
+        tensor(vec![1, 3 * hidden], p.clone()),
+    ];
+    let outputs = run(
+        model(op, vec![seq_len, batch, input_size], inits, 3),
+        Tensor::new(vec![seq_len, batch, input_size].into(), x.clone()),
+    );
+
+    let dims = [seq_len, batch, input_size];
+    let (y, h, c) = lstm_reference(&x, dims, &w, &r, &b, &p, hidden, false);
+    assert_eq!(outputs[0].dims().as_slice(), &[seq_len, 1, batch, hidden]);
+    assert_close(&outputs[0], &y);
+    assert_close(&outputs[1], &h);
+    assert_close(&outputs[2], &c);
+}
+
+#[test]
+fn bidirectional_lstm() {
+    let (seq_len, batch, input_size, hidden) = (4, 1, 2, 3);
+    let w = data(2 * 4 * hidden * input_size, 1);
+    let r = data(2 * 4 * hidden * hidden, 2);
+    let b = data(2 * 8 * hidden, 3);
+    let x = data(seq_len * batch * input_size, 5);
+
+    let op = Op::LSTM(LSTM {
+        activations: ["Sigmoid", "Tanh", "Tanh"]
+            .repeat(2)
+            .iter()
+            .map(|&a| a.into())
+            .collect(),
+        direction: RnnDirection::Bidirectional,
+        hidden_size: Some(hidden),
+        ..Default::default()
+    });
+    let inits = vec![
+        tensor(vec![2, 4 * hidden, input_size], w.clone()),
+        tensor(vec![2, 4 * hidden, hidden], r.clone()),
+        tensor(vec![2, 8 * hidden], b.clone()),
+    ];
+    let outputs = run(
+        model(op, vec![seq_len, batch, input_size], inits, 2),
+        Tensor::new(vec![seq_len, batch, input_size].into(), x.clone()),
+    );
+
+    let dims = [seq_len, batch, input_size];
+    let (w_len, r_len, b_len) = (4 * hidden * input_size, 4 * hidden * hidden, 8 * hidden);
+    let p = vec![0.; 3 * hidden];
+    let (y_f, h_f, _) = lstm_reference(
+        &x,
+        dims,
+        &w[..w_len],
+        &r[..r_len],
+        &b[..b_len],
+        &p,
+        hidden,
+        false,
+    );
+    let (y_r, h_r, _) = lstm_reference(
+        &x,
+        dims,
+        &w[w_len..],
+        &r[r_len..],
+        &b[b_len..],
+        &p,
+        hidden,
+        true,
+    );
+    // Y is of shape [seq_len, num_directions, batch, hidden], where batch is 1.
+    let y = y_f
+        .chunks(hidden)
+        .zip(y_r.chunks(hidden))
+        .flat_map(|(f, r)| [f, r].concat())
+        .collect::<Vec<_>>();
+    assert_close(&outputs[0], &y);
+    assert_close(&outputs[1], &[h_f, h_r].concat());
+}
+
+/// A naive GRU of a single forward direction with the default activations.
+fn gru_reference(
+    x: &[f32],
+    [seq_len, batch, input_size]: [usize; 3],
+    w: &[f32],
+    r: &[f32],
+    b: &[f32],
+    hidden: usize,
+    linear_before_reset: bool,
+) -> (Vec<f32>, Vec<f32>) {
+    let mut y = vec![0.; seq_len * batch * hidden];
+    let mut h = vec![0.; batch * hidden];
+    let (wb, rb) = b.split_at(3 * hidden);
+    for t in 0..seq_len {
+        for n in 0..batch {
+            let xt = &x[(t * batch + n) * input_size..][..input_size];
+            let hs = h[n * hidden..][..hidden].to_vec();
+            let xw = affine(w, xt, &[], &[], wb);
+            let hr = affine(&[], &[], r, &hs, rb);
+            let z = (0..hidden)
+                .map(|j| sigmoid(xw[j] + hr[j]))
+                .collect::<Vec<_>>();
+            let reset = (0..hidden)
+                .map(|j| sigmoid(xw[hidden + j] + hr[hidden + j]))
+                .collect::<Vec<_>>();
+            let rh = if linear_before_reset {
+                (0..hidden)
+                    .map(|j| reset[j] * hr[2 * hidden + j])
+                    .collect::<Vec<_>>()
+            } else {
+                let reset_h = (0..hidden).map(|j| reset[j] * hs[j]).collect::<Vec<_>>();
+                affine(
+                    &[],
+                    &[],
+                    &r[2 * hidden * hidden..],
+                    &reset_h,
+                    &rb[2 * hidden..],
+                )
+            };
+            for j in 0..hidden {
+                let ht = (xw[2 * hidden + j] + rh[j]).tanh();
+                h[n * hidden + j] = (1. - z[j]) * ht + z[j] * hs[j];
+            }
+            y[(t * batch + n) * hidden..][..hidden].copy_from_slice(&h[n * hidden..][..hidden]);
+        }
+    }
+    (y, h)
+}
+
+#[test]
+fn gru() {
+    let (seq_len, batch, input_size, hidden) = (3, 2, 3, 2);
+    let w = data(3 * hidden * input_size, 1);
+    let r = data(3 * hidden * hidden, 2);
+    let b = data(6 * hidden, 3);
+    let x = data(seq_len * batch * input_size, 5);
+
+    for linear_before_reset in [false, true] {
+        let op = Op::GRU(GRU {
+            activations: vec!["Sigmoid".into(), "Tanh".into()],
+            hidden_size: Some(hidden),
+            linear_before_reset,
+            ..Default::default()
+        });
+        let inits = vec![
+            tensor(vec![1, 3 * hidden, input_size], w.clone()),
+            tensor(vec![1, 3 * hidden, hidden], r.clone()),
+            tensor(vec![1, 6 * hidden], b.clone()),
+        ];
+        let outputs = run(
+            model(op, vec![seq_len, batch, input_size], inits, 2),
+            Tensor::new(vec![seq_len, batch, input_size].into(), x.clone()),
+        );
+
+        let dims = [seq_len, batch, input_size];
+        let (y, h) = gru_reference(&x, dims, &w, &r, &b, hidden, linear_before_reset);
+        assert_close(&outputs[0], &y);
+        assert_close(&outputs[1], &h);
+    }
+}
+
+#[test]
+fn save_and_load_recurrent_ops() {
+    let lstm = Op::LSTM(LSTM {
+        activation_alpha: vec![0.1],
+        activation_beta: vec![],
+        activations: vec!["LeakyRelu".into(), "Tanh".into(), "Softsign".into()],
+        clip: Some(3.),
+        direction: RnnDirection::Reverse,
+        hidden_size: Some(2),
+        input_forget: true,
+        layout: 1,
+    });
+    let inits = vec![
+        tensor(vec![1, 8, 3], data(24, 1)),
+        tensor(vec![1, 8, 2], data(16, 2)),
+    ];
+    let mut model = model(lstm.clone(), vec![1, 2, 3], inits, 3);
+    let x = Tensor::new(vec![1, 2, 3].into(), data(6, 3));
+    let expected = run(model.clone(), x.clone());
+    model.opset_version = 17;
+    // Saved graph outputs need their shapes.
+    for (&id, output) in model.graph.outputs.iter().zip(&expected) {
+        model.graph.values.inner_mut()[id].shape = Some(TypedShape::new(
+            output.dims().clone().into(),
+            output.elem_ty(),
+        ));
+    }
+
+    let path = std::env::temp_dir().join("altius_save_and_load_recurrent_ops.onnx");
+    save_onnx(&model, &path).unwrap();
+    let loaded = load_onnx(&path).unwrap();
+    let _ = std::fs::remove_file(&path);
+    let (_, node) = loaded.graph.nodes.iter().next().unwrap();
+    assert_eq!(node.op, lstm);
+    let actual = run(loaded, x);
+    for (actual, expected) in actual.iter().zip(&expected) {
+        assert_close(actual, expected.data::<f32>());
+    }
+}
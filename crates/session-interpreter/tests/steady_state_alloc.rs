@@ -0,0 +1,137 @@
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    cell::Cell,
+};
+
+use altius_core::{
+    fixed_dim::FixedDimensions,
+    model::Model,
+    node::Node,
+    op::{Constant, Op},
+    tensor::{Tensor, TensorElemType, TypedShape},
+};
+use altius_session_interpreter::InterpreterSessionBuilder;
+use half::f16;
+
+/// The system allocator, counting allocations made by each thread.
+struct Counting;
+
+thread_local! {
+    static ALLOCS: Cell<usize> = const { Cell::new(0) };
+}
+
+fn count() {
+    let _ = ALLOCS.try_with(|allocs| allocs.set(allocs.get() + 1));
+}
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        count();
+        System.alloc(layout)
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        count();
+        System.alloc_zeroed(layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        count();
+        System.realloc(ptr, layout, new_size)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+/// Returns the result of `f` and the number of allocations it made.
+fn allocs<R>(f: impl FnOnce() -> R) -> (R, usize) {
+    let start = ALLOCS.with(Cell::get);
+    let result = f();
+    (result, ALLOCS.with(Cell::get) - start)
+}
+
+const DIMS: [usize; 2] = [4, 8];
+
+/// `y = tile((x + c) * (x + c), repeats)` in f16, where `c` is a constant.
+fn model() -> Model {
+    let mut model = Model {
+        opset_version: 13,
+        ..Default::default()
+    };
+    let x = model.graph.values.new_val_named_and_shaped(
+        "x",
+        TypedShape::new(
+            FixedDimensions::from(DIMS.to_vec()).into(),
+            TensorElemType::F16,
+        ),
+    );
+    let [c, add, mul, repeats, y] =
+        ["c", "add", "mul", "repeats", "y"].map(|name| model.graph.values.new_val_named(name));
+
+    model
+        .graph
+        .inits
+        .insert(repeats, Tensor::new(vec![2].into(), vec![2i64, 1]));
+    model.graph.add_node(
+        Node::new(Op::Constant(Constant {
+            value: Tensor::new(
+                DIMS.to_vec().into(),
+                (0..DIMS[0] * DIMS[1])
+                    .map(|i| f16::from_f32(i as f32 / 8.0))
+                    .collect(),
+            ),
+        }))
+        .with_out(c),
+    );
+    model
+        .graph
+        .add_node(Node::new(Op::Add).with_ins(vec![x, c]).with_out(add));
+    model
+        .graph
+        .add_node(Node::new(Op::Mul).with_ins(vec![add, add]).with_out(mul));
+    model
+        .graph
+        .add_node(Node::new(Op::Tile).with_ins(vec![mul, repeats]).with_out(y));
+    model.graph.inputs.push(x);
+    model.graph.outputs.push(y);
+    model
+}
+
+#[test]
+fn steady_state_runs_allocate_only_outputs() {
+    let sess = InterpreterSessionBuilder::new(model())
+        .with_intra_op_num_threads(1)
+        .build()
+        .unwrap();
+    let x = Tensor::new(
+        DIMS.to_vec().into(),
+        (0..DIMS[0] * DIMS[1])
+            .map(|i| f16::from_f32((i % 5) as f32 - 2.0))
+            .collect(),
+    );
+    let expected = (0..2 * DIMS[0] * DIMS[1])
+        .map(|i| {
+            let i = i % (DIMS[0] * DIMS[1]);
+            let sum = (i % 5) as f32 - 2.0 + i as f32 / 8.0;
+            f16::from_f32(sum * sum)
+        })
+        .collect::<Vec<_>>();
+    let y_dims = FixedDimensions::from(vec![DIMS[0] * 2, DIMS[1]]);
+
+    let y = sess.run(vec![x.clone()]).unwrap();
+    assert_eq!(y[0].data::<f16>(), expected.as_slice());
+
+    // The output handed over to the caller is all that a run allocates.
+    let (_, outputs) = allocs(|| vec![Tensor::uninit_of_type(TensorElemType::F16, y_dims.clone())]);
+    for _ in 0..3 {
+        let inputs = vec![x.clone()];
+        let (y, run) = allocs(|| sess.run(inputs).unwrap());
+        assert_eq!(y[0].data::<f16>(), expected.as_slice());
+        assert_eq!(run, outputs);
+    }
+}
@@ -2,10 +2,14 @@
 #![allow(clippy::excessive_precision)]
 
 pub mod plan;
+pub mod resize;
 
 use std::borrow::Cow;
 
-use altius_core::{analysis::shape::ShapeError, tensor::Tensor};
+use altius_core::{
+    analysis::shape::ShapeError, custom_op::CustomOpError, optimize::pass_manager::PassError,
+    quantize::QuantizeError, tensor::Tensor,
+};
 use cranelift_module::ModuleError;
 use thiserror::Error;
 
@@ -15,6 +19,18 @@ pub enum SessionError {
     #[error("Shape: {0}")]
     Shape(#[from] ShapeError),
 
+    /// Errors arised from optimization passes.
+    #[error("Optimize: {0}")]
+    Optimize(#[from] PassError),
+
+    /// Errors arised from quantization.
+    #[error("Quantize: {0}")]
+    Quantize(#[from] QuantizeError),
+
+    /// Errors arised from user-registered custom ops.
+    #[error("CustomOp: {0}")]
+    CustomOp(#[from] CustomOpError),
+
     #[error("Io: {0}")]
     Io(#[from] std::io::Error),
 
@@ -1,3 +1,5 @@
+use std::{collections::BTreeMap, ops::Range};
+
 use altius_core::{model::Model, node::NodeId, value::ValueId};
 use rustc_hash::FxHashMap;
 
@@ -30,7 +32,8 @@ pub fn create_execution_plan(model: &Model) -> Vec<NodeExecutionPlan> {
         };
 
         for &output_id in &node.outputs {
-            if !value_users.contains_key(&output_id) {
+            // Graph outputs must survive even if other nodes use them.
+            if !value_users.contains_key(&output_id) || model.graph.outputs.contains(&output_id) {
                 continue;
             }
 
@@ -56,3 +59,157 @@ pub fn create_execution_plan(model: &Model) -> Vec<NodeExecutionPlan> {
 
     new_sorted_nodes
 }
+
+/// Byte offsets of values in a single arena, assigned by [`create_memory_plan`].
+#[derive(Debug, Default)]
+pub struct MemoryPlan {
+    /// Offsets of planned values, which are aligned to 32 bytes.
+    pub offsets: FxHashMap<ValueId, usize>,
+
+    /// Size of the arena in bytes.
+    pub arena_size: usize,
+}
+
+/// Assigns offsets in an arena to values whose size in bytes `value_size` returns, following
+/// the lifetimes given by `execution_plans`. Values alive at the same time never overlap.
+///
+/// Graph inputs, outputs and initializers are never planned since they live beyond a run.
+/// Neither are values of zero bytes.
+pub fn create_memory_plan(
+    model: &Model,
+    execution_plans: &[NodeExecutionPlan],
+    value_size: impl Fn(ValueId) -> Option<usize>,
+) -> MemoryPlan {
+    let graph = &model.graph;
+    let value_users = model.get_value_users();
+    let mut regions = Regions::default();
+    let mut offsets = FxHashMap::default();
+
+    for plan in execution_plans {
+        let node = &graph.nodes[plan.node_id];
+        let outputs = node.outputs.iter().filter(|id| {
+            !graph.outputs.contains(id)
+                && !graph.inputs.contains(id)
+                && !graph.inits.contains_key(id)
+        });
+        for &id in outputs {
+            let Some(size) = value_size(id).filter(|&size| size > 0) else {
+                continue;
+            };
+            offsets.insert(id, regions.alloc(id, size).start);
+        }
+
+        // Values nobody uses die right after they are computed.
+        for id in &node.outputs {
+            if !value_users.contains_key(id) {
+                regions.free(*id);
+            }
+        }
+        for id in &plan.free_vals {
+            regions.free(*id);
+        }
+    }
+
+    MemoryPlan {
+        offsets,
+        arena_size: regions.size(),
+    }
+}
+
+/// Manages memory regions for temporary tensors.
+/// Each region is allocated at the first free space large enough for it.
+#[derive(Default)]
+pub struct Regions {
+    start_to_region: BTreeMap<usize, Range<usize>>,
+    val_to_start: FxHashMap<ValueId, usize>,
+    max_allocated: usize,
+}
+
+impl Regions {
+    pub fn alloc(&mut self, id: ValueId, size: usize) -> Range<usize> {
+        let region = self.find_first_free_region(size);
+        self.val_to_start.insert(id, region.start);
+        self.start_to_region.insert(region.start, region.clone());
+        self.max_allocated = self
+            .max_allocated
+            .max(self.start_to_region.last_key_value().unwrap().1.end);
+        region
+    }
+
+    pub fn free(&mut self, id: ValueId) {
+        if let Some(start) = self.val_to_start.remove(&id) {
+            self.start_to_region.remove(&start).unwrap();
+        }
+    }
+
+    /// Returns the size of memory needed to hold all the regions ever allocated.
+    pub fn size(&self) -> usize {
+        self.max_allocated
+    }
+
+    fn find_first_free_region(&self, size: usize) -> Range<usize> {
+        // Sorted by start offset
+        let regions = self.start_to_region.values().collect::<Vec<_>>();
+        log::debug!(
+            "regions: {regions:?} (count: {count})",
+            count = regions.len()
+        );
+        fn roundup(x: usize) -> usize {
+            let mask = 32 - 1;
+            (x + mask) & !mask
+        }
+        match self.start_to_region.len() {
+            0 => 0..roundup(size),
+            1 if regions[0].start >= size => 0..roundup(size),
+            1 => roundup(regions[0].end)..roundup(regions[0].end + size),
+            _ => {
+                for r in regions.windows(2) {
+                    let [cur, next] = [r[0], r[1]];
+                    if next.start - cur.end >= size {
+                        return roundup(cur.end)..roundup(cur.end + size);
+                    }
+                }
+                let last = regions.last().unwrap();
+                roundup(last.end)..roundup(last.end + size)
+            }
+        }
+    }
+}
+
+#[test]
+fn reuse_freed_regions() {
+    use altius_core::{node::Node, op::Op};
+
+    let mut model = Model::default();
+    let x = model.graph.values.new_val_named("x");
+    let vals = (0..3)
+        .map(|i| model.graph.values.new_val_named(format!("v{i}")))
+        .collect::<Vec<_>>();
+    let y = model.graph.values.new_val_named("y");
+    let unused = model.graph.values.new_val_named("unused");
+    for (&input, &output) in [x, vals[0], vals[1], vals[2]]
+        .iter()
+        .zip(&[vals[0], vals[1], vals[2], y])
+    {
+        model
+            .graph
+            .add_node(Node::new(Op::ReLU).with_in(input).with_out(output));
+    }
+    model
+        .graph
+        .add_node(Node::new(Op::ReLU).with_in(x).with_out(unused));
+    model.graph.inputs.push(x);
+    model.graph.outputs.push(y);
+
+    let plans = create_execution_plan(&model);
+    let plan = create_memory_plan(&model, &plans, |_| Some(100));
+
+    assert!(!plan.offsets.contains_key(&x) && !plan.offsets.contains_key(&y));
+    // `v0` and `v1` are alive at the same time, but `v2` can take the place of `v0`.
+    assert_ne!(plan.offsets[&vals[0]], plan.offsets[&vals[1]]);
+    assert_ne!(plan.offsets[&vals[1]], plan.offsets[&vals[2]]);
+    assert_eq!(plan.offsets[&vals[2]], plan.offsets[&vals[0]]);
+    assert!(plan.offsets.contains_key(&unused));
+    assert!(plan.offsets.values().all(|offset| offset % 32 == 0));
+    assert_eq!(plan.arena_size, plan.offsets.values().max().unwrap() + 128);
+}
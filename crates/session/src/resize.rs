@@ -0,0 +1,154 @@
+//! Sampling positions of `Resize`, shared by the backends.
+//!
+//! Resizing is separable: an output element is the sum of the input elements it's
+//! interpolated from, weighted by the product of the weights along every axis.
+//! <https://github.com/onnx/onnx/blob/main/docs/Operators.md#Resize>
+
+use altius_core::{op::Resize, tensor::Tensor};
+
+use crate::SessionError;
+
+/// The input indices and their weights each output index of an axis is interpolated from.
+/// `None` means the output index is mapped outside the input by `tf_crop_and_resize`,
+/// where the output element takes `extrapolation_value`.
+pub type Taps = Vec<Option<Vec<(usize, f32)>>>;
+
+/// Computes `Taps` for every axis of the input.
+/// Omitted `roi` and `scales` are given as `None` (or as empty tensors).
+pub fn compute_taps(
+    resize: &Resize,
+    input_dims: &[usize],
+    output_dims: &[usize],
+    roi: Option<&Tensor>,
+    scales: Option<&Tensor>,
+) -> Result<Vec<Taps>, SessionError> {
+    let rank = input_dims.len();
+    let roi = roi.filter(|roi| roi.elem_ty().is_f32() && roi.data::<f32>().len() == rank * 2);
+    let roi = roi.map(|roi| roi.data::<f32>());
+    if resize.coordinate_transformation_mode == "tf_crop_and_resize" && roi.is_none() {
+        return Err(SessionError::Message(
+            format!(
+                "Resize: tf_crop_and_resize needs roi of {} floats",
+                rank * 2
+            )
+            .into(),
+        ));
+    }
+    let scales = scales
+        .filter(|scales| scales.dims().total_elems() > 0)
+        .map(|scales| scales.data::<f32>());
+    if output_dims.len() != rank || scales.map_or(false, |scales| scales.len() != rank) {
+        return Err(SessionError::Message(
+            format!("Resize: Cannot resize {input_dims:?} into {output_dims:?}").into(),
+        ));
+    }
+
+    (0..rank)
+        .map(|i| {
+            let scale = scales.map_or(output_dims[i] as f64 / input_dims[i] as f64, |scales| {
+                scales[i] as f64
+            });
+            let roi = roi.map_or((0., 1.), |roi| (roi[i] as f64, roi[rank + i] as f64));
+            axis_taps(resize, input_dims[i], output_dims[i], scale, roi)
+        })
+        .collect()
+}
+
+/// Computes `Taps` of an axis of `input_len` elements resized into `output_len` ones.
+fn axis_taps(
+    resize: &Resize,
+    input_len: usize,
+    output_len: usize,
+    scale: f64,
+    (roi_start, roi_end): (f64, f64),
+) -> Result<Taps, SessionError> {
+    let message = |name: &str, value: &str| {
+        Err(SessionError::Message(
+            format!("Resize: Unsupported {name} '{value}'").into(),
+        ))
+    };
+    let last = input_len as f64 - 1.;
+    // The resized length before being truncated into an integer.
+    let length = scale * input_len as f64;
+
+    let mut taps = Vec::with_capacity(output_len);
+    for o in 0..output_len {
+        let x = o as f64;
+        let x = match resize.coordinate_transformation_mode.as_str() {
+            "half_pixel" => (x + 0.5) / scale - 0.5,
+            "half_pixel_symmetric" => {
+                let offset = input_len as f64 / 2. * (1. - output_len as f64 / length);
+                offset + (x + 0.5) / scale - 0.5
+            }
+            "pytorch_half_pixel" if length > 1. => (x + 0.5) / scale - 0.5,
+            "pytorch_half_pixel" => 0.,
+            "align_corners" if length > 1. => x * last / (length - 1.),
+            "align_corners" => 0.,
+            "asymmetric" => x / scale,
+            "tf_crop_and_resize" => {
+                let x = if output_len > 1 {
+                    roi_start * last + x * (roi_end - roi_start) * last / (output_len - 1) as f64
+                } else {
+                    0.5 * (roi_start + roi_end) * last
+                };
+                if !(0. ..=last).contains(&x) {
+                    taps.push(None);
+                    continue;
+                }
+                x
+            }
+            mode => return message("coordinate_transformation_mode", mode),
+        };
+
+        let clamp = |i: f64| i.clamp(0., last) as usize;
+        let floor = x.floor();
+        let ratio = x - floor;
+        let mut tap = match resize.mode.as_str() {
+            "nearest" => {
+                let i = match resize.nearest_mode.as_str() {
+                    "round_prefer_floor" if ratio == 0.5 => floor,
+                    "round_prefer_floor" => x.round(),
+                    "round_prefer_ceil" => (x + 0.5).floor(),
+                    "floor" => floor,
+                    "ceil" => x.ceil(),
+                    mode => return message("nearest_mode", mode),
+                };
+                vec![(clamp(i), 1.)]
+            }
+            "linear" => vec![(clamp(floor), 1. - ratio), (clamp(floor + 1.), ratio)],
+            "cubic" => {
+                let a = resize.cubic_coeff_a as f64;
+                // The cubic convolution kernel for distances in [0, 1] and in [1, 2].
+                let near = |d: f64| ((a + 2.) * d - (a + 3.)) * d * d + 1.;
+                let far = |d: f64| ((a * d - 5. * a) * d + 8. * a) * d - 4. * a;
+                let weights = [
+                    far(ratio + 1.),
+                    near(ratio),
+                    near(1. - ratio),
+                    far(2. - ratio),
+                ];
+                let mut tap = (-1..=2)
+                    .zip(weights)
+                    .filter(|&(i, _)| {
+                        resize.exclude_outside == 0 || (0. ..=last).contains(&(floor + i as f64))
+                    })
+                    .map(|(i, w)| (clamp(floor + i as f64), w))
+                    .collect::<Vec<_>>();
+                if resize.exclude_outside != 0 {
+                    let sum = tap.iter().map(|&(_, w)| w).sum::<f64>();
+                    tap.iter_mut().for_each(|(_, w)| *w /= sum);
+                }
+                tap
+            }
+            mode => return message("mode", mode),
+        }
+        .into_iter()
+        .map(|(i, w)| (i, w as f32))
+        .collect::<Vec<_>>();
+        // Taps of no weight are common, especially along axes that aren't resized.
+        tap.retain(|&(_, w)| w != 0.);
+        taps.push(Some(tap));
+    }
+
+    Ok(taps)
+}
@@ -49,6 +49,7 @@ fn export_onnx(path: &str) {
     let mut model = Model {
         graph: Graph::default(),
         opset_version: 12,
+        ..Default::default()
     };
     let x = model.graph.values.new_val_named_and_shaped(
         "x",